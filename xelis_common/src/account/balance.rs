@@ -144,6 +144,10 @@ impl VersionedBalance {
         self.balance_type != BalanceType::Input
     }
 
+    pub fn get_balance_type(&self) -> BalanceType {
+        self.balance_type
+    }
+
     pub fn set_balance_type(&mut self, balance_type: BalanceType) {
         self.balance_type = balance_type;
     }
@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+// Amounts are u64 values in atomic units
+// A plain `+` panics in debug and wraps in release, these helpers return an error instead
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Amount overflow")]
+pub struct AmountOverflow;
+
+// Add two amounts
+pub fn checked_add(a: u64, b: u64) -> Result<u64, AmountOverflow> {
+    a.checked_add(b).ok_or(AmountOverflow)
+}
+
+// Subtract an amount, used when it must be lower than the total
+pub fn checked_sub(a: u64, b: u64) -> Result<u64, AmountOverflow> {
+    a.checked_sub(b).ok_or(AmountOverflow)
+}
+
+// Sum of all the amounts
+pub fn checked_sum<I: IntoIterator<Item = u64>>(amounts: I) -> Result<u64, AmountOverflow> {
+    amounts.into_iter().try_fold(0, checked_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_amounts() {
+        assert_eq!(checked_add(u64::MAX - 1, 1), Ok(u64::MAX));
+        assert_eq!(checked_add(u64::MAX, 1), Err(AmountOverflow));
+        assert_eq!(checked_sub(10, 10), Ok(0));
+        assert_eq!(checked_sub(10, 11), Err(AmountOverflow));
+
+        assert_eq!(checked_sum([]), Ok(0));
+        assert_eq!(checked_sum([1, 2, 3]), Ok(6));
+        assert_eq!(checked_sum([u64::MAX / 2, u64::MAX / 2, 1]), Ok(u64::MAX));
+        assert_eq!(checked_sum([u64::MAX / 2, u64::MAX / 2, 2]), Err(AmountOverflow));
+    }
+}
@@ -0,0 +1,333 @@
+use std::borrow::Cow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use crate::{
+    block::BlockHeader,
+    crypto::{Hash, PublicKey},
+    difficulty::{CumulativeDifficulty, Difficulty},
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    time::{TimestampMillis, TimestampSeconds},
+    transaction::Transaction
+};
+use super::daemon::{
+    AccountHistoryEntry,
+    AccountHistoryType,
+    BlockResponse,
+    BlockType,
+    RPCBlockResponse
+};
+
+// Encoding of the result for the heavy RPC methods
+// Binary results are the base64 of the Serializer encoding
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseEncoding {
+    #[default]
+    Json,
+    Binary
+}
+
+impl ResponseEncoding {
+    pub fn is_json(&self) -> bool {
+        *self == Self::Json
+    }
+}
+
+// Encode a value for a binary response
+pub fn encode_binary<T: Serializer>(value: &T) -> String {
+    STANDARD.encode(value.to_bytes())
+}
+
+// Decode a binary response
+pub fn decode_binary<T: Serializer>(value: &str) -> Result<T, ReaderError> {
+    let bytes = STANDARD.decode(value).map_err(|_| ReaderError::InvalidValue)?;
+    let mut reader = Reader::new(&bytes);
+    let value = T::read(&mut reader)?;
+    if reader.remaining() != 0 {
+        return Err(ReaderError::InvalidSize)
+    }
+    Ok(value)
+}
+
+// Entries of a binary response
+// Network is sent once to rebuild the addresses from the public keys
+pub struct BinaryPayload<T> {
+    pub mainnet: bool,
+    pub entries: Vec<T>
+}
+
+impl<T: Serializer> Serializer for BinaryPayload<T> {
+    fn write(&self, writer: &mut Writer) {
+        self.mainnet.write(writer);
+        self.entries.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            mainnet: reader.read_bool()?,
+            entries: Vec::read(reader)?
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.mainnet.size() + self.entries.size()
+    }
+}
+
+impl Serializer for BlockType {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u8(match self {
+            Self::Sync => 0,
+            Self::Side => 1,
+            Self::Orphaned => 2,
+            Self::Normal => 3
+        });
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(match reader.read_u8()? {
+            0 => Self::Sync,
+            1 => Self::Side,
+            2 => Self::Orphaned,
+            3 => Self::Normal,
+            _ => return Err(ReaderError::InvalidValue)
+        })
+    }
+
+    fn size(&self) -> usize {
+        1
+    }
+}
+
+// Binary version of a block response without its transactions
+#[derive(Serializer)]
+pub struct BinaryBlock {
+    pub hash: Hash,
+    pub topoheight: Option<u64>,
+    pub block_type: BlockType,
+    pub difficulty: Difficulty,
+    pub cumulative_difficulty: CumulativeDifficulty,
+    pub supply: Option<u64>,
+    pub reward: Option<u64>,
+    pub miner_reward: Option<u64>,
+    pub dev_reward: Option<u64>,
+    pub total_fees: Option<u64>,
+    pub total_size_in_bytes: u64,
+    pub first_seen: Option<TimestampMillis>,
+    pub header: BlockHeader
+}
+
+impl BinaryBlock {
+    pub fn into_response(self, mainnet: bool) -> BlockResponse {
+        let header = self.header;
+        RPCBlockResponse {
+            hash: Cow::Owned(self.hash),
+            topoheight: self.topoheight,
+            block_type: self.block_type,
+            difficulty: Cow::Owned(self.difficulty),
+            supply: self.supply,
+            reward: self.reward,
+            miner_reward: self.miner_reward,
+            dev_reward: self.dev_reward,
+            cumulative_difficulty: Cow::Owned(self.cumulative_difficulty),
+            total_fees: self.total_fees,
+            total_size_in_bytes: self.total_size_in_bytes as usize,
+            version: header.get_version(),
+            tips: Cow::Owned(header.get_tips().clone()),
+            timestamp: header.get_timestamp(),
+            first_seen: self.first_seen,
+            height: header.get_height(),
+            nonce: header.get_nonce(),
+            extra_nonce: Cow::Owned(*header.get_extra_nonce()),
+            miner: Cow::Owned(header.get_miner().as_address(mainnet)),
+            txs_hashes: Cow::Owned(header.get_txs_hashes().clone()),
+            transactions: Vec::new()
+        }
+    }
+}
+
+// Binary version of a transaction response
+#[derive(Serializer)]
+pub struct BinaryTransaction {
+    pub blocks: Option<Vec<Hash>>,
+    pub executed_in_block: Option<Hash>,
+    pub in_mempool: bool,
+    pub first_seen: Option<TimestampSeconds>,
+    pub tx: Transaction
+}
+
+// Same as AccountHistoryType but using the public keys
+pub enum BinaryHistoryType {
+    DevFee { reward: u64 },
+    Mining { reward: u64 },
+    Burn { amount: u64 },
+    Outgoing { to: PublicKey },
+    Incoming { from: PublicKey }
+}
+
+impl Serializer for BinaryHistoryType {
+    fn write(&self, writer: &mut Writer) {
+        match self {
+            Self::DevFee { reward } => {
+                writer.write_u8(0);
+                writer.write_u64(reward);
+            },
+            Self::Mining { reward } => {
+                writer.write_u8(1);
+                writer.write_u64(reward);
+            },
+            Self::Burn { amount } => {
+                writer.write_u8(2);
+                writer.write_u64(amount);
+            },
+            Self::Outgoing { to } => {
+                writer.write_u8(3);
+                to.write(writer);
+            },
+            Self::Incoming { from } => {
+                writer.write_u8(4);
+                from.write(writer);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(match reader.read_u8()? {
+            0 => Self::DevFee { reward: reader.read_u64()? },
+            1 => Self::Mining { reward: reader.read_u64()? },
+            2 => Self::Burn { amount: reader.read_u64()? },
+            3 => Self::Outgoing { to: PublicKey::read(reader)? },
+            4 => Self::Incoming { from: PublicKey::read(reader)? },
+            _ => return Err(ReaderError::InvalidValue)
+        })
+    }
+
+    fn size(&self) -> usize {
+        1 + match self {
+            Self::DevFee { reward } | Self::Mining { reward } => reward.size(),
+            Self::Burn { amount } => amount.size(),
+            Self::Outgoing { to: key } | Self::Incoming { from: key } => key.size()
+        }
+    }
+}
+
+#[derive(Serializer)]
+pub struct BinaryAccountHistoryEntry {
+    pub topoheight: u64,
+    pub hash: Hash,
+    pub history_type: BinaryHistoryType,
+    pub block_timestamp: TimestampMillis
+}
+
+impl From<&AccountHistoryEntry> for BinaryAccountHistoryEntry {
+    fn from(entry: &AccountHistoryEntry) -> Self {
+        let history_type = match &entry.history_type {
+            AccountHistoryType::DevFee { reward } => BinaryHistoryType::DevFee { reward: *reward },
+            AccountHistoryType::Mining { reward } => BinaryHistoryType::Mining { reward: *reward },
+            AccountHistoryType::Burn { amount } => BinaryHistoryType::Burn { amount: *amount },
+            AccountHistoryType::Outgoing { to } => BinaryHistoryType::Outgoing { to: to.get_public_key().clone() },
+            AccountHistoryType::Incoming { from } => BinaryHistoryType::Incoming { from: from.get_public_key().clone() }
+        };
+
+        Self {
+            topoheight: entry.topoheight,
+            hash: entry.hash.clone(),
+            history_type,
+            block_timestamp: entry.block_timestamp
+        }
+    }
+}
+
+impl BinaryAccountHistoryEntry {
+    pub fn into_entry(self, mainnet: bool) -> AccountHistoryEntry {
+        let history_type = match self.history_type {
+            BinaryHistoryType::DevFee { reward } => AccountHistoryType::DevFee { reward },
+            BinaryHistoryType::Mining { reward } => AccountHistoryType::Mining { reward },
+            BinaryHistoryType::Burn { amount } => AccountHistoryType::Burn { amount },
+            BinaryHistoryType::Outgoing { to } => AccountHistoryType::Outgoing { to: to.to_address(mainnet) },
+            BinaryHistoryType::Incoming { from } => AccountHistoryType::Incoming { from: from.to_address(mainnet) }
+        };
+
+        AccountHistoryEntry {
+            topoheight: self.topoheight,
+            hash: self.hash,
+            history_type,
+            block_timestamp: self.block_timestamp
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexSet;
+    use crate::{block::EXTRA_NONCE_SIZE, crypto::{Hashable, KeyPair}};
+    use super::*;
+
+    fn block(height: u64, miner: &PublicKey) -> BinaryBlock {
+        let mut tips = IndexSet::new();
+        tips.insert(Hash::new([height as u8; 32]));
+        let header = BlockHeader::new(0, height, 1711135311567 + height, tips, [7u8; EXTRA_NONCE_SIZE], miner.clone(), IndexSet::new());
+        BinaryBlock {
+            hash: header.hash(),
+            topoheight: Some(height),
+            block_type: BlockType::Sync,
+            difficulty: Difficulty::from(15000000u64),
+            cumulative_difficulty: CumulativeDifficulty::from(15000000 * height + 1),
+            supply: Some(146230061 * height),
+            reward: Some(146229454),
+            miner_reward: Some(131606509),
+            dev_reward: Some(14622945),
+            total_fees: None,
+            total_size_in_bytes: 124,
+            first_seen: Some(1711135312000 + height),
+            header
+        }
+    }
+
+    #[test]
+    fn test_blocks_round_trip_and_size() {
+        let miner = KeyPair::new().get_public_key().compress();
+        let payload = BinaryPayload {
+            mainnet: false,
+            entries: (0..100).map(|height| block(height, &miner)).collect()
+        };
+
+        let encoded = encode_binary(&payload);
+        let json = serde_json::to_string(&payload.entries.into_iter().map(|block| block.into_response(false)).collect::<Vec<_>>()).unwrap();
+        // Base64 of the binary encoding must stay smaller than the JSON version
+        assert!(encoded.len() < json.len());
+
+        let decoded: BinaryPayload<BinaryBlock> = decode_binary(&encoded).unwrap();
+        assert!(!decoded.mainnet);
+        assert_eq!(decoded.entries.len(), 100);
+        let decoded_json = serde_json::to_string(&decoded.entries.into_iter().map(|block| block.into_response(false)).collect::<Vec<_>>()).unwrap();
+        assert_eq!(decoded_json, json);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let key = KeyPair::new().get_public_key().compress();
+        let entry = AccountHistoryEntry {
+            topoheight: 10,
+            hash: Hash::zero(),
+            history_type: AccountHistoryType::Outgoing { to: key.as_address(true) },
+            block_timestamp: 1711135311567
+        };
+        let payload = BinaryPayload {
+            mainnet: true,
+            entries: vec![BinaryAccountHistoryEntry::from(&entry)]
+        };
+
+        let decoded: BinaryPayload<BinaryAccountHistoryEntry> = decode_binary(&encode_binary(&payload)).unwrap();
+        let decoded = decoded.entries.into_iter().next().unwrap().into_entry(decoded.mainnet);
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&entry).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        assert!(decode_binary::<BinaryPayload<BinaryBlock>>("not base64!").is_err());
+        // Trailing bytes are rejected
+        let encoded = STANDARD.encode([0u8, 0, 0, 1]);
+        assert!(decode_binary::<BinaryPayload<BinaryBlock>>(&encoded).is_err());
+    }
+}
@@ -1,19 +1,19 @@
 use std::{
     borrow::Cow,
     collections::{HashSet, HashMap},
-    net::SocketAddr
+    net::{IpAddr, SocketAddr}
 };
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize, Serializer, Deserializer, de::Error};
 use crate::{
-    account::{CiphertextCache, VersionedBalance, VersionedNonce},
+    account::{BalanceType, CiphertextCache, VersionedBalance, VersionedNonce},
     block::EXTRA_NONCE_SIZE,
     crypto::{Address, Hash},
     difficulty::{CumulativeDifficulty, Difficulty},
     network::Network,
     time::{TimestampMillis, TimestampSeconds}
 };
-use super::RPCTransaction;
+use super::{binary::ResponseEncoding, RPCTransaction};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq)]
 pub enum BlockType {
@@ -57,6 +57,9 @@ pub struct RPCBlockResponse<'a> {
     pub version: u8,
     pub tips: Cow<'a, IndexSet<Hash>>,
     pub timestamp: TimestampMillis,
+    // local time at which the node received it for the first time
+    #[serde(default)]
+    pub first_seen: Option<TimestampMillis>,
     pub height: u64,
     pub nonce: u64,
     #[serde(serialize_with = "serialize_extra_nonce")]
@@ -100,7 +103,10 @@ pub struct GetBlockByHashParams<'a> {
 
 #[derive(Serialize, Deserialize)]
 pub struct GetBlockTemplateParams<'a> {
-    pub address: Cow<'a, Address>
+    pub address: Cow<'a, Address>,
+    // Hex bytes written at the start of the extra nonce, used by pools to tag their blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_nonce: Option<Cow<'a, String>>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -140,7 +146,12 @@ pub struct GetMinerWorkResult {
     // this is for visual purposes only
     pub topoheight: u64,
     // difficulty required for valid block POW
-    pub difficulty: Difficulty
+    pub difficulty: Difficulty,
+    // lower difficulty used to track shares of the miner
+    // A POW meeting it but not the block difficulty should be submitted as a share
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_difficulty: Option<Difficulty>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -156,9 +167,27 @@ pub struct SubmitBlockParams {
     // hex: represent the BlockHeader (Block)
     pub block_template: String,
     // optional miner work to apply to the block template
+    // its timestamp, nonce, extra nonce and miner key replace the ones from the template
     pub miner_work: Option<String>
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitBlockStatus {
+    // Block was accepted in chain
+    Accepted,
+    // Block was built on tips that are not valid anymore
+    StaleTips,
+    // Block PoW doesn't reach the required difficulty
+    InvalidPow
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubmitBlockResult<'a> {
+    pub status: SubmitBlockStatus,
+    pub block_hash: Cow<'a, Hash>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetBalanceParams<'a> {
     pub address: Cow<'a, Address>,
@@ -241,10 +270,56 @@ pub struct GetInfoResult {
     pub block_reward: u64,
     // count how many transactions are present in mempool
     pub mempool_size: usize,
+    // size in bytes of all transactions present in mempool
+    #[serde(default)]
+    pub mempool_size_bytes: usize,
+    // count how many transactions were evicted from mempool due to its size limit
+    #[serde(default)]
+    pub mempool_evicted_count: u64,
     // software version on which the daemon is running
     pub version: String,
     // Network state (mainnet, testnet, devnet)
-    pub network: Network
+    pub network: Network,
+    // Is the address index enabled (get_address_transactions)
+    #[serde(default)]
+    pub address_index_enabled: bool,
+    // count of peers connected, None if P2P is disabled
+    #[serde(default)]
+    pub peer_count: Option<usize>,
+    // maximum peers allowed, None if P2P is disabled
+    #[serde(default)]
+    pub max_peers: Option<usize>,
+    // expected part of the next block reward (block_reward) going to the miner
+    #[serde(default)]
+    pub miner_reward: u64,
+    // expected part of the next block reward (block_reward) going to the dev fund
+    #[serde(default)]
+    pub dev_reward: u64,
+    // count of mined blocks submitted on stale tips since the node started
+    #[serde(default)]
+    pub orphaned_blocks: u64,
+    // false until the warm-up after startup is done, miner work is refused before
+    #[serde(default)]
+    pub ready: bool,
+    // node started with --safe-mode, storage is read only and P2P and mining are disabled
+    #[serde(default)]
+    pub safe_mode: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetBlockRewardAtParams {
+    pub height: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetBlockRewardAtResult {
+    pub height: u64,
+    // expected circulating supply before this block
+    pub supply: u64,
+    // full block reward (miner + dev fee)
+    pub reward: u64,
+    pub miner_reward: u64,
+    pub dev_reward: u64
 }
 
 #[derive(Serialize, Deserialize)]
@@ -252,6 +327,26 @@ pub struct SubmitTransactionParams {
     pub data: String // should be in hex format
 }
 
+// Error code of submit_transaction when the TX is rejected by the mempool
+pub const TX_REJECTED_ERROR_CODE: i16 = -32014;
+
+// RPC error returned as part of a result
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub code: i16,
+    pub message: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValidateTransactionResult {
+    pub hash: Hash,
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    // Error that submit_transaction would have returned
+    pub error: Option<QueryError>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetTransactionParams<'a> {
     pub hash: Cow<'a, Hash>
@@ -315,7 +410,20 @@ pub struct PeerEntry<'a> {
     pub pruned_topoheight: Option<u64>,
     pub peers: Cow<'a, HashMap<SocketAddr, Direction>>,
     pub cumulative_difficulty: Cow<'a, CumulativeDifficulty>,
-    pub connected_on: TimestampSeconds
+    pub connected_on: TimestampSeconds,
+    // Objects requested from this peer and their outcome
+    #[serde(default)]
+    pub requested_objects: u64,
+    #[serde(default)]
+    pub fulfilled_objects: u64,
+    #[serde(default)]
+    pub timed_out_objects: u64,
+    // Packets are compressed with this peer
+    #[serde(default)]
+    pub compression: bool,
+    // Compressed size divided by the original size of the compressed packets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -329,21 +437,95 @@ pub struct P2pStatusResult<'a> {
     pub peer_id: u64
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct BannedPeerEntry {
+    pub ip: IpAddr,
+    // Timestamp in seconds until which the address is banned
+    pub banned_until: TimestampSeconds
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetP2pStatsResult {
+    // Incoming connections dropped by the rate limiter
+    pub rate_limited_connections: u64,
+    // Incoming connections rejected before the handshake (not allowed, already connected...)
+    pub rejected_connections: u64,
+    // Incoming connections allowed per minute from the same IP, None if disabled
+    pub connection_rate_limit: Option<u64>,
+    pub connection_rate_burst: Option<u64>,
+    // Total bytes received and sent on P2P connections since startup
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    // Packets received and sent per packet type since startup
+    pub messages_in: HashMap<String, u64>,
+    pub messages_out: HashMap<String, u64>,
+    pub peer_count: usize,
+    // Handshakes completed or failed since startup (incoming and outgoing)
+    pub handshakes_succeeded: u64,
+    pub handshakes_failed: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetRpcStatsResult {
+    // Requests rejected because the client IP exceeded the rate limit
+    pub throttled_requests: u64,
+    // Requests rejected because the client IP had too many requests in progress
+    pub concurrent_rejections: u64,
+    // Client IPs with a request in progress or an open WebSocket
+    pub active_clients: usize,
+    // Requests allowed per minute from the same IP, None if disabled
+    pub rate_limit: Option<u64>,
+    // Concurrent requests allowed from the same IP, None if disabled
+    pub concurrent_limit: Option<usize>,
+    pub trust_proxy: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SetAuxCommitmentParams {
+    // None to stop committing a value
+    pub commitment: Option<Hash>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetAuxCommitmentResult {
+    pub commitment: Option<Hash>,
+    // Bytes of the commitment written at the start of the extra nonce
+    pub size: usize
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetTopoHeightRangeParams {
     pub start_topoheight: Option<u64>,
-    pub end_topoheight: Option<u64>
+    pub end_topoheight: Option<u64>,
+    // Binary encoding of the result, JSON by default
+    #[serde(default, skip_serializing_if = "ResponseEncoding::is_json")]
+    pub encoding: ResponseEncoding
+}
+
+// Bounds are inclusive and required, the blocks are returned in topological order
+#[derive(Serialize, Deserialize)]
+pub struct GetBlocksRangeParams {
+    pub start_topoheight: u64,
+    pub end_topoheight: u64,
+    #[serde(default)]
+    pub include_txs: bool
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GetHeightRangeParams {
     pub start_height: Option<u64>,
-    pub end_height: Option<u64>
+    pub end_height: Option<u64>,
+    // Binary encoding of the result, JSON by default
+    #[serde(default, skip_serializing_if = "ResponseEncoding::is_json")]
+    pub encoding: ResponseEncoding
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GetTransactionsParams {
-    pub tx_hashes: Vec<Hash>
+    pub tx_hashes: Vec<Hash>,
+    // Binary encoding of the result, JSON by default
+    #[serde(default, skip_serializing_if = "ResponseEncoding::is_json")]
+    pub encoding: ResponseEncoding
 }
 
 #[derive(Serialize, Deserialize)]
@@ -367,12 +549,19 @@ fn default_xelis_asset() -> Hash {
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct GetAccountHistoryParams {
-    pub address: Address,
+pub struct GetAccountHistoryParams<'a> {
+    pub address: Cow<'a, Address>,
     #[serde(default = "default_xelis_asset")]
     pub asset: Hash,
     pub minimum_topoheight: Option<u64>,
-    pub maximum_topoheight: Option<u64>
+    pub maximum_topoheight: Option<u64>,
+    // How many balance versions (topoheights) to skip
+    pub skip: Option<usize>,
+    // Maximum balance versions (topoheights) to scan
+    pub maximum: Option<usize>,
+    // Binary encoding of the result, JSON by default
+    #[serde(default, skip_serializing_if = "ResponseEncoding::is_json")]
+    pub encoding: ResponseEncoding
 }
 
 #[derive(Serialize, Deserialize)]
@@ -394,11 +583,80 @@ pub struct AccountHistoryEntry {
     pub block_timestamp: TimestampMillis
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetAddressTransactionsParams<'a> {
+    pub address: Cow<'a, Address>,
+    pub skip: Option<usize>,
+    pub maximum: Option<usize>,
+    pub minimum_topoheight: Option<u64>,
+    pub maximum_topoheight: Option<u64>
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressTransactionType {
+    // Account is the source of the transaction
+    Outgoing,
+    // Account is a destination of the transaction
+    Incoming,
+    // Account received the block reward
+    Mining,
+    // Account received the dev fee part of the block reward
+    DevFee
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddressTransactionEntry<'a> {
+    // Transaction hash, None for block rewards
+    pub tx_hash: Option<Cow<'a, Hash>>,
+    // Block in which the transaction was executed or the reward given
+    pub block_hash: Cow<'a, Hash>,
+    pub topoheight: u64,
+    pub block_timestamp: TimestampMillis,
+    pub entry_type: AddressTransactionType
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetAccountBalanceHistoryParams<'a> {
+    pub address: Cow<'a, Address>,
+    #[serde(default = "default_xelis_asset")]
+    pub asset: Hash,
+    pub minimum_topoheight: Option<u64>,
+    // To fetch the next page, set it to the topoheight of the last entry minus one
+    pub maximum_topoheight: Option<u64>,
+    pub maximum: Option<usize>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AccountBalanceHistoryEntry<'a> {
+    pub topoheight: u64,
+    pub block_hash: Cow<'a, Hash>,
+    // Is it an incoming, outgoing or both balance change
+    pub balance_type: BalanceType,
+    pub block_timestamp: TimestampMillis,
+    // Topoheight of the previous balance version
+    pub previous_topoheight: Option<u64>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetAccountAssetsParams<'a> {
     pub address: Cow<'a, Address>
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetAccountAssetsWithBalancesParams<'a> {
+    pub address: Cow<'a, Address>,
+    pub skip: Option<usize>,
+    pub maximum: Option<usize>
+}
+
+// Asset owned by an account with its last balance
+#[derive(Serialize, Deserialize)]
+pub struct AccountAssetWithBalance<'a> {
+    pub asset: Cow<'a, Hash>,
+    pub balance: GetBalanceResult
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetAssetParams<'a> {
     pub asset: Cow<'a, Hash>
@@ -406,18 +664,40 @@ pub struct GetAssetParams<'a> {
 
 #[derive(Serialize, Deserialize)]
 pub struct GetAssetsParams {
+    // skip and maximum are unstable if assets are added between two pages
     pub skip: Option<usize>,
     pub maximum: Option<usize>,
     pub minimum_topoheight: Option<u64>,
-    pub maximum_topoheight: Option<u64>
+    pub maximum_topoheight: Option<u64>,
+    // Cursor returned by the previous page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    // Setting it (or cursor) enables the cursor pagination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GetAccountsParams {
+    // skip and maximum are unstable if accounts are added between two pages
     pub skip: Option<usize>,
     pub maximum: Option<usize>,
     pub minimum_topoheight: Option<u64>,
-    pub maximum_topoheight: Option<u64>
+    pub maximum_topoheight: Option<u64>,
+    // Cursor returned by the previous page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    // Setting it (or cursor) enables the cursor pagination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>
+}
+
+// A page returned by a list endpoint using the cursor pagination
+#[derive(Serialize, Deserialize)]
+pub struct PagedResult<T> {
+    pub items: Vec<T>,
+    // None if this is the last page
+    pub next_cursor: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -438,6 +718,152 @@ pub struct IsTxExecutedInBlockParams<'a> {
     pub block_hash: Cow<'a, Hash>
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetFeeHistogramParams {
+    // number of blocks to aggregate, by default the whole window kept by the node
+    #[serde(default)]
+    pub window: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HistogramBucket {
+    // upper bound included, None for the last bucket
+    pub le: Option<u64>,
+    pub count: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HistogramResult {
+    pub buckets: Vec<HistogramBucket>,
+    pub sum: u64,
+    pub count: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetFeeHistogramResult {
+    // number of blocks aggregated
+    pub blocks: usize,
+    pub fees: HistogramResult,
+    pub sizes: HistogramResult
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EstimateFeesParams {
+    // size in bytes of the draft transaction
+    pub tx_size: usize,
+    #[serde(default)]
+    pub transfers: usize,
+    // transfers to accounts not yet registered
+    #[serde(default)]
+    pub new_addresses: usize
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EstimateFeesResult {
+    // minimum fee accepted by this node, consensus rules and relay policy included
+    pub minimum_fee: u64,
+    // fee needed to be included in the next block based on the mempool
+    pub suggested_fee: u64,
+    // fee rates are per kB
+    pub min_relay_fee_rate: u64,
+    pub suggested_fee_rate: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetStatsParams {
+    // number of recent blocks used, 100 by default
+    #[serde(default)]
+    pub blocks: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetMempoolSummaryResult {
+    pub size: usize,
+    pub size_bytes: usize,
+    pub evicted_count: u64
+}
+
+// Sub-queries that can be composed in get_many
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum ManyQuery<'a> {
+    GetInfo,
+    GetBlockAtTopoheight(GetBlockAtTopoHeightParams),
+    GetBalance(GetBalanceParams<'a>),
+    GetMempoolSummary,
+    GetStats(GetStatsParams)
+}
+
+impl ManyQuery<'_> {
+    // RPC method used to check the permissions of the sub-query
+    pub fn get_method(&self) -> &'static str {
+        match self {
+            Self::GetInfo => "get_info",
+            Self::GetBlockAtTopoheight(_) => "get_block_at_topoheight",
+            Self::GetBalance(_) => "get_balance",
+            Self::GetMempoolSummary => "get_mempool_summary",
+            Self::GetStats(_) => "get_stats"
+        }
+    }
+}
+
+// Sub-queries keyed by the label under which their result is returned
+// Q is kept generic so the daemon can parse each sub-query on its own
+#[derive(Serialize, Deserialize)]
+pub struct GetManyParams<Q> {
+    pub queries: IndexMap<String, Q>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ManyQueryResult {
+    Result(serde_json::Value),
+    Error(QueryError)
+}
+
+pub type GetManyResult = IndexMap<String, ManyQueryResult>;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetStatsResult {
+    // number of blocks used, lower than requested if the chain is shorter or pruned
+    pub blocks: u64,
+    pub start_topoheight: u64,
+    pub end_topoheight: u64,
+    // in milliseconds
+    pub average_block_time: TimestampMillis,
+    pub median_block_time: TimestampMillis,
+    // in bytes, including the transactions
+    pub average_block_size: u64,
+    pub average_txs_per_block: f64,
+    // fees of the transactions executed in these blocks
+    pub total_fees: u64,
+    // sum of the block rewards
+    pub supply_emitted: u64,
+    // expected reward of the next block
+    pub emission_per_block: u64,
+    // average delay in milliseconds between the block timestamp and the time we first saw it
+    // None if no block has a first seen timestamp
+    #[serde(default)]
+    pub average_first_seen_delay: Option<i64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetTransactionExecutorParams<'a> {
+    pub hash: Cow<'a, Hash>
+}
+
+// Error codes of get_transaction_executor when the TX is not executed
+pub const TX_IN_MEMPOOL_ERROR_CODE: i16 = -32010;
+pub const TX_NOT_EXECUTED_ERROR_CODE: i16 = -32011;
+pub const TX_NOT_FOUND_ERROR_CODE: i16 = -32012;
+
+#[derive(Serialize, Deserialize)]
+pub struct GetTransactionExecutorResult<'a> {
+    pub block_hash: Cow<'a, Hash>,
+    pub block_topoheight: u64,
+    pub block_timestamp: TimestampMillis
+}
+
 // Struct to define dev fee threshold
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct DevFeeThreshold {
@@ -454,33 +880,61 @@ pub struct SizeOnDiskResult {
     pub size_formatted: String
 }
 
+// Approximate usage of a storage column (tree)
+#[derive(Serialize, Deserialize)]
+pub struct StorageColumnStats {
+    pub name: String,
+    pub entries: u64,
+    // sum of the keys and values sizes, without the storage overhead
+    pub size_bytes: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetStorageStatsResult {
+    // biggest columns first
+    pub columns: Vec<StorageColumnStats>,
+    pub total_entries: u64,
+    pub total_size_bytes: u64,
+    // real size used on disk, overhead included
+    pub size_on_disk: u64,
+    pub pruned_topoheight: Option<u64>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetMempoolCacheParams<'a> {
     pub address: Cow<'a, Address>
 }
 
+// Error code of get_mempool_cache when the account has no TX in mempool
+pub const ACCOUNT_NOT_IN_MEMPOOL_ERROR_CODE: i16 = -32013;
+
 #[derive(Serialize, Deserialize)]
 pub struct GetMempoolCacheResult {
     // lowest nonce used
-    min: u64,
+    pub min: u64,
     // highest nonce used
-    max: u64,
+    pub max: u64,
     // all txs ordered by nonce
-    txs: Vec<Hash>,
+    pub txs: Vec<Hash>,
     // All "final" cached balances used
-    balances: HashMap<Hash, CiphertextCache>
+    pub balances: HashMap<Hash, CiphertextCache>
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GetDifficultyResult {
     pub difficulty: Difficulty,
+    // PoW hashes must be lower or equal to it
+    pub target: Hash,
+    // Estimated over the average block time window
     pub hashrate: Difficulty,
-    pub hashrate_formatted: String
+    pub hashrate_formatted: String,
+    pub average_block_time: TimestampMillis
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ValidateAddressParams<'a> {
-    pub address: Cow<'a, Address>,
+    // Kept as a string to report why it can't be parsed
+    pub address: Cow<'a, str>,
     #[serde(default)]
     pub allow_integrated: bool
 }
@@ -488,7 +942,20 @@ pub struct ValidateAddressParams<'a> {
 #[derive(Serialize, Deserialize)]
 pub struct ValidateAddressResult {
     pub is_valid: bool,
-    pub is_integrated: bool
+    pub is_integrated: bool,
+    // Address string can be decoded
+    #[serde(default)]
+    pub is_parsed: bool,
+    // Address prefix matches the network of the daemon
+    #[serde(default)]
+    pub is_network_valid: bool,
+    // Size in bytes of the integrated data
+    #[serde(default)]
+    pub data_size: Option<usize>,
+    // Why the address can't be parsed
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -505,6 +972,51 @@ pub enum ExtractKeyFromAddressResult {
     Hex(String)
 }
 
+// Conditions watched by the alerting rules of the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    // No new block ordered for too long
+    NoNewBlock,
+    // Not enough peers connected for too long
+    LowPeerCount,
+    // Free space below the limit on the storage disk
+    LowDiskSpace,
+    // Mempool size above the limit
+    MempoolFull
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved
+}
+
+// Alert whose condition is currently met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveAlert {
+    pub kind: AlertKind,
+    // When the alert was fired
+    pub since: TimestampMillis,
+    pub value: u64,
+    pub threshold: u64,
+    pub message: String,
+    // False if the notification was skipped because of the cooldown
+    pub notified: bool
+}
+
+// Sent through the alert channels when an alert is fired or resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertNotification {
+    pub kind: AlertKind,
+    pub status: AlertStatus,
+    pub value: u64,
+    pub threshold: u64,
+    pub message: String,
+    pub timestamp: TimestampMillis
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NotifyEvent {
@@ -551,6 +1063,15 @@ pub enum NotifyEvent {
     // and that he notified us
     // It contains PeerPeerDisconnectedEvent as value
     PeerPeerDisconnected,
+    // When a transaction is removed from mempool without being executed
+    // It contains TransactionRemovedFromMempoolEvent as value
+    TransactionRemovedFromMempool,
+    // When the fee rates required to enter the mempool have changed
+    // It contains FeePolicyUpdatedEvent as value
+    FeePolicyUpdated,
+    // When a new block template is available (new block or TX in mempool)
+    // It contains BlockTemplateUpdatedEvent as value
+    BlockTemplateUpdated,
 }
 
 // Value of NotifyEvent::NewBlock
@@ -571,7 +1092,10 @@ pub struct BlockOrderedEvent<'a> {
 pub struct BlockOrphanedEvent<'a> {
     pub block_hash: Cow<'a, Hash>,
     // Tpoheight of the block before being orphaned
-    pub old_topoheight: u64
+    pub old_topoheight: u64,
+    // Common base of the DAG reorg, blocks at or below it kept their order
+    pub base_hash: Cow<'a, Hash>,
+    pub base_topoheight: u64
 }
 
 // Value of NotifyEvent::StableHeightChanged
@@ -586,6 +1110,57 @@ pub type TransactionAddedInMempoolEvent = TransactionResponse<'static>;
 // Value of NotifyEvent::TransactionOrphaned
 pub type TransactionOrphanedEvent = TransactionResponse<'static>;
 
+// Why a transaction left the mempool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolRemovalReason {
+    // Not included in a block before the mempool TTL
+    Expired,
+    // Mempool was cleared manually
+    Cleared,
+    // Executed in a block
+    Mined,
+    // Another TX took its place
+    Replaced,
+    // Not compatible anymore with the chain state
+    Invalid,
+    // Removed to respect the mempool size limit
+    Evicted
+}
+
+// Value of NotifyEvent::TransactionRemovedFromMempool
+#[derive(Serialize, Deserialize)]
+pub struct TransactionRemovedFromMempoolEvent<'a> {
+    pub hash: Cow<'a, Hash>,
+    pub reason: MempoolRemovalReason
+}
+
+// Fee rates per kB required by the node to accept a TX in its mempool
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeePolicy {
+    // relay minimum configured on the node
+    pub min_relay_fee_rate: u64,
+    // floor set by the TXs evicted from a full mempool, 0 if it isn't full
+    pub mempool_fee_rate: u64
+}
+
+// Value of NotifyEvent::FeePolicyUpdated
+// Changes close in time are sent as one event from the first previous to the last new policy
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeePolicyUpdatedEvent {
+    pub previous: FeePolicy,
+    pub new: FeePolicy
+}
+
+// Value of NotifyEvent::BlockTemplateUpdated
+#[derive(Serialize, Deserialize)]
+pub struct BlockTemplateUpdatedEvent<'a> {
+    // header work hash of the template, same as the job sent by getwork
+    pub job_id: Cow<'a, Hash>,
+    pub height: u64,
+    pub difficulty: Difficulty
+}
+
 // Value of NotifyEvent::TransactionExecuted
 #[derive(Serialize, Deserialize)]
 pub struct TransactionExecutedEvent<'a> {
@@ -1,4 +1,5 @@
 mod data;
+pub mod binary;
 pub mod wallet;
 pub mod daemon;
 pub mod query;
@@ -13,6 +14,7 @@ use crate::{
         proofs::CiphertextValidityProof,
         Address,
         Hash,
+        PaymentId,
         Signature
     },
     transaction::{
@@ -187,5 +189,8 @@ pub struct SplitAddressResult {
     // Normal address
     pub address: Address,
     // Encoded data from address
-    pub integrated_data: DataElement
+    pub integrated_data: DataElement,
+    // payment id found in the integrated data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<PaymentId>
 }
\ No newline at end of file
@@ -1,7 +1,12 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter},
+    str::FromStr
+};
 use serde::{Deserialize, Serialize};
 use crate::{
-    crypto::{Address, Hash},
+    crypto::{Address, Hash, PaymentId},
+    time::TimestampMillis,
     transaction::{
         builder::{FeeBuilder, TransactionTypeBuilder},
         Transaction
@@ -21,6 +26,40 @@ pub struct BuildTransactionParams {
     pub tx_as_hex: bool
 }
 
+// Build a transaction without broadcasting it to report what it reveals
+#[derive(Serialize, Deserialize)]
+pub struct GetPrivacyReportParams {
+    #[serde(flatten)]
+    pub tx_type: TransactionTypeBuilder,
+    pub fee: Option<FeeBuilder>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateDraftParams {
+    #[serde(flatten)]
+    pub tx_type: TransactionTypeBuilder,
+    pub fee: Option<FeeBuilder>,
+    // Time in ms before the draft can be confirmed
+    pub delay: u64,
+    // Time in ms during which the draft can be confirmed once unlocked
+    pub expiration: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DraftParams {
+    pub id: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfirmDraftParams {
+    pub id: u64,
+    // Cannot be broadcasted if set to false
+    pub broadcast: bool,
+    // Returns the TX in HEX format also
+    #[serde(default = "default_false_value")]
+    pub tx_as_hex: bool
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct EstimateFeesParams {
     #[serde(flatten)]
@@ -74,6 +113,12 @@ pub struct GetAddressParams {
     pub integrated_data: Option<DataElement>
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GenerateIntegratedAddressParams {
+    // Data embedded in the address, up to EXTRA_DATA_LIMIT_SIZE bytes once serialized
+    pub data: DataElement
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RescanParams {
     pub until_topoheight: Option<u64>,
@@ -104,6 +149,47 @@ pub struct BalanceChanged {
     pub balance: u64
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionBumped {
+    // hash of the new version
+    pub hash: Hash,
+    // hash of the version replaced in mempool
+    pub replaced: Hash,
+    pub nonce: u64,
+    pub fee: u64,
+    pub previous_fee: u64,
+    // number of bumps done for this nonce
+    pub attempts: u32
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionRebroadcasted {
+    pub hash: Hash,
+    pub nonce: u64,
+    // number of rebroadcasts done for this transaction
+    pub attempts: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RebroadcastFailure {
+    // Still neither executed nor in mempool after all rebroadcasts
+    MaxAttemptsReached,
+    // Another transaction was created by us with the same nonce
+    NonceConflict
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionRebroadcastFailed {
+    pub hash: Hash,
+    pub nonce: u64,
+    pub attempts: u32,
+    pub reason: RebroadcastFailure,
+    // transaction using the same nonce in case of conflict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflicting: Option<Hash>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetValueFromKeyParams {
     pub tree: String,
@@ -144,6 +230,62 @@ pub struct QueryDBParams {
     pub return_on_first: bool
 }
 
+// Permission of a RPC API key
+// Each permission includes the previous ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyPermission {
+    // Balances, history and other queries
+    Read,
+    // Build and sign transactions
+    Spend,
+    // Manage the API keys and the wallet state
+    Admin
+}
+
+impl FromStr for ApiKeyPermission {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "read" => Self::Read,
+            "spend" => Self::Spend,
+            "admin" => Self::Admin,
+            _ => return Err("Invalid permission, expected read, spend or admin")
+        })
+    }
+}
+
+impl Display for ApiKeyPermission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Read => "read",
+            Self::Spend => "spend",
+            Self::Admin => "admin"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A RPC API key, the key itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyEntry {
+    pub name: String,
+    pub permission: ApiKeyPermission,
+    pub created_at: TimestampMillis
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddApiKeyParams {
+    pub name: String,
+    pub permission: ApiKeyPermission
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RevokeApiKeyParams {
+    pub name: String
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NotifyEvent {
@@ -167,6 +309,15 @@ pub enum NotifyEvent {
     Online,
     // Same here
     Offline,
+    // When a pending transaction got rebuilt with a higher fee by the auto bump policy
+    // Contains a TransactionBumped as value
+    TransactionBumped,
+    // When a submitted transaction dropped by the daemon got submitted again
+    // Contains a TransactionRebroadcasted as value
+    TransactionRebroadcasted,
+    // When a submitted transaction won't be submitted again
+    // Contains a TransactionRebroadcastFailed as value
+    TransactionRebroadcastFailed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -178,7 +329,10 @@ pub struct TransferOut {
     // Plaintext amount
     pub amount: u64,
     // extra data
-    pub extra_data: Option<DataElement>
+    pub extra_data: Option<DataElement>,
+    // payment id found in the extra data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<PaymentId>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -188,7 +342,10 @@ pub struct TransferIn {
     // Plaintext amount
     pub amount: u64,
     // extra data
-    pub extra_data: Option<DataElement>
+    pub extra_data: Option<DataElement>,
+    // payment id found in the extra data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<PaymentId>
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -212,6 +369,11 @@ pub enum EntryType {
         fee: u64,
         // Nonce used
         nonce: u64
+    },
+    // Asset discovered without scanning its transactions yet
+    Discovered {
+        asset: Hash,
+        amount: u64
     }
 }
 
@@ -1,22 +1,129 @@
 use std::hash::{Hash as StdHash, Hasher};
+use thiserror::Error;
 use crate::{
     serializer::{Serializer, Writer, Reader, ReaderError},
     crypto::Hash
 };
 
+// Maximum size in bytes of an asset name
+pub const ASSET_NAME_MAX_SIZE: usize = 32;
+// Maximum size in bytes of an asset ticker
+pub const ASSET_TICKER_MAX_SIZE: usize = 8;
+
+// Bit set in the decimals byte when metadata is written after it
+// Assets registered without metadata keep the same encoding
+const METADATA_FLAG: u8 = 0x80;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AssetMetadataError {
+    #[error("Asset name is empty")]
+    EmptyName,
+    #[error("Asset name is too long: {} bytes, maximum is {}", _0, ASSET_NAME_MAX_SIZE)]
+    NameTooLong(usize),
+    #[error("Asset ticker is empty")]
+    EmptyTicker,
+    #[error("Asset ticker is too long: {} bytes, maximum is {}", _0, ASSET_TICKER_MAX_SIZE)]
+    TickerTooLong(usize),
+    #[error("Asset max supply can't be zero")]
+    ZeroMaxSupply
+}
+
+// Metadata given by the creator of an asset to display it
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AssetMetadata {
+    name: String,
+    ticker: String,
+    // Maximum supply in atomic units, None if unlimited
+    #[serde(default)]
+    max_supply: Option<u64>
+}
+
+impl AssetMetadata {
+    pub fn new(name: String, ticker: String, max_supply: Option<u64>) -> Result<Self, AssetMetadataError> {
+        if name.is_empty() {
+            return Err(AssetMetadataError::EmptyName)
+        }
+
+        if name.len() > ASSET_NAME_MAX_SIZE {
+            return Err(AssetMetadataError::NameTooLong(name.len()))
+        }
+
+        if ticker.is_empty() {
+            return Err(AssetMetadataError::EmptyTicker)
+        }
+
+        if ticker.len() > ASSET_TICKER_MAX_SIZE {
+            return Err(AssetMetadataError::TickerTooLong(ticker.len()))
+        }
+
+        if max_supply == Some(0) {
+            return Err(AssetMetadataError::ZeroMaxSupply)
+        }
+
+        Ok(Self {
+            name,
+            ticker,
+            max_supply
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    pub fn get_max_supply(&self) -> Option<u64> {
+        self.max_supply
+    }
+}
+
+impl Serializer for AssetMetadata {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_string(&self.name);
+        writer.write_string(&self.ticker);
+        self.max_supply.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let name = reader.read_string()?;
+        let ticker = reader.read_string()?;
+        let max_supply = Option::read(reader)?;
+        Self::new(name, ticker, max_supply).map_err(|e| ReaderError::Any(e.into()))
+    }
+
+    fn size(&self) -> usize {
+        self.name.size() + self.ticker.size() + self.max_supply.size()
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct AssetData {
     // At which topoheight this asset is registered
     topoheight: u64,
     // How many atomic units is needed for a full coin
     decimals: u8,
+    // Assets registered without metadata have None
+    #[serde(flatten)]
+    metadata: Option<AssetMetadata>
 }
 
 impl AssetData {
     pub fn new(topoheight: u64, decimals: u8) -> Self {
         Self {
             topoheight,
-            decimals
+            decimals,
+            metadata: None
+        }
+    }
+
+    pub fn with_metadata(topoheight: u64, decimals: u8, metadata: AssetMetadata) -> Self {
+        Self {
+            topoheight,
+            decimals,
+            metadata: Some(metadata)
         }
     }
 
@@ -27,22 +134,43 @@ impl AssetData {
     pub fn get_decimals(&self) -> u8 {
         self.decimals
     }
+
+    pub fn get_metadata(&self) -> Option<&AssetMetadata> {
+        self.metadata.as_ref()
+    }
 }
 
 impl Serializer for AssetData {
     fn write(&self, writer: &mut Writer) {
         writer.write_u64(&self.topoheight);
-        writer.write_u8(self.decimals);
+        match &self.metadata {
+            Some(metadata) => {
+                writer.write_u8(self.decimals | METADATA_FLAG);
+                metadata.write(writer);
+            },
+            None => writer.write_u8(self.decimals)
+        }
     }
 
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
-        Ok(
-            Self::new(reader.read_u64()?, reader.read_u8()?)
-        )
+        let topoheight = reader.read_u64()?;
+        let value = reader.read_u8()?;
+        let decimals = value & !METADATA_FLAG;
+        let metadata = if value & METADATA_FLAG != 0 {
+            Some(AssetMetadata::read(reader)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            topoheight,
+            decimals,
+            metadata
+        })
     }
 
     fn size(&self) -> usize {
-        self.topoheight.size() + self.decimals.size()
+        self.topoheight.size() + self.decimals.size() + self.metadata.as_ref().map_or(0, |metadata| metadata.size())
     }
 }
 
@@ -107,4 +235,61 @@ impl PartialEq for AssetWithData {
     }
 }
 
-impl Eq for AssetWithData {}
\ No newline at end of file
+impl Eq for AssetWithData {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_limits() {
+        assert!(AssetMetadata::new("a".repeat(ASSET_NAME_MAX_SIZE), "b".repeat(ASSET_TICKER_MAX_SIZE), None).is_ok());
+        assert_eq!(AssetMetadata::new("a".repeat(ASSET_NAME_MAX_SIZE + 1), "XYZ".to_owned(), None), Err(AssetMetadataError::NameTooLong(ASSET_NAME_MAX_SIZE + 1)));
+        assert_eq!(AssetMetadata::new("Token".to_owned(), "b".repeat(ASSET_TICKER_MAX_SIZE + 1), None), Err(AssetMetadataError::TickerTooLong(ASSET_TICKER_MAX_SIZE + 1)));
+        assert_eq!(AssetMetadata::new(String::new(), "XYZ".to_owned(), None), Err(AssetMetadataError::EmptyName));
+        assert_eq!(AssetMetadata::new("Token".to_owned(), "XYZ".to_owned(), Some(0)), Err(AssetMetadataError::ZeroMaxSupply));
+    }
+
+    #[test]
+    fn test_asset_data_backward_compatibility() {
+        // Encoding of an asset registered before the metadata
+        let mut writer = Writer::new();
+        writer.write_u64(&42);
+        writer.write_u8(8);
+        let bytes = writer.bytes();
+
+        let data = AssetData::from_bytes(&bytes).unwrap();
+        assert_eq!(data.get_topoheight(), 42);
+        assert_eq!(data.get_decimals(), 8);
+        assert!(data.get_metadata().is_none());
+        assert_eq!(data.to_bytes(), bytes);
+
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json, serde_json::json!({ "topoheight": 42, "decimals": 8 }));
+        let data: AssetData = serde_json::from_value(json).unwrap();
+        assert!(data.get_metadata().is_none());
+    }
+
+    #[test]
+    fn test_asset_data_with_metadata() {
+        let metadata = AssetMetadata::new("Token".to_owned(), "TKN".to_owned(), Some(1_000_000)).unwrap();
+        let data = AssetData::with_metadata(10, 6, metadata.clone());
+        let bytes = data.to_bytes();
+        assert_eq!(bytes.len(), data.size());
+
+        let decoded = AssetData::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.get_decimals(), 6);
+        assert_eq!(decoded.get_metadata(), Some(&metadata));
+
+        // Still readable when followed by other values
+        let assets = vec![AssetWithData::new(Hash::zero(), data), AssetWithData::new(Hash::max(), AssetData::new(0, 8))];
+        let decoded = Vec::<AssetWithData>::from_bytes(&assets.to_bytes()).unwrap();
+        assert_eq!(decoded[0].get_data().get_metadata(), Some(&metadata));
+        assert!(decoded[1].get_data().get_metadata().is_none());
+
+        let json = serde_json::to_value(&decoded[0]).unwrap();
+        assert_eq!(json["name"], "Token");
+        assert_eq!(json["ticker"], "TKN");
+        assert_eq!(json["max_supply"], 1_000_000);
+    }
+}
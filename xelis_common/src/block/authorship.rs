@@ -0,0 +1,282 @@
+use thiserror::Error;
+use crate::{
+    crypto::{
+        elgamal::DecompressionError,
+        hash,
+        Hash,
+        Hashable,
+        KeyPair,
+        PrivateKey,
+        PublicKey,
+        Signature,
+        HASH_SIZE,
+        SIGNATURE_SIZE
+    },
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+use super::{
+    BlockHeader,
+    EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET,
+    EXTRA_NONCE_AUTHORSHIP_TAG_SIZE,
+    EXTRA_NONCE_SIZE
+};
+
+// Domains used to separate the authorship derivations from any other use of the wallet key
+const AUTHORSHIP_KEY_DOMAIN: &[u8] = b"xelis-block-authorship-key";
+const AUTHORSHIP_TAG_DOMAIN: &[u8] = b"xelis-block-authorship-tag";
+const AUTHORSHIP_PROOF_DOMAIN: &[u8] = b"xelis-block-authorship-proof";
+
+pub type AuthorshipTag = [u8; EXTRA_NONCE_AUTHORSHIP_TAG_SIZE];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuthorshipProofError {
+    #[error("Proof is for block {} but block {} was given", _0, _1)]
+    BlockMismatch(Hash, Hash),
+    #[error("Proof is for height {} but block is at height {}", _0, _1)]
+    HeightMismatch(u64, u64),
+    #[error("Authorship tag of the block doesn't match the proof")]
+    InvalidTag,
+    #[error("Invalid miner key in block")]
+    InvalidMinerKey,
+    #[error("Proof is not signed by the miner of the block")]
+    InvalidSignature
+}
+
+impl From<DecompressionError> for AuthorshipProofError {
+    fn from(_: DecompressionError) -> Self {
+        Self::InvalidMinerKey
+    }
+}
+
+// Secret derived from the wallet private key and given to the miner to tag its blocks
+// It can't be used to recover the private key, but whoever knows it can link all the tags together
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorshipKey(Hash);
+
+impl AuthorshipKey {
+    pub fn from_private_key(key: &PrivateKey) -> Self {
+        let mut bytes = Vec::with_capacity(AUTHORSHIP_KEY_DOMAIN.len() + HASH_SIZE);
+        bytes.extend_from_slice(AUTHORSHIP_KEY_DOMAIN);
+        bytes.extend_from_slice(key.as_scalar().as_bytes());
+        Self(hash(&bytes))
+    }
+
+    // Key revealed by the proof of a block at this height
+    // Knowing it doesn't allow to derive the key of any other height
+    pub fn get_height_key(&self, height: u64) -> Hash {
+        let mut bytes = Vec::with_capacity(HASH_SIZE + 8);
+        bytes.extend_from_slice(self.0.as_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        hash(&bytes)
+    }
+
+    // Tag to write in the extra nonce of a block at this height
+    pub fn get_tag(&self, height: u64) -> AuthorshipTag {
+        get_authorship_tag(&self.get_height_key(height))
+    }
+}
+
+impl Serializer for AuthorshipKey {
+    fn write(&self, writer: &mut Writer) {
+        self.0.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self(Hash::read(reader)?))
+    }
+
+    fn size(&self) -> usize {
+        HASH_SIZE
+    }
+}
+
+// Tag committed by a height key, third parties only see random bytes
+pub fn get_authorship_tag(height_key: &Hash) -> AuthorshipTag {
+    let mut bytes = Vec::with_capacity(AUTHORSHIP_TAG_DOMAIN.len() + HASH_SIZE);
+    bytes.extend_from_slice(AUTHORSHIP_TAG_DOMAIN);
+    bytes.extend_from_slice(height_key.as_bytes());
+
+    let mut tag = [0u8; EXTRA_NONCE_AUTHORSHIP_TAG_SIZE];
+    tag.copy_from_slice(&hash(&bytes).as_bytes()[..EXTRA_NONCE_AUTHORSHIP_TAG_SIZE]);
+    tag
+}
+
+// Write the authorship tag before the worker id bytes of the extra nonce
+pub fn write_authorship_tag(extra_nonce: &mut [u8; EXTRA_NONCE_SIZE], tag: &AuthorshipTag) {
+    extra_nonce[EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET..EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET + EXTRA_NONCE_AUTHORSHIP_TAG_SIZE].copy_from_slice(tag);
+}
+
+// Check that the extra nonce contains the authorship tag
+pub fn has_authorship_tag(extra_nonce: &[u8; EXTRA_NONCE_SIZE], tag: &AuthorshipTag) -> bool {
+    extra_nonce[EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET..EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET + EXTRA_NONCE_AUTHORSHIP_TAG_SIZE] == tag[..]
+}
+
+// Proof that a block was mined by the owner of its miner key
+// Only the height key of this block is revealed, the tags of the other blocks stay unlinkable
+#[derive(Clone, Debug)]
+pub struct MinedBlockProof {
+    block_hash: Hash,
+    height: u64,
+    height_key: Hash,
+    signature: Signature
+}
+
+impl MinedBlockProof {
+    // Create the proof for a block, the caller must check that the block contains our tag
+    pub fn new(keypair: &KeyPair, block_hash: Hash, height: u64) -> Self {
+        let height_key = AuthorshipKey::from_private_key(keypair.get_private_key()).get_height_key(height);
+        let signature = keypair.sign(&Self::get_signed_data(&block_hash, &height_key));
+        Self {
+            block_hash,
+            height,
+            height_key,
+            signature
+        }
+    }
+
+    fn get_signed_data(block_hash: &Hash, height_key: &Hash) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(AUTHORSHIP_PROOF_DOMAIN.len() + HASH_SIZE * 2);
+        bytes.extend_from_slice(AUTHORSHIP_PROOF_DOMAIN);
+        bytes.extend_from_slice(block_hash.as_bytes());
+        bytes.extend_from_slice(height_key.as_bytes());
+        bytes
+    }
+
+    pub fn get_block_hash(&self) -> &Hash {
+        &self.block_hash
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    // Verify the proof against the block values
+    // It can be used from an API response without rebuilding the header
+    pub fn verify_with(&self, block_hash: &Hash, height: u64, extra_nonce: &[u8; EXTRA_NONCE_SIZE], miner: &PublicKey) -> Result<(), AuthorshipProofError> {
+        if self.block_hash != *block_hash {
+            return Err(AuthorshipProofError::BlockMismatch(self.block_hash.clone(), block_hash.clone()))
+        }
+
+        if self.height != height {
+            return Err(AuthorshipProofError::HeightMismatch(self.height, height))
+        }
+
+        if !has_authorship_tag(extra_nonce, &get_authorship_tag(&self.height_key)) {
+            return Err(AuthorshipProofError::InvalidTag)
+        }
+
+        let key = miner.decompress()?;
+        if !self.signature.verify(&Self::get_signed_data(&self.block_hash, &self.height_key), &key) {
+            return Err(AuthorshipProofError::InvalidSignature)
+        }
+
+        Ok(())
+    }
+
+    // Verify the proof against the block header, its hash is computed from it
+    pub fn verify(&self, header: &BlockHeader) -> Result<(), AuthorshipProofError> {
+        self.verify_with(&header.hash(), header.get_height(), header.get_extra_nonce(), header.get_miner())
+    }
+}
+
+impl Serializer for MinedBlockProof {
+    fn write(&self, writer: &mut Writer) {
+        self.block_hash.write(writer);
+        writer.write_u64(&self.height);
+        self.height_key.write(writer);
+        self.signature.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            block_hash: Hash::read(reader)?,
+            height: reader.read_u64()?,
+            height_key: Hash::read(reader)?,
+            signature: Signature::read(reader)?
+        })
+    }
+
+    fn size(&self) -> usize {
+        HASH_SIZE * 2 + 8 + SIGNATURE_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged_header(keypair: &KeyPair, height: u64) -> BlockHeader {
+        let mut extra_nonce = [7u8; EXTRA_NONCE_SIZE];
+        let tag = AuthorshipKey::from_private_key(keypair.get_private_key()).get_tag(height);
+        write_authorship_tag(&mut extra_nonce, &tag);
+        BlockHeader::new(0, height, 0, Default::default(), extra_nonce, keypair.get_public_key().compress(), Default::default())
+    }
+
+    #[test]
+    fn test_proof_verification() {
+        let keypair = KeyPair::new();
+        let header = tagged_header(&keypair, 10);
+        let proof = MinedBlockProof::new(&keypair, header.hash(), 10);
+        assert!(proof.verify(&header).is_ok());
+
+        // Still valid once serialized
+        let proof = MinedBlockProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(proof.verify(&header).is_ok());
+
+        // Another block of the same miner
+        let other = tagged_header(&keypair, 11);
+        assert!(matches!(proof.verify(&other), Err(AuthorshipProofError::BlockMismatch(_, _))));
+    }
+
+    #[test]
+    fn test_proof_rejected() {
+        let keypair = KeyPair::new();
+        let header = tagged_header(&keypair, 10);
+
+        // Block not tagged by us
+        let mut untagged = header.clone();
+        untagged.set_extra_nonce([7u8; EXTRA_NONCE_SIZE]);
+        let proof = MinedBlockProof::new(&keypair, untagged.hash(), 10);
+        assert_eq!(proof.verify(&untagged), Err(AuthorshipProofError::InvalidTag));
+
+        // Someone else reusing our address can't prove the block
+        let other = KeyPair::new();
+        let proof = MinedBlockProof::new(&other, header.hash(), 10);
+        assert_eq!(proof.verify(&header), Err(AuthorshipProofError::InvalidTag));
+
+        // Our revealed height key can't be signed by someone else
+        let proof = MinedBlockProof::new(&keypair, header.hash(), 10);
+        let mut forged = proof.clone();
+        forged.signature = other.sign(&MinedBlockProof::get_signed_data(&forged.block_hash, &forged.height_key));
+        assert_eq!(forged.verify(&header), Err(AuthorshipProofError::InvalidSignature));
+
+        // Wrong height
+        assert!(matches!(proof.verify_with(&header.hash(), 11, header.get_extra_nonce(), header.get_miner()), Err(AuthorshipProofError::HeightMismatch(10, 11))));
+    }
+
+    #[test]
+    fn test_tags_not_linkable() {
+        let keypair = KeyPair::new();
+        let key = AuthorshipKey::from_private_key(keypair.get_private_key());
+        let first = key.get_tag(100);
+        let second = key.get_tag(101);
+        assert_ne!(first, second);
+
+        // Tags of consecutive heights share no more bytes than random ones
+        let same_bytes = first.iter().zip(second.iter()).filter(|(a, b)| a == b).count();
+        assert!(same_bytes < EXTRA_NONCE_AUTHORSHIP_TAG_SIZE / 2);
+
+        // The height key revealed by a proof doesn't give the tag of the next height
+        let revealed = key.get_height_key(100);
+        assert_eq!(get_authorship_tag(&revealed), first);
+        assert_ne!(get_authorship_tag(&hash(revealed.as_bytes())), second);
+        assert_ne!(revealed, key.get_height_key(101));
+
+        // Same height with another wallet gives another tag
+        let other = AuthorshipKey::from_private_key(KeyPair::new().get_private_key());
+        assert_ne!(other.get_tag(100), first);
+
+        // The tag is deterministic
+        assert_eq!(key.get_tag(100), first);
+    }
+}
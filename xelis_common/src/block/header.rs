@@ -9,15 +9,17 @@ use crate::{
         elgamal::CompressedPublicKey,
         hash,
         pow_hash,
+        pow_hash_with,
         Hash,
         Hashable,
+        ScratchPad,
         HASH_SIZE
     },
     serializer::{Reader, ReaderError, Serializer, Writer},
     time::TimestampMillis
 };
 use xelis_hash::Error as XelisHashError;
-use super::{MinerWork, EXTRA_NONCE_SIZE};
+use super::{MinerWork, EXTRA_NONCE_SIZE, MAX_EXTRA_NONCE_PREFIX_SIZE};
 
 // Serialize the extra nonce in a hexadecimal string
 pub fn serialize_extra_nonce<S: serde::Serializer>(extra_nonce: &[u8; EXTRA_NONCE_SIZE], s: S) -> Result<S::Ok, S::Error> {
@@ -93,6 +95,23 @@ impl BlockHeader {
         self.extra_nonce = values;
     }
 
+    // Write the prefix at the start of the extra nonce
+    // Returns false if it would overwrite the worker id bytes
+    pub fn set_extra_nonce_prefix(&mut self, prefix: &[u8]) -> bool {
+        self.set_extra_nonce_prefix_at(0, prefix)
+    }
+
+    // Same as set_extra_nonce_prefix but after the first offset bytes
+    // used when the start of the extra nonce holds the aux commitment
+    pub fn set_extra_nonce_prefix_at(&mut self, offset: usize, prefix: &[u8]) -> bool {
+        if offset + prefix.len() > MAX_EXTRA_NONCE_PREFIX_SIZE {
+            return false
+        }
+
+        self.extra_nonce[offset..offset + prefix.len()].copy_from_slice(prefix);
+        true
+    }
+
     pub fn get_height(&self) -> u64 {
         self.height
     }
@@ -190,6 +209,11 @@ impl BlockHeader {
         pow_hash(&self.get_serialized_header())
     }
 
+    // Same as get_pow_hash but reusing the given scratch pad
+    pub fn get_pow_hash_with(&self, scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
+        pow_hash_with(&self.get_serialized_header(), scratch_pad)
+    }
+
     pub fn get_transactions(&self) -> &IndexSet<Hash> {
         &self.txs_hashes
     }
@@ -242,8 +266,8 @@ impl Serializer for BlockHeader {
             }
         }
 
-        let txs_count = reader.read_u16()?;
-        let mut txs_hashes = IndexSet::with_capacity(txs_count as usize);
+        let txs_count = reader.read_length_prefix(HASH_SIZE)?;
+        let mut txs_hashes = IndexSet::with_capacity(txs_count);
         for _ in 0..txs_count {
             if !txs_hashes.insert(reader.read_hash()?) {
                 debug!("Error, duplicate tx hash found in block header");
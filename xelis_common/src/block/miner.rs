@@ -5,10 +5,11 @@ use log::debug;
 use crate::{
     crypto::{
         elgamal::RISTRETTO_COMPRESSED_SIZE,
-        pow_hash_with_scratch_pad,
         Hash,
         Hashable,
         AlignedInput,
+        OptimizedPowHasher,
+        PowHasher,
         PublicKey,
         ScratchPad,
         XelisHashError
@@ -17,7 +18,7 @@ use crate::{
     time::TimestampMillis,
 };
 
-use super::{BlockHeader, BLOCK_WORK_SIZE, EXTRA_NONCE_SIZE};
+use super::{write_aux_commitment, has_aux_commitment, write_authorship_tag, AuthorshipTag, BlockHeader, BLOCK_WORK_SIZE, EXTRA_NONCE_SIZE, MAX_EXTRA_NONCE_PREFIX_SIZE};
 
 // This structure is used by xelis-miner which allow to compute a valid block POW hash
 #[derive(Clone, Debug)]
@@ -68,8 +69,9 @@ impl<'a> MinerWork<'a> {
         self.miner.as_ref().map(|m| m.as_ref())
     }
 
+    // Compute the POW hash using the selected hashing implementation
     #[inline(always)]
-    pub fn get_pow_hash(&mut self, scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
+    pub fn get_pow_hash<H: PowHasher + ?Sized>(&mut self, hasher: &H, scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
         if self.cache.is_none() {
             let mut input = AlignedInput::default();
             input.as_mut_slice()?[0..BLOCK_WORK_SIZE].copy_from_slice(&self.to_bytes());
@@ -77,13 +79,43 @@ impl<'a> MinerWork<'a> {
         }
 
         let mut bytes = self.cache.as_mut().unwrap().as_mut_slice()?.clone();
-        pow_hash_with_scratch_pad(&mut bytes, scratch_pad)
+        hasher.pow_hash(&mut bytes, scratch_pad)
+    }
+
+    // Compute the POW hash and check if it is lower or equal to the target
+    // Both are read as 256 bits big-endian integers (same as check_difficulty_against_target),
+    // so comparing their bytes in lexicographic order gives the same result
+    pub fn verify_pow(&mut self, scratch_pad: &mut ScratchPad, target: &Hash) -> Result<bool, XelisHashError> {
+        let hash = self.get_pow_hash(&OptimizedPowHasher, scratch_pad)?;
+        Ok(hash.as_bytes() <= target.as_bytes())
     }
 
     pub fn get_extra_nonce(&mut self) -> &mut [u8; EXTRA_NONCE_SIZE] {
         &mut self.extra_nonce
     }
 
+    // Write the aux commitment in the first bytes of the extra nonce
+    // it must be called after filling the extra nonce with random bytes
+    pub fn set_aux_commitment(&mut self, commitment: &Hash) {
+        write_aux_commitment(&mut self.extra_nonce, commitment);
+    }
+
+    pub fn has_aux_commitment(&self, commitment: &Hash) -> bool {
+        has_aux_commitment(&self.extra_nonce, commitment)
+    }
+
+    // Write the authorship tag of a solo miner before the worker id bytes
+    pub fn set_authorship_tag(&mut self, tag: &AuthorshipTag) {
+        write_authorship_tag(&mut self.extra_nonce, tag);
+    }
+
+    // Part of the extra nonce free for the miner
+    // It is between the prefix set by the pool and the worker id bytes
+    pub fn get_extra_nonce_free_space(&mut self, prefix_len: usize) -> &mut [u8] {
+        let start = prefix_len.min(MAX_EXTRA_NONCE_PREFIX_SIZE);
+        &mut self.extra_nonce[start..MAX_EXTRA_NONCE_PREFIX_SIZE]
+    }
+
     #[inline(always)]
     pub fn set_timestamp(&mut self, timestamp: TimestampMillis) -> Result<(), XelisHashError> {
         self.timestamp = timestamp;
@@ -108,6 +140,8 @@ impl<'a> MinerWork<'a> {
         self.miner = Some(miner);
     }
 
+    // Worker id is written in the last bytes of the extra nonce
+    // so the prefix set by a pool is never overwritten
     #[inline(always)]
     pub fn set_thread_id(&mut self, id: u8) {
         self.extra_nonce[EXTRA_NONCE_SIZE - 1] = id;
@@ -151,7 +185,7 @@ impl<'a> Serializer for MinerWork<'a> {
         let header_work_hash = reader.read_hash()?;
         let timestamp = reader.read_u64()?;
         let nonce = reader.read_u64()?;
-        let extra_nonce = reader.read_bytes_32()?;
+        let extra_nonce = reader.read_bytes(EXTRA_NONCE_SIZE)?;
         let miner = Some(Cow::Owned(PublicKey::read(reader)?));
 
         Ok(MinerWork {
@@ -170,4 +204,94 @@ impl<'a> Serializer for MinerWork<'a> {
 }
 
 // no need to override hash() as its already serialized in good format
-impl Hashable for MinerWork<'_> {}
\ No newline at end of file
+impl Hashable for MinerWork<'_> {}
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+    use crate::{block::EXTRA_NONCE_AUX_SIZE, crypto::KeyPair, difficulty::check_difficulty_against_target};
+    use super::*;
+
+    fn hash_from_u256(value: U256) -> Hash {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        Hash::new(bytes)
+    }
+
+    #[test]
+    fn test_verify_pow() {
+        let mut scratch_pad = ScratchPad::default();
+        let mut work = MinerWork::new(Hash::zero(), 0);
+        let hash = work.get_pow_hash(&OptimizedPowHasher, &mut scratch_pad).unwrap();
+        let value = U256::from_big_endian(hash.as_bytes());
+
+        // Target above and equal to the hash
+        assert!(work.verify_pow(&mut scratch_pad, &Hash::max()).unwrap());
+        assert!(work.verify_pow(&mut scratch_pad, &hash).unwrap());
+        assert!(work.verify_pow(&mut scratch_pad, &hash_from_u256(value + 1)).unwrap());
+
+        // Target below the hash
+        let below = hash_from_u256(value - 1);
+        assert!(!work.verify_pow(&mut scratch_pad, &below).unwrap());
+        assert!(!work.verify_pow(&mut scratch_pad, &Hash::zero()).unwrap());
+
+        // Same result as the comparison on integers
+        assert!(!check_difficulty_against_target(&hash, &(value - 1)));
+    }
+
+    #[test]
+    fn test_verify_pow_endianness() {
+        let mut scratch_pad = ScratchPad::default();
+        let mut work = MinerWork::new(Hash::zero(), 0);
+        let hash = work.get_pow_hash(&OptimizedPowHasher, &mut scratch_pad).unwrap();
+
+        // Hash with its first byte lowered is a lower integer in big-endian
+        // even if all its other bytes are higher
+        let mut bytes = [u8::MAX; 32];
+        bytes[0] = hash.as_bytes()[0];
+        assert!(work.verify_pow(&mut scratch_pad, &Hash::new(bytes)).unwrap());
+        if bytes[0] > 0 {
+            bytes[0] -= 1;
+            assert!(!work.verify_pow(&mut scratch_pad, &Hash::new(bytes)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_extra_nonce_prefix() {
+        let prefix = [7u8; MAX_EXTRA_NONCE_PREFIX_SIZE];
+        let mut header = BlockHeader::new(0, 0, 0, Default::default(), [0u8; EXTRA_NONCE_SIZE], KeyPair::new().get_public_key().compress(), Default::default());
+        assert!(!header.set_extra_nonce_prefix(&[0u8; MAX_EXTRA_NONCE_PREFIX_SIZE + 1]));
+        assert!(header.set_extra_nonce_prefix(&prefix[..4]));
+
+        let mut work = MinerWork::from_block(header);
+        work.get_extra_nonce_free_space(4).fill(1);
+        work.set_thread_id_u16(u16::MAX);
+
+        let extra_nonce = work.get_extra_nonce();
+        assert_eq!(extra_nonce[..4], prefix[..4]);
+        assert!(extra_nonce[4..MAX_EXTRA_NONCE_PREFIX_SIZE].iter().all(|v| *v == 1));
+        assert_eq!(extra_nonce[MAX_EXTRA_NONCE_PREFIX_SIZE..], [255, 255]);
+    }
+
+    #[test]
+    fn test_aux_commitment_layout() {
+        let commitment = Hash::new([9u8; 32]);
+        let prefix = [7u8; MAX_EXTRA_NONCE_PREFIX_SIZE - EXTRA_NONCE_AUX_SIZE];
+        let mut header = BlockHeader::new(0, 0, 0, Default::default(), [0u8; EXTRA_NONCE_SIZE], KeyPair::new().get_public_key().compress(), Default::default());
+        // Pool prefix can't overlap the worker id once moved after the commitment
+        assert!(!header.set_extra_nonce_prefix_at(EXTRA_NONCE_AUX_SIZE, &[0u8; MAX_EXTRA_NONCE_PREFIX_SIZE - EXTRA_NONCE_AUX_SIZE + 1]));
+        assert!(header.set_extra_nonce_prefix_at(EXTRA_NONCE_AUX_SIZE, &prefix));
+
+        let mut work = MinerWork::from_block(header);
+        work.set_aux_commitment(&commitment);
+        // Thread ids of the miner are only in the last bytes
+        work.set_thread_id_u16(u16::MAX);
+        work.set_thread_id(1);
+        assert!(work.has_aux_commitment(&commitment));
+        assert!(!work.has_aux_commitment(&Hash::zero()));
+
+        let extra_nonce = work.get_extra_nonce();
+        assert_eq!(extra_nonce[..EXTRA_NONCE_AUX_SIZE], commitment.as_bytes()[..EXTRA_NONCE_AUX_SIZE]);
+        assert_eq!(extra_nonce[EXTRA_NONCE_AUX_SIZE..MAX_EXTRA_NONCE_PREFIX_SIZE], prefix);
+        assert_eq!(extra_nonce[MAX_EXTRA_NONCE_PREFIX_SIZE..], [255, 1]);
+    }
+}
@@ -1,14 +1,29 @@
 mod header;
 mod block;
 mod miner;
+mod authorship;
 
 pub use header::BlockHeader;
 pub use block::Block;
 pub use miner::MinerWork;
+pub use authorship::*;
 
 use crate::crypto::{Hash, HASH_SIZE};
 
 pub const EXTRA_NONCE_SIZE: usize = 32;
+// Last bytes of the extra nonce reserved for the worker id set by the miner
+pub const EXTRA_NONCE_WORKER_ID_SIZE: usize = 2;
+// Maximum size of the extra nonce prefix set by a pool in the block template
+pub const MAX_EXTRA_NONCE_PREFIX_SIZE: usize = EXTRA_NONCE_SIZE - EXTRA_NONCE_WORKER_ID_SIZE;
+// First bytes of the extra nonce committing an auxiliary value (sidechain anchor) when the node has one set
+// Only the first bytes of the 32 bytes value fit next to the pool prefix and the worker id, the layout is then:
+// [aux commitment (16) | pool prefix and miner space (14) | worker id (2)]
+pub const EXTRA_NONCE_AUX_SIZE: usize = 16;
+// Bytes just before the worker id holding the authorship tag of a solo miner
+// It uses the end of the miner space, no pool prefix is set when mining solo:
+// [aux commitment (16) | miner space (2) | authorship tag (12) | worker id (2)]
+pub const EXTRA_NONCE_AUTHORSHIP_TAG_SIZE: usize = 12;
+pub const EXTRA_NONCE_AUTHORSHIP_TAG_OFFSET: usize = MAX_EXTRA_NONCE_PREFIX_SIZE - EXTRA_NONCE_AUTHORSHIP_TAG_SIZE;
 pub const HEADER_WORK_SIZE: usize = 73;
 pub const BLOCK_WORK_SIZE: usize = 112; // 32 + 8 + 8 + 32 + 32 = 112
 
@@ -24,6 +39,16 @@ pub fn get_combined_hash_for_tips<'a, I: Iterator<Item = &'a Hash>>(tips: I) ->
     Hash::new(bytes)
 }
 
+// Write the auxiliary commitment at the start of the extra nonce
+pub fn write_aux_commitment(extra_nonce: &mut [u8; EXTRA_NONCE_SIZE], commitment: &Hash) {
+    extra_nonce[..EXTRA_NONCE_AUX_SIZE].copy_from_slice(&commitment.as_bytes()[..EXTRA_NONCE_AUX_SIZE]);
+}
+
+// Check that the extra nonce still contains the auxiliary commitment
+pub fn has_aux_commitment(extra_nonce: &[u8; EXTRA_NONCE_SIZE], commitment: &Hash) -> bool {
+    extra_nonce[..EXTRA_NONCE_AUX_SIZE] == commitment.as_bytes()[..EXTRA_NONCE_AUX_SIZE]
+}
+
 #[cfg(test)]
 mod tests {
     use crate::crypto::Hash;
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fmt::{Display, Formatter},
     mem,
     str::FromStr
@@ -7,6 +8,7 @@ use crate::{
     api::{DataElement, ValueType, DataValue},
     serializer::{Serializer, Writer, Reader, ReaderError},
     config::{PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS},
+    network::Network,
     transaction::EXTRA_DATA_LIMIT_SIZE
 };
 use super::{
@@ -16,7 +18,120 @@ use super::{
 use core::fmt;
 use log::debug;
 use serde::de::Error as SerdeError;
-use anyhow::Error;
+use thiserror::Error;
+
+// Key used in the integrated data to attach a payment id to the address
+pub const PAYMENT_ID_KEY: &str = "payment_id";
+pub const PAYMENT_ID_SIZE: usize = 8;
+
+// Identifier integrated in an address to attribute the payments received on it
+// It is stored as an u64 in the integrated data and is sent encrypted in the extra data of the transfer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PaymentId([u8; PAYMENT_ID_SIZE]);
+
+impl PaymentId {
+    pub const fn new(bytes: [u8; PAYMENT_ID_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PAYMENT_ID_SIZE] {
+        &self.0
+    }
+
+    // Integrated data representing this payment id
+    pub fn to_data(&self) -> DataElement {
+        let mut fields = HashMap::new();
+        fields.insert(DataValue::String(PAYMENT_ID_KEY.to_owned()), DataElement::Value(DataValue::U64(u64::from_be_bytes(self.0))));
+        DataElement::Fields(fields)
+    }
+
+    // Search the payment id in an integrated or extra data
+    pub fn from_data(data: &DataElement) -> Option<Self> {
+        match data.get_value_by_key(&DataValue::String(PAYMENT_ID_KEY.to_owned()), None)? {
+            DataValue::U64(value) => Some(Self(value.to_be_bytes())),
+            _ => None
+        }
+    }
+}
+
+impl From<u64> for PaymentId {
+    fn from(value: u64) -> Self {
+        Self(value.to_be_bytes())
+    }
+}
+
+impl Serializer for PaymentId {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_bytes(&self.0);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self(reader.read_bytes(PAYMENT_ID_SIZE)?))
+    }
+
+    fn size(&self) -> usize {
+        PAYMENT_ID_SIZE
+    }
+}
+
+impl Display for PaymentId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for PaymentId {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|e| AddressError::InvalidPaymentId(e.to_string()))?;
+        let bytes: [u8; PAYMENT_ID_SIZE] = bytes.try_into()
+            .map_err(|_| AddressError::InvalidPaymentId(format!("expected {} bytes", PAYMENT_ID_SIZE)))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl serde::Serialize for PaymentId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'a> serde::Deserialize<'a> for PaymentId {
+    fn deserialize<D: serde::Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        PaymentId::from_str(&hex).map_err(SerdeError::custom)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Invalid address checksum")]
+    InvalidChecksum,
+    #[error("Unknown address prefix '{}', expected {} or {}", _0, PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS)]
+    UnknownPrefix(String),
+    #[error("Address is for {}, expected {}", _0, _1)]
+    WrongNetwork(&'static str, &'static str), // network of the address, expected network
+    #[error("Malformed address: {}", _0)]
+    Malformed(String),
+    #[error("Invalid payment id: {}", _0)]
+    InvalidPaymentId(String)
+}
+
+impl From<Bech32Error> for AddressError {
+    fn from(e: Bech32Error) -> Self {
+        match e {
+            Bech32Error::InvalidChecksum => Self::InvalidChecksum,
+            e => Self::Malformed(e.to_string())
+        }
+    }
+}
+
+impl From<ReaderError> for AddressError {
+    fn from(e: ReaderError) -> Self {
+        Self::Malformed(e.to_string())
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AddressType {
@@ -26,6 +141,25 @@ pub enum AddressType {
     Data(DataElement)
 }
 
+// Result of the validation of an address against a network
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressValidation {
+    // Prefix of the address is the one of the network
+    pub is_network_valid: bool,
+    pub is_integrated: bool,
+    // Size in bytes of the integrated data
+    pub data_size: Option<usize>,
+    // Integrated data fits in EXTRA_DATA_LIMIT_SIZE
+    pub is_data_size_valid: bool
+}
+
+impl AddressValidation {
+    // Check if the address can be used on this network
+    pub fn is_valid(&self, allow_integrated: bool) -> bool {
+        self.is_network_valid && self.is_data_size_valid && (allow_integrated || !self.is_integrated)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Address {
     mainnet: bool,
@@ -42,6 +176,11 @@ impl Address {
         }
     }
 
+    // Create an integrated address carrying only a payment id
+    pub fn new_integrated(mainnet: bool, key: PublicKey, payment_id: PaymentId) -> Self {
+        Self::new(mainnet, AddressType::Data(payment_id.to_data()), key)
+    }
+
     // Get the public key from the address
     pub fn get_public_key(&self) -> &PublicKey {
         &self.key
@@ -104,6 +243,32 @@ impl Address {
         self.mainnet
     }
 
+    // Check if the address has integrated data
+    pub fn is_integrated(&self) -> bool {
+        !self.is_normal()
+    }
+
+    // Get the payment id integrated in the address if present
+    pub fn extract_payment_id(&self) -> Option<&DataValue> {
+        self.get_extra_data()?.get_value_by_key(&DataValue::String(PAYMENT_ID_KEY.to_owned()), None)
+    }
+
+    // Get the 8 bytes payment id integrated in the address if present
+    pub fn get_payment_id(&self) -> Option<PaymentId> {
+        PaymentId::from_data(self.get_extra_data()?)
+    }
+
+    // Verify that the address can be used on this network
+    pub fn validate_for_network(&self, network: &Network) -> AddressValidation {
+        let data_size = self.get_extra_data().map(|data| data.size());
+        AddressValidation {
+            is_network_valid: self.mainnet == network.is_mainnet(),
+            is_integrated: data_size.is_some(),
+            data_size,
+            is_data_size_valid: data_size.map_or(true, |size| size <= EXTRA_DATA_LIMIT_SIZE)
+        }
+    }
+
     // Compress the address to a byte array
     // We don't use Serializer trait to avoid storing mainnet bool
     fn compress(&self) -> Vec<u8> {
@@ -145,24 +310,28 @@ impl Address {
     }
 
     // Parse an address from a string (human readable format)
-    pub fn from_string(address: &String) -> Result<Self, Error> {
+    pub fn from_string(address: &String) -> Result<Self, AddressError> {
         let (hrp, decoded) = decode(address)?;
         // check that hrp is valid one
         if hrp != PREFIX_ADDRESS && hrp != TESTNET_PREFIX_ADDRESS {
-            return Err(Bech32Error::InvalidPrefix(hrp, format!("{} or {}", PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS)).into())
+            return Err(AddressError::UnknownPrefix(hrp))
         }
 
         let bits = convert_bits(&decoded, 5, 8, false)?;
         let addr = Address::decompress(&bits, hrp.as_str())?;
+        Ok(addr)
+    }
 
-        // now check that the hrp decoded is the one for the network state
-        if (addr.is_mainnet() && hrp != PREFIX_ADDRESS) || (!addr.is_mainnet() && hrp != TESTNET_PREFIX_ADDRESS) {
-            let expected = if addr.is_mainnet() {
-                PREFIX_ADDRESS
+    // Parse an address and verify that it is for this network
+    pub fn from_string_for_network(address: &String, network: &Network) -> Result<Self, AddressError> {
+        let addr = Self::from_string(address)?;
+        if addr.is_mainnet() != network.is_mainnet() {
+            let (found, expected) = if addr.is_mainnet() {
+                ("mainnet", "testnet")
             } else {
-                TESTNET_PREFIX_ADDRESS
+                ("testnet", "mainnet")
             };
-            return Err(Bech32Error::InvalidPrefix(hrp, expected.to_owned()).into())
+            return Err(AddressError::WrongNetwork(found, expected))
         }
 
         Ok(addr)
@@ -170,7 +339,7 @@ impl Address {
 }
 
 impl FromStr for Address {
-    type Err = Error;
+    type Err = AddressError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Address::from_string(&s.to_owned())
     }
@@ -227,6 +396,26 @@ impl Serializer for AddressType {
     }
 }
 
+// Unlike the string format, the network is stored as a bool
+impl Serializer for Address {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_bool(self.mainnet);
+        self.key.write(writer);
+        self.addr_type.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let mainnet = reader.read_bool()?;
+        let key = PublicKey::read(reader)?;
+        let addr_type = AddressType::read(reader)?;
+        Ok(Self::new(mainnet, addr_type, key))
+    }
+
+    fn size(&self) -> usize {
+        1 + self.key.size() + self.addr_type.size()
+    }
+}
+
 impl serde::Serialize for Address {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -252,9 +441,20 @@ impl Display for Address {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use crate::crypto::KeyPair;
 
-    use super::{Address, AddressType};
+    use super::*;
+
+    fn key() -> PublicKey {
+        KeyPair::new().get_public_key().compress()
+    }
+
+    // Each string takes 3 bytes more in the data element
+    fn integrated(mainnet: bool, lengths: &[usize]) -> Address {
+        let values = lengths.iter().map(|len| DataElement::Value(DataValue::String("a".repeat(*len)))).collect();
+        Address::new(mainnet, AddressType::Data(DataElement::Array(values)), key())
+    }
 
     #[test]
     fn test_serde() {
@@ -264,4 +464,211 @@ mod tests {
         let addr2: Address = Address::from_string(&v).unwrap();
         assert_eq!(addr, addr2);
     }
+
+    #[test]
+    fn test_validate_for_network() {
+        let mainnet = Address::new(true, AddressType::Normal, key());
+        let validation = mainnet.validate_for_network(&Network::Mainnet);
+        assert_eq!(validation, AddressValidation { is_network_valid: true, is_integrated: false, data_size: None, is_data_size_valid: true });
+        assert!(validation.is_valid(false));
+
+        // Wrong network
+        for network in [Network::Testnet, Network::Dev] {
+            let validation = mainnet.validate_for_network(&network);
+            assert!(!validation.is_network_valid);
+            assert!(!validation.is_valid(true));
+        }
+
+        let testnet = Address::new(false, AddressType::Normal, key());
+        assert!(testnet.validate_for_network(&Network::Dev).is_valid(false));
+        assert!(!testnet.validate_for_network(&Network::Mainnet).is_valid(false));
+    }
+
+    #[test]
+    fn test_validate_integrated() {
+        let addr = integrated(false, &[10]);
+        let validation = addr.validate_for_network(&Network::Testnet);
+        assert!(validation.is_integrated);
+        assert_eq!(validation.data_size, Some(15));
+        assert!(validation.is_valid(true));
+        assert!(!validation.is_valid(false));
+        assert_eq!(Address::from_string(&addr.to_string()).unwrap(), addr);
+
+        // Biggest data allowed
+        let max = integrated(false, &[200, 200, 200, 200, 207]);
+        assert_eq!(max.validate_for_network(&Network::Testnet).data_size, Some(EXTRA_DATA_LIMIT_SIZE));
+        assert!(max.validate_for_network(&Network::Testnet).is_valid(true));
+        assert_eq!(Address::from_string(&max.to_string()).unwrap(), max);
+
+        // Oversized data is detected and can't be parsed
+        let oversized = integrated(false, &[200, 200, 200, 200, 208]);
+        let validation = oversized.validate_for_network(&Network::Testnet);
+        assert!(!validation.is_data_size_valid);
+        assert!(!validation.is_valid(true));
+        assert!(Address::from_string(&oversized.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_mangled_strings() {
+        let addr = Address::new(true, AddressType::Normal, key()).to_string();
+        assert!(Address::from_string(&addr).is_ok());
+
+        // Every altered character breaks the checksum
+        let charset = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+        let start = PREFIX_ADDRESS.len() + 1;
+        for i in start..addr.len() {
+            let current = addr.as_bytes()[i] as char;
+            let replacement = charset.chars().find(|c| *c != current).unwrap();
+            let mut mangled = addr.clone();
+            mangled.replace_range(i..i + 1, &replacement.to_string());
+            assert!(Address::from_string(&mangled).is_err(), "{} should be invalid", mangled);
+        }
+
+        // Truncated, extended, swapped characters
+        assert!(Address::from_string(&addr[..addr.len() - 1].to_owned()).is_err());
+        assert!(Address::from_string(&format!("{}q", addr)).is_err());
+        let mut swapped = addr.clone().into_bytes();
+        let last = swapped.len() - 1;
+        swapped.swap(last, last - 1);
+        if swapped != addr.as_bytes() {
+            assert!(Address::from_string(&String::from_utf8(swapped).unwrap()).is_err());
+        }
+
+        // Separator, case and characters outside of the charset
+        assert!(Address::from_string(&addr.replace(':', "")).is_err());
+        assert!(Address::from_string(&format!("{}{}", &addr[..start], addr[start..].to_uppercase())).is_err());
+        assert!(Address::from_string(&format!("{}b{}", &addr[..start], &addr[start + 1..])).is_err());
+        assert!(Address::from_string(&"".to_owned()).is_err());
+        // Prefix is case sensitive
+        assert!(Address::from_string(&addr.to_uppercase()).is_err());
+    }
+
+    #[test]
+    fn test_wrong_prefix() {
+        let addr = Address::new(true, AddressType::Normal, key());
+        let bits = convert_bits(&addr.compress(), 8, 5, true).unwrap();
+        for prefix in ["xelis", "btc", "xex", "xe"] {
+            let encoded = encode(prefix.to_owned(), &bits).unwrap();
+            assert!(Address::from_string(&encoded).is_err(), "{} should be invalid", encoded);
+        }
+
+        // Same data with the testnet prefix is a valid testnet address
+        let testnet = encode(TESTNET_PREFIX_ADDRESS.to_owned(), &bits).unwrap();
+        let parsed = Address::from_string(&testnet).unwrap();
+        assert!(!parsed.is_mainnet());
+        assert!(!parsed.validate_for_network(&Network::Mainnet).is_network_valid);
+    }
+
+    #[test]
+    fn test_integrated_helpers() {
+        let normal = Address::new(true, AddressType::Normal, key());
+        assert!(normal.is_mainnet());
+        assert!(!normal.is_integrated());
+        assert_eq!(normal.extract_payment_id(), None);
+
+        let mut fields = HashMap::new();
+        fields.insert(DataValue::String(PAYMENT_ID_KEY.to_owned()), DataElement::Value(DataValue::U64(1337)));
+        let with_payment_id = Address::new(false, AddressType::Data(DataElement::Fields(fields)), key());
+        assert!(!with_payment_id.is_mainnet());
+        assert!(with_payment_id.is_integrated());
+        assert_eq!(with_payment_id.extract_payment_id(), Some(&DataValue::U64(1337)));
+
+        // Integrated data without payment id
+        let other = integrated(false, &[10]);
+        assert!(other.is_integrated());
+        assert_eq!(other.extract_payment_id(), None);
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        let addr = Address::new(true, AddressType::Normal, key());
+        let string = addr.to_string();
+
+        // Bad checksum
+        let last = string.len() - 1;
+        let replacement = if string.ends_with('q') { "p" } else { "q" };
+        let mut mangled = string.clone();
+        mangled.replace_range(last.., replacement);
+        assert_eq!(Address::from_string(&mangled), Err(AddressError::InvalidChecksum));
+
+        // Unknown prefix with a valid checksum
+        let bits = convert_bits(&addr.compress(), 8, 5, true).unwrap();
+        let encoded = encode("btc".to_owned(), &bits).unwrap();
+        assert_eq!(Address::from_string(&encoded), Err(AddressError::UnknownPrefix("btc".to_owned())));
+
+        // Wrong network
+        assert_eq!(Address::from_string_for_network(&string, &Network::Mainnet), Ok(addr.clone()));
+        assert_eq!(Address::from_string_for_network(&string, &Network::Testnet), Err(AddressError::WrongNetwork("mainnet", "testnet")));
+        let testnet = Address::new(false, AddressType::Normal, key()).to_string();
+        assert_eq!(Address::from_string_for_network(&testnet, &Network::Mainnet), Err(AddressError::WrongNetwork("testnet", "mainnet")));
+        assert!(Address::from_string_for_network(&testnet, &Network::Dev).is_ok());
+
+        // Malformed data with a valid checksum
+        let bits = convert_bits(&[1, 2, 3], 8, 5, true).unwrap();
+        let encoded = encode(PREFIX_ADDRESS.to_owned(), &bits).unwrap();
+        assert!(matches!(Address::from_string(&encoded), Err(AddressError::Malformed(_))));
+        // Missing separator
+        assert!(matches!(Address::from_string(&string.replace(':', "")), Err(AddressError::Malformed(_))));
+        assert!(matches!("hello".parse::<Address>(), Err(AddressError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_payment_id() {
+        let payment_id = PaymentId::new([1, 2, 3, 4, 5, 6, 7, 8]);
+        let addr = Address::new_integrated(true, key(), payment_id);
+        assert!(addr.is_integrated());
+        assert_eq!(addr.get_payment_id(), Some(payment_id));
+        assert_eq!(addr.extract_payment_id(), Some(&DataValue::U64(0x0102030405060708)));
+
+        // String format round trip
+        let string = addr.to_string();
+        let parsed = Address::from_string(&string).unwrap();
+        assert_eq!(parsed, addr);
+        assert_eq!(parsed.get_payment_id(), Some(payment_id));
+
+        // Serializer round trip keeps the network
+        for addr in [addr.clone(), Address::new(false, AddressType::Normal, key())] {
+            let bytes = addr.to_bytes();
+            assert_eq!(bytes.len(), addr.size());
+            assert_eq!(Address::from_bytes(&bytes).unwrap(), addr);
+        }
+
+        // Payment id is lost once the data is extracted
+        let (data, normal) = addr.extract_data();
+        assert_eq!(PaymentId::from_data(&data.unwrap()), Some(payment_id));
+        assert_eq!(normal.get_payment_id(), None);
+
+        // Other integrated data doesn't have a payment id
+        assert_eq!(integrated(true, &[10]).get_payment_id(), None);
+    }
+
+    #[test]
+    fn test_payment_id_encoding() {
+        let payment_id = PaymentId::from(1337);
+        assert_eq!(payment_id.to_string(), "0000000000000539");
+        assert_eq!("0000000000000539".parse::<PaymentId>().unwrap(), payment_id);
+        assert!(matches!("0539".parse::<PaymentId>(), Err(AddressError::InvalidPaymentId(_))));
+        assert!(matches!("zz00000000000539".parse::<PaymentId>(), Err(AddressError::InvalidPaymentId(_))));
+
+        let json = serde_json::to_string(&payment_id).unwrap();
+        assert_eq!(json, "\"0000000000000539\"");
+        assert_eq!(serde_json::from_str::<PaymentId>(&json).unwrap(), payment_id);
+
+        assert_eq!(PaymentId::from_bytes(&payment_id.to_bytes()).unwrap(), payment_id);
+        assert!(PaymentId::from_bytes(&[0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_integrated_checksum() {
+        let addr = Address::new_integrated(false, key(), PaymentId::from(42)).to_string();
+        let start = TESTNET_PREFIX_ADDRESS.len() + 1;
+        let charset = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+        for i in start..addr.len() {
+            let current = addr.as_bytes()[i] as char;
+            let replacement = charset.chars().find(|c| *c != current).unwrap();
+            let mut mangled = addr.clone();
+            mangled.replace_range(i..i + 1, &replacement.to_string());
+            assert_eq!(Address::from_string(&mangled), Err(AddressError::InvalidChecksum), "{} should be invalid", mangled);
+        }
+    }
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
-use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    sync::OnceLock
+};
 
 use curve25519_dalek::{traits::Identity, RistrettoPoint, Scalar};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -7,26 +11,43 @@ use super::{pedersen::{DecryptHandle, PedersenCommitment}, CompressedCiphertext,
 // Represents a twisted ElGamal Ciphertext
 // One part is a Pedersen commitment to be bulletproofs compatible
 // The other part is a handle to be used for decryption
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct Ciphertext {
     commitment: PedersenCommitment,
     handle: DecryptHandle,
+    // Compressed form computed on first use
+    // It is reset by any arithmetic operation
+    compressed: OnceLock<CompressedCiphertext>
 }
 
 impl Ciphertext {
     // Create a new Ciphertext
     pub fn new(commitment: PedersenCommitment, handle: DecryptHandle) -> Self {
-        Self { commitment, handle }
+        Self {
+            commitment,
+            handle,
+            compressed: OnceLock::new()
+        }
     }
-    
-    // Create a ciphertext with a zero value
-    pub fn zero() -> Self {
+
+    // Create a Ciphertext from its points and their already known compressed form
+    // The compressed form must match the points
+    pub(crate) fn with_compressed(commitment: PedersenCommitment, handle: DecryptHandle, compressed: CompressedCiphertext) -> Self {
         Self {
-            commitment: PedersenCommitment::from_point(RistrettoPoint::identity()),
-            handle: DecryptHandle::from_point(RistrettoPoint::identity()),
+            commitment,
+            handle,
+            compressed: OnceLock::from(compressed)
         }
     }
 
+    // Create a ciphertext with a zero value
+    pub fn zero() -> Self {
+        Self::new(
+            PedersenCommitment::from_point(RistrettoPoint::identity()),
+            DecryptHandle::from_point(RistrettoPoint::identity())
+        )
+    }
+
     // Get the commitment
     pub fn commitment(&self) -> &PedersenCommitment {
         &self.commitment
@@ -37,12 +58,35 @@ impl Ciphertext {
         &self.handle
     }
 
-    // Compress the Ciphertext
-    pub fn compress(&self) -> CompressedCiphertext {
-        CompressedCiphertext::new(
+    // Compressed Ciphertext, computed only once until the next operation
+    pub fn compressed(&self) -> &CompressedCiphertext {
+        self.compressed.get_or_init(|| CompressedCiphertext::new(
             CompressedCommitment::new(self.commitment.as_point().compress()),
             CompressedHandle::new(self.handle.as_point().compress())
-        )
+        ))
+    }
+
+    // Compress the Ciphertext
+    pub fn compress(&self) -> CompressedCiphertext {
+        self.compressed().clone()
+    }
+}
+
+// The cached compressed form is not part of the value
+impl PartialEq for Ciphertext {
+    fn eq(&self, other: &Self) -> bool {
+        self.commitment == other.commitment && self.handle == other.handle
+    }
+}
+
+impl Eq for Ciphertext {}
+
+impl fmt::Debug for Ciphertext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ciphertext")
+            .field("commitment", &self.commitment)
+            .field("handle", &self.handle)
+            .finish()
     }
 }
 
@@ -52,10 +96,7 @@ impl Add<u64> for Ciphertext {
     type Output = Self;
 
     fn add(self, rhs: u64) -> Self {
-        Self {
-            commitment: self.commitment + Scalar::from(rhs),
-            handle: self.handle,
-        }
+        Self::new(self.commitment + Scalar::from(rhs), self.handle)
     }
 }
 
@@ -63,10 +104,7 @@ impl Add for Ciphertext {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
-        Self {
-            commitment: self.commitment + rhs.commitment,
-            handle: self.handle + rhs.handle,
-        }
+        Self::new(self.commitment + rhs.commitment, self.handle + rhs.handle)
     }
 }
 
@@ -74,30 +112,21 @@ impl Add<&Ciphertext> for Ciphertext {
     type Output = Self;
 
     fn add(self, rhs: &Self) -> Self {
-        Self {
-            commitment: self.commitment + &rhs.commitment,
-            handle: self.handle + &rhs.handle,
-        }
+        Self::new(self.commitment + &rhs.commitment, self.handle + &rhs.handle)
     }
 }
 
 impl Add<Scalar> for Ciphertext {
     type Output = Self;
     fn add(self, rhs: Scalar) -> Self {
-        Self {
-            commitment: self.commitment + rhs,
-            handle: self.handle,
-        }
+        Self::new(self.commitment + rhs, self.handle)
     }
 }
 
 impl Add<&Scalar> for Ciphertext {
     type Output = Self;
     fn add(self, rhs: &Scalar) -> Self {
-        Self {
-            commitment: self.commitment + rhs,
-            handle: self.handle,
-        }
+        Self::new(self.commitment + rhs, self.handle)
     }
 }
 
@@ -105,12 +134,14 @@ impl Add<&Scalar> for Ciphertext {
 
 impl AddAssign<u64> for Ciphertext {
     fn add_assign(&mut self, rhs: u64) {
+        self.compressed.take();
         self.commitment += Scalar::from(rhs);
     }
 }
 
 impl AddAssign for Ciphertext {
     fn add_assign(&mut self, rhs: Self) {
+        self.compressed.take();
         self.commitment += rhs.commitment;
         self.handle += rhs.handle;
     }
@@ -118,6 +149,7 @@ impl AddAssign for Ciphertext {
 
 impl AddAssign<&Ciphertext> for Ciphertext {
     fn add_assign(&mut self, rhs: &Self) {
+        self.compressed.take();
         self.commitment += &rhs.commitment;
         self.handle += &rhs.handle;
     }
@@ -125,12 +157,14 @@ impl AddAssign<&Ciphertext> for Ciphertext {
 
 impl AddAssign<Scalar> for Ciphertext {
     fn add_assign(&mut self, rhs: Scalar) {
+        self.compressed.take();
         self.commitment += rhs;
     }
 }
 
 impl AddAssign<&Scalar> for Ciphertext {
     fn add_assign(&mut self, rhs: &Scalar) {
+        self.compressed.take();
         self.commitment += rhs;
     }
 }
@@ -141,10 +175,7 @@ impl Sub<u64> for Ciphertext {
     type Output = Self;
 
     fn sub(self, rhs: u64) -> Self {
-        Self {
-            commitment: self.commitment - Scalar::from(rhs),
-            handle: self.handle,
-        }
+        Self::new(self.commitment - Scalar::from(rhs), self.handle)
     }
 }
 
@@ -152,10 +183,7 @@ impl Sub for Ciphertext {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
-        Self {
-            commitment: self.commitment - rhs.commitment,
-            handle: self.handle - rhs.handle,
-        }
+        Self::new(self.commitment - rhs.commitment, self.handle - rhs.handle)
     }
 }
 
@@ -163,30 +191,21 @@ impl Sub<&Ciphertext> for Ciphertext {
     type Output = Self;
 
     fn sub(self, rhs: &Self) -> Self {
-        Self {
-            commitment: self.commitment - &rhs.commitment,
-            handle: self.handle - &rhs.handle,
-        }
+        Self::new(self.commitment - &rhs.commitment, self.handle - &rhs.handle)
     }
 }
 
 impl Sub<Scalar> for Ciphertext {
     type Output = Self;
     fn sub(self, rhs: Scalar) -> Self {
-        Self {
-            commitment: self.commitment - rhs,
-            handle: self.handle,
-        }
+        Self::new(self.commitment - rhs, self.handle)
     }
 }
 
 impl Sub<&Scalar> for Ciphertext {
     type Output = Self;
     fn sub(self, rhs: &Scalar) -> Self {
-        Self {
-            commitment: self.commitment - rhs,
-            handle: self.handle,
-        }
+        Self::new(self.commitment - rhs, self.handle)
     }
 }
 
@@ -194,12 +213,14 @@ impl Sub<&Scalar> for Ciphertext {
 
 impl SubAssign<u64> for Ciphertext {
     fn sub_assign(&mut self, rhs: u64) {
+        self.compressed.take();
         self.commitment -= Scalar::from(rhs);
     }
 }
 
 impl SubAssign for Ciphertext {
     fn sub_assign(&mut self, rhs: Self) {
+        self.compressed.take();
         self.commitment -= rhs.commitment;
         self.handle -= rhs.handle;
     }
@@ -207,6 +228,7 @@ impl SubAssign for Ciphertext {
 
 impl SubAssign<&Ciphertext> for Ciphertext {
     fn sub_assign(&mut self, rhs: &Self) {
+        self.compressed.take();
         self.commitment -= &rhs.commitment;
         self.handle -= &rhs.handle;
     }
@@ -214,12 +236,14 @@ impl SubAssign<&Ciphertext> for Ciphertext {
 
 impl SubAssign<Scalar> for Ciphertext {
     fn sub_assign(&mut self, rhs: Scalar) {
+        self.compressed.take();
         self.commitment -= rhs;
     }
 }
 
 impl SubAssign<&Scalar> for Ciphertext {
     fn sub_assign(&mut self, rhs: &Scalar) {
+        self.compressed.take();
         self.commitment -= rhs;
     }
 }
@@ -229,7 +253,7 @@ impl Serialize for Ciphertext {
     where
         S: serde::ser::Serializer,
     {
-        self.compress().serialize(serializer)
+        self.compressed().serialize(serializer)
     }
 }
 
@@ -241,4 +265,69 @@ impl<'a> Deserialize<'a> for Ciphertext {
         let compressed = CompressedCiphertext::deserialize(deserializer)?;
         compressed.decompress().map_err(serde::de::Error::custom)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+    use super::*;
+    use super::super::KeyPair;
+
+    // Compression without the cache
+    fn fresh_compress(ciphertext: &Ciphertext) -> CompressedCiphertext {
+        Ciphertext::new(ciphertext.commitment().clone(), ciphertext.handle().clone()).compress()
+    }
+
+    #[test]
+    fn test_cached_compression() {
+        let keypair = KeyPair::new();
+        let public_key = keypair.get_public_key();
+        let mut ciphertext = public_key.encrypt(Scalar::from(50u64));
+        let compressed = ciphertext.compress();
+        assert_eq!(*ciphertext.compressed(), compressed);
+
+        // Decompressed ciphertext reuses its compressed form
+        let decompressed = compressed.decompress().unwrap();
+        assert_eq!(decompressed, ciphertext);
+        assert_eq!(fresh_compress(&decompressed), compressed);
+
+        let other = public_key.encrypt(Scalar::from(20u64));
+        ciphertext += &other;
+        assert_eq!(ciphertext.compress(), fresh_compress(&ciphertext));
+        assert_ne!(ciphertext.compress(), compressed);
+        ciphertext -= 10u64;
+        assert_eq!(ciphertext.compress(), fresh_compress(&ciphertext));
+        ciphertext += Scalar::from(3u64);
+        assert_eq!(ciphertext.compress(), fresh_compress(&ciphertext));
+        ciphertext -= other.clone();
+        assert_eq!(ciphertext.compress(), fresh_compress(&ciphertext));
+
+        let sum = ciphertext.clone() + other.clone() - 5u64;
+        assert_eq!(sum.compress(), fresh_compress(&sum));
+        let decrypted = keypair.get_private_key().decrypt_to_point(&sum);
+        assert_eq!(decrypted, Scalar::from(58u64) * &super::super::G);
+    }
+
+    // Run it with --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_cached_compression() {
+        const ITERATIONS: u32 = 10_000;
+        let ciphertext = KeyPair::new().get_public_key().encrypt(Scalar::from(1u64));
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(fresh_compress(&ciphertext));
+        }
+        let fresh = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(ciphertext.compress());
+        }
+        let cached = start.elapsed();
+
+        println!("fresh compression: {:?}/op, cached compression: {:?}/op", fresh / ITERATIONS, cached / ITERATIONS);
+        assert!(cached < fresh);
+    }
+}
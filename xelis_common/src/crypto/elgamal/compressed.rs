@@ -139,7 +139,8 @@ impl CompressedCiphertext {
         let commitment = self.commitment.decompress()?;
         let handle = self.handle.decompress()?;
 
-        Ok(Ciphertext::new(commitment, handle))
+        // No need to compress it again later
+        Ok(Ciphertext::with_compressed(commitment, handle, self.clone()))
     }
 }
 
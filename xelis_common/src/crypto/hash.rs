@@ -4,6 +4,8 @@ use std::{
     convert::TryInto,
     hash::Hasher,
     borrow::Cow,
+    ops::{Deref, DerefMut},
+    sync::Mutex
 };
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Serialize};
@@ -49,21 +51,83 @@ impl Hash {
     }
 }
 
+// A new scratch pad is allocated for each call, see pow_hash_with to reuse one
 pub fn pow_hash(work: &[u8]) -> Result<Hash, XelisHashError> {
-    let mut scratchpad = ScratchPad::default();
+    pow_hash_with(work, &mut ScratchPad::default())
+}
 
+pub fn pow_hash_with(work: &[u8], scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
     // Make sure the input has good alignment
     let mut input = AlignedInput::default();
     let slice = input.as_mut_slice()?;
     slice[..work.len()].copy_from_slice(work);
 
-    pow_hash_with_scratch_pad(input.as_mut_slice()?, &mut scratchpad)
+    pow_hash_with_scratch_pad(input.as_mut_slice()?, scratch_pad)
 }
 
 pub fn pow_hash_with_scratch_pad(input: &mut [u8; BYTES_ARRAY_INPUT], scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
     xelis_hash(input, scratch_pad).map(|bytes| Hash::new(bytes))
 }
 
+// Implementation used to compute a POW hash
+// It is selected at runtime to compare or accelerate the hashing
+pub trait PowHasher: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn pow_hash(&self, input: &mut [u8; BYTES_ARRAY_INPUT], scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError>;
+}
+
+// Default implementation, reuse the scratch pad between two hashes
+pub struct OptimizedPowHasher;
+
+impl PowHasher for OptimizedPowHasher {
+    fn name(&self) -> &'static str {
+        "optimized"
+    }
+
+    fn pow_hash(&self, input: &mut [u8; BYTES_ARRAY_INPUT], scratch_pad: &mut ScratchPad) -> Result<Hash, XelisHashError> {
+        pow_hash_with_scratch_pad(input, scratch_pad)
+    }
+}
+
+// Reference implementation, slower as it uses a new input and scratch pad for each hash
+// The result doesn't depend on any state kept between two hashes
+pub struct ReferencePowHasher;
+
+impl PowHasher for ReferencePowHasher {
+    fn name(&self) -> &'static str {
+        "reference"
+    }
+
+    fn pow_hash(&self, input: &mut [u8; BYTES_ARRAY_INPUT], _: &mut ScratchPad) -> Result<Hash, XelisHashError> {
+        let mut scratch_pad = ScratchPad::default();
+        let mut aligned = AlignedInput::default();
+        let slice = aligned.as_mut_slice()?;
+        slice.copy_from_slice(input);
+        pow_hash_with_scratch_pad(slice, &mut scratch_pad)
+    }
+}
+
+// Used for launch param
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum PowBackend {
+    #[default]
+    Optimized,
+    Reference
+}
+
+impl PowBackend {
+    pub const ALL: [PowBackend; 2] = [PowBackend::Optimized, PowBackend::Reference];
+
+    pub fn get_hasher(&self) -> &'static dyn PowHasher {
+        match self {
+            Self::Optimized => &OptimizedPowHasher,
+            Self::Reference => &ReferencePowHasher
+        }
+    }
+}
+
 impl Serializer for Hash {
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
         let hash = reader.read_hash()?;
@@ -149,4 +213,166 @@ impl<'a> Into<Cow<'a, Hash>> for &'a Hash {
     fn into(self) -> Cow<'a, Hash> {
         Cow::Borrowed(self)
     }
-}
\ No newline at end of file
+}
+// Pool of scratch pads shared between the mining threads
+// Each hash fully overwrites the scratch pad before reading it,
+// so it can be reused by another job or thread without being reset
+pub struct ScratchPadPool {
+    pads: Mutex<Vec<Box<ScratchPad>>>,
+    // max scratch pads kept once given back
+    capacity: usize
+}
+
+impl ScratchPadPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            pads: Mutex::new(Vec::with_capacity(capacity)),
+            capacity
+        }
+    }
+
+    // Get a scratch pad from the pool or allocate a new one if none is available
+    // It is given back to the pool when dropped
+    pub fn get(&self) -> PooledScratchPad<'_> {
+        let pad = self.lock().pop().unwrap_or_default();
+        PooledScratchPad {
+            pool: self,
+            pad: Some(pad)
+        }
+    }
+
+    // Allocate the missing scratch pads up to the capacity
+    // so the next hashes don't pay for it
+    pub fn fill(&self) {
+        let mut pads = self.lock();
+        while pads.len() < self.capacity {
+            pads.push(Box::default());
+        }
+    }
+
+    // Scratch pads ready to be reused
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Box<ScratchPad>>> {
+        // A panic while holding the lock can't leave the list in a bad state
+        self.pads.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn give_back(&self, pad: Box<ScratchPad>) {
+        let mut pads = self.lock();
+        if pads.len() < self.capacity {
+            pads.push(pad);
+        }
+    }
+}
+
+pub struct PooledScratchPad<'a> {
+    pool: &'a ScratchPadPool,
+    pad: Option<Box<ScratchPad>>
+}
+
+impl Deref for PooledScratchPad<'_> {
+    type Target = ScratchPad;
+
+    fn deref(&self) -> &Self::Target {
+        self.pad.as_ref().expect("scratch pad already given back")
+    }
+}
+
+impl DerefMut for PooledScratchPad<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.pad.as_mut().expect("scratch pad already given back")
+    }
+}
+
+impl Drop for PooledScratchPad<'_> {
+    fn drop(&mut self) {
+        if let Some(pad) = self.pad.take() {
+            self.pool.give_back(pad);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+    use super::*;
+
+    fn pow_inputs(count: u8) -> Vec<AlignedInput> {
+        (0..count).map(|i| {
+            let mut input = AlignedInput::default();
+            input.as_mut_slice().unwrap().iter_mut().enumerate().for_each(|(j, v)| *v = (j as u8).wrapping_mul(i));
+            input
+        }).collect()
+    }
+
+    #[test]
+    fn test_pow_backends_equivalence() {
+        let mut scratch_pad = ScratchPad::default();
+        for mut input in pow_inputs(4) {
+            let expected = pow_hash(input.as_mut_slice().unwrap()).unwrap();
+            for backend in PowBackend::ALL {
+                // The scratch pad is reused by the backends
+                let mut bytes = input.as_mut_slice().unwrap().clone();
+                assert_eq!(backend.get_hasher().pow_hash(&mut bytes, &mut scratch_pad).unwrap(), expected, "{}", backend.get_hasher().name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_scratch_pad_pool() {
+        let pool = ScratchPadPool::new(1);
+        for mut input in pow_inputs(4) {
+            let expected = pow_hash(input.as_mut_slice().unwrap()).unwrap();
+            // The same scratch pad is reused for each input
+            let mut scratch_pad = pool.get();
+            let mut bytes = input.as_mut_slice().unwrap().clone();
+            assert_eq!(pow_hash_with_scratch_pad(&mut bytes, &mut scratch_pad).unwrap(), expected);
+        }
+        assert_eq!(pool.available(), 1);
+
+        // Pads above the capacity are dropped
+        let first = pool.get();
+        let second = pool.get();
+        assert_eq!(pool.available(), 0);
+        drop(first);
+        drop(second);
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_scratch_pad_pool_fill() {
+        let pool = ScratchPadPool::new(2);
+        pool.fill();
+        assert_eq!(pool.available(), 2);
+
+        let work = [7u8; 112];
+        let expected = pow_hash(&work).unwrap();
+        assert_eq!(pow_hash_with(&work, &mut pool.get()).unwrap(), expected);
+        // Pad was given back, nothing is allocated again
+        pool.fill();
+        assert_eq!(pool.available(), 2);
+    }
+
+    // Compare the speed of the backends, run it with --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_pow_backends() {
+        let mut inputs = pow_inputs(100);
+        let mut results = Vec::new();
+        for backend in PowBackend::ALL {
+            let hasher = backend.get_hasher();
+            let mut scratch_pad = ScratchPad::default();
+            let start = Instant::now();
+            let hashes = inputs.iter_mut()
+                .map(|input| hasher.pow_hash(&mut input.as_mut_slice().unwrap().clone(), &mut scratch_pad).unwrap())
+                .collect::<Vec<_>>();
+            println!("{}: {} hashes in {:?}", hasher.name(), hashes.len(), start.elapsed());
+            results.push(hashes);
+        }
+
+        assert!(results.windows(2).all(|w| w[0] == w[1]));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{varuint::VarUint, crypto::Hash};
+use crate::{varuint::VarUint, crypto::{Hash, HASH_SIZE}};
 use primitive_types::U256;
 use thiserror::Error;
 
@@ -37,6 +37,14 @@ pub fn compute_difficulty_target(difficulty: &Difficulty) -> Result<U256, Diffic
     Ok(U256::max_value() / diff)
 }
 
+// Difficulty target as a hash, a valid PoW hash must be lower or equal to it
+pub fn compute_difficulty_target_hash(difficulty: &Difficulty) -> Result<Hash, DifficultyError> {
+    let target = compute_difficulty_target(difficulty)?;
+    let mut bytes = [0u8; HASH_SIZE];
+    target.to_big_endian(&mut bytes);
+    Ok(Hash::new(bytes))
+}
+
 // Check if the hash is below the target difficulty
 pub fn check_difficulty_against_target(hash: &Hash, target: &U256) -> bool {
     let hash_work = U256::from_big_endian(hash.as_bytes());
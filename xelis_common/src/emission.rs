@@ -0,0 +1,85 @@
+use crate::config::MAXIMUM_SUPPLY;
+
+// Emission speed factor for the emission curve
+// It is used to calculate based on the supply the block reward
+pub const EMISSION_SPEED_FACTOR: u64 = 20;
+
+// Calculate the block reward based on the current supply
+// This is the function used by the consensus to reward each block
+// Block time target is in milliseconds
+pub fn get_block_reward(supply: u64, block_time_target: u64) -> u64 {
+    // Prevent any overflow
+    if supply >= MAXIMUM_SUPPLY {
+        // Max supply reached, do we want to generate small fixed amount of coins? 
+        return 0
+    }
+
+    let base_reward = (MAXIMUM_SUPPLY - supply) >> EMISSION_SPEED_FACTOR;
+    base_reward * block_time_target / 1000 / 180
+}
+
+// Project the supply after `blocks` blocks starting from `supply`
+// This expects one block per height, side blocks rewards are not included
+pub fn get_projected_supply(mut supply: u64, blocks: u64, block_time_target: u64) -> u64 {
+    for _ in 0..blocks {
+        let reward = get_block_reward(supply, block_time_target);
+        // Nothing will be emitted anymore
+        if reward == 0 {
+            break;
+        }
+        supply += reward;
+    }
+
+    supply
+}
+
+// Expected supply before the block at this height is rewarded
+// Genesis block (height 0) is rewarded with an empty supply
+pub fn get_supply_at_height(height: u64, block_time_target: u64) -> u64 {
+    get_projected_supply(0, height, block_time_target)
+}
+
+// Expected block reward at this height following the emission curve
+pub fn get_block_reward_at_height(height: u64, block_time_target: u64) -> u64 {
+    get_block_reward(get_supply_at_height(height, block_time_target), block_time_target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_TIME_TARGET: u64 = 15_000;
+
+    #[test]
+    fn test_genesis_reward() {
+        assert_eq!(get_block_reward(0, BLOCK_TIME_TARGET), 146230061);
+        assert_eq!(get_block_reward_at_height(0, BLOCK_TIME_TARGET), 146230061);
+        assert_eq!(get_block_reward_at_height(1, BLOCK_TIME_TARGET), 146230050);
+    }
+
+    #[test]
+    fn test_mid_curve_reward() {
+        assert_eq!(get_supply_at_height(1_000_000, BLOCK_TIME_TARGET), 140570325740083);
+        assert_eq!(get_block_reward_at_height(1_000_000, BLOCK_TIME_TARGET), 135058536);
+        assert_eq!(get_block_reward_at_height(3_250_000, BLOCK_TIME_TARGET), 112944251);
+    }
+
+    #[test]
+    fn test_projected_supply_continuity() {
+        let supply = get_supply_at_height(1_000, BLOCK_TIME_TARGET);
+        assert_eq!(get_projected_supply(supply, 1_000, BLOCK_TIME_TARGET), get_supply_at_height(2_000, BLOCK_TIME_TARGET));
+    }
+
+    #[test]
+    fn test_tail_reward() {
+        // Smallest remaining supply still producing a reward
+        let min_remaining = 12 << EMISSION_SPEED_FACTOR;
+        assert_eq!(get_block_reward(MAXIMUM_SUPPLY - min_remaining, BLOCK_TIME_TARGET), 1);
+        assert_eq!(get_block_reward(MAXIMUM_SUPPLY - min_remaining + 1, BLOCK_TIME_TARGET), 0);
+        assert_eq!(get_block_reward(MAXIMUM_SUPPLY, BLOCK_TIME_TARGET), 0);
+        assert_eq!(get_block_reward(u64::MAX, BLOCK_TIME_TARGET), 0);
+
+        // Supply reached once the emission is over
+        assert_eq!(get_projected_supply(1839999987417089, 1, BLOCK_TIME_TARGET), 1839999987417089);
+    }
+}
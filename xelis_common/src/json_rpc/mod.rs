@@ -4,16 +4,18 @@ use thiserror::Error;
 use tokio_tungstenite::tungstenite::Error as TungsteniteError;
 
 mod http;
+mod socket;
 mod websocket;
 
 pub use http::JsonRPCClient;
-pub use websocket::{WebSocketJsonRPCClientImpl, WebSocketJsonRPCClient, EventReceiver};
+pub use socket::{get_unix_socket_path, UNIX_SOCKET_PREFIX};
+pub use websocket::{WebSocketJsonRPCClientImpl, WebSocketJsonRPCClient, UnixJsonRPCClient, EventReceiver, ConnectionState, RequestKind, DEFAULT_TIMEOUT, DEFAULT_MAX_RETRIES};
 
 const JSON_RPC_VERSION: &str = "2.0";
 
 const PARSE_ERROR_CODE: i16 = -32700;
 const INVALID_REQUEST_CODE: i16 = -32600;
-const METHOD_NOT_FOUND_CODE: i16 = -32601;
+pub const METHOD_NOT_FOUND_CODE: i16 = -32601;
 const INVALID_PARAMS_CODE: i16 = -32602;
 const INTERNAL_ERROR_CODE: i16 = -32603;
 
@@ -58,7 +60,7 @@ pub enum JsonRPCError {
     #[error("Server did not respond to the request")]
     NoResponse,
     #[error("No response in the given time")]
-    TimedOut,
+    Timeout,
     #[error("Server returned a response without result")]
     MissingResult,
     #[error("Error while (de)serializing JSON data: {}", _0)]
@@ -72,5 +74,7 @@ pub enum JsonRPCError {
     #[error(transparent)]
     SocketError(#[from] TungsteniteError),
     #[error(transparent)]
-    Any(#[from] anyhow::Error)
+    Any(#[from] anyhow::Error),
+    #[error("No response for {}, the request may have been executed by the server", _0)]
+    MaybeSubmitted(String)
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll}
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream
+};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio_tungstenite::{
+    client_async_tls,
+    tungstenite::client::IntoClientRequest,
+    MaybeTlsStream,
+    WebSocketStream
+};
+use super::JsonRPCError;
+
+// Prefix of a target using a Unix domain socket instead of TCP
+// Example: unix:///var/run/xelis/daemon.sock
+pub const UNIX_SOCKET_PREFIX: &str = "unix://";
+
+// Request sent over the Unix domain socket, the host is never resolved
+const UNIX_SOCKET_REQUEST: &str = "ws://localhost/json_rpc";
+
+// Connection used below the WebSocket protocol
+pub enum ClientSocket {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream)
+}
+
+pub type ClientStream = WebSocketStream<MaybeTlsStream<ClientSocket>>;
+
+// Path of the socket if the target is a Unix domain socket
+pub fn get_unix_socket_path(target: &str) -> Option<&str> {
+    target.strip_prefix(UNIX_SOCKET_PREFIX)
+}
+
+// Connect to the target, using TLS if requested by the scheme
+pub async fn connect(target: &str) -> Result<ClientStream, JsonRPCError> {
+    let (ws, response) = match get_unix_socket_path(target) {
+        Some(path) => connect_unix(path).await?,
+        None => {
+            let request = target.into_client_request()?;
+            let uri = request.uri();
+            let host = uri.host().ok_or_else(|| JsonRPCError::ConnectionError(format!("No host in {}", target)))?;
+            let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+            let stream = TcpStream::connect((host, port)).await
+                .map_err(|e| JsonRPCError::ConnectionError(e.to_string()))?;
+
+            client_async_tls(request, ClientSocket::Tcp(stream)).await?
+        }
+    };
+
+    let status = response.status();
+    if status.is_server_error() || status.is_client_error() {
+        return Err(JsonRPCError::ConnectionError(status.to_string()));
+    }
+
+    Ok(ws)
+}
+
+#[cfg(unix)]
+async fn connect_unix(path: &str) -> Result<(ClientStream, tokio_tungstenite::tungstenite::handshake::client::Response), JsonRPCError> {
+    let stream = UnixStream::connect(path).await
+        .map_err(|e| JsonRPCError::ConnectionError(format!("{}: {}", path, e)))?;
+
+    Ok(client_async_tls(UNIX_SOCKET_REQUEST, ClientSocket::Unix(stream)).await?)
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(_: &str) -> Result<(ClientStream, tokio_tungstenite::tungstenite::handshake::client::Response), JsonRPCError> {
+    Err(JsonRPCError::ConnectionError("Unix domain sockets are not supported on this platform".to_owned()))
+}
+
+impl AsyncRead for ClientSocket {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf)
+        }
+    }
+}
+
+impl AsyncWrite for ClientSocket {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf)
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx)
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio::net::UnixListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+    use crate::json_rpc::WebSocketJsonRPCClientImpl;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unix_socket_client() {
+        let path = std::env::temp_dir().join(format!("xelis-json-rpc-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // Answer each request with its method name
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["method"] });
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+            }
+        });
+
+        let path_str = path.to_str().unwrap();
+        let client = WebSocketJsonRPCClientImpl::<String>::with_unix_socket(path_str).await.unwrap();
+        assert_eq!(get_unix_socket_path(client.get_target()), Some(path_str));
+        let result: String = client.call("get_info").await.unwrap();
+        assert_eq!(result, "get_info");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     sync::{
-        atomic::{AtomicUsize, Ordering, AtomicBool},
+        atomic::{AtomicUsize, AtomicU64, Ordering, AtomicBool},
         Arc
     },
     collections::HashMap,
@@ -10,6 +10,7 @@ use std::{
     time::Duration
 };
 use anyhow::Error;
+use rand::Rng;
 use futures_util::{
     StreamExt,
     stream::{SplitSink, SplitStream},
@@ -18,24 +19,24 @@ use futures_util::{
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{Value, json};
 use tokio::{
-    net::TcpStream,
     sync::{broadcast, oneshot, Mutex},
     task::JoinHandle,
-    time::{sleep, timeout}
-};
-use tokio_tungstenite::{
-    WebSocketStream,
-    MaybeTlsStream,
-    connect_async,
-    tungstenite::Message
+    time::{sleep, timeout, timeout_at, Instant}
 };
+use tokio_tungstenite::tungstenite::Message;
 use log::{debug, error, trace, warn};
 use crate::{
     api::SubscribeParams,
     utils::{sanitize_daemon_address, spawn_task}
 };
 
-use super::{JSON_RPC_VERSION, JsonRPCError, JsonRPCResponse, JsonRPCResult};
+use super::{
+    socket::{self, get_unix_socket_path, ClientStream, UNIX_SOCKET_PREFIX},
+    JSON_RPC_VERSION,
+    JsonRPCError,
+    JsonRPCResponse,
+    JsonRPCResult
+};
 
 // EventReceiver allows to get the event value parsed directly
 pub struct EventReceiver<T: DeserializeOwned> {
@@ -77,10 +78,13 @@ impl<T: DeserializeOwned> EventReceiver<T> {
 // it has a tokio task running in background to handle all incoming messages
 pub type WebSocketJsonRPCClient<E> = Arc<WebSocketJsonRPCClientImpl<E>>;
 
+// The same client connected through a Unix domain socket
+pub type UnixJsonRPCClient<E> = WebSocketJsonRPCClient<E>;
+
 // A JSON-RPC Client over WebSocket protocol to support events
 // It can be used in multi-thread safely because each request/response are linked using the id attribute.
 pub struct WebSocketJsonRPCClientImpl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> {
-    ws: Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
+    ws: Mutex<SplitSink<ClientStream, Message>>,
     count: AtomicUsize,
     requests: Mutex<HashMap<usize, oneshot::Sender<JsonRPCResponse>>>,
     // This contains all id sent to register to a event on daemon
@@ -89,8 +93,10 @@ pub struct WebSocketJsonRPCClientImpl<E: Serialize + Hash + Eq + Send + Sync + C
     // This contains all events registered by the app with its usize
     // This allows us to subscribe to same channel if its already subscribed
     events_to_id: Mutex<HashMap<E, usize>>,
-    // websocket server address
-    target: String,
+    // websocket server addresses, ordered by preference
+    targets: Vec<String>,
+    // index of the target we are currently connected to
+    current_target: AtomicUsize,
     // auto reconnect duration
     auto_reconnect: Mutex<Option<Duration>>,
     // is the client online
@@ -99,30 +105,105 @@ pub struct WebSocketJsonRPCClientImpl<E: Serialize + Hash + Eq + Send + Sync + C
     offline_channel: Mutex<Option<broadcast::Sender<()>>>,
     // This channel is called each time we connect
     online_channel: Mutex<Option<broadcast::Sender<()>>>,
+    // This channel is called each time the connection state changes
+    connection_changed_channel: Mutex<Option<broadcast::Sender<ConnectionState>>>,
     // Background task that keep alive WS connection
     background_task: Mutex<Option<JoinHandle<()>>>,
-    // Timeout for a request
-    timeout_after: Duration,
+    // Default timeout for a request, in milliseconds
+    timeout_after: AtomicU64,
+    // How many times a read-only request is sent again
+    max_retries: AtomicUsize,
 }
 
+// Default timeout of a request
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Read-only requests are retried after a timeout or a connection reset
+pub const DEFAULT_MAX_RETRIES: usize = 3;
+// Delay before the first retry, doubled at each retry
+// A random delay up to this value is added
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
 pub const DEFAULT_AUTO_RECONNECT: Duration = Duration::from_secs(5);
+// Maximum delay between two reconnection rounds
+pub const MAX_AUTO_RECONNECT: Duration = Duration::from_secs(60);
+
+// Define if a request can be sent again automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    // Doesn't change the server state, it is retried on failure
+    ReadOnly,
+    // Changes the server state and is never retried
+    // MaybeSubmitted error is returned if no response was received
+    Mutating
+}
+
+impl RequestKind {
+    // Methods starting with get_ or count_ are read-only
+    pub fn from_method(method: &str) -> Self {
+        if method.starts_with("get_") || method.starts_with("count_") {
+            Self::ReadOnly
+        } else {
+            Self::Mutating
+        }
+    }
+}
+
+// State sent through the connection changed channel
+#[derive(Debug, Clone)]
+pub enum ConnectionState {
+    // Connected to the target address
+    Connected(String),
+    // Connection lost, the client may try to reconnect
+    Disconnected
+}
 
 impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCClientImpl<E> {
-    async fn connect_to(target: &String) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, JsonRPCError> {
-        let (ws, response) = connect_async(target).await?;
-        let status = response.status();
-        if status.is_server_error() || status.is_client_error() {
-            return Err(JsonRPCError::ConnectionError(status.to_string()));
+    async fn connect_to(target: &String) -> Result<ClientStream, JsonRPCError> {
+        socket::connect(target).await
+    }
+
+    // Connect to the first reachable target, in order
+    // Returns the index of the target used
+    async fn connect_to_any(targets: &[String]) -> Result<(usize, ClientStream), JsonRPCError> {
+        let mut last_error = None;
+        for (i, target) in targets.iter().enumerate() {
+            match Self::connect_to(target).await {
+                Ok(ws) => return Ok((i, ws)),
+                Err(e) => {
+                    debug!("Error while connecting to {}: {:?}", target, e);
+                    last_error = Some(e);
+                }
+            }
         }
 
-        Ok(ws)
+        Err(last_error.unwrap_or_else(|| JsonRPCError::ConnectionError("No target address provided".to_owned())))
     }
 
-    pub async fn new(mut target: String) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
-        target = sanitize_daemon_address(target.as_str());
+    pub async fn new(target: String) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
+        Self::with_targets(vec![target]).await
+    }
+
+    // Connect to a server listening on a Unix domain socket
+    // Same protocol as over TCP, so every request and event works identically
+    pub async fn with_unix_socket(path: &str) -> Result<UnixJsonRPCClient<E>, JsonRPCError> {
+        Self::with_targets(vec![format!("{}{}", UNIX_SOCKET_PREFIX, path)]).await
+    }
+
+    // Create a client with several target addresses
+    // The first reachable one is used, and the others are
+    // tried in order when the connection is lost
+    pub async fn with_targets(targets: Vec<String>) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
+        // Unix domain socket paths are kept as is
+        let targets: Vec<String> = targets.iter()
+            .map(|target| match get_unix_socket_path(target) {
+                Some(_) => target.clone(),
+                None => sanitize_daemon_address(target.as_str())
+            })
+            .collect();
+
+        let (current_target, ws) = Self::connect_to_any(&targets).await?;
 
-        let ws = Self::connect_to(&target).await?;
-        
         let (write, read) = ws.split();
         let client = Arc::new(WebSocketJsonRPCClientImpl {
             ws: Mutex::new(write),
@@ -130,13 +211,16 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             requests: Mutex::new(HashMap::new()),
             handler_by_id: Mutex::new(HashMap::new()),
             events_to_id: Mutex::new(HashMap::new()),
-            target,
+            targets,
+            current_target: AtomicUsize::new(current_target),
             auto_reconnect: Mutex::new(Some(DEFAULT_AUTO_RECONNECT)),
             online: AtomicBool::new(true),
             offline_channel: Mutex::new(None),
             online_channel: Mutex::new(None),
+            connection_changed_channel: Mutex::new(None),
             background_task: Mutex::new(None),
-            timeout_after: Duration::from_secs(5),
+            timeout_after: AtomicU64::new(DEFAULT_TIMEOUT.as_millis() as u64),
+            max_retries: AtomicUsize::new(DEFAULT_MAX_RETRIES),
         });
 
         {
@@ -155,13 +239,41 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         Ok(client)
     }
 
+    // Set the default timeout used by each request
+    pub fn with_timeout(self: Arc<Self>, timeout: Duration) -> Arc<Self> {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.timeout_after.store(timeout.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_after.load(Ordering::SeqCst))
+    }
+
+    // Set how many times a read-only request is sent again on failure
+    pub fn with_max_retries(self: Arc<Self>, max_retries: usize) -> Arc<Self> {
+        self.set_max_retries(max_retries);
+        self
+    }
+
+    pub fn set_max_retries(&self, max_retries: usize) {
+        self.max_retries.store(max_retries, Ordering::SeqCst);
+    }
+
+    pub fn get_max_retries(&self) -> usize {
+        self.max_retries.load(Ordering::SeqCst)
+    }
+
     // Generate a new ID for a JSON-RPC request
     fn next_id(&self) -> usize {
         self.count.fetch_add(1, Ordering::SeqCst)
     }
 
     // Notify a channel if we lose/gain the connection
-    async fn notify_connection_channel(&self, mutex: &Mutex<Option<broadcast::Sender<()>>>) {
+    async fn notify_connection_channel<T: Clone>(&self, mutex: &Mutex<Option<broadcast::Sender<T>>>, value: T) {
         let mut channel = mutex.lock().await;
         if let Some(sender) = channel.as_ref() {
             // Nobody listen anymore, close the channel
@@ -169,7 +281,7 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
                 *channel = None;
             } else {
                 // Notify receivers
-                if let Err(e) = sender.send(()) {
+                if let Err(e) = sender.send(value) {
                     error!("Error sending event to the request: {:?}", e);
                 }
             }
@@ -177,7 +289,7 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
     }
 
     // Register to a channel
-    async fn register_to_connection_channel(&self, mutex: &Mutex<Option<broadcast::Sender<()>>>) -> broadcast::Receiver<()> {
+    async fn register_to_connection_channel<T: Clone>(&self, mutex: &Mutex<Option<broadcast::Sender<T>>>) -> broadcast::Receiver<T> {
         let mut channel = mutex.lock().await;
         match channel.as_ref() {
             Some(sender) => sender.subscribe(),
@@ -198,6 +310,34 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
     pub async fn on_connection(&self) -> broadcast::Receiver<()> {
         self.register_to_connection_channel(&self.online_channel).await
     }
+
+    // Call this function to be notified each time the connection state changes
+    // Connected state contains the target address in use, allowing to detect a failover
+    pub async fn on_connection_changed(&self) -> broadcast::Receiver<ConnectionState> {
+        self.register_to_connection_channel(&self.connection_changed_channel).await
+    }
+
+    // Get the target address currently used
+    pub fn get_target(&self) -> &String {
+        &self.targets[self.current_target.load(Ordering::SeqCst)]
+    }
+
+    // Get all the target addresses configured
+    pub fn get_targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    // We are connected to a target, notify all channels
+    async fn notify_connected(&self, index: usize) {
+        let previous = self.current_target.swap(index, Ordering::SeqCst);
+        if previous != index {
+            warn!("Switched from {} to {}", self.targets[previous], self.targets[index]);
+        }
+
+        self.set_online(true);
+        self.notify_connection_channel(&self.online_channel, ()).await;
+        self.notify_connection_channel(&self.connection_changed_channel, ConnectionState::Connected(self.targets[index].clone())).await;
+    }
     
     // Should the client try to reconnect to the server if the connection is lost
     pub async fn should_auto_reconnect(&self) -> bool {
@@ -272,8 +412,8 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             task.abort();
         }
 
-        {
-            let ws = Self::connect_to(&self.target).await?;
+        let index = {
+            let (index, ws) = Self::connect_to_any(&self.targets).await?;
             let (write, read) = ws.split();
             {
                 let mut lock = self.ws.lock().await;
@@ -287,36 +427,44 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
                 };
             });
             *task = Some(handle);
-        }
-        self.set_online(true);
+            index
+        };
+        self.notify_connected(index).await;
 
         Ok(true)
     }
 
     // Try to reconnect to the server
-    async fn try_reconnect(self: &Arc<Self>) -> Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>> {
+    async fn try_reconnect(self: &Arc<Self>) -> Option<SplitStream<ClientStream>> {
         trace!("try reconnect");
         // We are not online anymore
         self.set_online(false);
 
         // Notify that we are offline
-        self.notify_connection_channel(&self.offline_channel).await;
+        self.notify_connection_channel(&self.offline_channel, ()).await;
+        self.notify_connection_channel(&self.connection_changed_channel, ConnectionState::Disconnected).await;
 
         // Check if we should reconnect
         let mut reconnect = {
             let reconnect = self.auto_reconnect.lock().await;
             reconnect.clone()
         };
-        
+
+        // Delay before the next round, doubled after each failed round
+        let mut delay = None;
+
         // Try to reconnect to the server
-        while let Some(duration) = reconnect.as_ref() {
-            sleep(*duration).await;
+        while let Some(duration) = reconnect {
+            let current = delay.unwrap_or(duration);
+            sleep(current).await;
             debug!("Trying to reconnect to the server...");
 
-            let ws = match Self::connect_to(&self.target).await {
-                Ok(ws) => ws,
+            // Retry all targets in order, so we go back to the preferred one if it is available
+            let (index, ws) = match Self::connect_to_any(&self.targets).await {
+                Ok(v) => v,
                 Err(e) => {
                     debug!("Error while reconnecting to the server: {:?}", e);
+                    delay = Some((current * 2).min(MAX_AUTO_RECONNECT.max(duration)));
                     reconnect = {
                         let reconnect = self.auto_reconnect.lock().await;
                         reconnect.clone()
@@ -342,11 +490,8 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
                 });
             }
 
-            // We are online again
-            self.set_online(true);
-
-            // Notify that we are online again
-            self.notify_connection_channel(&self.online_channel).await;
+            // We are online again, notify it
+            self.notify_connected(index).await;
 
             return Some(read)
         }
@@ -373,9 +518,45 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         }
     }
 
+    // Send the response to its requester or to the event subscribed using its ID
+    // Returns the ID of the event if nobody listens to it anymore
+    async fn handle_response(&self, response: JsonRPCResponse) -> Option<usize> {
+        let Some(id) = response.id else {
+            return None
+        };
+
+        // send the response to the requester if it matches the ID
+        {
+            let mut requests = self.requests.lock().await;
+            if let Some(sender) = requests.remove(&id) {
+                if let Err(e) = sender.send(response) {
+                    error!("Error sending response to the request: {:?}", e);
+                }
+                return None;
+            }
+        }
+
+        // Check if this ID corresponds to a event subscribed
+        {
+            let mut handlers = self.handler_by_id.lock().await;
+            if let Some(sender) = handlers.get_mut(&id) {
+                // Check that we still have someone who listen it
+                if sender.receiver_count() == 0 {
+                    return Some(id)
+                }
+
+                if let Err(e) = sender.send(response.result.unwrap_or_default()) {
+                    error!("Error sending event to the request: {:?}", e);
+                }
+            }
+        }
+
+        None
+    }
+
     // Task running in background to handle every messages from the WebSocket server
     // This includes Events propagated and responses to JSON-RPC requests
-    async fn read(self: Arc<Self>, mut read: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>) -> Result<(), JsonRPCError> {
+    async fn read(self: Arc<Self>, mut read: SplitStream<ClientStream>) -> Result<(), JsonRPCError> {
         while let Some(res) = read.next().await {
             let msg = match res {
                 Ok(msg) => msg,
@@ -396,30 +577,23 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
 
             match msg {
                 Message::Text(text) => {
-                    let response: JsonRPCResponse = serde_json::from_str(&text)?;
-                    if let Some(id) = response.id {
-                        // send the response to the requester if it matches the ID
-                        {
-                            let mut requests = self.requests.lock().await;
-                            if let Some(sender) = requests.remove(&id) {
-                                if let Err(e) = sender.send(response) {
-                                    error!("Error sending response to the request: {:?}", e);
-                                }
-                                continue;
-                            }
-                        }
+                    // A batch request is answered with an array of responses
+                    let responses: Vec<JsonRPCResponse> = match serde_json::from_str(&text)? {
+                        Value::Array(values) => values.into_iter()
+                            .map(serde_json::from_value)
+                            .collect::<Result<_, _>>()?,
+                        value => vec![serde_json::from_value(value)?]
+                    };
 
-                        // Check if this ID corresponds to a event subscribed
-                        {
-                            let mut handlers = self.handler_by_id.lock().await;
-                            if let Some(sender) = handlers.get_mut(&id) {
-                                // Check that we still have someone who listen it
-                                if sender.receiver_count() > 0 {
-                                    if let Err(e) = sender.send(response.result.unwrap_or_default()) {
-                                        error!("Error sending event to the request: {:?}", e);
-                                    }
+                    for response in responses {
+                        if let Some(id) = self.handle_response(response).await {
+                            // Don't wait for the server response in the read task
+                            let zelf = Arc::clone(&self);
+                            spawn_task("ws-unsubscribe-event", async move {
+                                if let Err(e) = zelf.unsubscribe_unused_event(id).await {
+                                    debug!("Error while unsubscribing from unused event: {}", e);
                                 }
-                            }
+                            });
                         }
                     }
                 },
@@ -443,6 +617,98 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         self.send(method, None, params).await
     }
 
+    // Call a method with parameters and a specific timeout instead of the default one
+    pub async fn call_with_timeout<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: &P, timeout: Duration) -> JsonRPCResult<R> {
+        self.send_with_timeout(method, None, params, timeout).await
+    }
+
+    // Call a method with parameters, failures are handled depending on the kind of request
+    // Read-only requests are sent again after a timeout or a connection reset
+    // Mutating requests are never sent again, MaybeSubmitted is returned instead
+    // so the caller can check the server state before retrying
+    pub async fn call_with_kind<P: Serialize, R: DeserializeOwned>(&self, method: &str, kind: RequestKind, params: &P) -> JsonRPCResult<R> {
+        let mut retries = 0;
+        loop {
+            let err = match self.send(method, None, params).await {
+                Ok(res) => return Ok(res),
+                Err(e) => e
+            };
+
+            // Was the request sent but not answered
+            let no_response = matches!(err, JsonRPCError::Timeout | JsonRPCError::NoResponse);
+            match kind {
+                RequestKind::Mutating if no_response => return Err(JsonRPCError::MaybeSubmitted(method.to_owned())),
+                RequestKind::ReadOnly if no_response || matches!(err, JsonRPCError::SocketError(_) | JsonRPCError::ConnectionError(_)) => {
+                    if retries >= self.get_max_retries() {
+                        return Err(err)
+                    }
+
+                    let backoff = RETRY_BACKOFF * 2u32.pow(retries.min(8) as u32);
+                    let jitter = rand::thread_rng().gen_range(0..=RETRY_BACKOFF.as_millis() as u64);
+                    retries += 1;
+                    debug!("Retrying {} ({}/{}) after error: {}", method, retries, self.get_max_retries(), err);
+                    sleep(backoff + Duration::from_millis(jitter)).await;
+                },
+                _ => return Err(err)
+            };
+        }
+    }
+
+    // Send several requests in a single message and wait for all their responses
+    // Results are in the same order as the requests, each one can fail independently
+    // The default timeout applies to the whole batch
+    pub async fn call_batch<P: Serialize>(&self, requests: &[(&str, P)]) -> JsonRPCResult<Vec<JsonRPCResult<Value>>> {
+        if requests.is_empty() {
+            return Ok(Vec::new())
+        }
+
+        let mut messages = Vec::with_capacity(requests.len());
+        let mut receivers = Vec::with_capacity(requests.len());
+        {
+            let mut pending = self.requests.lock().await;
+            for (method, params) in requests {
+                let id = self.next_id();
+                let (sender, receiver) = oneshot::channel();
+                pending.insert(id, sender);
+                receivers.push((id, receiver));
+                messages.push(json!({
+                    "jsonrpc": JSON_RPC_VERSION,
+                    "method": method,
+                    "id": id,
+                    "params": params
+                }));
+            }
+        }
+
+        let sent = match serde_json::to_string(&messages) {
+            Ok(text) => self.ws.lock().await.send(Message::Text(text)).await.map_err(JsonRPCError::from),
+            Err(e) => Err(e.into())
+        };
+        if let Err(e) = sent {
+            let mut pending = self.requests.lock().await;
+            for (id, _) in receivers {
+                pending.remove(&id);
+            }
+            return Err(e)
+        }
+
+        let deadline = Instant::now() + self.get_timeout();
+        let mut results = Vec::with_capacity(receivers.len());
+        for (id, receiver) in receivers {
+            let result = match timeout_at(deadline, receiver).await {
+                Ok(Ok(response)) => Self::into_result(response),
+                Ok(Err(_)) => Err(JsonRPCError::NoResponse),
+                Err(_) => {
+                    self.requests.lock().await.remove(&id);
+                    Err(JsonRPCError::Timeout)
+                }
+            };
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
     // Verify if we already subscribed to this event or not
     pub async fn has_event(&self, event: &E) -> bool {
         let events = self.events_to_id.lock().await;
@@ -487,22 +753,52 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
     }
 
     // Unsubscribe from an event
-    pub async fn unsubscribe_event(&self, event: &E) -> JsonRPCResult<()> {        
+    // All its receivers are closed
+    pub async fn unsubscribe_event(&self, event: &E) -> JsonRPCResult<()> {
         // Retrieve the id for this event
         let id = {
             let mut ids = self.events_to_id.lock().await;
             ids.remove(event).ok_or(JsonRPCError::EventNotRegistered)?
         };
 
-        // Send the unsubscribe rpc method
-        self.send::<E, bool>("unsubscribe", None, event).await?;
-
-        // delete it from events list
+        // delete it from events list, dropping the sender closes the receivers
         {
             let mut handlers = self.handler_by_id.lock().await;
             handlers.remove(&id);
         }
 
+        // Send the unsubscribe rpc method
+        self.send::<_, bool>("unsubscribe", None, &SubscribeParams {
+            notify: Cow::Borrowed(event)
+        }).await?;
+
+        Ok(())
+    }
+
+    // Unsubscribe from the event using this id if all its receivers were dropped
+    // This is detected when the server sends the event again
+    async fn unsubscribe_unused_event(&self, id: usize) -> JsonRPCResult<()> {
+        let event = {
+            let mut ids = self.events_to_id.lock().await;
+            let mut handlers = self.handler_by_id.lock().await;
+            // It may have been subscribed again in the meantime
+            if !handlers.get(&id).is_some_and(|sender| sender.receiver_count() == 0) {
+                return Ok(())
+            }
+            handlers.remove(&id);
+
+            let Some(event) = ids.iter().find(|(_, value)| **value == id).map(|(event, _)| event.clone()) else {
+                return Ok(())
+            };
+            ids.remove(&event);
+            event
+        };
+
+        debug!("No receiver left for event with id {}, unsubscribing", id);
+        self.send::<_, bool>("unsubscribe", None, &SubscribeParams {
+            notify: Cow::Borrowed(&event)
+        }).await?;
+
         Ok(())
     }
 
@@ -518,8 +814,15 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         Ok(())
     }
 
-    // Send a request to the server and wait for the response
+    // Send a request to the server and wait for the response using the default timeout
     async fn send<P: Serialize, R: DeserializeOwned>(&self, method: &str, id: Option<usize>, params: &P) -> JsonRPCResult<R> {
+        self.send_with_timeout(method, id, params, self.get_timeout()).await
+    }
+
+    // Send a request to the server and wait for the response
+    // If no response is received in time, the request is removed
+    // so a late response is ignored instead of being sent to another request
+    async fn send_with_timeout<P: Serialize, R: DeserializeOwned>(&self, method: &str, id: Option<usize>, params: &P, timeout_after: Duration) -> JsonRPCResult<R> {
         let id = id.unwrap_or_else(|| self.next_id());
         let (sender, receiver) = oneshot::channel();
         {
@@ -527,12 +830,26 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             requests.insert(id, sender);
         }
 
-        self.send_message_internal(Some(id), method, params).await?;
+        if let Err(e) = self.send_message_internal(Some(id), method, params).await {
+            self.requests.lock().await.remove(&id);
+            return Err(e)
+        }
 
-        let response = timeout(self.timeout_after, receiver).await
-            .or(Err(JsonRPCError::TimedOut))?
-            .or(Err(JsonRPCError::NoResponse))?;
+        let response = match timeout(timeout_after, receiver).await {
+            Ok(res) => res.or(Err(JsonRPCError::NoResponse))?,
+            Err(_) => {
+                debug!("Request {} with id {} timed out after {:?}", method, id, timeout_after);
+                self.requests.lock().await.remove(&id);
+                return Err(JsonRPCError::Timeout)
+            }
+        };
 
+        let result = Self::into_result(response)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    // Get the result of a response or the error returned by the server
+    fn into_result(response: JsonRPCResponse) -> JsonRPCResult<Value> {
         if let Some(error) = response.error {
             return Err(JsonRPCError::ServerError {
                 code: error.code,
@@ -541,9 +858,7 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             });
         }
 
-        let result = response.result.ok_or(JsonRPCError::NoResponse)?;
-
-        Ok(serde_json::from_value(result)?)
+        response.result.ok_or(JsonRPCError::NoResponse)
     }
 
     // Send a request to the server without waiting for the response
@@ -556,4 +871,190 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
     pub async fn notify<P: Serialize>(&self, method: &str) -> JsonRPCResult<()> {
         self.notify_with(method, &Value::Null).await
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+    use super::*;
+
+    // Start a server which never answers the first request it receives
+    // Returns its address and the counter of requests received
+    async fn mock_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("ws://{}", listener.local_addr().unwrap());
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    continue;
+                }
+
+                let response = json!({
+                    "jsonrpc": JSON_RPC_VERSION,
+                    "id": request["id"],
+                    "result": true
+                });
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+            }
+        });
+
+        (address, received)
+    }
+
+    async fn connect(address: String) -> WebSocketJsonRPCClient<String> {
+        WebSocketJsonRPCClientImpl::new(address).await.unwrap()
+            .with_timeout(Duration::from_millis(100))
+    }
+
+    #[tokio::test]
+    async fn test_read_only_retried() {
+        let (address, received) = mock_server().await;
+        let client = connect(address).await;
+
+        let res: bool = client.call_with_kind("get_info", RequestKind::ReadOnly, &Value::Null).await.unwrap();
+        assert!(res);
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mutating_not_retried() {
+        let (address, received) = mock_server().await;
+        let client = connect(address).await;
+
+        let res = client.call_with_kind::<_, bool>("submit_transaction", RequestKind::Mutating, &Value::Null).await;
+        assert!(matches!(res, Err(JsonRPCError::MaybeSubmitted(_))));
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+
+        // Caller decides to send it again
+        let res: bool = client.call_with_kind("submit_transaction", RequestKind::Mutating, &Value::Null).await.unwrap();
+        assert!(res);
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_partial_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let requests: Vec<Value> = serde_json::from_str(&text).unwrap();
+                // Answer in reverse order, responses are matched by their id
+                let responses: Vec<Value> = requests.iter().rev().map(|request| if request["method"] == "fail" {
+                    json!({ "jsonrpc": JSON_RPC_VERSION, "id": request["id"], "error": { "code": -32603, "message": "failed" } })
+                } else {
+                    json!({ "jsonrpc": JSON_RPC_VERSION, "id": request["id"], "result": request["params"] })
+                }).collect();
+                ws.send(Message::Text(json!(responses).to_string())).await.unwrap();
+            }
+        });
+
+        let client = connect(address).await;
+        let results = client.call_batch(&[("echo", json!(1)), ("fail", json!(2)), ("echo", json!(3))]).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &json!(1));
+        assert!(matches!(results[1], Err(JsonRPCError::ServerError { code: -32603, .. })));
+        assert_eq!(results[2].as_ref().unwrap(), &json!(3));
+
+        assert!(client.call_batch::<Value>(&[]).await.unwrap().is_empty());
+    }
+
+    // Server keeping track of the subscriptions
+    // Calling the `trigger` method sends an event to every subscription
+    async fn events_server() -> (String, Arc<Mutex<HashMap<String, Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("ws://{}", listener.local_addr().unwrap());
+        let subscriptions = Arc::new(Mutex::new(HashMap::new()));
+        let state = subscriptions.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                let event = request["params"]["notify"].as_str().unwrap_or_default().to_owned();
+                let mut state = state.lock().await;
+                let accepted = match request["method"].as_str().unwrap() {
+                    "subscribe" => state.insert(event, request["id"].clone()).is_none(),
+                    "unsubscribe" => state.remove(&event).is_some(),
+                    _ => {
+                        for id in state.values() {
+                            let event = json!({ "jsonrpc": JSON_RPC_VERSION, "id": id, "result": "event" });
+                            ws.send(Message::Text(event.to_string())).await.unwrap();
+                        }
+                        true
+                    }
+                };
+
+                let response = if accepted {
+                    json!({ "jsonrpc": JSON_RPC_VERSION, "id": request["id"], "result": true })
+                } else {
+                    json!({ "jsonrpc": JSON_RPC_VERSION, "id": request["id"], "error": { "code": -32603, "message": "invalid subscription" } })
+                };
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+            }
+        });
+
+        (address, subscriptions)
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_after_unsubscribe() {
+        let (address, subscriptions) = events_server().await;
+        let client = connect(address).await;
+        let event = "new_block".to_owned();
+
+        let mut receiver = client.subscribe_event::<String>(event.clone()).await.unwrap();
+        client.call::<bool>("trigger").await.unwrap();
+        assert_eq!(receiver.next().await.unwrap(), "event");
+
+        client.unsubscribe_event(&event).await.unwrap();
+        assert!(!client.has_event(&event).await);
+        assert!(subscriptions.lock().await.is_empty());
+        // Local receiver is closed
+        assert!(receiver.next().await.is_err());
+        assert!(matches!(client.unsubscribe_event(&event).await, Err(JsonRPCError::EventNotRegistered)));
+
+        let mut receiver = client.subscribe_event::<String>(event.clone()).await.unwrap();
+        client.call::<bool>("trigger").await.unwrap();
+        assert_eq!(receiver.next().await.unwrap(), "event");
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_last_receiver_dropped() {
+        let (address, subscriptions) = events_server().await;
+        let client = connect(address).await;
+        let event = "new_block".to_owned();
+
+        let receiver = client.subscribe_event::<String>(event.clone()).await.unwrap();
+        drop(receiver);
+        assert_eq!(subscriptions.lock().await.len(), 1);
+
+        // Next event received has no receiver left
+        client.call::<bool>("trigger").await.unwrap();
+        for _ in 0..50 {
+            if subscriptions.lock().await.is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(subscriptions.lock().await.is_empty());
+        assert!(!client.has_event(&event).await);
+
+        let mut receiver = client.subscribe_event::<String>(event.clone()).await.unwrap();
+        client.call::<bool>("trigger").await.unwrap();
+        assert_eq!(receiver.next().await.unwrap(), "event");
+    }
+
+    #[test]
+    fn test_request_kind() {
+        assert_eq!(RequestKind::from_method("get_info"), RequestKind::ReadOnly);
+        assert_eq!(RequestKind::from_method("count_assets"), RequestKind::ReadOnly);
+        assert_eq!(RequestKind::from_method("submit_block"), RequestKind::Mutating);
+    }
+}
@@ -1,3 +1,6 @@
+// Allow the derive macros to refer to this crate by its name
+extern crate self as xelis_common;
+
 pub mod crypto;
 pub mod serializer;
 pub mod transaction;
@@ -9,12 +12,16 @@ pub mod utils;
 pub mod config;
 pub mod immutable;
 pub mod difficulty;
+pub mod emission;
 pub mod network;
 pub mod asset;
 pub mod context;
 pub mod queue;
 pub mod varuint;
 pub mod time;
+pub mod rate_limiter;
+pub mod mnemonics;
+pub mod amount;
 
 pub mod thread_pool;
 
@@ -27,6 +34,9 @@ pub mod prompt;
 #[cfg(feature = "rpc_server")]
 pub mod rpc_server;
 
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 #[cfg(feature = "clap")]
 // If clap feature is enabled, build the correct style for CLI
 pub fn get_cli_styles() -> clap::builder::Styles {
@@ -0,0 +1,317 @@
+pub mod languages;
+
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use thiserror::Error;
+use crate::{
+    crypto::PrivateKey,
+    serializer::{ReaderError, Serializer}
+};
+use languages::*;
+
+const KEY_SIZE: usize = 32;
+pub const SEED_LENGTH: usize = 24;
+const WORDS_LIST: usize = 1626;
+const WORDS_LIST_U32: u32 = WORDS_LIST as u32;
+
+lazy_static! {
+    pub static ref LANGUAGES: Vec<Language<'static>> = vec![
+        english::ENGLISH,
+        french::FRENCH,
+        italian::ITALIAN,
+        spanish::SPANISH,
+        portuguese::PORTUGUESE,
+        japanese::JAPANESE,
+        chinese_simplified::CHINESE_SIMPLIFIED,
+        russian::RUSSIAN,
+        esperanto::ESPERANTO,
+        dutch::DUTCH,
+        german::GERMAN
+    ];
+}
+
+#[derive(Error, Debug)]
+pub enum MnemonicsError {
+    #[error("Invalid number of words, expected {} words", SEED_LENGTH + 1)]
+    InvalidWordsCount,
+    #[error("Invalid checksum for seed")]
+    InvalidChecksum,
+    #[error("No language found for this seed")]
+    NoLanguageFound,
+    #[error("Seed words are valid in several languages: {}, please select one", .0.join(", "))]
+    AmbiguousLanguage(Vec<&'static str>),
+    #[error("Invalid language index {}", _0)]
+    InvalidLanguageIndex(usize),
+    #[error("Unknown language '{}', available languages: {}", _0, get_languages_names().join(", "))]
+    UnknownLanguage(String),
+    #[error("Word '{}' is not in the {} words list", _0, _1)]
+    UnknownWord(String, &'static str),
+    #[error("Invalid word list length")]
+    InvalidWordListLength,
+    #[error("Invalid key length")]
+    InvalidKeyLength,
+    #[error("Word list sanity check error")]
+    SanityCheck,
+    #[error(transparent)]
+    InvalidKey(#[from] ReaderError)
+}
+
+pub struct Language<'a> {
+    name: &'a str,
+    prefix_length: usize, // number of utf-8 chars to use for checksum
+    words: [&'a str; WORDS_LIST]
+}
+
+impl<'a> Language<'a> {
+    pub fn get_name(&self) -> &'a str {
+        self.name
+    }
+}
+
+// Names of all the supported languages, ordered by index
+pub fn get_languages_names() -> Vec<&'static str> {
+    LANGUAGES.iter().map(|language| language.name).collect()
+}
+
+// Find a language by its name (case insensitive) or by its index
+pub fn get_language_index(value: &str) -> Result<usize, MnemonicsError> {
+    let value = value.trim();
+    if let Ok(index) = value.parse::<usize>() {
+        if index >= LANGUAGES.len() {
+            return Err(MnemonicsError::InvalidLanguageIndex(index))
+        }
+        return Ok(index)
+    }
+
+    LANGUAGES.iter()
+        .position(|language| language.name.eq_ignore_ascii_case(value) || language.name.replace(' ', "_").eq_ignore_ascii_case(value))
+        .ok_or_else(|| MnemonicsError::UnknownLanguage(value.to_owned()))
+}
+
+// Split a seed in words, extra whitespaces are ignored
+pub fn split_seed(seed: &str) -> Vec<String> {
+    seed.split_whitespace().map(str::to_owned).collect()
+}
+
+fn calculate_checksum_index(words: &[String], prefix_len: usize) -> Result<u32, MnemonicsError> {
+    if words.len() != SEED_LENGTH {
+        return Err(MnemonicsError::InvalidWordsCount);
+    }
+
+    let mut chars: Vec<char> = Vec::new();
+    for word in words {
+        let mut word_chars: Vec<char> = word.chars().collect();
+        if word_chars.len() > prefix_len {
+            word_chars.truncate(prefix_len);
+        }
+
+        chars.extend_from_slice(&word_chars);
+    }
+    let value: String = chars.into_iter().collect();
+    let checksum = crc32fast::hash(value.as_bytes());
+    Ok(checksum % SEED_LENGTH as u32)
+}
+
+fn verify_checksum(words: &[String], prefix_len: usize) -> Result<bool, MnemonicsError> {
+    let checksum_index = calculate_checksum_index(&words[0..SEED_LENGTH], prefix_len)?;
+    let checksum_word = words.get(checksum_index as usize).ok_or(MnemonicsError::InvalidChecksum)?;
+    let expected_checksum_word = words.get(SEED_LENGTH).ok_or(MnemonicsError::InvalidWordsCount)?;
+    Ok(checksum_word == expected_checksum_word)
+}
+
+// Indices of the words in the language, None if a word isn't part of it
+// Words are compared without case
+fn find_indices_in_language(words: &[String], language: &Language) -> Option<Vec<usize>> {
+    // this map is used to store the indices of the words in the language
+    let language_words: HashMap<String, usize> = language.words.iter()
+        .enumerate()
+        .map(|(i, word)| (word.to_lowercase(), i))
+        .collect();
+
+    words.iter()
+        .map(|word| language_words.get(&word.to_lowercase()).copied())
+        .collect()
+}
+
+// Verify the checksum using the words as written in the language list
+fn verify_indices_checksum(indices: &[usize], language: &Language) -> Result<bool, MnemonicsError> {
+    let words: Vec<String> = indices.iter().map(|i| language.words[*i].to_owned()).collect();
+    verify_checksum(&words, language.prefix_length)
+}
+
+// Detect the language of the words and returns their indices with the language index
+// A seed having a valid checksum in several languages is rejected
+fn find_indices(words: &[String]) -> Result<(Vec<usize>, usize), MnemonicsError> {
+    let mut invalid_checksum = false;
+    let mut candidates = Vec::new();
+    for (i, language) in LANGUAGES.iter().enumerate() {
+        let Some(indices) = find_indices_in_language(words, language) else {
+            // incorrect language for this word, try the next one
+            continue;
+        };
+
+        // we were able to build the indices, now verify checksum
+        if verify_indices_checksum(&indices, language)? {
+            candidates.push((indices, i));
+        } else {
+            invalid_checksum = true;
+        }
+    }
+
+    if candidates.len() > 1 {
+        let names = candidates.iter().map(|(_, i)| LANGUAGES[*i].name).collect();
+        return Err(MnemonicsError::AmbiguousLanguage(names))
+    }
+
+    match candidates.pop() {
+        Some(candidate) => Ok(candidate),
+        None if invalid_checksum => Err(MnemonicsError::InvalidChecksum),
+        None => Err(MnemonicsError::NoLanguageFound)
+    }
+}
+
+fn indices_to_key(indices: &[usize]) -> Result<PrivateKey, MnemonicsError> {
+    let mut dest = Vec::with_capacity(KEY_SIZE);
+    for i in (0..SEED_LENGTH).step_by(3) {
+        let a = indices.get(i).ok_or(MnemonicsError::InvalidWordsCount)?;
+        let b = indices.get(i + 1).ok_or(MnemonicsError::InvalidWordsCount)?;
+        let c = indices.get(i + 2).ok_or(MnemonicsError::InvalidWordsCount)?;
+
+        let val = a + WORDS_LIST * (((WORDS_LIST - a) + b) % WORDS_LIST) + WORDS_LIST * WORDS_LIST * (((WORDS_LIST - b) + c) % WORDS_LIST);
+        if val % WORDS_LIST != *a {
+            return Err(MnemonicsError::SanityCheck)
+        }
+
+        let val = val as u32;
+        dest.extend_from_slice(&val.to_le_bytes());
+    }
+
+    Ok(PrivateKey::from_bytes(&dest)?)
+}
+
+// convert a words list to a Private Key (32 bytes)
+pub fn words_to_key(words: &[String]) -> Result<PrivateKey, MnemonicsError> {
+    words_to_key_and_language(words).map(|(key, _)| key)
+}
+
+// convert a words list to a Private Key and returns the index of the language detected
+pub fn words_to_key_and_language(words: &[String]) -> Result<(PrivateKey, usize), MnemonicsError> {
+    if words.len() != SEED_LENGTH + 1 {
+        return Err(MnemonicsError::InvalidWordsCount);
+    }
+
+    let (indices, language_index) = find_indices(words)?;
+    debug!("Language found: {}", LANGUAGES[language_index].name);
+
+    Ok((indices_to_key(&indices)?, language_index))
+}
+
+// convert a words list to a Private Key using the language selected
+pub fn words_to_key_with_language(words: &[String], language_index: usize) -> Result<PrivateKey, MnemonicsError> {
+    if words.len() != SEED_LENGTH + 1 {
+        return Err(MnemonicsError::InvalidWordsCount);
+    }
+
+    let language = LANGUAGES.get(language_index).ok_or(MnemonicsError::InvalidLanguageIndex(language_index))?;
+    let indices = match find_indices_in_language(words, language) {
+        Some(indices) => indices,
+        None => {
+            let word = words.iter().find(|word| !language.words.iter().any(|w| w.to_lowercase() == word.to_lowercase())).cloned().unwrap_or_default();
+            return Err(MnemonicsError::UnknownWord(word, language.name))
+        }
+    };
+
+    if !verify_indices_checksum(&indices, language)? {
+        return Err(MnemonicsError::InvalidChecksum)
+    }
+
+    indices_to_key(&indices)
+}
+
+pub fn key_to_words(key: &PrivateKey, language_index: usize) -> Result<Vec<String>, MnemonicsError> {
+    let language = LANGUAGES.get(language_index).ok_or(MnemonicsError::InvalidLanguageIndex(language_index))?;
+    key_to_words_with_language(key, language)
+}
+
+pub fn key_to_words_with_language(key: &PrivateKey, language: &Language) -> Result<Vec<String>, MnemonicsError> {
+    if language.words.len() != WORDS_LIST {
+        return Err(MnemonicsError::InvalidWordListLength);
+    }
+
+    let bytes = key.to_bytes();
+    if bytes.len() != KEY_SIZE {
+        return Err(MnemonicsError::InvalidKeyLength);
+    }
+
+    let mut words = Vec::with_capacity(SEED_LENGTH + 1);
+    for i in (0..KEY_SIZE).step_by(4) {
+        let val = u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let a = val % WORDS_LIST_U32;
+        let b = ((val / WORDS_LIST_U32) + a) % WORDS_LIST_U32;
+        let c = ((val / WORDS_LIST_U32 / WORDS_LIST_U32) + b) % WORDS_LIST_U32;
+
+        words.push(language.words[a as usize].to_owned());
+        words.push(language.words[b as usize].to_owned());
+        words.push(language.words[c as usize].to_owned());
+    }
+
+    let checksum = calculate_checksum_index(&words, language.prefix_length)?;
+    words.push(words[checksum as usize].clone());
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::KeyPair;
+    use super::*;
+
+    #[test]
+    fn test_languages() {
+        let (_, key) = KeyPair::new().split();
+        for (i, language) in LANGUAGES.iter().enumerate() {
+            let words = key_to_words_with_language(&key, language).unwrap();
+            let (nkey, index) = words_to_key_and_language(&words).unwrap();
+            assert_eq!(key.as_scalar(), nkey.as_scalar());
+            assert_eq!(index, i);
+
+            let words2 = key_to_words_with_language(&nkey, language).unwrap();
+            assert_eq!(words, words2);
+
+            let nkey = words_to_key_with_language(&words, i).unwrap();
+            assert_eq!(key.as_scalar(), nkey.as_scalar());
+        }
+    }
+
+    #[test]
+    fn test_language_index() {
+        assert_eq!(get_language_index("english").unwrap(), 0);
+        assert_eq!(get_language_index("French").unwrap(), 1);
+        assert_eq!(get_language_index("5").unwrap(), 5);
+        assert_eq!(get_language_index("chinese_simplified").unwrap(), 6);
+        assert!(matches!(get_language_index("klingon"), Err(MnemonicsError::UnknownLanguage(_))));
+        assert!(matches!(get_language_index("42"), Err(MnemonicsError::InvalidLanguageIndex(42))));
+    }
+
+    #[test]
+    fn test_invalid_seeds() {
+        let (_, key) = KeyPair::new().split();
+        let words = key_to_words(&key, 0).unwrap();
+        assert!(matches!(words_to_key(&words[..SEED_LENGTH]), Err(MnemonicsError::InvalidWordsCount)));
+
+        // Checksum word replaced by another word of the list
+        let mut invalid = words.clone();
+        invalid[SEED_LENGTH] = if invalid[SEED_LENGTH] == "abbey" { "abducts" } else { "abbey" }.to_owned();
+        assert!(matches!(words_to_key(&invalid), Err(MnemonicsError::InvalidChecksum)));
+
+        let mut invalid = words.clone();
+        invalid[0] = "notaword".to_owned();
+        assert!(matches!(words_to_key(&invalid), Err(MnemonicsError::NoLanguageFound)));
+        assert!(matches!(words_to_key_with_language(&invalid, 0), Err(MnemonicsError::UnknownWord(_, "English"))));
+
+        // Case and spaces are ignored
+        let seed = format!("  {}  ", words.join("  ").to_uppercase());
+        assert_eq!(words_to_key(&split_seed(&seed)).unwrap().as_scalar(), key.as_scalar());
+    }
+}
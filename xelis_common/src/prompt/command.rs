@@ -1,4 +1,4 @@
-use std::{collections::HashMap, pin::Pin, future::Future, fmt::Display, time::{Instant, Duration}, sync::{Mutex, PoisonError}, rc::Rc, str::FromStr};
+use std::{collections::HashMap, pin::Pin, future::Future, fmt::Display, time::{Instant, Duration}, sync::{atomic::{AtomicBool, Ordering}, Mutex, PoisonError}, rc::Rc, str::FromStr};
 
 use crate::{config::VERSION, async_handler, context::Context};
 
@@ -7,6 +7,9 @@ use anyhow::Error;
 use thiserror::Error;
 use log::{info, warn, error};
 
+// Skip the confirmation of a command
+pub const CONFIRMATION_FLAG: &str = "--yes";
+
 #[derive(Error, Debug)]
 pub enum CommandError {
     #[error("Expected a command name")]
@@ -52,7 +55,11 @@ pub struct Command {
     description: String,
     required_args: Vec<Arg>,
     optional_args: Vec<Arg>,
-    callback: CommandHandler
+    callback: CommandHandler,
+    // sensitive commands are not saved in the history file
+    sensitive: bool,
+    // message to confirm before executing the command
+    confirmation: Option<String>
 }
 
 impl Command {
@@ -62,7 +69,9 @@ impl Command {
             description: description.to_owned(),
             required_args: Vec::new(),
             optional_args: Vec::new(),
-            callback
+            callback,
+            sensitive: false,
+            confirmation: None
         }
     }
 
@@ -72,7 +81,9 @@ impl Command {
             description: description.to_owned(),
             required_args: Vec::new(),
             optional_args,
-            callback
+            callback,
+            sensitive: false,
+            confirmation: None
         }
     }
 
@@ -82,7 +93,9 @@ impl Command {
             description: description.to_owned(),
             required_args,
             optional_args: Vec::new(),
-            callback
+            callback,
+            sensitive: false,
+            confirmation: None
         }
     }
 
@@ -92,7 +105,9 @@ impl Command {
             description: description.to_owned(),
             required_args,
             optional_args,
-            callback
+            callback,
+            sensitive: false,
+            confirmation: None
         }
     }
 
@@ -107,6 +122,27 @@ impl Command {
         }
     }
 
+    // Mark the command as containing secrets
+    pub fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
+
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    // Ask the user to confirm the message before executing the command
+    // It is skipped using --yes on the command line
+    pub fn with_confirmation(mut self, message: &str) -> Self {
+        self.confirmation = Some(message.to_owned());
+        self
+    }
+
+    pub fn get_confirmation(&self) -> Option<&String> {
+        self.confirmation.as_ref()
+    }
+
     pub fn get_name(&self) -> &String {
         &self.name
     }
@@ -129,11 +165,15 @@ impl Command {
             .map(|arg| format!("<{}>", arg.get_name()))
             .collect();
 
-        let optional_args: Vec<String> = self.get_optional_args()
+        let mut optional_args: Vec<String> = self.get_optional_args()
             .iter()
             .map(|arg| format!("[{}]", arg.get_name()))
             .collect();
 
+        if self.confirmation.is_some() {
+            optional_args.push(format!("[{}]", CONFIRMATION_FLAG));
+        }
+
         format!("{} {}{}", self.get_name(), required_args.join(" "), optional_args.join(" "))
     }
 }
@@ -143,7 +183,9 @@ pub struct CommandManager {
     commands: Mutex<Vec<Rc<Command>>>,
     context: Mutex<Context>,
     prompt: ShareablePrompt,
-    running_since: Instant
+    running_since: Instant,
+    // confirmations are accepted automatically
+    skip_confirmation: AtomicBool
 }
 
 impl CommandManager {
@@ -152,7 +194,8 @@ impl CommandManager {
             commands: Mutex::new(Vec::new()),
             context: Mutex::new(context),
             prompt,
-            running_since: Instant::now()
+            running_since: Instant::now(),
+            skip_confirmation: AtomicBool::new(false)
         }
     }
 
@@ -173,6 +216,11 @@ impl CommandManager {
         Ok(())
     }
 
+    // Used by scripts to execute commands without confirmation
+    pub fn set_skip_confirmation(&self, value: bool) {
+        self.skip_confirmation.store(value, Ordering::SeqCst);
+    }
+
     pub fn store_in_context<T: Send + Sync + 'static>(&self, data: T) -> Result<(), CommandError> {
         let mut context = self.context.lock()?;
         context.store(data);
@@ -207,8 +255,21 @@ impl CommandManager {
         &self.commands
     }
 
+    // Check if the command line is a call to a sensitive command
+    pub fn is_sensitive_command(&self, line: &str) -> bool {
+        let Some(command_name) = line.split_whitespace().next() else {
+            return false
+        };
+
+        match self.commands.lock() {
+            Ok(commands) => commands.iter().any(|command| *command.get_name() == command_name && command.is_sensitive()),
+            // Don't take the risk of saving it
+            Err(_) => true
+        }
+    }
+
     pub async fn handle_command(&self, value: String) -> Result<(), CommandError> {
-        let mut command_split = value.split_whitespace();
+        let mut command_split = value.split_whitespace().peekable();
         let command_name = command_split.next().ok_or(CommandError::ExpectedCommandName)?;
         let command = {
             let commands = self.commands.lock()?;
@@ -221,16 +282,44 @@ impl CommandManager {
         }
 
         // include all options args available
-        for optional_arg in command.get_optional_args() {
-            if let Some(arg_value) = command_split.next() {
-                arguments.insert(optional_arg.get_name().clone(), optional_arg.get_type().to_value(arg_value)?);
-            } else {
-                break;
+        // they are given in order, or by name using --name value
+        // a bool option given by name without a value is set to true
+        let mut positional_args = command.get_optional_args().iter();
+        let mut confirmed = self.skip_confirmation.load(Ordering::SeqCst);
+        while let Some(token) = command_split.next() {
+            if token == CONFIRMATION_FLAG && command.get_confirmation().is_some() {
+                confirmed = true;
+                continue;
             }
+
+            let optional_arg = match token.strip_prefix("--") {
+                Some(name) => command.get_optional_args().iter()
+                    .find(|arg| *arg.get_name() == name)
+                    .ok_or_else(|| CommandError::InvalidArgument(format!("unknown option --{}", name)))?,
+                None => positional_args.find(|arg| !arguments.contains_key(arg.get_name()))
+                    .ok_or(CommandError::TooManyArguments)?
+            };
+
+            let arg_value = if token.starts_with("--") {
+                if let ArgType::Bool = optional_arg.get_type() {
+                    match command_split.next_if(|value| value.parse::<bool>().is_ok()) {
+                        Some(value) => value,
+                        None => "true"
+                    }
+                } else {
+                    command_split.next().ok_or_else(|| CommandError::ExpectedRequiredArg(optional_arg.get_name().to_owned()))?
+                }
+            } else {
+                token
+            };
+            arguments.insert(optional_arg.get_name().clone(), optional_arg.get_type().to_value(arg_value)?);
         }
 
-        if command_split.next().is_some() {
-            return Err(CommandError::TooManyArguments);
+        if let Some(message) = command.get_confirmation() {
+            if !confirmed && !self.prompt.ask_confirmation_with(message).await? {
+                self.message("Command has been aborted");
+                return Ok(())
+            }
         }
 
         command.execute(self, ArgumentManager::new(arguments)).await
@@ -292,4 +381,40 @@ fn set_log_level(manager: &CommandManager, mut args: ArgumentManager) -> Result<
     manager.message(format!("Log level set to {}", level));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use crate::prompt::Prompt;
+    use super::*;
+
+    static EXECUTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn destructive(_: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+        EXECUTED.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_confirmation() {
+        let prompt = Prompt::scripted(vec!["n", "", "invalid", "y"]);
+        let manager = CommandManager::new(prompt);
+        manager.add_command(Command::new("destroy", "Destroy everything", CommandHandler::Sync(destructive)).with_confirmation("Everything will be destroyed.")).unwrap();
+
+        // "n" and the default answer refuse it
+        manager.handle_command("destroy".to_owned()).await.unwrap();
+        manager.handle_command("destroy".to_owned()).await.unwrap();
+        assert_eq!(EXECUTED.load(Ordering::SeqCst), 0);
+
+        // Invalid answers are asked again
+        manager.handle_command("destroy".to_owned()).await.unwrap();
+        assert_eq!(EXECUTED.load(Ordering::SeqCst), 1);
+
+        // No input is left, it would block if it was asked
+        manager.handle_command("destroy --yes".to_owned()).await.unwrap();
+        manager.set_skip_confirmation(true);
+        manager.handle_command("destroy".to_owned()).await.unwrap();
+        assert_eq!(EXECUTED.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, OpenOptions},
+    io::{Error as IOError, Write},
+    path::PathBuf
+};
+
+// Maximum entries kept in the history file
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
+// Commands entered in the prompt, the most recent first
+// Entries loaded from the file come before the ones of the current session
+pub struct History {
+    entries: VecDeque<String>,
+    path: Option<PathBuf>
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            path: None
+        }
+    }
+
+    // Load the history file, it is rewritten if it has too many entries
+    pub fn load(path: PathBuf) -> Result<Self, IOError> {
+        let mut entries = VecDeque::new();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+            let skip = lines.len().saturating_sub(MAX_HISTORY_ENTRIES);
+            for line in lines.iter().skip(skip) {
+                entries.push_front(line.to_string());
+            }
+
+            if skip > 0 {
+                let mut content = String::new();
+                for line in entries.iter().rev() {
+                    content.push_str(line);
+                    content.push('\n');
+                }
+                fs::write(&path, content)?;
+            }
+        }
+
+        Ok(Self {
+            entries,
+            path: Some(path)
+        })
+    }
+
+    // Add an entry for the current session only
+    pub fn push(&mut self, line: String) {
+        self.entries.push_front(line);
+    }
+
+    // Append an entry to the history file
+    pub fn persist(&self, line: &str) -> Result<(), IOError> {
+        if let Some(path) = self.path.as_ref() {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, index: usize) -> Option<&String> {
+        self.entries.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Find the most recent entry containing the query, starting at index
+    pub fn search(&self, query: &str, from: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None
+        }
+
+        self.entries.iter()
+            .enumerate()
+            .skip(from)
+            .find(|(_, entry)| entry.contains(query))
+            .map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_file() {
+        let path = std::env::temp_dir().join(format!("xelis-history-{}", rand::random::<u64>()));
+        let content: String = (0..MAX_HISTORY_ENTRIES + 10).map(|i| format!("command {}\n", i)).collect();
+        fs::write(&path, content).unwrap();
+
+        // Only the most recent entries are kept
+        let history = History::load(path.clone()).unwrap();
+        assert_eq!(history.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(history.get(0).unwrap(), &format!("command {}", MAX_HISTORY_ENTRIES + 9));
+        assert_eq!(history.get(MAX_HISTORY_ENTRIES - 1).unwrap(), "command 10");
+
+        history.persist("balance").unwrap();
+        let history = History::load(path.clone()).unwrap();
+        assert_eq!(history.get(0).unwrap(), "balance");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_search() {
+        let mut history = History::new();
+        history.push("transfer".to_owned());
+        history.push("balance".to_owned());
+        history.push("transfer_all".to_owned());
+
+        assert_eq!(history.search("trans", 0), Some(0));
+        // Searching again continues to older entries
+        assert_eq!(history.search("trans", 1), Some(2));
+        assert_eq!(history.search("trans", 3), None);
+        assert_eq!(history.search("", 0), None);
+    }
+}
@@ -1,17 +1,17 @@
 pub mod command;
 pub mod argument;
+pub mod history;
 
 use crate::{
     crypto::Hash,
     serializer::{Serializer, ReaderError},
 };
 use std::{
-    collections::VecDeque,
     fmt::{self, Display, Formatter},
     fs::create_dir_all,
     future::Future,
     io::{stdout, Error as IOError, Write},
-    path::Path,
+    path::{Path, PathBuf},
     pin::Pin,
     str::FromStr,
     sync::{
@@ -35,7 +35,10 @@ use tokio::{
     },
     time::{interval, timeout}
 };
-use self::command::{CommandError, CommandManager};
+use self::{
+    command::{CommandError, CommandManager},
+    history::History
+};
 use anyhow::Error;
 use fern::colors::{ColoredLevelConfig, Color};
 use regex::Regex;
@@ -147,7 +150,35 @@ struct State {
     prompt_sender: Mutex<Option<oneshot::Sender<String>>>,
     has_exited: AtomicBool,
     ascii_escape_regex: Regex,
-    interactive: bool
+    interactive: bool,
+    history: Mutex<History>
+}
+
+// Reverse incremental search in the history
+struct ReverseSearch {
+    query: String,
+    // index in history of the current match
+    index: Option<usize>,
+    // no entry matches the query
+    failed: bool
+}
+
+impl ReverseSearch {
+    fn new() -> Self {
+        Self {
+            query: String::new(),
+            index: None,
+            failed: false
+        }
+    }
+
+    // Keep the previous match if nothing was found
+    fn update(&mut self, index: Option<usize>) {
+        self.failed = index.is_none() && !self.query.is_empty();
+        if index.is_some() {
+            self.index = index;
+        }
+    }
 }
 
 impl State {
@@ -169,7 +200,8 @@ impl State {
             prompt_sender: Mutex::new(None),
             has_exited: AtomicBool::new(false),
             ascii_escape_regex: Regex::new("\x1B\\[[0-9;]*[A-Za-z]").unwrap(),
-            interactive
+            interactive,
+            history: Mutex::new(History::new())
         }
     }
 
@@ -200,11 +232,11 @@ impl State {
     fn ioloop(self: &Arc<Self>, sender: UnboundedSender<String>) -> Result<(), PromptError> {
         debug!("ioloop started");
 
-        // all the history of commands
-        let mut history: VecDeque<String> = VecDeque::new();
         // current index in history in case we use arrows to move in history
         let mut history_index = 0;
         let mut is_in_history = false;
+        // reverse search started using CTRL+R
+        let mut search: Option<ReverseSearch> = None;
         loop {
             if !is_in_history {
                 history_index = 0;
@@ -215,7 +247,10 @@ impl State {
                     match event {
                         Event::Resize(width, _) => {
                             self.width.store(width, Ordering::SeqCst);
-                            self.show()?;
+                            match search.as_ref() {
+                                Some(current) => self.show_search(current)?,
+                                None => self.show()?
+                            };
                         }
                         Event::Paste(s) => {
                             is_in_history = false;
@@ -228,6 +263,58 @@ impl State {
                                 continue;
                             }
 
+                            // Keys are handled by the reverse search while it is active
+                            if let Some(current) = search.as_mut() {
+                                match key.code {
+                                    KeyCode::Char(c) if key.modifiers == KeyModifiers::CONTROL => {
+                                        match c {
+                                            // Search the next older match
+                                            'r' => {
+                                                let from = current.index.map(|i| i + 1).unwrap_or(0);
+                                                current.update(self.history.lock()?.search(&current.query, from));
+                                            },
+                                            // Cancel the search
+                                            'c' | 'g' => {
+                                                search = None;
+                                                self.show()?;
+                                                continue;
+                                            },
+                                            _ => {}
+                                        }
+                                    },
+                                    KeyCode::Char(c) => {
+                                        current.query.push(c);
+                                        let from = current.index.unwrap_or(0);
+                                        current.update(self.history.lock()?.search(&current.query, from));
+                                    },
+                                    KeyCode::Backspace => {
+                                        current.query.pop();
+                                        current.update(self.history.lock()?.search(&current.query, 0));
+                                    },
+                                    code => {
+                                        // Any other key accepts the match
+                                        let matched = current.index.and_then(|i| self.history.lock().ok()?.get(i).cloned());
+                                        search = None;
+                                        let mut buffer = self.user_input.lock()?;
+                                        if let Some(matched) = matched {
+                                            buffer.clear();
+                                            buffer.push_str(&matched);
+                                        }
+
+                                        // Enter executes it directly
+                                        if code != KeyCode::Enter {
+                                            self.show_input(&buffer)?;
+                                            continue;
+                                        }
+                                    }
+                                };
+
+                                if let Some(current) = search.as_ref() {
+                                    self.show_search(current)?;
+                                    continue;
+                                }
+                            }
+
                             match key.code {
                                 KeyCode::Up => {
                                     let mut buffer = self.user_input.lock()?;
@@ -236,9 +323,10 @@ impl State {
                                     }
 
                                     if is_in_history {
-                                        if history_index < history.len() {
+                                        let history = self.history.lock()?;
+                                        if let Some(entry) = history.get(history_index) {
                                             buffer.clear();
-                                            buffer.push_str(&history[history_index]);
+                                            buffer.push_str(entry);
                                             self.show_input(&buffer)?;
                                             if history_index + 1 < history.len() {
                                                 history_index += 1;
@@ -252,8 +340,8 @@ impl State {
                                         buffer.clear();
                                         if history_index > 0 {
                                             history_index -= 1;
-                                            if history_index < history.len() {
-                                                buffer.push_str(&history[history_index]);
+                                            if let Some(entry) = self.history.lock()?.get(history_index) {
+                                                buffer.push_str(entry);
                                             }
                                         } else {
                                             is_in_history = false;
@@ -268,6 +356,16 @@ impl State {
                                         break;
                                     }
 
+                                    // start a reverse search, not available for masked inputs
+                                    if key.modifiers == KeyModifiers::CONTROL && c == 'r' {
+                                        if !self.should_mask_input() {
+                                            let current = ReverseSearch::new();
+                                            self.show_search(&current)?;
+                                            search = Some(current);
+                                        }
+                                        continue;
+                                    }
+
                                     let mut buffer = self.user_input.lock()?;
                                     buffer.push(c);
                                     self.show_input(&buffer)?;
@@ -297,7 +395,7 @@ impl State {
                                         }
                                     } else {
                                         if !cloned_buffer.is_empty() {
-                                            history.push_front(cloned_buffer.clone());
+                                            self.history.lock()?.push(cloned_buffer.clone());
                                             if let Err(e) = sender.send(cloned_buffer) {
                                                 error!("Error while sending input to command handler: {}", e);
                                                 break;
@@ -396,6 +494,24 @@ impl State {
         let input = self.user_input.lock()?;
         self.show_input(&input)
     }
+
+    // Show the current match of the reverse search with the query highlighted
+    fn show_search(&self, search: &ReverseSearch) -> Result<(), PromptError> {
+        let entry = match search.index {
+            Some(index) => self.history.lock()?.get(index).cloned().unwrap_or_default(),
+            None => String::new()
+        };
+
+        let prompt = format!("({}reverse-i-search)`{}': ", if search.failed { "failed " } else { "" }, search.query);
+        let input = match entry.find(&search.query) {
+            Some(start) if !search.query.is_empty() => {
+                let end = start + search.query.len();
+                format!("{}\x1B[7m{}\x1B[0m{}", &entry[..start], &entry[start..end], &entry[end..])
+            },
+            _ => entry
+        };
+        self.show_with_prompt_and_input(&prompt, &input)
+    }
 }
 
 struct OptionReader {
@@ -443,7 +559,7 @@ type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
 type AsyncF<'a, T1, T2, R> = Box<dyn Fn(&'a T1, T2) -> LocalBoxFuture<'a, R> + 'a>;
 
 impl Prompt {
-    pub fn new(level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool, disable_file_log_date_based: bool, disable_colors: bool, interactive: bool) -> Result<ShareablePrompt, PromptError> {
+    pub fn new(level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool, disable_file_log_date_based: bool, disable_colors: bool, interactive: bool, history_file: Option<String>) -> Result<ShareablePrompt, PromptError> {
         let (read_input_sender, read_input_receiver) = mpsc::channel(1);
         let prompt = Self {
             state: Arc::new(State::new(interactive)),
@@ -468,6 +584,22 @@ impl Prompt {
         }
 
         if prompt.state.is_interactive() {
+            // By default, the history is saved next to the log file
+            let path = match history_file {
+                Some(path) => PathBuf::from(path),
+                None => Path::new(dir_path).join(format!("{}.history", filename_log.trim_end_matches(".log")))
+            };
+
+            match History::load(path.clone()) {
+                Ok(history) => {
+                    debug!("Loaded {} entries from history file {}", history.len(), path.display());
+                    *prompt.state.history.lock()? = history;
+                },
+                Err(e) => {
+                    warn!("Couldn't load history file {}, history won't be saved: {}", path.display(), e);
+                }
+            };
+
             let (input_sender, input_receiver) = mpsc::unbounded_channel::<String>();
             let state = Arc::clone(&prompt.state);
             // spawn a thread to prevent IO blocking - https://github.com/tokio-rs/tokio/issues/2466
@@ -526,6 +658,13 @@ impl Prompt {
                     break;
                 },
                 Some(input) = &mut input_receiver => {
+                    // commands containing secrets are never written in the history file
+                    if !command_manager.is_some_and(|manager| manager.is_sensitive_command(&input)) {
+                        if let Err(e) = self.state.history.lock()?.persist(&input) {
+                            warn!("Couldn't save command in history file: {}", e);
+                        }
+                    }
+
                     if let Some(command_manager) = command_manager.as_ref() {
                         match command_manager.handle_command(input).await {
                             Err(CommandError::Exit) => break,
@@ -628,6 +767,13 @@ impl Prompt {
         Ok(res == "y")
     }
 
+    // Ask to confirm the message, anything else than "y" is a refusal
+    pub async fn ask_confirmation_with(&self, message: &str) -> Result<bool, PromptError> {
+        let prompt = self.colorize_string(Color::Yellow, &format!("{} Are you sure? [y/N]: ", message));
+        let res = self.read_valid_str_value(prompt, vec!["y", "n", ""]).await?;
+        Ok(res == "y")
+    }
+
     pub async fn read<F: FromStr>(&self, prompt: String) -> Result<F, PromptError>
     where
         <F as FromStr>::Err: Display
@@ -705,6 +851,41 @@ impl Prompt {
         input
     }
 
+    // Send the input to the pending reader as if it was typed by the user
+    // Returns false if no reader is waiting
+    pub fn submit_input(&self, input: String) -> Result<bool, PromptError> {
+        let sender = self.state.prompt_sender.lock()?.take();
+        Ok(match sender {
+            Some(sender) => sender.send(input).is_ok(),
+            None => false
+        })
+    }
+
+    // Prompt without terminal nor logger, the inputs are sent in order to each reader
+    #[cfg(test)]
+    pub(crate) fn scripted(inputs: Vec<&str>) -> ShareablePrompt {
+        let (read_input_sender, read_input_receiver) = mpsc::channel(1);
+        let prompt = Arc::new(Self {
+            state: Arc::new(State::new(false)),
+            input_receiver: Mutex::new(None),
+            read_input_receiver: AsyncMutex::new(read_input_receiver),
+            read_input_sender,
+            disable_colors: true
+        });
+
+        let inputs: Vec<String> = inputs.into_iter().map(String::from).collect();
+        let script = Arc::clone(&prompt);
+        tokio::spawn(async move {
+            for input in inputs {
+                while !script.submit_input(input.clone()).unwrap() {
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+
+        prompt
+    }
+
     // should we replace user input by * ?
     pub fn should_mask_input(&self) -> bool {
         self.state.should_mask_input()
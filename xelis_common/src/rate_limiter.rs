@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex
+};
+use crate::time::{get_current_time_in_millis, TimestampMillis};
+
+// Millis in one minute, rates are configured per minute
+const MILLIS_PER_MINUTE: u64 = 60 * 1000;
+
+// Above this count of buckets, full buckets are removed
+// as they behave like a new one
+const CLEANUP_THRESHOLD: usize = 4096;
+
+// A token bucket refilled continuously at `rate` tokens per minute
+// up to `burst` tokens
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    // tokens available, scaled by MILLIS_PER_MINUTE to stay in integers
+    tokens: u64,
+    // last time the bucket was refilled
+    last_refill: TimestampMillis
+}
+
+impl TokenBucket {
+    // Create a full bucket
+    pub fn new(burst: u64, now: TimestampMillis) -> Self {
+        Self {
+            tokens: burst * MILLIS_PER_MINUTE,
+            last_refill: now
+        }
+    }
+
+    // Refill the bucket based on the elapsed time
+    fn refill(&mut self, rate: u64, burst: u64, now: TimestampMillis) {
+        let elapsed = now.saturating_sub(self.last_refill);
+        self.tokens = self.tokens.saturating_add(elapsed.saturating_mul(rate)).min(burst * MILLIS_PER_MINUTE);
+        self.last_refill = now;
+    }
+
+    // Is the bucket full
+    fn is_full(&self, burst: u64) -> bool {
+        self.tokens >= burst * MILLIS_PER_MINUTE
+    }
+
+    // Try to consume one token
+    // If no token is available, returns the time in ms to wait for the next one
+    pub fn try_consume(&mut self, rate: u64, burst: u64, now: TimestampMillis) -> Result<(), u64> {
+        self.refill(rate, burst, now);
+        if self.tokens >= MILLIS_PER_MINUTE {
+            self.tokens -= MILLIS_PER_MINUTE;
+            Ok(())
+        } else if rate == 0 {
+            Err(u64::MAX)
+        } else {
+            let missing = MILLIS_PER_MINUTE - self.tokens;
+            Err(missing.div_ceil(rate))
+        }
+    }
+}
+
+// Rate limiter keeping one token bucket per key (IP address for example)
+pub struct RateLimiter<K: Hash + Eq> {
+    // tokens refilled per minute
+    rate: u64,
+    // maximum tokens available at once
+    burst: u64,
+    buckets: Mutex<HashMap<K, TokenBucket>>
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    pub fn new(rate: u64, burst: u64) -> Self {
+        Self {
+            rate,
+            burst: burst.max(1),
+            buckets: Mutex::new(HashMap::new())
+        }
+    }
+
+    // Tokens refilled per minute
+    pub fn get_rate(&self) -> u64 {
+        self.rate
+    }
+
+    // Maximum tokens available at once
+    pub fn get_burst(&self) -> u64 {
+        self.burst
+    }
+
+    // Try to consume a token for this key
+    // Returns the time in ms to wait before a new attempt if limited
+    pub fn check(&self, key: K) -> Result<(), u64> {
+        self.check_at(key, get_current_time_in_millis())
+    }
+
+    // Same as check but using the provided current time
+    pub fn check_at(&self, key: K, now: TimestampMillis) -> Result<(), u64> {
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(e) => e.into_inner()
+        };
+
+        if buckets.len() >= CLEANUP_THRESHOLD {
+            let (rate, burst) = (self.rate, self.burst);
+            buckets.retain(|_, bucket| {
+                bucket.refill(rate, burst, now);
+                !bucket.is_full(burst)
+            });
+        }
+
+        buckets.entry(key)
+            .or_insert_with(|| TokenBucket::new(self.burst, now))
+            .try_consume(self.rate, self.burst, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_limited() {
+        let mut bucket = TokenBucket::new(3, 0);
+        for _ in 0..3 {
+            assert!(bucket.try_consume(60, 3, 0).is_ok());
+        }
+        // 60 per minute = 1 token per second
+        assert_eq!(bucket.try_consume(60, 3, 0), Err(1000));
+    }
+
+    #[test]
+    fn test_refill() {
+        let mut bucket = TokenBucket::new(1, 0);
+        assert!(bucket.try_consume(60, 1, 0).is_ok());
+        assert_eq!(bucket.try_consume(60, 1, 400), Err(600));
+        assert!(bucket.try_consume(60, 1, 1000).is_ok());
+
+        // Refill never goes above the burst
+        assert!(bucket.try_consume(60, 1, 60_000).is_ok());
+        assert!(bucket.try_consume(60, 1, 60_000).is_err());
+    }
+
+    #[test]
+    fn test_limiter_per_key() {
+        let limiter = RateLimiter::new(1, 1);
+        assert!(limiter.check_at("a", 0).is_ok());
+        assert!(limiter.check_at("a", 0).is_err());
+        // Another key has its own bucket
+        assert!(limiter.check_at("b", 0).is_ok());
+        // One token per minute
+        assert!(limiter.check_at("a", 60_000).is_ok());
+    }
+
+    #[test]
+    fn test_zero_rate() {
+        let limiter = RateLimiter::new(0, 1);
+        assert!(limiter.check_at(1, 0).is_ok());
+        assert_eq!(limiter.check_at(1, 1_000_000), Err(u64::MAX));
+    }
+}
@@ -0,0 +1,15 @@
+use actix_web::http::header::HeaderValue;
+use async_trait::async_trait;
+use crate::context::Context;
+use super::InternalRpcError;
+
+// Authorization header sent by the client, stored in the request context
+pub struct ClientAuthorization(pub HeaderValue);
+
+// Checked before executing every RPC method, HTTP or WebSocket
+// It is called for each message so a revoked credential
+// is refused even on an already opened WebSocket session
+#[async_trait]
+pub trait RpcAuthorizer: Send + Sync {
+    async fn authorize(&self, context: &Context, method: &str) -> Result<(), InternalRpcError>;
+}
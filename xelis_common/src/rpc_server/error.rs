@@ -52,6 +52,13 @@ pub enum InternalRpcError {
     Custom(i16, String),
     #[error("{}", _1)]
     CustomStr(i16, &'static str),
+    #[error("Too many requests, retry after {}ms", _0)]
+    RateLimited(u64),
+    #[error("Too many concurrent requests")]
+    ConcurrentLimitReached,
+    // Same error for a missing, invalid or insufficient credential
+    #[error("Unauthorized")]
+    Unauthorized,
 }
 
 impl InternalRpcError {
@@ -70,6 +77,8 @@ impl InternalRpcError {
             Self::ClientNotFound => -32002,
             InternalRpcError::SerializeResponse(_) => -32003,
             InternalRpcError::AnyError(_) => -32004,
+            Self::RateLimited(_) | Self::ConcurrentLimitReached => -32029,
+            Self::Unauthorized => -32005,
             // Events invalid requests
             Self::EventNotSubscribed => -1,
             Self::EventAlreadySubscribed => -2,
@@ -101,13 +110,20 @@ impl RpcResponseError {
     }
 
     pub fn to_json(&self) -> Value {
+        let mut error = json!({
+            "code": self.error.get_code(),
+            "message": self.error.to_string()
+        });
+
+        // Let the client know when it can retry
+        if let InternalRpcError::RateLimited(retry_after_ms) = &self.error {
+            error["retry_after_ms"] = json!(retry_after_ms);
+        }
+
         json!({
             "jsonrpc": JSON_RPC_VERSION,
             "id": self.get_id(),
-            "error": {
-                "code": self.error.get_code(),
-                "message": self.error.to_string()
-            }
+            "error": error
         })
     }
 }
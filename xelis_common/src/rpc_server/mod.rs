@@ -1,17 +1,22 @@
 pub mod websocket;
 mod error;
 mod rpc_handler;
+mod rate_limit;
+mod auth;
 
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 pub use error::{RpcResponseError, InternalRpcError};
 pub use rpc_handler::{RPCHandler, Handler};
 pub use rpc_handler::parse_params;
+pub use rate_limit::{RpcRateLimiter, ConcurrencyGuard, ClientIp};
+pub use auth::{RpcAuthorizer, ClientAuthorization};
 
-use actix_web::{HttpResponse, web::{self, Data, Payload}, Responder, HttpRequest};
+use actix_web::{HttpResponse, web::{self, Data, Payload}, Responder, HttpRequest, http::header::AUTHORIZATION};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::context::Context;
 use self::websocket::{WebSocketServerShared, WebSocketHandler};
 
 pub const JSON_RPC_VERSION: &str = "2.0";
@@ -51,15 +56,40 @@ impl<'a> RpcResponse<'a> {
 // trait to retrieve easily a JSON RPC handler for registered route
 pub trait RPCServerHandler<T: Send + Clone> {
     fn get_rpc_handler(&self) -> &RPCHandler<T>;
+
+    // rate limiter applied to the clients, None if disabled
+    fn get_rate_limiter(&self) -> Option<&Arc<RpcRateLimiter>> {
+        None
+    }
 }
 
 // JSON RPC handler endpoint
-pub async fn json_rpc<T, H>(server: Data<H>, body: web::Bytes) -> Result<impl Responder, RpcResponseError>
+pub async fn json_rpc<T, H>(server: Data<H>, request: HttpRequest, body: web::Bytes) -> Result<impl Responder, RpcResponseError>
 where
     T: Send + Sync + Clone + 'static,
     H: RPCServerHandler<T>
 {
-    let result = server.get_rpc_handler().handle_request(&body).await?;
+    let handler = server.get_rpc_handler();
+    let mut context = Context::new();
+    context.store(handler.get_data().clone());
+    if let Some(value) = request.headers().get(AUTHORIZATION) {
+        context.store(ClientAuthorization(value.clone()));
+    }
+
+    let result = match server.get_rate_limiter() {
+        Some(limiter) => {
+            let ip = limiter.get_client_ip(request.headers(), request.peer_addr())
+                .ok_or_else(|| RpcResponseError::new(None, InternalRpcError::InvalidRequestStr("unknown client address")))?;
+
+            limiter.check(&ip).map_err(|e| RpcResponseError::new(None, e))?;
+            // keep the guard until the request is done
+            let _guard = limiter.acquire(ip).map_err(|e| RpcResponseError::new(None, e))?;
+
+            context.store(ClientIp(ip));
+            handler.handle_request_with_context(context, &body).await?
+        },
+        None => handler.handle_request_with_context(context, &body).await?
+    };
     Ok(HttpResponse::Ok().json(result))
 }
 
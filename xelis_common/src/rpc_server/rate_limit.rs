@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex
+    }
+};
+use actix_web::http::header::HeaderMap;
+use crate::rate_limiter::RateLimiter;
+use super::InternalRpcError;
+
+// Header set by reverse proxies with the original client IP
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+// IP address of the client that sent the request
+// It is stored in the request Context when a rate limiter is used
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+// Limit the requests per minute and the concurrent requests of each client IP
+// WebSocket connections also count as a concurrent request while they are open
+pub struct RpcRateLimiter {
+    // None if requests per minute are not limited
+    limiter: Option<RateLimiter<IpAddr>>,
+    // 0 if concurrent requests are not limited
+    concurrent_limit: usize,
+    // use the X-Forwarded-For header to determine the client IP
+    trust_proxy: bool,
+    // requests in progress per IP
+    in_flight: Mutex<HashMap<IpAddr, usize>>,
+    // requests rejected because of the rate limit
+    throttled_requests: AtomicU64,
+    // requests rejected because of the concurrent limit
+    concurrent_rejections: AtomicU64
+}
+
+impl RpcRateLimiter {
+    pub fn new(requests_per_minute: u64, concurrent_limit: usize, trust_proxy: bool) -> Self {
+        Self {
+            // A client can burst up to one minute of requests
+            limiter: if requests_per_minute > 0 {
+                Some(RateLimiter::new(requests_per_minute, requests_per_minute))
+            } else {
+                None
+            },
+            concurrent_limit,
+            trust_proxy,
+            in_flight: Mutex::new(HashMap::new()),
+            throttled_requests: AtomicU64::new(0),
+            concurrent_rejections: AtomicU64::new(0)
+        }
+    }
+
+    // Resolve the client IP of a request
+    // X-Forwarded-For is only used if we trust the proxy
+    // The client controls the first addresses of the header, only the last one is appended by our proxy
+    pub fn get_client_ip(&self, headers: &HeaderMap, peer_addr: Option<SocketAddr>) -> Option<IpAddr> {
+        if self.trust_proxy {
+            let forwarded = headers.get(FORWARDED_FOR_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|value| value.trim().parse().ok());
+
+            if forwarded.is_some() {
+                return forwarded
+            }
+        }
+
+        peer_addr.map(|addr| addr.ip())
+    }
+
+    // Consume a token for this IP
+    pub fn check(&self, ip: &IpAddr) -> Result<(), InternalRpcError> {
+        if let Some(limiter) = self.limiter.as_ref() {
+            if let Err(retry_after_ms) = limiter.check(*ip) {
+                self.throttled_requests.fetch_add(1, Ordering::Relaxed);
+                return Err(InternalRpcError::RateLimited(retry_after_ms))
+            }
+        }
+        Ok(())
+    }
+
+    // Register a new request in progress for this IP
+    // It is released once the returned guard is dropped
+    pub fn acquire(self: &Arc<Self>, ip: IpAddr) -> Result<ConcurrencyGuard, InternalRpcError> {
+        {
+            let mut in_flight = self.lock_in_flight();
+            let count = in_flight.entry(ip).or_insert(0);
+            if self.concurrent_limit > 0 && *count >= self.concurrent_limit {
+                self.concurrent_rejections.fetch_add(1, Ordering::Relaxed);
+                return Err(InternalRpcError::ConcurrentLimitReached)
+            }
+            *count += 1;
+        }
+
+        Ok(ConcurrencyGuard {
+            limiter: Arc::clone(self),
+            ip
+        })
+    }
+
+    fn release(&self, ip: &IpAddr) {
+        let mut in_flight = self.lock_in_flight();
+        if let Some(count) = in_flight.get_mut(ip) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(ip);
+            }
+        }
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<IpAddr, usize>> {
+        match self.in_flight.lock() {
+            Ok(in_flight) => in_flight,
+            Err(e) => e.into_inner()
+        }
+    }
+
+    // Requests allowed per minute, None if not limited
+    pub fn get_requests_per_minute(&self) -> Option<u64> {
+        self.limiter.as_ref().map(|limiter| limiter.get_rate())
+    }
+
+    // Concurrent requests allowed per IP, None if not limited
+    pub fn get_concurrent_limit(&self) -> Option<usize> {
+        if self.concurrent_limit > 0 {
+            Some(self.concurrent_limit)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_proxy_trusted(&self) -> bool {
+        self.trust_proxy
+    }
+
+    pub fn get_throttled_requests(&self) -> u64 {
+        self.throttled_requests.load(Ordering::Relaxed)
+    }
+
+    pub fn get_concurrent_rejections(&self) -> u64 {
+        self.concurrent_rejections.load(Ordering::Relaxed)
+    }
+
+    // Count of IPs with at least one request in progress
+    pub fn count_active_clients(&self) -> usize {
+        self.lock_in_flight().len()
+    }
+}
+
+// Request in progress, released on drop
+pub struct ConcurrencyGuard {
+    limiter: Arc<RpcRateLimiter>,
+    ip: IpAddr
+}
+
+impl ConcurrencyGuard {
+    pub fn get_ip(&self) -> &IpAddr {
+        &self.ip
+    }
+}
+
+impl Drop for ConcurrencyGuard {
+    fn drop(&mut self) {
+        self.limiter.release(&self.ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use super::*;
+
+    fn forwarded_headers(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(HeaderName::from_static("x-forwarded-for"), HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let limiter = RpcRateLimiter::new(2, 0, false);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        assert!(limiter.check(&ip).is_ok());
+        assert!(limiter.check(&ip).is_ok());
+        assert!(matches!(limiter.check(&ip), Err(InternalRpcError::RateLimited(retry)) if retry > 0));
+        assert_eq!(limiter.get_throttled_requests(), 1);
+        assert_eq!(InternalRpcError::RateLimited(1).get_code(), -32029);
+    }
+
+    #[test]
+    fn test_concurrent_limit() {
+        let limiter = Arc::new(RpcRateLimiter::new(0, 1, false));
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let guard = limiter.acquire(ip).unwrap();
+        assert!(limiter.acquire(ip).is_err());
+        assert_eq!(limiter.get_concurrent_rejections(), 1);
+
+        // Released on drop
+        drop(guard);
+        assert_eq!(limiter.count_active_clients(), 0);
+        assert!(limiter.acquire(ip).is_ok());
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let peer: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        let headers = forwarded_headers("1.2.3.4, 10.0.0.2");
+
+        // Header is ignored if the proxy is not trusted
+        let limiter = RpcRateLimiter::new(0, 0, false);
+        assert_eq!(limiter.get_client_ip(&headers, Some(peer)), Some(peer.ip()));
+
+        // Last address is the one seen by the proxy, previous ones are set by the client
+        let limiter = RpcRateLimiter::new(0, 0, true);
+        assert_eq!(limiter.get_client_ip(&headers, Some(peer)), "10.0.0.2".parse().ok());
+        assert_eq!(limiter.get_client_ip(&forwarded_headers("1.2.3.4"), Some(peer)), "1.2.3.4".parse().ok());
+        assert_eq!(limiter.get_client_ip(&forwarded_headers("invalid"), Some(peer)), Some(peer.ip()));
+        assert_eq!(limiter.get_client_ip(&forwarded_headers("127.0.0.1, invalid"), Some(peer)), Some(peer.ip()));
+    }
+}
@@ -1,16 +1,18 @@
-use std::{collections::HashMap, pin::Pin, future::Future};
+use std::{collections::HashMap, pin::Pin, future::Future, sync::Arc};
 use serde::de::DeserializeOwned;
 use serde_json::{json, Map, Value};
 use crate::context::Context;
 
-use super::{InternalRpcError, RpcResponseError, RpcRequest, JSON_RPC_VERSION};
+use super::{InternalRpcError, RpcAuthorizer, RpcResponseError, RpcRequest, JSON_RPC_VERSION};
 use log::{error, trace};
 
 pub type Handler = fn(&'_ Context, Value) -> Pin<Box<dyn Future<Output = Result<Value, InternalRpcError>> + Send + '_>>;
 
 pub struct RPCHandler<T: Send + Clone + 'static> {
     methods: HashMap<String, Handler>, // all RPC methods registered
-    data: T
+    data: T,
+    // checks the client credentials before each method, None if disabled
+    authorizer: Option<Arc<dyn RpcAuthorizer>>
 }
 
 impl<T> RPCHandler<T>
@@ -20,10 +22,30 @@ where
     pub fn new(data: T) -> Self {
         Self {
             methods: HashMap::new(),
-            data
+            data,
+            authorizer: None
         }
     }
 
+    pub fn set_authorizer(&mut self, authorizer: Arc<dyn RpcAuthorizer>) {
+        self.authorizer = Some(authorizer);
+    }
+
+    // Verify that the client can call the requested method
+    // This is done before searching the method to not reveal which ones exist
+    pub async fn authorize(&self, context: &Context, request: &RpcRequest) -> Result<(), RpcResponseError> {
+        self.authorize_method(context, &request.method).await
+            .map_err(|e| RpcResponseError::new(request.id.clone(), e))
+    }
+
+    // Same check for a method called internally, like the sub-queries of a composite method
+    pub async fn authorize_method(&self, context: &Context, method: &str) -> Result<(), InternalRpcError> {
+        if let Some(authorizer) = self.authorizer.as_ref() {
+            authorizer.authorize(context, method).await?;
+        }
+        Ok(())
+    }
+
     pub async fn handle_request(&self, body: &[u8]) -> Result<Value, RpcResponseError> {
         let mut context = Context::new();
 
@@ -78,6 +100,7 @@ where
     }
 
     pub async fn execute_method<'a>(&'a self, context: &'a Context, mut request: RpcRequest) -> Result<Option<Value>, RpcResponseError> {
+        self.authorize(context, &request).await?;
         let handler = match self.methods.get(&request.method) {
             Some(handler) => handler,
             None => return Err(RpcResponseError::new(request.id, InternalRpcError::MethodNotFound(request.method)))
@@ -1,5 +1,5 @@
-use std::{collections::{HashMap, HashSet}, hash::Hash, borrow::Cow};
-use actix_web::web::Bytes;
+use std::{collections::{HashMap, HashSet}, hash::Hash, borrow::Cow, sync::Arc};
+use actix_web::{http::header::AUTHORIZATION, web::Bytes};
 use async_trait::async_trait;
 use log::{trace, debug};
 use serde_json::{Value, json};
@@ -9,8 +9,12 @@ use crate::{
     api::{EventResult, SubscribeParams},
     context::Context,
     rpc_server::{
+        ClientAuthorization,
+        ClientIp,
+        ConcurrencyGuard,
         Id,
         InternalRpcError,
+        RpcRateLimiter,
         RPCHandler,
         RpcRequest,
         RpcResponse,
@@ -22,7 +26,11 @@ use super::{WebSocketSessionShared, WebSocketHandler};
 // generic websocket handler supporting event subscriptions 
 pub struct EventWebSocketHandler<T: Sync + Send + Clone + 'static, E: Serialize + DeserializeOwned + Sync + Send + Eq + Hash + Clone + 'static> {
     events: RwLock<HashMap<WebSocketSessionShared<Self>, HashMap<E, Option<Id>>>>,
-    handler: RPCHandler<T>
+    handler: RPCHandler<T>,
+    // rate limiter applied on every message received, including subscriptions
+    rate_limiter: Option<Arc<RpcRateLimiter>>,
+    // each open session counts as a concurrent request of its IP
+    quotas: RwLock<HashMap<WebSocketSessionShared<Self>, ConcurrencyGuard>>
 }
 
 impl<T, E> EventWebSocketHandler<T, E>
@@ -33,7 +41,18 @@ where
     pub fn new(handler: RPCHandler<T>) -> Self {
         Self {
             events: RwLock::new(HashMap::new()),
-            handler
+            handler,
+            rate_limiter: None,
+            quotas: RwLock::new(HashMap::new())
+        }
+    }
+
+    pub fn with_rate_limiter(handler: RPCHandler<T>, rate_limiter: Arc<RpcRateLimiter>) -> Self {
+        Self {
+            events: RwLock::new(HashMap::new()),
+            handler,
+            rate_limiter: Some(rate_limiter),
+            quotas: RwLock::new(HashMap::new())
         }
     }
 
@@ -115,11 +134,13 @@ where
         let method = request.method.clone();
         match method.as_str() {
             "subscribe" => {
+                self.handler.authorize(context, &request).await?;
                 let event = self.parse_event(&mut request)?;
                 self.subscribe_session_to_event(context.get::<WebSocketSessionShared<Self>>().unwrap(), event, request.id.clone()).await?;
                 Ok(Some(json!(RpcResponse::new(Cow::Borrowed(&request.id), Cow::Owned(Value::Bool(true))))))
             },
             "unsubscribe" => {
+                self.handler.authorize(context, &request).await?;
                 let event = self.parse_event(&mut request)?;
                 self.unsubscribe_session_from_event(context.get::<WebSocketSessionShared<Self>>().unwrap(), event, request.id.clone()).await?;
                 Ok(Some(json!(RpcResponse::new(Cow::Borrowed(&request.id), Cow::Owned(Value::Bool(true))))))
@@ -135,6 +156,20 @@ where
         let mut context = Context::default();
         context.store(session.clone());
         context.store(self.handler.get_data().clone());
+        // the credential is checked on each message, a revoked key is refused in opened sessions
+        if let Some(value) = session.get_request().headers().get(AUTHORIZATION) {
+            context.store(ClientAuthorization(value.clone()));
+        }
+
+        if let Some(limiter) = self.rate_limiter.as_ref() {
+            let ip = {
+                let quotas = self.quotas.read().await;
+                quotas.get(session).map(|guard| *guard.get_ip())
+                    .ok_or_else(|| RpcResponseError::new(None, InternalRpcError::ClientNotFound))?
+            };
+            limiter.check(&ip).map_err(|e| RpcResponseError::new(None, e))?;
+            context.store(ClientIp(ip));
+        }
 
         match request {
             e @ Value::Object(_) => self.execute_method_internal(&context, e).await.map(|e| e.unwrap_or(Value::Null)),
@@ -168,11 +203,30 @@ where
     T: Sync + Send + Clone + 'static,
     E: Serialize + DeserializeOwned + Sync + Send + Eq + Hash + Clone + 'static
 {
+    async fn on_connection(&self, session: &WebSocketSessionShared<Self>) -> Result<(), anyhow::Error> {
+        if let Some(limiter) = self.rate_limiter.as_ref() {
+            let request = session.get_request();
+            let ip = limiter.get_client_ip(request.headers(), request.head().peer_addr)
+                .ok_or_else(|| anyhow::anyhow!("unknown client address"))?;
+
+            let guard = limiter.acquire(ip)?;
+            let mut quotas = self.quotas.write().await;
+            quotas.insert(session.clone(), guard);
+        }
+        Ok(())
+    }
+
     async fn on_close(&self, session: &WebSocketSessionShared<Self>) -> Result<(), anyhow::Error> {
         trace!("deleting ws session from events");
-        let mut sessions = self.events.write().await;
-        sessions.remove(session);
+        {
+            let mut sessions = self.events.write().await;
+            sessions.remove(session);
+        }
         trace!("session deleted from events");
+
+        // release its concurrent slot
+        let mut quotas = self.quotas.write().await;
+        quotas.remove(session);
         Ok(())
     }
 
@@ -271,13 +271,13 @@ impl<T: Serializer> Serializer for Option<T> {
 
 impl<T: Serializer> Serializer for Vec<T> {
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
-        let count = reader.read_u16()?;
-        if count > MAX_ITEMS as u16 {
+        let count = reader.read_length_prefix(1)?;
+        if count > MAX_ITEMS {
             warn!("Received {} while maximum is set to {}", count, MAX_ITEMS);
             return Err(ReaderError::InvalidSize)
         }
 
-        let mut values = Vec::with_capacity(count as usize);
+        let mut values = Vec::with_capacity(count);
         for _ in 0..count {
             values.push(T::read(reader)?);
         }
@@ -335,8 +335,8 @@ impl Serializer for bool {
 // Supports up to 2^16 elements
 impl<K: Serializer + Eq + StdHash, V: Serializer + Eq + StdHash> Serializer for HashMap<K, V> {
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
-        let size = reader.read_u16()?;
-        let mut map = HashMap::with_capacity(size as usize);
+        let size = reader.read_length_prefix(1)?;
+        let mut map = HashMap::with_capacity(size);
         for _ in 0..size {
             let k = K::read(reader)?;
             let v = V::read(reader)?;
@@ -476,8 +476,8 @@ impl<A: Serializer, B: Serializer, C: Serializer> Serializer for (A, B, C) {
 
 impl<K: Serializer + std::hash::Hash + Eq, V: Serializer> Serializer for IndexMap<K, V> {
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
-        let size = reader.read_u16()?;
-        let mut map = IndexMap::with_capacity(size as usize);
+        let size = reader.read_length_prefix(1)?;
+        let mut map = IndexMap::with_capacity(size);
         for _ in 0..size {
             let k = K::read(reader)?;
             let v = V::read(reader)?;
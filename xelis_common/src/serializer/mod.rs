@@ -1,9 +1,12 @@
 mod defaults;
 mod reader;
+mod varint;
 mod writer;
 
 pub use reader::{Reader, ReaderError};
+pub use varint::{varint_u64_size, VarU64};
 pub use writer::Writer;
+pub use xelis_derive::Serializer;
 use std::marker::Sized;
 
 pub trait Serializer {
@@ -46,4 +49,106 @@ pub trait Serializer {
         let mut reader = Reader::new(bytes);
         Self::read(&mut reader)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use crate::crypto::Hash;
+    use super::*;
+
+    #[derive(Serializer, Debug, PartialEq)]
+    struct Simple {
+        id: u64,
+        name: String,
+        hash: Hash,
+        flags: [u8; 4],
+        parent: Option<Hash>
+    }
+
+    #[derive(Serializer, Debug, PartialEq)]
+    struct WithCache<'a> {
+        hash: Cow<'a, Hash>,
+        values: Vec<u16>,
+        #[serializer(skip)]
+        cache: Option<u64>
+    }
+
+    #[derive(Serializer, Debug, PartialEq)]
+    struct Tuple(u8, bool, VarU64);
+
+    #[test]
+    fn test_derive_round_trip() {
+        let value = Simple {
+            id: 42,
+            name: "xelis".to_owned(),
+            hash: Hash::new([1u8; 32]),
+            flags: [1, 2, 3, 4],
+            parent: Some(Hash::zero())
+        };
+
+        let bytes = value.to_bytes();
+        assert_eq!(bytes.len(), value.size());
+        assert_eq!(Simple::from_bytes(&bytes).unwrap(), value);
+
+        // Same layout as writing each field by hand
+        let mut writer = Writer::new();
+        value.id.write(&mut writer);
+        value.name.write(&mut writer);
+        value.hash.write(&mut writer);
+        value.flags.write(&mut writer);
+        value.parent.write(&mut writer);
+        assert_eq!(writer.bytes(), bytes);
+    }
+
+    #[test]
+    fn test_derive_skip() {
+        let value = WithCache {
+            hash: Cow::Owned(Hash::new([2u8; 32])),
+            values: vec![1, 2, 3],
+            cache: Some(10)
+        };
+
+        let bytes = value.to_bytes();
+        assert_eq!(bytes.len(), value.size());
+        assert_eq!(bytes.len(), 32 + value.values.size());
+
+        // Skipped field is set to its default value
+        let decoded = WithCache::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.hash, value.hash);
+        assert_eq!(decoded.values, value.values);
+        assert_eq!(decoded.cache, None);
+    }
+
+    #[test]
+    fn test_derive_tuple() {
+        let value = Tuple(7, true, VarU64(300));
+        let bytes = value.to_bytes();
+        assert_eq!(bytes, vec![7, 1, 0xac, 0x02]);
+        assert_eq!(Tuple::from_bytes(&bytes).unwrap(), value);
+        assert!(Tuple::from_bytes(&bytes[..3]).is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_round_trip() {
+        use std::net::SocketAddr;
+
+        let v4: SocketAddr = "192.168.1.10:2125".parse().unwrap();
+        let bytes = v4.to_bytes();
+        assert_eq!(bytes, vec![0, 192, 168, 1, 10, 0x08, 0x4d]);
+        assert_eq!(bytes.len(), v4.size());
+        assert_eq!(SocketAddr::from_bytes(&bytes).unwrap(), v4);
+
+        let v6: SocketAddr = "[2001:db8::ff00:42:8329]:2125".parse().unwrap();
+        let bytes = v6.to_bytes();
+        assert_eq!(bytes.len(), 1 + 16 + 2);
+        assert_eq!(bytes.len(), v6.size());
+        assert_eq!(&bytes[..3], &[1, 0x20, 0x01]);
+        assert_eq!(SocketAddr::from_bytes(&bytes).unwrap(), v6);
+        assert!(SocketAddr::from_bytes(&bytes[..10]).is_err());
+
+        // Both families in the same list
+        let list = vec![v4, v6];
+        assert_eq!(Vec::<SocketAddr>::from_bytes(&list.to_bytes()).unwrap(), list);
+    }
 }
\ No newline at end of file
@@ -3,7 +3,7 @@ use thiserror::Error;
 
 use crate::crypto::Hash;
 
-use super::Serializer;
+use super::{Serializer, varint::MAX_VARINT_U64_SIZE};
 
 #[derive(Error, Debug)]
 pub enum ReaderError {
@@ -97,6 +97,15 @@ impl<'a> Reader<'a> {
         Ok(byte)
     }
 
+    // Read the next byte without consuming it
+    // Useful to branch on a tag before reading the value
+    pub fn peek_u8(&self) -> Result<u8, ReaderError> {
+        if self.size() == 0 {
+            return Err(ReaderError::InvalidSize)
+        }
+        Ok(self.bytes[self.total])
+    }
+
     pub fn read_u16(&mut self) -> Result<u16, ReaderError> {
         Ok(u16::from_be_bytes(self.read_bytes(2)?))
     }
@@ -113,6 +122,41 @@ impl<'a> Reader<'a> {
         Ok(u128::from_be_bytes(self.read_bytes(16)?))
     }
 
+    // Read a value written by Writer::write_varint_u64
+    // Overlong encodings are refused so each value has a single encoding
+    pub fn read_varint_u64(&mut self) -> Result<u64, ReaderError> {
+        let mut value: u64 = 0;
+        for i in 0..MAX_VARINT_U64_SIZE {
+            let byte = self.read_u8()?;
+            // 10th byte can only hold the highest bit of a u64
+            if i == MAX_VARINT_U64_SIZE - 1 && byte > 1 {
+                return Err(ReaderError::InvalidValue)
+            }
+
+            value |= ((byte & 0x7F) as u64) << (i * 7);
+            if byte & 0x80 == 0 {
+                // A last byte at zero means a shorter encoding was possible
+                if byte == 0 && i > 0 {
+                    return Err(ReaderError::InvalidValue)
+                }
+                return Ok(value)
+            }
+        }
+
+        Err(ReaderError::InvalidValue)
+    }
+
+    // Read a u16 length prefix and verify that the remaining bytes can hold
+    // that many items of at least min_item_size bytes
+    // This prevents allocating a huge collection from a forged length
+    pub fn read_length_prefix(&mut self, min_item_size: usize) -> Result<usize, ReaderError> {
+        let count = self.read_u16()? as usize;
+        if count * min_item_size > self.size() {
+            return Err(ReaderError::InvalidSize)
+        }
+        Ok(count)
+    }
+
     pub fn read_string_with_size(&mut self, size: usize) -> Result<String, ReaderError> {
         let bytes: Vec<u8> = self.read_bytes(size)?;
         match String::from_utf8(bytes) {
@@ -150,7 +194,47 @@ impl<'a> Reader<'a> {
         self.bytes.len() - self.total
     }
 
+    // Bytes left to read, same as size()
+    pub fn remaining(&self) -> usize {
+        self.size()
+    }
+
     pub fn total_read(&self) -> usize {
         self.total
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peek_u8() {
+        let mut reader = Reader::new(&[1, 2]);
+        assert_eq!(reader.peek_u8().unwrap(), 1);
+        assert_eq!(reader.remaining(), 2);
+        assert_eq!(reader.read_u8().unwrap(), 1);
+        assert_eq!(reader.peek_u8().unwrap(), 2);
+        assert_eq!(reader.read_u8().unwrap(), 2);
+        assert_eq!(reader.remaining(), 0);
+
+        // Nothing left to peek
+        assert!(matches!(reader.peek_u8(), Err(ReaderError::InvalidSize)));
+        assert!(matches!(Reader::new(&[]).peek_u8(), Err(ReaderError::InvalidSize)));
+    }
+
+    #[test]
+    fn test_length_prefix_above_remaining_bytes() {
+        // 65535 items announced but only 4 bytes available
+        let bytes = [0xFF, 0xFF, 0, 0, 0, 0];
+        let mut reader = Reader::new(&bytes);
+        assert!(matches!(reader.read_length_prefix(1), Err(ReaderError::InvalidSize)));
+
+        let mut reader = Reader::new(&bytes[..]);
+        assert!(Vec::<u8>::read(&mut reader).is_err());
+
+        let bytes = [0, 2, 1, 2];
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.read_length_prefix(1).unwrap(), 2);
+    }
+}
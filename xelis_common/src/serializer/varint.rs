@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use super::{Reader, ReaderError, Serializer, Writer};
+
+// A u64 takes at most 10 bytes with 7 bits per byte
+pub const MAX_VARINT_U64_SIZE: usize = 10;
+
+// Bytes used by Writer::write_varint_u64 to write this value
+pub const fn varint_u64_size(value: u64) -> usize {
+    let bits = 64 - value.leading_zeros() as usize;
+    if bits == 0 {
+        1
+    } else {
+        (bits + 6) / 7
+    }
+}
+
+// u64 serialized using the varint encoding
+// New serializers can opt in by using it instead of a u64
+// Existing formats keep their fixed width encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VarU64(pub u64);
+
+impl From<u64> for VarU64 {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VarU64> for u64 {
+    fn from(value: VarU64) -> Self {
+        value.0
+    }
+}
+
+impl Serializer for VarU64 {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_varint_u64(&self.0);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self(reader.read_varint_u64()?))
+    }
+
+    fn size(&self) -> usize {
+        varint_u64_size(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        let values = [(0, 1), (1, 1), (127, 1), (128, 2), (16383, 2), (16384, 3), (u32::MAX as u64, 5), (u64::MAX - 1, 10), (u64::MAX, 10)];
+        for (value, size) in values {
+            let varint = VarU64(value);
+            let bytes = varint.to_bytes();
+            assert_eq!(bytes.len(), size, "size of {}", value);
+            assert_eq!(varint.size(), size);
+            assert_eq!(VarU64::from_bytes(&bytes).unwrap(), varint);
+        }
+
+        assert_eq!(VarU64(127).to_bytes(), vec![0x7F]);
+        assert_eq!(VarU64(128).to_bytes(), vec![0x80, 0x01]);
+    }
+
+    #[test]
+    fn test_varint_malformed() {
+        // Overlong encoding of 0 and 1
+        assert!(matches!(Reader::new(&[0x80, 0x00]).read_varint_u64(), Err(ReaderError::InvalidValue)));
+        assert!(matches!(Reader::new(&[0x81, 0x80, 0x00]).read_varint_u64(), Err(ReaderError::InvalidValue)));
+        // Above u64::MAX
+        let mut bytes = [0xFF; 10];
+        bytes[9] = 0x02;
+        assert!(matches!(Reader::new(&bytes).read_varint_u64(), Err(ReaderError::InvalidValue)));
+        // Too many bytes
+        assert!(Reader::new(&[0xFF; 11]).read_varint_u64().is_err());
+        // Truncated
+        assert!(matches!(Reader::new(&[0x80]).read_varint_u64(), Err(ReaderError::InvalidSize)));
+    }
+}
@@ -42,6 +42,17 @@ impl Writer {
         self.bytes.extend(value.to_be_bytes());
     }
 
+    // LEB128 encoding: 7 bits per byte, lowest bits first
+    // The highest bit of each byte is set if another byte follows
+    pub fn write_varint_u64(&mut self, value: &u64) {
+        let mut value = *value;
+        while value >= 0x80 {
+            self.bytes.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
     pub fn write_string(&mut self, value: &String) {
         self.bytes.push(value.len() as u8);
         self.bytes.extend(value.as_bytes());
@@ -0,0 +1,280 @@
+use curve25519_dalek::Scalar;
+use proptest::{
+    collection::{hash_set, vec},
+    option,
+    prelude::*
+};
+use crate::{
+    account::{BalanceType, CiphertextCache, VersionedBalance, VersionedNonce},
+    api::{DataElement, DataValue},
+    block::{BlockHeader, MinerWork, EXTRA_NONCE_SIZE},
+    config::{COIN_VALUE, TIPS_LIMIT, XELIS_ASSET},
+    crypto::{
+        elgamal::{Ciphertext, CompressedPublicKey, PrivateKey},
+        Hash,
+        KeyPair,
+        HASH_SIZE
+    },
+    transaction::{
+        builder::{
+            AccountState,
+            FeeBuilder,
+            FeeHelper,
+            TransactionBuilder,
+            TransactionTypeBuilder,
+            TransferBuilder
+        },
+        BurnPayload,
+        Reference,
+        Transaction
+    }
+};
+
+// Generators producing valid instances of the consensus types
+// They are available to other crates through the testing feature
+
+// Keep the generated headers small, real blocks are bounded by the block size
+const MAX_TXS_PER_HEADER: usize = 64;
+// Each transfer requires proofs to be generated
+const MAX_TRANSFERS_PER_TX: usize = 4;
+// Balance of the generated senders, enough for any amount and fees
+const SENDER_BALANCE: u64 = 1000 * COIN_VALUE;
+
+// KeyPair has no Debug impl, so keys are generated from a seed
+fn keypair_from_seed(seed: [u8; 32]) -> KeyPair {
+    let scalar = Scalar::from_bytes_mod_order(seed);
+    let scalar = if scalar == Scalar::ZERO { Scalar::ONE } else { scalar };
+    KeyPair::from_private_key(PrivateKey::from_scalar(scalar))
+}
+
+pub fn arb_hash() -> impl Strategy<Value = Hash> {
+    any::<[u8; HASH_SIZE]>().prop_map(Hash::new)
+}
+
+pub fn arb_public_key() -> impl Strategy<Value = CompressedPublicKey> {
+    any::<[u8; 32]>().prop_map(|seed| keypair_from_seed(seed).get_public_key().compress())
+}
+
+pub fn arb_block_header() -> impl Strategy<Value = BlockHeader> {
+    (
+        any::<u64>(),
+        any::<u64>(),
+        any::<u64>(),
+        any::<[u8; EXTRA_NONCE_SIZE]>(),
+        hash_set(arb_hash(), 0..=TIPS_LIMIT),
+        hash_set(arb_hash(), 0..=MAX_TXS_PER_HEADER),
+        arb_public_key()
+    ).prop_map(|(height, timestamp, nonce, extra_nonce, tips, txs_hashes, miner)| {
+        let mut header = BlockHeader::new(0, height, timestamp, tips.into_iter().collect(), extra_nonce, miner, txs_hashes.into_iter().collect());
+        header.nonce = nonce;
+        header
+    })
+}
+
+pub fn arb_miner_work() -> impl Strategy<Value = MinerWork<'static>> {
+    arb_block_header().prop_map(MinerWork::from_block)
+}
+
+pub fn arb_ciphertext() -> impl Strategy<Value = CiphertextCache> {
+    (any::<[u8; 32]>(), any::<u64>()).prop_map(|(seed, amount)| {
+        let ciphertext = keypair_from_seed(seed).get_public_key().encrypt(amount);
+        CiphertextCache::Compressed(ciphertext.compress())
+    })
+}
+
+pub fn arb_versioned_balance() -> impl Strategy<Value = VersionedBalance> {
+    let balance_type = prop_oneof![
+        Just(BalanceType::Input),
+        Just(BalanceType::Output),
+        Just(BalanceType::Both)
+    ];
+
+    (arb_ciphertext(), arb_ciphertext(), balance_type, option::of(any::<u64>()))
+        .prop_map(|(final_balance, output_balance, balance_type, previous_topoheight)| {
+            let mut balance = VersionedBalance::new(final_balance, previous_topoheight);
+            balance.set_balance_type(balance_type);
+            // The output balance is only stored when the version has both inputs and outputs
+            if balance_type == BalanceType::Both {
+                balance.set_output_balance(Some(output_balance));
+            }
+            balance
+        })
+}
+
+pub fn arb_versioned_nonce() -> impl Strategy<Value = VersionedNonce> {
+    (any::<u64>(), option::of(any::<u64>())).prop_map(|(nonce, previous_topoheight)| VersionedNonce::new(nonce, previous_topoheight))
+}
+
+fn arb_transaction_type() -> impl Strategy<Value = TransactionTypeBuilder> {
+    let transfer = (any::<[u8; 32]>(), 1..=COIN_VALUE, option::of("[a-zA-Z0-9 ]{0,32}"))
+        .prop_map(|(seed, amount, extra_data)| TransferBuilder {
+            asset: XELIS_ASSET,
+            amount,
+            destination: keypair_from_seed(seed).get_public_key().to_address(false),
+            extra_data: extra_data.map(|value| DataElement::Value(DataValue::String(value)))
+        });
+
+    prop_oneof![
+        vec(transfer, 1..=MAX_TRANSFERS_PER_TX).prop_map(TransactionTypeBuilder::Transfers),
+        (1..=COIN_VALUE).prop_map(|amount| TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount }))
+    ]
+}
+
+// Transactions are built and proved from a funded sender
+// Keep the number of cases low when using it
+pub fn arb_transaction() -> impl Strategy<Value = Transaction> {
+    (any::<[u8; 32]>(), any::<u32>(), arb_transaction_type(), arb_hash(), any::<u32>())
+        .prop_map(|(seed, nonce, data, hash, topoheight)| {
+            let reference = Reference {
+                hash,
+                topoheight: topoheight as u64
+            };
+            build_transaction(&keypair_from_seed(seed), SENDER_BALANCE, nonce as u64, reference, data)
+        })
+}
+
+// Build a transaction from a sender having only this XELIS balance
+// The balance ciphertext used is the encryption of the balance with the sender key
+pub fn build_transaction(keypair: &KeyPair, balance: u64, nonce: u64, reference: Reference, data: TransactionTypeBuilder) -> Transaction {
+    build_transaction_with_fee(keypair, balance, nonce, reference, data, FeeBuilder::Multiplier(1f64))
+}
+
+// Same as build_transaction but with the fee to pay
+pub fn build_transaction_with_fee(keypair: &KeyPair, balance: u64, nonce: u64, reference: Reference, data: TransactionTypeBuilder, fee: FeeBuilder) -> Transaction {
+    let mut state = SenderState {
+        balance,
+        ciphertext: CiphertextCache::Decompressed(keypair.get_public_key().encrypt(balance)),
+        nonce,
+        reference
+    };
+
+    let builder = TransactionBuilder::new(0, keypair.get_public_key().compress(), data, fee);
+    builder.build(&mut state, keypair).expect("generated transaction must be valid")
+}
+
+// Sender account holding only XELIS
+struct SenderState {
+    balance: u64,
+    ciphertext: CiphertextCache,
+    nonce: u64,
+    reference: Reference
+}
+
+impl FeeHelper for SenderState {
+    type Error = ();
+
+    fn account_exists(&self, _: &CompressedPublicKey) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+impl AccountState for SenderState {
+    fn is_mainnet(&self) -> bool {
+        false
+    }
+
+    fn get_account_balance(&self, asset: &Hash) -> Result<u64, Self::Error> {
+        if *asset != XELIS_ASSET {
+            return Err(())
+        }
+        Ok(self.balance)
+    }
+
+    fn get_reference(&self) -> Reference {
+        self.reference.clone()
+    }
+
+    fn get_account_ciphertext(&self, asset: &Hash) -> Result<CiphertextCache, Self::Error> {
+        if *asset != XELIS_ASSET {
+            return Err(())
+        }
+        Ok(self.ciphertext.clone())
+    }
+
+    fn update_account_balance(&mut self, asset: &Hash, new_balance: u64, ciphertext: Ciphertext) -> Result<(), Self::Error> {
+        if *asset != XELIS_ASSET {
+            return Err(())
+        }
+        self.balance = new_balance;
+        self.ciphertext = CiphertextCache::Decompressed(ciphertext);
+        Ok(())
+    }
+
+    fn get_nonce(&self) -> Result<u64, Self::Error> {
+        Ok(self.nonce)
+    }
+
+    fn update_nonce(&mut self, new_nonce: u64) -> Result<(), Self::Error> {
+        self.nonce = new_nonce;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block::BLOCK_WORK_SIZE,
+        crypto::Hashable,
+        serializer::Serializer
+    };
+    use super::*;
+
+    // Serialized bytes must match size() and be identical after a round trip
+    fn assert_round_trip<T: Serializer>(value: &T) -> T {
+        let bytes = value.to_bytes();
+        assert_eq!(bytes.len(), value.size());
+        let decoded = T::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        decoded
+    }
+
+    proptest! {
+        #[test]
+        fn test_block_header(header in arb_block_header()) {
+            let decoded = assert_round_trip(&header);
+            prop_assert_eq!(decoded.hash(), header.hash());
+            prop_assert_eq!(decoded.get_work_hash(), header.get_work_hash());
+        }
+
+        #[test]
+        fn test_miner_work(header in arb_block_header()) {
+            let work = MinerWork::from_block(header.clone());
+            let bytes = work.to_bytes();
+            prop_assert_eq!(bytes.len(), BLOCK_WORK_SIZE);
+            assert_round_trip(&work);
+
+            // Applying the work gives back the POW relevant bytes of the header
+            let mut rebuilt = header.clone();
+            rebuilt.apply_miner_work(work.clone());
+            prop_assert_eq!(rebuilt.get_work_hash(), header.get_work_hash());
+            prop_assert_eq!(rebuilt.hash(), work.hash());
+            prop_assert_eq!(rebuilt.get_miner(), header.get_miner());
+            prop_assert_eq!(rebuilt.get_extra_nonce(), header.get_extra_nonce());
+            prop_assert_eq!(rebuilt.get_timestamp(), header.get_timestamp());
+        }
+
+        #[test]
+        fn test_versioned_balance(balance in arb_versioned_balance()) {
+            let decoded = assert_round_trip(&balance);
+            prop_assert_eq!(decoded.get_balance_type(), balance.get_balance_type());
+            prop_assert_eq!(decoded.get_previous_topoheight(), balance.get_previous_topoheight());
+        }
+
+        #[test]
+        fn test_versioned_nonce(nonce in arb_versioned_nonce()) {
+            let decoded = assert_round_trip(&nonce);
+            prop_assert_eq!(decoded.get_nonce(), nonce.get_nonce());
+            prop_assert_eq!(decoded.get_previous_topoheight(), nonce.get_previous_topoheight());
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_transaction(tx in arb_transaction()) {
+            let decoded = assert_round_trip(&tx);
+            prop_assert_eq!(decoded.hash(), tx.hash());
+        }
+    }
+}
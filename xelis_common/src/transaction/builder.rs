@@ -11,6 +11,7 @@ use std::{
 };
 use crate::{
     account::CiphertextCache,
+    amount::{checked_add, AmountOverflow},
     api::DataElement,
     config::XELIS_ASSET,
     crypto::{
@@ -39,8 +40,7 @@ use crate::{
         HASH_SIZE,
         SIGNATURE_SIZE
     },
-    serializer::{Reader, ReaderError, Serializer, Writer},
-    utils::calculate_tx_fee
+    serializer::{Reader, ReaderError, Serializer, Writer}
 };
 use thiserror::Error;
 use super::{
@@ -76,6 +76,8 @@ pub enum GenerationError<T> {
     ExtraDataAndIntegratedAddress,
     #[error("Proof generation error: {0}")]
     Proof(#[from] ProofGenerationError),
+    #[error(transparent)]
+    AmountOverflow(#[from] AmountOverflow),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -345,7 +347,7 @@ impl TransactionBuilder {
                     (0, 0)
                 };
 
-                let expected_fee = calculate_tx_fee(size, transfers, new_addresses);
+                let expected_fee = Transaction::estimate_fee(size, transfers, new_addresses);
                 (expected_fee as f64 * multiplier) as u64
             },
             // If the value is set, use it
@@ -380,30 +382,30 @@ impl TransactionBuilder {
     }
 
     /// Compute the full cost of the transaction
-    pub fn get_transaction_cost(&self, fee: u64, asset: &Hash) -> u64 {
+    pub fn get_transaction_cost(&self, fee: u64, asset: &Hash) -> Result<u64, AmountOverflow> {
         let mut cost = 0;
 
         if *asset == XELIS_ASSET {
             // Fees are applied to the native blockchain asset only.
-            cost += fee;
+            cost = fee;
         }
 
         match &self.data {
             TransactionTypeBuilder::Transfers(transfers) => {
                 for transfer in transfers {
                     if &transfer.asset == asset {
-                        cost += transfer.amount;
+                        cost = checked_add(cost, transfer.amount)?;
                     }
                 }
             }
             TransactionTypeBuilder::Burn(payload) => {
                 if *asset == payload.asset {
-                    cost += payload.amount
+                    cost = checked_add(cost, payload.amount)?;
                 }
             }
         }
 
-        cost
+        Ok(cost)
     }
 
     pub fn build<B: AccountState>(
@@ -502,7 +504,7 @@ impl TransactionBuilder {
         let mut range_proof_values: Vec<_> = used_assets
             .iter()
             .map(|asset| {
-                let cost = self.get_transaction_cost(fee, &asset);
+                let cost = self.get_transaction_cost(fee, &asset)?;
                 let source_new_balance = state
                     .get_account_balance(asset)
                     .map_err(GenerationError::State)?
@@ -1,5 +1,7 @@
 use std::fmt;
 use crate::{
+    amount::{checked_add, AmountOverflow},
+    config::XELIS_ASSET,
     crypto::{
         elgamal::{CompressedCiphertext, CompressedCommitment, CompressedHandle, CompressedPublicKey},
         proofs::{CiphertextValidityProof, CommitmentEqProof},
@@ -7,7 +9,8 @@ use crate::{
         Hashable,
         Signature,
     },
-    serializer::{Reader, ReaderError, Serializer, Writer}
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    utils::calculate_tx_fee
 };
 use bulletproofs::RangeProof;
 use log::debug;
@@ -232,6 +235,37 @@ impl Transaction {
         &self.reference
     }
 
+    // Get the number of transfers outputs
+    pub fn get_outputs_count(&self) -> usize {
+        match &self.data {
+            TransactionType::Transfers(transfers) => transfers.len(),
+            TransactionType::Burn(_) => 0
+        }
+    }
+
+    // Plain amount spent for this asset: the fee for XELIS and the burned amount
+    // Transfers amounts are encrypted and not included
+    pub fn get_plain_spent(&self, asset: &Hash) -> Result<u64, AmountOverflow> {
+        let mut spent = 0;
+        if *asset == XELIS_ASSET {
+            spent = self.fee;
+        }
+
+        if let TransactionType::Burn(payload) = &self.data {
+            if payload.asset == *asset {
+                spent = checked_add(spent, payload.amount)?;
+            }
+        }
+
+        Ok(spent)
+    }
+
+    // Minimum fee required by the chain for a transaction
+    // based on its serialized size, its outputs count and the outputs sent to a new address
+    pub fn estimate_fee(tx_size: usize, output_count: usize, new_addresses: usize) -> u64 {
+        calculate_tx_fee(tx_size, output_count, new_addresses)
+    }
+
     pub fn consume(self) -> (CompressedPublicKey, TransactionType) {
         (self.source, self.data)
     }
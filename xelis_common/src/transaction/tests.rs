@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 use crate::{
     account::CiphertextCache,
+    amount::AmountOverflow,
     api::{DataElement, DataValue},
     config::{COIN_VALUE, XELIS_ASSET},
     crypto::{
@@ -24,11 +25,12 @@ use super::{
         AccountState,
         FeeBuilder,
         FeeHelper,
+        GenerationError,
         TransactionBuilder,
         TransactionTypeBuilder,
         TransferBuilder
     },
-    verify::BlockchainVerificationState,
+    verify::{BlockchainVerificationState, VerificationError},
     BurnPayload,
     Reference,
     Role,
@@ -112,6 +114,28 @@ fn create_tx_for(account: Account, destination: Address, amount: u64, extra_data
     tx
 }
 
+#[test]
+fn test_estimate_fee() {
+    // Pin the fee formula: 10000 per started KB, 5000 per output, 100000 per new address
+    assert_eq!(Transaction::estimate_fee(0, 0, 0), 0);
+    assert_eq!(Transaction::estimate_fee(1, 0, 0), 10000);
+    assert_eq!(Transaction::estimate_fee(1024, 1, 0), 15000);
+    assert_eq!(Transaction::estimate_fee(1025, 1, 0), 25000);
+    assert_eq!(Transaction::estimate_fee(3000, 2, 1), 140000);
+    assert_eq!(Transaction::estimate_fee(65536, 255, 255), 640000 + 1275000 + 25500000);
+
+    let mut alice = Account::new();
+    alice.balances.insert(XELIS_ASSET, Balance {
+        balance: 100 * COIN_VALUE,
+        ciphertext: CiphertextCache::Decompressed(alice.keypair.get_public_key().encrypt(100 * COIN_VALUE)),
+    });
+
+    // Built with a multiplier of 1, destination is a new address
+    let tx = create_tx_for(alice, Account::new().address(), 50, None);
+    assert_eq!(tx.get_outputs_count(), 1);
+    assert_eq!(tx.get_fee(), Transaction::estimate_fee(tx.size(), tx.get_outputs_count(), 1));
+}
+
 #[test]
 fn test_encrypt_decrypt() {
     let r = PedersenOpening::generate_new();
@@ -272,6 +296,54 @@ async fn test_burn_tx_verify() {
     tx.verify(&mut state).await.unwrap();
 }
 
+#[tokio::test]
+async fn test_amount_overflow() {
+    let mut alice = Account::new();
+    let bob = Account::new();
+    alice.set_balance(XELIS_ASSET, u64::MAX);
+
+    let mut state = AccountStateImpl {
+        balances: alice.balances.clone(),
+        nonce: alice.nonce,
+        reference: Reference {
+            topoheight: 0,
+            hash: Hash::zero(),
+        },
+    };
+
+    // Transfers + fee past u64::MAX can't be built
+    let transfer = TransferBuilder {
+        amount: u64::MAX / 2 + 1,
+        destination: bob.address(),
+        asset: XELIS_ASSET,
+        extra_data: None,
+    };
+    let data = TransactionTypeBuilder::Transfers(vec![transfer.clone(), transfer]);
+    let builder = TransactionBuilder::new(0, alice.keypair.get_public_key().compress(), data, FeeBuilder::Value(1));
+    assert!(matches!(builder.build(&mut state, &alice.keypair), Err(GenerationError::AmountOverflow(AmountOverflow))));
+
+    // Burn + fee past u64::MAX is rejected before any proof verification
+    let data = TransactionTypeBuilder::Burn(BurnPayload {
+        amount: COIN_VALUE,
+        asset: XELIS_ASSET,
+    });
+    let builder = TransactionBuilder::new(0, alice.keypair.get_public_key().compress(), data, FeeBuilder::Value(1));
+    let mut tx = builder.build(&mut state, &alice.keypair).unwrap();
+    tx.fee = u64::MAX;
+
+    let mut chain_state = ChainState {
+        accounts: HashMap::new(),
+    };
+    chain_state.accounts.insert(alice.keypair.get_public_key().compress(), AccountChainState {
+        balances: HashMap::new(),
+        nonce: alice.nonce,
+    });
+
+    assert!(matches!(tx.verify(&mut chain_state).await, Err(VerificationError::AmountOverflow(AmountOverflow))));
+    // Nonce is untouched
+    assert_eq!(chain_state.accounts.values().next().unwrap().nonce, alice.nonce);
+}
+
 #[tokio::test]
 async fn test_max_transfers() {
     let mut alice = Account::new();
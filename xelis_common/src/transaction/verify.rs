@@ -2,7 +2,7 @@ use bulletproofs::RangeProof;
 use curve25519_dalek::{ristretto::CompressedRistretto, traits::Identity, RistrettoPoint, Scalar};
 use log::{debug, trace};
 use merlin::Transcript;
-use crate::{config::XELIS_ASSET, crypto::{elgamal::{Ciphertext, CompressedPublicKey, DecompressionError, DecryptHandle, PedersenCommitment}, proofs::{BatchCollector, ProofVerificationError, BP_GENS, BULLET_PROOF_SIZE, PC_GENS}, Hash, ProtocolTranscript, SIGNATURE_SIZE}, serializer::Serializer, transaction::{EXTRA_DATA_LIMIT_SIZE, MAX_TRANSFER_COUNT}};
+use crate::{amount::AmountOverflow, config::XELIS_ASSET, crypto::{elgamal::{Ciphertext, CompressedPublicKey, DecompressionError, DecryptHandle, PedersenCommitment}, proofs::{BatchCollector, ProofVerificationError, BP_GENS, BULLET_PROOF_SIZE, PC_GENS}, Hash, ProtocolTranscript, SIGNATURE_SIZE}, serializer::Serializer, transaction::{EXTRA_DATA_LIMIT_SIZE, MAX_TRANSFER_COUNT}};
 use super::{Reference, Role, Transaction, TransactionType, TransferPayload};
 use thiserror::Error;
 use std::iter;
@@ -73,6 +73,8 @@ pub enum VerificationError<T> {
     InvalidSignature,
     #[error("Proof verification error: {0}")]
     Proof(#[from] ProofVerificationError),
+    #[error(transparent)]
+    AmountOverflow(#[from] AmountOverflow),
 }
 
 struct DecompressedTransferCt {
@@ -147,6 +149,17 @@ impl Transaction {
         transcript
     }
 
+    // Verify only the signature of the source key
+    // This doesn't require any state
+    pub fn verify_signature(&self) -> bool {
+        let Ok(owner) = self.source.decompress() else {
+            return false;
+        };
+
+        let bytes = self.to_bytes();
+        self.signature.verify(&bytes[..bytes.len() - SIGNATURE_SIZE], &owner)
+    }
+
     // Verify that the commitment assets match the assets used in the tx
     fn verify_commitment_assets(&self) -> bool {
         let has_commitment_for_asset = |asset| {
@@ -196,6 +209,9 @@ impl Transaction {
         state.pre_verify_tx(&self).await
             .map_err(VerificationError::State)?;
 
+        // Fee and burned amount must not wrap when spent together
+        self.get_plain_spent(&XELIS_ASSET)?;
+
         // First, check the nonce
         let account_nonce = state.get_account_nonce(&self.source).await
             .map_err(VerificationError::State)?;
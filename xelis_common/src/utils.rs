@@ -26,9 +26,55 @@ macro_rules! async_handler {
     };
 }
 
+// Marker added in front of a value when non-zero decimals are hidden
+pub const APPROXIMATE_MARKER: char = '≈';
+
 // Format any coin value using the requested decimals count
+// It is exact and never uses the scientific notation
 pub fn format_coin(value: u64, decimals: u8) -> String {
-    format!("{:.1$}", value as f64 / 10usize.pow(decimals as u32) as f64, decimals as usize)
+    format_coin_with_precision(value, decimals, decimals)
+}
+
+// Format a coin value showing only display_decimals decimals, the value is rounded
+// The APPROXIMATE_MARKER is added when the hidden decimals are not all zero
+// This must be used only for display, never for the exported or sent amounts
+pub fn format_coin_with_precision(value: u64, decimals: u8, display_decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let display_decimals = (display_decimals as usize).min(decimals);
+
+    // Digits of the value with at least one digit for the integer part
+    let mut digits: Vec<u8> = format!("{:0>1$}", value, decimals + 1).bytes().map(|c| c - b'0').collect();
+    let kept = digits.len() - (decimals - display_decimals);
+    let approximated = digits[kept..].iter().any(|d| *d != 0);
+    let round_up = digits.get(kept).is_some_and(|d| *d >= 5);
+    digits.truncate(kept);
+
+    if round_up {
+        // Propagate the carry, it may add a new digit to the integer part
+        match digits.iter().rposition(|d| *d != 9) {
+            Some(i) => {
+                digits[i] += 1;
+                digits[i + 1..].iter_mut().for_each(|d| *d = 0);
+            },
+            None => {
+                digits.iter_mut().for_each(|d| *d = 0);
+                digits.insert(0, 1);
+            }
+        }
+    }
+
+    let integer_len = digits.len() - display_decimals;
+    let mut formatted = String::with_capacity(digits.len() + 4);
+    if approximated {
+        formatted.push(APPROXIMATE_MARKER);
+    }
+    formatted.extend(digits[..integer_len].iter().map(|d| (b'0' + d) as char));
+    if display_decimals > 0 {
+        formatted.push('.');
+        formatted.extend(digits[integer_len..].iter().map(|d| (b'0' + d) as char));
+    }
+
+    formatted
 }
 
 // Format value using XELIS decimals
@@ -182,6 +228,35 @@ mod tests {
         assert_eq!(format_difficulty(value), "1.15G");
     }
 
+    #[test]
+    fn test_format_coin() {
+        assert_eq!(format_coin(0, 8), "0.00000000");
+        assert_eq!(format_coin(123_456_789, 8), "1.23456789");
+        assert_eq!(format_coin(42, 0), "42");
+        // No precision loss nor scientific notation with high decimals
+        assert_eq!(format_coin(u64::MAX, 8), "184467440737.09551615");
+        assert_eq!(format_coin(1, 18), "0.000000000000000001");
+        assert_eq!(format_coin(1, 30), format!("0.{}1", "0".repeat(29)));
+    }
+
+    #[test]
+    fn test_format_coin_with_precision() {
+        // 1.234567891234567891 with 18 decimals
+        let value = 1_234_567_891_234_567_891;
+        assert_eq!(format_coin_with_precision(value, 18, 6), "≈1.234568");
+        assert_eq!(format_coin_with_precision(value, 18, 18), "1.234567891234567891");
+        assert_eq!(format_coin_with_precision(value, 18, 0), "≈1");
+        // Display precision above the asset decimals is ignored
+        assert_eq!(format_coin_with_precision(150, 2, 6), "1.50");
+
+        // No marker if only zeros are hidden
+        assert_eq!(format_coin_with_precision(1_500_000, 6, 2), "1.50");
+        // Rounding carry
+        assert_eq!(format_coin_with_precision(9_999_999, 6, 2), "≈10.00");
+        assert_eq!(format_coin_with_precision(999, 18, 6), "≈0.000000");
+        assert_eq!(format_coin_with_precision(500_000_000_000, 18, 6), "≈0.000001");
+    }
+
     #[test]
     fn test_from_xelis() {
         let value = from_xelis("100.123");
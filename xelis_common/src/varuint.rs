@@ -47,6 +47,15 @@ impl VarUint {
     pub const fn one() -> Self {
         Self(U256::one())
     }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    // Used for sums of work that must never panic, U256::MAX is far above any reachable value
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
 }
 
 impl Serializer for VarUint {
@@ -124,21 +133,31 @@ impl From<VarUint> for U256 {
     }
 }
 
+// Conversions to smaller integers saturate instead of panicking
 impl From<VarUint> for u128 {
     fn from(c: VarUint) -> u128 {
-        c.0.as_u128()
+        if c.0.bits() > 128 {
+            u128::MAX
+        } else {
+            c.0.as_u128()
+        }
     }
 }
 
 impl From<VarUint> for u64 {
     fn from(c: VarUint) -> u64 {
-        c.0.as_u64()
+        if c.0.bits() > 64 {
+            u64::MAX
+        } else {
+            c.0.as_u64()
+        }
     }
 }
 
 impl From<VarUint> for f64 {
     fn from(c: VarUint) -> f64 {
-        c.0.as_u128() as f64
+        // Sum each 64 bits limb to support the full range
+        c.0.0.iter().rev().fold(0f64, |acc, limb| acc * 18446744073709551616f64 + *limb as f64)
     }
 }
 
@@ -244,10 +263,21 @@ impl Serialize for VarUint {
     }
 }
 
+// Values are always serialized as a string to not lose precision in JavaScript clients
+// but a number is still accepted for values sent the old way
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VarUintValue {
+    Number(u64),
+    String(String)
+}
+
 impl <'de> Deserialize<'de> for VarUint {
     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let s = String::deserialize(deserializer)?;
-        Ok(VarUint::new(U256::from_dec_str(&s).map_err(serde::de::Error::custom)?))
+        match VarUintValue::deserialize(deserializer)? {
+            VarUintValue::Number(n) => Ok(VarUint::from_u64(n)),
+            VarUintValue::String(s) => Ok(VarUint::new(U256::from_dec_str(&s).map_err(serde::de::Error::custom)?))
+        }
     }
 }
 
@@ -297,4 +327,39 @@ mod tests {
         assert_eq!(compact.as_ref(), compact2.as_ref());
         assert!(json.contains("123456"));
     }
+
+    #[test]
+    fn test_json_above_u64() {
+        let value = VarUint::from_u128(u128::MAX);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, format!("\"{}\"", u128::MAX));
+        assert_eq!(serde_json::from_str::<VarUint>(&json).unwrap(), value);
+
+        // Numbers are still accepted
+        assert_eq!(serde_json::from_str::<VarUint>("42").unwrap(), VarUint::from_u64(42));
+    }
+
+    #[test]
+    fn test_previous_encoding() {
+        // Value encoded when it was fitting in a u64
+        let value = VarUint::from_u64(u64::MAX);
+        let bytes = value.to_bytes();
+        let previous = VarUint::read(&mut Reader::new(&bytes)).unwrap();
+        assert_eq!(previous, value);
+        assert_eq!(u64::from(previous), u64::MAX);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let above_u128 = VarUint::from_u128(u128::MAX) + VarUint::one();
+        assert!(above_u128 > VarUint::from_u128(u128::MAX));
+        assert_eq!(u128::from(above_u128), u128::MAX);
+        assert_eq!(u64::from(VarUint::from_u128(u128::MAX)), u64::MAX);
+        assert_eq!(f64::from(above_u128), 2f64.powi(128));
+
+        let max = VarUint::new(U256::max_value());
+        assert_eq!(max.checked_add(VarUint::one()), None);
+        assert_eq!(max.saturating_add(VarUint::one()), max);
+        assert_eq!(VarUint::one().checked_add(VarUint::one()), Some(VarUint::from_u64(2)));
+    }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use lazy_static::lazy_static;
 use xelis_common::{
     api::daemon::DevFeeThreshold,
+    config::FEE_PER_KB,
     crypto::{
         Address,
         Hash,
@@ -22,11 +23,83 @@ pub const DEFAULT_RPC_BIND_ADDRESS: &str = "0.0.0.0:8080";
 // Default cache size for storage DB
 pub const DEFAULT_CACHE_SIZE: usize = 1024;
 
+// Default maximum size of all TXs in mempool (256 MB)
+pub const DEFAULT_MEMPOOL_MAX_SIZE_BYTES: usize = 256 * 1024 * 1024;
+
+// Default time in seconds before an unconfirmed TX is removed from mempool (6 hours)
+pub const DEFAULT_MEMPOOL_TX_TTL: u64 = 6 * 60 * 60;
+// Default minimum fee increase in percent to replace a TX in mempool
+pub const DEFAULT_MEMPOOL_REPLACEMENT_FEE_INCREMENT: u64 = 10;
+// Default minimum fee rate per kB to relay a TX, same as the consensus rule
+pub const DEFAULT_MIN_RELAY_FEE_RATE: u64 = FEE_PER_KB;
+// Default window in ms to coalesce the fee policy and block template events
+pub const DEFAULT_RPC_EVENTS_COALESCE_WINDOW: u64 = 1000;
+// Default maximum blocks returned by a single get_blocks_range request
+pub const DEFAULT_RPC_MAX_BLOCKS_RANGE: u64 = 100;
+// Interval in seconds between each check for expired TXs
+pub const MEMPOOL_EXPIRATION_INTERVAL: u64 = 60;
+
+// Default interval in seconds between two evaluations of the alert rules
+pub const DEFAULT_ALERT_INTERVAL: u64 = 30;
+// Default minimum delay in seconds between two notifications of the same alert
+pub const DEFAULT_ALERT_COOLDOWN: u64 = 15 * 60;
+// Default minutes the peer count must stay below the minimum before the alert
+pub const DEFAULT_ALERT_MIN_PEERS_MINUTES: u64 = 5;
+// Timeout in milliseconds of the webhook request sent for an alert
+pub const ALERT_WEBHOOK_TIMEOUT: u64 = 5_000;
+// Timeout in milliseconds of the command executed for an alert
+pub const ALERT_EXEC_TIMEOUT: u64 = 10_000;
+
+// Count of recent blocks whose miner balances are loaded during the warm-up
+pub const WARM_UP_RECENT_BLOCKS: u64 = 32;
+// Metadata registered with the native asset
+pub const XELIS_ASSET_NAME: &str = "XELIS";
+pub const XELIS_ASSET_TICKER: &str = "XEL";
+
+// Scratch pads kept allocated to verify the PoW of blocks and getwork shares
+pub const POW_SCRATCH_PADS: usize = 4;
+
+// Default count of recent blocks aggregated in the fee and size histograms
+pub const DEFAULT_FEE_HISTOGRAM_WINDOW: u64 = 100;
+
+// Default and maximum count of recent blocks used by the chain stats
+pub const DEFAULT_STATS_BLOCKS: u64 = 100;
+pub const MAX_STATS_BLOCKS: u64 = 1000;
+
+// Maximum blocks above the current height projected by get_block_reward_at (about one year)
+pub const MAX_BLOCK_REWARD_PROJECTION: u64 = 365 * 24 * 60 * 60 * MILLIS_PER_SECOND / BLOCK_TIME_MILLIS;
+
+// Maximum sub-queries in a single get_many request
+pub const MAX_GET_MANY_QUERIES: usize = 16;
+
+// Threads used to serialize the balances of a block before its commit
+pub const COMMIT_WORKERS: usize = 4;
+// Below this amount of balances changed, they are serialized on the current thread
+pub const PARALLEL_COMMIT_THRESHOLD: usize = 64;
+
+// Version of the snapshot file format
+pub const SNAPSHOT_FORMAT_VERSION: u8 = 1;
+// Log the snapshot export/import progress every N blocks
+pub const SNAPSHOT_PROGRESS_INTERVAL: u64 = 10_000;
+// Version of the replay file format used by export_blocks and --replay
+pub const REPLAY_FORMAT_VERSION: u8 = 1;
+
+// Lowest share difficulty that can be set for a getwork miner
+pub const GETWORK_MIN_SHARE_DIFFICULTY: Difficulty = Difficulty::from_u64(1000);
+// Initial share difficulty is the network difficulty divided by this factor
+pub const GETWORK_INITIAL_SHARE_DIFFICULTY_DIVISOR: u64 = 1000;
+// Minimum delay in ms between two retargets of the share difficulty
+pub const GETWORK_SHARE_RETARGET_DELAY: u64 = 30 * MILLIS_PER_SECOND;
+// Maximum factor applied to the share difficulty at each retarget
+pub const GETWORK_SHARE_RETARGET_MAX_FACTOR: u64 = 4;
+
 // Block rules
 // Millis per second, it is used to prevent having random 1000 values anywhere
 pub const MILLIS_PER_SECOND: u64 = 1000;
 // Block Time in milliseconds
 pub const BLOCK_TIME_MILLIS: u64 = 15 * MILLIS_PER_SECOND; // 15s block time
+// Blocks used by the average block time and the hashrate estimate
+pub const BLOCK_TIME_AVERAGE_WINDOW: u64 = 50;
 // Minimum difficulty (each difficulty point is in H/s)
 // Current: BLOCK TIME in millis * 20 = 20 KH/s minimum
 // This is to prevent spamming the network with low difficulty blocks
@@ -84,9 +157,6 @@ pub const SIDE_BLOCK_REWARD_MAX_BLOCKS: u64 = 3;
 // minimum 5% of block reward for side block
 // This is the minimum given for all others valid side blocks
 pub const SIDE_BLOCK_REWARD_MIN_PERCENT: u64 = 5;
-// Emission speed factor for the emission curve
-// It is used to calculate based on the supply the block reward
-pub const EMISSION_SPEED_FACTOR: u64 = 20;
 
 // Developer address for paying dev fees until Smart Contracts integration
 // (testnet/mainnet format is converted lazily later)
@@ -119,8 +189,24 @@ pub const P2P_PING_PEER_LIST_DELAY: u64 = 60 * 5;
 pub const P2P_PING_PEER_LIST_LIMIT: usize = 16;
 // default number of maximum peers
 pub const P2P_DEFAULT_MAX_PEERS: usize = 32;
+// default number of slots reserved to priority peers
+pub const P2P_DEFAULT_PRIORITY_SLOTS: usize = 0;
 // time in seconds between each time we try to connect to a new peer
 pub const P2P_EXTEND_PEERLIST_DELAY: u64 = 60;
+// Delay in seconds between each summary of the versions used by peers
+pub const P2P_VERSION_STATS_DELAY: u64 = 30 * 60;
+// Stored peers seen during this time in seconds are included in the version stats
+pub const PEER_VERSION_STATS_RECENT_TIME: u64 = 24 * 60 * 60;
+// Default incoming connections allowed per minute from the same IP
+pub const P2P_DEFAULT_CONNECTION_RATE_LIMIT: u64 = 30;
+// Default burst of incoming connections allowed from the same IP
+pub const P2P_DEFAULT_CONNECTION_RATE_BURST: u64 = 10;
+// Default maximum size of a P2P message, a full block must always fit in it
+pub const P2P_DEFAULT_MAX_MESSAGE_SIZE: u32 = PEER_MAX_PACKET_SIZE;
+// Lowest maximum size of a P2P message allowed, handshakes and pings must always fit in it
+pub const P2P_MIN_MAX_MESSAGE_SIZE: u32 = 64 * 1024;
+// Default fraction of peers running a newer version to suggest an upgrade
+pub const DEFAULT_UPGRADE_NUDGE_RATIO: f64 = 0.5;
 // Peer wait on error accept new p2p connections in seconds
 pub const P2P_PEER_WAIT_ON_ERROR: u64 = 15;
 // Delay in second to connect to priority nodes
@@ -139,6 +225,10 @@ pub const P2P_PING_TIMEOUT: u64 = P2P_PING_DELAY * 6;
 pub const PEER_FAIL_TIME_RESET: u64 = 30 * 60;
 // number of fail to disconnect the peer
 pub const PEER_FAIL_LIMIT: u8 = 50;
+// Eviction score added for each fail, in seconds without useful data
+pub const PEER_EVICTION_FAIL_PENALTY: u64 = 60;
+// Latency in ms counted as one second without useful data in the eviction score
+pub const PEER_EVICTION_LATENCY_DIVISOR: u64 = 100;
 // number of fail during handshake before temp ban
 pub const PEER_FAIL_TO_CONNECT_LIMIT: u8 = 3;
 // number of seconds to temp ban the peer in case of fail reached
@@ -147,8 +237,17 @@ pub const PEER_TEMP_BAN_TIME: u64 = 15 * 60;
 // number of seconds to temp ban the peer in case of fail reached during handshake
 // Set to 1 minute
 pub const PEER_TEMP_BAN_TIME_ON_CONNECT: u64 = 60;
+// Penalties added to the fail count of a peer on protocol violations
+// Peer sent a block with an invalid POW
+pub const PEER_PENALTY_INVALID_POW: u8 = 25;
+// Peer sent a packet that couldn't be deserialized
+pub const PEER_PENALTY_INVALID_PACKET: u8 = 10;
+// Peer sent us data we didn't request
+pub const PEER_PENALTY_UNREQUESTED_DATA: u8 = 5;
 // millis until we timeout
 pub const PEER_TIMEOUT_REQUEST_OBJECT: u64 = 15_000;
+// blocks requested at the same time during a sync
+pub const DEFAULT_SYNC_PARALLEL_REQUESTS: usize = 8;
 // millis until we timeout during a bootstrap request
 pub const PEER_TIMEOUT_BOOTSTRAP_STEP: u64 = 60_000;
 // millis until we timeout during a handshake
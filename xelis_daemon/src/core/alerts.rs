@@ -0,0 +1,488 @@
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    time::Duration
+};
+use async_trait::async_trait;
+use human_bytes::human_bytes;
+use log::{debug, error, info, warn};
+use tokio::{
+    io::AsyncWriteExt,
+    process::Command,
+    sync::Mutex,
+    time::timeout
+};
+use xelis_common::{
+    api::daemon::{ActiveAlert, AlertKind, AlertNotification, AlertStatus},
+    time::{get_current_time_in_millis, TimestampMillis}
+};
+use crate::config::{ALERT_EXEC_TIMEOUT, ALERT_WEBHOOK_TIMEOUT};
+use super::{blockchain::Blockchain, storage::Storage};
+
+// Env variable containing the kind of the alert
+pub const ALERT_KIND_ENV: &str = "XELIS_ALERT_KIND";
+// Env variable containing the status of the alert (firing or resolved)
+pub const ALERT_STATUS_ENV: &str = "XELIS_ALERT_STATUS";
+
+// Snapshot of the node state on which the rules are evaluated
+#[derive(Debug, Clone, Default)]
+pub struct NodeStatus {
+    pub topoheight: u64,
+    pub peers: usize,
+    // None if it couldn't be read
+    pub disk_free_bytes: Option<u64>,
+    pub mempool_size_bytes: usize
+}
+
+// Source of the node status, simulated in tests
+#[async_trait]
+pub trait StatusCollector: Send + Sync {
+    async fn collect_status(&self) -> NodeStatus;
+}
+
+#[async_trait]
+impl<S: Storage> StatusCollector for Blockchain<S> {
+    async fn collect_status(&self) -> NodeStatus {
+        let peers = match self.get_p2p().read().await.as_ref() {
+            Some(p2p) => p2p.get_peer_count().await,
+            None => 0
+        };
+
+        let mempool_size_bytes = self.get_mempool().read().await.size_bytes();
+        let disk_free_bytes = match self.get_alerts() {
+            Some(alerts) => match fs2::available_space(&alerts.disk_path) {
+                Ok(space) => Some(space),
+                Err(e) => {
+                    debug!("Error while reading free disk space of {}: {}", alerts.disk_path, e);
+                    None
+                }
+            },
+            None => None
+        };
+
+        NodeStatus {
+            topoheight: self.get_topo_height(),
+            peers,
+            disk_free_bytes,
+            mempool_size_bytes
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AlertRule {
+    // No new topoheight for this duration
+    NoNewBlock { max_delay: u64 },
+    // Less peers than the minimum for this duration
+    LowPeerCount { min_peers: usize, delay: u64 },
+    LowDiskSpace { min_free_bytes: u64 },
+    MempoolFull { max_size_bytes: usize }
+}
+
+impl AlertRule {
+    pub fn get_kind(&self) -> AlertKind {
+        match self {
+            Self::NoNewBlock { .. } => AlertKind::NoNewBlock,
+            Self::LowPeerCount { .. } => AlertKind::LowPeerCount,
+            Self::LowDiskSpace { .. } => AlertKind::LowDiskSpace,
+            Self::MempoolFull { .. } => AlertKind::MempoolFull
+        }
+    }
+
+    // Time in milliseconds the condition must be met before firing the alert
+    fn get_delay(&self) -> u64 {
+        match self {
+            Self::LowPeerCount { delay, .. } => *delay,
+            _ => 0
+        }
+    }
+
+    // Errors are for the alerts on which the node can't work correctly
+    fn is_critical(&self) -> bool {
+        matches!(self, Self::NoNewBlock { .. } | Self::LowDiskSpace { .. })
+    }
+
+    fn build_message(&self, value: u64) -> String {
+        match self {
+            Self::NoNewBlock { max_delay } => format!("No new block ordered for {}s (limit: {}s)", value / 1000, max_delay / 1000),
+            Self::LowPeerCount { min_peers, .. } => format!("Only {} peers connected (minimum: {})", value, min_peers),
+            Self::LowDiskSpace { min_free_bytes } => format!("Only {} free on disk (minimum: {})", human_bytes(value as f64), human_bytes(*min_free_bytes as f64)),
+            Self::MempoolFull { max_size_bytes } => format!("Mempool size is {} (maximum: {})", human_bytes(value as f64), human_bytes(*max_size_bytes as f64))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AlertState {
+    // When the condition started to be met
+    condition_since: Option<TimestampMillis>,
+    // When the alert was fired
+    active_since: Option<TimestampMillis>,
+    // Last firing notification sent
+    last_notified: Option<TimestampMillis>,
+    // Firing notification sent for the current activation
+    notified: bool,
+    value: u64,
+    threshold: u64
+}
+
+// Evaluate the rules on each node status
+// It only decides which notifications to send
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    // Minimum delay between two firing notifications of the same alert
+    cooldown: u64,
+    states: HashMap<AlertKind, AlertState>,
+    // Last topoheight seen and since when
+    last_topoheight: Option<(u64, TimestampMillis)>
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>, cooldown: u64) -> Self {
+        Self {
+            rules,
+            cooldown,
+            states: HashMap::new(),
+            last_topoheight: None
+        }
+    }
+
+    pub fn has_rules(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    // Returns if the condition of the rule is met, with the value and threshold compared
+    // None if the value is unknown, the alert state is kept as is
+    fn check(&self, rule: &AlertRule, status: &NodeStatus, now: TimestampMillis) -> Option<(bool, u64, u64)> {
+        Some(match rule {
+            AlertRule::NoNewBlock { max_delay } => {
+                let elapsed = self.last_topoheight.map_or(0, |(_, since)| now.saturating_sub(since));
+                (elapsed >= *max_delay, elapsed, *max_delay)
+            },
+            AlertRule::LowPeerCount { min_peers, .. } => (status.peers < *min_peers, status.peers as u64, *min_peers as u64),
+            AlertRule::LowDiskSpace { min_free_bytes } => {
+                let free = status.disk_free_bytes?;
+                (free < *min_free_bytes, free, *min_free_bytes)
+            },
+            AlertRule::MempoolFull { max_size_bytes } => (status.mempool_size_bytes > *max_size_bytes, status.mempool_size_bytes as u64, *max_size_bytes as u64)
+        })
+    }
+
+    pub fn evaluate(&mut self, status: &NodeStatus, now: TimestampMillis) -> Vec<AlertNotification> {
+        if self.last_topoheight.map_or(true, |(topoheight, _)| topoheight != status.topoheight) {
+            self.last_topoheight = Some((status.topoheight, now));
+        }
+
+        let mut notifications = Vec::new();
+        for rule in self.rules.iter() {
+            let Some((met, value, threshold)) = self.check(rule, status, now) else {
+                continue;
+            };
+            let state = self.states.entry(rule.get_kind()).or_default();
+            state.value = value;
+            state.threshold = threshold;
+
+            let alert_status = if met {
+                let since = *state.condition_since.get_or_insert(now);
+                if now.saturating_sub(since) < rule.get_delay() {
+                    continue;
+                }
+
+                state.active_since.get_or_insert(now);
+                // Also sent again as a reminder while the alert is active
+                if state.last_notified.is_some_and(|last| now.saturating_sub(last) < self.cooldown) {
+                    continue;
+                }
+                state.last_notified = Some(now);
+                state.notified = true;
+                AlertStatus::Firing
+            } else {
+                state.condition_since = None;
+                // No resolve notification if the firing one was skipped
+                if state.active_since.take().is_none() || !state.notified {
+                    continue;
+                }
+                state.notified = false;
+                AlertStatus::Resolved
+            };
+
+            let message = match alert_status {
+                AlertStatus::Firing => rule.build_message(value),
+                AlertStatus::Resolved => format!("Resolved: {}", rule.build_message(value))
+            };
+
+            notifications.push(AlertNotification {
+                kind: rule.get_kind(),
+                status: alert_status,
+                value,
+                threshold,
+                message,
+                timestamp: now
+            });
+        }
+
+        notifications
+    }
+
+    pub fn get_active_alerts(&self) -> Vec<ActiveAlert> {
+        let mut alerts = Vec::new();
+        for rule in self.rules.iter() {
+            let kind = rule.get_kind();
+            let Some(state) = self.states.get(&kind) else {
+                continue;
+            };
+
+            if let Some(since) = state.active_since {
+                alerts.push(ActiveAlert {
+                    kind,
+                    since,
+                    value: state.value,
+                    threshold: state.threshold,
+                    message: rule.build_message(state.value),
+                    notified: state.notified
+                });
+            }
+        }
+
+        alerts
+    }
+
+    fn is_critical(&self, kind: AlertKind) -> bool {
+        self.rules.iter().any(|rule| rule.get_kind() == kind && rule.is_critical())
+    }
+}
+
+// Evaluate the rules periodically and send the notifications
+// through the log, the prompt, the webhook and the exec hook
+pub struct AlertManager {
+    engine: Mutex<AlertEngine>,
+    // Directory used to check the free disk space
+    disk_path: String,
+    webhook_url: Option<String>,
+    exec_command: Option<String>
+}
+
+impl AlertManager {
+    pub fn new(engine: AlertEngine, disk_path: String, webhook_url: Option<String>, exec_command: Option<String>) -> Self {
+        Self {
+            engine: Mutex::new(engine),
+            disk_path,
+            webhook_url,
+            exec_command
+        }
+    }
+
+    // Collect the status and send the notifications of the alerts fired or resolved
+    pub async fn evaluate<C: StatusCollector + ?Sized>(&self, collector: &C, now: TimestampMillis) -> Vec<AlertNotification> {
+        let status = collector.collect_status().await;
+        let (notifications, critical) = {
+            let mut engine = self.engine.lock().await;
+            let notifications = engine.evaluate(&status, now);
+            let critical: Vec<bool> = notifications.iter().map(|n| engine.is_critical(n.kind)).collect();
+            (notifications, critical)
+        };
+
+        for (notification, critical) in notifications.iter().zip(critical) {
+            self.notify(notification, critical).await;
+        }
+
+        notifications
+    }
+
+    pub async fn tick<C: StatusCollector + ?Sized>(&self, collector: &C) {
+        self.evaluate(collector, get_current_time_in_millis()).await;
+    }
+
+    pub async fn get_active_alerts(&self) -> Vec<ActiveAlert> {
+        self.engine.lock().await.get_active_alerts()
+    }
+
+    async fn notify(&self, notification: &AlertNotification, critical: bool) {
+        match notification.status {
+            AlertStatus::Firing if critical => error!("Alert {:?}: {}", notification.kind, notification.message),
+            AlertStatus::Firing => warn!("Alert {:?}: {}", notification.kind, notification.message),
+            AlertStatus::Resolved => info!("Alert {:?}: {}", notification.kind, notification.message)
+        };
+
+        if let Some(url) = self.webhook_url.as_ref() {
+            if let Err(e) = send_webhook(url, notification).await {
+                warn!("Error while sending alert to webhook {}: {}", url, e);
+            }
+        }
+
+        if let Some(command) = self.exec_command.as_ref() {
+            if let Err(e) = run_exec_hook(command, notification).await {
+                warn!("Error while running alert command {}: {}", command, e);
+            }
+        }
+    }
+}
+
+async fn send_webhook(url: &str, notification: &AlertNotification) -> Result<(), anyhow::Error> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(ALERT_WEBHOOK_TIMEOUT))
+        .build()?;
+
+    client.post(url)
+        .json(notification)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+// Execute the command with the notification as JSON on stdin
+async fn run_exec_hook(command: &str, notification: &AlertNotification) -> Result<(), anyhow::Error> {
+    let mut child = Command::new(command)
+        .env(ALERT_KIND_ENV, serde_json::to_value(notification.kind)?.as_str().unwrap_or_default())
+        .env(ALERT_STATUS_ENV, serde_json::to_value(notification.status)?.as_str().unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let bytes = serde_json::to_vec(notification)?;
+    let res = timeout(Duration::from_millis(ALERT_EXEC_TIMEOUT), async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The command may not read its stdin
+            if let Err(e) = stdin.write_all(&bytes).await {
+                debug!("Error while writing alert to command {}: {}", command, e);
+            }
+        }
+        child.wait().await
+    }).await;
+
+    match res {
+        Ok(status) => {
+            let status = status?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("exit code {:?}", status.code()))
+            }
+            Ok(())
+        },
+        Err(_) => {
+            child.kill().await?;
+            Err(anyhow::anyhow!("timed out"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+    use super::*;
+
+    // Status set by the test
+    struct SimulatedCollector(StdMutex<NodeStatus>);
+
+    impl SimulatedCollector {
+        fn new() -> Self {
+            Self(StdMutex::new(NodeStatus {
+                topoheight: 0,
+                peers: 8,
+                disk_free_bytes: Some(10_000),
+                mempool_size_bytes: 0
+            }))
+        }
+
+        fn update(&self, f: impl FnOnce(&mut NodeStatus)) {
+            f(&mut self.0.lock().unwrap());
+        }
+    }
+
+    #[async_trait]
+    impl StatusCollector for SimulatedCollector {
+        async fn collect_status(&self) -> NodeStatus {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn manager(rule: AlertRule, cooldown: u64) -> AlertManager {
+        AlertManager::new(AlertEngine::new(vec![rule], cooldown), String::new(), None, None)
+    }
+
+    fn statuses(notifications: &[AlertNotification]) -> Vec<AlertStatus> {
+        notifications.iter().map(|n| n.status).collect()
+    }
+
+    #[tokio::test]
+    async fn test_no_new_block_alert() {
+        let collector = SimulatedCollector::new();
+        let alerts = manager(AlertRule::NoNewBlock { max_delay: 60_000 }, 0);
+
+        assert!(alerts.evaluate(&collector, 0).await.is_empty());
+        assert!(alerts.evaluate(&collector, 59_999).await.is_empty());
+        let fired = alerts.evaluate(&collector, 60_000).await;
+        assert_eq!(statuses(&fired), [AlertStatus::Firing]);
+        assert_eq!(fired[0].kind, AlertKind::NoNewBlock);
+        assert_eq!(alerts.get_active_alerts().await.len(), 1);
+
+        collector.update(|status| status.topoheight = 1);
+        assert_eq!(statuses(&alerts.evaluate(&collector, 61_000).await), [AlertStatus::Resolved]);
+        assert!(alerts.get_active_alerts().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_low_peer_count_alert() {
+        let collector = SimulatedCollector::new();
+        let alerts = manager(AlertRule::LowPeerCount { min_peers: 4, delay: 30_000 }, 0);
+
+        collector.update(|status| status.peers = 2);
+        assert!(alerts.evaluate(&collector, 0).await.is_empty());
+        // The condition must be met during the whole delay
+        collector.update(|status| status.peers = 5);
+        assert!(alerts.evaluate(&collector, 10_000).await.is_empty());
+        collector.update(|status| status.peers = 2);
+        assert!(alerts.evaluate(&collector, 20_000).await.is_empty());
+        assert!(alerts.evaluate(&collector, 40_000).await.is_empty());
+        assert_eq!(statuses(&alerts.evaluate(&collector, 50_000).await), [AlertStatus::Firing]);
+
+        collector.update(|status| status.peers = 4);
+        assert_eq!(statuses(&alerts.evaluate(&collector, 60_000).await), [AlertStatus::Resolved]);
+    }
+
+    #[tokio::test]
+    async fn test_low_disk_space_alert() {
+        let collector = SimulatedCollector::new();
+        let alerts = manager(AlertRule::LowDiskSpace { min_free_bytes: 5_000 }, 0);
+
+        assert!(alerts.evaluate(&collector, 0).await.is_empty());
+        collector.update(|status| status.disk_free_bytes = Some(4_000));
+        let fired = alerts.evaluate(&collector, 1_000).await;
+        assert_eq!(statuses(&fired), [AlertStatus::Firing]);
+        assert_eq!((fired[0].value, fired[0].threshold), (4_000, 5_000));
+
+        // Unknown free space doesn't resolve the alert
+        collector.update(|status| status.disk_free_bytes = None);
+        assert!(alerts.evaluate(&collector, 2_000).await.is_empty());
+        assert_eq!(alerts.get_active_alerts().await.len(), 1);
+        collector.update(|status| status.disk_free_bytes = Some(6_000));
+        assert_eq!(statuses(&alerts.evaluate(&collector, 3_000).await), [AlertStatus::Resolved]);
+    }
+
+    #[tokio::test]
+    async fn test_mempool_alert_cooldown() {
+        let collector = SimulatedCollector::new();
+        let alerts = manager(AlertRule::MempoolFull { max_size_bytes: 1_000 }, 60_000);
+
+        collector.update(|status| status.mempool_size_bytes = 2_000);
+        assert_eq!(statuses(&alerts.evaluate(&collector, 0).await), [AlertStatus::Firing]);
+        // Still active, reminded only after the cooldown
+        assert!(alerts.evaluate(&collector, 30_000).await.is_empty());
+        assert_eq!(statuses(&alerts.evaluate(&collector, 60_000).await), [AlertStatus::Firing]);
+
+        collector.update(|status| status.mempool_size_bytes = 0);
+        assert_eq!(statuses(&alerts.evaluate(&collector, 70_000).await), [AlertStatus::Resolved]);
+
+        // Fired again during the cooldown: no notification at all
+        collector.update(|status| status.mempool_size_bytes = 2_000);
+        assert!(alerts.evaluate(&collector, 80_000).await.is_empty());
+        let active = alerts.get_active_alerts().await;
+        assert_eq!(active.len(), 1);
+        assert!(!active[0].notified);
+        collector.update(|status| status.mempool_size_bytes = 0);
+        assert!(alerts.evaluate(&collector, 90_000).await.is_empty());
+    }
+}
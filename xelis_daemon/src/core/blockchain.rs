@@ -3,26 +3,37 @@ use indexmap::IndexSet;
 use lru::LruCache;
 use serde_json::{Value, json};
 use xelis_common::{
+    amount::checked_add,
     api::{
         daemon::{
+            AddressTransactionType,
             BlockOrderedEvent,
             BlockOrphanedEvent,
             BlockType,
+            EstimateFeesResult,
+            FeePolicy,
+            FeePolicyUpdatedEvent,
+            GetStatsResult,
             NotifyEvent,
             StableHeightChangedEvent,
+            SubmitBlockStatus,
+            MempoolRemovalReason,
             TransactionExecutedEvent,
+            TransactionRemovedFromMempoolEvent,
             TransactionResponse
         },
         RPCTransaction
     },
-    asset::AssetData,
+    asset::{AssetData, AssetMetadata},
     block::{
+        write_aux_commitment,
         Block,
         BlockHeader,
         EXTRA_NONCE_SIZE
     },
     config::{
         COIN_DECIMALS,
+        FEE_PER_KB,
         MAXIMUM_SUPPLY,
         MAX_TRANSACTION_SIZE,
         TIPS_LIMIT,
@@ -32,9 +43,12 @@ use xelis_common::{
         Hash,
         Hashable,
         PublicKey,
+        ScratchPadPool,
+        XelisHashError,
         HASH_SIZE
     },
     difficulty::{check_difficulty, CumulativeDifficulty, Difficulty},
+    emission,
     immutable::Immutable,
     network::Network,
     serializer::Serializer,
@@ -44,28 +58,38 @@ use xelis_common::{
         TimestampMillis
     },
     transaction::{verify::BlockchainVerificationState, Transaction, TransactionType},
-    utils::{calculate_tx_fee, format_xelis, spawn_task},
+    utils::{format_xelis, spawn_task},
     varuint::VarUint
 };
 use crate::{
     config::{
         get_genesis_block_hash, get_hex_genesis_block, get_minimum_difficulty,
         BLOCK_TIME_MILLIS, CHAIN_SYNC_RESPONSE_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MIN_BLOCKS,
-        DEFAULT_CACHE_SIZE, DEFAULT_P2P_BIND_ADDRESS, DEFAULT_RPC_BIND_ADDRESS, DEV_FEES,
-        DEV_PUBLIC_KEY, EMISSION_SPEED_FACTOR, GENESIS_BLOCK_DIFFICULTY, MAX_BLOCK_SIZE,
-        MILLIS_PER_SECOND, P2P_DEFAULT_MAX_PEERS, SIDE_BLOCK_REWARD_MAX_BLOCKS, PRUNE_SAFETY_LIMIT,
+        DEFAULT_CACHE_SIZE, DEFAULT_FEE_HISTOGRAM_WINDOW, DEFAULT_MEMPOOL_MAX_SIZE_BYTES, DEFAULT_P2P_BIND_ADDRESS, DEFAULT_RPC_BIND_ADDRESS, DEV_FEES,
+        DEV_PUBLIC_KEY, GENESIS_BLOCK_DIFFICULTY, MAX_BLOCK_SIZE,
+        P2P_DEFAULT_MAX_PEERS, P2P_DEFAULT_PRIORITY_SLOTS, SIDE_BLOCK_REWARD_MAX_BLOCKS, PRUNE_SAFETY_LIMIT,
         SIDE_BLOCK_REWARD_PERCENT, SIDE_BLOCK_REWARD_MIN_PERCENT, STABLE_LIMIT, TIMESTAMP_IN_FUTURE_LIMIT,
-        P2P_DEFAULT_CONCURRENCY_TASK_COUNT_LIMIT
+        P2P_DEFAULT_CONCURRENCY_TASK_COUNT_LIMIT, DEFAULT_UPGRADE_NUDGE_RATIO,
+        P2P_DEFAULT_CONNECTION_RATE_LIMIT, P2P_DEFAULT_CONNECTION_RATE_BURST, P2P_DEFAULT_MAX_MESSAGE_SIZE,
+        PEER_TIMEOUT_REQUEST_OBJECT, DEFAULT_SYNC_PARALLEL_REQUESTS, DEFAULT_MEMPOOL_TX_TTL, DEFAULT_MEMPOOL_REPLACEMENT_FEE_INCREMENT, MEMPOOL_EXPIRATION_INTERVAL,
+        DEFAULT_MIN_RELAY_FEE_RATE,
+        DEFAULT_RPC_EVENTS_COALESCE_WINDOW,
+        DEFAULT_RPC_MAX_BLOCKS_RANGE,
+        WARM_UP_RECENT_BLOCKS, POW_SCRATCH_PADS, XELIS_ASSET_NAME, XELIS_ASSET_TICKER, MAX_STATS_BLOCKS, BLOCK_TIME_AVERAGE_WINDOW, MILLIS_PER_SECOND,
+        DEFAULT_ALERT_INTERVAL, DEFAULT_ALERT_COOLDOWN, DEFAULT_ALERT_MIN_PEERS_MINUTES
     },
     core::{
+        alerts::{AlertEngine, AlertManager, AlertRule},
         blockdag,
         difficulty,
         error::BlockchainError,
-        mempool::Mempool,
+        fee_histogram::{BlockHistograms, FeeHistogram},
+        mempool::{get_fee_rate, Mempool, SortedTx},
         nonce_checker::NonceChecker,
         simulator::Simulator,
         storage::{DagOrderProvider, DifficultyProvider, Storage},
         tx_selector::{TxSelector, TxSelectorEntry},
+        tx_policy::TxPolicy,
         state::{ChainState, ApplicableChainState},
     },
     p2p::P2pServer,
@@ -74,6 +98,7 @@ use crate::{
             get_block_type_for_block,
             get_block_response
         },
+        AdminCredentials,
         DaemonRpcServer,
         SharedDaemonRpcServer
     }
@@ -89,15 +114,19 @@ use std::{
     net::SocketAddr,
     num::NonZeroUsize,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc
     },
-    time::Instant
+    time::{Duration, Instant}
+};
+use tokio::{
+    sync::{Mutex, RwLock},
+    time::interval
 };
-use tokio::sync::{Mutex, RwLock};
 use log::{info, error, debug, warn, trace};
 use rand::Rng;
 
+use super::{replay, snapshot};
 use super::storage::{
     BlocksAtHeightProvider,
     ClientProtocolProvider,
@@ -110,15 +139,89 @@ pub struct Config {
     /// Optional node tag
     #[clap(long)]
     pub tag: Option<String>,
-    /// P2p bind address to listen for incoming connections
+    /// P2p bind addresses to listen for incoming connections, separated by a comma
+    /// [::]:2125 listens on both IPv4 and IPv6
     #[clap(long, default_value_t = String::from(DEFAULT_P2P_BIND_ADDRESS))]
     pub p2p_bind_address: String,
     /// Number of maximums peers allowed
     #[clap(long, default_value_t = P2P_DEFAULT_MAX_PEERS)]
     pub max_peers: usize,
+    /// Maximum connections we open to non-priority peers, by default the max peers.
+    #[clap(long)]
+    pub max_outgoing_peers: Option<usize>,
+    /// Slots reserved to the priority peers (seed, exclusive and priority nodes) within the max peers.
+    /// Other peers can't use them, so priority peers can always connect even if the node is busy.
+    #[clap(long, default_value_t = P2P_DEFAULT_PRIORITY_SLOTS)]
+    pub priority_peer_slots: usize,
+    /// Evict the worst peer (long time without useful data, fails and latency) when the peer list is full
+    /// and a peer with a better reputation tries to connect.
+    /// While syncing, a peer ahead of us can always evict a peer behind it.
+    #[clap(long)]
+    pub p2p_evict_peers: bool,
+    /// Fraction of peers (between 0 and 1) running a newer minor/major version
+    /// required to suggest an upgrade of the node. Set to 0 to disable it.
+    #[clap(long, default_value_t = DEFAULT_UPGRADE_NUDGE_RATIO)]
+    pub p2p_upgrade_nudge_ratio: f64,
+    /// Incoming P2P connections allowed per minute from the same IP.
+    /// Attempts above it are dropped before the handshake. Set to 0 to disable it.
+    #[clap(long, default_value_t = P2P_DEFAULT_CONNECTION_RATE_LIMIT)]
+    pub p2p_connection_rate_limit: u64,
+    /// Incoming P2P connections allowed at once from the same IP before the rate limit applies
+    #[clap(long, default_value_t = P2P_DEFAULT_CONNECTION_RATE_BURST)]
+    pub p2p_connection_rate_burst: u64,
+    /// SOCKS5 proxy (host:port) used for all outgoing P2P connections.
+    /// Inbound connections are not affected.
+    #[clap(long)]
+    pub socks_proxy: Option<String>,
+    /// Maximum size in bytes of a message received from a peer.
+    /// Peers sending a bigger message are disconnected. Below the max block size, bigger blocks can't be received.
+    #[clap(long, default_value_t = P2P_DEFAULT_MAX_MESSAGE_SIZE)]
+    pub p2p_max_message_size: u32,
+    /// Disable the LZ4 compression of the P2P packets.
+    /// When enabled, it is used only with the peers supporting it.
+    #[clap(long)]
+    pub disable_p2p_compression: bool,
+    /// Time in seconds to wait for an object requested to a peer during sync.
+    /// On timeout, the object is requested from another peer.
+    #[clap(long, default_value_t = PEER_TIMEOUT_REQUEST_OBJECT / 1000)]
+    pub p2p_request_timeout: u64,
+    /// Blocks requested at the same time from the peers during a sync.
+    /// Their PoW is verified in parallel while they are added in order, 1 syncs the blocks one by one.
+    /// It is not used in boost sync mode.
+    #[clap(long, default_value_t = DEFAULT_SYNC_PARALLEL_REQUESTS)]
+    pub sync_parallel_requests: usize,
     /// Rpc bind address to listen for HTTP requests
     #[clap(long, default_value_t = String::from(DEFAULT_RPC_BIND_ADDRESS))]
     pub rpc_bind_address: String,
+    /// Serve the JSON-RPC API on this Unix domain socket, only accessible by its owner.
+    /// It is started even with --disable-rpc-server to keep a local access.
+    #[clap(long)]
+    pub rpc_unix_socket: Option<String>,
+    /// Maximum RPC requests per minute from the same IP (0 = disabled).
+    /// WebSocket messages, including subscriptions, count against the same limit.
+    #[clap(long, default_value_t = 0)]
+    pub rpc_rate_limit: u64,
+    /// Maximum concurrent RPC requests and WebSocket connections from the same IP (0 = disabled).
+    #[clap(long, default_value_t = 0)]
+    pub rpc_concurrent_limit: usize,
+    /// Use the X-Forwarded-For header to determine the client IP.
+    /// Only enable it if the RPC server is behind a reverse proxy setting it.
+    #[clap(long)]
+    pub rpc_trust_proxy: bool,
+    /// Username required to call the admin RPC methods (get_rpc_stats, set_aux_commitment).
+    /// Without credentials, admin methods are only available to clients connecting from localhost.
+    #[clap(long, requires = "rpc_admin_password")]
+    pub rpc_admin_username: Option<String>,
+    /// Password required to call the admin RPC methods.
+    #[clap(long, requires = "rpc_admin_username")]
+    pub rpc_admin_password: Option<String>,
+    /// Maximum blocks returned by a single get_blocks_range request.
+    /// Requests for a bigger range are rejected.
+    #[clap(long, default_value_t = DEFAULT_RPC_MAX_BLOCKS_RANGE)]
+    pub rpc_max_blocks_range: u64,
+    /// Serve a minimal block explorer on the RPC server at /explorer.
+    #[clap(long)]
+    pub enable_explorer: bool,
     /// Add a priority node to connect when P2p is started.
     /// A priority node is connected only one time.
     #[clap(long)]
@@ -132,12 +235,51 @@ pub struct Config {
     /// It must ends with a slash.
     #[clap(long)]
     pub dir_path: Option<String>,
+    /// Import a snapshot file before starting the node.
+    /// The storage must be empty, the sync will resume from the snapshot topoheight.
+    #[clap(long)]
+    pub import_snapshot: Option<String>,
+    /// Replay the blocks of an export file on a temporary storage and exit.
+    /// Each block goes through the full validation, it stops at the first block
+    /// whose result differs from the status recorded in the file.
+    /// Use it with --log-level trace to see every check performed.
+    #[clap(long)]
+    pub replay: Option<String>,
     /// Set LRUCache size (0 = disabled).
     #[clap(long, default_value_t = DEFAULT_CACHE_SIZE)]
     pub cache_size: usize,
+    /// Set the maximum size in bytes of all TXs in mempool.
+    /// Once reached, TXs with the lowest fee rate are evicted.
+    #[clap(long, default_value_t = DEFAULT_MEMPOOL_MAX_SIZE_BYTES)]
+    pub mempool_max_size_bytes: usize,
+    /// Time in seconds after which an unconfirmed TX is removed from mempool (0 = disabled).
+    #[clap(long, default_value_t = DEFAULT_MEMPOOL_TX_TTL)]
+    pub mempool_tx_ttl: u64,
+    /// Minimum fee increase in percent for a TX to replace the mempool TX using the same nonce.
+    /// Pending TXs with a higher nonce from the same account are removed with it.
+    #[clap(long, default_value_t = DEFAULT_MEMPOOL_REPLACEMENT_FEE_INCREMENT)]
+    pub mempool_replacement_fee_increment: u64,
+    /// Minimum fee rate per kB in atomic units to accept a TX in mempool and relay it.
+    /// This is a local policy only: blocks including TXs below it are still accepted.
+    /// It can't be lower than the consensus rule.
+    #[clap(long, default_value_t = DEFAULT_MIN_RELAY_FEE_RATE)]
+    pub min_relay_fee_rate: u64,
+    /// Window in milliseconds to coalesce the FeePolicyUpdated and BlockTemplateUpdated events (0 = disabled).
+    /// Changes made during the window are sent as one event at its end.
+    #[clap(long, default_value_t = DEFAULT_RPC_EVENTS_COALESCE_WINDOW)]
+    pub rpc_events_coalesce_window: u64,
     /// Disable GetWork Server (WebSocket for miners).
     #[clap(long)]
     pub disable_getwork_server: bool,
+    /// Expected shares per minute for each GetWork miner (0 = disabled).
+    /// Miners will receive a lower share difficulty, auto-adjusted per connection,
+    /// to report their effective hashrate.
+    #[clap(long, default_value_t = 0)]
+    pub getwork_shares_per_minute: u64,
+    /// Commit a 32 bytes hex value (sidechain anchor) in the extra nonce of the blocks mined with this node.
+    /// Only its first 16 bytes fit in the extra nonce, it can be changed at runtime using set_aux_commitment.
+    #[clap(long)]
+    pub aux_commitment: Option<String>,
     /// Disable RPC Server
     /// This will also disable the GetWork Server as it is loaded on RPC server.
     #[clap(long)]
@@ -190,7 +332,67 @@ pub struct Config {
     pub disable_p2p_outgoing_connections: bool,
     /// Limit of concurrent tasks accepting new incoming connections.
     #[clap(long, default_value_t = P2P_DEFAULT_CONCURRENCY_TASK_COUNT_LIMIT)]
-    pub p2p_concurrency_task_count_limit: usize
+    pub p2p_concurrency_task_count_limit: usize,
+    /// Enable the index of transactions by involved address.
+    /// 
+    /// This allows to use the `get_address_transactions` RPC method but requires a lot of disk space.
+    /// 
+    /// When enabled on an existing chain, the index is rebuilt in background.
+    /// Disabling it will delete the whole index.
+    #[clap(long)]
+    pub enable_address_index: bool,
+    /// File of addresses and TX hashes to never relay or mine.
+    /// 
+    /// One entry per line. This is a local policy only:
+    /// blocks including these TXs are still accepted.
+    #[clap(long)]
+    pub tx_policy_file: Option<String>,
+    /// Count of recent blocks aggregated in the fees and sizes histograms.
+    /// 
+    /// They are available using the `get_fee_histogram` RPC method and the metrics endpoint.
+    #[clap(long, default_value_t = DEFAULT_FEE_HISTOGRAM_WINDOW)]
+    pub fee_histogram_window: u64,
+    /// Start the node in safe mode to inspect an existing chain.
+    /// 
+    /// P2P and mining are disabled and the storage is never written:
+    /// only the read RPC methods and the inspection commands are available.
+    #[clap(long)]
+    pub safe_mode: bool,
+    /// Raise an alert when no new block is ordered for this many minutes (0 = disabled).
+    /// 
+    /// Alerts are written in the log and the prompt, and sent to the webhook and command if set.
+    /// The active alerts are available using the `get_active_alerts` RPC method.
+    #[clap(long, default_value_t = 0)]
+    pub alert_no_block_minutes: u64,
+    /// Raise an alert when less peers than this are connected (0 = disabled).
+    #[clap(long, default_value_t = 0)]
+    pub alert_min_peers: usize,
+    /// Minutes the peer count must stay below --alert-min-peers before raising the alert.
+    #[clap(long, default_value_t = DEFAULT_ALERT_MIN_PEERS_MINUTES)]
+    pub alert_min_peers_minutes: u64,
+    /// Raise an alert when the free disk space of the storage directory is below this value in MB (0 = disabled).
+    #[clap(long, default_value_t = 0)]
+    pub alert_min_disk_free_mb: u64,
+    /// Raise an alert when the size of all TXs in mempool is above this value in bytes (0 = disabled).
+    #[clap(long, default_value_t = 0)]
+    pub alert_max_mempool_size_bytes: usize,
+    /// Interval in seconds between two evaluations of the alert rules.
+    #[clap(long, default_value_t = DEFAULT_ALERT_INTERVAL)]
+    pub alert_interval_secs: u64,
+    /// Minimum delay in seconds between two notifications of the same alert.
+    /// 
+    /// An active alert is notified again after it.
+    #[clap(long, default_value_t = DEFAULT_ALERT_COOLDOWN)]
+    pub alert_cooldown_secs: u64,
+    /// URL receiving each alert notification in a JSON POST request.
+    #[clap(long)]
+    pub alert_webhook_url: Option<String>,
+    /// Command executed on each alert notification.
+    /// 
+    /// The notification is sent as JSON on its stdin, its kind and status are set in
+    /// the XELIS_ALERT_KIND and XELIS_ALERT_STATUS env variables.
+    #[clap(long)]
+    pub alert_exec: Option<String>
 }
 
 pub struct Blockchain<S: Storage> {
@@ -226,11 +428,37 @@ pub struct Blockchain<S: Storage> {
     // using base hash, current tip hash and base height, this cache is used to store the DAG order
     full_order_cache: Mutex<LruCache<(Hash, Hash, u64), IndexSet<Hash>>>,
     // auto prune mode if enabled, will delete all blocks every N and keep only N top blocks (topoheight based)
-    auto_prune_keep_n_blocks: Option<u64>
+    auto_prune_keep_n_blocks: Option<u64>,
+    // index all transactions by involved address
+    address_index: bool,
+    // count of mined blocks submitted on stale tips
+    orphaned_blocks: AtomicU64,
+    // set once the warm-up is done, miner work is only accepted after it
+    ready: AtomicBool,
+    // scratch pads reused to verify the PoW, they are allocated during the warm-up
+    scratch_pads: ScratchPadPool,
+    // local policy filtering TXs from mempool and block templates
+    tx_policy: RwLock<TxPolicy>,
+    // fees and sizes of the TXs executed in the recent blocks
+    fee_histogram: Mutex<FeeHistogram>,
+    // storage is read only, p2p and mining are disabled
+    safe_mode: bool,
+    // minimum fee rate per kB to accept a TX in mempool
+    min_relay_fee_rate: AtomicU64,
+    // last fee policy notified to the RPC clients
+    fee_policy: Mutex<FeePolicy>,
+    // maximum blocks returned by get_blocks_range
+    rpc_max_blocks_range: u64,
+    // value committed in the extra nonce of the block templates
+    aux_commitment: RwLock<Option<Hash>>,
+    // chain stats computed for (top block hash, blocks count)
+    stats_cache: Mutex<LruCache<(Hash, u64), GetStatsResult>>,
+    // alerting rules evaluated periodically, None if no rule is set
+    alerts: Option<Arc<AlertManager>>
 }
 
 impl<S: Storage> Blockchain<S> {
-    pub async fn new(config: Config, network: Network, storage: S) -> Result<Arc<Self>, Error> {
+    pub async fn new(config: Config, network: Network, mut storage: S) -> Result<Arc<Self>, Error> {
         // Do some checks on config params
         {
             if config.simulator.is_some() && network != Network::Dev {
@@ -252,10 +480,45 @@ impl<S: Storage> Blockchain<S> {
                 }
             }
 
+            if let Some(value) = config.aux_commitment.as_ref() {
+                if let Err(e) = parse_aux_commitment(value) {
+                    error!("Aux commitment must be a 32 bytes value in hex format");
+                    return Err(e.into())
+                }
+            }
+
             if config.allow_boost_sync && config.allow_fast_sync {
                 error!("Boost sync and fast sync can't be enabled at the same time!");
                 return Err(BlockchainError::ConfigSyncMode.into())
             }
+
+            if config.safe_mode {
+                if config.auto_prune_keep_n_blocks.is_some() {
+                    error!("Auto prune mode can't be enabled in safe mode!");
+                    return Err(BlockchainError::ConfigSafeMode("auto prune mode").into())
+                }
+
+                if config.import_snapshot.is_some() {
+                    error!("A snapshot can't be imported in safe mode!");
+                    return Err(BlockchainError::ConfigSafeMode("snapshot import").into())
+                }
+
+                if config.simulator.is_some() {
+                    error!("Simulator mode can't be enabled in safe mode!");
+                    return Err(BlockchainError::ConfigSafeMode("simulator mode").into())
+                }
+
+                if !storage.has_blocks().await {
+                    error!("Safe mode requires an existing chain on disk!");
+                    return Err(BlockchainError::SafeMode.into())
+                }
+
+                warn!("Safe mode enabled: P2P and mining are disabled, storage is read only");
+            }
+        }
+
+        if let Some(path) = config.import_snapshot.as_ref() {
+            snapshot::import_snapshot(&mut storage, network, path).await?;
         }
 
         let on_disk = storage.has_blocks().await;
@@ -267,13 +530,39 @@ impl<S: Storage> Blockchain<S> {
             (height, topoheight)
         } else { (0, 0) };
 
+        // Prepare the address index
+        // In safe mode, any change on it is deferred to the next normal start
+        let mut address_index = config.enable_address_index;
+        if config.safe_mode {
+            let lowest = storage.get_address_index_lowest_topoheight().await?;
+            if address_index && lowest.is_none() {
+                warn!("Safe mode: address index creation is deferred, it is disabled for this session");
+                address_index = false;
+            } else if !address_index && lowest.is_some() {
+                warn!("Safe mode: address index deletion is deferred");
+            }
+        } else if config.enable_address_index {
+            if storage.get_address_index_lowest_topoheight().await?.is_none() {
+                // Index everything from the next block, previous blocks will be indexed in background
+                let lowest = if on_disk { topoheight + 1 } else { 0 };
+                info!("Address index enabled, live indexing from topoheight {}", lowest);
+                storage.set_address_index_lowest_topoheight(lowest).await?;
+            }
+        } else if storage.get_address_index_lowest_topoheight().await?.is_some() {
+            warn!("Address index is disabled, deleting it");
+            storage.clear_address_index().await?;
+        }
+
+        let alerts = Self::build_alerts(&config);
+        let tx_policy = TxPolicy::new(config.tx_policy_file, &network)?;
+
         info!("Initializing chain...");
         let blockchain = Self {
             height: AtomicU64::new(height),
             topoheight: AtomicU64::new(topoheight),
             stable_height: AtomicU64::new(0),
             stable_topoheight: AtomicU64::new(0),
-            mempool: RwLock::new(Mempool::new(network)),
+            mempool: RwLock::new(Mempool::new(network, config.mempool_max_size_bytes, config.mempool_tx_ttl, config.mempool_replacement_fee_increment)),
             storage: RwLock::new(storage),
             p2p: RwLock::new(None),
             rpc: RwLock::new(None),
@@ -283,13 +572,33 @@ impl<S: Storage> Blockchain<S> {
             tip_base_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
             tip_work_score_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
             full_order_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
-            auto_prune_keep_n_blocks: config.auto_prune_keep_n_blocks
+            auto_prune_keep_n_blocks: config.auto_prune_keep_n_blocks,
+            address_index,
+            orphaned_blocks: AtomicU64::new(0),
+            ready: AtomicBool::new(false),
+            scratch_pads: ScratchPadPool::new(POW_SCRATCH_PADS),
+            tx_policy: RwLock::new(tx_policy),
+            fee_histogram: Mutex::new(FeeHistogram::new(config.fee_histogram_window)),
+            safe_mode: config.safe_mode,
+            min_relay_fee_rate: AtomicU64::new(config.min_relay_fee_rate.max(FEE_PER_KB)),
+            fee_policy: Mutex::new(FeePolicy {
+                min_relay_fee_rate: config.min_relay_fee_rate.max(FEE_PER_KB),
+                mempool_fee_rate: 0
+            }),
+            rpc_max_blocks_range: config.rpc_max_blocks_range.max(1),
+            aux_commitment: RwLock::new(config.aux_commitment.and_then(|value| parse_aux_commitment(&value).ok())),
+            stats_cache: Mutex::new(LruCache::new(NonZeroUsize::new(16).unwrap())),
+            alerts
         };
 
         // include genesis block
         if !on_disk {
             blockchain.create_genesis_block().await?;
         } else {
+            if !blockchain.safe_mode {
+                blockchain.register_native_asset_metadata().await?;
+            }
+
             debug!("Retrieving tips for computing current difficulty");
             let storage = blockchain.get_storage().read().await;
             let tips_set = storage.get_tips().await?;
@@ -311,7 +620,7 @@ impl<S: Storage> Blockchain<S> {
 
         let arc = Arc::new(blockchain);
         // create P2P Server
-        if !config.disable_p2p_server {
+        if !config.disable_p2p_server && !config.safe_mode {
             info!("Starting P2p server...");
             // setup exclusive nodes
             let mut exclusive_nodes: Vec<SocketAddr> = Vec::with_capacity(config.exclusive_nodes.len());
@@ -326,7 +635,7 @@ impl<S: Storage> Blockchain<S> {
                 exclusive_nodes.push(addr);
             }
 
-            match P2pServer::new(config.p2p_concurrency_task_count_limit, config.dir_path, config.tag, config.max_peers, config.p2p_bind_address, Arc::clone(&arc), exclusive_nodes.is_empty(), exclusive_nodes, config.allow_fast_sync, config.allow_boost_sync, config.max_chain_response_size, !config.disable_ip_sharing, config.disable_p2p_outgoing_connections) {
+            match P2pServer::new(config.p2p_concurrency_task_count_limit, config.dir_path, config.tag, config.max_peers, config.max_outgoing_peers, config.priority_peer_slots, config.p2p_bind_address, Arc::clone(&arc), exclusive_nodes.is_empty(), exclusive_nodes, config.allow_fast_sync, config.allow_boost_sync, config.max_chain_response_size, !config.disable_ip_sharing, config.disable_p2p_outgoing_connections, config.p2p_evict_peers, config.p2p_upgrade_nudge_ratio, config.p2p_connection_rate_limit, config.p2p_connection_rate_burst, config.socks_proxy, config.p2p_max_message_size, config.p2p_request_timeout, config.sync_parallel_requests, !config.disable_p2p_compression) {
                 Ok(p2p) => {
                     // connect to priority nodes
                     for addr in config.priority_nodes {
@@ -347,14 +656,73 @@ impl<S: Storage> Blockchain<S> {
         }
 
         // create RPC Server
-        if !config.disable_rpc_server {
-            info!("RPC Server will listen on: {}", config.rpc_bind_address);
-            match DaemonRpcServer::new(config.rpc_bind_address, Arc::clone(&arc), config.disable_getwork_server).await {
+        if !config.disable_rpc_server || config.rpc_unix_socket.is_some() {
+            let bind_address = if config.disable_rpc_server {
+                None
+            } else {
+                info!("RPC Server will listen on: {}", config.rpc_bind_address);
+                Some(config.rpc_bind_address)
+            };
+
+            if let Some(path) = config.rpc_unix_socket.as_ref() {
+                info!("RPC Server will listen on the Unix socket: {}", path);
+            }
+
+            let admin_credentials = match (config.rpc_admin_username, config.rpc_admin_password) {
+                (Some(username), Some(password)) => Some(AdminCredentials { username, password }),
+                _ => None
+            };
+
+            match DaemonRpcServer::new(bind_address, config.rpc_unix_socket, Arc::clone(&arc), config.disable_getwork_server, config.getwork_shares_per_minute, config.rpc_rate_limit, config.rpc_concurrent_limit, config.rpc_trust_proxy, admin_credentials, config.enable_explorer, config.rpc_events_coalesce_window).await {
                 Ok(server) => *arc.rpc.write().await = Some(server),
                 Err(e) => error!("Error while starting RPC server: {}", e)
             };
         }
 
+        // Index the blocks that were there before enabling the address index
+        if arc.address_index && !arc.safe_mode {
+            let blockchain = Arc::clone(&arc);
+            spawn_task("address-index-backfill", async move {
+                if let Err(e) = blockchain.backfill_address_index().await {
+                    error!("Error while building the address index: {}", e);
+                }
+            });
+        }
+
+        // Remove periodically the TXs stuck in mempool
+        if config.mempool_tx_ttl > 0 && !config.safe_mode {
+            let blockchain = Arc::downgrade(&arc);
+            spawn_task("mempool-expiration", async move {
+                let mut interval = interval(Duration::from_secs(MEMPOOL_EXPIRATION_INTERVAL));
+                loop {
+                    interval.tick().await;
+                    let Some(blockchain) = blockchain.upgrade() else {
+                        break;
+                    };
+                    blockchain.expire_mempool_txs().await;
+                }
+            });
+        }
+
+        // Evaluate the alert rules periodically
+        if let Some(alerts) = arc.alerts.clone() {
+            let blockchain = Arc::downgrade(&arc);
+            let period = Duration::from_secs(config.alert_interval_secs.max(1));
+            spawn_task("alerts", async move {
+                let mut interval = interval(period);
+                loop {
+                    interval.tick().await;
+                    let Some(blockchain) = blockchain.upgrade() else {
+                        break;
+                    };
+                    alerts.tick(&*blockchain).await;
+                }
+            });
+        }
+
+        // Warm up before accepting miner work
+        arc.warm_up().await;
+
         // Start the simulator task if necessary
         if let Some(simulator) = arc.simulator {
             warn!("Simulator {} mode enabled!", simulator);
@@ -367,11 +735,172 @@ impl<S: Storage> Blockchain<S> {
         Ok(arc)
     }
 
+    // Pay the lazy initialization costs (storage caches, PoW hashing, block template)
+    // so the first miner requesting a block template doesn't stall
+    // Errors are only logged, the node is marked as ready in any case
+    async fn warm_up(&self) {
+        info!("Warming up...");
+        let start = Instant::now();
+        if let Err(e) = self.warm_up_internal().await {
+            warn!("Error during warm-up: {}", e);
+        }
+
+        self.ready.store(true, Ordering::SeqCst);
+        info!("Node is ready, warm-up done in {:?}", start.elapsed());
+    }
+
+    async fn warm_up_internal(&self) -> Result<(), BlockchainError> {
+        let storage = self.storage.read().await;
+
+        // Allocate the scratch pads used to verify the PoW
+        self.scratch_pads.fill();
+
+        // Load the tips headers in cache
+        let tips = storage.get_tips().await?;
+        for tip in tips.iter() {
+            storage.get_block_header_by_hash(tip).await?;
+        }
+
+        // Load the latest balances of the recent miners
+        let topoheight = self.get_topo_height();
+        let lowest = topoheight.saturating_sub(WARM_UP_RECENT_BLOCKS)
+            .max(storage.get_pruned_topoheight().await?.unwrap_or(0));
+        let mut miners = HashSet::new();
+        for topo in (lowest..=topoheight).rev() {
+            let hash = storage.get_hash_at_topo_height(topo).await?;
+            let header = storage.get_block_header_by_hash(&hash).await?;
+            let miner = header.get_miner();
+            if miners.insert(miner.clone()) && storage.has_balance_for(miner, &XELIS_ASSET).await? {
+                storage.get_last_balance(miner, &XELIS_ASSET).await?;
+            }
+        }
+        debug!("Warm-up loaded balances of {} recent miners", miners.len());
+
+        // Fill the fee histogram with the recent blocks
+        {
+            let mut fee_histogram = self.fee_histogram.lock().await;
+            let window = fee_histogram.get_window();
+            let lowest = (topoheight + 1).saturating_sub(window)
+                .max(storage.get_pruned_topoheight().await?.unwrap_or(0));
+            for topo in lowest..=topoheight {
+                let hash = storage.get_hash_at_topo_height(topo).await?;
+                let block = storage.get_block_by_hash(&hash).await?;
+                let mut histograms = BlockHistograms::new();
+                for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+                    if storage.is_tx_executed_in_block(tx_hash, &hash)? {
+                        histograms.observe(tx.get_fee(), tx.size());
+                    }
+                }
+                fee_histogram.set_block(topo, histograms);
+            }
+        }
+
+        // Build a first block template, mining is disabled in safe mode
+        if !self.safe_mode {
+            self.get_block_template_for_storage(&storage, DEV_PUBLIC_KEY.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    // Is the warm-up done and the node ready to accept miner work
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    // Compute the PoW hash of a block header using a pooled scratch pad
+    pub fn get_pow_hash(&self, header: &BlockHeader) -> Result<Hash, XelisHashError> {
+        header.get_pow_hash_with(&mut self.scratch_pads.get())
+    }
+
+    // Is the node running in safe mode
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    // Refuse any operation writing in storage or producing blocks in safe mode
+    pub fn check_safe_mode(&self) -> Result<(), BlockchainError> {
+        if self.safe_mode {
+            return Err(BlockchainError::SafeMode)
+        }
+        Ok(())
+    }
+
     // Detect if the simulator task has been started
     pub fn is_simulator_enabled(&self) -> bool {
         self.simulator.is_some()
     }
 
+    // Is the index of transactions by address enabled
+    pub fn is_address_index_enabled(&self) -> bool {
+        self.address_index
+    }
+
+    // Index all accounts involved in a block ordered at topoheight
+    // Only the transactions executed in this block are indexed
+    async fn index_block_addresses(&self, storage: &mut S, hash: &Hash, block: &Block, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("indexing addresses for block {} at topoheight {}", hash, topoheight);
+        for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+            if !storage.is_tx_executed_in_block(tx_hash, hash)? {
+                continue;
+            }
+
+            storage.index_address_transaction(tx.get_source(), topoheight, tx_hash, AddressTransactionType::Outgoing).await?;
+            if let TransactionType::Transfers(transfers) = tx.get_data() {
+                for transfer in transfers {
+                    storage.index_address_transaction(transfer.get_destination(), topoheight, tx_hash, AddressTransactionType::Incoming).await?;
+                }
+            }
+        }
+
+        if get_block_dev_fee(block.get_height()) != 0 {
+            storage.index_address_transaction(&DEV_PUBLIC_KEY, topoheight, hash, AddressTransactionType::DevFee).await?;
+        }
+        storage.index_address_transaction(block.get_miner(), topoheight, hash, AddressTransactionType::Mining).await?;
+
+        Ok(())
+    }
+
+    // Index all blocks that were ordered before enabling the address index
+    // It goes from the lowest indexed topoheight down to the pruned topoheight
+    // Storage is locked by batch to not block the chain for too long
+    async fn backfill_address_index(&self) -> Result<(), BlockchainError> {
+        const BATCH_SIZE: u64 = 100;
+
+        let mut first_lowest: Option<u64> = None;
+        loop {
+            let mut storage = self.storage.write().await;
+            let lowest = match storage.get_address_index_lowest_topoheight().await? {
+                Some(lowest) => lowest,
+                None => return Ok(())
+            };
+            let pruned_topoheight = storage.get_pruned_topoheight().await?.unwrap_or(0);
+            if lowest <= pruned_topoheight {
+                if first_lowest.is_some() {
+                    info!("Address index is now complete");
+                }
+                return Ok(())
+            }
+
+            let start = *first_lowest.get_or_insert(lowest);
+            if start == lowest {
+                info!("Building address index from topoheight {} to {}", lowest - 1, pruned_topoheight);
+            }
+
+            let end = lowest.saturating_sub(BATCH_SIZE).max(pruned_topoheight);
+            for topoheight in (end..lowest).rev() {
+                let hash = storage.get_hash_at_topo_height(topoheight).await?;
+                let block = storage.get_block_by_hash(&hash).await?;
+                self.index_block_addresses(&mut storage, &hash, &block, topoheight).await?;
+            }
+            storage.set_address_index_lowest_topoheight(end).await?;
+
+            let total = start.saturating_sub(pruned_topoheight).max(1);
+            let done = start.saturating_sub(end);
+            info!("Address index: {}/{} blocks indexed ({}%)", done, total, done * 100 / total);
+        }
+    }
+
     // Stop all blockchain modules
     // Each module is stopped in its own context
     // So no deadlock occurs in case they are linked
@@ -434,7 +963,21 @@ impl<S: Storage> Blockchain<S> {
         let mut mempool = self.mempool.write().await;
         debug!("Clearing mempool");
         mempool.clear();
+        drop(mempool);
+
+        self.update_fee_policy().await;
+
+        Ok(())
+    }
 
+    // Chains created before the asset metadata registered the native asset without it
+    async fn register_native_asset_metadata(&self) -> Result<(), BlockchainError> {
+        let mut storage = self.storage.write().await;
+        let data = storage.get_asset(&XELIS_ASSET).await?;
+        if data.get_metadata().is_none() {
+            info!("Registering the metadata of XELIS asset");
+            storage.update_asset(&XELIS_ASSET, AssetData::with_metadata(data.get_topoheight(), data.get_decimals(), get_native_asset_metadata()?)).await?;
+        }
         Ok(())
     }
 
@@ -444,7 +987,7 @@ impl<S: Storage> Blockchain<S> {
 
         // register XELIS asset
         debug!("Registering XELIS asset: {} at topoheight 0", XELIS_ASSET);
-        storage.add_asset(&XELIS_ASSET, AssetData::new(0, COIN_DECIMALS)).await?;
+        storage.add_asset(&XELIS_ASSET, AssetData::with_metadata(0, COIN_DECIMALS, get_native_asset_metadata()?)).await?;
 
         let (genesis_block, genesis_hash) = if let Some(genesis_block) = get_hex_genesis_block(&self.network) {
             info!("De-serializing genesis block for network {}...", self.network);
@@ -492,7 +1035,7 @@ impl<S: Storage> Blockchain<S> {
             let (difficulty, _) = self.get_difficulty_at_tips(&*storage, block.get_tips().iter()).await?;
             (block, difficulty)
         };
-        let mut hash = header.get_pow_hash()?;
+        let mut hash = self.get_pow_hash(&header)?;
         let mut current_height = self.get_height();
         while !self.is_simulator_enabled() && !check_difficulty(&hash, &difficulty)? {
             if self.get_height() != current_height {
@@ -501,7 +1044,7 @@ impl<S: Storage> Blockchain<S> {
             }
             header.nonce += 1;
             header.timestamp = get_current_time_in_millis();
-            hash = header.get_pow_hash()?;
+            hash = self.get_pow_hash(&header)?;
         }
 
         let block = self.build_block_from_header(Immutable::Owned(header)).await?;
@@ -510,6 +1053,28 @@ impl<S: Storage> Blockchain<S> {
         Ok(block)
     }
 
+    // Export all the chain data up to a stable topoheight in a snapshot file
+    // It can be imported using --import-snapshot on a new node
+    pub async fn export_snapshot(&self, path: &str, up_to_topoheight: u64) -> Result<(), BlockchainError> {
+        let stable_topoheight = self.get_stable_topoheight();
+        if up_to_topoheight > stable_topoheight {
+            return Err(BlockchainError::SnapshotTopoHeightNotStable(up_to_topoheight, stable_topoheight))
+        }
+
+        snapshot::export_snapshot(&self.storage, self.network, path, up_to_topoheight).await
+    }
+
+    // Export the ordered blocks between the two topoheights in a replay file
+    // It can be replayed using --replay on a debug build
+    pub async fn export_blocks(&self, path: &str, min_topoheight: u64, max_topoheight: u64) -> Result<usize, BlockchainError> {
+        let topoheight = self.get_topo_height();
+        if min_topoheight > max_topoheight || max_topoheight > topoheight {
+            return Err(BlockchainError::InvalidExportRange(min_topoheight, max_topoheight))
+        }
+
+        replay::export_blocks(&self.storage, self.network, path, min_topoheight, max_topoheight).await
+    }
+
     // Prune the chain until topoheight
     // This will delete all blocks / versioned balances / txs until topoheight in param
     pub async fn prune_until_topoheight(&self, topoheight: u64) -> Result<u64, BlockchainError> {
@@ -522,6 +1087,7 @@ impl<S: Storage> Blockchain<S> {
     // and then delete all blocks before it
     // keep a marge of PRUNE_SAFETY_LIMIT
     pub async fn prune_until_topoheight_for_storage(&self, topoheight: u64, storage: &mut S) -> Result<u64, BlockchainError> {
+        self.check_safe_mode()?;
         if topoheight == 0 {
             return Err(BlockchainError::PruneZero)
         }
@@ -560,6 +1126,10 @@ impl<S: Storage> Blockchain<S> {
             storage.delete_versioned_nonces_below_topoheight(located_sync_topoheight).await?;
             // Also delete registrations
             storage.delete_registrations_below_topoheight(located_sync_topoheight).await?;
+            // And the address index
+            if self.address_index {
+                storage.delete_address_index_below_topoheight(located_sync_topoheight).await?;
+            }
 
             // Update the pruned topoheight
             storage.set_pruned_topoheight(located_sync_topoheight).await?;
@@ -627,6 +1197,113 @@ impl<S: Storage> Blockchain<S> {
         self.mempool.read().await.size()
     }
 
+    // Get the size in bytes of all transactions in the mempool
+    pub async fn get_mempool_size_bytes(&self) -> usize {
+        self.mempool.read().await.size_bytes()
+    }
+
+    // Remove the TXs that were not included in a block before the mempool TTL
+    // Returns the count of TXs removed
+    pub async fn expire_mempool_txs(&self) -> usize {
+        let now = get_current_time_in_seconds();
+        let expired = {
+            let mut mempool = self.mempool.write().await;
+            mempool.expire_txs(now)
+        };
+
+        for (hash, sorted_tx) in expired.iter() {
+            info!("TX {} expired after {}s in mempool", hash, now.saturating_sub(sorted_tx.get_first_seen()));
+        }
+
+        let count = expired.len();
+        self.notify_txs_removed_from_mempool(expired, MempoolRemovalReason::Expired).await;
+        self.update_fee_policy().await;
+        count
+    }
+
+    // Remove all TXs from mempool
+    // Returns the count of TXs removed
+    pub async fn clear_mempool(&self) -> usize {
+        let removed = {
+            let mut mempool = self.mempool.write().await;
+            mempool.clear()
+        };
+
+        let count = removed.len();
+        self.notify_txs_removed_from_mempool(removed, MempoolRemovalReason::Cleared).await;
+        self.update_fee_policy().await;
+        count
+    }
+
+    // Fee rates currently required to accept a TX in mempool
+    pub async fn get_fee_policy(&self) -> FeePolicy {
+        *self.fee_policy.lock().await
+    }
+
+    // Compare the fee policy with the last one and notify the RPC clients if it changed
+    // Mempool lock must not be held by the caller
+    async fn update_fee_policy(&self) {
+        let rpc = self.rpc.read().await;
+        self.update_fee_policy_with(rpc.as_ref()).await;
+    }
+
+    async fn update_fee_policy_with(&self, rpc: Option<&SharedDaemonRpcServer<S>>) {
+        let new = FeePolicy {
+            min_relay_fee_rate: self.get_min_relay_fee_rate(),
+            mempool_fee_rate: self.mempool.read().await.get_minimum_fee_rate()
+        };
+
+        // Keep the lock until notified so the events are pushed in order
+        let mut policy = self.fee_policy.lock().await;
+        if *policy == new {
+            return;
+        }
+
+        let previous = std::mem::replace(&mut *policy, new);
+        debug!("Fee policy updated from {:?} to {:?}", previous, new);
+        if let Some(rpc) = rpc {
+            rpc.notify_fee_policy_updated(FeePolicyUpdatedEvent { previous, new }).await;
+        }
+    }
+
+    // Notify the RPC clients of a new block template
+    // It is built in a new task as the storage may be locked by the caller
+    async fn notify_block_template_updated(&self) {
+        if let Some(rpc) = self.rpc.read().await.as_ref() {
+            let rpc = rpc.clone();
+            spawn_task("rpc-notify-block-template", async move {
+                rpc.notify_block_template_updated().await;
+            });
+        }
+    }
+
+    // Notify the RPC clients about the TXs that left the mempool without being executed in a block
+    async fn notify_txs_removed_from_mempool(&self, txs: Vec<(Arc<Hash>, SortedTx)>, reason: MempoolRemovalReason) {
+        if txs.is_empty() {
+            return;
+        }
+
+        if let Some(rpc) = self.rpc.read().await.as_ref() {
+            if rpc.is_event_tracked(&NotifyEvent::TransactionRemovedFromMempool).await {
+                let rpc = rpc.clone();
+                spawn_task("rpc-notify-tx-removed", async move {
+                    for (hash, _) in txs {
+                        let event = TransactionRemovedFromMempoolEvent {
+                            hash: Cow::Borrowed(&hash),
+                            reason
+                        };
+                        rpc.notify_clients_with(&NotifyEvent::TransactionRemovedFromMempool, event).await;
+                    }
+                });
+            }
+        }
+    }
+
+    // Get the count of transactions evicted from the mempool due to its size limit
+    pub async fn get_mempool_evicted_count(&self) -> u64 {
+        self.mempool.read().await.get_evicted_count()
+    }
+
     // Get the current top block hash in chain
     pub async fn get_top_block_hash(&self) -> Result<Hash, BlockchainError> {
         let storage = self.storage.read().await;
@@ -1026,7 +1703,7 @@ impl<S: Storage> Blockchain<S> {
         let mut score = CumulativeDifficulty::zero();
         for (hash, value) in map {
             set.insert(hash);
-            score += value;
+            score = score.saturating_add(value);
         }
 
         // save this result in cache
@@ -1235,6 +1912,106 @@ impl<S: Storage> Blockchain<S> {
         &self.mempool
     }
 
+    // Returns the local TX policy used
+    pub fn get_tx_policy(&self) -> &RwLock<TxPolicy> {
+        &self.tx_policy
+    }
+
+    pub fn get_fee_histogram(&self) -> &Mutex<FeeHistogram> {
+        &self.fee_histogram
+    }
+
+    pub fn get_alerts(&self) -> Option<&Arc<AlertManager>> {
+        self.alerts.as_ref()
+    }
+
+    // Alert manager with the rules enabled in the config
+    fn build_alerts(config: &Config) -> Option<Arc<AlertManager>> {
+        let mut rules = Vec::new();
+        if config.alert_no_block_minutes > 0 {
+            rules.push(AlertRule::NoNewBlock { max_delay: config.alert_no_block_minutes * 60 * MILLIS_PER_SECOND });
+        }
+        if config.alert_min_peers > 0 {
+            rules.push(AlertRule::LowPeerCount { min_peers: config.alert_min_peers, delay: config.alert_min_peers_minutes * 60 * MILLIS_PER_SECOND });
+        }
+        if config.alert_min_disk_free_mb > 0 {
+            rules.push(AlertRule::LowDiskSpace { min_free_bytes: config.alert_min_disk_free_mb * 1024 * 1024 });
+        }
+        if config.alert_max_mempool_size_bytes > 0 {
+            rules.push(AlertRule::MempoolFull { max_size_bytes: config.alert_max_mempool_size_bytes });
+        }
+
+        let engine = AlertEngine::new(rules, config.alert_cooldown_secs * MILLIS_PER_SECOND);
+        if !engine.has_rules() {
+            return None
+        }
+
+        // Storage directory is created in it
+        let disk_path = match config.dir_path.as_deref() {
+            Some(path) if !path.is_empty() => path.to_owned(),
+            _ => ".".to_owned()
+        };
+        Some(Arc::new(AlertManager::new(engine, disk_path, config.alert_webhook_url.clone(), config.alert_exec.clone())))
+    }
+
+    pub fn get_min_relay_fee_rate(&self) -> u64 {
+        self.min_relay_fee_rate.load(Ordering::SeqCst)
+    }
+
+    // Update the relay minimum at runtime, it can't be lower than the consensus rule
+    // Returns the rate applied
+    pub async fn set_min_relay_fee_rate(&self, rate: u64) -> u64 {
+        let rate = rate.max(FEE_PER_KB);
+        self.min_relay_fee_rate.store(rate, Ordering::SeqCst);
+        self.update_fee_policy().await;
+        rate
+    }
+
+    // Job id, height and difficulty of the current block template
+    // The job id is the same as the one sent by the GetWork server
+    pub async fn get_block_template_job(&self) -> Result<(Hash, u64, Difficulty), BlockchainError> {
+        let storage = self.storage.read().await;
+        let header = self.get_block_template_for_storage(&storage, DEV_PUBLIC_KEY.clone()).await?;
+        let (difficulty, _) = self.get_difficulty_at_tips(&*storage, header.get_tips().iter()).await?;
+        Ok((header.get_work_hash(), header.get_height(), difficulty))
+    }
+
+    pub fn get_rpc_max_blocks_range(&self) -> u64 {
+        self.rpc_max_blocks_range
+    }
+
+    pub async fn get_aux_commitment(&self) -> Option<Hash> {
+        self.aux_commitment.read().await.clone()
+    }
+
+    // Only the templates built after it are affected, jobs already sent keep the previous value
+    pub async fn set_aux_commitment(&self, commitment: Option<Hash>) {
+        match commitment.as_ref() {
+            Some(commitment) => info!("Aux commitment set to {}", commitment),
+            None => info!("Aux commitment removed")
+        };
+        *self.aux_commitment.write().await = commitment;
+    }
+
+    // Estimate the fees of a TX based on its size and transfers
+    // Minimum fee is the highest between the consensus rule and our relay policy
+    // Suggested fee is the one needed to be included in the next block based on the mempool
+    pub async fn estimate_fees(&self, tx_size: usize, transfers: usize, new_addresses: usize) -> EstimateFeesResult {
+        let rate_to_fee = |rate: u64| (rate as u128 * tx_size as u128).div_ceil(1024) as u64;
+        let min_relay_fee_rate = self.get_min_relay_fee_rate();
+        let minimum_fee = Transaction::estimate_fee(tx_size, transfers, new_addresses).max(rate_to_fee(min_relay_fee_rate));
+
+        let next_block_fee_rate = self.mempool.read().await.get_next_block_fee_rate(MAX_BLOCK_SIZE);
+        let suggested_fee_rate = next_block_fee_rate.max(min_relay_fee_rate);
+
+        EstimateFeesResult {
+            minimum_fee,
+            suggested_fee: minimum_fee.max(rate_to_fee(suggested_fee_rate)),
+            min_relay_fee_rate,
+            suggested_fee_rate
+        }
+    }
+
     // Add a tx to the mempool, its hash will be computed
     pub async fn add_tx_to_mempool(&self, tx: Transaction, broadcast: bool) -> Result<(), BlockchainError> {
         let hash = tx.hash();
@@ -1244,50 +2021,133 @@ impl<S: Storage> Blockchain<S> {
     // Add a tx to the mempool with the given hash, it is not computed and the TX is transformed into an Arc
     pub async fn add_tx_to_mempool_with_hash<'a>(&'a self, tx: Transaction, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
         let storage = self.storage.read().await;
+        if storage.is_quarantined() {
+            return Err(BlockchainError::Quarantined)
+        }
         self.add_tx_to_mempool_with_storage_and_hash(&*storage, Arc::new(tx), hash, broadcast).await
     }
 
-    // Add a tx to the mempool with the given hash, it will verify the TX and check that it is not already in mempool or in blockchain
-    // and its validity (nonce, balance, etc...)
-    pub async fn add_tx_to_mempool_with_storage_and_hash<'a>(&'a self, storage: &S, tx: Arc<Transaction>, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
+    // Run all the checks done before adding a TX to the mempool, without adding it nor broadcasting it
+    pub async fn validate_tx(&self, tx: &Arc<Transaction>, hash: &Hash) -> Result<(), BlockchainError> {
+        let storage = self.storage.read().await;
+        if storage.is_quarantined() {
+            return Err(BlockchainError::Quarantined)
+        }
+
         let tx_size = tx.size();
+        self.check_tx_relay_policy(hash, tx, tx_size).await?;
+
+        let mempool = self.mempool.read().await;
+        let replacement = self.is_mempool_replacement(&*storage, &mempool, hash, tx)?;
+        mempool.validate_tx(&*storage, self.get_topo_height(), hash, tx, tx_size, replacement).await
+    }
+
+    // Local checks on the TX before its verification: size, policy and relay fee rate
+    async fn check_tx_relay_policy(&self, hash: &Hash, tx: &Transaction, tx_size: usize) -> Result<(), BlockchainError> {
+        self.check_safe_mode()?;
         if tx_size > MAX_TRANSACTION_SIZE {
             return Err(BlockchainError::TxTooBig(tx_size, MAX_TRANSACTION_SIZE))
         }
 
-        {
-            let mut mempool = self.mempool.write().await;
-    
-            if mempool.contains_tx(&hash) {
-                return Err(BlockchainError::TxAlreadyInMempool(hash))
-            }
-    
-            // check that the TX is not already in blockchain
-            if storage.is_tx_executed_in_a_block(&hash)? {
-                return Err(BlockchainError::TxAlreadyInBlockchain(hash))
-            }
+        // Local policy only, blocks including this TX are still accepted
+        self.tx_policy.read().await.check_mempool_tx(hash, tx)?;
 
-            let current_topoheight = self.get_topo_height();
-            // get the highest nonce available
-            // if presents, it means we have at least one tx from this owner in mempool
-            if let Some(cache) = mempool.get_cache_for(tx.get_source()) {
-                // we accept to delete a tx from mempool if the new one has a higher fee
-                if let Some(hash) = cache.has_tx_with_same_nonce(tx.get_nonce()) {
-                    // A TX with the same nonce is already in mempool
-                    return Err(BlockchainError::TxNonceAlreadyUsed(tx.get_nonce(), hash.as_ref().clone()))
-                }
+        // Relay policy, also local only
+        let fee_rate = get_fee_rate(tx.get_fee(), tx_size);
+        let min_relay_fee_rate = self.get_min_relay_fee_rate();
+        if fee_rate < min_relay_fee_rate {
+            debug!("TX {} has a fee rate of {} below the relay minimum {}", hash, fee_rate, min_relay_fee_rate);
+            return Err(BlockchainError::FeeRateBelowRelayMinimum(fee_rate, min_relay_fee_rate))
+        }
+        Ok(())
+    }
+
+    // Check that the TX is not already known and that its nonce fits the pending TXs of its sender
+    // Returns true if it replaces a TX in mempool
+    fn is_mempool_replacement(&self, storage: &S, mempool: &Mempool, hash: &Hash, tx: &Transaction) -> Result<bool, BlockchainError> {
+        if mempool.contains_tx(hash) {
+            return Err(BlockchainError::TxAlreadyInMempool(hash.clone()))
+        }
 
+        // check that the TX is not already in blockchain
+        if storage.is_tx_executed_in_a_block(hash)? {
+            return Err(BlockchainError::TxAlreadyInBlockchain(hash.clone()))
+        }
+
+        // get the highest nonce available
+        // if presents, it means we have at least one tx from this owner in mempool
+        if let Some(cache) = mempool.get_cache_for(tx.get_source()) {
+            // we accept to replace a tx from mempool if the new one has a higher fee
+            if cache.has_tx_with_same_nonce(tx.get_nonce()).is_some() {
+                return Ok(true)
+            } else if !(tx.get_nonce() <= cache.get_max() + 1 && tx.get_nonce() >= cache.get_min()) {
                 // check that the nonce is in the range
-                if !(tx.get_nonce() <= cache.get_max() + 1 && tx.get_nonce() >= cache.get_min()) {
-                    debug!("TX {} nonce is not in the range of the pending TXs for this owner, received: {}, expected between {} and {}", hash, tx.get_nonce(), cache.get_min(), cache.get_max());
-                    return Err(BlockchainError::InvalidTxNonceMempoolCache(tx.get_nonce(), cache.get_min(), cache.get_max()))
-                }
+                debug!("TX {} nonce is not in the range of the pending TXs for this owner, received: {}, expected between {} and {}", hash, tx.get_nonce(), cache.get_min(), cache.get_max());
+                return Err(BlockchainError::InvalidTxNonceMempoolCache(tx.get_nonce(), cache.get_min(), cache.get_max()))
             }
-
-            mempool.add_tx(storage, current_topoheight, hash.clone(), tx.clone(), tx_size).await?;
         }
+        Ok(false)
+    }
 
-        if broadcast {
+    // Add a tx to the mempool with the given hash, it will verify the TX and check that it is not already in mempool or in blockchain
+    // and its validity (nonce, balance, etc...)
+    pub async fn add_tx_to_mempool_with_storage_and_hash<'a>(&'a self, storage: &S, tx: Arc<Transaction>, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
+        let tx_size = tx.size();
+        self.check_tx_relay_policy(&hash, &tx, tx_size).await?;
+
+        let (replaced, dependents, evicted) = {
+            let mut mempool = self.mempool.write().await;
+            let replacement = self.is_mempool_replacement(storage, &mempool, &hash, &tx)?;
+            let current_topoheight = self.get_topo_height();
+            if replacement {
+                let (replaced, dependents, evicted) = mempool.replace_tx(storage, current_topoheight, hash.clone(), tx.clone(), tx_size).await?;
+                (vec![replaced], dependents, evicted)
+            } else {
+                (Vec::new(), Vec::new(), mempool.add_tx(storage, current_topoheight, hash.clone(), tx.clone(), tx_size).await?)
+            }
+        };
+
+        // Notify the TX replaced by this one
+        // TXs depending on it can't be executed anymore
+        if !replaced.is_empty() {
+            debug!("TX {} replaced, {} TXs depending on it removed", hash, dependents.len());
+            self.notify_txs_removed_from_mempool(replaced, MempoolRemovalReason::Replaced).await;
+            self.notify_txs_removed_from_mempool(dependents, MempoolRemovalReason::Invalid).await;
+        }
+
+        // Notify all the TXs evicted from mempool to make room for this one
+        if !evicted.is_empty() {
+            debug!("{} TXs evicted from mempool while adding {}", evicted.len(), hash);
+            if let Some(rpc) = self.rpc.read().await.as_ref() {
+                if rpc.is_event_tracked(&NotifyEvent::TransactionOrphaned).await {
+                    for (tx_hash, sorted_tx) in evicted.iter() {
+                        let data = RPCTransaction::from_tx(sorted_tx.get_tx(), &tx_hash, storage.is_mainnet());
+                        let data: TransactionResponse<'_> = TransactionResponse {
+                            blocks: None,
+                            executed_in_block: None,
+                            in_mempool: false,
+                            first_seen: Some(sorted_tx.get_first_seen()),
+                            data,
+                        };
+                        let json = json!(data);
+
+                        let rpc = rpc.clone();
+                        spawn_task("rpc-notify-tx-evicted", async move {
+                            if let Err(e) = rpc.notify_clients(&NotifyEvent::TransactionOrphaned, json).await {
+                                debug!("Error while broadcasting event TransactionOrphaned to websocket: {}", e);
+                            }
+                        });
+                    }
+                }
+            }
+            self.notify_txs_removed_from_mempool(evicted, MempoolRemovalReason::Evicted).await;
+        }
+
+        // Evictions raise the mempool fee floor
+        self.update_fee_policy().await;
+        self.notify_block_template_updated().await;
+
+        if broadcast {
             // P2p broadcast to others peers
             if let Some(p2p) = self.p2p.read().await.as_ref() {
                 let p2p = p2p.clone();
@@ -1387,7 +2247,10 @@ impl<S: Storage> Blockchain<S> {
     // Generate a block header template without transactions
     pub async fn get_block_header_template_for_storage(&self, storage: &S, address: PublicKey) -> Result<BlockHeader, BlockchainError> {
         trace!("get block header template");
-        let extra_nonce: [u8; EXTRA_NONCE_SIZE] = rand::thread_rng().gen::<[u8; EXTRA_NONCE_SIZE]>(); // generate random bytes
+        let mut extra_nonce: [u8; EXTRA_NONCE_SIZE] = rand::thread_rng().gen::<[u8; EXTRA_NONCE_SIZE]>(); // generate random bytes
+        if let Some(commitment) = self.aux_commitment.read().await.as_ref() {
+            write_aux_commitment(&mut extra_nonce, commitment);
+        }
         let tips_set = storage.get_tips().await?;
         let mut tips = Vec::with_capacity(tips_set.len());
         for hash in tips_set {
@@ -1440,6 +2303,11 @@ impl<S: Storage> Blockchain<S> {
     // This function is called when a miner request a new block template
     // We create a block candidate with selected TXs from mempool
     pub async fn get_block_template_for_storage(&self, storage: &S, address: PublicKey) -> Result<BlockHeader, BlockchainError> {
+        self.check_safe_mode()?;
+        if storage.is_quarantined() {
+            return Err(BlockchainError::Quarantined)
+        }
+
         let mut block = self.get_block_header_template_for_storage(storage, address).await?;
 
         trace!("Locking mempool for building block template");
@@ -1472,23 +2340,27 @@ impl<S: Storage> Blockchain<S> {
         trace!("build chain state for block template");
         let mut chain_state = ChainState::new(storage, topoheight);
 
-        let mut failed_sources = HashSet::new();
+        let tx_policy = self.tx_policy.read().await;
         while let Some(TxSelectorEntry { size, hash, tx }) = tx_selector.next() {
-            if block_size + total_txs_size + size >= MAX_BLOCK_SIZE {
-                break;
+            let source = tx.get_source();
+            // A smaller TX from another sender may still fit
+            // but the next nonces of this sender can't be included without this one
+            if block_size + total_txs_size + size + HASH_SIZE >= MAX_BLOCK_SIZE {
+                debug!("Skipping TX {} and the next ones of its source: not enough space left in block", hash);
+                tx_selector.skip_source(source);
+                continue;
             }
 
             // Check if the TX is valid for this potential block
-            trace!("Checking TX {} with nonce {}, {}", hash, tx.get_nonce(), tx.get_source().as_address(self.network.is_mainnet()));
-            let source = tx.get_source();
-            if failed_sources.contains(&source) {
-                debug!("Skipping TX {} because its source has failed before", hash);
+            trace!("Checking TX {} with nonce {}, {}", hash, tx.get_nonce(), source.as_address(self.network.is_mainnet()));
+            if tx_policy.is_filtered_for_template(hash, tx) {
+                tx_selector.skip_source(source);
                 continue;
             }
 
             if let Err(e) = tx.verify(&mut chain_state).await {
                 warn!("TX {} ({}) is not valid for mining: {}", hash, source.as_address(self.network.is_mainnet()), e);
-                failed_sources.insert(source);
+                tx_selector.skip_source(source);
             } else {
                 trace!("Selected {} (nonce: {}, fees: {}) for mining", hash, tx.get_nonce(), format_xelis(tx.get_fee()));
                 // TODO no clone
@@ -1501,6 +2373,53 @@ impl<S: Storage> Blockchain<S> {
         Ok(block)
     }
 
+    // Count of mined blocks submitted on stale tips since the node started
+    pub fn get_orphaned_blocks_count(&self) -> u64 {
+        self.orphaned_blocks.load(Ordering::Acquire)
+    }
+
+    // Submit a block header mined by a miner
+    // PoW is verified first to reject invalid work without doing the full validation
+    // A block built on outdated tips is not considered as invalid but as stale
+    // and is counted in the orphaned blocks
+    pub async fn submit_mined_block(&self, header: BlockHeader) -> Result<(SubmitBlockStatus, Hash), BlockchainError> {
+        self.check_safe_mode()?;
+        let block_hash = header.hash();
+        let pow_hash = {
+            let storage = self.storage.read().await;
+            // Difficulty can't be computed if tips are not available anymore
+            for tip in header.get_tips() {
+                if !storage.has_block_with_hash(tip).await? {
+                    debug!("Mined block {} has a tip {} which is not in chain", block_hash, tip);
+                    self.orphaned_blocks.fetch_add(1, Ordering::AcqRel);
+                    return Ok((SubmitBlockStatus::StaleTips, block_hash))
+                }
+            }
+
+            let pow_hash = self.get_pow_hash(&header)?;
+            match self.verify_proof_of_work(&*storage, &pow_hash, header.get_tips().iter()).await {
+                Ok(_) => pow_hash,
+                Err(BlockchainError::InvalidDifficulty) => {
+                    debug!("Mined block {} has an invalid PoW", block_hash);
+                    return Ok((SubmitBlockStatus::InvalidPow, block_hash))
+                },
+                Err(e) => return Err(e)
+            }
+        };
+
+        // PoW is already verified, don't compute it again
+        let block = self.build_block_from_header(Immutable::Owned(header)).await?;
+        match self.add_new_block_with_pow_hash(block, pow_hash, true, true).await {
+            Ok(_) => Ok((SubmitBlockStatus::Accepted, block_hash)),
+            Err(e) if e.is_stale_block() => {
+                debug!("Mined block {} is stale: {}", block_hash, e);
+                self.orphaned_blocks.fetch_add(1, Ordering::AcqRel);
+                Ok((SubmitBlockStatus::StaleTips, block_hash))
+            },
+            Err(e) => Err(e)
+        }
+    }
+
     // Build a block using the header and search for TXs in mempool and storage
     pub async fn build_block_from_header(&self, header: Immutable<BlockHeader>) -> Result<Block, BlockchainError> {
         trace!("Searching TXs for block at height {}", header.get_height());
@@ -1530,9 +2449,24 @@ impl<S: Storage> Blockchain<S> {
         self.add_new_block_for_storage(&mut storage, block, broadcast, mining).await
     }
 
+    // Add a new block in chain with its PoW hash already computed
+    // Used by the sync to compute the PoW hashes in parallel
+    pub async fn add_new_block_with_pow_hash(&self, block: Block, pow_hash: Hash, broadcast: bool, mining: bool) -> Result<(), BlockchainError> {
+        let mut storage = self.storage.write().await;
+        self.add_new_block_for_storage_internal(&mut storage, block, Some(pow_hash), broadcast, mining).await
+    }
+
     // Add a new block in chain using the requested storage
     pub async fn add_new_block_for_storage(&self, storage: &mut S, block: Block, broadcast: bool, mining: bool) -> Result<(), BlockchainError> {
+        self.add_new_block_for_storage_internal(storage, block, None, broadcast, mining).await
+    }
+
+    async fn add_new_block_for_storage_internal(&self, storage: &mut S, block: Block, pow_hash: Option<Hash>, broadcast: bool, mining: bool) -> Result<(), BlockchainError> {
+        self.check_safe_mode()?;
         let start = Instant::now();
+        if storage.is_quarantined() {
+            return Err(BlockchainError::Quarantined)
+        }
 
         // Verify that the block is on the correct version
         if block.get_version() != self.get_version_at_height(block.get_height()) {
@@ -1641,7 +2575,10 @@ impl<S: Storage> Blockchain<S> {
         }
 
         // verify PoW and get difficulty for this block based on tips
-        let pow_hash = block.get_pow_hash()?;
+        let pow_hash = match pow_hash {
+            Some(pow_hash) => pow_hash,
+            None => self.get_pow_hash(&block)?
+        };
         debug!("POW hash: {}", pow_hash);
         let (difficulty, p) = self.verify_proof_of_work(storage, &pow_hash, block.get_tips().iter()).await?;
         debug!("PoW is valid for difficulty {}", difficulty);
@@ -1737,6 +2674,19 @@ impl<S: Storage> Blockchain<S> {
         // Add block to chain
         storage.save_block(block.clone(), &txs, difficulty, p, block_hash.clone()).await?;
 
+        // Remember when we saw the block and its TXs for the first time
+        // TXs waiting in our mempool were seen at their admission
+        {
+            let mempool = self.mempool.read().await;
+            for tx_hash in block.get_transactions() {
+                let first_seen = mempool.get_sorted_tx(tx_hash)
+                    .map(|tx| tx.get_first_seen() * 1000)
+                    .unwrap_or(current_timestamp);
+                storage.set_first_seen_if_absent(tx_hash, first_seen).await?;
+            }
+        }
+        storage.set_first_seen_if_absent(&block_hash, current_timestamp).await?;
+
         // Compute cumulative difficulty for block
         let cumulative_difficulty = {
             let cumulative_difficulty: CumulativeDifficulty = if tips_count == 0 {
@@ -1811,6 +2761,7 @@ impl<S: Storage> Blockchain<S> {
                     }
 
                     debug!("Cleaning transactions executions at topo height {} (block {})", topoheight, hash_at_topo);
+                    self.fee_histogram.lock().await.rewind(topoheight);
 
                     let block = storage.get_block_header_by_hash(&hash_at_topo).await?;
 
@@ -1821,6 +2772,8 @@ impl<S: Storage> Blockchain<S> {
                         let value = json!(BlockOrphanedEvent {
                             block_hash: Cow::Borrowed(&hash_at_topo),
                             old_topoheight: topoheight,
+                            base_hash: Cow::Borrowed(&base_hash),
+                            base_topoheight: base_topo_height
                         });
                         events.entry(NotifyEvent::BlockOrphaned).or_insert_with(Vec::new).push(value);
                     }
@@ -1841,6 +2794,9 @@ impl<S: Storage> Blockchain<S> {
                     storage.delete_versioned_balances_at_topoheight(topoheight).await?;
                     storage.delete_versioned_nonces_at_topoheight(topoheight).await?;
                     storage.delete_registrations_at_topoheight(topoheight).await?;
+                    if self.address_index {
+                        storage.delete_address_index_at_topoheight(topoheight).await?;
+                    }
 
                     topoheight += 1;
                 }
@@ -1903,12 +2859,13 @@ impl<S: Storage> Blockchain<S> {
 
                 storage.set_block_reward_at_topo_height(highest_topo, block_reward)?;
                 
-                let supply = past_supply + block_reward;
+                let supply = checked_add(past_supply, block_reward)?;
                 trace!("set block supply to {} at {}", supply, highest_topo);
                 storage.set_supply_at_topo_height(highest_topo, supply)?;
 
                 // All fees from the transactions executed in this block
                 let mut total_fees = 0;
+                let mut histograms = BlockHistograms::new();
                 // Chain State used for the verification
                 trace!("building chain state to execute TXs in block {}", block_hash);
                 let mut chain_state = ApplicableChainState::new(storage, highest_topo);
@@ -1964,9 +2921,11 @@ impl<S: Storage> Blockchain<S> {
                         }
 
                         // Increase total tx fees for miner
-                        total_fees += tx.get_fee();
+                        total_fees = checked_add(total_fees, tx.get_fee())?;
+                        histograms.observe(tx.get_fee(), tx.size());
                     }
                 }
+                self.fee_histogram.lock().await.set_block(highest_topo, histograms);
 
                 let dev_fee_percentage = get_block_dev_fee(block.get_height());
                 // Dev fee are only applied on block reward
@@ -1978,11 +2937,15 @@ impl<S: Storage> Blockchain<S> {
                 }
                 
                 // reward the miner
-                chain_state.reward_miner(block.get_miner(), block_reward + total_fees).await?;
+                chain_state.reward_miner(block.get_miner(), checked_add(block_reward, total_fees)?).await?;
 
                 // apply changes from Chain State
                 chain_state.apply_changes().await?;
 
+                if self.address_index {
+                    self.index_block_addresses(storage, &hash, &block, highest_topo).await?;
+                }
+
                 if should_track_events.contains(&NotifyEvent::BlockOrdered) {
                     let value = json!(BlockOrderedEvent {
                         block_hash: Cow::Borrowed(&hash),
@@ -2104,6 +3067,23 @@ impl<S: Storage> Blockchain<S> {
         } else {
             Vec::new()
         };
+        self.update_fee_policy_with(rpc_server.as_ref()).await;
+
+        // Notify the TXs that left the mempool, either executed or not valid anymore
+        if should_track_events.contains(&NotifyEvent::TransactionRemovedFromMempool) {
+            for (tx_hash, _) in mempool_deleted_txs.iter() {
+                let reason = if storage.is_tx_executed_in_a_block(tx_hash)? {
+                    MempoolRemovalReason::Mined
+                } else {
+                    MempoolRemovalReason::Invalid
+                };
+                let value = json!(TransactionRemovedFromMempoolEvent {
+                    hash: Cow::Borrowed(tx_hash.as_ref()),
+                    reason
+                });
+                events.entry(NotifyEvent::TransactionRemovedFromMempool).or_insert_with(Vec::new).push(value);
+            }
+        }
 
         if orphan_event_tracked {
             for (tx_hash, sorted_tx) in mempool_deleted_txs {
@@ -2198,6 +3178,14 @@ impl<S: Storage> Blockchain<S> {
                 }
             }
 
+            // Template events are coalesced, no need to skip them while syncing
+            {
+                let rpc = rpc.clone();
+                spawn_task("rpc-notify-block-template", async move {
+                    rpc.notify_block_template_updated().await;
+                });
+            }
+
             // atm, we always notify websocket clients
             trace!("Notifying websocket clients");
             if should_track_events.contains(&NotifyEvent::NewBlock) {
@@ -2262,6 +3250,130 @@ impl<S: Storage> Blockchain<S> {
         self.internal_get_block_reward(past_supply, is_side_block, side_blocks_count).await
     }
 
+    // Re-validate all the blocks stored from `from_topoheight` to `to_topoheight` (inclusive)
+    // Nothing is written, the storage is only locked in read mode while verifying each block
+    // Progress is logged every 1000 blocks and it can be cancelled using `stop`
+    // Returns the first topoheight failing with the reason, or None if no mismatch was found
+    pub async fn verify_chain(&self, from_topoheight: u64, to_topoheight: u64, stop: &AtomicBool) -> Result<Option<(u64, String)>, BlockchainError> {
+        info!("Verifying chain from topoheight {} to {}", from_topoheight, to_topoheight);
+        let pruned_topoheight = self.storage.read().await.get_pruned_topoheight().await?;
+        for topoheight in from_topoheight..=to_topoheight {
+            if stop.load(Ordering::SeqCst) {
+                warn!("Chain verification stopped at topoheight {}", topoheight);
+                return Ok(None)
+            }
+
+            {
+                let storage = self.storage.read().await;
+                if let Some(reason) = self.verify_block_at_topoheight(&storage, topoheight, pruned_topoheight).await? {
+                    return Ok(Some((topoheight, reason)))
+                }
+            }
+
+            let verified = topoheight - from_topoheight + 1;
+            if verified % 1000 == 0 {
+                info!("Chain verification: {} blocks verified, topoheight {}/{}", verified, topoheight, to_topoheight);
+            }
+        }
+
+        info!("Chain verification done, {} blocks verified", to_topoheight + 1 - from_topoheight);
+        Ok(None)
+    }
+
+    // Verify the block stored at this topoheight: its hash, its POW against the stored difficulty,
+    // its TXs signatures, the block reward and supply, and that the stored versioned balances and nonces
+    // are the same as the ones obtained by re-executing the block
+    // Returns the reason of the first mismatch found
+    async fn verify_block_at_topoheight(&self, storage: &S, topoheight: u64, pruned_topoheight: Option<u64>) -> Result<Option<String>, BlockchainError> {
+        let hash = storage.get_hash_at_topo_height(topoheight).await?;
+        let block = storage.get_block_by_hash(&hash).await?;
+
+        let block_hash = block.hash();
+        if block_hash != hash {
+            return Ok(Some(format!("block {} has a computed hash of {}", hash, block_hash)))
+        }
+
+        // Genesis block has no POW
+        if block.get_height() != 0 && !self.is_simulator_enabled() {
+            let difficulty = storage.get_difficulty_for_block_hash(&hash).await?;
+            let pow_hash = self.get_pow_hash(&block)?;
+            if !check_difficulty(&pow_hash, &difficulty)? {
+                return Ok(Some(format!("block {} POW doesn't match its stored difficulty {}", hash, difficulty)))
+            }
+        }
+
+        for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+            if tx.hash() != *tx_hash {
+                return Ok(Some(format!("TX {} in block {} has a different computed hash", tx_hash, hash)))
+            }
+
+            if !tx.verify_signature() {
+                return Ok(Some(format!("TX {} in block {} has an invalid signature", tx_hash, hash)))
+            }
+        }
+
+        let past_supply = if topoheight == 0 {
+            0
+        } else {
+            storage.get_supply_at_topo_height(topoheight - 1).await?
+        };
+
+        let block_reward = storage.get_block_reward_at_topo_height(topoheight)?;
+        // Near the pruned topoheight, we don't know previous blocks to determine if it was a side block
+        // Let's trust its stored reward
+        if pruned_topoheight.map_or(true, |pruned| topoheight.saturating_sub(pruned) > STABLE_LIMIT) {
+            let expected_block_reward = self.get_block_reward(storage, &hash, past_supply, topoheight).await?;
+            if block_reward != expected_block_reward {
+                return Ok(Some(format!("block reward saved for {} is {} while expecting {}", hash, format_xelis(block_reward), format_xelis(expected_block_reward))))
+            }
+        }
+
+        let supply = storage.get_supply_at_topo_height(topoheight).await?;
+        let expected_supply = checked_add(past_supply, block_reward)?;
+        if supply != expected_supply {
+            return Ok(Some(format!("supply saved is {} while expecting {}", format_xelis(supply), format_xelis(expected_supply))))
+        }
+
+        // There is no previous state for the genesis block
+        if topoheight == 0 {
+            return Ok(None)
+        }
+
+        // Re-execute the block on the state right before it
+        let mut chain_state = ChainState::new(storage, topoheight - 1);
+        let mut nonces = HashMap::new();
+        let mut total_fees = 0;
+        for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+            if !storage.is_tx_executed_in_block(tx_hash, &hash)? {
+                continue;
+            }
+
+            if let Err(e) = tx.apply_with_partial_verify(&mut chain_state).await {
+                return Ok(Some(format!("TX {} can't be re-executed: {}", tx_hash, e)))
+            }
+
+            // Same as during the execution, the next nonce is the highest one used + 1
+            let nonce = nonces.entry(tx.get_source()).or_insert(0);
+            *nonce = (*nonce).max(tx.get_nonce() + 1);
+            total_fees = checked_add(total_fees, tx.get_fee())?;
+        }
+
+        for (key, nonce) in nonces {
+            chain_state.update_account_nonce(key, nonce).await?;
+        }
+
+        let mut block_reward = block_reward;
+        let dev_fee_percentage = get_block_dev_fee(block.get_height());
+        if dev_fee_percentage != 0 {
+            let dev_fee_part = block_reward * dev_fee_percentage / 100;
+            chain_state.reward_miner(&DEV_PUBLIC_KEY, dev_fee_part).await?;
+            block_reward -= dev_fee_part;
+        }
+        chain_state.reward_miner(block.get_miner(), checked_add(block_reward, total_fees)?).await?;
+
+        chain_state.verify_changes_at(topoheight).await
+    }
+
     // retrieve all txs hashes until height or until genesis block that were executed in a block
     // for this we get all tips and recursively retrieve all txs from tips until we reach height
     async fn get_all_executed_txs_until_height<P>(&self, provider: &P, until_height: u64, tips: impl Iterator<Item = Hash>) -> Result<HashSet<Hash>, BlockchainError>
@@ -2374,6 +3486,7 @@ impl<S: Storage> Blockchain<S> {
 
     // Rewind the chain by removing N blocks from the top
     pub async fn rewind_chain_for_storage(&self, storage: &mut S, count: u64, stop_at_stable_height: bool) -> Result<u64, BlockchainError> {
+        self.check_safe_mode()?;
         trace!("rewind chain with count = {}", count);
         let current_height = self.get_height();
         let current_topoheight = self.get_topo_height();
@@ -2385,6 +3498,7 @@ impl<S: Storage> Blockchain<S> {
         };
         let (new_height, new_topoheight, txs) = storage.pop_blocks(current_height, current_topoheight, count, until).await?;
         debug!("New topoheight: {} (diff: {})", new_topoheight, current_topoheight - new_topoheight);
+        self.fee_histogram.lock().await.rewind(new_topoheight + 1);
 
         // Try to add all txs back to mempool if possible
         // We try to prevent lost/to be orphaned
@@ -2431,25 +3545,175 @@ impl<S: Storage> Blockchain<S> {
         Ok(new_topoheight)
     }
 
+    // Find the highest topoheight until which every block and its pointers can be read from disk
+    // Returns None if even the lowest block available is not readable
+    pub async fn find_consistent_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
+        let mut storage = self.storage.write().await;
+        // Values must be read from the disk and not from the caches
+        storage.clear_caches().await?;
+
+        let lowest = storage.get_pruned_topoheight().await?.unwrap_or(0);
+        let top = self.get_topo_height();
+        for topoheight in lowest..=top {
+            if let Err(e) = self.check_entries_at_topoheight(&*storage, topoheight).await {
+                warn!("Block at topoheight {} is not consistent: {}", topoheight, e);
+                if topoheight == lowest {
+                    return Ok(None)
+                }
+                return Ok(Some(topoheight - 1))
+            }
+        }
+
+        Ok(Some(top))
+    }
+
+    // Read all the entries stored for the block at this topoheight
+    async fn check_entries_at_topoheight(&self, storage: &S, topoheight: u64) -> Result<(), BlockchainError> {
+        let hash = storage.get_hash_at_topo_height(topoheight).await?;
+        let block = storage.get_block_by_hash(&hash).await?;
+        if block.hash() != hash {
+            return Err(BlockchainError::CorruptedData("blocks".to_owned(), hash.to_hex()))
+        }
+
+        if storage.get_topo_height_for_hash(&hash).await? != topoheight {
+            return Err(BlockchainError::CorruptedData("topo_at_hash".to_owned(), hash.to_hex()))
+        }
+
+        storage.get_difficulty_for_block_hash(&hash).await?;
+        storage.get_cumulative_difficulty_for_block_hash(&hash).await?;
+        storage.get_block_reward_at_topo_height(topoheight)?;
+        storage.get_supply_at_topo_height(topoheight).await?;
+        Ok(())
+    }
+
+    // Rewind the chain to the highest consistent topoheight and leave the recovery mode
+    // Returns the new topoheight
+    pub async fn recover_with_rewind(&self) -> Result<u64, BlockchainError> {
+        self.check_safe_mode()?;
+        let Some(consistent_topoheight) = self.find_consistent_topoheight().await? else {
+            return Err(BlockchainError::Quarantined)
+        };
+
+        let mut storage = self.storage.write().await;
+        let count = self.get_topo_height() - consistent_topoheight;
+        let topoheight = if count > 0 {
+            info!("Rewinding {} blocks to the consistent topoheight {}", count, consistent_topoheight);
+            self.rewind_chain_for_storage(&mut storage, count, false).await?
+        } else {
+            consistent_topoheight
+        };
+
+        storage.clear_corruption();
+        info!("Storage recovered at topoheight {}", topoheight);
+        Ok(topoheight)
+    }
+
     // Calculate the average block time on the last 50 blocks
     // It will return the target block time if we don't have enough blocks
     // We calculate it by taking the timestamp of the block at topoheight - 50 and the timestamp of the block at topoheight
     // It is the same as computing the average time between the last 50 blocks but much faster
     // Genesis block timestamp isn't take in count for this calculation
-    pub async fn get_average_block_time<P>(&self, provider: &P) -> Result<TimestampMillis, BlockchainError>
+    // Compute the stats of the last N blocks ordered
+    // They are cached until a new block is added
+    pub async fn get_stats(&self, blocks: u64) -> Result<GetStatsResult, BlockchainError> {
+        let storage = self.storage.read().await;
+        self.get_stats_for_storage(&*storage, blocks).await
+    }
+
+    // Same as get_stats but using an already locked storage
+    pub async fn get_stats_for_storage(&self, storage: &S, blocks: u64) -> Result<GetStatsResult, BlockchainError> {
+        if blocks == 0 || blocks > MAX_STATS_BLOCKS {
+            return Err(BlockchainError::InvalidStatsBlocks(MAX_STATS_BLOCKS))
+        }
+
+        let topoheight = self.get_topo_height();
+        let key = (storage.get_hash_at_topo_height(topoheight).await?, blocks);
+        if let Some(stats) = self.stats_cache.lock().await.get(&key) {
+            return Ok(stats.clone())
+        }
+
+        let lowest = storage.get_pruned_topoheight().await?.unwrap_or(0);
+        let start_topoheight = (topoheight + 1).saturating_sub(blocks).max(lowest);
+        let mut timestamps = Vec::new();
+        let mut total_size = 0;
+        let mut total_txs = 0;
+        let mut total_fees = 0;
+        let mut supply_emitted = 0;
+        // Blocks synced before first seen timestamps were recorded have none
+        let mut first_seen_delays = Vec::new();
+        for topo in start_topoheight..=topoheight {
+            let hash = storage.get_hash_at_topo_height(topo).await?;
+            let block = storage.get_block_by_hash(&hash).await?;
+            timestamps.push(block.get_timestamp());
+            if let Some(first_seen) = storage.get_first_seen(&hash).await? {
+                first_seen_delays.push(first_seen as i64 - block.get_timestamp() as i64);
+            }
+            total_size += block.size() as u64;
+            total_txs += block.get_txs_count() as u64;
+            for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+                if storage.is_tx_executed_in_block(tx_hash, &hash)? {
+                    total_fees = checked_add(total_fees, tx.get_fee())?;
+                }
+            }
+            supply_emitted = checked_add(supply_emitted, storage.get_block_reward_at_topo_height(topo)?)?;
+        }
+
+        // Timestamps of DAG blocks aren't always increasing in topological order
+        let mut block_times: Vec<TimestampMillis> = timestamps.windows(2)
+            .map(|pair| pair[1].saturating_sub(pair[0]))
+            .collect();
+        block_times.sort_unstable();
+        let median_block_time = match block_times.len() {
+            0 => 0,
+            len if len % 2 == 0 => (block_times[len / 2 - 1] + block_times[len / 2]) / 2,
+            len => block_times[len / 2]
+        };
+
+        let count = timestamps.len() as u64;
+        let average_block_time = match (timestamps.first(), timestamps.last()) {
+            (Some(first), Some(last)) if count > 1 => last.saturating_sub(*first) / (count - 1),
+            _ => 0
+        };
+
+        let supply = storage.get_supply_at_topo_height(topoheight).await?;
+        let stats = GetStatsResult {
+            blocks: count,
+            start_topoheight,
+            end_topoheight: topoheight,
+            average_block_time,
+            median_block_time,
+            average_block_size: total_size / count,
+            average_txs_per_block: total_txs as f64 / count as f64,
+            total_fees,
+            supply_emitted,
+            emission_per_block: get_block_reward(supply),
+            average_first_seen_delay: if first_seen_delays.is_empty() {
+                None
+            } else {
+                Some(first_seen_delays.iter().sum::<i64>() / first_seen_delays.len() as i64)
+            }
+        };
+
+        self.stats_cache.lock().await.put(key, stats.clone());
+        Ok(stats)
+    }
+
+    // Topoheights range used by the block time moving average
+    // None if we don't have enough blocks yet
+    async fn get_average_window<P>(&self, provider: &P) -> Result<Option<(u64, u64)>, BlockchainError>
     where
-        P: DifficultyProvider + PrunedTopoheightProvider + DagOrderProvider
+        P: PrunedTopoheightProvider
     {
         // current topoheight
         let topoheight = self.get_topo_height();
 
-        // we need to get the block hash at topoheight - 50 to compare
-        // if topoheight is 0, returns the target as we don't have any block
+        // we need to get the block hash at topoheight - window to compare
+        // if topoheight is 0, we don't have any block
         // otherwise returns topoheight
-        let mut count = if topoheight > 50 {
-            50
+        let mut count = if topoheight > BLOCK_TIME_AVERAGE_WINDOW {
+            BLOCK_TIME_AVERAGE_WINDOW
         } else if topoheight <= 1 {
-            return Ok(BLOCK_TIME_MILLIS);
+            return Ok(None);
         } else {
             topoheight - 1
         };
@@ -2461,24 +3725,64 @@ impl<S: Storage> Blockchain<S> {
             }
         }
 
-        let now_hash = provider.get_hash_at_topo_height(topoheight).await?;
+        Ok(Some((topoheight - count, topoheight)))
+    }
+
+    pub async fn get_average_block_time<P>(&self, provider: &P) -> Result<TimestampMillis, BlockchainError>
+    where
+        P: DifficultyProvider + PrunedTopoheightProvider + DagOrderProvider
+    {
+        // returns the target as we don't have enough blocks
+        let Some((start, end)) = self.get_average_window(provider).await? else {
+            return Ok(BLOCK_TIME_MILLIS);
+        };
+
+        let now_hash = provider.get_hash_at_topo_height(end).await?;
         let now_timestamp = provider.get_timestamp_for_block_hash(&now_hash).await?;
 
-        let count_hash = provider.get_hash_at_topo_height(topoheight - count).await?;
+        let count_hash = provider.get_hash_at_topo_height(start).await?;
         let count_timestamp = provider.get_timestamp_for_block_hash(&count_hash).await?;
 
         let diff = now_timestamp - count_timestamp;
-        Ok(diff / count)
+        Ok(diff / (end - start))
+    }
+
+    // Estimate the network hashrate in H/s over the block time moving average window
+    // The work done by its blocks is divided by the time spent to mine them
+    pub async fn get_estimated_hashrate<P>(&self, provider: &P) -> Result<Difficulty, BlockchainError>
+    where
+        P: DifficultyProvider + PrunedTopoheightProvider + DagOrderProvider
+    {
+        let difficulty = self.get_difficulty().await;
+        let Some((start, end)) = self.get_average_window(provider).await? else {
+            return Ok(difficulty * MILLIS_PER_SECOND / BLOCK_TIME_MILLIS)
+        };
+
+        let start_hash = provider.get_hash_at_topo_height(start).await?;
+        let start_timestamp = provider.get_timestamp_for_block_hash(&start_hash).await?;
+        let end_hash = provider.get_hash_at_topo_height(end).await?;
+        let end_timestamp = provider.get_timestamp_for_block_hash(&end_hash).await?;
+
+        let elapsed = end_timestamp.saturating_sub(start_timestamp);
+        if elapsed == 0 {
+            return Ok(difficulty * MILLIS_PER_SECOND / BLOCK_TIME_MILLIS)
+        }
+
+        let mut work = Difficulty::zero();
+        for topoheight in start + 1..=end {
+            let hash = provider.get_hash_at_topo_height(topoheight).await?;
+            work += provider.get_difficulty_for_block_hash(&hash).await?;
+        }
+
+        Ok(work * MILLIS_PER_SECOND / elapsed)
     }
 }
 
 
 // Estimate the required fees for a transaction
 pub async fn estimate_required_tx_fees<P: AccountProvider>(provider: &P, current_topoheight: u64, tx: &Transaction) -> Result<u64, BlockchainError> {
-    let mut output_count = 0;
     let mut new_addresses = 0;
     if let TransactionType::Transfers(transfers) = tx.get_data() {
-        output_count = transfers.len();
         for transfer in transfers {
             if !provider.is_account_registered_below_topoheight(transfer.get_destination(), current_topoheight).await? {
                 new_addresses += 1;
@@ -2486,7 +3790,7 @@ pub async fn estimate_required_tx_fees<P: AccountProvider>(provider: &P, current
         }
     }
 
-    Ok(calculate_tx_fee(tx.size(), output_count, new_addresses))
+    Ok(Transaction::estimate_fee(tx.size(), tx.get_outputs_count(), new_addresses))
 }
 
 // Get the block reward for a side block based on how many side blocks exists at same height
@@ -2506,15 +3810,14 @@ pub fn side_block_reward_percentage(side_blocks: u64) -> u64 {
 }
 
 // Calculate the block reward based on the current supply
+// Emission curve is shared with xelis_common to be usable by others tools
 pub fn get_block_reward(supply: u64) -> u64 {
-    // Prevent any overflow
-    if supply >= MAXIMUM_SUPPLY {
-        // Max supply reached, do we want to generate small fixed amount of coins? 
-        return 0
-    }
+    emission::get_block_reward(supply, BLOCK_TIME_MILLIS)
+}
 
-    let base_reward = (MAXIMUM_SUPPLY - supply) >> EMISSION_SPEED_FACTOR;
-    base_reward * BLOCK_TIME_MILLIS / MILLIS_PER_SECOND / 180
+// Name, ticker and maximum supply of XELIS
+pub fn get_native_asset_metadata() -> Result<AssetMetadata, BlockchainError> {
+    Ok(AssetMetadata::new(XELIS_ASSET_NAME.to_owned(), XELIS_ASSET_TICKER.to_owned(), Some(MAXIMUM_SUPPLY))?)
 }
 
 // Returns the fee percentage for a block at a given height
@@ -2543,10 +3846,429 @@ pub fn get_block_dev_fee(height: u64) -> u64 {
 //     Ok(get_combined_hash_for_tips(merkles.iter()))
 // }
 
+// Parse the 32 bytes hex value committed in the extra nonce
+pub fn parse_aux_commitment(value: &str) -> Result<Hash, BlockchainError> {
+    if value.len() != HASH_SIZE * 2 {
+        return Err(BlockchainError::InvalidAuxCommitment(value.to_owned()))
+    }
+
+    Hash::from_hex(value.to_owned()).map_err(|_| BlockchainError::InvalidAuxCommitment(value.to_owned()))
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::core::{
+        storage::SledStorage,
+        testing::{test_blockchain, test_blockchain_with, test_config}
+    };
     use super::*;
 
+    #[tokio::test]
+    async fn test_warm_up() {
+        use xelis_common::crypto::KeyPair;
+
+        let blockchain = test_blockchain().await;
+        assert!(blockchain.is_ready());
+        // Scratch pads were allocated before verifying any PoW
+        assert_eq!(blockchain.scratch_pads.available(), POW_SCRATCH_PADS);
+
+        let key = KeyPair::new().get_public_key().compress();
+        for _ in 0..3 {
+            let block = blockchain.mine_block(&key).await.unwrap();
+            blockchain.add_new_block(block, false, false).await.unwrap();
+        }
+        // Hashes reused the pooled scratch pads
+        assert_eq!(blockchain.scratch_pads.available(), POW_SCRATCH_PADS);
+
+        // Warm-up must also succeed on a chain with blocks and miners
+        blockchain.ready.store(false, Ordering::SeqCst);
+        blockchain.warm_up_internal().await.unwrap();
+        assert_eq!(blockchain.scratch_pads.available(), POW_SCRATCH_PADS);
+    }
+
+    #[tokio::test]
+    async fn test_block_template_waits_warm_up() {
+        use xelis_common::{crypto::KeyPair, rpc_server::RPCHandler};
+        use crate::rpc::rpc::register_methods;
+
+        let blockchain = test_blockchain().await;
+        let address = KeyPair::new().get_public_key().compress().as_address(false);
+        let mut handler = RPCHandler::new(Arc::clone(&blockchain));
+        register_methods(&mut handler, true);
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_block_template", "params": { "address": address } }).to_string();
+
+        // Rejected like the getwork server until the warm-up is done
+        blockchain.ready.store(false, Ordering::SeqCst);
+        assert!(handler.handle_request(request.as_bytes()).await.is_err());
+
+        blockchain.ready.store(true, Ordering::SeqCst);
+        let response = handler.handle_request(request.as_bytes()).await.unwrap();
+        assert!(response["result"]["template"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fees() {
+        let blockchain = test_blockchain().await;
+
+        // Empty mempool, only the consensus rule applies
+        let estimate = blockchain.estimate_fees(1500, 2, 1).await;
+        assert_eq!(estimate.minimum_fee, Transaction::estimate_fee(1500, 2, 1));
+        assert_eq!(estimate.suggested_fee, estimate.minimum_fee);
+        assert_eq!(estimate.suggested_fee_rate, FEE_PER_KB);
+
+        // Relay policy above the consensus rule
+        let blockchain = test_blockchain_with(&["--min-relay-fee-rate", "40000"]).await;
+        let estimate = blockchain.estimate_fees(2048, 0, 0).await;
+        assert_eq!(estimate.minimum_fee, 80000);
+        assert_eq!(estimate.min_relay_fee_rate, 40000);
+
+        // Never below the consensus rule
+        let blockchain = test_blockchain_with(&["--min-relay-fee-rate", "0"]).await;
+        assert_eq!(blockchain.get_min_relay_fee_rate(), FEE_PER_KB);
+    }
+
+    #[tokio::test]
+    async fn test_submit_mined_block_status() {
+        use xelis_common::crypto::KeyPair;
+
+        // PoW is not verified by the simulator
+        let blockchain = test_blockchain_with(&["--simulator", "blockchain"]).await;
+        let key = KeyPair::new().get_public_key().compress();
+        let header = blockchain.get_block_template(key).await.unwrap();
+
+        // Built on a tip we don't know
+        let mut stale = header.clone();
+        stale.tips = [Hash::new([1u8; 32])].into_iter().collect();
+        let (status, hash) = blockchain.submit_mined_block(stale.clone()).await.unwrap();
+        assert!(matches!(status, SubmitBlockStatus::StaleTips));
+        assert_eq!(hash, stale.hash());
+        assert_eq!(blockchain.get_orphaned_blocks_count(), 1);
+
+        let (status, hash) = blockchain.submit_mined_block(header).await.unwrap();
+        assert!(matches!(status, SubmitBlockStatus::Accepted));
+        assert!(blockchain.has_block(&hash).await.unwrap());
+        assert_eq!(blockchain.get_orphaned_blocks_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_submit_mined_block_invalid_pow() {
+        use xelis_common::crypto::KeyPair;
+
+        let blockchain = test_blockchain().await;
+        let key = KeyPair::new().get_public_key().compress();
+        let mut header = blockchain.get_block_template(key).await.unwrap();
+        let (difficulty, _) = {
+            let storage = blockchain.get_storage().read().await;
+            blockchain.get_difficulty_at_tips(&*storage, header.get_tips().iter()).await.unwrap()
+        };
+        while check_difficulty(&blockchain.get_pow_hash(&header).unwrap(), &difficulty).unwrap() {
+            header.nonce += 1;
+        }
+
+        let (status, hash) = blockchain.submit_mined_block(header).await.unwrap();
+        assert!(matches!(status, SubmitBlockStatus::InvalidPow));
+        assert!(!blockchain.has_block(&hash).await.unwrap());
+        assert_eq!(blockchain.get_orphaned_blocks_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_hashrate() {
+        let blockchain = test_blockchain().await;
+
+        // Not enough blocks for the moving average, the target block time is used
+        let storage = blockchain.get_storage().read().await;
+        let difficulty = blockchain.get_difficulty().await;
+        assert_eq!(blockchain.get_average_block_time(&*storage).await.unwrap(), BLOCK_TIME_MILLIS);
+        assert_eq!(blockchain.get_estimated_hashrate(&*storage).await.unwrap(), difficulty * MILLIS_PER_SECOND / BLOCK_TIME_MILLIS);
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let blockchain = test_blockchain().await;
+
+        assert!(matches!(blockchain.get_stats(0).await, Err(BlockchainError::InvalidStatsBlocks(MAX_STATS_BLOCKS))));
+        assert!(matches!(blockchain.get_stats(MAX_STATS_BLOCKS + 1).await, Err(BlockchainError::InvalidStatsBlocks(_))));
+
+        // Only the genesis block is available
+        let stats = blockchain.get_stats(10).await.unwrap();
+        assert_eq!(stats.blocks, 1);
+        assert_eq!((stats.start_topoheight, stats.end_topoheight), (0, 0));
+        assert_eq!((stats.average_block_time, stats.median_block_time), (0, 0));
+        assert_eq!(stats.total_fees, 0);
+
+        let storage = blockchain.get_storage().read().await;
+        let genesis = storage.get_block_by_hash(&storage.get_hash_at_topo_height(0).await.unwrap()).await.unwrap();
+        assert_eq!(stats.average_block_size, genesis.size() as u64);
+        assert_eq!(stats.supply_emitted, storage.get_supply_at_topo_height(0).await.unwrap());
+        assert_eq!(stats.emission_per_block, get_block_reward(stats.supply_emitted));
+        drop(storage);
+
+        assert!(blockchain.stats_cache.lock().await.contains(&(blockchain.get_top_block_hash().await.unwrap(), 10)));
+    }
+
+    #[tokio::test]
+    async fn test_first_seen() {
+        use xelis_common::crypto::KeyPair;
+        use crate::core::storage::FirstSeenProvider;
+
+        let blockchain = test_blockchain().await;
+
+        let key = KeyPair::new().get_public_key().compress();
+        let mut hashes = Vec::new();
+        for _ in 0..2 {
+            let block = blockchain.mine_block(&key).await.unwrap();
+            hashes.push(block.hash());
+            let before = get_current_time_in_millis();
+            blockchain.add_new_block(block, false, false).await.unwrap();
+            let first_seen = blockchain.get_storage().read().await.get_first_seen(hashes.last().unwrap()).await.unwrap().unwrap();
+            assert!(first_seen >= before && first_seen <= get_current_time_in_millis());
+        }
+
+        // Already stored values are never overwritten
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            let first_seen = storage.get_first_seen(&hashes[0]).await.unwrap();
+            assert!(!storage.set_first_seen_if_absent(&hashes[0], 1).await.unwrap());
+            assert_eq!(storage.get_first_seen(&hashes[0]).await.unwrap(), first_seen);
+        }
+
+        let stats = blockchain.get_stats(10).await.unwrap();
+        assert!(stats.average_first_seen_delay.is_some());
+
+        // Deleted with the block
+        blockchain.rewind_chain(1, false).await.unwrap();
+        let storage = blockchain.get_storage().read().await;
+        assert_eq!(storage.get_first_seen(&hashes[1]).await.unwrap(), None);
+        assert!(storage.get_first_seen(&hashes[0]).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_safe_mode_is_read_only() {
+        use xelis_common::{api::daemon::GetInfoResult, json_rpc::{JsonRPCError, WebSocketJsonRPCClientImpl}};
+
+        // Write-implying flags and empty storages are refused
+        let config = test_config(&["--disable-rpc-server", "--safe-mode", "--auto-prune-keep-n-blocks", "100"]);
+        let storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        let err = Blockchain::new(config, Network::Dev, storage).await.err().unwrap();
+        assert!(matches!(err.downcast::<BlockchainError>(), Ok(BlockchainError::ConfigSafeMode(_))));
+
+        let config = test_config(&["--disable-rpc-server", "--safe-mode"]);
+        let storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        let err = Blockchain::new(config, Network::Dev, storage).await.err().unwrap();
+        assert!(matches!(err.downcast::<BlockchainError>(), Ok(BlockchainError::SafeMode)));
+
+        // Copy an existing chain in a new storage
+        let blockchain = test_blockchain().await;
+        let path = std::env::temp_dir().join(format!("xelis-safe-mode-{}.bin", rand::thread_rng().gen::<u64>()));
+        let path = path.to_str().unwrap();
+        blockchain.export_snapshot(path, 0).await.unwrap();
+        let mut storage = SledStorage::temporary(Some(DEFAULT_CACHE_SIZE), Network::Dev).unwrap();
+        snapshot::import_snapshot(&mut storage, Network::Dev, path).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let rpc_address = format!("127.0.0.1:{}", port);
+        let config = test_config(&["--safe-mode", "--enable-address-index", "--rpc-bind-address", &rpc_address]);
+        let checksum = storage.checksum().unwrap();
+        let blockchain = Blockchain::new(config, Network::Dev, storage).await.unwrap();
+        assert!(blockchain.is_safe_mode());
+        assert!(blockchain.get_p2p().read().await.is_none());
+        assert!(blockchain.get_rpc().read().await.as_ref().unwrap().getwork_server().is_none());
+        // Address index creation is deferred
+        assert!(!blockchain.is_address_index_enabled());
+
+        let client = WebSocketJsonRPCClientImpl::<NotifyEvent>::new(format!("ws://{}/json_rpc", rpc_address)).await.unwrap();
+        let info: GetInfoResult = client.call("get_info").await.unwrap();
+        assert!(info.safe_mode);
+        let _: Value = client.call("get_top_block").await.unwrap();
+        let _: Value = client.call("get_tips").await.unwrap();
+        let _: Value = client.call("get_mempool").await.unwrap();
+        let _: Value = client.call("count_accounts").await.unwrap();
+        let _: Value = client.call_with("get_block_at_topoheight", &json!({ "topoheight": 0, "include_txs": true })).await.unwrap();
+        let _: Value = client.call_with("get_blocks_range_by_topoheight", &json!({ "start_topoheight": 0 })).await.unwrap();
+        let _: Value = client.call_with("has_balance", &json!({ "address": DEV_PUBLIC_KEY.as_address(false), "asset": XELIS_ASSET })).await.unwrap();
+        let _: Value = client.call_with("get_assets", &json!({})).await.unwrap();
+
+        // Mutating methods are refused
+        let result: Result<bool, _> = client.call_with("submit_transaction", &json!({ "data": "00" })).await;
+        assert!(matches!(result, Err(JsonRPCError::ServerError { .. })));
+        let result: Result<Value, _> = client.call_with("submit_block", &json!({ "block_template": "00" })).await;
+        assert!(matches!(result, Err(JsonRPCError::ServerError { .. })));
+        assert!(matches!(blockchain.get_block_template(DEV_PUBLIC_KEY.clone()).await, Err(BlockchainError::SafeMode)));
+        assert!(matches!(blockchain.prune_until_topoheight(1).await, Err(BlockchainError::SafeMode)));
+        assert!(matches!(blockchain.rewind_chain(1, false).await, Err(BlockchainError::SafeMode)));
+
+        // Nothing was written during the session
+        assert_eq!(blockchain.get_storage().read().await.checksum().unwrap(), checksum);
+    }
+
+    #[tokio::test]
+    async fn test_native_asset_metadata() {
+        use xelis_common::rpc_server::RPCHandler;
+        use crate::{core::storage::AssetProvider, rpc::rpc::register_methods};
+
+        let blockchain = test_blockchain().await;
+        let mut handler = RPCHandler::new(Arc::clone(&blockchain));
+        register_methods(&mut handler, false);
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_asset", "params": { "asset": XELIS_ASSET } }).to_string();
+
+        // Registered with the genesis block
+        let response = handler.handle_request(request.as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["decimals"], COIN_DECIMALS);
+        assert_eq!(response["result"]["name"], XELIS_ASSET_NAME);
+        assert_eq!(response["result"]["ticker"], XELIS_ASSET_TICKER);
+        assert_eq!(response["result"]["max_supply"], MAXIMUM_SUPPLY);
+
+        // Chain created before the metadata
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            let count = storage.count_assets().await.unwrap();
+            storage.update_asset(&XELIS_ASSET, AssetData::new(0, COIN_DECIMALS)).await.unwrap();
+            assert_eq!(storage.count_assets().await.unwrap(), count);
+        }
+        let response = handler.handle_request(request.as_bytes()).await.unwrap();
+        assert!(response["result"].get("name").is_none());
+
+        blockchain.register_native_asset_metadata().await.unwrap();
+        let response = handler.handle_request(request.as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["ticker"], XELIS_ASSET_TICKER);
+        assert_eq!(response["result"]["topoheight"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain() {
+        use xelis_common::{
+            account::{CiphertextCache, VersionedBalance, VersionedNonce},
+            config::COIN_VALUE,
+            crypto::KeyPair,
+            testing::build_transaction,
+            transaction::{builder::TransactionTypeBuilder, BurnPayload, Reference}
+        };
+        use crate::core::{storage::{BalanceProvider, NonceProvider}, testing::fund_account};
+
+        let blockchain = test_blockchain_with(&["--simulator", "blockchain"]).await;
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        // Sender is also rewarded in the same block
+        let reference = Reference {
+            hash: blockchain.get_storage().read().await.get_hash_at_topo_height(0).await.unwrap(),
+            topoheight: 0
+        };
+        let tx = build_transaction(&keypair, balance, 0, reference, TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE }));
+        blockchain.add_tx_to_mempool(tx, false).await.unwrap();
+        let block = blockchain.mine_block(&key).await.unwrap();
+        blockchain.add_new_block(block, false, false).await.unwrap();
+
+        let (topoheight, nonce) = blockchain.get_storage().read().await.get_last_nonce(&key).await.unwrap();
+        assert_eq!(nonce.get_nonce(), 1);
+        assert_eq!(blockchain.verify_chain(0, topoheight, &AtomicBool::new(false)).await.unwrap(), None);
+
+        // Stopped before verifying anything
+        assert_eq!(blockchain.verify_chain(0, topoheight, &AtomicBool::new(true)).await.unwrap(), None);
+
+        // Corrupted balance of the sender
+        let version = VersionedBalance::new(CiphertextCache::Decompressed(keypair.get_public_key().encrypt(balance)), None);
+        blockchain.get_storage().write().await.set_last_balance_to(&key, &XELIS_ASSET, topoheight, &version).await.unwrap();
+        let (topo, reason) = blockchain.verify_chain(0, topoheight, &AtomicBool::new(false)).await.unwrap().unwrap();
+        assert_eq!(topo, topoheight);
+        assert!(reason.starts_with("balance"));
+
+        blockchain.get_storage().write().await.set_last_nonce_to(&key, topoheight, &VersionedNonce::new(5, None)).await.unwrap();
+        let (topo, reason) = blockchain.verify_chain(0, topoheight, &AtomicBool::new(false)).await.unwrap().unwrap();
+        assert_eq!(topo, topoheight);
+        assert!(reason.starts_with("nonce of"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_tx() {
+        use xelis_common::{
+            config::COIN_VALUE,
+            crypto::KeyPair,
+            testing::{build_transaction, build_transaction_with_fee},
+            transaction::{builder::{FeeBuilder, TransactionTypeBuilder}, BurnPayload, Reference}
+        };
+        use crate::core::testing::fund_account;
+
+        let blockchain = test_blockchain().await;
+
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let burn = || TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE });
+        let tx = Arc::new(build_transaction(&keypair, balance, 0, reference.clone(), burn()));
+        let hash = tx.hash();
+
+        // Valid but not added
+        blockchain.validate_tx(&tx, &hash).await.unwrap();
+        assert!(blockchain.get_mempool().read().await.get_txs().is_empty());
+
+        blockchain.add_tx_to_mempool(tx.as_ref().clone(), false).await.unwrap();
+        assert!(matches!(blockchain.validate_tx(&tx, &hash).await, Err(BlockchainError::TxAlreadyInMempool(_))));
+
+        let gap = Arc::new(build_transaction(&keypair, balance, 5, reference.clone(), burn()));
+        assert!(matches!(blockchain.validate_tx(&gap, &gap.hash()).await, Err(BlockchainError::InvalidTxNonceMempoolCache(5, 0, 0))));
+
+        // Replacements are checked without touching the pending TX
+        let required_fee = blockchain.get_mempool().read().await.get_replacement_fee(tx.get_fee());
+        let low = Arc::new(build_transaction_with_fee(&keypair, balance, 0, reference.clone(), burn(), FeeBuilder::Value(required_fee - 1)));
+        assert!(matches!(blockchain.validate_tx(&low, &low.hash()).await, Err(BlockchainError::ReplacementFeeTooLow(_, _, _))));
+        let replacement = Arc::new(build_transaction_with_fee(&keypair, balance, 0, reference, burn(), FeeBuilder::Value(required_fee)));
+        blockchain.validate_tx(&replacement, &replacement.hash()).await.unwrap();
+
+        let mempool = blockchain.get_mempool().read().await;
+        assert_eq!(mempool.size(), 1);
+        assert!(mempool.contains_tx(&hash));
+    }
+
+    #[tokio::test]
+    async fn test_amount_overflow_is_rejected() {
+        use xelis_common::{
+            amount::AmountOverflow,
+            config::COIN_VALUE,
+            crypto::KeyPair,
+            testing::build_transaction,
+            transaction::{builder::TransactionTypeBuilder, verify::VerificationError, BurnPayload, Reference}
+        };
+        use crate::core::testing::fund_account;
+
+        let blockchain = test_blockchain().await;
+
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let tx = build_transaction(&keypair, balance, 0, reference, TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE }));
+
+        // Rewrite the fee so burned amount + fee goes past u64::MAX
+        // Layout: version (1), source (32), burn type (1), asset (32), amount (8), fee (8)
+        let mut bytes = tx.to_bytes();
+        bytes[74..82].copy_from_slice(&u64::MAX.to_be_bytes());
+        let tx = Transaction::from_bytes(&bytes).unwrap();
+        assert_eq!(tx.get_fee(), u64::MAX);
+
+        assert!(matches!(blockchain.add_tx_to_mempool(tx.clone(), false).await, Err(BlockchainError::AmountOverflow(AmountOverflow))));
+        assert!(blockchain.get_mempool().read().await.get_txs().is_empty());
+
+        // Same result when the TX is verified as part of a block
+        let storage = blockchain.get_storage().read().await;
+        let mut chain_state = ChainState::new(&*storage, blockchain.get_topo_height());
+        assert!(matches!(Transaction::verify_batch(&[Arc::new(tx)], &mut chain_state).await, Err(VerificationError::AmountOverflow(AmountOverflow))));
+    }
+
     #[test]
     fn test_reward_side_block_percentage() {
         assert_eq!(side_block_reward_percentage(0), SIDE_BLOCK_REWARD_PERCENT);
@@ -2570,4 +4292,87 @@ mod tests {
         assert_eq!(get_block_dev_fee(DEV_FEES[1].height), 5);
         assert_eq!(get_block_dev_fee(DEV_FEES[1].height + 1), 5);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_corruption_detection() {
+        use crate::core::storage::{BlockDagProvider, CORRUPTION_THRESHOLD};
+
+        let blockchain = test_blockchain().await;
+        let genesis_hash = blockchain.get_top_block_hash().await.unwrap();
+        assert_eq!(blockchain.find_consistent_topoheight().await.unwrap(), Some(0));
+
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            storage.repair_entry("supply", &0u64.to_be_bytes(), &[1, 2, 3]).await.unwrap();
+            storage.repair_entry("blocks", genesis_hash.as_bytes(), &[0xFF; 4]).await.unwrap();
+        }
+
+        // A single failed read is not enough
+        {
+            let storage = blockchain.get_storage().read().await;
+            assert!(matches!(storage.get_supply_at_topo_height(0).await, Err(BlockchainError::CorruptedData(_, _))));
+            assert!(!storage.is_quarantined());
+            for _ in 1..CORRUPTION_THRESHOLD {
+                assert!(storage.get_supply_at_topo_height(0).await.is_err());
+            }
+            assert!(storage.is_quarantined());
+        }
+
+        // Reads still work but nothing new is accepted
+        assert!(matches!(blockchain.get_block_template(DEV_PUBLIC_KEY.clone()).await, Err(BlockchainError::Quarantined)));
+        assert_eq!(blockchain.find_consistent_topoheight().await.unwrap(), None);
+
+        let storage = blockchain.get_storage().read().await;
+        let report = storage.get_corruption_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].tree, "blocks");
+        assert_eq!(report[0].key, genesis_hash.to_hex());
+        assert_eq!(report[1].tree, "supply");
+        assert_eq!(storage.get_corrupted_keys("blocks"), vec![genesis_hash.as_bytes().to_vec()]);
+    }
+
+    // Mines real blocks at the devnet difficulty, run it with --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_corruption_rewind_recovery() {
+        use xelis_common::crypto::KeyPair;
+        use crate::core::storage::{BlockDagProvider, CORRUPTION_THRESHOLD};
+
+        let blockchain = test_blockchain().await;
+
+        let key = KeyPair::new().get_public_key().compress();
+        for _ in 0..3 {
+            let block = blockchain.mine_block(&key).await.unwrap();
+            blockchain.add_new_block(block, false, false).await.unwrap();
+        }
+        assert_eq!(blockchain.get_topo_height(), 3);
+
+        // Corrupt the reward of topoheight 2
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            storage.repair_entry("rewards", &2u64.to_be_bytes(), &[0xAB]).await.unwrap();
+            for _ in 0..CORRUPTION_THRESHOLD {
+                assert!(storage.get_block_reward_at_topo_height(2).is_err());
+            }
+            assert!(storage.is_quarantined());
+        }
+
+        let block = blockchain.mine_block(&key).await;
+        assert!(matches!(block, Err(BlockchainError::Quarantined)));
+        assert_eq!(blockchain.find_consistent_topoheight().await.unwrap(), Some(1));
+
+        assert_eq!(blockchain.recover_with_rewind().await.unwrap(), 1);
+        assert_eq!(blockchain.get_topo_height(), 1);
+        {
+            let storage = blockchain.get_storage().read().await;
+            assert!(!storage.is_quarantined());
+            assert!(storage.get_corruption_report().is_empty());
+        }
+
+        // The chain can grow again
+        let block = blockchain.mine_block(&key).await.unwrap();
+        blockchain.add_new_block(block, false, false).await.unwrap();
+        assert_eq!(blockchain.get_topo_height(), 2);
+        assert_eq!(blockchain.find_consistent_topoheight().await.unwrap(), Some(2));
+    }
+}
@@ -2,6 +2,8 @@ use crate::p2p::error::P2pError;
 use std::sync::PoisonError;
 use thiserror::Error;
 use xelis_common::{
+    amount::AmountOverflow,
+    asset::AssetMetadataError,
     crypto::{
         bech32::Bech32Error,
         elgamal::DecompressionError,
@@ -11,6 +13,7 @@ use xelis_common::{
         XelisHashError
     },
     difficulty::DifficultyError,
+    network::Network,
     prompt::PromptError,
     rpc_server::InternalRpcError,
     serializer::ReaderError,
@@ -305,12 +308,76 @@ pub enum BlockchainError {
     TransactionProof(ProofVerificationError),
     #[error("Error while generating pow hash")]
     POWHashError(#[from] XelisHashError),
+    #[error("Invalid asset metadata: {}", _0)]
+    AssetMetadata(#[from] AssetMetadataError),
+    #[error("Fee too low for mempool, minimum fee rate is {} per kB", format_xelis(*_0))]
+    FeeTooLowForMempool(u64),
+    #[error("Miner work doesn't match the block template")]
+    InvalidMinerWork,
+    #[error("Invalid snapshot file")]
+    InvalidSnapshot,
+    #[error("Unsupported snapshot format version {}", _0)]
+    UnsupportedSnapshotVersion(u8),
+    #[error("Snapshot is for {} network while node is running on {}", _0, _1)]
+    SnapshotNetworkMismatch(Network, Network),
+    #[error("Snapshot checksum doesn't match, file is corrupted")]
+    SnapshotChecksumMismatch,
+    #[error("A snapshot can only be imported in an empty storage")]
+    SnapshotStorageNotEmpty,
+    #[error("Snapshot topoheight {} is above the stable topoheight {}", _0, _1)]
+    SnapshotTopoHeightNotStable(u64, u64),
+    #[error("A snapshot can't be exported from a pruned chain")]
+    SnapshotPrunedChain,
+    #[error("TX {} is filtered by the local TX policy", _0)]
+    TxFilteredByPolicy(Hash),
+    #[error("Invalid TX policy entry '{}', expected an address or a TX hash", _0)]
+    InvalidTxPolicyEntry(String),
+    #[error("No TX policy file configured")]
+    NoTxPolicyFile,
+    #[error("Corrupted entry in tree {} for key {}", _0, _1)]
+    CorruptedData(String, String),
+    #[error("Storage is in recovery mode due to corrupted data")]
+    Quarantined,
+    #[error("Operation not allowed, node is running in safe mode")]
+    SafeMode,
+    #[error("Safe mode can't be used with {}", _0)]
+    ConfigSafeMode(&'static str),
+    #[error("Invalid blocks count for stats, must be between 1 and {}", _0)]
+    InvalidStatsBlocks(u64),
+    #[error("Fee too low to replace TX {}, expected at least {}, got {}", _0, format_xelis(*_1), format_xelis(*_2))]
+    ReplacementFeeTooLow(Hash, u64, u64),
+    #[error("Fee rate {} per kB is below the minimum relay fee rate {} per kB", format_xelis(*_0), format_xelis(*_1))]
+    FeeRateBelowRelayMinimum(u64, u64),
+    #[error("Invalid replay file")]
+    InvalidReplayFile,
+    #[error("Unsupported replay format version {}", _0)]
+    UnsupportedReplayVersion(u8),
+    #[error("Replay file is for {} network while node is running on {}", _0, _1)]
+    ReplayNetworkMismatch(Network, Network),
+    #[error("Invalid blocks export range from topoheight {} to {}", _0, _1)]
+    InvalidExportRange(u64, u64),
+    #[error(transparent)]
+    AmountOverflow(#[from] AmountOverflow),
+    #[error("Invalid aux commitment '{}', expected a 32 bytes value in hex format", _0)]
+    InvalidAuxCommitment(String),
 }
 
 impl BlockchainError {
     pub unsafe fn id(&self) -> usize {
         *(self as *const Self as *const _)
     }
+
+    // Is this error caused by a block built on tips that are outdated
+    // This happens when a miner submit a block from an old template
+    pub fn is_stale_block(&self) -> bool {
+        matches!(self,
+            Self::InvalidTipsNotFound(_, _)
+            | Self::InvalidTipsDifficulty(_, _)
+            | Self::InvalidBlockHeightStableHeight
+            | Self::InvalidReachability
+            | Self::BlockDeviation
+        )
+    }
 }
 
 impl From<BlockchainError> for InternalRpcError {
@@ -333,7 +400,8 @@ impl From<VerificationError<BlockchainError>> for BlockchainError {
             VerificationError::SenderIsReceiver => BlockchainError::NoSenderOutput,
             VerificationError::InvalidSignature => BlockchainError::InvalidTransactionSignature,
             VerificationError::State(s) => s,
-            VerificationError::Proof(proof) => BlockchainError::TransactionProof(proof)
+            VerificationError::Proof(proof) => BlockchainError::TransactionProof(proof),
+            VerificationError::AmountOverflow(e) => BlockchainError::AmountOverflow(e)
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, fmt::Write};
+use xelis_common::api::daemon::{HistogramBucket, HistogramResult};
+
+// Upper bounds (included) of the buckets, in atomic units for fees
+// and in bytes for sizes. A last bucket counts everything above
+pub const FEE_BUCKETS: [u64; 8] = [1000, 2500, 5000, 10_000, 25_000, 50_000, 100_000, 1_000_000];
+pub const SIZE_BUCKETS: [u64; 8] = [256, 512, 1024, 2048, 4096, 8192, 16_384, 65_536];
+
+// Distribution of values over fixed buckets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    bounds: &'static [u64],
+    // count per bucket, not cumulative
+    counts: Vec<u64>,
+    sum: u64
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [u64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0
+        }
+    }
+
+    pub fn observe(&mut self, value: u64) {
+        let index = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[index] += 1;
+        self.sum += value;
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        for (count, value) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += value;
+        }
+        self.sum += other.sum;
+    }
+
+    pub fn subtract(&mut self, other: &Self) {
+        for (count, value) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count -= value;
+        }
+        self.sum -= other.sum;
+    }
+
+    pub fn get_counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn to_result(&self) -> HistogramResult {
+        let buckets = self.counts.iter().enumerate()
+            .map(|(i, count)| HistogramBucket {
+                le: self.bounds.get(i).copied(),
+                count: *count
+            })
+            .collect();
+
+        HistogramResult {
+            buckets,
+            sum: self.sum,
+            count: self.count()
+        }
+    }
+
+    // Write it in the Prometheus text format, buckets are cumulative
+    pub fn write_prometheus(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            let _ = match self.bounds.get(i) {
+                Some(bound) => writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative),
+                None => writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, cumulative)
+            };
+        }
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, cumulative);
+    }
+}
+
+// Fees and sizes of the TXs executed in a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHistograms {
+    pub fees: Histogram,
+    pub sizes: Histogram
+}
+
+impl BlockHistograms {
+    pub fn new() -> Self {
+        Self {
+            fees: Histogram::new(&FEE_BUCKETS),
+            sizes: Histogram::new(&SIZE_BUCKETS)
+        }
+    }
+
+    pub fn observe(&mut self, fee: u64, size: usize) {
+        self.fees.observe(fee);
+        self.sizes.observe(size as u64);
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.fees.add(&other.fees);
+        self.sizes.add(&other.sizes);
+    }
+
+    fn subtract(&mut self, other: &Self) {
+        self.fees.subtract(&other.fees);
+        self.sizes.subtract(&other.sizes);
+    }
+}
+
+// Histograms of the last ordered blocks, indexed by topoheight
+// The total of the whole window is kept up to date for the metrics
+pub struct FeeHistogram {
+    window: u64,
+    blocks: BTreeMap<u64, BlockHistograms>,
+    total: BlockHistograms
+}
+
+impl FeeHistogram {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            blocks: BTreeMap::new(),
+            total: BlockHistograms::new()
+        }
+    }
+
+    pub fn get_window(&self) -> u64 {
+        self.window
+    }
+
+    // Set the block ordered at this topoheight, replacing the previous one if any
+    // Blocks going out of the window are removed
+    pub fn set_block(&mut self, topoheight: u64, histograms: BlockHistograms) {
+        self.total.add(&histograms);
+        if let Some(previous) = self.blocks.insert(topoheight, histograms) {
+            self.total.subtract(&previous);
+        }
+
+        let Some(highest) = self.blocks.keys().next_back().copied() else {
+            return
+        };
+        while let Some(entry) = self.blocks.first_entry() {
+            if *entry.key() + self.window > highest {
+                break;
+            }
+            self.total.subtract(&entry.remove());
+        }
+    }
+
+    // Remove all blocks at or above this topoheight
+    pub fn rewind(&mut self, topoheight: u64) {
+        for (_, histograms) in self.blocks.split_off(&topoheight) {
+            self.total.subtract(&histograms);
+        }
+    }
+
+    // Aggregate the last N blocks known
+    // Returns the number of blocks aggregated
+    pub fn aggregate(&self, window: u64) -> (usize, BlockHistograms) {
+        if window == 0 {
+            return (0, BlockHistograms::new())
+        }
+
+        if window >= self.window {
+            return (self.blocks.len(), self.total.clone())
+        }
+
+        let mut total = BlockHistograms::new();
+        let mut count = 0;
+        if let Some(highest) = self.blocks.keys().next_back() {
+            for (_, histograms) in self.blocks.range(highest.saturating_sub(window - 1)..) {
+                total.add(histograms);
+                count += 1;
+            }
+        }
+        (count, total)
+    }
+
+    pub fn get_total(&self) -> &BlockHistograms {
+        &self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(txs: &[(u64, usize)]) -> BlockHistograms {
+        let mut histograms = BlockHistograms::new();
+        for (fee, size) in txs {
+            histograms.observe(*fee, *size);
+        }
+        histograms
+    }
+
+    #[test]
+    fn test_buckets() {
+        let histograms = block(&[(1000, 100), (1001, 256), (30_000, 257), (2_000_000, 100_000)]);
+        assert_eq!(histograms.fees.get_counts(), &[1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(histograms.sizes.get_counts(), &[2, 1, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(histograms.fees.to_result().sum, 1000 + 1001 + 30_000 + 2_000_000);
+
+        let mut out = String::new();
+        histograms.fees.write_prometheus(&mut out, "xelis_tx_fee", "Fees");
+        assert!(out.contains("xelis_tx_fee_bucket{le=\"2500\"} 2\n"));
+        assert!(out.contains("xelis_tx_fee_bucket{le=\"+Inf\"} 4\n"));
+        assert!(out.contains("xelis_tx_fee_count 4\n"));
+    }
+
+    #[test]
+    fn test_window_and_reorg() {
+        let mut histogram = FeeHistogram::new(3);
+        histogram.set_block(0, block(&[(1000, 300)]));
+        histogram.set_block(1, block(&[(5000, 300), (5000, 300)]));
+        histogram.set_block(2, block(&[]));
+        assert_eq!(histogram.get_total().fees.count(), 3);
+
+        // Block at topoheight 0 leaves the window
+        histogram.set_block(3, block(&[(60_000, 1000)]));
+        assert_eq!(histogram.get_total().fees.get_counts(), &[0, 0, 2, 0, 0, 0, 1, 0, 0]);
+
+        let (blocks, last) = histogram.aggregate(2);
+        assert_eq!(blocks, 2);
+        assert_eq!(last.fees.count(), 1);
+
+        // Reorg from topoheight 2: blocks 2 and 3 are ordered again differently
+        histogram.rewind(2);
+        assert_eq!(histogram.get_total().fees.count(), 2);
+        histogram.set_block(2, block(&[(1000, 300)]));
+        histogram.set_block(3, block(&[]));
+        assert_eq!(histogram.get_total().fees.get_counts(), &[1, 0, 2, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(histogram.get_total().sizes.get_counts(), &[0, 3, 0, 0, 0, 0, 0, 0, 0]);
+
+        // Same as aggregating everything from scratch
+        let mut expected = BlockHistograms::new();
+        for (_, histograms) in histogram.blocks.iter() {
+            expected.add(histograms);
+        }
+        assert_eq!(*histogram.get_total(), expected);
+    }
+}
@@ -17,11 +17,18 @@ use xelis_common::{
     network::Network,
     crypto::{
         Hash,
-        PublicKey
+        PublicKey,
+        HASH_SIZE
     },
     transaction::Transaction
 };
 
+// Compute the fee paid per kB for a TX
+// This is used to select which TXs to evict when the mempool is full
+pub fn get_fee_rate(fee: u64, size: usize) -> u64 {
+    fee.saturating_mul(1024) / (size.max(1) as u64)
+}
+
 // Wrap a TX with its hash and size in bytes for faster access
 // size of tx can be heavy to compute, so we store it here
 #[derive(serde::Serialize)]
@@ -55,21 +62,43 @@ pub struct Mempool {
     // store all txs waiting to be included in a block
     txs: HashMap<Arc<Hash>, SortedTx>,
     // store all sender's nonce for faster finding
-    caches: HashMap<PublicKey, AccountCache>
+    caches: HashMap<PublicKey, AccountCache>,
+    // maximum size in bytes of all txs
+    max_size_bytes: usize,
+    // current size in bytes of all txs
+    size_bytes: usize,
+    // how many txs were evicted due to the size limit
+    evicted_count: u64,
+    // highest fee rate (per kB) of evicted txs
+    // it is reset once the mempool is under its limit again
+    minimum_fee_rate: u64,
+    // time in seconds before a tx is expired, 0 if disabled
+    tx_ttl: u64,
+    // minimum fee increase in percent to replace a tx with the same nonce
+    replacement_fee_increment: u64
 }
 
 impl Mempool {
     // Create a new empty mempool
-    pub fn new(network: Network) -> Self {
+    pub fn new(network: Network, max_size_bytes: usize, tx_ttl: u64, replacement_fee_increment: u64) -> Self {
         Mempool {
             mainnet: network.is_mainnet(),
             txs: HashMap::new(),
-            caches: HashMap::new()
+            caches: HashMap::new(),
+            max_size_bytes,
+            size_bytes: 0,
+            evicted_count: 0,
+            minimum_fee_rate: 0,
+            tx_ttl,
+            replacement_fee_increment
         }
     }
 
     // All checks are made in Blockchain before calling this function
-    pub async fn add_tx<S: Storage>(&mut self, storage: &S, topoheight: u64, hash: Hash, tx: Arc<Transaction>, size: usize) -> Result<(), BlockchainError> {
+    // Returns all the txs evicted to respect the size limit
+    pub async fn add_tx<S: Storage>(&mut self, storage: &S, topoheight: u64, hash: Hash, tx: Arc<Transaction>, size: usize) -> Result<Vec<(Arc<Hash>, SortedTx)>, BlockchainError> {
+        self.check_space_for(&hash, &tx, size)?;
+
         let mut state = MempoolState::new(&self, storage, topoheight);
         tx.verify(&mut state).await?;
 
@@ -98,7 +127,9 @@ impl Mempool {
                 if let Some(tx_hash) = cache.txs.swap_remove_index(index) {
                     trace!("TX {} with same nonce found in cache, removing it from sorted txs", tx_hash);
                     // remove the tx hash from sorted txs
-                    if self.txs.remove(&tx_hash).is_none() {
+                    if let Some(sorted_tx) = self.txs.remove(&tx_hash) {
+                        self.size_bytes -= sorted_tx.get_size();
+                    } else {
                         warn!("TX {} not found in mempool while deleting collision with {}", tx_hash, hash);
                     }
                 } else {
@@ -132,15 +163,286 @@ impl Mempool {
         };
 
         // insert in map
-        self.txs.insert(hash, sorted_tx);
+        self.txs.insert(hash.clone(), sorted_tx);
+        self.size_bytes += size;
+
+        Ok(self.evict_txs(storage, topoheight, &hash).await)
+    }
+
+    // Minimum fee required to replace a TX paying this fee
+    pub fn get_replacement_fee(&self, fee: u64) -> u64 {
+        fee.saturating_add((fee.saturating_mul(self.replacement_fee_increment) / 100).max(1))
+    }
+
+    // Replace the TX using the same nonce from the same sender
+    // The new TX must pay at least the replacement fee of the old one
+    // TXs with a higher nonce from this sender were built on top of the replaced TX and are removed too
+    // Returns the replaced TX, the TXs removed with it and the TXs evicted to respect the size limit
+    pub async fn replace_tx<S: Storage>(&mut self, storage: &S, topoheight: u64, hash: Hash, tx: Arc<Transaction>, size: usize) -> Result<((Arc<Hash>, SortedTx), Vec<(Arc<Hash>, SortedTx)>, Vec<(Arc<Hash>, SortedTx)>), BlockchainError> {
+        let key = tx.get_source();
+        let nonce = tx.get_nonce();
+        let index = self.get_replaced_tx_index(&hash, &tx)?;
+
+        // Without the sender cache, the state is built from the storage
+        // so the TXs kept are verified again with the new one
+        let Some(mut cache) = self.caches.remove(key) else {
+            return Err(BlockchainError::TxNotFound(hash))
+        };
 
+        let result = {
+            let mut txs = Vec::with_capacity(index + 1);
+            for tx_hash in cache.txs.iter().take(index) {
+                match self.txs.get(tx_hash) {
+                    Some(sorted_tx) => txs.push(sorted_tx.get_tx()),
+                    None => warn!("TX {} not found in mempool while verifying a replacement", tx_hash)
+                }
+            }
+            txs.push(&tx);
+
+            let mut state = MempoolState::new(&self, storage, topoheight);
+            match Transaction::verify_batch(txs.as_slice(), &mut state).await {
+                Ok(()) => state.get_sender_balances(key)
+                    .map(|balances| balances.into_iter().map(|(asset, ciphertext)| (asset.clone(), ciphertext)).collect::<HashMap<Hash, Ciphertext>>())
+                    .ok_or_else(|| BlockchainError::AccountNotFound(key.as_address(self.mainnet))),
+                Err(e) => Err(e.into())
+            }
+        };
+
+        let balances = match result {
+            Ok(balances) => balances,
+            Err(e) => {
+                self.caches.insert(key.clone(), cache);
+                return Err(e)
+            }
+        };
+
+        let hash = Arc::new(hash);
+        let mut removed = Vec::with_capacity(cache.txs.len() - index);
+        for tx_hash in cache.txs.drain(index..) {
+            match self.txs.remove(&tx_hash) {
+                Some(sorted_tx) => {
+                    self.size_bytes -= sorted_tx.get_size();
+                    removed.push((tx_hash, sorted_tx));
+                },
+                None => warn!("TX {} not found in mempool while replacing it with {}", tx_hash, hash)
+            }
+        }
+
+        cache.max = nonce;
+        cache.txs.insert(hash.clone());
+        cache.set_balances(balances);
+        self.caches.insert(key.clone(), cache);
+
+        self.txs.insert(hash.clone(), SortedTx {
+            size,
+            first_seen: get_current_time_in_seconds(),
+            tx
+        });
+        self.size_bytes += size;
+
+        // First one removed is the replaced TX
+        let replaced = removed.remove(0);
+        debug!("TX {} replaced by {} with {} TXs depending on it", replaced.0, hash, removed.len());
+        let evicted = self.evict_txs(storage, topoheight, &hash).await;
+        Ok((replaced, removed, evicted))
+    }
+
+    // Run the same verification as add_tx or replace_tx without changing the mempool
+    pub async fn validate_tx<S: Storage>(&self, storage: &S, topoheight: u64, hash: &Hash, tx: &Arc<Transaction>, size: usize, replacement: bool) -> Result<(), BlockchainError> {
+        if !replacement {
+            self.check_space_for(hash, tx, size)?;
+            let mut state = MempoolState::new(&self, storage, topoheight);
+            tx.verify(&mut state).await?;
+            return Ok(())
+        }
+
+        let key = tx.get_source();
+        let index = self.get_replaced_tx_index(hash, tx)?;
+        let mut txs = Vec::with_capacity(index + 1);
+        if let Some(cache) = self.caches.get(key) {
+            for tx_hash in cache.txs.iter().take(index) {
+                match self.txs.get(tx_hash) {
+                    Some(sorted_tx) => txs.push(sorted_tx.get_tx()),
+                    None => warn!("TX {} not found in mempool while validating a replacement", tx_hash)
+                }
+            }
+        }
+        txs.push(tx);
+
+        let mut state = MempoolState::without_cache_for(&self, storage, topoheight, key);
+        Transaction::verify_batch(txs.as_slice(), &mut state).await?;
         Ok(())
     }
 
+    // Reject directly the TX if the mempool is full and its fee rate is too low
+    fn check_space_for(&self, hash: &Hash, tx: &Transaction, size: usize) -> Result<(), BlockchainError> {
+        if self.size_bytes + size > self.max_size_bytes {
+            let fee_rate = get_fee_rate(tx.get_fee(), size);
+            let minimum_fee_rate = self.get_lowest_evictable_fee_rate(tx.get_source())
+                .map_or(self.minimum_fee_rate, |rate| rate.max(self.minimum_fee_rate));
+
+            if fee_rate <= minimum_fee_rate {
+                debug!("TX {} has a fee rate of {} but mempool is full, minimum fee rate is {}", hash, fee_rate, minimum_fee_rate);
+                return Err(BlockchainError::FeeTooLowForMempool(minimum_fee_rate))
+            }
+        }
+        Ok(())
+    }
+
+    // Index in the sender cache of the TX replaced by this one
+    // It also checks that the replacement fee is paid
+    fn get_replaced_tx_index(&self, hash: &Hash, tx: &Transaction) -> Result<usize, BlockchainError> {
+        let nonce = tx.get_nonce();
+        let (replaced_hash, index) = match self.caches.get(tx.get_source()) {
+            Some(cache) => match cache.has_tx_with_same_nonce(nonce) {
+                Some(replaced_hash) => (replaced_hash, (nonce - cache.get_min()) as usize),
+                None => return Err(BlockchainError::InvalidTxNonceMempoolCache(nonce, cache.get_min(), cache.get_max()))
+            },
+            None => return Err(BlockchainError::TxNotFound(hash.clone()))
+        };
+
+        let required_fee = self.get_replacement_fee(self.get_sorted_tx(replaced_hash)?.get_fee());
+        if tx.get_fee() < required_fee {
+            debug!("TX {} can't replace {}, fee {} is lower than {}", hash, replaced_hash, tx.get_fee(), required_fee);
+            return Err(BlockchainError::ReplacementFeeTooLow(replaced_hash.as_ref().clone(), required_fee, tx.get_fee()))
+        }
+        Ok(index)
+    }
+
+    // Fee rate per kB needed to be included in the next block
+    // TXs are ordered by fee rate and fill the block until its size limit
+    // Returns 0 if the whole mempool fits in the next block and nothing was evicted
+    pub fn get_next_block_fee_rate(&self, max_size: usize) -> u64 {
+        let mut txs: Vec<(u64, usize)> = self.txs.values()
+            .map(|sorted_tx| (sorted_tx.get_fee_rate(), sorted_tx.get_size()))
+            .collect();
+        txs.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+        let mut size = 0;
+        let mut fee_rate = 0;
+        for (rate, tx_size) in txs {
+            // hash of each TX is also in the block header
+            size += tx_size + HASH_SIZE;
+            if size > max_size {
+                fee_rate = rate + 1;
+                break;
+            }
+        }
+
+        // A full mempool only accepts TXs above its eviction floor
+        if self.minimum_fee_rate > 0 {
+            fee_rate = fee_rate.max(self.minimum_fee_rate + 1);
+        }
+
+        fee_rate
+    }
+
+    // Search the lowest fee rate of the txs that can be evicted
+    // Only the last TX (highest nonce) of each sender can be evicted
+    // so we never break the nonce chain of a sender
+    // The sender of the TX being added is excluded as its TX depends on it
+    fn get_lowest_evictable_fee_rate(&self, excluded: &PublicKey) -> Option<u64> {
+        self.caches.iter()
+            .filter(|(key, _)| *key != excluded)
+            .filter_map(|(_, cache)| cache.txs.last())
+            .filter_map(|hash| self.txs.get(hash))
+            .map(|sorted_tx| sorted_tx.get_fee_rate())
+            .min()
+    }
+
+    // Evict the txs with the lowest fee rate until we are under the size limit
+    // Only the last TX (highest nonce) of each sender is a candidate,
+    // so TXs required by higher nonces are only evicted once those are evicted too
+    // The TX just added is never evicted
+    async fn evict_txs<S: Storage>(&mut self, storage: &S, topoheight: u64, protected: &Hash) -> Vec<(Arc<Hash>, SortedTx)> {
+        let mut evicted = Vec::new();
+        let mut senders = HashSet::new();
+        while self.size_bytes > self.max_size_bytes {
+            let candidate = self.caches.values()
+                .filter_map(|cache| cache.txs.last())
+                .filter(|hash| hash.as_ref() != protected)
+                .filter_map(|hash| self.txs.get(hash).map(|sorted_tx| (hash, sorted_tx.get_fee_rate())))
+                .min_by_key(|(_, fee_rate)| *fee_rate)
+                .map(|(hash, fee_rate)| (Arc::clone(hash), fee_rate));
+
+            let Some((hash, fee_rate)) = candidate else {
+                warn!("No TX can be evicted from mempool while being over its size limit");
+                break;
+            };
+
+            debug!("Evicting TX {} with fee rate {} from mempool", hash, fee_rate);
+            match self.remove_tx(&hash) {
+                Ok(sorted_tx) => {
+                    self.evicted_count += 1;
+                    self.minimum_fee_rate = self.minimum_fee_rate.max(fee_rate);
+                    senders.insert(sorted_tx.get_tx().get_source().clone());
+                    evicted.push((hash, sorted_tx));
+                },
+                Err(e) => {
+                    warn!("Error while evicting TX {} from mempool: {}", hash, e);
+                    break;
+                }
+            }
+        }
+
+        // Expected balances of the senders still include the spendings of their evicted TXs
+        for key in senders {
+            let removed = self.update_sender_balances(storage, topoheight, &key).await;
+            self.evicted_count += removed.len() as u64;
+            evicted.extend(removed);
+        }
+
+        evicted
+    }
+
+    // Verify again the TXs left for this sender to compute its expected balances
+    // If they are not valid anymore, they are all removed and returned
+    async fn update_sender_balances<S: Storage>(&mut self, storage: &S, topoheight: u64, key: &PublicKey) -> Vec<(Arc<Hash>, SortedTx)> {
+        // Without the sender cache, the state is built from the storage
+        let Some(mut cache) = self.caches.remove(key) else {
+            return Vec::new()
+        };
+
+        let result = {
+            let txs: Vec<&Arc<Transaction>> = cache.txs.iter()
+                .filter_map(|tx_hash| self.txs.get(tx_hash))
+                .map(SortedTx::get_tx)
+                .collect();
+
+            let mut state = MempoolState::new(&self, storage, topoheight);
+            match Transaction::verify_batch(txs.as_slice(), &mut state).await {
+                Ok(()) => state.get_sender_balances(key)
+                    .map(|balances| balances.into_iter().map(|(asset, ciphertext)| (asset.clone(), ciphertext)).collect::<HashMap<Hash, Ciphertext>>())
+                    .ok_or_else(|| BlockchainError::AccountNotFound(key.as_address(self.mainnet))),
+                Err(e) => Err(e.into())
+            }
+        };
+
+        match result {
+            Ok(balances) => {
+                cache.set_balances(balances);
+                self.caches.insert(key.clone(), cache);
+                Vec::new()
+            },
+            Err(e) => {
+                warn!("Error while verifying the TXs left for sender {} after an eviction, removing them: {}", key.as_address(self.mainnet), e);
+                let mut removed = Vec::with_capacity(cache.txs.len());
+                for tx_hash in cache.txs {
+                    if let Some(sorted_tx) = self.txs.remove(&tx_hash) {
+                        self.size_bytes -= sorted_tx.get_size();
+                        removed.push((tx_hash, sorted_tx));
+                    }
+                }
+                removed
+            }
+        }
+    }
+
     // Remove a TX using its hash from mempool
     // This will recalculate the cache bounds
-    pub fn remove_tx(&mut self, hash: &Hash) -> Result<(), BlockchainError> {
+    pub fn remove_tx(&mut self, hash: &Hash) -> Result<SortedTx, BlockchainError> {
         let tx = self.txs.remove(hash).ok_or_else(|| BlockchainError::TxNotFound(hash.clone()))?;
+        self.size_bytes -= tx.get_size();
         // remove the tx hash from sorted txs
         let key = tx.get_tx().get_source();
         let mut delete = false;
@@ -195,7 +497,7 @@ impl Mempool {
             self.caches.remove(key);
         }
 
-        Ok(())
+        Ok(tx)
     }
 
     // Get the nonce cache for all keys
@@ -256,10 +558,64 @@ impl Mempool {
         self.txs.len()
     }
 
+    // Returns the size in bytes of all txs in mempool
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    // Returns how many txs were evicted due to the size limit
+    pub fn get_evicted_count(&self) -> u64 {
+        self.evicted_count
+    }
+
+    // Returns the fee rate floor set by the evicted txs, 0 if mempool isn't full
+    pub fn get_minimum_fee_rate(&self) -> u64 {
+        self.minimum_fee_rate
+    }
+
     // Clear all txs and caches in mempool
-    pub fn clear(&mut self) {
-        self.txs.clear();
+    // Returns all the txs removed
+    pub fn clear(&mut self) -> Vec<(Arc<Hash>, SortedTx)> {
+        let txs = self.txs.drain().collect();
         self.caches.clear();
+        self.size_bytes = 0;
+        self.evicted_count = 0;
+        self.minimum_fee_rate = 0;
+        txs
+    }
+
+    // Remove the txs that were not included in a block before the TTL
+    // TXs of a sender depend on the previous nonces, so they only expire
+    // once all of them expired: a recent TX keeps the older ones alive
+    pub fn expire_txs(&mut self, now: TimestampSeconds) -> Vec<(Arc<Hash>, SortedTx)> {
+        let mut expired = Vec::new();
+        if self.tx_ttl == 0 {
+            return expired
+        }
+
+        let keys: Vec<PublicKey> = self.caches.iter()
+            .filter(|(_, cache)| cache.txs.iter().all(|hash| self.txs.get(hash).map_or(true, |tx| tx.get_first_seen() + self.tx_ttl <= now)))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            let Some(cache) = self.caches.remove(&key) else {
+                continue;
+            };
+
+            for hash in cache.txs {
+                if let Some(sorted_tx) = self.txs.remove(&hash) {
+                    self.size_bytes -= sorted_tx.get_size();
+                    expired.push((hash, sorted_tx));
+                }
+            }
+        }
+
+        if self.size_bytes < self.max_size_bytes {
+            self.minimum_fee_rate = 0;
+        }
+
+        expired
     }
 
     // delete all old txs not compatible anymore with current state of chain
@@ -439,6 +795,15 @@ impl Mempool {
             }
         }
 
+        for (_, sorted_tx) in deleted_transactions.iter() {
+            self.size_bytes -= sorted_tx.get_size();
+        }
+
+        // We have room again, no need to require a higher fee rate
+        if self.size_bytes < self.max_size_bytes {
+            self.minimum_fee_rate = 0;
+        }
+
         deleted_transactions
     }
 
@@ -461,6 +826,11 @@ impl SortedTx {
         self.size
     }
 
+    // Fee paid per kB
+    pub fn get_fee_rate(&self) -> u64 {
+        get_fee_rate(self.get_fee(), self.size)
+    }
+
     pub fn get_first_seen(&self) -> TimestampSeconds {
         self.first_seen
     }
@@ -531,4 +901,190 @@ impl AccountCache {
         let index = ((nonce - self.min) % (self.max + 1 - self.min)) as usize;
         self.txs.get_index(index)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mempool_eviction() {
+        use xelis_common::{
+            account::{CiphertextCache, VersionedBalance, VersionedNonce},
+            config::{COIN_VALUE, XELIS_ASSET},
+            crypto::{Hashable, KeyPair},
+            serializer::Serializer,
+            testing::{build_transaction, build_transaction_with_fee},
+            transaction::{builder::{FeeBuilder, TransactionTypeBuilder}, BurnPayload, Reference}
+        };
+        use crate::core::{storage::{BalanceProvider, NonceProvider}, testing::test_blockchain_with};
+
+        let balance = 10 * COIN_VALUE;
+        let burn = || TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE });
+        // Only two TXs fit in the mempool
+        let sample = build_transaction(&KeyPair::new(), balance, 0, Reference { hash: Hash::zero(), topoheight: 0 }, burn());
+        let max_size = (sample.size() * 5 / 2).to_string();
+        let blockchain = test_blockchain_with(&["--mempool-max-size-bytes", &max_size]).await;
+
+        let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new()).collect();
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            for keypair in keypairs.iter() {
+                let key = keypair.get_public_key().compress();
+                let version = VersionedBalance::new(CiphertextCache::Decompressed(keypair.get_public_key().encrypt(balance)), None);
+                storage.set_last_balance_to(&key, &XELIS_ASSET, 0, &version).await.unwrap();
+                storage.set_last_nonce_to(&key, 0, &VersionedNonce::new(0, None)).await.unwrap();
+            }
+        }
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let fee = sample.get_fee();
+        let with_fee = |keypair: &KeyPair, balance: u64, nonce: u64, fee: u64| build_transaction_with_fee(keypair, balance, nonce, reference.clone(), burn(), FeeBuilder::Value(fee));
+        let (first, second, third) = (&keypairs[0], &keypairs[1], &keypairs[2]);
+
+        // First sender fills the mempool, its last TX pays the lowest fee
+        let first_0 = with_fee(first, balance, 0, fee * 2);
+        let first_1 = with_fee(first, balance - COIN_VALUE - fee * 2, 1, fee);
+        let first_1_hash = first_1.hash();
+        blockchain.add_tx_to_mempool(first_0.clone(), false).await.unwrap();
+        blockchain.add_tx_to_mempool(first_1.clone(), false).await.unwrap();
+
+        // Only the last TX of a sender can be evicted
+        blockchain.add_tx_to_mempool(with_fee(second, balance, 0, fee * 4), false).await.unwrap();
+        {
+            let mempool = blockchain.get_mempool().read().await;
+            assert_eq!(mempool.size(), 2);
+            assert!(!mempool.contains_tx(&first_1_hash));
+            assert_eq!(mempool.get_evicted_count(), 1);
+            assert_eq!(mempool.get_minimum_fee_rate(), get_fee_rate(fee, first_1.size()));
+            assert_eq!(mempool.get_cache_for(&first.get_public_key().compress()).unwrap().get_max(), 0);
+        }
+
+        // Cheapest TX left sets the fee rate required while the mempool is full
+        let minimum_fee_rate = get_fee_rate(first_0.get_fee(), first_0.size());
+        assert!(matches!(blockchain.add_tx_to_mempool(with_fee(third, balance, 0, fee), false).await, Err(BlockchainError::FeeTooLowForMempool(rate)) if rate == minimum_fee_rate));
+
+        // Expected balances of the first sender no longer include the evicted TX
+        blockchain.add_tx_to_mempool(with_fee(first, balance - COIN_VALUE - fee * 2, 1, fee * 8), false).await.unwrap();
+        let mut mempool = blockchain.get_mempool().write().await;
+        assert_eq!(mempool.size(), 2);
+        assert_eq!(mempool.get_evicted_count(), 2);
+        assert!(mempool.get_cache_for(&second.get_public_key().compress()).is_none());
+
+        mempool.clear();
+        assert_eq!(mempool.get_evicted_count(), 0);
+        assert_eq!(mempool.get_minimum_fee_rate(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mempool_expiration() {
+        use std::time::Duration;
+        use xelis_common::{
+            config::{COIN_VALUE, XELIS_ASSET},
+            crypto::{Hashable, KeyPair},
+            testing::build_transaction,
+            transaction::{builder::TransactionTypeBuilder, BurnPayload, Reference}
+        };
+        use crate::core::testing::{fund_account, test_blockchain_with};
+
+        let blockchain = test_blockchain_with(&["--mempool-tx-ttl", "60"]).await;
+
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let burn = || TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE });
+        let first = build_transaction(&keypair, balance, 0, reference.clone(), burn());
+        let first_hash = first.hash();
+        let balance = balance - COIN_VALUE - first.get_fee();
+        blockchain.add_tx_to_mempool(first, false).await.unwrap();
+        let first_seen = blockchain.get_mempool().read().await.get_sorted_tx(&first_hash).unwrap().get_first_seen();
+
+        // Next nonce is seen in a later second
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let second = build_transaction(&keypair, balance, 1, reference, burn());
+        blockchain.add_tx_to_mempool(second, false).await.unwrap();
+
+        let mut mempool = blockchain.get_mempool().write().await;
+        assert!(mempool.expire_txs(first_seen + 59).is_empty());
+        // First TX expired but the second one depends on it
+        assert!(mempool.expire_txs(first_seen + 60).is_empty());
+        assert_eq!(mempool.size(), 2);
+
+        let expired = mempool.expire_txs(first_seen + 120);
+        assert_eq!(expired.len(), 2);
+        assert_eq!(mempool.size(), 0);
+        assert_eq!(mempool.size_bytes(), 0);
+        assert!(mempool.get_cache_for(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mempool_replacement() {
+        use xelis_common::{
+            config::{COIN_VALUE, XELIS_ASSET},
+            crypto::{Hashable, KeyPair},
+            serializer::Serializer,
+            testing::{build_transaction, build_transaction_with_fee},
+            transaction::{builder::{FeeBuilder, TransactionTypeBuilder}, BurnPayload, Reference}
+        };
+        use crate::{
+            config::DEFAULT_MEMPOOL_REPLACEMENT_FEE_INCREMENT,
+            core::testing::{fund_account, test_blockchain}
+        };
+
+        let blockchain = test_blockchain().await;
+
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let burn = || TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE });
+        let first = build_transaction(&keypair, balance, 0, reference.clone(), burn());
+        let (first_hash, first_fee) = (first.hash(), first.get_fee());
+        blockchain.add_tx_to_mempool(first.clone(), false).await.unwrap();
+        let second = build_transaction(&keypair, balance - COIN_VALUE - first_fee, 1, reference.clone(), burn());
+        blockchain.add_tx_to_mempool(second, false).await.unwrap();
+
+        // Not enough to replace it
+        let required_fee = blockchain.get_mempool().read().await.get_replacement_fee(first_fee);
+        assert_eq!(required_fee, first_fee + (first_fee * DEFAULT_MEMPOOL_REPLACEMENT_FEE_INCREMENT / 100).max(1));
+        let low = build_transaction_with_fee(&keypair, balance, 0, reference.clone(), burn(), FeeBuilder::Value(required_fee - 1));
+        assert!(matches!(blockchain.add_tx_to_mempool(low, false).await, Err(BlockchainError::ReplacementFeeTooLow(hash, fee, _)) if hash == first_hash && fee == required_fee));
+        assert_eq!(blockchain.get_mempool().read().await.size(), 2);
+
+        // Replacement removes the TX built on top of the replaced one
+        let replacement = build_transaction_with_fee(&keypair, balance, 0, reference.clone(), burn(), FeeBuilder::Value(required_fee));
+        let replacement_hash = replacement.hash();
+        blockchain.add_tx_to_mempool(replacement.clone(), false).await.unwrap();
+        {
+            let mempool = blockchain.get_mempool().read().await;
+            assert_eq!(mempool.size(), 1);
+            assert!(!mempool.contains_tx(&first_hash));
+            assert_eq!(mempool.size_bytes(), replacement.size());
+            let cache = mempool.get_cache_for(&key).unwrap();
+            assert_eq!((cache.get_min(), cache.get_max()), (0, 0));
+            assert_eq!(cache.get_txs().first().unwrap().as_ref(), &replacement_hash);
+        }
+
+        // Next nonce is built on the replacement
+        let next = build_transaction(&keypair, balance - COIN_VALUE - required_fee, 1, reference, burn());
+        blockchain.add_tx_to_mempool(next, false).await.unwrap();
+        assert_eq!(blockchain.get_mempool().read().await.get_cache_for(&key).unwrap().get_max(), 1);
+
+        // The old TX can't come back with its lower fee
+        assert!(matches!(blockchain.add_tx_to_mempool(first, false).await, Err(BlockchainError::ReplacementFeeTooLow(_, _, _))));
+    }
+}
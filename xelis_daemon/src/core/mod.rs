@@ -8,4 +8,11 @@ pub mod simulator;
 pub mod nonce_checker;
 pub mod tx_selector;
 pub mod state;
-pub mod merkle;
\ No newline at end of file
+pub mod merkle;
+pub mod snapshot;
+pub mod replay;
+pub mod tx_policy;
+pub mod fee_histogram;
+pub mod alerts;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufWriter, Write}
+};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use xelis_common::{
+    block::Block,
+    crypto::{Hash, Hashable, PublicKey},
+    network::Network,
+    serializer::Serializer,
+    transaction::TransactionType
+};
+use crate::config::REPLAY_FORMAT_VERSION;
+use super::{
+    blockchain::Blockchain,
+    error::BlockchainError,
+    storage::Storage
+};
+
+// Replay file layout: JSON lines
+// The first line is the header, then one line per block in the order they must be added
+// Each block is annotated with the status it had on the node that recorded it
+#[derive(Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u8,
+    pub network: Network
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpectedStatus {
+    Accepted,
+    Rejected
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayEntry {
+    // Hex of the block serialized with its transactions
+    pub block: String,
+    pub expected: ExpectedStatus
+}
+
+// First block whose result doesn't match the recorded status
+#[derive(Debug)]
+pub struct Divergence {
+    // Index of the block in the file
+    pub index: usize,
+    pub hash: Hash,
+    pub expected: ExpectedStatus,
+    // Error returned by the validation, None if the block was accepted
+    pub error: Option<String>
+}
+
+#[derive(Debug)]
+pub struct ReplayReport {
+    // Blocks replayed, divergent one included
+    pub replayed: usize,
+    pub divergence: Option<Divergence>
+}
+
+// Export the blocks ordered between the two topoheights (inclusive) as accepted blocks
+pub async fn export_blocks<S: Storage>(storage: &RwLock<S>, network: Network, path: &str, min_topoheight: u64, max_topoheight: u64) -> Result<usize, BlockchainError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let header = ReplayHeader {
+        version: REPLAY_FORMAT_VERSION,
+        network
+    };
+    writeln!(writer, "{}", serde_json::to_string(&header).map_err(|_| BlockchainError::InvalidReplayFile)?)?;
+
+    let mut count = 0;
+    for topoheight in min_topoheight..=max_topoheight {
+        let block = {
+            let storage = storage.read().await;
+            let hash = storage.get_hash_at_topo_height(topoheight).await?;
+            storage.get_block_by_hash(&hash).await?
+        };
+
+        let entry = ReplayEntry {
+            block: block.to_hex(),
+            expected: ExpectedStatus::Accepted
+        };
+        writeln!(writer, "{}", serde_json::to_string(&entry).map_err(|_| BlockchainError::InvalidReplayFile)?)?;
+        count += 1;
+    }
+    writer.flush()?;
+
+    info!("{} blocks exported to {}", count, path);
+    Ok(count)
+}
+
+// Add each block of the file through the full validation pipeline
+// It stops at the first block having a different result than the recorded one
+// Every check is logged by the validation at debug/trace level
+pub async fn replay_blocks<S: Storage, R: BufRead>(blockchain: &Blockchain<S>, reader: R) -> Result<ReplayReport, BlockchainError> {
+    let mut lines = reader.lines();
+    let header: ReplayHeader = match lines.next() {
+        Some(line) => serde_json::from_str(&line?).map_err(|_| BlockchainError::InvalidReplayFile)?,
+        None => return Err(BlockchainError::InvalidReplayFile)
+    };
+
+    if header.version != REPLAY_FORMAT_VERSION {
+        return Err(BlockchainError::UnsupportedReplayVersion(header.version))
+    }
+
+    let network = *blockchain.get_network();
+    if header.network != network {
+        return Err(BlockchainError::ReplayNetworkMismatch(header.network, network))
+    }
+
+    let mut replayed = 0;
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: ReplayEntry = serde_json::from_str(&line).map_err(|_| BlockchainError::InvalidReplayFile)?;
+        let block = Block::from_hex(entry.block)?;
+        let hash = block.hash();
+        let tips = block.get_tips().iter().map(Hash::to_string).collect::<Vec<_>>().join(",");
+        info!("replay index={} block={} height={} timestamp={} tips=[{}] txs={} miner={} expected={:?}", index, hash, block.get_height(), block.get_timestamp(), tips, block.get_txs_count(), block.get_miner().as_address(network.is_mainnet()), entry.expected);
+        for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+            info!("replay index={} tx={} source={} nonce={} fee={} size={} outputs={}", index, tx_hash, tx.get_source().as_address(network.is_mainnet()), tx.get_nonce(), tx.get_fee(), tx.size(), tx.get_outputs_count());
+        }
+
+        // Keep the block to dump its accounts in case of divergence
+        let accounts = get_involved_accounts(&block);
+        let result = match blockchain.add_new_block(block, false, false).await {
+            // Genesis block is created by the node itself
+            Ok(()) | Err(BlockchainError::AlreadyInChain) => None,
+            Err(e) => Some(e.to_string())
+        };
+        replayed += 1;
+
+        let status = if result.is_none() { ExpectedStatus::Accepted } else { ExpectedStatus::Rejected };
+        info!("replay index={} block={} status={:?} error={:?}", index, hash, status, result);
+        if status != entry.expected {
+            error!("Divergence at index {} for block {}: expected {:?}, got {:?}", index, hash, entry.expected, status);
+            dump_accounts(blockchain, &accounts).await?;
+            return Ok(ReplayReport {
+                replayed,
+                divergence: Some(Divergence {
+                    index,
+                    hash,
+                    expected: entry.expected,
+                    error: result
+                })
+            })
+        }
+    }
+
+    Ok(ReplayReport {
+        replayed,
+        divergence: None
+    })
+}
+
+// Miner, transactions sources and destinations of a block
+fn get_involved_accounts(block: &Block) -> HashSet<PublicKey> {
+    let mut accounts = HashSet::new();
+    accounts.insert(block.get_miner().clone());
+    for tx in block.get_transactions() {
+        accounts.insert(tx.get_source().clone());
+        if let TransactionType::Transfers(transfers) = tx.get_data() {
+            for transfer in transfers {
+                accounts.insert(transfer.get_destination().clone());
+            }
+        }
+    }
+    accounts
+}
+
+// Log the nonce and balances of the accounts
+async fn dump_accounts<S: Storage>(blockchain: &Blockchain<S>, accounts: &HashSet<PublicKey>) -> Result<(), BlockchainError> {
+    let mainnet = blockchain.get_network().is_mainnet();
+    let storage = blockchain.get_storage().read().await;
+    for key in accounts {
+        let address = key.as_address(mainnet);
+        if !storage.has_nonce(key).await? {
+            info!("dump account={} unknown", address);
+            continue;
+        }
+
+        let (topoheight, nonce) = storage.get_last_nonce(key).await?;
+        info!("dump account={} nonce={} nonce_topoheight={}", address, nonce.get_nonce(), topoheight);
+        for asset in storage.get_assets_for(key).await? {
+            let (topoheight, balance) = storage.get_last_balance(key, &asset).await?;
+            let balance = serde_json::to_string(&balance).map_err(|_| BlockchainError::InvalidReplayFile)?;
+            info!("dump account={} asset={} balance_topoheight={} balance={}", address, asset, topoheight, balance);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::core::testing::test_blockchain;
+
+    #[tokio::test]
+    async fn test_replay_regression() {
+        let blockchain = test_blockchain().await;
+
+        // Blocks with unknown tips and without tips must stay rejected
+        let file = include_str!("../../tests/replay/rejected_blocks.jsonl");
+        let report = replay_blocks(&blockchain, file.as_bytes()).await.unwrap();
+        assert_eq!(report.replayed, 2);
+        assert!(report.divergence.is_none());
+
+        let diverging = file.replacen("\"rejected\"", "\"accepted\"", 1);
+        let report = replay_blocks(&blockchain, diverging.as_bytes()).await.unwrap();
+        assert_eq!(report.replayed, 1);
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(divergence.expected, ExpectedStatus::Accepted);
+        assert!(divergence.error.is_some());
+
+        // Exported blocks are accepted
+        let path = std::env::temp_dir().join(format!("xelis-replay-{}.jsonl", rand::thread_rng().gen::<u64>()));
+        let path = path.to_str().unwrap();
+        assert_eq!(blockchain.export_blocks(path, 0, 0).await.unwrap(), 1);
+        let content = std::fs::read_to_string(path).unwrap();
+        let report = replay_blocks(&blockchain, content.as_bytes()).await.unwrap();
+        assert_eq!(report.replayed, 1);
+        assert!(report.divergence.is_none());
+        assert!(blockchain.export_blocks(path, 0, 1).await.is_err());
+        std::fs::remove_file(path).unwrap();
+
+        // Another network is refused
+        let file = file.replacen("Dev", "Testnet", 1);
+        assert!(matches!(replay_blocks(&blockchain, file.as_bytes()).await, Err(BlockchainError::ReplayNetworkMismatch(Network::Testnet, Network::Dev))));
+    }
+}
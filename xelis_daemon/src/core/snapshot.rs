@@ -0,0 +1,525 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    sync::Arc
+};
+use log::{debug, info};
+use tokio::sync::RwLock;
+use xelis_common::{
+    account::{VersionedBalance, VersionedNonce},
+    asset::AssetData,
+    block::BlockHeader,
+    crypto::{Hash, Hashable, PublicKey},
+    difficulty::{CumulativeDifficulty, Difficulty},
+    immutable::Immutable,
+    network::Network,
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    transaction::Transaction,
+    varuint::VarUint
+};
+use crate::config::{SNAPSHOT_FORMAT_VERSION, SNAPSHOT_PROGRESS_INTERVAL};
+use super::{error::BlockchainError, storage::Storage};
+
+// Snapshot file layout:
+// magic, format version, then records (kind u8, size u32, payload)
+// The end record is followed by the blake3 checksum of all the previous bytes
+// Records are written in this order: header, blocks by topoheight, assets, registrations, nonces, balances
+const SNAPSHOT_MAGIC: &[u8; 7] = b"XELSNAP";
+
+const RECORD_END: u8 = 0;
+const RECORD_HEADER: u8 = 1;
+const RECORD_BLOCK: u8 = 2;
+const RECORD_ASSET: u8 = 3;
+const RECORD_REGISTRATION: u8 = 4;
+const RECORD_NONCE: u8 = 5;
+const RECORD_BALANCE: u8 = 6;
+
+// Maximum size of a single record, prevent allocating too much on a corrupted file
+const MAX_RECORD_SIZE: usize = 64 * 1024 * 1024;
+// Keys and assets are read from the storage by pages of this size
+const ITEMS_PER_PAGE: usize = 1024;
+
+// Header of the snapshot
+pub struct SnapshotHeader {
+    pub network: Network,
+    // All the data is included up to this topoheight
+    pub topoheight: u64,
+    // Height of the block at topoheight
+    pub height: u64,
+    // Block at topoheight, it will be the tip once imported
+    pub top_hash: Hash
+}
+
+impl Serializer for SnapshotHeader {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            network: Network::read(reader)?,
+            topoheight: reader.read_u64()?,
+            height: reader.read_u64()?,
+            top_hash: reader.read_hash()?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        self.network.write(writer);
+        writer.write_u64(&self.topoheight);
+        writer.write_u64(&self.height);
+        writer.write_hash(&self.top_hash);
+    }
+}
+
+// Write the snapshot file while computing its checksum
+struct SnapshotWriter {
+    inner: BufWriter<File>,
+    hasher: blake3::Hasher
+}
+
+impl SnapshotWriter {
+    fn create(path: &str) -> Result<Self, BlockchainError> {
+        let mut writer = Self {
+            inner: BufWriter::new(File::create(path)?),
+            hasher: blake3::Hasher::new()
+        };
+        writer.write_raw(SNAPSHOT_MAGIC)?;
+        writer.write_raw(&[SNAPSHOT_FORMAT_VERSION])?;
+        Ok(writer)
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> Result<(), BlockchainError> {
+        self.hasher.update(bytes);
+        self.inner.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn write_record(&mut self, kind: u8, writer: Writer) -> Result<(), BlockchainError> {
+        let payload = writer.bytes();
+        self.write_raw(&[kind])?;
+        self.write_raw(&(payload.len() as u32).to_be_bytes())?;
+        self.write_raw(&payload)
+    }
+
+    // Write the end record and the checksum
+    fn finish(mut self) -> Result<(), BlockchainError> {
+        self.write_raw(&[RECORD_END])?;
+        let checksum = self.hasher.finalize();
+        self.inner.write_all(checksum.as_bytes())?;
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+// Read the snapshot file record by record while computing its checksum
+struct SnapshotReader {
+    inner: BufReader<File>,
+    hasher: blake3::Hasher,
+    finished: bool
+}
+
+impl SnapshotReader {
+    // Open the file and read its header
+    fn open(path: &str) -> Result<(Self, SnapshotHeader), BlockchainError> {
+        let mut reader = Self {
+            inner: BufReader::new(File::open(path)?),
+            hasher: blake3::Hasher::new(),
+            finished: false
+        };
+
+        let mut magic = [0u8; 7];
+        reader.read_raw(&mut magic)?;
+        if magic != *SNAPSHOT_MAGIC {
+            return Err(BlockchainError::InvalidSnapshot)
+        }
+
+        let mut version = [0u8; 1];
+        reader.read_raw(&mut version)?;
+        if version[0] != SNAPSHOT_FORMAT_VERSION {
+            return Err(BlockchainError::UnsupportedSnapshotVersion(version[0]))
+        }
+
+        let header = match reader.next_record()? {
+            Some((RECORD_HEADER, payload)) => SnapshotHeader::from_bytes(&payload)?,
+            _ => return Err(BlockchainError::InvalidSnapshot)
+        };
+
+        Ok((reader, header))
+    }
+
+    fn read_raw(&mut self, buf: &mut [u8]) -> Result<(), BlockchainError> {
+        self.inner.read_exact(buf)?;
+        self.hasher.update(buf);
+        Ok(())
+    }
+
+    // Returns None once the end record is reached and the checksum verified
+    fn next_record(&mut self) -> Result<Option<(u8, Vec<u8>)>, BlockchainError> {
+        if self.finished {
+            return Ok(None)
+        }
+
+        let mut kind = [0u8; 1];
+        self.read_raw(&mut kind)?;
+        if kind[0] == RECORD_END {
+            let expected = self.hasher.finalize();
+            let mut checksum = [0u8; 32];
+            // The checksum itself is not part of the hash
+            self.inner.read_exact(&mut checksum)?;
+            if *expected.as_bytes() != checksum {
+                return Err(BlockchainError::SnapshotChecksumMismatch)
+            }
+            self.finished = true;
+            return Ok(None)
+        }
+
+        let mut size = [0u8; 4];
+        self.read_raw(&mut size)?;
+        let size = u32::from_be_bytes(size) as usize;
+        if size > MAX_RECORD_SIZE {
+            return Err(BlockchainError::InvalidSnapshot)
+        }
+
+        let mut payload = vec![0u8; size];
+        self.read_raw(&mut payload)?;
+        Ok(Some((kind[0], payload)))
+    }
+}
+
+// Export all the chain data up to the requested topoheight (included)
+// The storage lock is only held while reading a page of records so the node keeps running
+// during the export, pages continue after the last key read to not be shifted by new entries
+pub async fn export_snapshot<S: Storage>(storage: &RwLock<S>, network: Network, path: &str, topoheight: u64) -> Result<(), BlockchainError> {
+    let header = {
+        let storage = storage.read().await;
+        if storage.get_pruned_topoheight().await?.is_some() {
+            return Err(BlockchainError::SnapshotPrunedChain)
+        }
+
+        let top_hash = storage.get_hash_at_topo_height(topoheight).await?;
+        let height = storage.get_height_for_block_hash(&top_hash).await?;
+        SnapshotHeader { network, topoheight, height, top_hash }
+    };
+
+    info!("Exporting snapshot up to topoheight {} to {}", topoheight, path);
+    let mut file = SnapshotWriter::create(path)?;
+    let mut writer = Writer::new();
+    header.write(&mut writer);
+    file.write_record(RECORD_HEADER, writer)?;
+
+    // Blocks
+    let mut current = 0;
+    while current <= topoheight {
+        let end = topoheight.min(current + ITEMS_PER_PAGE as u64 - 1);
+        let storage = storage.read().await;
+        for block_topoheight in current..=end {
+            file.write_record(RECORD_BLOCK, write_block(&*storage, block_topoheight).await?)?;
+            if block_topoheight > 0 && block_topoheight % SNAPSHOT_PROGRESS_INTERVAL == 0 {
+                info!("Snapshot export: {}/{} blocks", block_topoheight, topoheight + 1);
+            }
+        }
+        current = end + 1;
+    }
+
+    // Assets registered up to topoheight
+    let mut assets = 0;
+    let mut last_asset = None;
+    loop {
+        let page = {
+            let storage = storage.read().await;
+            storage.get_assets_after(last_asset.as_ref(), ITEMS_PER_PAGE, 0, topoheight).await?
+        };
+
+        let count = page.len();
+        for asset in page {
+            let (hash, data) = asset.consume();
+            let mut writer = Writer::new();
+            hash.write(&mut writer);
+            data.write(&mut writer);
+            file.write_record(RECORD_ASSET, writer)?;
+            last_asset = Some(hash);
+        }
+
+        assets += count;
+        if count < ITEMS_PER_PAGE {
+            break
+        }
+    }
+
+    // Accounts with all their versions up to topoheight
+    let mut accounts = 0;
+    let mut last_key = None;
+    loop {
+        let storage = storage.read().await;
+        let keys = storage.get_keys_after(last_key.as_ref(), ITEMS_PER_PAGE, 0, topoheight).await?;
+
+        let count = keys.len();
+        for key in keys {
+            write_account(&*storage, &mut file, &key, topoheight).await?;
+            last_key = Some(key);
+        }
+
+        accounts += count;
+        if count < ITEMS_PER_PAGE {
+            break
+        }
+    }
+
+    file.finish()?;
+    info!("Snapshot exported: {} blocks, {} assets, {} accounts", topoheight + 1, assets, accounts);
+    Ok(())
+}
+
+async fn write_block<S: Storage>(storage: &S, topoheight: u64) -> Result<Writer, BlockchainError> {
+    let hash = storage.get_hash_at_topo_height(topoheight).await?;
+    let header = storage.get_block_header_by_hash(&hash).await?;
+
+    let mut writer = Writer::new();
+    writer.write_u64(&topoheight);
+    writer.write_hash(&hash);
+    header.write(&mut writer);
+
+    for tx_hash in header.get_txs_hashes() {
+        storage.get_transaction(tx_hash).await?.write(&mut writer);
+    }
+
+    storage.get_difficulty_for_block_hash(&hash).await?.write(&mut writer);
+    storage.get_cumulative_difficulty_for_block_hash(&hash).await?.write(&mut writer);
+    storage.get_estimated_covariance_for_block_hash(&hash).await?.write(&mut writer);
+    writer.write_u64(&storage.get_block_reward_at_topo_height(topoheight)?);
+    writer.write_u64(&storage.get_supply_at_topo_height(topoheight).await?);
+
+    // Transactions executed in this block
+    for tx_hash in header.get_txs_hashes() {
+        writer.write_bool(storage.is_tx_executed_in_block(tx_hash, &hash)?);
+    }
+
+    Ok(writer)
+}
+
+async fn write_account<S: Storage>(storage: &S, file: &mut SnapshotWriter, key: &PublicKey, topoheight: u64) -> Result<(), BlockchainError> {
+    if storage.is_account_registered_below_topoheight(key, topoheight + 1).await? {
+        let mut writer = Writer::new();
+        key.write(&mut writer);
+        writer.write_u64(&storage.get_account_registration_topoheight(key).await?);
+        file.write_record(RECORD_REGISTRATION, writer)?;
+    }
+
+    // Nonces from the newest to the oldest version
+    let mut next = storage.get_nonce_at_maximum_topoheight(key, topoheight).await?;
+    let mut last = true;
+    while let Some((version_topoheight, version)) = next {
+        let mut writer = Writer::new();
+        key.write(&mut writer);
+        writer.write_u64(&version_topoheight);
+        version.write(&mut writer);
+        writer.write_bool(last);
+        file.write_record(RECORD_NONCE, writer)?;
+
+        next = match version.get_previous_topoheight() {
+            Some(previous) => Some((previous, storage.get_nonce_at_exact_topoheight(key, previous).await?)),
+            None => None
+        };
+        last = false;
+    }
+
+    // Balances from the newest to the oldest version for each asset
+    for asset in storage.get_assets_for(key).await? {
+        let versions = storage.get_balance_history(key, &asset, 0, topoheight, usize::MAX).await?;
+        for (i, (version_topoheight, version)) in versions.into_iter().enumerate() {
+            let mut writer = Writer::new();
+            key.write(&mut writer);
+            asset.write(&mut writer);
+            writer.write_u64(&version_topoheight);
+            version.write(&mut writer);
+            writer.write_bool(i == 0);
+            file.write_record(RECORD_BALANCE, writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Read only the header of a snapshot
+pub fn read_snapshot_header(path: &str) -> Result<SnapshotHeader, BlockchainError> {
+    let (_, header) = SnapshotReader::open(path)?;
+    Ok(header)
+}
+
+// Import a snapshot in an empty storage
+// The whole file is verified before writing anything
+pub async fn import_snapshot<S: Storage>(storage: &mut S, network: Network, path: &str) -> Result<SnapshotHeader, BlockchainError> {
+    if storage.has_blocks().await {
+        return Err(BlockchainError::SnapshotStorageNotEmpty)
+    }
+
+    let header = read_snapshot_header(path)?;
+    if header.network != network {
+        return Err(BlockchainError::SnapshotNetworkMismatch(header.network, network))
+    }
+
+    info!("Verifying snapshot {} up to topoheight {}", path, header.topoheight);
+    {
+        let (mut reader, _) = SnapshotReader::open(path)?;
+        while reader.next_record()?.is_some() {}
+    }
+
+    info!("Importing snapshot {}", path);
+    let (mut reader, _) = SnapshotReader::open(path)?;
+    let (mut blocks, mut assets, mut accounts) = (0u64, 0u64, 0u64);
+    while let Some((kind, payload)) = reader.next_record()? {
+        let mut record = Reader::new(&payload);
+        match kind {
+            RECORD_BLOCK => {
+                let topoheight = record.read_u64()?;
+                if topoheight != blocks || topoheight > header.topoheight {
+                    debug!("Expected block at topoheight {} in snapshot, got {}", blocks, topoheight);
+                    return Err(BlockchainError::InvalidSnapshot)
+                }
+
+                read_block(storage, &mut record, topoheight).await?;
+                blocks += 1;
+                if blocks % SNAPSHOT_PROGRESS_INTERVAL == 0 {
+                    info!("Snapshot import: {}/{} blocks", blocks, header.topoheight + 1);
+                }
+            },
+            RECORD_ASSET => {
+                let hash = Hash::read(&mut record)?;
+                let data = AssetData::read(&mut record)?;
+                storage.add_asset(&hash, data).await?;
+                assets += 1;
+            },
+            RECORD_REGISTRATION => {
+                let key = PublicKey::read(&mut record)?;
+                let topoheight = record.read_u64()?;
+                storage.set_account_registration_topoheight(&key, topoheight).await?;
+            },
+            RECORD_NONCE => {
+                let key = PublicKey::read(&mut record)?;
+                let topoheight = record.read_u64()?;
+                let version = VersionedNonce::read(&mut record)?;
+                if record.read_bool()? {
+                    storage.set_last_nonce_to(&key, topoheight, &version).await?;
+                    accounts += 1;
+                } else {
+                    storage.set_nonce_at_topoheight(&key, topoheight, &version).await?;
+                }
+            },
+            RECORD_BALANCE => {
+                let key = PublicKey::read(&mut record)?;
+                let asset = Hash::read(&mut record)?;
+                let topoheight = record.read_u64()?;
+                let version = VersionedBalance::read(&mut record)?;
+                if record.read_bool()? {
+                    storage.set_last_balance_to(&key, &asset, topoheight, &version).await?;
+                } else {
+                    storage.set_balance_at_topoheight(&asset, topoheight, &key, &version).await?;
+                }
+            },
+            _ => return Err(BlockchainError::InvalidSnapshot)
+        }
+    }
+
+    if blocks != header.topoheight + 1 {
+        return Err(BlockchainError::InvalidSnapshot)
+    }
+
+    storage.set_top_topoheight(header.topoheight)?;
+    storage.set_top_height(header.height)?;
+    storage.store_tips(&HashSet::from([header.top_hash.clone()]))?;
+
+    info!("Snapshot imported: {} blocks, {} assets, {} accounts", blocks, assets, accounts);
+    Ok(header)
+}
+
+async fn read_block<S: Storage>(storage: &mut S, record: &mut Reader<'_>, topoheight: u64) -> Result<(), BlockchainError> {
+    let hash = Hash::read(record)?;
+    let header = BlockHeader::read(record)?;
+    if header.hash() != hash {
+        return Err(BlockchainError::InvalidSnapshot)
+    }
+
+    let mut txs = Vec::with_capacity(header.get_txs_count());
+    for tx_hash in header.get_txs_hashes() {
+        let tx = Transaction::read(record)?;
+        if tx.hash() != *tx_hash {
+            return Err(BlockchainError::InvalidSnapshot)
+        }
+        txs.push(Immutable::Owned(tx));
+    }
+
+    let difficulty = Difficulty::read(record)?;
+    let cumulative_difficulty = CumulativeDifficulty::read(record)?;
+    let p = VarUint::read(record)?;
+    let reward = record.read_u64()?;
+    let supply = record.read_u64()?;
+
+    for tx_hash in header.get_txs_hashes() {
+        storage.add_block_for_tx(tx_hash, &hash)?;
+        if record.read_bool()? {
+            storage.set_tx_executed_in_block(tx_hash, &hash)?;
+        }
+    }
+
+    storage.set_supply_at_topo_height(topoheight, supply)?;
+    storage.set_block_reward_at_topo_height(topoheight, reward)?;
+    storage.set_topo_height_for_block(&hash, topoheight).await?;
+    storage.set_cumulative_difficulty_for_block_hash(&hash, cumulative_difficulty).await?;
+    storage.save_block(Arc::new(header), &txs, difficulty, p, hash).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::core::{storage::SledStorage, testing::test_blockchain};
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        use crate::core::storage::DagOrderProvider;
+
+        let blockchain = test_blockchain().await;
+
+        let path = std::env::temp_dir().join(format!("xelis-snapshot-{}.bin", rand::thread_rng().gen::<u64>()));
+        let path = path.to_str().unwrap();
+        blockchain.export_snapshot(path, 0).await.unwrap();
+
+        // Another network is refused before reading the content
+        let mut storage = SledStorage::temporary(None, Network::Testnet).unwrap();
+        assert!(matches!(import_snapshot(&mut storage, Network::Testnet, path).await, Err(BlockchainError::SnapshotNetworkMismatch(Network::Dev, Network::Testnet))));
+
+        let mut storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        let header = import_snapshot(&mut storage, Network::Dev, path).await.unwrap();
+        let expected = blockchain.get_storage().read().await.get_hash_at_topo_height(0).await.unwrap();
+        assert_eq!(header.top_hash, expected);
+        assert_eq!(storage.get_top_block_hash().await.unwrap(), expected);
+
+        // Only an empty storage can import a snapshot
+        assert!(matches!(import_snapshot(&mut storage, Network::Dev, path).await, Err(BlockchainError::SnapshotStorageNotEmpty)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_accounts_pages() {
+        use xelis_common::crypto::KeyPair;
+        use crate::core::{storage::NonceProvider, testing::fund_account};
+
+        // More accounts than a page of the export
+        let blockchain = test_blockchain().await;
+        let keys: Vec<_> = (0..1100).map(|_| KeyPair::new().get_public_key().compress()).collect();
+        for key in &keys {
+            fund_account(&blockchain, key, 100).await;
+        }
+
+        let path = std::env::temp_dir().join(format!("xelis-snapshot-pages-{}.bin", rand::thread_rng().gen::<u64>()));
+        let path = path.to_str().unwrap();
+        blockchain.export_snapshot(path, 0).await.unwrap();
+
+        let mut storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        import_snapshot(&mut storage, Network::Dev, path).await.unwrap();
+        for key in &keys {
+            assert!(storage.has_nonce(key).await.unwrap());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -25,7 +25,7 @@ use xelis_common::{
 use crate::core::{
     blockchain,
     error::BlockchainError,
-    storage::Storage
+    storage::{AccountChanges, Storage}
 };
 
 // Sender changes
@@ -187,24 +187,58 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
     // This will consume ChainState and apply all changes to the storage
     // In case of incoming and outgoing transactions in same state, the final balance will be computed
     pub async fn apply_changes(mut self) -> Result<(), BlockchainError> {
-        // Apply changes for sender accounts
-        for (key, account) in &mut self.inner.accounts {
+        self.inner.merge_sender_changes().await?;
+
+        // Only the reads are done here, all the writes are committed at once
+        let mut changes = AccountChanges::default();
+        for (key, account) in &self.inner.accounts {
             trace!("Saving {} for {} at topoheight {}", account.nonce, key.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
-            self.inner.storage.set_last_nonce_to(key, self.inner.topoheight, &account.nonce).await?;
+            changes.nonces.push((*key, account.nonce.clone()));
+        }
+
+        // Apply all balances changes at topoheight
+        // We injected the sender balances in the receiver balances previously
+        for (account, balances) in self.inner.receiver_balances {
+            for (asset, version) in balances {
+                trace!("Saving versioned balance {} for {} at topoheight {}", version, account.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
+                changes.balances.push((account, asset, version));
+            }
+
+            // If the account has no nonce set, set it to 0
+            if !self.inner.accounts.contains_key(account) && !self.inner.storage.has_nonce(account).await? {
+                debug!("{} has now a balance but without any nonce registered, set default (0) nonce", account.as_address(self.inner.storage.is_mainnet()));
+                changes.nonces.push((account, VersionedNonce::new(0, None)));
+            }
+
+            // Mark it as registered at this topoheight
+            if !self.inner.storage.is_account_registered_below_topoheight(account, self.inner.topoheight).await? {
+                changes.registrations.push(account);
+            }
+        }
+
+        self.inner.storage.apply_account_changes(self.inner.topoheight, changes).await
+    }
+}
 
-            let balances = self.inner.receiver_balances.entry(&key).or_insert_with(HashMap::new);
+impl<'a, S: Storage> ChainState<'a, S> {
+    // Merge the sender changes in the receiver balances to build the final versions
+    // In case of incoming and outgoing transactions in same state, the final balance will be computed
+    // Storage is only read, so it is also used to verify the stored versions
+    async fn merge_sender_changes(&mut self) -> Result<(), BlockchainError> {
+        for (key, account) in &mut self.accounts {
+            let balances = self.receiver_balances.entry(&key).or_insert_with(HashMap::new);
             // Because account balances are only used to verify the validity of ZK Proofs, we can't store them
             // We have to recompute the final balance for each asset using the existing current balance
             // Otherwise, we could have a front running problem
             // Example: Alice sends 100 to Bob, Bob sends 100 to Charlie
             // But Bob built its ZK Proof with the balance before Alice's transaction
             for (asset, echange) in account.assets.drain() {
-                trace!("{} {} updated for {} at topoheight {}", echange.version, asset, key.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
+                trace!("{} {} updated for {} at topoheight {}", echange.version, asset, key.as_address(self.storage.is_mainnet()), self.topoheight);
                 let Echange { version, output_sum, output_balance_used, new_version, .. } = echange;
                 trace!("sender output sum: {:?}", output_sum.compress());
                 match balances.entry(asset) {
                     Entry::Occupied(mut o) => {
-                        trace!("{} already has a balance for {} at topoheight {}", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                        trace!("{} already has a balance for {} at topoheight {}", key.as_address(self.storage.is_mainnet()), asset, self.topoheight);
                         // We got incoming funds while spending some
                         // We need to split the version in two
                         // Output balance is the balance after outputs spent without incoming funds
@@ -241,16 +275,16 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
                         *final_balance -= output_sum;
                     },
                     Entry::Vacant(e) => {
-                        trace!("{} has no balance for {} at topoheight {}", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                        trace!("{} has no balance for {} at topoheight {}", key.as_address(self.storage.is_mainnet()), asset, self.topoheight);
                         // We have no incoming update for this key
                         // Select the right final version
                         // For that, we must check if we used the output balance and/or if we are not on the last version 
                         let mut version = if output_balance_used || !new_version {
                             // We must fetch again the version to sum it with the output
                             // This is necessary to build the final balance
-                            let mut version = self.inner.storage.get_new_versioned_balance(key, asset, self.inner.topoheight).await?;
+                            let mut version = self.storage.get_new_versioned_balance(key, asset, self.topoheight).await?;
                             // Substract the output sum
-                            trace!("{} has no balance for {} at topoheight {}, substract output sum", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                            trace!("{} has no balance for {} at topoheight {}, substract output sum", key.as_address(self.storage.is_mainnet()), asset, self.topoheight);
                             *version.get_mut_balance().computable()? -= output_sum;
                             version
                         } else {
@@ -267,27 +301,38 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
             }
         }
 
-        // Apply all balances changes at topoheight
-        // We injected the sender balances in the receiver balances previously
-        for (account, balances) in self.inner.receiver_balances {
-            for (asset, version) in balances {
-                trace!("Saving versioned balance {} for {} at topoheight {}", version, account.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
-                self.inner.storage.set_last_balance_to(account, asset, self.inner.topoheight, &version).await?;
-            }
+        Ok(())
+    }
 
-            // If the account has no nonce set, set it to 0
-            if !self.inner.accounts.contains_key(account) && !self.inner.storage.has_nonce(account).await? {
-                debug!("{} has now a balance but without any nonce registered, set default (0) nonce", account.as_address(self.inner.storage.is_mainnet()));
-                self.inner.storage.set_last_nonce_to(account, self.inner.topoheight, &VersionedNonce::new(0, None)).await?;
+    // Verify that the final versions built by this chain state are the ones stored at `topoheight`
+    // The chain state must have been created at the topoheight below it to read the previous state
+    // Nothing is written in the storage
+    // Returns the first mismatch found
+    pub async fn verify_changes_at(mut self, topoheight: u64) -> Result<Option<String>, BlockchainError> {
+        self.merge_sender_changes().await?;
+
+        let mainnet = self.storage.is_mainnet();
+        for (key, account) in &self.accounts {
+            let nonce = self.storage.get_nonce_at_exact_topoheight(key, topoheight).await?;
+            if nonce.get_nonce() != account.nonce.get_nonce() {
+                return Ok(Some(format!("nonce of {} is {} but expected {}", key.as_address(mainnet), nonce.get_nonce(), account.nonce.get_nonce())))
             }
+        }
 
-            // Mark it as registered at this topoheight
-            if !self.inner.storage.is_account_registered_below_topoheight(account, self.inner.topoheight).await? {
-                self.inner.storage.set_account_registration_topoheight(account, self.inner.topoheight).await?;
+        for (key, balances) in &self.receiver_balances {
+            for (asset, version) in balances {
+                if !self.storage.has_balance_at_exact_topoheight(key, asset, topoheight).await? {
+                    return Ok(Some(format!("no balance {} stored for {}", asset, key.as_address(mainnet))))
+                }
+
+                let stored = self.storage.get_balance_at_exact_topoheight(key, asset, topoheight).await?;
+                if stored != *version {
+                    return Ok(Some(format!("balance {} of {} doesn't match the re-executed state", asset, key.as_address(mainnet))))
+                }
             }
         }
 
-        Ok(())
+        Ok(None)
     }
 }
 
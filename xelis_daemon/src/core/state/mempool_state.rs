@@ -17,7 +17,7 @@ use xelis_common::{
 use crate::core::{
     blockchain,
     error::BlockchainError,
-    mempool::Mempool,
+    mempool::{AccountCache, Mempool},
     storage::Storage
 };
 
@@ -43,6 +43,8 @@ pub struct MempoolState<'a, S: Storage> {
     accounts: HashMap<&'a PublicKey, Account<'a>>,
     // The current topoheight of the chain
     topoheight: u64,
+    // Sender whose mempool cache is not used, its balances and nonce are read from the storage
+    skip_cache_for: Option<&'a PublicKey>
 }
 
 impl<'a, S: Storage> MempoolState<'a, S> {
@@ -53,9 +55,26 @@ impl<'a, S: Storage> MempoolState<'a, S> {
             receiver_balances: HashMap::new(),
             accounts: HashMap::new(),
             topoheight,
+            skip_cache_for: None
         }
     }
 
+    // Verify the TXs of a sender as if it had nothing pending in mempool
+    // Used to check a replacement without removing its cache
+    pub fn without_cache_for(mempool: &'a Mempool, storage: &'a S, topoheight: u64, key: &'a PublicKey) -> Self {
+        Self {
+            skip_cache_for: Some(key),
+            ..Self::new(mempool, storage, topoheight)
+        }
+    }
+
+    fn get_mempool_cache<'b>(mempool: &'b Mempool, skip_cache_for: Option<&PublicKey>, key: &PublicKey) -> Option<&'b AccountCache> {
+        if skip_cache_for.is_some_and(|skipped| skipped == key) {
+            return None
+        }
+        mempool.get_cache_for(key)
+    }
+
     // Retrieve the sender balances
     pub fn get_sender_balances(&mut self, key: &PublicKey) -> Option<HashMap<&Hash, Ciphertext>> {
         let account = self.accounts.remove(key)?;
@@ -93,7 +112,7 @@ impl<'a, S: Storage> MempoolState<'a, S> {
                 let account = o.into_mut();
                 match account.assets.entry(asset) {
                     Entry::Occupied(entry) => Ok(entry.into_mut()),
-                    Entry::Vacant(entry) => match self.mempool.get_cache_for(key) {
+                    Entry::Vacant(entry) => match Self::get_mempool_cache(self.mempool, self.skip_cache_for, key) {
                         Some(cache) => {
                             if let Some(version) = cache.get_balances().get(asset) {
                                 Ok(entry.insert(version.clone()))
@@ -134,7 +153,7 @@ impl<'a, S: Storage> MempoolState<'a, S> {
     async fn internal_get_account_nonce(&mut self, key: &'a PublicKey) -> Result<u64, BlockchainError> {
         match self.accounts.entry(key) {
             Entry::Occupied(o) => Ok(o.get().nonce),
-            Entry::Vacant(e) => match self.mempool.get_cache_for(key) {
+            Entry::Vacant(e) => match Self::get_mempool_cache(self.mempool, self.skip_cache_for, key) {
                 Some(cache) => Ok(cache.get_next_nonce()),
                 None => {
                     let nonce = self.storage.get_nonce_at_maximum_topoheight(key, self.topoheight).await?
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex
+    }
+};
+use log::{error, warn};
+use serde::Serialize;
+use xelis_common::{serializer::ReaderError, time::{get_current_time_in_millis, TimestampMillis}};
+
+// Failed reads of the same key before entering the recovery mode
+// A single failure may come from a read during a write
+pub const CORRUPTION_THRESHOLD: u64 = 3;
+
+// An entry of the storage that can't be deserialized
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptedEntry {
+    pub tree: String,
+    // key in hex format
+    pub key: String,
+    pub errors: u64,
+    pub last_error: String,
+    pub detected_at: TimestampMillis
+}
+
+// Keep track of the entries failing to be deserialized
+// Once an entry failed too many times, the storage is quarantined:
+// no new block or TX is accepted until a recovery is done
+pub struct CorruptionTracker {
+    entries: Mutex<HashMap<(String, Vec<u8>), CorruptedEntry>>,
+    quarantined: AtomicBool
+}
+
+impl CorruptionTracker {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            quarantined: AtomicBool::new(false)
+        }
+    }
+
+    // Record a failed read of a key
+    // Returns true if the storage just got quarantined
+    pub fn record(&self, tree: &str, key: &[u8], error: &ReaderError) -> bool {
+        let errors = {
+            let Ok(mut entries) = self.entries.lock() else {
+                return false
+            };
+
+            let entry = entries.entry((tree.to_owned(), key.to_vec())).or_insert_with(|| CorruptedEntry {
+                tree: tree.to_owned(),
+                key: hex::encode(key),
+                errors: 0,
+                last_error: String::new(),
+                detected_at: get_current_time_in_millis()
+            });
+            entry.errors += 1;
+            entry.last_error = error.to_string();
+            entry.errors
+        };
+
+        warn!("Entry {} in tree {} can't be read ({} times): {}", hex::encode(key), tree, errors, error);
+        if errors < CORRUPTION_THRESHOLD || self.quarantined.swap(true, Ordering::SeqCst) {
+            return false
+        }
+
+        error!("Database corruption detected, node is now in recovery mode: no block or transaction will be accepted");
+        for entry in self.get_report() {
+            error!("Corrupted entry in tree {}: key {} failed {} times, last error: {}", entry.tree, entry.key, entry.errors, entry.last_error);
+        }
+        error!("Use the 'recover_storage' command to find the last consistent topoheight and recover from it");
+        true
+    }
+
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantined.load(Ordering::SeqCst)
+    }
+
+    // All the entries that failed to be read
+    pub fn get_report(&self) -> Vec<CorruptedEntry> {
+        let Ok(entries) = self.entries.lock() else {
+            return Vec::new()
+        };
+
+        let mut report: Vec<CorruptedEntry> = entries.values().cloned().collect();
+        report.sort_by(|a, b| a.tree.cmp(&b.tree).then(a.key.cmp(&b.key)));
+        report
+    }
+
+    // Get the raw keys that failed in this tree
+    pub fn get_keys_in_tree(&self, tree: &str) -> Vec<Vec<u8>> {
+        let Ok(entries) = self.entries.lock() else {
+            return Vec::new()
+        };
+
+        entries.keys().filter(|(name, _)| name == tree).map(|(_, key)| key.clone()).collect()
+    }
+
+    // Forget all entries and leave the recovery mode
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+        self.quarantined.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold() {
+        let tracker = CorruptionTracker::new();
+        for _ in 0..CORRUPTION_THRESHOLD - 1 {
+            assert!(!tracker.record("blocks", &[1, 2], &ReaderError::InvalidValue));
+        }
+        // Another key doesn't count for the first one
+        assert!(!tracker.record("supply", &[3], &ReaderError::InvalidSize));
+        assert!(!tracker.is_quarantined());
+
+        assert!(tracker.record("blocks", &[1, 2], &ReaderError::InvalidValue));
+        assert!(tracker.is_quarantined());
+        // Only reported once
+        assert!(!tracker.record("blocks", &[1, 2], &ReaderError::InvalidValue));
+
+        let report = tracker.get_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].key, "0102");
+        assert_eq!(report[0].errors, CORRUPTION_THRESHOLD + 1);
+        assert_eq!(tracker.get_keys_in_tree("supply"), vec![vec![3]]);
+
+        tracker.clear();
+        assert!(!tracker.is_quarantined());
+        assert!(tracker.get_report().is_empty());
+    }
+}
@@ -1,16 +1,20 @@
 mod providers;
 mod sled;
+mod corruption;
 
 pub use self::{
     sled::SledStorage,
     providers::*,
+    corruption::{CorruptedEntry, CORRUPTION_THRESHOLD},
 };
 
 use std::{collections::HashSet, sync::Arc};
 use async_trait::async_trait;
 use xelis_common::{
+    account::{VersionedBalance, VersionedNonce},
+    api::daemon::StorageColumnStats,
     block::{Block, BlockHeader},
-    crypto::Hash,
+    crypto::{Hash, PublicKey},
     network::Network,
     transaction::Transaction,
 };
@@ -19,11 +23,37 @@ use crate::core::error::BlockchainError;
 // Represents the tips of the chain or of a block
 pub type Tips = HashSet<Hash>;
 
+// Account changes of a block, written at once at its topoheight
+#[derive(Default)]
+pub struct AccountChanges<'a> {
+    // new nonce version of each account
+    pub nonces: Vec<(&'a PublicKey, VersionedNonce)>,
+    // new balance version of each account and asset
+    pub balances: Vec<(&'a PublicKey, &'a Hash, VersionedBalance)>,
+    // accounts registered at this topoheight
+    pub registrations: Vec<&'a PublicKey>
+}
+
 #[async_trait]
-pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider + AccountProvider + ClientProtocolProvider + BlockDagProvider + MerkleHashProvider + Sync + Send + 'static {
+pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider + AccountProvider + ClientProtocolProvider + BlockDagProvider + MerkleHashProvider + AddressIndexProvider + FirstSeenProvider + Sync + Send + 'static {
     // Is the chain running on mainnet
     fn is_mainnet(&self) -> bool;
 
+    // Is the storage in recovery mode due to corrupted entries
+    fn is_quarantined(&self) -> bool;
+
+    // Get all the entries that failed to be read
+    fn get_corruption_report(&self) -> Vec<CorruptedEntry>;
+
+    // Get the keys of the corrupted entries in a tree
+    fn get_corrupted_keys(&self, tree: &str) -> Vec<Vec<u8>>;
+
+    // Forget the corrupted entries and leave the recovery mode
+    fn clear_corruption(&self);
+
+    // Overwrite a raw entry, used to restore data fetched from peers
+    async fn repair_entry(&mut self, tree: &str, key: &[u8], value: &[u8]) -> Result<(), BlockchainError>;
+
     // Clear caches if exists
     async fn clear_caches(&mut self) -> Result<(), BlockchainError>;
 
@@ -107,6 +137,17 @@ pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider +
     // Get the size of the chain on disk in bytes
     async fn get_size_on_disk(&self) -> Result<u64, BlockchainError>;
 
+    // Get the entries count and approximate size of each column, biggest first
+    // Every entry is read, this is slow on a big storage
+    async fn get_storage_stats(&self) -> Result<Vec<StorageColumnStats>, BlockchainError>;
+
+    // Write all the account changes of a block at topoheight in one atomic commit
+    // This has the same result as setting each nonce, balance and registration one by one
+    async fn apply_account_changes(&mut self, topoheight: u64, changes: AccountChanges<'_>) -> Result<(), BlockchainError>;
+
+    // Rewrite the storage to reclaim the space left by deleted entries
+    async fn compact(&mut self) -> Result<(), BlockchainError>;
+
     // Stop the storage and wait for it to finish
     async fn stop(&mut self) -> Result<(), BlockchainError>;
 }
\ No newline at end of file
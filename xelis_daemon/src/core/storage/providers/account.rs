@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use sled::Batch;
 use xelis_common::{crypto::PublicKey, serializer::Serializer};
 use crate::core::{error::{BlockchainError, DiskContext}, storage::SledStorage};
 
@@ -31,6 +32,24 @@ fn prefixed_db_key_no_u64(topoheight: &[u8], key: &PublicKey) -> [u8; 40] {
     buf
 }
 
+impl SledStorage {
+    // Build the writes of the registrations and their prefixed entries
+    // The prefixed entry of a previous registration is deleted
+    pub fn prepare_registrations(&self, keys: &[&PublicKey], topoheight: u64) -> Result<(Batch, Batch), BlockchainError> {
+        let mut registrations = Batch::default();
+        let mut prefixed = Batch::default();
+        for key in keys {
+            if let Some(old) = self.registrations.get(key.as_bytes())? {
+                prefixed.remove(&prefixed_db_key_no_u64(&old, key)[..]);
+            }
+
+            registrations.insert(&key.as_bytes()[..], topoheight.to_bytes());
+            prefixed.insert(&prefixed_db_key(topoheight, key)[..], &[] as &[u8]);
+        }
+
+        Ok((registrations, prefixed))
+    }
+}
 
 #[async_trait]
 impl AccountProvider for SledStorage {
@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use log::trace;
+use xelis_common::{
+    api::daemon::AddressTransactionType,
+    crypto::{Hash, PublicKey, HASH_SIZE},
+    serializer::Serializer
+};
+use crate::core::{
+    error::BlockchainError,
+    storage::{
+        sled::ADDRESS_INDEX_LOWEST_TOPOHEIGHT,
+        SledStorage
+    }
+};
+
+// Size of a key in the address index tree: key (32) + topoheight (8) + hash (32)
+const ADDRESS_INDEX_KEY_SIZE: usize = 32 + 8 + HASH_SIZE;
+
+// Address index provider keep track of all transactions (and block rewards)
+// involving an account, ordered by topoheight
+// It is optional and must be enabled in config as it can use a lot of disk space
+#[async_trait]
+pub trait AddressIndexProvider {
+    // Index a transaction or a block reward for an account at a specific topoheight
+    // For block rewards, the hash is the block hash
+    async fn index_address_transaction(&mut self, key: &PublicKey, topoheight: u64, hash: &Hash, entry_type: AddressTransactionType) -> Result<(), BlockchainError>;
+
+    // Get up to `maximum` entries for an account in the inclusive range of topoheights
+    // Entries are returned from the highest topoheight to the lowest
+    async fn get_address_transactions(&self, key: &PublicKey, minimum_topoheight: u64, maximum_topoheight: u64, skip: usize, maximum: usize) -> Result<Vec<(u64, Hash, AddressTransactionType)>, BlockchainError>;
+
+    // Delete all entries indexed at a certain topoheight
+    async fn delete_address_index_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Delete all entries indexed above the topoheight
+    async fn delete_address_index_above_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Delete all entries indexed below the topoheight
+    async fn delete_address_index_below_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Get the lowest topoheight from which the index is complete up to the top of the chain
+    // None if the index was never enabled
+    async fn get_address_index_lowest_topoheight(&self) -> Result<Option<u64>, BlockchainError>;
+
+    // Set the lowest topoheight from which the index is complete
+    async fn set_address_index_lowest_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Delete the whole index
+    async fn clear_address_index(&mut self) -> Result<(), BlockchainError>;
+}
+
+fn entry_type_to_byte(entry_type: AddressTransactionType) -> u8 {
+    match entry_type {
+        AddressTransactionType::Outgoing => 0,
+        AddressTransactionType::Incoming => 1,
+        AddressTransactionType::Mining => 2,
+        AddressTransactionType::DevFee => 3
+    }
+}
+
+fn entry_type_from_byte(byte: u8) -> Result<AddressTransactionType, BlockchainError> {
+    Ok(match byte {
+        0 => AddressTransactionType::Outgoing,
+        1 => AddressTransactionType::Incoming,
+        2 => AddressTransactionType::Mining,
+        3 => AddressTransactionType::DevFee,
+        _ => return Err(BlockchainError::Unknown)
+    })
+}
+
+// key (32) + topoheight (8) + hash (32)
+fn address_index_key(key: &PublicKey, topoheight: u64, hash: &Hash) -> [u8; ADDRESS_INDEX_KEY_SIZE] {
+    let mut buf = [0u8; ADDRESS_INDEX_KEY_SIZE];
+    buf[0..32].copy_from_slice(key.as_bytes());
+    buf[32..40].copy_from_slice(&topoheight.to_be_bytes());
+    buf[40..72].copy_from_slice(hash.as_bytes());
+    buf
+}
+
+// topoheight (8) + key (32) + hash (32)
+fn prefixed_address_index_key(key: &PublicKey, topoheight: u64, hash: &Hash) -> [u8; ADDRESS_INDEX_KEY_SIZE] {
+    let mut buf = [0u8; ADDRESS_INDEX_KEY_SIZE];
+    buf[0..8].copy_from_slice(&topoheight.to_be_bytes());
+    buf[8..40].copy_from_slice(key.as_bytes());
+    buf[40..72].copy_from_slice(hash.as_bytes());
+    buf
+}
+
+// Convert a prefixed key (topoheight first) to the key used in the address index tree
+fn from_prefixed_key(prefixed: &[u8]) -> [u8; ADDRESS_INDEX_KEY_SIZE] {
+    let mut buf = [0u8; ADDRESS_INDEX_KEY_SIZE];
+    buf[0..32].copy_from_slice(&prefixed[8..40]);
+    buf[32..40].copy_from_slice(&prefixed[0..8]);
+    buf[40..72].copy_from_slice(&prefixed[40..72]);
+    buf
+}
+
+impl SledStorage {
+    fn delete_address_index_prefixed_entry(&self, prefixed: &[u8]) -> Result<(), BlockchainError> {
+        self.address_index_prefixed.remove(prefixed)?;
+        self.address_index.remove(&from_prefixed_key(prefixed))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AddressIndexProvider for SledStorage {
+    async fn index_address_transaction(&mut self, key: &PublicKey, topoheight: u64, hash: &Hash, entry_type: AddressTransactionType) -> Result<(), BlockchainError> {
+        trace!("index address transaction {} at topoheight {}", hash, topoheight);
+        let db_key = address_index_key(key, topoheight, hash);
+        // Outgoing has priority over incoming in case of a transfer to itself
+        if entry_type == AddressTransactionType::Incoming {
+            if let Some(value) = self.address_index.get(&db_key)? {
+                if value.first() == Some(&entry_type_to_byte(AddressTransactionType::Outgoing)) {
+                    return Ok(())
+                }
+            }
+        }
+
+        self.address_index.insert(&db_key, &[entry_type_to_byte(entry_type)])?;
+        self.address_index_prefixed.insert(&prefixed_address_index_key(key, topoheight, hash), &[])?;
+        Ok(())
+    }
+
+    async fn get_address_transactions(&self, key: &PublicKey, minimum_topoheight: u64, maximum_topoheight: u64, skip: usize, maximum: usize) -> Result<Vec<(u64, Hash, AddressTransactionType)>, BlockchainError> {
+        trace!("get address transactions, minimum topoheight: {}, maximum topoheight: {}, skip: {}, maximum: {}", minimum_topoheight, maximum_topoheight, skip, maximum);
+        let mut start = [0u8; 40];
+        start[0..32].copy_from_slice(key.as_bytes());
+        start[32..40].copy_from_slice(&minimum_topoheight.to_be_bytes());
+
+        let mut end = [u8::MAX; ADDRESS_INDEX_KEY_SIZE];
+        end[0..32].copy_from_slice(key.as_bytes());
+        end[32..40].copy_from_slice(&maximum_topoheight.to_be_bytes());
+
+        let mut entries = Vec::new();
+        for el in self.address_index.range(start.as_slice()..=end.as_slice()).rev().skip(skip).take(maximum) {
+            let (db_key, value) = el?;
+            let topoheight = u64::from_bytes(&db_key[32..40])?;
+            let hash = Hash::from_bytes(&db_key[40..72])?;
+            let entry_type = entry_type_from_byte(*value.first().ok_or(BlockchainError::Unknown)?)?;
+            entries.push((topoheight, hash, entry_type));
+        }
+
+        Ok(entries)
+    }
+
+    async fn delete_address_index_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete address index at topoheight {}", topoheight);
+        for el in self.address_index_prefixed.scan_prefix(&topoheight.to_be_bytes()).keys() {
+            let key = el?;
+            self.delete_address_index_prefixed_entry(&key)?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_address_index_above_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete address index above topoheight {}", topoheight);
+        for el in self.address_index_prefixed.range((topoheight + 1).to_be_bytes()..).keys() {
+            let key = el?;
+            self.delete_address_index_prefixed_entry(&key)?;
+        }
+
+        // Index is only complete until the new top
+        if let Some(lowest) = self.get_address_index_lowest_topoheight().await? {
+            if lowest > topoheight + 1 {
+                self.set_address_index_lowest_topoheight(topoheight + 1).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_address_index_below_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete address index below topoheight {}", topoheight);
+        for el in self.address_index_prefixed.range(..topoheight.to_be_bytes()).keys() {
+            let key = el?;
+            self.delete_address_index_prefixed_entry(&key)?;
+        }
+
+        // Nothing below can be indexed anymore
+        if let Some(lowest) = self.get_address_index_lowest_topoheight().await? {
+            if lowest < topoheight {
+                self.set_address_index_lowest_topoheight(topoheight).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_address_index_lowest_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
+        trace!("get address index lowest topoheight");
+        self.load_optional_from_disk(&self.extra, ADDRESS_INDEX_LOWEST_TOPOHEIGHT)
+    }
+
+    async fn set_address_index_lowest_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("set address index lowest topoheight to {}", topoheight);
+        self.extra.insert(ADDRESS_INDEX_LOWEST_TOPOHEIGHT, &topoheight.to_be_bytes())?;
+        Ok(())
+    }
+
+    async fn clear_address_index(&mut self) -> Result<(), BlockchainError> {
+        trace!("clear address index");
+        self.address_index.clear()?;
+        self.address_index_prefixed.clear()?;
+        self.extra.remove(ADDRESS_INDEX_LOWEST_TOPOHEIGHT)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::atomic::Ordering;
+use std::{ops::Bound, sync::atomic::Ordering};
 use async_trait::async_trait;
 use indexmap::IndexSet;
 use log::trace;
@@ -28,6 +28,10 @@ pub trait AssetProvider {
     // TODO: replace with impl Iterator<Item = Result<Hash, BlockchainError>> when async trait methods are stable
     async fn get_partial_assets(&self, maximum: usize, skip: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<AssetWithData>, BlockchainError>;
 
+    // Get a page of assets ordered by hash, starting after the given asset
+    // Unlike skip, the next pages are not shifted by assets added meanwhile
+    async fn get_assets_after(&self, after: Option<&Hash>, maximum: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<AssetWithData>, BlockchainError>;
+
     // Get chunked assets
     // This is useful to not retrieve all assets at once
     async fn get_chunked_assets(&self, maximum: usize, skip: usize) -> Result<IndexSet<Hash>, BlockchainError>;
@@ -41,6 +45,9 @@ pub trait AssetProvider {
 
     // Add an asset to the storage
     async fn add_asset(&mut self, hash: &Hash, data: AssetData) -> Result<(), BlockchainError>;
+
+    // Overwrite the data of an asset already registered
+    async fn update_asset(&mut self, hash: &Hash, data: AssetData) -> Result<(), BlockchainError>;
 }
 
 #[async_trait]
@@ -88,6 +95,29 @@ impl AssetProvider for SledStorage {
         Ok(assets)
     }
 
+    async fn get_assets_after(&self, after: Option<&Hash>, maximum: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<AssetWithData>, BlockchainError> {
+        trace!("get assets after {:?}, maximum: {}", after, maximum);
+        let iter = match after {
+            Some(asset) => self.assets.range::<&[u8], _>((Bound::Excluded(asset.as_bytes().as_slice()), Bound::Unbounded)),
+            None => self.assets.iter()
+        };
+
+        let mut assets = IndexSet::new();
+        for el in iter {
+            let (key, value) = el?;
+            let data = AssetData::from_bytes(&value)?;
+            if data.get_topoheight() >= minimum_topoheight && data.get_topoheight() <= maximum_topoheight {
+                let asset = Hash::from_bytes(&key)?;
+                assets.insert(AssetWithData::new(asset, data));
+
+                if assets.len() == maximum {
+                    break;
+                }
+            }
+        }
+        Ok(assets)
+    }
+
     async fn get_chunked_assets(&self, maximum: usize, skip: usize) -> Result<IndexSet<Hash>, BlockchainError> {
         let mut assets = IndexSet::with_capacity(maximum);
         for el in self.assets.iter().keys().skip(skip).take(maximum) {
@@ -127,4 +157,14 @@ impl AssetProvider for SledStorage {
         }
         Ok(())
     }
+
+    async fn update_asset(&mut self, asset: &Hash, data: AssetData) -> Result<(), BlockchainError> {
+        trace!("update asset {}", asset);
+        if !self.has_asset(asset).await? {
+            return Err(BlockchainError::AssetNotFound(asset.clone()))
+        }
+
+        self.assets.insert(asset.as_bytes(), data.to_bytes())?;
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
+use std::{panic::resume_unwind, thread};
 use async_trait::async_trait;
 use log::{trace, error};
+use sled::Batch;
 use xelis_common::{
     account::VersionedBalance,
     crypto::{
@@ -9,7 +11,10 @@ use xelis_common::{
     serializer::Serializer
 };
 
-use crate::core::{error::{BlockchainError, DiskContext}, storage::SledStorage};
+use crate::{
+    config::{COMMIT_WORKERS, PARALLEL_COMMIT_THRESHOLD},
+    core::{error::{BlockchainError, DiskContext}, storage::SledStorage}
+};
 use super::AssetProvider;
 
 #[async_trait]
@@ -43,6 +48,10 @@ pub trait BalanceProvider: AssetProvider {
     // Get the last balance of the account, this is based on the last topoheight (pointer) available
     async fn get_last_balance(&self, key: &PublicKey, asset: &Hash) -> Result<(u64, VersionedBalance), BlockchainError>;
 
+    // Get up to `maximum` versions of the balance, from the highest one under or equal to maximum topoheight
+    // by following the previous topoheight pointers until the minimum topoheight (inclusive)
+    async fn get_balance_history(&self, key: &PublicKey, asset: &Hash, minimum_topoheight: u64, maximum_topoheight: u64, maximum: usize) -> Result<Vec<(u64, VersionedBalance)>, BlockchainError>;
+
     // Get the asset versioned balances for multiple keys
     async fn get_versioned_balances<'a, I: Iterator<Item = &'a PublicKey> + Send>(&self, asset: &Hash, keys: I, maximum_topoheight: u64) -> Result<Vec<Option<VersionedBalance>>, BlockchainError>;
 
@@ -86,6 +95,46 @@ impl SledStorage {
         bytes
     }
 
+    // Build the writes of the balances versions and their last topoheight pointers
+    // The serialization of the versions is split across the commit workers
+    pub fn prepare_balances(&self, balances: &[(&PublicKey, &Hash, VersionedBalance)], topoheight: u64) -> (Batch, Batch) {
+        trace!("prepare {} balances at topoheight {}", balances.len(), topoheight);
+        let chunk_size = if balances.len() > PARALLEL_COMMIT_THRESHOLD {
+            balances.len().div_ceil(COMMIT_WORKERS)
+        } else {
+            balances.len().max(1)
+        };
+
+        let serialize = |chunk: &[(&PublicKey, &Hash, VersionedBalance)]| -> Vec<([u8; 64], [u8; 72], Vec<u8>)> {
+            chunk.iter()
+                .map(|(key, asset, version)| (self.get_balance_key_for(key, asset), self.get_versioned_balance_key(key, asset, topoheight), version.to_bytes()))
+                .collect()
+        };
+
+        let entries: Vec<_> = if chunk_size < balances.len() {
+            thread::scope(|scope| {
+                let handles: Vec<_> = balances.chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || serialize(chunk)))
+                    .collect();
+
+                handles.into_iter()
+                    .flat_map(|handle| handle.join().unwrap_or_else(|e| resume_unwind(e)))
+                    .collect()
+            })
+        } else {
+            serialize(balances)
+        };
+
+        let mut last_topoheights = Batch::default();
+        let mut versions = Batch::default();
+        for (key, versioned_key, value) in entries {
+            last_topoheights.insert(&key[..], &topoheight.to_be_bytes()[..]);
+            versions.insert(&versioned_key[..], value);
+        }
+
+        (last_topoheights, versions)
+    }
+
     async fn has_balance_internal(&self, key: &[u8; 64]) -> Result<bool, BlockchainError> {
         trace!("has balance internal");
         Ok(self.balances.contains_key(key)?)
@@ -279,6 +328,32 @@ impl BalanceProvider for SledStorage {
         Ok((topoheight, version))
     }
 
+    async fn get_balance_history(&self, key: &PublicKey, asset: &Hash, minimum_topoheight: u64, maximum_topoheight: u64, maximum: usize) -> Result<Vec<(u64, VersionedBalance)>, BlockchainError> {
+        trace!("get balance {} history for {} from {} to {}", asset, key.as_address(self.is_mainnet()), minimum_topoheight, maximum_topoheight);
+        let mut history = Vec::new();
+        let mut next = self.get_balance_at_maximum_topoheight(key, asset, maximum_topoheight).await?;
+        while let Some((topoheight, version)) = next.take() {
+            if topoheight < minimum_topoheight || history.len() >= maximum {
+                break;
+            }
+
+            if let Some(previous) = version.get_previous_topoheight() {
+                if previous >= topoheight {
+                    error!("FATAL ERROR: Previous topoheight ({}) should not be higher than current version ({})!", previous, topoheight);
+                    return Err(BlockchainError::Unknown)
+                }
+
+                if previous >= minimum_topoheight {
+                    next = Some((previous, self.get_balance_at_exact_topoheight(key, asset, previous).await?));
+                }
+            }
+
+            history.push((topoheight, version));
+        }
+
+        Ok(history)
+    }
+
     async fn get_versioned_balances<'a, I: Iterator<Item = &'a PublicKey> + Send>(&self, asset: &Hash, keys: I, maximum_topoheight: u64) -> Result<Vec<Option<VersionedBalance>>, BlockchainError> {
         trace!("get balances for asset {} at maximum topoheight {}", asset, maximum_topoheight);
         let mut balances = Vec::new();
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use log::trace;
+use xelis_common::{
+    crypto::Hash,
+    time::TimestampMillis
+};
+use crate::core::{
+    error::BlockchainError,
+    storage::SledStorage
+};
+
+// Local time at which this node saw a block or a transaction for the first time
+// Blocks and transactions share the same tree as their hashes can't collide
+#[async_trait]
+pub trait FirstSeenProvider {
+    // Get the first seen timestamp in milliseconds
+    async fn get_first_seen(&self, hash: &Hash) -> Result<Option<TimestampMillis>, BlockchainError>;
+
+    // Store the first seen timestamp only if none is set yet, so reorgs don't overwrite it
+    // Returns true if it got stored
+    async fn set_first_seen_if_absent(&mut self, hash: &Hash, timestamp: TimestampMillis) -> Result<bool, BlockchainError>;
+
+    // Delete the first seen timestamp with its object
+    async fn delete_first_seen(&mut self, hash: &Hash) -> Result<(), BlockchainError>;
+}
+
+#[async_trait]
+impl FirstSeenProvider for SledStorage {
+    async fn get_first_seen(&self, hash: &Hash) -> Result<Option<TimestampMillis>, BlockchainError> {
+        trace!("get first seen for {}", hash);
+        self.load_optional_from_disk(&self.first_seen, hash.as_bytes())
+    }
+
+    async fn set_first_seen_if_absent(&mut self, hash: &Hash, timestamp: TimestampMillis) -> Result<bool, BlockchainError> {
+        trace!("set first seen for {} to {}", hash, timestamp);
+        let stored = self.first_seen.compare_and_swap(hash.as_bytes(), None as Option<&[u8]>, Some(timestamp.to_be_bytes().to_vec()))?;
+        Ok(stored.is_ok())
+    }
+
+    async fn delete_first_seen(&mut self, hash: &Hash) -> Result<(), BlockchainError> {
+        trace!("delete first seen for {}", hash);
+        self.first_seen.remove(hash.as_bytes())?;
+        Ok(())
+    }
+}
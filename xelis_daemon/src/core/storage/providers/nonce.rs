@@ -1,8 +1,9 @@
-use std::sync::atomic::Ordering;
+use std::{ops::Bound, sync::atomic::Ordering};
 
 use async_trait::async_trait;
 use indexmap::IndexSet;
 use log::{trace, error};
+use sled::Batch;
 use xelis_common::{
     account::VersionedNonce,
     crypto::PublicKey,
@@ -30,6 +31,10 @@ pub trait NonceProvider: BalanceProvider {
     // Returned keys must have a nonce or a balance updated in the range given
     async fn get_partial_keys(&self, maximum: usize, skip: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<PublicKey>, BlockchainError>;
 
+    // Get a page of keys ordered by their bytes, starting after the given key
+    // Unlike skip, the next pages are not shifted by accounts registered meanwhile
+    async fn get_keys_after(&self, after: Option<&PublicKey>, maximum: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<PublicKey>, BlockchainError>;
+
     // Get the last topoheigh that the account has a nonce
     async fn get_last_topoheight_for_nonce(&self, key: &PublicKey) -> Result<u64, BlockchainError>;
 
@@ -69,6 +74,24 @@ impl SledStorage {
         Ok(())
     }
 
+    // Build the writes of the nonces versions and their last topoheight pointers
+    // Returns also how many accounts have their first nonce
+    pub fn prepare_nonces(&self, nonces: &[(&PublicKey, VersionedNonce)], topoheight: u64) -> Result<(Batch, Batch, u64), BlockchainError> {
+        trace!("prepare {} nonces at topoheight {}", nonces.len(), topoheight);
+        let mut last_topoheights = Batch::default();
+        let mut versions = Batch::default();
+        let mut new_accounts = 0;
+        for (key, version) in nonces {
+            versions.insert(&self.get_versioned_nonce_key(key, topoheight)[..], version.to_bytes());
+            if !self.nonces.contains_key(key.as_bytes())? {
+                new_accounts += 1;
+            }
+            last_topoheights.insert(&key.as_bytes()[..], &topoheight.to_be_bytes()[..]);
+        }
+
+        Ok((last_topoheights, versions, new_accounts))
+    }
+
     // Versioned key is a 40 bytes key with topoheight as first bytes and the key as last bytes
     pub fn get_versioned_nonce_key(&self, key: &PublicKey, topoheight: u64) -> [u8; 40] {
         trace!("get versioned balance key at {} for {}", topoheight, key.as_address(self.is_mainnet()));
@@ -146,6 +169,29 @@ impl NonceProvider for SledStorage {
         Ok(keys)
     }
 
+    async fn get_keys_after(&self, after: Option<&PublicKey>, maximum: usize, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<IndexSet<PublicKey>, BlockchainError> {
+        trace!("get keys after, maximum: {}, minimum_topoheight: {}, maximum_topoheight: {}", maximum, minimum_topoheight, maximum_topoheight);
+        let iter = match after {
+            Some(key) => self.nonces.range::<&[u8], _>((Bound::Excluded(key.as_bytes().as_slice()), Bound::Unbounded)),
+            None => self.nonces.iter()
+        };
+
+        let mut keys: IndexSet<PublicKey> = IndexSet::new();
+        for el in iter.keys() {
+            let key = el?;
+            let pkey = PublicKey::from_bytes(&key)?;
+            if self.has_key_updated_in_range(&pkey, minimum_topoheight, maximum_topoheight).await? {
+                keys.insert(pkey);
+
+                if keys.len() == maximum {
+                    break;
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
     async fn get_last_nonce(&self, key: &PublicKey) -> Result<(u64, VersionedNonce), BlockchainError> {
         trace!("get last nonce {}", key.as_address(self.is_mainnet()));
         if !self.has_nonce(key).await? {
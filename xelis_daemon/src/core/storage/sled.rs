@@ -5,28 +5,43 @@ use crate::{
     config::PRUNE_SAFETY_LIMIT,
     core::error::{BlockchainError, DiskContext}
 };
+use super::{
+    corruption::{CorruptedEntry, CorruptionTracker},
+    AccountChanges
+};
 use xelis_common::{
     account::{VersionedBalance, VersionedNonce},
+    api::daemon::StorageColumnStats,
     block::{Block, BlockHeader},
     crypto::{Hash, PublicKey},
-    difficulty::{CumulativeDifficulty, Difficulty},
+    difficulty::CumulativeDifficulty,
     immutable::Immutable,
     network::Network,
-    serializer::{Reader, Serializer},
+    serializer::{Reader, ReaderError, Serializer},
     transaction::Transaction
 };
 use std::{
     collections::HashSet,
+    fs,
     hash::Hash as StdHash,
+    path::Path,
     sync::{Arc, atomic::{AtomicU64, Ordering}},
-    num::NonZeroUsize
+    num::NonZeroUsize,
+    panic::resume_unwind,
+    thread
 };
 use tokio::sync::Mutex;
 use lru::LruCache;
-use sled::Tree;
+use sled::{
+    transaction::{ConflictableTransactionError, TransactionError, TransactionResult},
+    Batch,
+    Transactional,
+    Tree
+};
 use log::{debug, trace, warn, info};
 
 use super::{
+    AddressIndexProvider,
     BalanceProvider,
     BlocksAtHeightProvider,
     DagOrderProvider,
@@ -51,10 +66,16 @@ pub(super) const ACCOUNTS_COUNT: &[u8; 4] = b"CACC";
 pub(super) const TXS_COUNT: &[u8; 4] = b"CTXS";
 const ASSETS_COUNT: &[u8; 4] = b"CAST";
 pub(super) const BLOCKS_COUNT: &[u8; 4] = b"CBLK";
+// Lowest topoheight from which the address index is complete
+pub(super) const ADDRESS_INDEX_LOWEST_TOPOHEIGHT: &[u8; 4] = b"AIDX";
 
 pub struct SledStorage {
     // Network used by the storage
     network: Network,
+    // Directory of the database, None if it is temporary
+    path: Option<String>,
+    // Size of each cache, kept to reopen the database
+    cache_size: Option<usize>,
     // All trees used to store data
     // all txs stored on disk
     pub(super) transactions: Tree,
@@ -98,6 +119,12 @@ pub struct SledStorage {
     pub(super) registrations: Tree,
     // Account registrations prefixed by their topoheight for easier deletion
     pub(super) registrations_prefixed: Tree,
+    // Optional index of transactions by involved account
+    pub(super) address_index: Tree,
+    // Address index entries prefixed by their topoheight for easier deletion
+    pub(super) address_index_prefixed: Tree,
+    // First seen timestamps of the blocks and transactions
+    pub(super) first_seen: Tree,
     // opened DB used for assets to create dynamic assets
     db: sled::Db,
 
@@ -133,7 +160,9 @@ pub struct SledStorage {
     // Count of transactions
     pub(super) transactions_count: AtomicU64,
     // Count of blocks
-    pub(super) blocks_count: AtomicU64
+    pub(super) blocks_count: AtomicU64,
+    // Entries that failed to be deserialized
+    corruption: CorruptionTracker
 }
 
 macro_rules! init_cache {
@@ -148,9 +177,27 @@ macro_rules! init_cache {
 
 impl SledStorage {
     pub fn new(dir_path: String, cache_size: Option<usize>, network: Network) -> Result<Self, BlockchainError> {
-        let sled = sled::open(format!("{}{}", dir_path, network.to_string().to_lowercase()))?;
+        let path = format!("{}{}", dir_path, network.to_string().to_lowercase());
+        let sled = sled::open(&path)?;
+        Self::with_db(sled, Some(path), cache_size, network)
+    }
+
+    // Create a storage kept in memory and deleted once dropped
+    pub fn temporary(cache_size: Option<usize>, network: Network) -> Result<Self, BlockchainError> {
+        let sled = sled::Config::new().temporary(true).open()?;
+        Self::with_db(sled, None, cache_size, network)
+    }
+
+    // CRC32 of all the keys and values stored, used to detect any write
+    pub fn checksum(&self) -> Result<u32, BlockchainError> {
+        Ok(self.db.checksum()?)
+    }
+
+    fn with_db(sled: sled::Db, path: Option<String>, cache_size: Option<usize>, network: Network) -> Result<Self, BlockchainError> {
         let mut storage = Self {
             network,
+            path,
+            cache_size,
             transactions: sled.open_tree("transactions")?,
             txs_executed: sled.open_tree("txs_executed")?,
             blocks: sled.open_tree("blocks")?,
@@ -172,6 +219,9 @@ impl SledStorage {
             merkle_hashes: sled.open_tree("merkle_hashes")?,
             registrations: sled.open_tree("registrations")?,
             registrations_prefixed: sled.open_tree("registrations_prefixed")?,
+            address_index: sled.open_tree("address_index")?,
+            address_index_prefixed: sled.open_tree("address_index_prefixed")?,
+            first_seen: sled.open_tree("first_seen")?,
             db: sled,
             transactions_cache: init_cache!(cache_size),
             blocks_cache: init_cache!(cache_size),
@@ -187,7 +237,8 @@ impl SledStorage {
             assets_count: AtomicU64::new(0),
             accounts_count: AtomicU64::new(0),
             transactions_count: AtomicU64::new(0),
-            blocks_count: AtomicU64::new(0)
+            blocks_count: AtomicU64::new(0),
+            corruption: CorruptionTracker::new()
         };
 
         // Verify that we are opening a DB on same network
@@ -244,12 +295,19 @@ impl SledStorage {
         self.network.is_mainnet()
     }
 
+    // Record the entry as corrupted and build the error to return
+    fn on_corrupted_data(&self, tree: &Tree, key: &[u8], error: ReaderError) -> BlockchainError {
+        let name = String::from_utf8_lossy(&tree.name()).into_owned();
+        self.corruption.record(&name, key, &error);
+        BlockchainError::CorruptedData(name, hex::encode(key))
+    }
+
     pub(super) fn load_optional_from_disk<T: Serializer>(&self, tree: &Tree, key: &[u8]) -> Result<Option<T>, BlockchainError> {
         match tree.get(key)? {
             Some(bytes) => {
                 let bytes = bytes.to_vec();
                 let mut reader = Reader::new(&bytes);
-                let value = T::read(&mut reader)?;
+                let value = T::read(&mut reader).map_err(|e| self.on_corrupted_data(tree, key, e))?;
                 Ok(Some(value))
             },
             None => Ok(None)
@@ -261,7 +319,7 @@ impl SledStorage {
             Some(bytes) => {
                 let bytes = bytes.to_vec();
                 let mut reader = Reader::new(&bytes);
-                let value = T::read(&mut reader)?;
+                let value = T::read(&mut reader).map_err(|e| self.on_corrupted_data(tree, key, e))?;
                 Ok(value)
             },
             None => Err(BlockchainError::NotFoundOnDisk(context))
@@ -385,6 +443,30 @@ impl Storage for SledStorage {
         self.network.is_mainnet()
     }
 
+    fn is_quarantined(&self) -> bool {
+        self.corruption.is_quarantined()
+    }
+
+    fn get_corruption_report(&self) -> Vec<CorruptedEntry> {
+        self.corruption.get_report()
+    }
+
+    fn get_corrupted_keys(&self, tree: &str) -> Vec<Vec<u8>> {
+        self.corruption.get_keys_in_tree(tree)
+    }
+
+    fn clear_corruption(&self) {
+        self.corruption.clear();
+    }
+
+    async fn repair_entry(&mut self, tree: &str, key: &[u8], value: &[u8]) -> Result<(), BlockchainError> {
+        warn!("Repairing entry {} in tree {}", hex::encode(key), tree);
+        let tree = self.db.open_tree(tree)?;
+        tree.insert(key, value)?;
+        // Make sure no stale value is served from the caches
+        self.clear_caches().await
+    }
+
     async fn clear_caches(&mut self) -> Result<(), BlockchainError> {
         if let Some(cache) = self.transactions_cache.as_ref() {
             let mut cache = cache.lock().await;
@@ -448,15 +530,14 @@ impl Storage for SledStorage {
         let block = self.delete_data(&self.blocks, &self.blocks_cache, &hash).await?;
         trace!("block header deleted successfully");
 
+        // These values are not read back so a corrupted entry can still be rewinded
         trace!("Deleting supply and block reward");
-        let supply: u64 = self.delete_cacheable_data(&self.supply, &None, &topoheight).await?;
-        trace!("Supply was {}", supply);
-
-        let reward: u64 = self.delete_cacheable_data(&self.rewards, &None, &topoheight).await?;
-        trace!("Reward for block {} was: {}", hash, reward);
+        self.supply.remove(topoheight.to_be_bytes())?;
+        self.rewards.remove(topoheight.to_be_bytes())?;
 
         trace!("Deleting difficulty");
-        let _: Difficulty = self.delete_cacheable_data(&self.difficulty, &None, &hash).await?;
+        self.difficulty.remove(hash.as_bytes())?;
+        self.first_seen.remove(hash.as_bytes())?;
 
         trace!("Deleting cumulative difficulty");
         let cumulative_difficulty: CumulativeDifficulty = self.delete_cacheable_data(&self.cumulative_difficulty, &self.cumulative_difficulty_cache, &hash).await?;
@@ -482,6 +563,7 @@ impl Storage for SledStorage {
             if self.contains_data(&self.transactions, &self.transactions_cache, tx_hash).await? {
                 trace!("Deleting TX {} in block {}", tx_hash, hash);
                 let tx: Arc<Transaction> = self.delete_data(&self.transactions, &self.transactions_cache, tx_hash).await?;
+                self.first_seen.remove(tx_hash.as_bytes())?;
                 txs.push((tx_hash.clone(), tx));
             }
         }
@@ -962,6 +1044,8 @@ impl Storage for SledStorage {
         self.delete_versioned_nonces_above_topoheight(topoheight).await?;
         // Delete also registrations
         self.delete_registrations_above_topoheight(topoheight).await?;
+        // And the address index
+        self.delete_address_index_above_topoheight(topoheight).await?;
 
         trace!("Cleaning caches");
         // Clear all caches to not have old data after rewind
@@ -1043,6 +1127,106 @@ impl Storage for SledStorage {
         Ok(self.db.size_on_disk()?)
     }
 
+    async fn get_storage_stats(&self) -> Result<Vec<StorageColumnStats>, BlockchainError> {
+        trace!("get storage stats");
+        let mut columns = Vec::new();
+        for name in self.db.tree_names() {
+            let tree = self.db.open_tree(&name)?;
+            let mut entries = 0;
+            let mut size_bytes = 0;
+            for el in tree.iter() {
+                let (key, value) = el?;
+                entries += 1;
+                size_bytes += (key.len() + value.len()) as u64;
+            }
+
+            columns.push(StorageColumnStats {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                entries,
+                size_bytes
+            });
+        }
+
+        columns.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.name.cmp(&b.name)));
+        Ok(columns)
+    }
+
+    async fn apply_account_changes(&mut self, topoheight: u64, changes: AccountChanges<'_>) -> Result<(), BlockchainError> {
+        trace!("apply {} nonces, {} balances and {} registrations at topoheight {}", changes.nonces.len(), changes.balances.len(), changes.registrations.len(), topoheight);
+        // Each write set uses its own trees, they are prepared at the same time
+        let storage = &*self;
+        let ((balances, versioned_balances), nonces, registrations) = thread::scope(|scope| {
+            let balances = scope.spawn(|| storage.prepare_balances(&changes.balances, topoheight));
+            let nonces = storage.prepare_nonces(&changes.nonces, topoheight);
+            let registrations = storage.prepare_registrations(&changes.registrations, topoheight);
+            (balances.join().unwrap_or_else(|e| resume_unwind(e)), nonces, registrations)
+        });
+        let (nonces, versioned_nonces, new_accounts) = nonces?;
+        let (registrations, registrations_prefixed) = registrations?;
+
+        let accounts_count = self.count_accounts().await? + new_accounts;
+        let mut extra = Batch::default();
+        if new_accounts > 0 {
+            extra.insert(&ACCOUNTS_COUNT[..], &accounts_count.to_be_bytes()[..]);
+        }
+
+        // Single commit point, nothing is written if any batch fails
+        let trees: &[&Tree] = &[&self.balances, &self.versioned_balances, &self.nonces, &self.versioned_nonces, &self.registrations, &self.registrations_prefixed, &self.extra];
+        let batches = [balances, versioned_balances, nonces, versioned_nonces, registrations, registrations_prefixed, extra];
+        let res: TransactionResult<(), BlockchainError> = trees.transaction(|trees| {
+            for (tree, batch) in trees.iter().zip(batches.iter()) {
+                tree.apply_batch(batch)?;
+            }
+            Ok::<(), ConflictableTransactionError<BlockchainError>>(())
+        });
+
+        match res {
+            Ok(()) => {},
+            Err(TransactionError::Abort(e)) => return Err(e),
+            Err(TransactionError::Storage(e)) => return Err(e.into())
+        };
+
+        self.accounts_count.store(accounts_count, Ordering::SeqCst);
+        Ok(())
+    }
+
+    // sled has no compaction API, all the trees are copied in a new database
+    // which replaces the current one once fully written
+    async fn compact(&mut self) -> Result<(), BlockchainError> {
+        let path = self.path.clone().ok_or(BlockchainError::UnsupportedOperation)?;
+        let compact_path = format!("{}.compact", path);
+        let old_path = format!("{}.old", path);
+
+        info!("Compacting database, writes are paused until it's done");
+        self.db.flush_async().await?;
+        if Path::new(&compact_path).exists() {
+            fs::remove_dir_all(&compact_path)?;
+        }
+
+        {
+            let compacted = sled::open(&compact_path)?;
+            compacted.import(self.db.export());
+            compacted.flush_async().await?;
+        }
+
+        fs::rename(&path, &old_path)?;
+        if let Err(e) = fs::rename(&compact_path, &path) {
+            // Put back the current database
+            fs::rename(&old_path, &path)?;
+            return Err(e.into())
+        }
+
+        let mut storage = Self::with_db(sled::open(&path)?, Some(path), self.cache_size, self.network)?;
+        // Keep the entries detected as corrupted until they are repaired
+        std::mem::swap(&mut storage.corruption, &mut self.corruption);
+        // Previous database is closed once dropped
+        *self = storage;
+
+        fs::remove_dir_all(&old_path)?;
+        info!("Database compacted");
+        Ok(())
+    }
+
     async fn stop(&mut self) -> Result<(), BlockchainError> {
         info!("Stopping Storage...");
         info!("Flushing Sled database");
@@ -1050,4 +1234,146 @@ impl Storage for SledStorage {
         info!("Sled database flushed");
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+    use xelis_common::{
+        account::CiphertextCache,
+        config::XELIS_ASSET,
+        crypto::KeyPair
+    };
+    use crate::{config::PARALLEL_COMMIT_THRESHOLD, core::storage::AccountProvider};
+    use super::*;
+
+    struct Accounts {
+        keys: Vec<PublicKey>,
+        balances: Vec<VersionedBalance>,
+        asset: Hash
+    }
+
+    impl Accounts {
+        fn new(count: usize) -> Self {
+            let mut keys = Vec::new();
+            let mut balances = Vec::new();
+            for i in 0..count {
+                let keypair = KeyPair::new();
+                balances.push(VersionedBalance::new(CiphertextCache::Decompressed(keypair.get_public_key().encrypt(i as u64)), Some(1)));
+                keys.push(keypair.get_public_key().compress());
+            }
+
+            Self {
+                keys,
+                balances,
+                asset: Hash::new([1; 32])
+            }
+        }
+
+        // Half of the accounts already exist, each account has two assets updated
+        fn changes(&self) -> AccountChanges<'_> {
+            let mut changes = AccountChanges::default();
+            for (i, (key, balance)) in self.keys.iter().zip(self.balances.iter()).enumerate() {
+                changes.nonces.push((key, VersionedNonce::new(i as u64, Some(1))));
+                changes.balances.push((key, &XELIS_ASSET, balance.clone()));
+                changes.balances.push((key, &self.asset, balance.clone()));
+                if i % 2 == 0 {
+                    changes.registrations.push(key);
+                }
+            }
+            changes
+        }
+
+        // Previous state at topoheight 1
+        async fn setup(&self, storage: &mut SledStorage) {
+            for key in self.keys.iter().step_by(2) {
+                storage.set_last_nonce_to(key, 1, &VersionedNonce::new(0, None)).await.unwrap();
+                storage.set_account_registration_topoheight(key, 1).await.unwrap();
+            }
+        }
+    }
+
+    // Write the changes one by one like before the batched commit
+    async fn apply_sequentially(storage: &mut SledStorage, topoheight: u64, changes: AccountChanges<'_>) {
+        for (key, nonce) in changes.nonces {
+            storage.set_last_nonce_to(key, topoheight, &nonce).await.unwrap();
+        }
+
+        for (key, asset, version) in changes.balances {
+            storage.set_last_balance_to(key, asset, topoheight, &version).await.unwrap();
+        }
+
+        for key in changes.registrations {
+            storage.set_account_registration_topoheight(key, topoheight).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_changes_match_sequential_writes() {
+        for count in [1, 10, PARALLEL_COMMIT_THRESHOLD + 1, 500] {
+            let accounts = Accounts::new(count);
+            let mut sequential = SledStorage::temporary(None, Network::Dev).unwrap();
+            let mut batched = SledStorage::temporary(None, Network::Dev).unwrap();
+            accounts.setup(&mut sequential).await;
+            accounts.setup(&mut batched).await;
+            assert_eq!(sequential.checksum().unwrap(), batched.checksum().unwrap());
+
+            apply_sequentially(&mut sequential, 5, accounts.changes()).await;
+            batched.apply_account_changes(5, accounts.changes()).await.unwrap();
+
+            assert_eq!(sequential.checksum().unwrap(), batched.checksum().unwrap());
+            assert_eq!(sequential.count_accounts().await.unwrap(), batched.count_accounts().await.unwrap());
+            assert_eq!(batched.count_accounts().await.unwrap(), count as u64);
+            for key in accounts.keys.iter() {
+                assert_eq!(batched.get_account_registration_topoheight(key).await.unwrap(), sequential.get_account_registration_topoheight(key).await.unwrap());
+                assert_eq!(batched.get_last_nonce(key).await.unwrap().0, 5);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_empty_account_changes() {
+        let mut storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        let checksum = storage.checksum().unwrap();
+        storage.apply_account_changes(1, AccountChanges::default()).await.unwrap();
+        assert_eq!(storage.checksum().unwrap(), checksum);
+    }
+
+    // Compare the commit latency of a block updating 500 accounts, run it with --ignored --nocapture
+    #[tokio::test]
+    #[ignore]
+    async fn bench_account_changes() {
+        let accounts = Accounts::new(500);
+        let mut sequential = SledStorage::temporary(None, Network::Dev).unwrap();
+        let mut batched = SledStorage::temporary(None, Network::Dev).unwrap();
+
+        let start = Instant::now();
+        apply_sequentially(&mut sequential, 5, accounts.changes()).await;
+        let sequential_time = start.elapsed();
+
+        let start = Instant::now();
+        batched.apply_account_changes(5, accounts.changes()).await.unwrap();
+        let batched_time = start.elapsed();
+
+        println!("sequential: {:?}, batched: {:?}", sequential_time, batched_time);
+    }
+
+    #[tokio::test]
+    async fn test_storage_stats() {
+        let mut storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        let accounts = Accounts::new(10);
+        accounts.setup(&mut storage).await;
+
+        let columns = storage.get_storage_stats().await.unwrap();
+        let column = |name: &str| columns.iter().find(|column| column.name == name).unwrap();
+        assert_eq!(column("nonces").entries, 5);
+        assert_eq!(column("registrations").entries, 5);
+        assert_eq!(column("blocks").entries, 0);
+        assert_eq!(column("blocks").size_bytes, 0);
+        // Network is always stored
+        assert!(column("extra").size_bytes > 0);
+
+        // Biggest first
+        assert!(columns.windows(2).all(|w| w[0].size_bytes >= w[1].size_bytes));
+    }
+}
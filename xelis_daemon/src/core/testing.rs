@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use clap::Parser;
+use xelis_common::{
+    account::{CiphertextCache, VersionedBalance, VersionedNonce},
+    config::XELIS_ASSET,
+    crypto::PublicKey,
+    network::Network
+};
+use crate::config::DEFAULT_CACHE_SIZE;
+use super::{
+    blockchain::{Blockchain, Config},
+    storage::{BalanceProvider, NonceProvider, SledStorage}
+};
+
+#[derive(Parser)]
+struct TestConfig {
+    #[clap(flatten)]
+    config: Config
+}
+
+// Parse the daemon options, the binary name is added before them
+pub fn test_config(args: &[&str]) -> Config {
+    TestConfig::parse_from(std::iter::once("xelis_daemon").chain(args.iter().copied())).config
+}
+
+// Start a devnet chain using this config on a temporary storage
+pub async fn start_blockchain(config: Config) -> Arc<Blockchain<SledStorage>> {
+    let storage = SledStorage::temporary(Some(DEFAULT_CACHE_SIZE), Network::Dev).unwrap();
+    Blockchain::new(config, Network::Dev, storage).await.unwrap()
+}
+
+// Devnet chain without P2P and RPC servers
+pub async fn test_blockchain() -> Arc<Blockchain<SledStorage>> {
+    test_blockchain_with(&[]).await
+}
+
+// Same as test_blockchain with more daemon options
+pub async fn test_blockchain_with(args: &[&str]) -> Arc<Blockchain<SledStorage>> {
+    let mut options = vec!["--disable-p2p-server", "--disable-rpc-server"];
+    options.extend_from_slice(args);
+    start_blockchain(test_config(&options)).await
+}
+
+// Register the account with a XELIS balance
+pub async fn fund_account(blockchain: &Blockchain<SledStorage>, key: &PublicKey, amount: u64) {
+    let ciphertext = key.decompress().unwrap().encrypt(amount);
+    let mut storage = blockchain.get_storage().write().await;
+    storage.set_last_balance_to(key, &XELIS_ASSET, 0, &VersionedBalance::new(CiphertextCache::Decompressed(ciphertext), None)).await.unwrap();
+    storage.set_last_nonce_to(key, 0, &VersionedNonce::new(0, None)).await.unwrap();
+}
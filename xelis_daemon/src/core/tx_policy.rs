@@ -0,0 +1,257 @@
+use std::{
+    collections::HashSet,
+    fs,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use log::{info, warn};
+use xelis_common::{
+    crypto::{
+        Address,
+        Hash,
+        PublicKey
+    },
+    network::Network,
+    serializer::Serializer,
+    transaction::{Transaction, TransactionType}
+};
+use super::error::BlockchainError;
+
+// Local policy to never relay or mine some transactions
+// Filtered TXs are refused in mempool and skipped in block templates
+// but blocks containing them are still accepted: this is not a consensus rule
+//
+// The file contains one entry per line, an address or a TX hash
+// Empty lines and lines starting with '#' are ignored
+pub struct TxPolicy {
+    // file from which the entries are loaded and saved
+    path: Option<String>,
+    mainnet: bool,
+    // TXs sent by or to these addresses are filtered
+    addresses: HashSet<PublicKey>,
+    hashes: HashSet<Hash>,
+    // TXs refused in mempool
+    filtered_mempool: AtomicU64,
+    // TXs skipped when building a block template
+    filtered_templates: AtomicU64
+}
+
+// An entry of the policy file
+pub enum TxPolicyEntry {
+    Address(PublicKey),
+    Hash(Hash)
+}
+
+impl TxPolicyEntry {
+    pub fn parse(value: &str, mainnet: bool) -> Result<Self, BlockchainError> {
+        let value = value.trim();
+        if let Ok(hash) = Hash::from_hex(value.to_owned()) {
+            return Ok(Self::Hash(hash))
+        }
+
+        let address = Address::from_string(&value.to_owned())
+            .map_err(|_| BlockchainError::InvalidTxPolicyEntry(value.to_owned()))?;
+        if address.is_mainnet() != mainnet {
+            return Err(BlockchainError::InvalidTxPolicyEntry(value.to_owned()))
+        }
+
+        Ok(Self::Address(address.to_public_key()))
+    }
+}
+
+impl TxPolicy {
+    pub fn new(path: Option<String>, network: &Network) -> Result<Self, BlockchainError> {
+        let mut policy = Self {
+            path,
+            mainnet: network.is_mainnet(),
+            addresses: HashSet::new(),
+            hashes: HashSet::new(),
+            filtered_mempool: AtomicU64::new(0),
+            filtered_templates: AtomicU64::new(0)
+        };
+
+        if policy.path.is_some() {
+            policy.reload()?;
+        }
+
+        Ok(policy)
+    }
+
+    // Load again all the entries from the policy file
+    // Entries are replaced only if the whole file is valid
+    pub fn reload(&mut self) -> Result<usize, BlockchainError> {
+        let Some(path) = self.path.as_ref() else {
+            return Err(BlockchainError::NoTxPolicyFile)
+        };
+
+        let content = fs::read_to_string(path)?;
+        let mut addresses = HashSet::new();
+        let mut hashes = HashSet::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            match TxPolicyEntry::parse(line, self.mainnet)? {
+                TxPolicyEntry::Address(key) => addresses.insert(key),
+                TxPolicyEntry::Hash(hash) => hashes.insert(hash)
+            };
+        }
+
+        self.addresses = addresses;
+        self.hashes = hashes;
+
+        let count = self.len();
+        info!("Local TX policy loaded from {} with {} entries, this is a local policy only and not a consensus rule", path, count);
+        Ok(count)
+    }
+
+    // Write the current entries in the policy file if any
+    fn save(&self) -> Result<(), BlockchainError> {
+        if let Some(path) = self.path.as_ref() {
+            let mut content = String::from("# Local TX policy, one address or TX hash per line\n");
+            for key in self.addresses.iter() {
+                content.push_str(&key.as_address(self.mainnet).to_string());
+                content.push('\n');
+            }
+            for hash in self.hashes.iter() {
+                content.push_str(&hash.to_string());
+                content.push('\n');
+            }
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+
+    // Add the entry if not present, remove it otherwise
+    // Returns true if the entry was added
+    pub fn toggle(&mut self, entry: TxPolicyEntry) -> Result<bool, BlockchainError> {
+        let added = match entry {
+            TxPolicyEntry::Address(key) => if self.addresses.remove(&key) { false } else { self.addresses.insert(key) },
+            TxPolicyEntry::Hash(hash) => if self.hashes.remove(&hash) { false } else { self.hashes.insert(hash) }
+        };
+        self.save()?;
+        Ok(added)
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        self.mainnet
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len() + self.hashes.len()
+    }
+
+    pub fn get_addresses(&self) -> &HashSet<PublicKey> {
+        &self.addresses
+    }
+
+    pub fn get_hashes(&self) -> &HashSet<Hash> {
+        &self.hashes
+    }
+
+    // Check if the TX hash, its source or one of its destinations is filtered
+    pub fn is_filtered(&self, hash: &Hash, tx: &Transaction) -> bool {
+        if self.hashes.contains(hash) || self.addresses.contains(tx.get_source()) {
+            return true
+        }
+
+        match tx.get_data() {
+            TransactionType::Transfers(transfers) => transfers.iter().any(|transfer| self.addresses.contains(transfer.get_destination())),
+            TransactionType::Burn(_) => false
+        }
+    }
+
+    // Check a TX before adding it in mempool
+    pub fn check_mempool_tx(&self, hash: &Hash, tx: &Transaction) -> Result<(), BlockchainError> {
+        if self.is_filtered(hash, tx) {
+            self.filtered_mempool.fetch_add(1, Ordering::Relaxed);
+            warn!("TX {} refused in mempool by the local TX policy (not a consensus rule)", hash);
+            return Err(BlockchainError::TxFilteredByPolicy(hash.clone()))
+        }
+        Ok(())
+    }
+
+    // Check a TX before including it in a block template
+    pub fn is_filtered_for_template(&self, hash: &Hash, tx: &Transaction) -> bool {
+        let filtered = self.is_filtered(hash, tx);
+        if filtered {
+            self.filtered_templates.fetch_add(1, Ordering::Relaxed);
+            info!("TX {} not selected for mining by the local TX policy (not a consensus rule)", hash);
+        }
+        filtered
+    }
+
+    pub fn get_filtered_mempool_count(&self) -> u64 {
+        self.filtered_mempool.load(Ordering::Relaxed)
+    }
+
+    pub fn get_filtered_templates_count(&self) -> u64 {
+        self.filtered_templates.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::crypto::KeyPair;
+    use super::*;
+
+    #[test]
+    fn test_policy_file() {
+        let key = KeyPair::new().get_public_key().compress();
+        let hash = Hash::new([7u8; 32]);
+        let path = std::env::temp_dir().join(format!("xelis-tx-policy-{}.txt", rand::random::<u64>()));
+        let path = path.to_str().unwrap().to_owned();
+        fs::write(&path, format!("# comment\n\n{}\n{}\n", key.as_address(false), hash)).unwrap();
+
+        let mut policy = TxPolicy::new(Some(path.clone()), &Network::Testnet).unwrap();
+        assert!(policy.get_addresses().contains(&key));
+        assert!(policy.get_hashes().contains(&hash));
+
+        // Removing an entry is saved in the file
+        assert!(!policy.toggle(TxPolicyEntry::Hash(hash.clone())).unwrap());
+        assert_eq!(policy.reload().unwrap(), 1);
+        assert!(!policy.get_hashes().contains(&hash));
+
+        // Address from another network is refused
+        assert!(TxPolicyEntry::parse(&key.as_address(true).to_string(), false).is_err());
+        // An invalid file keeps the previous entries
+        fs::write(&path, "invalid").unwrap();
+        assert!(policy.reload().is_err());
+        assert_eq!(policy.len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tx_policy_is_local_only() {
+        use std::sync::Arc;
+        use xelis_common::{
+            config::{COIN_VALUE, XELIS_ASSET},
+            testing::build_transaction,
+            transaction::{builder::TransactionTypeBuilder, BurnPayload, Reference}
+        };
+        use crate::core::{
+            state::ChainState,
+            testing::{fund_account, test_blockchain}
+        };
+
+        let blockchain = test_blockchain().await;
+
+        // Fund a sender at the genesis topoheight
+        let keypair = KeyPair::new();
+        let key = keypair.get_public_key().compress();
+        let balance = 10 * COIN_VALUE;
+        fund_account(&blockchain, &key, balance).await;
+
+        let reference = Reference {
+            hash: blockchain.get_top_block_hash().await.unwrap(),
+            topoheight: 0
+        };
+        let tx = build_transaction(&keypair, balance, 0, reference, TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE }));
+
+        blockchain.get_tx_policy().write().await.toggle(TxPolicyEntry::Address(key)).unwrap();
+        assert!(matches!(blockchain.add_tx_to_mempool(tx.clone(), false).await, Err(BlockchainError::TxFilteredByPolicy(_))));
+        assert_eq!(blockchain.get_tx_policy().read().await.get_filtered_mempool_count(), 1);
+        assert!(blockchain.get_mempool().read().await.get_txs().is_empty());
+
+        // The same TX included in a block from a peer goes through the block verification
+        let storage = blockchain.get_storage().read().await;
+        let mut chain_state = ChainState::new(&*storage, blockchain.get_topo_height());
+        Transaction::verify_batch(&[Arc::new(tx)], &mut chain_state).await.unwrap();
+    }
+}
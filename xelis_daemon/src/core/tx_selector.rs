@@ -35,26 +35,64 @@ impl PartialEq for TxSelectorEntry<'_> {
 impl Eq for TxSelectorEntry<'_> {}
 
 // this struct is used to store transactions in a queue
-// and to order them by fees
+// and to order them by fee rate
 // Each Transactions is for a specific sender
-#[derive(PartialEq, Eq)]
-struct Transactions<'a>(VecDeque<TxSelectorEntry<'a>>);
+struct Transactions<'a> {
+    entries: VecDeque<TxSelectorEntry<'a>>,
+    // fees and size of the best package (lowest nonces first)
+    // a low fee TX is selected with the higher fee TXs depending on it
+    fees: u64,
+    size: usize
+}
+
+impl<'a> Transactions<'a> {
+    fn new(entries: VecDeque<TxSelectorEntry<'a>>) -> Self {
+        let (mut fees, mut size) = (0, 0);
+        let (mut best_fees, mut best_size) = (0, 0);
+        for entry in entries.iter() {
+            fees += entry.tx.get_fee();
+            size += entry.size;
+            if best_size == 0 || compare_fee_rate(fees, size, best_fees, best_size) == Ordering::Greater {
+                (best_fees, best_size) = (fees, size);
+            }
+        }
+
+        Self {
+            entries,
+            fees: best_fees,
+            size: best_size
+        }
+    }
+}
+
+// Compare fees per byte without losing precision
+fn compare_fee_rate(fees: u64, size: usize, other_fees: u64, other_size: usize) -> Ordering {
+    (fees as u128 * other_size.max(1) as u128).cmp(&(other_fees as u128 * size.max(1) as u128))
+}
+
+impl PartialEq for Transactions<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Transactions<'_> {}
 
 impl PartialOrd for Transactions<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.front().map(|e| e.tx.get_fee()).partial_cmp(&other.0.front().map(|e| e.tx.get_fee()))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Transactions<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.front().map(|e| e.tx.get_fee()).cmp(&other.0.front().map(|e| e.tx.get_fee()))
+        compare_fee_rate(self.fees, self.size, other.fees, other.size)
     }
 }
 
 // TX selector is used to select transactions from the mempool
 // It create sub groups of transactions by sender and order them by nonces
-// It joins all sub groups in a queue that is ordered by fees
+// It joins all sub groups in a queue that is ordered by fee per byte
 pub struct TxSelector<'a> {
     queue: BinaryHeap<Transactions<'a>>
 }
@@ -69,7 +107,9 @@ impl<'a> TxSelector<'a> {
 
         // push every group to the queue
         for group in groups {
-            queue.push(Transactions(VecDeque::from(group)));
+            if !group.is_empty() {
+                queue.push(Transactions::new(VecDeque::from(group)));
+            }
         }
 
         Self {
@@ -110,18 +150,96 @@ impl<'a> TxSelector<'a> {
         Self::grouped(iter)
     }
 
-    // Get the next transaction with the highest fee
+    // Get the next transaction with the highest fee rate
+    // TXs of the same sender are always returned by nonce order
     pub fn next(&mut self) -> Option<TxSelectorEntry<'a>> {
-        // get the group with the highest fee
-        let mut group = self.queue.pop()?;
-        // get the entry with the highest fee from this group
-        let entry = group.0.pop_front()?;
-
-        // if its not empty, push it back to the queue
-        if !group.0.is_empty() {
-            self.queue.push(group);
+        // get the group with the highest fee rate
+        let group = self.queue.pop()?;
+        let mut entries = group.entries;
+        // its lowest nonce must be included first
+        let entry = entries.pop_front()?;
+
+        // if its not empty, push it back to the queue with its new fee rate
+        if !entries.is_empty() {
+            self.queue.push(Transactions::new(entries));
         }
 
         Some(entry)
     }
-}
\ No newline at end of file
+
+    // Remove the remaining TXs of this sender
+    // Used when a TX can't be included, the next nonces can't be included either
+    pub fn skip_source(&mut self, source: &PublicKey) {
+        self.queue.retain(|group| group.entries.front().map_or(true, |entry| entry.tx.get_source() != source));
+    }
+}
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        config::{COIN_VALUE, XELIS_ASSET},
+        crypto::{Hashable, KeyPair},
+        serializer::Serializer,
+        testing::build_transaction_with_fee,
+        transaction::{builder::{FeeBuilder, TransactionTypeBuilder}, BurnPayload, Reference}
+    };
+    use super::*;
+
+    fn burn(keypair: &KeyPair, nonce: u64, fee: u64) -> (Arc<Hash>, Arc<Transaction>) {
+        let reference = Reference {
+            hash: Hash::zero(),
+            topoheight: 0
+        };
+        let data = TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 1 });
+        let tx = build_transaction_with_fee(keypair, 100 * COIN_VALUE, nonce, reference, data, FeeBuilder::Value(fee));
+        (Arc::new(tx.hash()), Arc::new(tx))
+    }
+
+    fn select(txs: &[(Arc<Hash>, Arc<Transaction>)]) -> Vec<(u64, u64)> {
+        let mut selector = TxSelector::new(txs.iter().map(|(hash, tx)| (tx.size(), hash, tx)));
+        let mut selected = Vec::new();
+        while let Some(entry) = selector.next() {
+            selected.push((entry.tx.get_fee(), entry.tx.get_nonce()));
+        }
+        selected
+    }
+
+    #[test]
+    fn test_fee_rate_order() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        let txs = vec![burn(&alice, 0, 1000), burn(&bob, 0, 10_000)];
+        assert_eq!(select(&txs), vec![(10_000, 0), (1000, 0)]);
+    }
+
+    #[test]
+    fn test_high_fee_blocked_by_low_nonce() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        // Alice's high fee TX depends on her low fee TX
+        let txs = vec![burn(&alice, 1, 100_000), burn(&alice, 0, 1000), burn(&bob, 0, 10_000)];
+
+        // Both are selected before Bob's TX as their combined fee rate is higher
+        // and the nonce order is kept
+        assert_eq!(select(&txs), vec![(1000, 0), (100_000, 1), (10_000, 0)]);
+    }
+
+    #[test]
+    fn test_low_fee_dependency_not_worth() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        // Combined fee rate of Alice's TXs is still lower than Bob's one
+        let txs = vec![burn(&alice, 0, 1000), burn(&alice, 1, 5000), burn(&bob, 0, 10_000)];
+        assert_eq!(select(&txs), vec![(10_000, 0), (1000, 0), (5000, 1)]);
+    }
+
+    #[test]
+    fn test_skip_source() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        let txs = vec![burn(&alice, 0, 50_000), burn(&alice, 1, 50_000), burn(&bob, 0, 10_000)];
+        let mut selector = TxSelector::new(txs.iter().map(|(hash, tx)| (tx.size(), hash, tx)));
+
+        let entry = selector.next().unwrap();
+        assert_eq!(entry.tx.get_nonce(), 0);
+        // Alice's first TX can't be included, her next nonce is skipped too
+        selector.skip_source(entry.tx.get_source());
+        assert_eq!(selector.next().unwrap().tx.get_fee(), 10_000);
+        assert!(selector.next().is_none());
+    }
+}
@@ -3,11 +3,12 @@ pub mod p2p;
 pub mod core;
 pub mod config;
 
-use config::{DEV_PUBLIC_KEY, STABLE_LIMIT};
+use config::DEV_PUBLIC_KEY;
 use fern::colors::Color;
+use human_bytes::human_bytes;
 use humantime::format_duration;
-use log::{trace, error, info, warn};
-use p2p::P2pServer;
+use log::{debug, trace, error, info, warn};
+use p2p::{slots::PeerLimits, P2pServer};
 use rpc::{
     getwork_server::SharedGetWorkServer,
     rpc::get_block_response_for_hash
@@ -19,7 +20,6 @@ use xelis_common::{
     crypto::{
         Address,Hashable
     },
-    difficulty::Difficulty,
     network::Network,
     prompt::{
         Prompt,
@@ -54,22 +54,31 @@ use crate::{
             Blockchain,
             get_block_reward
         },
+        replay,
+        snapshot,
         storage::{
             Storage,
             SledStorage
-        }
+        },
+        tx_policy::TxPolicyEntry
     },
     config::{
         BLOCK_TIME_MILLIS,
-        MILLIS_PER_SECOND
+        DEFAULT_STATS_BLOCKS,
+        MILLIS_PER_SECOND,
+        PEER_TEMP_BAN_TIME
     }
 };
 use core::blockdag;
 use std::{
     fs::File,
-    io::Write,
+    io::{BufReader, Write},
     net::{IpAddr, SocketAddr},
-    sync::Arc,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc
+    },
     time::Duration
 };
 use clap::Parser;
@@ -101,6 +110,14 @@ pub struct NodeConfig {
     /// You will not be able to write CLI commands in it or to have an updated prompt
     #[clap(long)]
     disable_interactive_mode: bool,
+    /// Command history file path
+    /// By default, it is saved next to the log file
+    #[clap(long)]
+    history_file: Option<String>,
+    /// Skip the confirmation of destructive commands
+    /// Useful when the commands are sent by a script
+    #[clap(long)]
+    skip_confirmation: bool,
     /// Log filename
     /// 
     /// By default filename is xelis-daemon.log.
@@ -119,13 +136,11 @@ pub struct NodeConfig {
     network: Network
 }
 
-const BLOCK_TIME: Difficulty = Difficulty::from_u64(BLOCK_TIME_MILLIS / MILLIS_PER_SECOND);
-
 #[tokio::main]
 async fn main() -> Result<()> {
     let mut config: NodeConfig = NodeConfig::parse();
 
-    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode)?;
+    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode, config.history_file.clone())?;
     info!("XELIS Blockchain running version: {}", VERSION);
     info!("----------------------------------------------");
 
@@ -135,6 +150,10 @@ async fn main() -> Result<()> {
     }
 
     let blockchain_config = config.nested;
+    if let Some(path) = blockchain_config.replay.clone() {
+        return run_replay(blockchain_config, config.network, &path).await;
+    }
+
     if let Some(path) = blockchain_config.dir_path.as_ref() {
         if !(path.ends_with("/") || path.ends_with("\\")) {
             return Err(anyhow::anyhow!("Path must end with / or \\"));
@@ -149,11 +168,15 @@ async fn main() -> Result<()> {
         };
 
         let dir_path = blockchain_config.dir_path.clone().unwrap_or_default();
+        // Opening a missing DB would create it
+        if blockchain_config.safe_mode && !Path::new(&format!("{}{}", dir_path, config.network.to_string().to_lowercase())).exists() {
+            return Err(anyhow::anyhow!("Safe mode requires an existing chain on disk"));
+        }
         SledStorage::new(dir_path, use_cache, config.network)?
     };
 
     let blockchain = Blockchain::new(blockchain_config, config.network, storage).await?;
-    if let Err(e) = run_prompt(prompt, blockchain.clone(), config.network).await {
+    if let Err(e) = run_prompt(prompt, blockchain.clone(), config.network, config.skip_confirmation).await {
         error!("Error while running prompt: {}", e);
     }
 
@@ -161,37 +184,81 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockchain<S>>, network: Network) -> Result<(), PromptError> {
+// Replay the blocks of an export file on a temporary storage
+async fn run_replay(mut config: Config, network: Network, path: &str) -> Result<()> {
+    config.disable_p2p_server = true;
+    config.disable_rpc_server = true;
+    config.disable_getwork_server = true;
+
+    let file = File::open(path).context("Error while opening replay file")?;
+    let storage = SledStorage::temporary(None, network)?;
+    let blockchain = Blockchain::new(config, network, storage).await?;
+
+    info!("Replaying blocks from {}", path);
+    let report = replay::replay_blocks(&blockchain, BufReader::new(file)).await;
+    blockchain.stop().await;
+
+    let report = report?;
+    match report.divergence {
+        Some(divergence) => {
+            error!("Replay stopped after {} blocks: block {} at index {} was expected to be {:?}, error: {}", report.replayed, divergence.hash, divergence.index, divergence.expected, divergence.error.as_deref().unwrap_or("none"));
+            Err(anyhow::anyhow!("Replay diverged at index {}", divergence.index))
+        },
+        None => {
+            info!("Replay completed: {} blocks matched their recorded status", report.replayed);
+            Ok(())
+        }
+    }
+}
+
+async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockchain<S>>, network: Network, skip_confirmation: bool) -> Result<(), PromptError> {
     let mut context = Context::default();
     context.store(blockchain.clone());
+    context.store(Arc::new(ChainVerification::default()));
 
     let command_manager = CommandManager::with_context(context, prompt.clone());
+    command_manager.set_skip_confirmation(skip_confirmation);
     command_manager.register_default_commands()?;
 
     // Register all our commands
     command_manager.add_command(Command::new("list_miners", "List all miners connected", CommandHandler::Async(async_handler!(list_miners::<S>))))?;
     command_manager.add_command(Command::new("list_peers", "List all peers connected", CommandHandler::Async(async_handler!(list_peers::<S>))))?;
+    command_manager.add_command(Command::with_arguments("set_max_peers", "Set the max peers, max outgoing peers and slots reserved to priority peers", vec![Arg::new("max_peers", ArgType::Number)], vec![Arg::new("max_outgoing_peers", ArgType::Number), Arg::new("priority_slots", ArgType::Number)], CommandHandler::Async(async_handler!(set_max_peers::<S>))))?;
     command_manager.add_command(Command::new("list_assets", "List all assets registered on chain", CommandHandler::Async(async_handler!(list_assets::<S>))))?;
     command_manager.add_command(Command::with_arguments("show_balance", "Show balance of an address", vec![], vec![Arg::new("history", ArgType::Number)], CommandHandler::Async(async_handler!(show_balance::<S>))))?;
     command_manager.add_command(Command::with_required_arguments("print_block", "Print block in json format", vec![Arg::new("hash", ArgType::Hash)], CommandHandler::Async(async_handler!(print_block::<S>))))?;
     command_manager.add_command(Command::new("top_block", "Print top block", CommandHandler::Async(async_handler!(top_block::<S>))))?;
-    command_manager.add_command(Command::with_required_arguments("pop_blocks", "Delete last N blocks", vec![Arg::new("amount", ArgType::Number)], CommandHandler::Async(async_handler!(pop_blocks::<S>))))?;
-    command_manager.add_command(Command::new("clear_mempool", "Clear all transactions in mempool", CommandHandler::Async(async_handler!(clear_mempool::<S>))))?;
+    command_manager.add_command(Command::with_required_arguments("pop_blocks", "Delete last N blocks", vec![Arg::new("amount", ArgType::Number)], CommandHandler::Async(async_handler!(pop_blocks::<S>))).with_confirmation("The last blocks will be deleted."))?;
+    command_manager.add_command(Command::new("clear_mempool", "Clear all transactions in mempool", CommandHandler::Async(async_handler!(clear_mempool::<S>))).with_confirmation("All transactions in mempool will be deleted."))?;
+    command_manager.add_command(Command::with_required_arguments("set_min_relay_fee_rate", "Set the minimum fee rate per kB to accept a TX in mempool", vec![Arg::new("rate", ArgType::Number)], CommandHandler::Async(async_handler!(set_min_relay_fee_rate::<S>))))?;
     command_manager.add_command(Command::with_arguments("add_tx", "Add a TX in hex format in mempool", vec![Arg::new("hex", ArgType::String)], vec![Arg::new("broadcast", ArgType::Bool)], CommandHandler::Async(async_handler!(add_tx::<S>))))?;
-    command_manager.add_command(Command::with_required_arguments("prune_chain", "Prune the chain until the specified topoheight", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(prune_chain::<S>))))?;
+    command_manager.add_command(Command::with_arguments("export_snapshot", "Export the chain until the specified topoheight (default is stable topoheight) in a snapshot file", vec![Arg::new("path", ArgType::String)], vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(export_snapshot::<S>))))?;
+    command_manager.add_command(Command::with_arguments("import_snapshot", "Verify a snapshot file and import it in a new storage in the specified directory", vec![Arg::new("path", ArgType::String), Arg::new("dir_path", ArgType::String)], vec![], CommandHandler::Async(async_handler!(import_snapshot::<S>))))?;
+    command_manager.add_command(Command::with_arguments("export_blocks", "Export the blocks between two topoheights (default is the full chain) in a replay file", vec![Arg::new("path", ArgType::String)], vec![Arg::new("min_topoheight", ArgType::Number), Arg::new("max_topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(export_blocks::<S>))))?;
+    command_manager.add_command(Command::with_required_arguments("prune_chain", "Prune the chain until the specified topoheight", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(prune_chain::<S>))).with_confirmation("The chain data before this topoheight will be deleted."))?;
+    command_manager.add_command(Command::new("compact", "Compact the storage to reclaim the space left by pruned and deleted data", CommandHandler::Async(async_handler!(compact::<S>))))?;
+    command_manager.add_command(Command::new("storage_stats", "Show the entries count and size of each storage column", CommandHandler::Async(async_handler!(storage_stats::<S>))))?;
     command_manager.add_command(Command::new("status", "Current daemon status", CommandHandler::Async(async_handler!(status::<S>))))?;
+    command_manager.add_command(Command::with_optional_arguments("stats", "Show the chain stats over the last N blocks", vec![Arg::new("blocks", ArgType::Number)], CommandHandler::Async(async_handler!(stats::<S>))))?;
     command_manager.add_command(Command::with_optional_arguments("blacklist", "View blacklist or add a peer address in it", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(blacklist::<S>))))?;
     command_manager.add_command(Command::with_optional_arguments("whitelist", "View whitelist or add a peer address in it", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(whitelist::<S>))))?;
-    command_manager.add_command(Command::with_optional_arguments("verify_chain", "Check chain supply", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(verify_chain::<S>))))?;
+    command_manager.add_command(Command::with_arguments("ban_peer", "Temp ban a peer address for a duration in seconds", vec![Arg::new("address", ArgType::String)], vec![Arg::new("duration", ArgType::Number)], CommandHandler::Async(async_handler!(ban_peer::<S>))).with_confirmation("The peer will be disconnected and banned."))?;
+    command_manager.add_command(Command::with_required_arguments("unban_peer", "Remove the temp ban of a peer address", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(unban_peer::<S>))))?;
+    command_manager.add_command(Command::with_optional_arguments("verify_chain", "Re-validate all blocks stored from a topoheight to the tip", vec![Arg::new("from_topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(verify_chain::<S>))))?;
+    command_manager.add_command(Command::new("stop_verify", "Stop the running chain verification", CommandHandler::Async(async_handler!(stop_verify))))?;
     command_manager.add_command(Command::with_required_arguments("kick_peer", "Kick a peer using its ip:port", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(kick_peer::<S>))))?;
     command_manager.add_command(Command::new("clear_caches", "Clear storage caches", CommandHandler::Async(async_handler!(clear_caches::<S>))))?;
     command_manager.add_command(Command::new("clear_rpc_connections", "Clear all WS connections from RPC", CommandHandler::Async(async_handler!(clear_rpc_connections::<S>))))?;
     command_manager.add_command(Command::new("clear_p2p_connections", "Clear all P2P connections", CommandHandler::Async(async_handler!(clear_p2p_connections::<S>))))?;
-    command_manager.add_command(Command::new("clear_p2p_peerlist", "Clear P2P peerlist", CommandHandler::Async(async_handler!(clear_p2p_peerlist::<S>))))?;
+    command_manager.add_command(Command::new("clear_p2p_peerlist", "Clear P2P peerlist", CommandHandler::Async(async_handler!(clear_p2p_peerlist::<S>))).with_confirmation("All known peers will be deleted."))?;
     command_manager.add_command(Command::with_optional_arguments("difficulty_dataset", "Create a dataset for difficulty from chain", vec![Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(difficulty_dataset::<S>))))?;
     command_manager.add_command(Command::with_optional_arguments("mine_block", "Mine a block on testnet", vec![Arg::new("count", ArgType::Number)], CommandHandler::Async(async_handler!(mine_block::<S>))))?;
     command_manager.add_command(Command::new("p2p_outgoing_connections", "Accept/refuse to connect to outgoing nodes", CommandHandler::Async(async_handler!(p2p_outgoing_connections::<S>))))?;
     command_manager.add_command(Command::with_required_arguments("add_peer", "Connect to a new peer using ip:port format", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(add_peer::<S>))))?;
+    command_manager.add_command(Command::with_optional_arguments("tx_policy", "View the local TX policy or add/remove an address or TX hash in it", vec![Arg::new("entry", ArgType::String)], CommandHandler::Async(async_handler!(tx_policy::<S>))))?;
+    command_manager.add_command(Command::new("reload_tx_policy", "Reload the local TX policy from its file", CommandHandler::Async(async_handler!(reload_tx_policy::<S>))))?;
+    command_manager.add_command(Command::new("corruption_report", "Show the storage entries that failed to be read", CommandHandler::Async(async_handler!(corruption_report::<S>))))?;
+    command_manager.add_command(Command::with_optional_arguments("recover_storage", "Recover a corrupted storage using 'rewind' or 'redownload'", vec![Arg::new("method", ArgType::String)], CommandHandler::Async(async_handler!(recover_storage::<S>))))?;
 
 
     // Don't keep the lock for ever
@@ -241,8 +308,23 @@ async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockch
             mempool.size()
         };
 
+        trace!("Retrieving active alerts");
+        let alerts = match blockchain.get_alerts() {
+            Some(alerts) => alerts.get_active_alerts().await.len(),
+            None => 0
+        };
+
         trace!("Retrieving network hashrate");
-        let network_hashrate: f64 = (blockchain.get_difficulty().await / BLOCK_TIME).into();
+        let network_hashrate: f64 = {
+            let storage = blockchain.get_storage().read().await;
+            match blockchain.get_estimated_hashrate::<S>(&*storage).await {
+                Ok(hashrate) => hashrate.into(),
+                Err(e) => {
+                    debug!("Error while estimating network hashrate: {}", e);
+                    0.0
+                }
+            }
+        };
 
         trace!("Building prompt message");
         Ok( 
@@ -255,6 +337,7 @@ async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockch
                 rpc_count,
                 miners,
                 mempool,
+                alerts,
                 network
             )
         )
@@ -263,7 +346,7 @@ async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockch
     prompt.start(Duration::from_secs(1), Box::new(async_handler!(closure)), Some(&command_manager)).await
 }
 
-fn build_prompt_message(prompt: &ShareablePrompt, topoheight: u64, median_topoheight: u64, network_hashrate: f64, peers_count: usize, rpc_count: usize, miners_count: usize, mempool: usize, network: Network) -> String {
+fn build_prompt_message(prompt: &ShareablePrompt, topoheight: u64, median_topoheight: u64, network_hashrate: f64, peers_count: usize, rpc_count: usize, miners_count: usize, mempool: usize, alerts: usize, network: Network) -> String {
     let topoheight_str = format!(
         "{}: {}/{}",
         prompt.colorize_str(Color::Yellow, "TopoHeight"),
@@ -303,8 +386,16 @@ fn build_prompt_message(prompt: &ShareablePrompt, topoheight: u64, median_topohe
         )
     } else { "".into() };
 
+    // Shown only while an alert is active, see get_active_alerts for details
+    let alerts_str = if alerts > 0 {
+        format!(
+            "{} ",
+            prompt.colorize_string(Color::Red, &format!("Alerts: {}", alerts))
+        )
+    } else { "".into() };
+
     format!(
-        "{} | {} | {} | {} | {} | {} | {} {}{} ",
+        "{} | {} | {} | {} | {} | {} | {} {}{}{} ",
         prompt.colorize_str(Color::Blue, "XELIS"),
         topoheight_str,
         network_hashrate_str,
@@ -312,59 +403,85 @@ fn build_prompt_message(prompt: &ShareablePrompt, topoheight: u64, median_topohe
         peers_str,
         rpc_str,
         miners_str,
+        alerts_str,
         network_str,
         prompt.colorize_str(Color::BrightBlack, ">>")
     )
 }
 
+// State of the chain verification started from the prompt
+#[derive(Default)]
+struct ChainVerification {
+    // Is a verification running
+    running: AtomicBool,
+    // Ask the running verification to stop
+    stop: AtomicBool
+}
+
 async fn verify_chain<S: Storage>(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let verification: &Arc<ChainVerification> = context.get()?;
 
-    let storage = blockchain.get_storage().read().await;
-    let mut pruned_topoheight = storage.get_pruned_topoheight().await.context("Error on pruned topoheight")?.unwrap_or(0);
-    let mut expected_supply = if pruned_topoheight > 0 {
-        let supply = storage.get_supply_at_topo_height(pruned_topoheight).await.context("Error while retrieving starting expected supply")?;
-        pruned_topoheight += 1;
-        supply
-    } else {
-        0
+    // Blocks below the pruned topoheight are not available anymore
+    let pruned_topoheight = {
+        let storage = blockchain.get_storage().read().await;
+        storage.get_pruned_topoheight().await.context("Error on pruned topoheight")?
     };
+    let minimum_topoheight = pruned_topoheight.map_or(0, |topo| topo + 1);
+    let topoheight = blockchain.get_topo_height();
 
-    let topoheight = if args.has_argument("topoheight") {
-        args.get_value("topoheight")?.to_number()?
+    let from_topoheight = if args.has_argument("from_topoheight") {
+        args.get_value("from_topoheight")?.to_number()?
     } else {
-        blockchain.get_topo_height()
+        minimum_topoheight
     };
 
-    for topo in pruned_topoheight..=topoheight {
-        let hash_at_topo = storage.get_hash_at_topo_height(topo).await.context("Error while retrieving hash at topo")?;
-        let block_reward = if pruned_topoheight == 0 || topo - pruned_topoheight > STABLE_LIMIT {
-            let block_reward = blockchain.get_block_reward(&*storage, &hash_at_topo, expected_supply, topo).await.context("Error while calculating block reward")?;
-            let expected_block_reward = storage.get_block_reward_at_topo_height(topo).context("Error while retrieving block reward")?;
-            // Verify the saved block reward
-            if block_reward != expected_block_reward {
-                manager.error(format!("Block reward saved is incorrect for {} at topoheight {}, got {} while expecting {}", hash_at_topo, topo, format_xelis(block_reward), format_xelis(expected_block_reward)));
-                return Ok(())
-            }
-            block_reward
-        } else {
-            // We are too near from the pruned topoheight, as we don't know previous blocks we can't verify if block was side block or not for rewards
-            // Let's trust its stored reward
-            storage.get_block_reward_at_topo_height(topo).context("Error while retrieving block reward for pruned topo")?
+    if from_topoheight < minimum_topoheight {
+        manager.error(format!("Cannot verify below topoheight {} as the chain is pruned", minimum_topoheight));
+        return Ok(())
+    }
+
+    if from_topoheight > topoheight {
+        manager.error(format!("Topoheight {} is above the current topoheight {}", from_topoheight, topoheight));
+        return Ok(())
+    }
+
+    if verification.running.swap(true, Ordering::SeqCst) {
+        manager.error("A chain verification is already running, use stop_verify to stop it");
+        return Ok(())
+    }
+    verification.stop.store(false, Ordering::SeqCst);
+
+    // The verification is heavy, run it on a blocking task to keep the prompt responsive
+    let blockchain = Arc::clone(blockchain);
+    let verification = Arc::clone(verification);
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        match handle.block_on(blockchain.verify_chain(from_topoheight, topoheight, &verification.stop)) {
+            Ok(Some((topo, reason))) => error!("Chain verification failed at topoheight {}: {}", topo, reason),
+            Ok(None) => if !verification.stop.load(Ordering::SeqCst) {
+                info!("Chain is valid from topoheight {} to {}", from_topoheight, topoheight);
+            },
+            Err(e) => error!("Error while verifying chain: {}", e)
         };
+        verification.running.store(false, Ordering::SeqCst);
+    });
 
-        let supply = storage.get_supply_at_topo_height(topo).await.context("Error while retrieving supply at topoheight")?;
-        expected_supply += block_reward;
+    manager.message(format!("Verifying chain from topoheight {} to {}, use stop_verify to cancel it", from_topoheight, topoheight));
+    Ok(())
+}
 
-        // Verify the supply at block
-        if supply != expected_supply {
-            manager.error(format!("Error for block {} at topoheight {}, expected {} found {}", hash_at_topo, topo, expected_supply, supply));
-            return Ok(())
-        }
+async fn stop_verify(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let verification: &Arc<ChainVerification> = context.get()?;
+    if !verification.running.load(Ordering::SeqCst) {
+        manager.error("No chain verification is running");
+        return Ok(())
     }
-    manager.message("Supply is valid");
 
+    verification.stop.store(true, Ordering::SeqCst);
+    manager.message("Chain verification will stop");
     Ok(())
 }
 
@@ -425,9 +542,14 @@ async fn list_peers<S: Storage>(manager: &CommandManager, _: ArgumentManager) ->
         Some(p2p) => {
             let peer_list = p2p.get_peer_list();
             for peer in peer_list.get_peers().read().await.values() {
-                manager.message(format!("{}", peer));
+                let counters = peer.get_request_counters().snapshot();
+                manager.message(format!("{} objects: {} requested, {} fulfilled, {} timed out", peer, counters.requested, counters.fulfilled, counters.timed_out));
             }
             manager.message(format!("Total peer(s) count: {}", peer_list.size().await));
+
+            let limits = p2p.get_peer_limits();
+            let usage = peer_list.get_slots_usage().await;
+            manager.message(format!("Slots: {}/{} used, {}/{} reserved to priority peers, {}/{} outgoing", usage.peers, limits.max_peers, usage.priority.min(limits.priority_slots), limits.priority_slots, usage.outgoing, limits.max_outgoing_peers));
         },
         None => {
             manager.message("No P2p server running!");
@@ -436,6 +558,47 @@ async fn list_peers<S: Storage>(manager: &CommandManager, _: ArgumentManager) ->
     Ok(())
 }
 
+async fn set_max_peers<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let p2p = blockchain.get_p2p().read().await;
+    let Some(p2p) = p2p.as_ref() else {
+        manager.message("No P2p server running!");
+        return Ok(())
+    };
+
+    // Values not provided are kept, the outgoing peers are capped to the new max peers
+    let previous = p2p.get_peer_limits();
+    let max_peers = arguments.get_value("max_peers")?.to_number()? as usize;
+    let max_outgoing_peers = if arguments.has_argument("max_outgoing_peers") {
+        arguments.get_value("max_outgoing_peers")?.to_number()? as usize
+    } else {
+        previous.max_outgoing_peers.min(max_peers)
+    };
+    let priority_slots = if arguments.has_argument("priority_slots") {
+        arguments.get_value("priority_slots")?.to_number()? as usize
+    } else {
+        previous.priority_slots
+    };
+
+    let limits = PeerLimits {
+        max_peers,
+        max_outgoing_peers,
+        priority_slots
+    };
+    if let Err(e) = p2p.set_peer_limits(limits) {
+        manager.error(format!("Error while setting max peers: {}", e));
+        return Ok(())
+    }
+
+    manager.message(format!("Max peers set to {} ({} outgoing, {} reserved to priority peers)", limits.max_peers, limits.max_outgoing_peers, limits.priority_slots));
+    let peers = p2p.get_peer_count().await;
+    if peers > limits.max_peers {
+        manager.warn(format!("{} peers are connected, they are kept until they disconnect", peers));
+    }
+    Ok(())
+}
+
 async fn list_assets<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -540,13 +703,92 @@ async fn pop_blocks<S: Storage>(manager: &CommandManager, mut arguments: Argumen
     Ok(())
 }
 
+async fn corruption_report<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    let report = storage.get_corruption_report();
+    if report.is_empty() {
+        manager.message("No corrupted entry detected");
+        return Ok(())
+    }
+
+    manager.message(format!("Recovery mode: {}", storage.is_quarantined()));
+    for entry in report {
+        manager.message(format!("- tree {} key {}: {} errors, last: {}", entry.tree, entry.key, entry.errors, entry.last_error));
+    }
+
+    Ok(())
+}
+
+async fn recover_storage<S: Storage>(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+
+    if !args.has_argument("method") {
+        let consistent_topoheight = blockchain.find_consistent_topoheight().await.context("Error while searching the consistent topoheight")?;
+        match consistent_topoheight {
+            Some(topoheight) => manager.message(format!("Highest consistent topoheight is {} (current topoheight {})", topoheight, blockchain.get_topo_height())),
+            None => manager.warn("No consistent topoheight found, only 'redownload' can be used")
+        };
+        manager.message("Use 'recover_storage rewind' to rewind the chain to it");
+        manager.message("Use 'recover_storage redownload' to download the corrupted blocks and transactions from peers");
+        return Ok(())
+    }
+
+    match args.get_value("method")?.to_string_value()?.as_str() {
+        "rewind" => {
+            let topoheight = blockchain.recover_with_rewind().await.context("Error while rewinding the storage")?;
+            manager.message(format!("Storage recovered, chain is now at topoheight {}", topoheight));
+        },
+        "redownload" => {
+            let p2p: Option<Arc<P2pServer<S>>> = blockchain.get_p2p().read().await.clone();
+            let Some(p2p) = p2p else {
+                manager.error("P2P is not enabled");
+                return Ok(())
+            };
+
+            let (repaired, failed) = p2p.repair_corrupted_entries().await.context("Error while downloading corrupted entries")?;
+            manager.message(format!("{} entries repaired, {} failed", repaired, failed));
+
+            // Only leave the recovery mode once everything can be read again
+            let consistent_topoheight = blockchain.find_consistent_topoheight().await.context("Error while searching the consistent topoheight")?;
+            if failed == 0 && consistent_topoheight == Some(blockchain.get_topo_height()) {
+                blockchain.get_storage().read().await.clear_corruption();
+                manager.message("Storage recovered");
+            } else {
+                manager.warn("Storage is still inconsistent, use 'recover_storage rewind' to rewind the chain");
+            }
+        },
+        method => {
+            return Err(CommandError::InvalidArgument(format!("Unknown recovery method '{}'", method)))
+        }
+    };
+
+    Ok(())
+}
+
 async fn clear_mempool<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
     info!("Clearing mempool...");
-    let mut mempool = blockchain.get_mempool().write().await;
-    mempool.clear();
-    info!("Mempool cleared");
+    let count = blockchain.clear_mempool().await;
+    info!("Mempool cleared, {} TXs removed", count);
+
+    Ok(())
+}
+
+async fn set_min_relay_fee_rate<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let rate = arguments.get_value("rate")?.to_number()?;
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let rate = blockchain.set_min_relay_fee_rate(rate).await;
+    manager.message(format!("Minimum relay fee rate set to {} per kB", format_xelis(rate)));
+
+    let policy = blockchain.get_fee_policy().await;
+    if policy.mempool_fee_rate > 0 {
+        manager.message(format!("Mempool is full, TXs must pay more than {} per kB", format_xelis(policy.mempool_fee_rate)));
+    }
 
     Ok(())
 }
@@ -587,6 +829,116 @@ async fn prune_chain<S: Storage>(manager: &CommandManager, mut arguments: Argume
     Ok(())
 }
 
+async fn compact<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    // Holding the storage prevents any write during the compaction
+    let mut storage = blockchain.get_storage().write().await;
+    let before = storage.get_size_on_disk().await.context("Error while retrieving size on disk")?;
+    manager.message(format!("Compacting storage ({}), the chain is paused until it's done", human_bytes(before as f64)));
+
+    if let Err(e) = storage.compact().await {
+        manager.error(format!("Error while compacting storage: {}", e));
+        return Ok(());
+    }
+
+    let after = storage.get_size_on_disk().await.context("Error while retrieving size on disk")?;
+    manager.message(format!("Storage compacted: {} -> {}, {} reclaimed", human_bytes(before as f64), human_bytes(after as f64), human_bytes(before.saturating_sub(after) as f64)));
+    Ok(())
+}
+
+async fn storage_stats<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    manager.message("Reading all the storage entries, this may take a while...");
+    let columns = storage.get_storage_stats().await.context("Error while retrieving storage stats")?;
+    let size_on_disk = storage.get_size_on_disk().await.context("Error while retrieving size on disk")?;
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?;
+
+    for column in columns.iter() {
+        manager.message(format!("- {}: {} entries, {}", column.name, column.entries, human_bytes(column.size_bytes as f64)));
+    }
+
+    let entries: u64 = columns.iter().map(|column| column.entries).sum();
+    let size_bytes: u64 = columns.iter().map(|column| column.size_bytes).sum();
+    manager.message(format!("Total: {} entries, {} ({} on disk)", entries, human_bytes(size_bytes as f64), human_bytes(size_on_disk as f64)));
+    match pruned_topoheight {
+        Some(topoheight) => manager.message(format!("Pruned topoheight: {}", topoheight)),
+        None => manager.message("Chain is not pruned")
+    }
+    Ok(())
+}
+
+async fn export_snapshot<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let topoheight = if arguments.has_argument("topoheight") {
+        arguments.get_value("topoheight")?.to_number()?
+    } else {
+        blockchain.get_stable_topoheight()
+    };
+
+    manager.message(format!("Exporting snapshot until topoheight {} to {}", topoheight, path));
+    if let Err(e) = blockchain.export_snapshot(&path, topoheight).await {
+        manager.error(format!("Error while exporting snapshot: {}", e));
+        return Ok(());
+    }
+    manager.message(format!("Snapshot has been exported to {}", path));
+    Ok(())
+}
+
+// The running node can't import in its own storage,
+// the new storage can be used by starting a node with --dir-path
+async fn import_snapshot<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let dir_path = arguments.get_value("dir_path")?.to_string_value()?;
+    if !(dir_path.ends_with("/") || dir_path.ends_with("\\")) {
+        manager.error("Directory path must end with / or \\");
+        return Ok(());
+    }
+
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let network = *blockchain.get_network();
+
+    manager.message(format!("Importing snapshot {} in {}", path, dir_path));
+    let res = match SledStorage::new(dir_path.clone(), None, network) {
+        Ok(mut storage) => snapshot::import_snapshot(&mut storage, network, &path).await,
+        Err(e) => Err(e)
+    };
+
+    match res {
+        Ok(header) => manager.message(format!("Snapshot has been imported until topoheight {}, start a node with --dir-path {} to use it", header.topoheight, dir_path)),
+        Err(e) => manager.error(format!("Error while importing snapshot: {}", e))
+    }
+    Ok(())
+}
+
+async fn export_blocks<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let min_topoheight = if arguments.has_argument("min_topoheight") {
+        arguments.get_value("min_topoheight")?.to_number()?
+    } else {
+        0
+    };
+    let max_topoheight = if arguments.has_argument("max_topoheight") {
+        arguments.get_value("max_topoheight")?.to_number()?
+    } else {
+        blockchain.get_topo_height()
+    };
+
+    manager.message(format!("Exporting blocks from topoheight {} to {} in {}", min_topoheight, max_topoheight, path));
+    match blockchain.export_blocks(&path, min_topoheight, max_topoheight).await {
+        Ok(count) => manager.message(format!("{} blocks have been exported to {}", count, path)),
+        Err(e) => manager.error(format!("Error while exporting blocks: {}", e))
+    };
+    Ok(())
+}
+
 async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -601,6 +953,7 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
     let tips = storage.get_tips().await.context("Error while retrieving tips")?;
     let top_block_hash = blockchain.get_top_block_hash_for_storage(&storage).await.context("Error while retrieving top block hash")?;
     let avg_block_time = blockchain.get_average_block_time::<S>(&storage).await.context("Error while retrieving average block time")?;
+    let hashrate = blockchain.get_estimated_hashrate::<S>(&storage).await.context("Error while estimating hashrate")?;
     let supply = storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply")?;
     let accounts_count = storage.count_accounts().await.context("Error while counting accounts")?;
     let transactions_count = storage.count_transactions().await.context("Error while counting transactions")?;
@@ -613,7 +966,7 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
     manager.message(format!("Stable Topo Height: {}", stable_topoheight));
     manager.message(format!("Topo Height: {}", topoheight));
     manager.message(format!("Difficulty: {}", format_difficulty(difficulty)));
-    manager.message(format!("Network Hashrate: {}", format_hashrate((difficulty / BLOCK_TIME).into())));
+    manager.message(format!("Network Hashrate: {}", format_hashrate(hashrate.into())));
     manager.message(format!("Top block hash: {}", top_block_hash));
     manager.message(format!("Average Block Time: {:.2}s", avg_block_time as f64 / MILLIS_PER_SECOND as f64));
     manager.message(format!("Target Block Time: {:.2}s", BLOCK_TIME_MILLIS as f64 / MILLIS_PER_SECOND as f64));
@@ -632,6 +985,10 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
         manager.message("Chain is in full mode");
     }
 
+    if blockchain.is_safe_mode() {
+        manager.message("Safe mode: storage is read only, P2P and mining are disabled");
+    }
+
     let elapsed_seconds = manager.running_since().as_secs();
     let elapsed = format_duration(Duration::from_secs(elapsed_seconds)).to_string();
     manager.message(format!("Uptime: {}", elapsed));
@@ -639,6 +996,28 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
     Ok(())
 }
 
+async fn stats<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let blocks = if arguments.has_argument("blocks") {
+        arguments.get_value("blocks")?.to_number()?
+    } else {
+        DEFAULT_STATS_BLOCKS
+    };
+
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let stats = blockchain.get_stats(blocks).await.context("Error while computing stats")?;
+
+    manager.message(format!("Stats of {} blocks (topoheight {} to {}):", stats.blocks, stats.start_topoheight, stats.end_topoheight));
+    manager.message(format!("Average Block Time: {:.2}s", stats.average_block_time as f64 / MILLIS_PER_SECOND as f64));
+    manager.message(format!("Median Block Time: {:.2}s", stats.median_block_time as f64 / MILLIS_PER_SECOND as f64));
+    manager.message(format!("Average Block Size: {}", human_bytes(stats.average_block_size as f64)));
+    manager.message(format!("Average TXs per block: {:.2}", stats.average_txs_per_block));
+    manager.message(format!("Total Fees: {} XELIS", format_xelis(stats.total_fees)));
+    manager.message(format!("Supply Emitted: {} XELIS", format_xelis(stats.supply_emitted)));
+    manager.message(format!("Emission per block: {} XELIS", format_xelis(stats.emission_per_block)));
+    Ok(())
+}
+
 async fn clear_rpc_connections<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -735,6 +1114,49 @@ async fn blacklist<S: Storage>(manager: &CommandManager, mut arguments: Argument
     Ok(())
 }
 
+async fn ban_peer<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    match blockchain.get_p2p().read().await.as_ref() {
+        Some(p2p) => {
+            let address: IpAddr = arguments.get_value("address")?.to_string_value()?.parse().context("Error while parsing ip address")?;
+            let duration = if arguments.has_argument("duration") {
+                arguments.get_value("duration")?.to_number()?
+            } else {
+                PEER_TEMP_BAN_TIME
+            };
+
+            p2p.get_peer_list().ban_address(&address, duration).await;
+            manager.message(format!("Peer {} has been banned for {}", address, format_duration(Duration::from_secs(duration))));
+        },
+        None => {
+            manager.error("P2P is not enabled");
+        }
+    };
+
+    Ok(())
+}
+
+async fn unban_peer<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    match blockchain.get_p2p().read().await.as_ref() {
+        Some(p2p) => {
+            let address: IpAddr = arguments.get_value("address")?.to_string_value()?.parse().context("Error while parsing ip address")?;
+            if p2p.get_peer_list().unban_address(&address).await {
+                manager.message(format!("Peer {} is not banned anymore", address));
+            } else {
+                manager.warn(format!("Peer {} is not banned", address));
+            }
+        },
+        None => {
+            manager.error("P2P is not enabled");
+        }
+    };
+
+    Ok(())
+}
+
 async fn whitelist<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -881,4 +1303,42 @@ async fn add_peer<S: Storage>(manager: &CommandManager, mut args: ArgumentManage
     };
 
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn tx_policy<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if arguments.has_argument("entry") {
+        let value = arguments.get_value("entry")?.to_string_value()?;
+        let mut policy = blockchain.get_tx_policy().write().await;
+        let entry = TxPolicyEntry::parse(&value, policy.is_mainnet()).context("Error while parsing TX policy entry")?;
+        if policy.toggle(entry).context("Error while saving TX policy")? {
+            manager.message(format!("{} has been added to the local TX policy", value));
+        } else {
+            manager.message(format!("{} has been removed from the local TX policy", value));
+        }
+    } else {
+        let policy = blockchain.get_tx_policy().read().await;
+        manager.message("Local TX policy, blocks including these TXs are still accepted:");
+        manager.message(format!("Addresses ({}):", policy.get_addresses().len()));
+        for key in policy.get_addresses() {
+            manager.message(format!("- {}", key.as_address(policy.is_mainnet())));
+        }
+        manager.message(format!("TX hashes ({}):", policy.get_hashes().len()));
+        for hash in policy.get_hashes() {
+            manager.message(format!("- {}", hash));
+        }
+        manager.message(format!("Filtered TXs in mempool/block templates: {}/{}", policy.get_filtered_mempool_count(), policy.get_filtered_templates_count()));
+    }
+
+    Ok(())
+}
+
+async fn reload_tx_policy<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let mut policy = blockchain.get_tx_policy().write().await;
+    let count = policy.reload().context("Error while reloading TX policy")?;
+    manager.message(format!("Local TX policy reloaded with {} entries", count));
+    Ok(())
+}
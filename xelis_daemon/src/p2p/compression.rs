@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::error::P2pError;
+
+// Packets below this size are sent raw, compressing them doesn't save enough
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+// First byte of each packet once the compression is enabled on a connection
+const MARKER_RAW: u8 = 0;
+const MARKER_LZ4: u8 = 1;
+
+// Size of the decompressed length written by lz4 before the compressed data
+const LZ4_SIZE_PREFIX: usize = 4;
+
+// Add the marker to the packet and compress it if it's above the threshold
+// If the compressed packet is not smaller, it is sent raw
+pub fn encode(packet: &[u8]) -> Vec<u8> {
+    if packet.len() >= COMPRESSION_THRESHOLD {
+        let compressed = lz4_flex::compress_prepend_size(packet);
+        if compressed.len() < packet.len() {
+            let mut bytes = Vec::with_capacity(compressed.len() + 1);
+            bytes.push(MARKER_LZ4);
+            bytes.extend(compressed);
+            return bytes
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(packet.len() + 1);
+    bytes.push(MARKER_RAW);
+    bytes.extend_from_slice(packet);
+    bytes
+}
+
+// Remove the marker and decompress the packet if needed
+// The decompressed size is verified before allocating anything
+// so a peer can't make us decompress more than max_size bytes
+pub fn decode(bytes: &[u8], max_size: usize) -> Result<Vec<u8>, P2pError> {
+    let (marker, content) = bytes.split_first().ok_or(P2pError::InvalidPacketSize)?;
+    match *marker {
+        MARKER_RAW => Ok(content.to_vec()),
+        MARKER_LZ4 => {
+            let prefix: [u8; LZ4_SIZE_PREFIX] = content.get(..LZ4_SIZE_PREFIX)
+                .ok_or(P2pError::InvalidPacketSize)?
+                .try_into()?;
+            let size = u32::from_le_bytes(prefix) as usize;
+            if size > max_size {
+                return Err(P2pError::DecompressedPacketTooLarge(size, max_size))
+            }
+
+            lz4_flex::decompress_size_prepended(content).map_err(|e| P2pError::InvalidCompressedPacket(e.to_string()))
+        },
+        marker => Err(P2pError::InvalidCompressionMarker(marker))
+    }
+}
+
+// Bytes of the compressed packets before and after compression
+// Raw packets sent once the compression is enabled are not counted
+pub struct CompressionStats {
+    uncompressed_in: AtomicUsize,
+    compressed_in: AtomicUsize,
+    uncompressed_out: AtomicUsize,
+    compressed_out: AtomicUsize
+}
+
+impl CompressionStats {
+    pub fn new() -> Self {
+        Self {
+            uncompressed_in: AtomicUsize::new(0),
+            compressed_in: AtomicUsize::new(0),
+            uncompressed_out: AtomicUsize::new(0),
+            compressed_out: AtomicUsize::new(0)
+        }
+    }
+
+    pub fn add_in(&self, compressed: usize, uncompressed: usize) {
+        self.compressed_in.fetch_add(compressed, Ordering::Relaxed);
+        self.uncompressed_in.fetch_add(uncompressed, Ordering::Relaxed);
+    }
+
+    pub fn add_out(&self, compressed: usize, uncompressed: usize) {
+        self.compressed_out.fetch_add(compressed, Ordering::Relaxed);
+        self.uncompressed_out.fetch_add(uncompressed, Ordering::Relaxed);
+    }
+
+    // Compressed size divided by the original size, None if nothing was compressed
+    pub fn get_ratio(&self) -> Option<f64> {
+        let uncompressed = self.uncompressed_in.load(Ordering::Relaxed) + self.uncompressed_out.load(Ordering::Relaxed);
+        if uncompressed == 0 {
+            return None
+        }
+
+        let compressed = self.compressed_in.load(Ordering::Relaxed) + self.compressed_out.load(Ordering::Relaxed);
+        Some(compressed as f64 / uncompressed as f64)
+    }
+}
+
+// Check if the encoded packet was compressed
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.first() == Some(&MARKER_LZ4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_packet_raw() {
+        let packet = [7u8; COMPRESSION_THRESHOLD - 1];
+        let encoded = encode(&packet);
+        assert!(!is_compressed(&encoded));
+        assert_eq!(encoded.len(), packet.len() + 1);
+        assert_eq!(decode(&encoded, packet.len()).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_big_packet_compressed() {
+        let packet = vec![7u8; COMPRESSION_THRESHOLD * 8];
+        let encoded = encode(&packet);
+        assert!(is_compressed(&encoded));
+        assert!(encoded.len() < packet.len());
+        assert_eq!(decode(&encoded, packet.len()).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let packet = vec![0u8; COMPRESSION_THRESHOLD * 64];
+        let encoded = encode(&packet);
+        assert!(matches!(decode(&encoded, COMPRESSION_THRESHOLD), Err(P2pError::DecompressedPacketTooLarge(size, max)) if size == packet.len() && max == COMPRESSION_THRESHOLD));
+
+        // Announced size lower than the real one
+        let mut forged = encoded.clone();
+        forged[1..1 + LZ4_SIZE_PREFIX].copy_from_slice(&1u32.to_le_bytes());
+        assert!(decode(&forged, COMPRESSION_THRESHOLD).is_err());
+    }
+
+    #[test]
+    fn test_invalid_marker() {
+        assert!(matches!(decode(&[2, 0, 0], 16), Err(P2pError::InvalidCompressionMarker(2))));
+        assert!(matches!(decode(&[], 16), Err(P2pError::InvalidPacketSize)));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::config::{PEER_TIMEOUT_DISCONNECT, PEER_TIMEOUT_INIT_CONNECTION};
 use super::{
+    compression::{self, CompressionStats},
     encryption::Encryption,
     error::P2pError,
     packet::Packet,
+    stats::NetworkStats,
     EncryptionKey
 };
 use std::{
@@ -10,10 +12,13 @@ use std::{
     convert::TryInto,
     fmt::{Display, Error, Formatter},
     net::SocketAddr,
-    sync::atomic::{
-        AtomicBool,
-        AtomicUsize,
-        Ordering
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicUsize,
+            Ordering
+        },
+        Arc
     },
     time::Duration
 };
@@ -71,14 +76,19 @@ pub struct Connection {
     // How many key rotation we sent
     rotate_key_out: AtomicUsize,
     // Encryption state used for packets
-    encryption: Encryption
+    encryption: Encryption,
+    // Packets are compressed once both sides announced it in the handshake
+    compression: AtomicBool,
+    compression_stats: CompressionStats,
+    // traffic counters shared with the P2P server
+    stats: Arc<NetworkStats>
 }
 
 // We are rotating every 1GB sent
 const ROTATE_EVERY_N_BYTES: usize = 1024 * 1024 * 1024;
 
 impl Connection {
-    pub fn new(stream: TcpStream, addr: SocketAddr, out: bool) -> Self {
+    pub fn new(stream: TcpStream, addr: SocketAddr, out: bool, stats: Arc<NetworkStats>) -> Self {
         let (read, write) = stream.into_split();
         Self {
             out,
@@ -94,6 +104,9 @@ impl Connection {
             rotate_key_in: AtomicUsize::new(0),
             rotate_key_out: AtomicUsize::new(0),
             encryption: Encryption::new(),
+            compression: AtomicBool::new(false),
+            compression_stats: CompressionStats::new(),
+            stats
         }
     }
 
@@ -161,7 +174,11 @@ impl Connection {
         // Verify if we already have one set
         
         // Build the packet
-        let mut packet = Bytes::from(Packet::KeyExchange(Cow::Borrowed(&new_key)).to_bytes());
+        let mut bytes = Packet::KeyExchange(Cow::Borrowed(&new_key)).to_bytes();
+        if self.is_compression_enabled() {
+            bytes = compression::encode(&bytes);
+        }
+        let mut packet = Bytes::from(bytes);
 
         // This is used to determine if we need to encrypt the packet or not
         // Check if we already had a key set, if so, encrypt it
@@ -202,6 +219,7 @@ impl Connection {
         let packet_len = packet.len() as u32;
         stream.write_all(&packet_len.to_be_bytes()).await?;
         stream.write_all(packet).await?;
+        self.stats.add_bytes_out(4 + packet.len());
 
         Ok(())
     }
@@ -213,6 +231,20 @@ impl Connection {
 
         // Count the bytes sent
         self.bytes_out.fetch_add(packet.len(), Ordering::Relaxed);
+        if let Some(id) = packet.first() {
+            self.stats.add_message_out(*id);
+        }
+
+        let encoded;
+        let packet = if self.is_compression_enabled() {
+            encoded = compression::encode(packet);
+            if compression::is_compressed(&encoded) {
+                self.compression_stats.add_out(encoded.len(), packet.len());
+            }
+            &encoded
+        } else {
+            packet
+        };
 
         if self.encryption.is_write_ready().await {
             let buffer = self.encryption.encrypt_packet(packet).await?;
@@ -244,15 +276,26 @@ impl Connection {
     pub async fn read_packet_bytes(&self, buf: &mut [u8], max_size: u32) -> P2pResult<Vec<u8>> {
         let mut stream = self.read.lock().await;
         let size = self.read_packet_size(&mut stream, buf, max_size).await?;
-        if size == 0 || size > max_size {
+        if size == 0 {
             if self.get_state() == State::Success {
-                warn!("Received invalid packet size: {} bytes (max: {} bytes) from peer {}", size, max_size, self.get_address());
+                warn!("Received empty packet from peer {}", self.get_address());
             }
             return Err(P2pError::InvalidPacketSize)
         }
         trace!("Size received: {}", size);
 
-        let bytes = self.read_all_bytes(&mut stream, buf, size).await?;
+        let mut bytes = self.read_all_bytes(&mut stream, buf, size).await?;
+        if self.is_compression_enabled() {
+            let decoded = compression::decode(&bytes, max_size as usize)?;
+            if compression::is_compressed(&bytes) {
+                self.compression_stats.add_in(bytes.len(), decoded.len());
+            }
+            bytes = decoded;
+        }
+
+        if let Some(id) = bytes.first() {
+            self.stats.add_message_in(*id);
+        }
         Ok(bytes)
     }
 
@@ -288,12 +331,13 @@ impl Connection {
         let array: [u8; 4] = buf[0..4].try_into()?;
         let size = u32::from_be_bytes(array);
 
-        // Verify if the size is valid
+        // Verify if the size is valid before reading anything else
+        // so a peer can't make us buffer an oversized message
         if size > max_usize {
             if self.get_state() == State::Success {
-                warn!("Received invalid packet size: {} bytes from {}", size, self);
+                warn!("Received a message of {} bytes (max: {} bytes) from {}", size, max_usize, self);
             }
-            return Err(P2pError::InvalidPacketSize)
+            return Err(P2pError::MessageTooLarge(size, max_usize))
         }
         Ok(size)
     }
@@ -342,6 +386,7 @@ impl Connection {
             }
         }
         self.bytes_in.fetch_add(read, Ordering::Relaxed);
+        self.stats.add_bytes_in(read);
 
         Ok(read)
     }
@@ -405,6 +450,20 @@ impl Connection {
         format_duration(Duration::from_secs(elapsed_seconds)).to_string()
     }
 
+    // Must be called once both handshakes are exchanged
+    // so each side switches at the same packet
+    pub fn enable_compression(&self) {
+        self.compression.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_compression_enabled(&self) -> bool {
+        self.compression.load(Ordering::SeqCst)
+    }
+
+    pub fn get_compression_stats(&self) -> &CompressionStats {
+        &self.compression_stats
+    }
+
     // Verify if the connection is closed
     pub fn is_closed(&self) -> bool {
         self.closed.load(Ordering::SeqCst)
@@ -415,4 +474,76 @@ impl Display for Connection {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), Error> {
         write!(f, "Connection[state: {:?}, peer: {}, read: {}, sent: {}, key rotation (in/out): ({}/{}), connected since: {}, closed: {}]", self.state, self.get_address(), human_bytes(self.bytes_in() as f64), human_bytes(self.bytes_out() as f64), self.key_rotation_in(), self.key_rotation_out(), self.get_human_uptime(), self.is_closed())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_oversized_message_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let connection = Connection::new(stream, peer_addr, false, Arc::new(NetworkStats::new()));
+
+        // Only the size prefix is sent, the body is never read
+        let max_size = 1024;
+        client.write_all(&(u32::MAX).to_be_bytes()).await.unwrap();
+
+        let mut buf = [0u8; 256];
+        let res = timeout(Duration::from_secs(1), connection.read_packet_bytes(&mut buf, max_size)).await
+            .expect("connection should not wait for the message body");
+        assert!(matches!(res, Err(P2pError::MessageTooLarge(u32::MAX, 1024))));
+    }
+
+    async fn connected_pair() -> (Connection, Connection) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let stats = Arc::new(NetworkStats::new());
+        (Connection::new(client, addr, true, stats.clone()), Connection::new(stream, peer_addr, false, stats))
+    }
+
+    #[tokio::test]
+    async fn test_packets_round_trip() {
+        let small = vec![1u8; 64];
+        let big = vec![2u8; 64 * 1024];
+        let mut buf = [0u8; 1024];
+
+        for enabled in [false, true] {
+            let (client, server) = connected_pair().await;
+            if enabled {
+                client.enable_compression();
+                server.enable_compression();
+            }
+
+            for packet in [&small, &big] {
+                client.send_bytes(packet).await.unwrap();
+                assert_eq!(&server.read_packet_bytes(&mut buf, 128 * 1024).await.unwrap(), packet);
+            }
+
+            // Only the big packet is compressed
+            let ratio = server.get_compression_stats().get_ratio();
+            assert_eq!(ratio.is_some(), enabled);
+            assert!(ratio.map_or(true, |ratio| ratio < 0.1));
+            assert_eq!(client.get_compression_stats().get_ratio(), ratio);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompressed_packet_too_large() {
+        let (client, server) = connected_pair().await;
+        client.enable_compression();
+        server.enable_compression();
+
+        // Compressed size is below the limit, but not once decompressed
+        let max_size = 4 * 1024;
+        client.send_bytes(&vec![0u8; 256 * 1024]).await.unwrap();
+        let mut buf = [0u8; 1024];
+        let res = server.read_packet_bytes(&mut buf, max_size).await;
+        assert!(matches!(res, Err(P2pError::DecompressedPacketTooLarge(_, 4096))));
+    }
+}
@@ -1,6 +1,13 @@
 use crate::{
     core::error::BlockchainError,
-    config::{CHAIN_SYNC_RESPONSE_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MIN_BLOCKS}
+    config::{
+        CHAIN_SYNC_RESPONSE_MAX_BLOCKS,
+        CHAIN_SYNC_RESPONSE_MIN_BLOCKS,
+        P2P_MIN_MAX_MESSAGE_SIZE,
+        PEER_PENALTY_INVALID_PACKET,
+        PEER_PENALTY_INVALID_POW,
+        PEER_PENALTY_UNREQUESTED_DATA
+    }
 };
 use tokio::{
     sync::{
@@ -29,18 +36,24 @@ use super::{
     encryption::EncryptionError,
     packet::{
         bootstrap_chain::StepKind,
+        disconnect::DisconnectReason,
         object::ObjectRequest,
-    }
+    },
+    socks::SocksError
 };
 
 #[derive(Error, Debug)]
 pub enum P2pError {
-    #[error("Invalid tag, it must be greater than 0 and maximum 16 chars")]
+    #[error("Invalid tag, it must be greater than 0 and maximum 16 printable ASCII chars")]
     InvalidTag,
     #[error("Invalid max chain response size, it must be between {} and {}", CHAIN_SYNC_RESPONSE_MIN_BLOCKS, CHAIN_SYNC_RESPONSE_MAX_BLOCKS)]
     InvalidMaxChainResponseSize,
     #[error("Invalid max peers, it must be greater than 0")]
     InvalidMaxPeers,
+    #[error("Invalid max outgoing peers, it can't be greater than max peers")]
+    InvalidMaxOutgoingPeers,
+    #[error("Invalid priority slots, they must be lower than max peers")]
+    InvalidPrioritySlots,
     #[error("Already closed")]
     AlreadyClosed,
     #[error("Incompatible with configured exclusive nodes")]
@@ -175,6 +188,46 @@ pub enum P2pError {
     SemaphoreAcquireError(#[from] AcquireError),
     #[error(transparent)]
     EncryptionError(#[from] EncryptionError),
+    #[error("Peer closed the connection: {}", _0)]
+    DisconnectedWithReason(DisconnectReason),
+    #[error(transparent)]
+    Socks(#[from] SocksError),
+    #[error("Invalid max message size, it must be at least {} bytes", P2P_MIN_MAX_MESSAGE_SIZE)]
+    InvalidMaxMessageSize,
+    #[error("Message of {} bytes is above the limit of {} bytes", _0, _1)]
+    MessageTooLarge(u32, u32),
+    #[error("Invalid request timeout, it must be at least 1 second")]
+    InvalidRequestTimeout,
+    #[error("No bind address configured")]
+    NoBindAddress,
+    #[error("Invalid sync parallel requests, it must be at least 1")]
+    InvalidSyncParallelRequests,
+    #[error("Decompressed packet of {} bytes is above the limit of {} bytes", _0, _1)]
+    DecompressedPacketTooLarge(usize, usize),
+    #[error("Invalid compressed packet: {}", _0)]
+    InvalidCompressedPacket(String),
+    #[error("Invalid compression marker {}", _0)]
+    InvalidCompressionMarker(u8)
+}
+
+impl P2pError {
+    // Penalty to add to the fail count of the peer responsible of this error
+    pub fn penalty(&self) -> u8 {
+        match self {
+            Self::BlockchainError(e) if matches!(**e, BlockchainError::InvalidDifficulty) => PEER_PENALTY_INVALID_POW,
+            Self::ReaderError(_)
+            | Self::InvalidPacket
+            | Self::InvalidPacketNotFullRead
+            | Self::InvalidCompressedPacket(_)
+            | Self::InvalidCompressionMarker(_) => PEER_PENALTY_INVALID_PACKET,
+            Self::ObjectNotRequested(_)
+            | Self::UnrequestedChainResponse
+            | Self::UnrequestedBootstrapChainResponse
+            | Self::InvalidObjectResponse(_)
+            | Self::InvalidObjectResponseType => PEER_PENALTY_UNREQUESTED_DATA,
+            _ => 1
+        }
+    }
 }
 
 impl From<BlockchainError> for P2pError {
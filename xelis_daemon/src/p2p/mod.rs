@@ -4,6 +4,13 @@ pub mod error;
 pub mod packet;
 pub mod peer_list;
 pub mod chain_validator;
+pub mod version_stats;
+pub mod socks;
+pub mod stats;
+pub mod scheduler;
+pub mod compression;
+pub mod slots;
+mod pipeline;
 mod tracker;
 mod encryption;
 
@@ -13,6 +20,7 @@ use indexmap::IndexSet;
 use lru::LruCache;
 use xelis_common::{
     account::VersionedNonce,
+    asset::{AssetData, AssetWithData},
     api::daemon::{
         Direction,
         NotifyEvent,
@@ -21,8 +29,9 @@ use xelis_common::{
     block::{Block, BlockHeader},
     config::{TIPS_LIMIT, VERSION},
     crypto::{Hash, Hashable},
-    difficulty::CumulativeDifficulty,
+    difficulty::{check_difficulty, CumulativeDifficulty},
     immutable::Immutable,
+    rate_limiter::RateLimiter,
     serializer::Serializer,
     thread_pool::ThreadPool,
     time::{
@@ -35,13 +44,15 @@ use xelis_common::{
 use crate::{
     config::{
         get_genesis_block_hash,
+        get_minimum_difficulty,
         get_seed_nodes,
         CHAIN_SYNC_DEFAULT_RESPONSE_BLOCKS, CHAIN_SYNC_DELAY, CHAIN_SYNC_REQUEST_EXPONENTIAL_INDEX_START,
         CHAIN_SYNC_REQUEST_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MIN_BLOCKS,
         CHAIN_SYNC_TOP_BLOCKS, MILLIS_PER_SECOND, NETWORK_ID, P2P_AUTO_CONNECT_PRIORITY_NODES_DELAY,
         P2P_EXTEND_PEERLIST_DELAY, P2P_PING_DELAY, P2P_PING_PEER_LIST_DELAY, P2P_PING_PEER_LIST_LIMIT,
-        PEER_FAIL_LIMIT, PEER_MAX_PACKET_SIZE, PEER_TIMEOUT_INIT_CONNECTION, PEER_TIMEOUT_INIT_OUTGOING_CONNECTION,
-        PRUNE_SAFETY_LIMIT, STABLE_LIMIT, P2P_PING_TIMEOUT, P2P_HEARTBEAT_INTERVAL, PEER_SEND_BYTES_TIMEOUT
+        P2P_MIN_MAX_MESSAGE_SIZE, PEER_FAIL_LIMIT, PEER_MAX_PACKET_SIZE, PEER_TIMEOUT_INIT_CONNECTION, PEER_TIMEOUT_INIT_OUTGOING_CONNECTION,
+        PRUNE_SAFETY_LIMIT, STABLE_LIMIT, P2P_PING_TIMEOUT, P2P_HEARTBEAT_INTERVAL, PEER_SEND_BYTES_TIMEOUT,
+        P2P_VERSION_STATS_DELAY, PEER_VERSION_STATS_RECENT_TIME, PEER_PENALTY_INVALID_POW
     },
     core::{
         blockchain::Blockchain,
@@ -65,7 +76,8 @@ use crate::{
                 NOTIFY_MAX_LEN
             }
         },
-        tracker::ResponseBlocker
+        tracker::ResponseBlocker,
+        version_stats::UpgradeNudge
     },
     rpc::rpc::get_peer_entry
 };
@@ -74,7 +86,8 @@ use self::{
     error::P2pError,
     packet::{
         chain::{BlockId, ChainRequest, ChainResponse},
-        handshake::Handshake,
+        disconnect::DisconnectReason,
+        handshake::{Handshake, FEATURE_COMPRESSION, FEATURE_IPV6_ADDRESSES},
         object::{ObjectRequest, ObjectResponse, OwnedObjectResponse},
         ping::Ping,
         Packet,
@@ -82,6 +95,11 @@ use self::{
     },
     peer::{Peer, TaskState, Rx},
     peer_list::{PeerList, SharedPeerList},
+    pipeline::SyncPipeline,
+    scheduler::{RequestScheduler, MAX_REQUEST_ATTEMPTS},
+    slots::{eviction_score, PeerLimits, PeerSlots},
+    socks::SocksError,
+    stats::NetworkStats,
     tracker::{ObjectTracker, SharedObjectTracker}
 };
 use tokio::{
@@ -98,24 +116,26 @@ use tokio::{
         },
         Mutex
     },
-    task::JoinHandle,
+    task::{spawn_blocking, JoinHandle},
     time::{interval, sleep, timeout}
 };
+use futures_util::{stream::FuturesUnordered, StreamExt};
 use log::{info, warn, error, debug, trace};
 use std::{
     borrow::Cow,
-    collections::{hash_map::Entry, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
     io,
     net::{IpAddr, SocketAddr},
     num::NonZeroUsize,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc
     },
-    time::Duration
+    time::{Duration, Instant}
 };
 use bytes::Bytes;
 use rand::{seq::IteratorRandom, Rng};
+use socket2::{Domain, Protocol, Socket, Type};
 
 // P2pServer is a fully async TCP server
 // Each connection will block on a data to send or to receive
@@ -125,10 +145,27 @@ pub struct P2pServer<S: Storage> {
     peer_id: u64,
     // node tag sent on handshake
     tag: Option<String>,
-    // max peers accepted by this server
-    max_peers: usize,
-    // ip:port address to receive connections
-    bind_address: SocketAddr,
+    // max peers accepted by this server, and slots reserved to priority peers
+    peer_slots: PeerSlots,
+    // evict the worst peer when full and a better one try to connect
+    evict_peers: bool,
+    // suggest to upgrade when enough peers run a newer version
+    upgrade_nudge: UpgradeNudge,
+    // rate limiter of incoming connections per IP, None if disabled
+    connection_rate_limiter: Option<RateLimiter<IpAddr>>,
+    // incoming connections dropped by the rate limiter
+    rate_limited_connections: AtomicU64,
+    // incoming connections rejected (not allowed, already connected...)
+    rejected_connections: AtomicU64,
+    // SOCKS5 proxy (host:port) used for outgoing connections
+    socks_proxy: Option<String>,
+    // peers sending a message bigger than this are disconnected
+    max_message_size: u32,
+    // traffic and handshake counters since startup
+    network_stats: Arc<NetworkStats>,
+    // ip:port addresses to receive connections
+    // the first one is announced in the handshake
+    bind_addresses: Vec<SocketAddr>,
     // all peers accepted
     peer_list: SharedPeerList,
     // reference to the chain to add blocks/txs
@@ -137,6 +174,8 @@ pub struct P2pServer<S: Storage> {
     connections_sender: Sender<(SocketAddr, bool)>,
     // used to requests objects to peers and avoid requesting the same object to multiple peers
     object_tracker: SharedObjectTracker,
+    // spread the sync requests over the peers and track the slow ones
+    request_scheduler: Arc<RequestScheduler>,
     // used to check if the server is running or not in tasks
     is_running: AtomicBool,
     // Synced cache to prevent concurrent tasks adding the block
@@ -149,6 +188,8 @@ pub struct P2pServer<S: Storage> {
     // This can be used safely from a trusted node
     // to boost the sync speed by allowing to request several blocks at same time
     allow_boost_sync_mode: bool,
+    // blocks requested at the same time during a sync without boost mode
+    sync_parallel_requests: usize,
     // max size of the chain response
     // this is a configurable paramater for nodes to manage their resources
     // Can be reduced for low devices, and increased for high end devices
@@ -160,6 +201,8 @@ pub struct P2pServer<S: Storage> {
     // Are we allowing others nodes to share us as a potential peer ?
     // Also if we allows to be listed in get_peers RPC API
     sharable: bool,
+    // Compress the packets with the peers supporting it
+    compression: bool,
     // Do we try to connect to others nodes
     // If this is enabled, only way to have peers is to let them connect to us
     outgoing_connections_disabled: AtomicBool,
@@ -170,8 +213,8 @@ pub struct P2pServer<S: Storage> {
 }
 
 impl<S: Storage> P2pServer<S> {
-    pub fn new(concurrency: usize, dir_path: Option<String>, tag: Option<String>, max_peers: usize, bind_address: String, blockchain: Arc<Blockchain<S>>, use_peerlist: bool, exclusive_nodes: Vec<SocketAddr>, allow_fast_sync_mode: bool, allow_boost_sync_mode: bool, max_chain_response_size: Option<usize>, sharable: bool, disable_outgoing_connections: bool) -> Result<Arc<Self>, P2pError> {
-        if tag.as_ref().is_some_and(|tag| tag.len() == 0 || tag.len() > 16) {
+    pub fn new(concurrency: usize, dir_path: Option<String>, tag: Option<String>, max_peers: usize, max_outgoing_peers: Option<usize>, priority_slots: usize, bind_address: String, blockchain: Arc<Blockchain<S>>, use_peerlist: bool, exclusive_nodes: Vec<SocketAddr>, allow_fast_sync_mode: bool, allow_boost_sync_mode: bool, max_chain_response_size: Option<usize>, sharable: bool, disable_outgoing_connections: bool, evict_peers: bool, upgrade_nudge_ratio: f64, connection_rate_limit: u64, connection_rate_burst: u64, socks_proxy: Option<String>, max_message_size: u32, request_timeout: u64, sync_parallel_requests: usize, compression: bool) -> Result<Arc<Self>, P2pError> {
+        if tag.as_ref().is_some_and(|tag| !Handshake::is_valid_tag(tag)) {
             return Err(P2pError::InvalidTag);
         }
 
@@ -179,43 +222,86 @@ impl<S: Storage> P2pServer<S> {
             return Err(P2pError::InvalidMaxChainResponseSize);
         }
 
-        if max_peers == 0 {
-            return Err(P2pError::InvalidMaxPeers);
+        let peer_slots = PeerSlots::new(PeerLimits {
+            max_peers,
+            max_outgoing_peers: max_outgoing_peers.unwrap_or(max_peers),
+            priority_slots
+        })?;
+
+        if max_message_size < P2P_MIN_MAX_MESSAGE_SIZE {
+            return Err(P2pError::InvalidMaxMessageSize);
+        }
+
+        if max_message_size < PEER_MAX_PACKET_SIZE {
+            warn!("P2P max message size is below the max block size, peers sending bigger blocks will be disconnected");
+        }
+
+        if request_timeout == 0 {
+            return Err(P2pError::InvalidRequestTimeout);
+        }
+
+        if sync_parallel_requests == 0 {
+            return Err(P2pError::InvalidSyncParallelRequests);
         }
 
         // set channel to communicate with listener thread
         let mut rng = rand::thread_rng();
         let peer_id: u64 = rng.gen(); // generate a random peer id for network
-        let addr: SocketAddr = bind_address.parse()?; // parse the bind address
+        // parse the bind addresses, separated by a comma
+        let bind_addresses = bind_address.split(',')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty())
+            .map(|addr| addr.parse())
+            .collect::<Result<Vec<SocketAddr>, _>>()?;
+        if bind_addresses.is_empty() {
+            return Err(P2pError::NoBindAddress);
+        }
+
         // create mspc channel for connections to peers
         let (connections_sender, connections_receiver) = mpsc::channel(max_peers);
         let (blocks_processor, blocks_processor_receiver) = mpsc::channel(TIPS_LIMIT * STABLE_LIMIT as usize);
 
         // Channel used to broadcast the stop message
         let (exit_sender, exit_receiver) = broadcast::channel(1);
-        let object_tracker = ObjectTracker::new(blockchain.clone(), exit_receiver);
 
         let (sender, event_receiver) = channel::<Arc<Peer>>(max_peers); 
         let peer_list = PeerList::new(max_peers, format!("{}peerlist-{}.json", dir_path.unwrap_or_default(), blockchain.get_network().to_string().to_lowercase()), Some(sender));
 
+        let request_scheduler = Arc::new(RequestScheduler::new(Duration::from_secs(request_timeout)));
+        let object_tracker = ObjectTracker::new(blockchain.clone(), peer_list.clone(), request_scheduler.clone(), exit_receiver);
 
         let server = Self {
             peer_id,
             tag,
-            max_peers,
-            bind_address: addr,
+            peer_slots,
+            evict_peers,
+            upgrade_nudge: UpgradeNudge::new(upgrade_nudge_ratio),
+            connection_rate_limiter: if connection_rate_limit > 0 {
+                Some(RateLimiter::new(connection_rate_limit, connection_rate_burst))
+            } else {
+                None
+            },
+            rate_limited_connections: AtomicU64::new(0),
+            rejected_connections: AtomicU64::new(0),
+            socks_proxy,
+            max_message_size,
+            network_stats: Arc::new(NetworkStats::new()),
+            bind_addresses,
             peer_list,
             blockchain,
             connections_sender,
             object_tracker,
+            request_scheduler,
             is_running: AtomicBool::new(true),
             blocks_propagation_queue: Mutex::new(LruCache::new(NonZeroUsize::new(STABLE_LIMIT as usize * TIPS_LIMIT).unwrap())),
             blocks_processor,
             allow_fast_sync_mode,
             allow_boost_sync_mode,
+            sync_parallel_requests,
             max_chain_response_size: max_chain_response_size.unwrap_or(CHAIN_SYNC_DEFAULT_RESPONSE_BLOCKS),
             exclusive_nodes: IndexSet::from_iter(exclusive_nodes.into_iter()),
             sharable,
+            compression,
             is_syncing: AtomicBool::new(false),
             outgoing_connections_disabled: AtomicBool::new(disable_outgoing_connections),
             exit_sender,
@@ -279,7 +365,7 @@ impl<S: Storage> P2pServer<S> {
                         break;
                     }
 
-                    let connect = if self.peer_list.size().await >= self.max_peers {
+                    let connect = if !self.accept_new_connections(true, true).await {
                         // if we have already reached the limit, we ignore this new connection
                         None
                     } else {
@@ -308,8 +394,14 @@ impl<S: Storage> P2pServer<S> {
     // connect to seed nodes, start p2p server
     // and wait on all new connections
     async fn start(self: &Arc<Self>, receiver: Receiver<(SocketAddr, bool)>, blocks_processor_receiver: Receiver<(Arc<Peer>, BlockHeader, Hash)>, event_receiver: Receiver<Arc<Peer>>, use_peerlist: bool, concurrency: usize) -> Result<(), P2pError> {
-        let listener = TcpListener::bind(self.get_bind_address()).await?;
-        info!("P2p Server will listen on: {}", self.get_bind_address());
+        let mut listeners = Vec::with_capacity(self.bind_addresses.len());
+        for addr in self.bind_addresses.iter() {
+            // An IPv6 wildcard address also accepts IPv4 connections
+            // unless an IPv4 address is bound on the same port
+            let only_v6 = addr.is_ipv6() && self.bind_addresses.iter().any(|other| other.is_ipv4() && other.port() == addr.port());
+            listeners.push(bind_listener(addr, only_v6)?);
+            info!("P2p Server will listen on: {}{}", addr, if addr.is_ipv6() && !only_v6 && addr.ip().is_unspecified() { " (dual-stack)" } else { "" });
+        }
 
         let mut exclusive_nodes = self.exclusive_nodes.clone();
         if exclusive_nodes.is_empty() {
@@ -341,6 +433,9 @@ impl<S: Storage> P2pServer<S> {
         // start the event loop task to handle peer disconnect events
         spawn_task("p2p-events", Arc::clone(&self).event_loop(event_receiver));
 
+        // start the task summarizing versions used by peers
+        spawn_task("p2p-version-stats", Arc::clone(&self).version_stats_loop());
+
 
         // start another task for peerlist loop
         if use_peerlist {
@@ -349,7 +444,10 @@ impl<S: Storage> P2pServer<S> {
 
         let (tx, mut rx) = channel(1);
         spawn_task("p2p-outgoing-connections", Arc::clone(&self).handle_outgoing_connections(priority_connections, receiver, tx.clone()));
-        spawn_task("p2p-incoming-connections", Arc::clone(&self).handle_incoming_connections(listener, tx, concurrency));
+        for listener in listeners {
+            spawn_task("p2p-incoming-connections", Arc::clone(&self).handle_incoming_connections(listener, tx.clone(), concurrency));
+        }
+        drop(tx);
 
         let mut exit_receiver = self.exit_sender.subscribe();
         loop {
@@ -373,6 +471,7 @@ impl<S: Storage> P2pServer<S> {
                             Err(e) => match e {
                                 P2pError::PeerListFull => {
                                     debug!("Peer list is full, we can't accept new connections");
+                                    self.send_disconnect_reason(peer.get_connection(), DisconnectReason::PeerListFull).await;
                                     if let Err(e) = peer.get_connection().close().await {
                                         debug!("Error while closing unhandled connection: {}", e);
                                     }
@@ -435,7 +534,7 @@ impl<S: Storage> P2pServer<S> {
             if !priority {
                 trace!("checking if connection can be accepted");
                 // check that this incoming peer isn't blacklisted
-                if !self.accept_new_connections().await || !self.peer_list.is_allowed(&addr.ip()).await {
+                if !self.accept_new_connections(false, true).await || !self.peer_list.is_allowed(&addr.ip()).await {
                     debug!("{} is not allowed, we can't connect to it", addr);
                     continue;
                 }
@@ -447,6 +546,11 @@ impl<S: Storage> P2pServer<S> {
 
             let connection = match self.connect_to_peer(addr).await {
                 Ok(connection) => connection,
+                Err(P2pError::Socks(e @ SocksError::ProxyUnreachable(..))) => {
+                    // Not the fault of the peer, don't increase its fail count
+                    error!("Can't connect to {}: {}", addr, e);
+                    continue;
+                },
                 Err(e) => {
                     debug!("Error while connecting to address {}: {}", addr, e);
 
@@ -462,7 +566,7 @@ impl<S: Storage> P2pServer<S> {
                 Ok(handshake) => handshake,
                 Err(e) => {
                     debug!("Error while verifying connection to address {}: {}", addr, e);
-                    if !priority {
+                    if !priority && !matches!(e, P2pError::DisconnectedWithReason(_)) {
                         self.peer_list.increase_fail_count_for_stored_peer(&addr.ip(), false).await;
                     }
                     continue;
@@ -481,32 +585,59 @@ impl<S: Storage> P2pServer<S> {
 
     async fn handle_incoming_connection(self: &Arc<Self>, res: io::Result<(TcpStream, SocketAddr)>, thread_pool: &ThreadPool, tx: &Sender<(Peer, Rx)>) -> Result<(), P2pError> {
         let (mut stream, addr) = res?;
+        let addr = to_canonical_address(addr);
+
+        // Drop attempts above the rate limit before doing anything else
+        if let Some(limiter) = self.connection_rate_limiter.as_ref() {
+            if limiter.check(addr.ip()).is_err() && !self.peer_list.is_whitelisted(&addr.ip()).await {
+                debug!("Rate limiting incoming connection from {}", addr);
+                self.rate_limited_connections.fetch_add(1, Ordering::Relaxed);
+                return Ok(())
+            }
+        }
 
         // Verify if we can accept new connections
         let reject = !self.is_compatible_with_exclusive_nodes(&addr)
             // check that this incoming peer isn't blacklisted
-            || !self.accept_new_connections().await
             || !self.peer_list.is_allowed(&addr.ip()).await
             || self.is_connected_to_addr(&addr).await;
 
         // Reject connection
         if reject {
             debug!("Rejecting connection from {}", addr);
+            self.rejected_connections.fetch_add(1, Ordering::Relaxed);
             stream.shutdown().await?;
             return Ok(())
         }
 
-        let connection = Connection::new(stream, addr, false);
+        // If we are full, the handshake is only needed when one of our peers may be evicted for it
+        let full = !self.accept_new_connections(false, false).await && !self.evict_peers && !self.is_syncing_chain();
+        if full {
+            self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let connection = Connection::new(stream, addr, false, Arc::clone(&self.network_stats));
         let zelf = Arc::clone(&self);
         let tx = tx.clone();
         thread_pool.execute(async move {
             let mut buffer = [0; 512];
+            if full {
+                debug!("Peer list is full, rejecting {} before the handshake", addr);
+                if let Err(e) = zelf.reject_full_connection(&mut buffer, connection).await {
+                    debug!("Error while rejecting incoming connection {}: {}", addr, e);
+                }
+                return;
+            }
+
             match zelf.create_verified_peer(&mut buffer, connection, false).await {
                 Ok((peer, rx)) => {
                     if let Err(e) = tx.send((peer, rx)).await {
                         error!("Error while sending new connection to listener: {}", e);
                     }
                 },
+                Err(P2pError::PeerListFull) => {
+                    debug!("Rejected incoming connection {} because peer list is full", addr);
+                },
                 Err(e) => {
                     debug!("Error while handling incoming connection {}: {}", addr, e);
                     zelf.peer_list.increase_fail_count_for_stored_peer(&addr.ip(), true).await;
@@ -587,15 +718,19 @@ impl<S: Storage> P2pServer<S> {
         let pruned_topoheight = storage.get_pruned_topoheight().await?;
         let cumulative_difficulty = storage.get_cumulative_difficulty_for_block_hash(&top_hash).await.unwrap_or_else(|_| CumulativeDifficulty::zero());
         let genesis_block = get_genesis_block_hash(self.blockchain.get_network());
-        let handshake = Handshake::new(Cow::Owned(VERSION.to_owned()), *self.blockchain.get_network(), Cow::Borrowed(self.get_tag()), Cow::Borrowed(&NETWORK_ID), self.get_peer_id(), self.bind_address.port(), get_current_time_in_seconds(), topoheight, block.get_height(), pruned_topoheight, Cow::Borrowed(&top_hash), Cow::Borrowed(genesis_block), Cow::Borrowed(&cumulative_difficulty), self.sharable);
+        let handshake = Handshake::new(Cow::Owned(VERSION.to_owned()), *self.blockchain.get_network(), Cow::Borrowed(self.get_tag()), Cow::Borrowed(&NETWORK_ID), self.get_peer_id(), self.get_bind_address().port(), get_current_time_in_seconds(), topoheight, block.get_height(), pruned_topoheight, Cow::Borrowed(&top_hash), Cow::Borrowed(genesis_block), Cow::Borrowed(&cumulative_difficulty), self.sharable, FEATURE_IPV6_ADDRESSES | if self.compression { FEATURE_COMPRESSION } else { 0 });
         Ok(Packet::Handshake(Cow::Owned(handshake)).to_bytes())
     }
 
     // Create a valid peer using the connection, if an error happen, it will close the stream and return the error
     async fn create_verified_peer(&self, buf: &mut [u8], mut connection: Connection, priority: bool) -> Result<(Peer, Rx), P2pError> {
         let handshake = match self.verify_connection(buf, &mut connection).await {
-            Ok(handshake) => handshake,
+            Ok(handshake) => {
+                self.network_stats.add_handshake(true);
+                handshake
+            },
             Err(e) => {
+                self.network_stats.add_handshake(false);
                 debug!("Error while verifying connection with {}: {}", connection, e);
                 connection.close().await?;
                 return Err(e);
@@ -625,6 +760,10 @@ impl<S: Storage> P2pServer<S> {
         let mut handshake: Handshake<'_> = match timeout(Duration::from_millis(PEER_TIMEOUT_INIT_CONNECTION), connection.read_packet(buf, buf.len() as u32)).await?? {
             // only allow handshake packet
             Packet::Handshake(h) => h.into_owned(),
+            Packet::Disconnect(reason) => {
+                debug!("{} refused our connection: {}", connection, reason);
+                return Err(P2pError::DisconnectedWithReason(reason))
+            },
             _ => return Err(P2pError::ExpectedHandshake)
         };
 
@@ -635,10 +774,22 @@ impl<S: Storage> P2pServer<S> {
         // if it's a outgoing connection, don't send the handshake back
         // because we have already sent it
         if !connection.is_out() {
+            if !self.accept_new_connections(false, false).await && self.find_peer_to_evict(&connection.get_address().ip(), handshake.get_topoheight()).await.is_none() {
+                debug!("Peer list is full, rejecting {}", connection);
+                self.send_disconnect_reason(connection, DisconnectReason::PeerListFull).await;
+                return Err(P2pError::PeerListFull)
+            }
+
             trace!("Sending handshake back to {}", connection);
             self.send_handshake(&connection).await?;
         }
 
+        // Both handshakes are sent raw, next packets are compressed if both sides support it
+        if self.compression && handshake.supports_compression() {
+            debug!("Enabling compression with {}", connection);
+            connection.enable_compression();
+        }
+
         // if we reach here, handshake is all good, we can start listening this new peer
         connection.set_state(State::Success);
 
@@ -652,7 +803,12 @@ impl<S: Storage> P2pServer<S> {
             return Err(P2pError::PeerIdAlreadyUsed(peer_id));
         }
 
-        self.peer_list.add_peer(peer, self.get_max_peers()).await?;
+        if let Err(e) = self.peer_list.add_peer(peer, &self.peer_slots).await {
+            if !matches!(e, P2pError::PeerListFull) || !self.evict_peer_for(peer).await {
+                return Err(e)
+            }
+            self.peer_list.add_peer(peer, &self.peer_slots).await?;
+        }
 
         if peer.sharable() {
             trace!("Locking RPC Server to notify PeerConnected event");
@@ -668,6 +824,84 @@ impl<S: Storage> P2pServer<S> {
         self.handle_connection(peer.clone(), rx).await
     }
 
+    // Find the worst peer that can be evicted to free a slot for a new peer from this IP
+    // While we are syncing, a peer advertising a higher topoheight than ours can evict a peer behind it
+    // Otherwise, if enabled, only a peer that never failed to connect (or a whitelisted one)
+    // can evict a misbehaving peer
+    // Priority peers are never evicted, the one with the highest eviction score is selected
+    async fn find_peer_to_evict(&self, ip: &IpAddr, topoheight: u64) -> Option<Arc<Peer>> {
+        let syncing = self.is_syncing_chain() && topoheight > self.blockchain.get_topo_height();
+        if !syncing && !self.evict_peers {
+            return None
+        }
+
+        let whitelisted = self.peer_list.is_whitelisted(ip).await;
+        if !syncing && !whitelisted && self.peer_list.get_stored_fail_count(ip).await != 0 {
+            return None
+        }
+
+        let now = get_current_time_in_seconds();
+        let mut worst: Option<(u64, Arc<Peer>)> = None;
+        for peer in self.peer_list.get_cloned_peers().await {
+            if peer.is_priority() || self.peer_list.is_whitelisted(&peer.get_ip()).await {
+                continue;
+            }
+
+            if syncing {
+                if peer.get_topoheight() >= topoheight {
+                    continue;
+                }
+            } else if !whitelisted && peer.get_fail_count() == 0 {
+                continue;
+            }
+
+            let score = eviction_score(peer.get_fail_count(), peer.get_latency(), peer.get_last_useful_data(), now);
+            if worst.as_ref().map_or(true, |(worst_score, _)| score > *worst_score) {
+                worst = Some((score, peer));
+            }
+        }
+
+        worst.map(|(_, peer)| peer)
+    }
+
+    // Disconnect the worst peer to let this one take its slot
+    // Returns true if a peer has been evicted
+    async fn evict_peer_for(&self, peer: &Arc<Peer>) -> bool {
+        let Some(worst) = self.find_peer_to_evict(&peer.get_ip(), peer.get_topoheight()).await else {
+            return false
+        };
+
+        info!("Evicting {} (fail count: {}, latency: {}ms, last useful data: {}s ago) for {}", worst, worst.get_fail_count(), worst.get_latency(), get_current_time_in_seconds().saturating_sub(worst.get_last_useful_data()), peer.get_outgoing_address());
+        self.send_disconnect_reason(worst.get_connection(), DisconnectReason::Evicted).await;
+        if let Err(e) = worst.close().await {
+            debug!("Error while closing evicted peer {}: {}", worst, e);
+        }
+
+        true
+    }
+
+    // Close a new connection without reading its handshake
+    // Keys are still exchanged so the peer can read why we refused it
+    async fn reject_full_connection(&self, buf: &mut [u8], mut connection: Connection) -> Result<(), P2pError> {
+        if let Err(e) = connection.exchange_keys(buf).await {
+            connection.close().await?;
+            return Err(e)
+        }
+
+        self.send_disconnect_reason(&connection, DisconnectReason::PeerListFull).await;
+        connection.close().await
+    }
+
+    // Notify the peer why we are closing the connection
+    async fn send_disconnect_reason(&self, connection: &Connection, reason: DisconnectReason) {
+        let packet = Packet::Disconnect(reason).to_bytes();
+        match timeout(Duration::from_millis(PEER_SEND_BYTES_TIMEOUT), connection.send_bytes(&packet)).await {
+            Ok(Err(e)) => debug!("Error while sending disconnect reason to {}: {}", connection, e),
+            Err(_) => debug!("Timed out while sending disconnect reason to {}", connection),
+            _ => {}
+        }
+    }
+
     // Verify that we don't have any exclusive nodes configured OR that we are part of this list
     pub fn is_compatible_with_exclusive_nodes(&self, addr: &SocketAddr) -> bool {
         self.exclusive_nodes.is_empty() || self.exclusive_nodes.contains(addr)
@@ -711,8 +945,11 @@ impl<S: Storage> P2pServer<S> {
             }
         }
 
-        let stream = timeout(Duration::from_millis(PEER_TIMEOUT_INIT_OUTGOING_CONNECTION), TcpStream::connect(&addr)).await??;
-        let connection = Connection::new(stream, addr, true);
+        let stream = match self.socks_proxy.as_ref() {
+            Some(proxy) => timeout(Duration::from_millis(PEER_TIMEOUT_INIT_OUTGOING_CONNECTION), socks::connect(proxy, &addr.ip().to_string(), addr.port())).await??,
+            None => timeout(Duration::from_millis(PEER_TIMEOUT_INIT_OUTGOING_CONNECTION), TcpStream::connect(&addr)).await??
+        };
+        let connection = Connection::new(stream, addr, true, Arc::clone(&self.network_stats));
         Ok(connection)
     }
 
@@ -758,6 +995,131 @@ impl<S: Storage> P2pServer<S> {
         self.build_generic_ping_packet_with_storage(&*storage).await
     }
 
+    // Topoheight of a block in a chain response
+    // Top blocks are alt blocks, the last topoheight of the response is used for them
+    fn get_chain_response_topoheight(common_topoheight: u64, index: usize, blocks_len: usize) -> u64 {
+        common_topoheight + 1 + index.min(blocks_len.saturating_sub(1)) as u64
+    }
+
+    // Request an object needed by the sync, spread over the peers having its topoheight
+    // If a peer doesn't answer in time, it gets a strike and the object is requested from another one
+    async fn request_sync_object(&self, peer: &Arc<Peer>, request: ObjectRequest, topoheight: u64) -> Result<OwnedObjectResponse, P2pError> {
+        self.request_sync_object_from(peer, request, topoheight, &mut HashSet::new()).await
+            .map(|(_, response)| response)
+    }
+
+    // Same as request_sync_object but skip the peers already tried
+    // Returns the peer that sent the object
+    async fn request_sync_object_from(&self, peer: &Arc<Peer>, request: ObjectRequest, topoheight: u64, tried: &mut HashSet<u64>) -> Result<(Arc<Peer>, OwnedObjectResponse), P2pError> {
+        let peers: Vec<Arc<Peer>> = self.peer_list.get_cloned_peers().await.into_iter().collect();
+        let mut last_error = None;
+        for attempt in 1..=MAX_REQUEST_ATTEMPTS {
+            // The peer that sent the chain has the object, it is kept for the last attempt
+            let selected = match self.request_scheduler.select(&peers, topoheight, &tried, Instant::now()) {
+                Some(selected) if attempt < MAX_REQUEST_ATTEMPTS || tried.contains(&peer.get_id()) => selected,
+                _ if !tried.contains(&peer.get_id()) => peer,
+                _ => break
+            };
+            tried.insert(selected.get_id());
+
+            match selected.request_blocking_object_with_timeout(request.clone(), self.request_scheduler.get_timeout()).await {
+                Ok(response) => return Ok((Arc::clone(selected), response)),
+                Err(P2pError::AsyncTimeOut(e)) => {
+                    warn!("{} didn't send {} in time, requesting it from another peer", selected, request);
+                    if self.request_scheduler.add_strike(selected.get_id(), Instant::now()) {
+                        warn!("{} is deprioritized for sync after too many timed out requests", selected);
+                    }
+                    last_error = Some(P2pError::AsyncTimeOut(e));
+                },
+                Err(e) if Arc::ptr_eq(selected, peer) => return Err(e),
+                Err(e) => {
+                    debug!("Error while requesting {} from {}: {}", request, selected, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(P2pError::ObjectNotFound(request)))
+    }
+
+    // Request a block for the sync pipeline and compute its PoW hash on the blocking threads
+    // A peer sending a block below the minimum difficulty is penalized
+    // and the block is requested from another peer
+    async fn request_sync_block(&self, peer: &Arc<Peer>, hash: Hash, topoheight: u64) -> Result<(Block, Hash), BlockchainError> {
+        let minimum_difficulty = get_minimum_difficulty(self.blockchain.get_network());
+        let skip_pow = self.blockchain.is_simulator_enabled();
+        let mut tried = HashSet::new();
+        loop {
+            let (selected, response) = self.request_sync_object_from(peer, ObjectRequest::Block(hash.clone()), topoheight, &mut tried).await?;
+            let OwnedObjectResponse::Block(block, _) = response else {
+                error!("{} sent us an invalid block response", selected);
+                return Err(P2pError::ExpectedBlock.into())
+            };
+
+            let (block, res) = spawn_blocking(move || {
+                let res = block.get_pow_hash()
+                    .map_err(BlockchainError::from)
+                    .and_then(|pow_hash| {
+                        // Full difficulty depends on the tips, it is verified when the block is added
+                        if skip_pow || block.get_height() == 0 || check_difficulty(&pow_hash, &minimum_difficulty)? {
+                            Ok(pow_hash)
+                        } else {
+                            Err(BlockchainError::InvalidDifficulty)
+                        }
+                    });
+                (block, res)
+            }).await.map_err(|e| BlockchainError::Any(e.into()))?;
+
+            match res {
+                Ok(pow_hash) => return Ok((block, pow_hash)),
+                Err(e) => {
+                    warn!("{} sent us block {} with an invalid PoW, requesting it from another peer", selected, hash);
+                    selected.increase_fail_count(PEER_PENALTY_INVALID_POW);
+                    if tried.contains(&peer.get_id()) {
+                        return Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    // Download the blocks in parallel through the sync pipeline
+    // They are added to the chain one by one in the order of the chain response
+    // Returns the highest pipeline depth reached
+    async fn sync_blocks_pipeline(&self, peer: &Arc<Peer>, blocks: Vec<(Hash, u64)>) -> Result<usize, BlockchainError> {
+        let mut pipeline = SyncPipeline::new(self.sync_parallel_requests);
+        let mut requests = FuturesUnordered::new();
+        let mut blocks = blocks.into_iter().enumerate();
+        loop {
+            while pipeline.has_free_slot() {
+                let Some((index, (hash, topoheight))) = blocks.next() else {
+                    break;
+                };
+                trace!("Requesting block {} at topoheight {} (pipeline depth = {})", hash, topoheight, pipeline.depth());
+                pipeline.on_requested();
+                requests.push(async move {
+                    (index, self.request_sync_block(peer, hash, topoheight).await)
+                });
+            }
+
+            let Some((index, res)) = requests.next().await else {
+                break;
+            };
+            pipeline.on_received(index, res?);
+
+            while let Some((block, pow_hash)) = pipeline.pop_ready() {
+                trace!("Adding block {} at height {} from pipeline", block.hash(), block.get_height());
+                match self.blockchain.add_new_block_with_pow_hash(block, pow_hash, false, false).await {
+                    // It may have been propagated to us during the sync
+                    Ok(()) | Err(BlockchainError::AlreadyInChain) => {},
+                    Err(e) => return Err(e)
+                }
+            }
+        }
+
+        Ok(pipeline.get_max_depth())
+    }
+
     // select a random peer which is greater than us to sync chain
     // candidate peer should have a greater topoheight or a higher block height than us
     // It must also have a greater cumulative difficulty than us
@@ -836,6 +1198,12 @@ impl<S: Storage> P2pServer<S> {
             }
         }
 
+        // Peers that didn't answer our requests in time are only used if there is no other one
+        let now = Instant::now();
+        if peers.iter().any(|p| !self.request_scheduler.is_deprioritized(p.get_id(), now)) {
+            peers.retain(|p| !self.request_scheduler.is_deprioritized(p.get_id(), now));
+        }
+
         let count = peers.len();
         trace!("peers available for random selection: {}", count);
         if count == 0 {
@@ -894,6 +1262,12 @@ impl<S: Storage> P2pServer<S> {
                 break;
             }
 
+            // No block can be added until the storage is recovered
+            if self.blockchain.get_storage().read().await.is_quarantined() {
+                debug!("Storage is in recovery mode, skipping chain sync");
+                continue;
+            }
+
             // first we have to check if we allow fast sync mode
             // and then we check if we have a potential peer above us to fast sync
             // otherwise we sync normally 
@@ -988,6 +1362,7 @@ impl<S: Storage> P2pServer<S> {
 
                     // Is it a peer from our local network
                     let is_local_peer = is_local_address(peer.get_connection().get_address());
+                    let supports_ipv6 = peer.supports_ipv6_addresses();
 
                     // all the peers we already shared with this peer
                     let mut shared_peers = peer.get_peers().lock().await;
@@ -1009,6 +1384,11 @@ impl<S: Storage> P2pServer<S> {
                             continue;
                         }
 
+                        if addr.is_ipv6() && !supports_ipv6 {
+                            trace!("{} is an IPv6 address but {} doesn't support it, skipping", addr, peer);
+                            continue;
+                        }
+
                         let send = match shared_peers.entry(*addr) {
                             Entry::Occupied(mut e) => e.get_mut().update(Direction::Out),
                             Entry::Vacant(e) => {
@@ -1064,6 +1444,57 @@ impl<S: Storage> P2pServer<S> {
         }
     }
 
+    // Get the traffic and handshake counters since startup
+    pub fn get_network_stats(&self) -> &NetworkStats {
+        &self.network_stats
+    }
+
+    // Get the count of incoming connections dropped by the rate limiter
+    pub fn get_rate_limited_connections(&self) -> u64 {
+        self.rate_limited_connections.load(Ordering::Relaxed)
+    }
+
+    // Get the count of incoming connections rejected before the handshake
+    pub fn get_rejected_connections(&self) -> u64 {
+        self.rejected_connections.load(Ordering::Relaxed)
+    }
+
+    // Get the rate limit (connections per minute, burst) of incoming connections
+    pub fn get_connection_rate_limit(&self) -> Option<(u64, u64)> {
+        self.connection_rate_limiter.as_ref().map(|limiter| (limiter.get_rate(), limiter.get_burst()))
+    }
+
+    // Get the versions used by connected and recently seen peers
+    pub async fn get_peer_version_stats(&self) -> HashMap<String, usize> {
+        self.peer_list.get_version_stats(PEER_VERSION_STATS_RECENT_TIME).await
+    }
+
+    // Periodically log a summary of the versions used by peers
+    // and suggest to upgrade if most of them run a newer version
+    async fn version_stats_loop(self: Arc<Self>) {
+        debug!("Starting version stats task...");
+        loop {
+            sleep(Duration::from_secs(P2P_VERSION_STATS_DELAY)).await;
+            if !self.is_running() {
+                debug!("Version stats loop task is stopped!");
+                break;
+            }
+
+            let stats = self.get_peer_version_stats().await;
+            if stats.is_empty() {
+                continue;
+            }
+
+            let mut summary: Vec<(&String, &usize)> = stats.iter().collect();
+            summary.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            info!("Peer versions: {}", summary.iter().map(|(version, count)| format!("{} ({})", version, count)).collect::<Vec<_>>().join(", "));
+
+            if let Some(version) = self.upgrade_nudge.check(VERSION, &stats) {
+                warn!("Most of your peers are running version {} while you are running {}, consider upgrading your node", version, VERSION);
+            }
+        }
+    }
+
     // try to extend our peerlist each time its possible by searching in known peerlist from disk
     async fn peerlist_loop(self: Arc<Self>) {
         debug!("Starting peerlist task...");
@@ -1074,10 +1505,10 @@ impl<S: Storage> P2pServer<S> {
                 break;
             }
 
-            if self.accept_new_connections().await {
+            if self.accept_new_connections(false, true).await {
                 let peer = {
                     trace!("Locking peer list write mode (peerlist loop)");
-                    self.peer_list.find_peer_to_connect().await
+                    self.peer_list.find_peer_to_connect(self.get_bind_address().is_ipv6()).await
                 };
                 trace!("End locking peer list write mode (peerlist loop)");
 
@@ -1105,6 +1536,7 @@ impl<S: Storage> P2pServer<S> {
                 },
                 peer = receiver.recv() => {
                     if let Some(peer) = peer {
+                        self.request_scheduler.remove_peer(peer.get_id());
                         if peer.sharable() {
                             if let Some(rpc) = self.blockchain.get_rpc().read().await.as_ref() {
                                 if rpc.is_event_tracked(&NotifyEvent::PeerDisconnected).await {
@@ -1182,7 +1614,7 @@ impl<S: Storage> P2pServer<S> {
                         Ok(block) => block,
                         Err(e) => {
                             error!("Error while building block {} from peer {}: {}", block_hash, peer, e);
-                            peer.increment_fail_count();
+                            peer.increase_fail_count(P2pError::from(e).penalty());
                             continue;
                         }
                     };
@@ -1190,7 +1622,7 @@ impl<S: Storage> P2pServer<S> {
                     debug!("Adding received block {} from {} to chain", block_hash, peer);
                     if let Err(e) = self.blockchain.add_new_block(block, true, false).await {
                         error!("Error while adding new block from {}: {}", peer, e);
-                        peer.increment_fail_count();
+                        peer.increase_fail_count(P2pError::from(e).penalty());
                     }
                 }
             }
@@ -1481,6 +1913,7 @@ impl<S: Storage> P2pServer<S> {
                         return Ok(())
                     }
                 }
+                peer.update_last_useful_data();
 
                 // Check that we are not already waiting on it
                 {
@@ -1539,6 +1972,7 @@ impl<S: Storage> P2pServer<S> {
                     .lock().await
                     .take()
                     .ok_or(P2pError::UnrequestedChainResponse)?;
+                peer.update_last_useful_data();
 
                 if sender.send(response).is_err() {
                     error!("Error while sending chain response to channel of {}", peer);
@@ -1641,6 +2075,7 @@ impl<S: Storage> P2pServer<S> {
                 trace!("Received a object response from {}", peer);
                 let response = response.to_owned();
                 trace!("Object response received is {}", response.get_hash());
+                let found = !matches!(response, ObjectResponse::NotFound(_));
 
                 // check if we requested it from this peer
                 let request = response.get_request();
@@ -1659,6 +2094,10 @@ impl<S: Storage> P2pServer<S> {
                 } else {
                     return Err(P2pError::ObjectNotRequested(request))
                 }
+
+                if found {
+                    peer.update_last_useful_data();
+                }
             },
             Packet::NotifyInventoryRequest(packet_wrapper) => {
                 trace!("Received a inventory request from {}", peer);
@@ -1751,6 +2190,10 @@ impl<S: Storage> P2pServer<S> {
                     return Err(P2pError::UnrequestedBootstrapChainResponse)
                 }
             },
+            Packet::Disconnect(reason) => {
+                debug!("{} is closing the connection: {}", peer, reason);
+                return Err(P2pError::DisconnectedWithReason(reason))
+            },
             Packet::PeerDisconnected(packet) => {
                 // This packet is used to keep sync between peers being shared
                 let addr = packet.to_addr();
@@ -1788,13 +2231,34 @@ impl<S: Storage> P2pServer<S> {
     // Packet is read from the same task always, while its handling is delegated to a unique task
     async fn listen_connection(self: &Arc<Self>, buf: &mut [u8], peer: &Arc<Peer>) -> Result<(), P2pError> {
         // Read & parse the packet
-        // 16 additional bytes are for AEAD
-        let packet = peer.get_connection().read_packet(buf, PEER_MAX_PACKET_SIZE).await?;
+        // An oversized message returns an error which closes the connection
+        let bytes = match peer.get_connection().read_packet_bytes(buf, self.max_message_size).await {
+            Ok(bytes) => bytes,
+            Err(e @ P2pError::DecompressedPacketTooLarge(..)) => {
+                // Same protection as for an oversized message, but it may be a zip bomb
+                warn!("{} sent a compressed packet too large: {}", peer, e);
+                if let Err(err) = peer.close_and_temp_ban().await {
+                    error!("Error while temp banning {}: {}", peer, err);
+                }
+                return Err(e)
+            },
+            Err(e) => return Err(e)
+        };
+        let packet = match peer.get_connection().read_packet_from_bytes(&bytes).await {
+            Ok(packet) => packet,
+            Err(e) => {
+                // Packet was fully read, the connection is still usable
+                // but the peer is penalized for sending invalid data
+                error!("Invalid packet received from {}: {}", peer, e);
+                peer.increase_fail_count(e.penalty());
+                return Ok(())
+            }
+        };
         let packet_id = packet.get_id();
         // Handle the packet
         if let Err(e) = self.handle_incoming_packet(&peer, packet).await {
             match e {
-                P2pError::Disconnected => {
+                P2pError::Disconnected | P2pError::DisconnectedWithReason(_) => {
                     debug!("Peer {} has disconnected, stopping...", peer);
                     return Err(e)
                 },
@@ -1804,7 +2268,7 @@ impl<S: Storage> P2pServer<S> {
                 },
                 e => {
                     error!("Error occured while handling incoming packet #{} from {}: {}", packet_id, peer, e);
-                    peer.increment_fail_count();
+                    peer.increase_fail_count(e.penalty());
                 }
             }
         }
@@ -2024,7 +2488,7 @@ impl<S: Storage> P2pServer<S> {
                 // Starting topoheight must be the next topoheight after common block
                 // Blocks in chain response must be ordered by topoheight otherwise it will give incorrect results 
                 let mut chain_validator = ChainValidator::new(&self.blockchain, common_topoheight + 1);
-                for hash in blocks {
+                for (i, hash) in blocks.into_iter().enumerate() {
                     trace!("Request block header for chain validator: {}", hash);
 
                     // check if we already have the block to not request it
@@ -2033,7 +2497,8 @@ impl<S: Storage> P2pServer<S> {
                         continue;
                     }
 
-                    let response = peer.request_blocking_object(ObjectRequest::BlockHeader(hash)).await?;
+                    let topoheight = Self::get_chain_response_topoheight(common_topoheight, i, blocks_len);
+                    let response = self.request_sync_object(peer, ObjectRequest::BlockHeader(hash), topoheight).await?;
                     if let OwnedObjectResponse::BlockHeader(header, hash) = response {
                         trace!("Received {} with hash {}", header, hash);
                         chain_validator.insert_block(hash, header).await?;
@@ -2055,7 +2520,7 @@ impl<S: Storage> P2pServer<S> {
                 self.blockchain.rewind_chain(pop_count, false).await?;
 
                 // now retrieve all txs from all blocks header and add block in chain
-                for (hash, header) in chain_validator.get_blocks() {
+                for (i, (hash, header)) in chain_validator.get_blocks().enumerate() {
                     trace!("Processing block {} from chain validator", hash);
                     // we don't already have this block, lets retrieve its txs and add in our chain
                     if !self.blockchain.has_block(&hash).await? {
@@ -2068,7 +2533,8 @@ impl<S: Storage> P2pServer<S> {
                                 trace!("Found the transaction {} on disk", tx_hash);
                                 transactions.push(Immutable::Arc(tx));
                             } else { // otherwise, ask it from peer
-                                let response = peer.request_blocking_object(ObjectRequest::Transaction(tx_hash.clone())).await?;
+                                let topoheight = Self::get_chain_response_topoheight(common_topoheight, i, blocks_len);
+                                let response = self.request_sync_object(peer, ObjectRequest::Transaction(tx_hash.clone()), topoheight).await?;
                                 if let OwnedObjectResponse::Transaction(tx, _) = response {
                                     trace!("Received transaction {} at block {} from {}", tx_hash, hash, peer);
                                     transactions.push(Immutable::Owned(tx));
@@ -2099,12 +2565,17 @@ impl<S: Storage> P2pServer<S> {
                 (None, None)
             };
 
+            // Blocks to download through the pipeline when boost sync is disabled
+            let mut pipeline_blocks = Vec::new();
             // Peekable is here to help to know if we are at the last element
             // so we create only one channel for the last blocker
-            let mut blocks_iter = blocks.into_iter().peekable();
-            while let Some(hash) = blocks_iter.next() {
+            let mut blocks_iter = blocks.into_iter().enumerate().peekable();
+            // Peers used to spread the requests
+            let peers: Vec<Arc<Peer>> = self.peer_list.get_cloned_peers().await.into_iter().collect();
+            while let Some((i, hash)) = blocks_iter.next() {
                 if !self.blockchain.has_block(&hash).await? {
                     trace!("Block {} is not found, asking it to {} (index = {})", hash, peer.get_outgoing_address(), total_requested);
+                    let topoheight = Self::get_chain_response_topoheight(common_topoheight, i, blocks_len);
                     // if it's allowed by the user, request all blocks in parallel
                     if self.allow_boost_sync() {
                         if let Some(notifier) = &mut notifier {
@@ -2116,19 +2587,13 @@ impl<S: Storage> P2pServer<S> {
                         }
 
                         let is_last = blocks_iter.peek().is_none();
-                        if let Some(blocker) = self.object_tracker.request_object_from_peer_with(Arc::clone(peer), ObjectRequest::Block(hash.clone()), group_id, is_last, is_last).await? {
+                        let selected = self.request_scheduler.select(&peers, topoheight, &HashSet::new(), Instant::now()).unwrap_or(peer);
+                        if let Some(blocker) = self.object_tracker.request_object_from_peer_with(Arc::clone(selected), ObjectRequest::Block(hash.clone()), group_id, is_last, is_last, Some(topoheight)).await? {
                             final_blocker = Some(blocker);
                         }
                     } else {
-                        // Otherwise, request them one by one and wait for the response
-                        let response = peer.request_blocking_object(ObjectRequest::Block(hash)).await?;
-                        if let OwnedObjectResponse::Block(block, hash) = response {
-                            trace!("Received block {} at height {} from {}", hash, block.get_height(), peer);
-                            self.blockchain.add_new_block(block, false, false).await?;
-                        } else {
-                            error!("{} sent us an invalid block response", peer);
-                            return Err(P2pError::ExpectedBlock.into())
-                        }
+                        // Otherwise, request them through the pipeline
+                        pipeline_blocks.push((hash, topoheight));
                     }
                     total_requested += 1;
                 } else {
@@ -2136,6 +2601,12 @@ impl<S: Storage> P2pServer<S> {
                 }
             }
 
+            let pipeline_depth = if pipeline_blocks.is_empty() {
+                0
+            } else {
+                self.sync_blocks_pipeline(peer, pipeline_blocks).await?
+            };
+
             if let (Some(mut notifier), Some(mut blocker)) = (notifier, final_blocker) {
                 debug!("Waiting for final blocker to finish...");
                 select! {
@@ -2160,7 +2631,7 @@ impl<S: Storage> P2pServer<S> {
                     }
                 }
             }
-            info!("we've synced {} on {} blocks and {} top blocks from {}", total_requested, blocks_len, top_len, peer);
+            info!("we've synced {} on {} blocks and {} top blocks from {} (pipeline depth: {}/{})", total_requested, blocks_len, top_len, peer, pipeline_depth, self.sync_parallel_requests);
         }
 
         let peer_topoheight = peer.get_topoheight();
@@ -2202,7 +2673,18 @@ impl<S: Storage> P2pServer<S> {
 
     // Get the maximum peers count allowed to be connected
     pub fn get_max_peers(&self) -> usize {
-        self.max_peers
+        self.peer_slots.get_limits().max_peers
+    }
+
+    // Get the connection limits
+    pub fn get_peer_limits(&self) -> PeerLimits {
+        self.peer_slots.get_limits()
+    }
+
+    // Update the connection limits, connected peers above them are kept
+    pub fn set_peer_limits(&self, limits: PeerLimits) -> Result<(), P2pError> {
+        info!("Setting max peers to {} ({} outgoing, {} reserved to priority peers)", limits.max_peers, limits.max_outgoing_peers, limits.priority_slots);
+        self.peer_slots.set_limits(limits)
     }
 
     // Get our unique peer ID
@@ -2210,9 +2692,9 @@ impl<S: Storage> P2pServer<S> {
         self.peer_id
     }
 
-    // Check if we are accepting new connections by verifying if we have free slots available
-    pub async fn accept_new_connections(&self) -> bool {
-        self.get_peer_count().await < self.get_max_peers()
+    // Check if we are accepting a new connection by verifying if we have a free slot available for it
+    pub async fn accept_new_connections(&self, priority: bool, out: bool) -> bool {
+        self.peer_slots.can_accept(&self.peer_list.get_slots_usage().await, priority, out)
     }
 
     // Returns the count of peers connected
@@ -2242,7 +2724,7 @@ impl<S: Storage> P2pServer<S> {
 
     // Check if we are already connected to a socket address (IPv4 or IPv6) including its port
     pub async fn is_connected_to_addr(&self, peer_addr: &SocketAddr) -> bool {
-        if *peer_addr == *self.get_bind_address() { // don't try to connect to ourself
+        if self.bind_addresses.contains(peer_addr) { // don't try to connect to ourself
             debug!("Trying to connect to ourself, ignoring.");
             return true
         }
@@ -2250,9 +2732,14 @@ impl<S: Storage> P2pServer<S> {
         self.peer_list.is_connected_to_addr(peer_addr).await
     }
 
-    // get the socket address on which we are listening
+    // get the main socket address on which we are listening
     pub fn get_bind_address(&self) -> &SocketAddr {
-        &self.bind_address
+        &self.bind_addresses[0]
+    }
+
+    // get all the socket addresses on which we are listening
+    pub fn get_bind_addresses(&self) -> &Vec<SocketAddr> {
+        &self.bind_addresses
     }
 
     // Get our peerlist
@@ -2395,7 +2882,14 @@ impl<S: Storage> P2pServer<S> {
                 }
 
                 let page = page.unwrap_or(0);
-                let assets = storage.get_partial_assets(MAX_ITEMS_PER_PAGE, page as usize * MAX_ITEMS_PER_PAGE, min, max).await?;
+                // Metadata is registered locally and older peers can't read it
+                let assets: IndexSet<AssetWithData> = storage.get_partial_assets(MAX_ITEMS_PER_PAGE, page as usize * MAX_ITEMS_PER_PAGE, min, max).await?
+                    .into_iter()
+                    .map(|asset| {
+                        let (hash, data) = asset.consume();
+                        AssetWithData::new(hash, AssetData::new(data.get_topoheight(), data.get_decimals()))
+                    })
+                    .collect();
                 let page = if assets.len() == MAX_ITEMS_PER_PAGE {
                     Some(page + 1)
                 } else {
@@ -2567,6 +3061,12 @@ impl<S: Storage> P2pServer<S> {
                     let mut storage = self.blockchain.get_storage().write().await;
                     for asset in assets {
                         let (asset, data) = asset.consume();
+                        // Keep the local data, it may contain the metadata
+                        if storage.has_asset(&asset).await? {
+                            debug!("Asset {} is already registered", asset);
+                            continue;
+                        }
+
                         debug!("Saving asset {} at topoheight {}", asset, stable_topoheight);
                         storage.add_asset(&asset, data).await?;
                     }
@@ -2776,6 +3276,60 @@ impl<S: Storage> P2pServer<S> {
         self.handle_chain_response(peer, response, requested_max_size).await
     }
 
+    // Download again the corrupted block headers and transactions from our peers
+    // Each object is verified against its hash before being written
+    // Returns the number of entries repaired and the ones that couldn't be
+    pub async fn repair_corrupted_entries(&self) -> Result<(usize, usize), BlockchainError> {
+        let peers = self.peer_list.get_cloned_peers().await;
+        let mut requests = Vec::new();
+        {
+            let storage = self.blockchain.get_storage().read().await;
+            for key in storage.get_corrupted_keys("blocks") {
+                requests.push(ObjectRequest::BlockHeader(Hash::from_bytes(&key)?));
+            }
+            for key in storage.get_corrupted_keys("transactions") {
+                requests.push(ObjectRequest::Transaction(Hash::from_bytes(&key)?));
+            }
+        }
+
+        let mut repaired = 0;
+        for request in requests.iter() {
+            let hash = request.get_hash();
+            let mut value = None;
+            for peer in peers.iter() {
+                let bytes = match peer.request_blocking_object(request.clone()).await {
+                    Ok(OwnedObjectResponse::BlockHeader(header, _)) if header.hash() == *hash => header.to_bytes(),
+                    Ok(OwnedObjectResponse::Transaction(tx, _)) if tx.hash() == *hash => tx.to_bytes(),
+                    Ok(_) => {
+                        debug!("{} sent an invalid response for {}", peer, request);
+                        continue;
+                    },
+                    Err(e) => {
+                        debug!("Error while requesting {} from {}: {}", request, peer, e);
+                        continue;
+                    }
+                };
+                value = Some(bytes);
+                break;
+            }
+
+            match value {
+                Some(bytes) => {
+                    let tree = match request {
+                        ObjectRequest::Transaction(_) => "transactions",
+                        _ => "blocks"
+                    };
+                    let mut storage = self.blockchain.get_storage().write().await;
+                    storage.repair_entry(tree, hash.as_bytes(), &bytes).await?;
+                    repaired += 1;
+                },
+                None => warn!("No peer could send a valid {}", request)
+            }
+        }
+
+        Ok((repaired, requests.len() - repaired))
+    }
+
     // Clear all p2p connections by kicking peers
     pub async fn clear_connections(&self) {
         self.peer_list.close_all().await;
@@ -2791,8 +3345,202 @@ pub fn is_local_address(socket_addr: &SocketAddr) -> bool {
         }
         IpAddr::V6(ipv6) => {
             // Check if it's a local IPv6 address (e.g., ::1)
+            // is_unique_local and is_unicast_link_local are not stable yet
             // https://github.com/rust-lang/rust/issues/27709
-            ipv6.is_loopback() // || ipv6.is_unique_local()
+            let segment = ipv6.segments()[0];
+            ipv6.is_loopback() || (segment & 0xfe00) == 0xfc00 || (segment & 0xffc0) == 0xfe80
         }
     }
-}
\ No newline at end of file
+}
+
+// A dual-stack listener reports IPv4 clients as IPv4-mapped IPv6 addresses (::ffff:a.b.c.d)
+// Convert them back so the same peer has the same address on both stacks
+pub fn to_canonical_address(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => SocketAddr::new(IpAddr::V4(ipv4), addr.port()),
+            None => addr
+        },
+        IpAddr::V4(_) => addr
+    }
+}
+
+// Bind a TCP listener on the address
+// only_v6 disables the IPv4 connections on an IPv6 socket
+fn bind_listener(addr: &SocketAddr, only_v6: bool) -> Result<TcpListener, P2pError> {
+    let domain = if addr.is_ipv6() { Domain::IPV6 } else { Domain::IPV4 };
+    let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(only_v6)?;
+    }
+    // Same behavior as the tokio listener
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&(*addr).into())?;
+    socket.listen(1024)?;
+    Ok(TcpListener::from_std(socket.into())?)
+}
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+    use rand::Rng;
+    use crate::core::testing::{start_blockchain, test_config};
+
+    #[tokio::test]
+    async fn test_full_peer_list_rejected_before_handshake() {
+        use tokio::net::TcpStream;
+        use super::{
+            connection::Connection,
+            packet::{disconnect::DisconnectReason, Packet},
+            stats::NetworkStats
+        };
+
+        let id = rand::thread_rng().gen::<u64>();
+        let free_address = || format!("127.0.0.1:{}", std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port());
+        let temp_dir = |name: &str| {
+            let dir = std::env::temp_dir().join(format!("xelis-full-{}-{}", name, id));
+            std::fs::create_dir_all(&dir).unwrap();
+            format!("{}/", dir.to_str().unwrap())
+        };
+
+        // Only one slot available
+        let first_address = free_address();
+        let first_dir = temp_dir("first");
+        let first = start_blockchain(test_config(&["--disable-rpc-server", "--disable-p2p-outgoing-connections", "--p2p-bind-address", &first_address, "--dir-path", &first_dir, "--max-peers", "1", "--priority-peer-slots", "0"])).await;
+
+        // Second node sharing the same genesis block takes it
+        let path = format!("{}genesis.bin", first_dir);
+        first.export_snapshot(&path, 0).await.unwrap();
+        let second_address = free_address();
+        let second_dir = temp_dir("second");
+        let second = start_blockchain(test_config(&["--disable-rpc-server", "--p2p-bind-address", &second_address, "--dir-path", &second_dir, "--exclusive-nodes", &first_address, "--import-snapshot", &path])).await;
+
+        let p2p = first.get_p2p().read().await.clone().unwrap();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while p2p.get_peer_count().await == 0 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await.unwrap();
+
+        // Refused right after the key exchange, no handshake is expected from us
+        let stream = TcpStream::connect(&first_address).await.unwrap();
+        let mut connection = Connection::new(stream, first_address.parse().unwrap(), true, Arc::new(NetworkStats::new()));
+        let mut buffer = [0u8; 512];
+        connection.exchange_keys(&mut buffer).await.unwrap();
+        let packet = tokio::time::timeout(Duration::from_secs(5), connection.read_packet(&mut buffer, 512)).await.unwrap().unwrap();
+        assert!(matches!(packet, Packet::Disconnect(DisconnectReason::PeerListFull)));
+        assert_eq!(p2p.get_peer_count().await, 1);
+
+        second.stop().await;
+        first.stop().await;
+        std::fs::remove_dir_all(first_dir).unwrap();
+        std::fs::remove_dir_all(second_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_drops_peer() {
+        use tokio::net::TcpStream;
+        use super::{
+            connection::Connection,
+            packet::Packet,
+            stats::NetworkStats
+        };
+
+        let id = rand::thread_rng().gen::<u64>();
+        let free_address = || format!("127.0.0.1:{}", std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port());
+        let temp_dir = |name: &str| {
+            let dir = std::env::temp_dir().join(format!("xelis-oversized-{}-{}", name, id));
+            std::fs::create_dir_all(&dir).unwrap();
+            format!("{}/", dir.to_str().unwrap())
+        };
+
+        // Limit lowered below the max block size
+        let first_address = free_address();
+        let first_dir = temp_dir("first");
+        let first = start_blockchain(test_config(&["--disable-rpc-server", "--disable-p2p-outgoing-connections", "--p2p-bind-address", &first_address, "--dir-path", &first_dir, "--p2p-max-message-size", "65536"])).await;
+
+        // Second node sharing the same genesis block, only used to build a valid handshake
+        let path = format!("{}genesis.bin", first_dir);
+        first.export_snapshot(&path, 0).await.unwrap();
+        let second_dir = temp_dir("second");
+        let second = start_blockchain(test_config(&["--disable-rpc-server", "--disable-p2p-outgoing-connections", "--p2p-bind-address", &free_address(), "--dir-path", &second_dir, "--import-snapshot", &path])).await;
+        let handshake = second.get_p2p().read().await.clone().unwrap().build_handshake().await.unwrap();
+
+        let stream = TcpStream::connect(&first_address).await.unwrap();
+        let mut connection = Connection::new(stream, first_address.parse().unwrap(), true, Arc::new(NetworkStats::new()));
+        let mut buffer = [0u8; 4096];
+        connection.exchange_keys(&mut buffer).await.unwrap();
+        connection.send_bytes(&handshake).await.unwrap();
+        let packet = tokio::time::timeout(Duration::from_secs(5), connection.read_packet(&mut buffer, 4096)).await.unwrap().unwrap();
+        assert!(matches!(packet, Packet::Handshake(_)));
+
+        let p2p = first.get_p2p().read().await.clone().unwrap();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while p2p.get_peer_count().await == 0 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await.unwrap();
+
+        // Above the limit, the peer is removed from the peer list
+        let payload: Vec<u8> = (0..70_000).map(|_| rand::thread_rng().gen()).collect();
+        connection.send_bytes(&payload).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while p2p.get_peer_count().await != 0 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await.unwrap();
+
+        second.stop().await;
+        first.stop().await;
+        std::fs::remove_dir_all(first_dir).unwrap();
+        std::fs::remove_dir_all(second_dir).unwrap();
+    }
+
+    // Mines real blocks at the devnet difficulty, run it with --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_sync_parallel_requests() {
+        use xelis_common::crypto::KeyPair;
+
+        let id = rand::thread_rng().gen::<u64>();
+        let free_address = || format!("127.0.0.1:{}", std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port());
+        let temp_dir = |name: &str| {
+            let dir = std::env::temp_dir().join(format!("xelis-sync-{}-{}", name, id));
+            std::fs::create_dir_all(&dir).unwrap();
+            format!("{}/", dir.to_str().unwrap())
+        };
+
+        let first_address = free_address();
+        let first_dir = temp_dir("first");
+        let first = start_blockchain(test_config(&["--disable-rpc-server", "--disable-p2p-outgoing-connections", "--p2p-bind-address", &first_address, "--dir-path", &first_dir])).await;
+
+        // Second node sharing the same genesis block
+        let path = format!("{}genesis.bin", first_dir);
+        first.export_snapshot(&path, 0).await.unwrap();
+
+        let key = KeyPair::new().get_public_key().compress();
+        for _ in 0..32 {
+            let block = first.mine_block(&key).await.unwrap();
+            first.add_new_block(block, false, false).await.unwrap();
+        }
+        let topoheight = first.get_topo_height();
+
+        let second_address = free_address();
+        let second_dir = temp_dir("second");
+        let second = start_blockchain(test_config(&["--disable-rpc-server", "--p2p-bind-address", &second_address, "--dir-path", &second_dir, "--exclusive-nodes", &first_address, "--import-snapshot", &path, "--sync-parallel-requests", "4"])).await;
+
+        let synced = tokio::time::timeout(Duration::from_secs(60), async {
+            while second.get_topo_height() < topoheight {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await;
+        assert!(synced.is_ok(), "second node is at topoheight {} instead of {}", second.get_topo_height(), topoheight);
+        assert_eq!(second.get_top_block_hash().await.unwrap(), first.get_top_block_hash().await.unwrap());
+
+        second.stop().await;
+        first.stop().await;
+        std::fs::remove_dir_all(first_dir).unwrap();
+        std::fs::remove_dir_all(second_dir).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt::{self, Display, Formatter};
+
+use xelis_common::serializer::{Serializer, Reader, ReaderError, Writer};
+
+// Reason sent to a peer right before closing the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    // We don't have any free slot for a new peer
+    PeerListFull,
+    // Peer was disconnected to free a slot for a better peer
+    Evicted
+}
+
+impl Serializer for DisconnectReason {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(match reader.read_u8()? {
+            0 => Self::PeerListFull,
+            1 => Self::Evicted,
+            _ => return Err(ReaderError::InvalidValue)
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u8(match self {
+            Self::PeerListFull => 0,
+            Self::Evicted => 1
+        });
+    }
+
+    fn size(&self) -> usize {
+        1
+    }
+}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PeerListFull => write!(f, "peer list is full"),
+            Self::Evicted => write!(f, "evicted for a better peer")
+        }
+    }
+}
@@ -52,13 +52,49 @@ pub struct Handshake<'a> {
     cumulative_difficulty: Cow<'a, CumulativeDifficulty>,
     // By default it's true, and peer allow to be shared to others and/or through API
     // If false, we must not share it
-    can_be_shared: bool
+    can_be_shared: bool,
+    // Optional features supported by the node, see FEATURE_* flags
+    // Missing in the handshake of older nodes
+    features: u8
 } // Server reply with his own list of peers, but we remove all already known by requester for the response.
 
+// Feature flags sent in the handshake
+// The node supports the compression
+pub const FEATURE_COMPRESSION: u8 = 1 << 0;
+// The node accepts IPv6 addresses in the shared peer list
+pub const FEATURE_IPV6_ADDRESSES: u8 = 1 << 1;
+
+// Characters allowed in a node tag
+fn is_tag_char(c: char) -> bool {
+    c.is_ascii_graphic() || c == ' '
+}
+
 impl<'a> Handshake<'a> {
     pub const MAX_LEN: usize = 16;
 
-    pub fn new(version: Cow<'a, String>, network: Network, node_tag: Cow<'a, Option<String>>, network_id: Cow<'a, [u8; 16]>, peer_id: u64, local_port: u16, utc_time: TimestampSeconds, topoheight: u64, height: u64, pruned_topoheight: Option<u64>, top_hash: Cow<'a, Hash>, genesis_hash: Cow<'a, Hash>, cumulative_difficulty: Cow<'a, CumulativeDifficulty>, can_be_shared: bool) -> Self {
+    // A tag is valid if it's not empty, not longer than MAX_LEN
+    // and only contains printable ASCII characters
+    pub fn is_valid_tag(tag: &str) -> bool {
+        !tag.is_empty() && tag.len() <= Self::MAX_LEN && tag.chars().all(is_tag_char)
+    }
+
+    // Remove all the characters that can't be displayed safely from a received tag
+    // Returns None if nothing is left
+    fn sanitize_tag(tag: String) -> Option<String> {
+        if tag.chars().all(is_tag_char) {
+            return Some(tag)
+        }
+
+        let sanitized = tag.chars().filter(|c| is_tag_char(*c)).collect::<String>();
+        let sanitized = sanitized.trim();
+        if sanitized.is_empty() {
+            None
+        } else {
+            Some(sanitized.to_owned())
+        }
+    }
+
+    pub fn new(version: Cow<'a, String>, network: Network, node_tag: Cow<'a, Option<String>>, network_id: Cow<'a, [u8; 16]>, peer_id: u64, local_port: u16, utc_time: TimestampSeconds, topoheight: u64, height: u64, pruned_topoheight: Option<u64>, top_hash: Cow<'a, Hash>, genesis_hash: Cow<'a, Hash>, cumulative_difficulty: Cow<'a, CumulativeDifficulty>, can_be_shared: bool, features: u8) -> Self {
         debug_assert!(version.len() > 0 && version.len() <= Handshake::MAX_LEN);
         // version cannot be greater than 16 chars
         if let Some(node_tag) = node_tag.as_ref() {
@@ -80,14 +116,15 @@ impl<'a> Handshake<'a> {
             top_hash,
             genesis_hash,
             cumulative_difficulty,
-            can_be_shared
+            can_be_shared,
+            features
         }
     }
 
     // Create a new peer using its connection and this handshake packet
     pub fn create_peer(self, connection: Connection, priority: bool, peer_list: SharedPeerList) -> (Peer, Rx) {
         let peers = HashSet::new();
-        Peer::new(connection, self.get_peer_id(), self.node_tag.into_owned(), self.local_port, self.version.into_owned(), self.top_hash.into_owned(), self.topoheight, self.height, self.pruned_topoheight, priority, self.cumulative_difficulty.into_owned(), peer_list, peers, self.can_be_shared)
+        Peer::new(connection, self.get_peer_id(), self.node_tag.into_owned(), self.local_port, self.version.into_owned(), self.top_hash.into_owned(), self.topoheight, self.height, self.pruned_topoheight, priority, self.cumulative_difficulty.into_owned(), peer_list, peers, self.can_be_shared, self.supports_ipv6_addresses())
     }
 
     pub fn get_version(&self) -> &String {
@@ -133,6 +170,14 @@ impl<'a> Handshake<'a> {
     pub fn get_pruned_topoheight(&self) -> &Option<u64> {
         &self.pruned_topoheight
     }
+
+    pub fn supports_compression(&self) -> bool {
+        self.features & FEATURE_COMPRESSION != 0
+    }
+
+    pub fn supports_ipv6_addresses(&self) -> bool {
+        self.features & FEATURE_IPV6_ADDRESSES != 0
+    }
 }
 
 impl Serializer for Handshake<'_> {
@@ -158,6 +203,7 @@ impl Serializer for Handshake<'_> {
         writer.write_hash(&self.genesis_hash); // Genesis Hash
         self.cumulative_difficulty.write(writer); // Cumulative Difficulty
         writer.write_bool(self.can_be_shared); // Can be shared
+        writer.write_u8(self.features); // Features
     }
 
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
@@ -175,12 +221,15 @@ impl Serializer for Handshake<'_> {
         let network = Network::read(reader)?;
 
         // Node Tag
-        let node_tag = reader.read_optional_string()?;
-        if let Some(tag) = &node_tag {
-            if tag.len() > Handshake::MAX_LEN {
-                return Err(ReaderError::InvalidSize)
-            }
-        }
+        let node_tag = match reader.read_optional_string()? {
+            Some(tag) => {
+                if tag.len() > Handshake::MAX_LEN {
+                    return Err(ReaderError::InvalidSize)
+                }
+                Handshake::sanitize_tag(tag)
+            },
+            None => None
+        };
 
         let network_id: [u8; 16] = reader.read_bytes(16)?;
         let peer_id = reader.read_u64()?;
@@ -199,8 +248,13 @@ impl Serializer for Handshake<'_> {
         let genesis_hash = reader.read_hash()?;
         let cumulative_difficulty = CumulativeDifficulty::read(reader)?;
         let can_be_shared = reader.read_bool()?;
+        let features = if reader.remaining() > 0 {
+            reader.read_u8()?
+        } else {
+            0
+        };
 
-        Ok(Handshake::new(Cow::Owned(version), network, Cow::Owned(node_tag), Cow::Owned(network_id), peer_id, local_port, utc_time, topoheight, height, pruned_topoheight, Cow::Owned(top_hash), Cow::Owned(genesis_hash), Cow::Owned(cumulative_difficulty), can_be_shared))
+        Ok(Handshake::new(Cow::Owned(version), network, Cow::Owned(node_tag), Cow::Owned(network_id), peer_id, local_port, utc_time, topoheight, height, pruned_topoheight, Cow::Owned(top_hash), Cow::Owned(genesis_hash), Cow::Owned(cumulative_difficulty), can_be_shared, features))
     }
 
     fn size(&self) -> usize {
@@ -231,7 +285,9 @@ impl Serializer for Handshake<'_> {
         // Cumulative Difficulty
         self.cumulative_difficulty.size() +
         // Can be shared
-        self.can_be_shared.size()
+        self.can_be_shared.size() +
+        // Features
+        self.features.size()
     }
 }
 
@@ -246,4 +302,41 @@ impl Display for Handshake<'_> {
         };
         write!(f, "Handshake[version: {}, node tag: {}, network_id: {}, peer_id: {}, utc_time: {}, block_height: {}, block_top_hash: {}]", self.get_version(), node_tag, hex::encode(self.get_network_id()), self.get_peer_id(), self.get_utc_time(), self.get_block_height(), self.get_block_top_hash())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_tag() {
+        assert!(Handshake::is_valid_tag("my node #1"));
+        assert!(!Handshake::is_valid_tag(""));
+        assert!(!Handshake::is_valid_tag("this tag is way too long"));
+        assert!(!Handshake::is_valid_tag("node\n"));
+        assert!(!Handshake::is_valid_tag("nœud"));
+    }
+
+    #[test]
+    fn test_sanitize_tag() {
+        assert_eq!(Handshake::sanitize_tag("node".to_owned()), Some("node".to_owned()));
+        assert_eq!(Handshake::sanitize_tag("\x1b[31mred\x1b".to_owned()), Some("[31mred".to_owned()));
+        assert_eq!(Handshake::sanitize_tag(" \n\t".to_owned()), None);
+    }
+
+    #[test]
+    fn test_features() {
+        let version = "1.0.0".to_owned();
+        let cumulative_difficulty = CumulativeDifficulty::zero();
+        let handshake = Handshake::new(Cow::Borrowed(&version), Network::Mainnet, Cow::Owned(None), Cow::Owned([0u8; 16]), 1, 2125, 0, 0, 0, None, Cow::Owned(Hash::zero()), Cow::Owned(Hash::zero()), Cow::Borrowed(&cumulative_difficulty), true, FEATURE_COMPRESSION | FEATURE_IPV6_ADDRESSES);
+        let bytes = handshake.to_bytes();
+        assert_eq!(bytes.len(), handshake.size());
+        let decoded = Handshake::from_bytes(&bytes).unwrap();
+        assert!(decoded.supports_compression());
+        assert!(decoded.supports_ipv6_addresses());
+
+        // Handshake of a node without the features byte
+        let previous = Handshake::from_bytes(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(!previous.supports_compression());
+        assert!(!previous.supports_ipv6_addresses());
+    }
+}
@@ -5,6 +5,7 @@ pub mod object;
 pub mod inventory;
 pub mod bootstrap_chain;
 pub mod peer_disconnected;
+pub mod disconnect;
 
 use self::bootstrap_chain::{BootstrapChainRequest, BootstrapChainResponse};
 use self::inventory::{NotifyInventoryResponse, NotifyInventoryRequest};
@@ -12,6 +13,7 @@ use self::object::{ObjectRequest, ObjectResponse};
 use self::chain::{ChainRequest, ChainResponse};
 use self::handshake::Handshake;
 use self::peer_disconnected::PacketPeerDisconnected;
+use self::disconnect::DisconnectReason;
 use self::ping::Ping;
 use std::borrow::Cow;
 use log::{debug, trace};
@@ -38,6 +40,7 @@ const NOTIFY_INV_RESPONSE_ID: u8 = 10;
 const BOOTSTRAP_CHAIN_REQUEST_ID: u8 = 11;
 const BOOTSTRAP_CHAIN_RESPONSE_ID: u8 = 12;
 const PEER_DISCONNECTED_ID: u8 = 13;
+const DISCONNECT_ID: u8 = 14;
 
 // PacketWrapper allows us to link any Packet to a Ping
 #[derive(Debug)]
@@ -97,6 +100,8 @@ pub enum Packet<'a> {
     BootstrapChainRequest(BootstrapChainRequest<'a>),
     BootstrapChainResponse(BootstrapChainResponse),
     PeerDisconnected(PacketPeerDisconnected),
+    // sent before closing the connection to explain why
+    Disconnect(DisconnectReason),
     // Encryption
     KeyExchange(Cow<'a, EncryptionKey>),
 }
@@ -117,9 +122,32 @@ impl Packet<'_> {
             Packet::BootstrapChainRequest(_) => BOOTSTRAP_CHAIN_REQUEST_ID,
             Packet::BootstrapChainResponse(_) => BOOTSTRAP_CHAIN_RESPONSE_ID,
             Packet::PeerDisconnected(_) => PEER_DISCONNECTED_ID,
+            Packet::Disconnect(_) => DISCONNECT_ID,
             Packet::KeyExchange(_) => KEY_EXCHANGE_ID,
         }
     }
+
+    // Name of the packet type for this id, None if unknown
+    pub fn get_name_from_id(id: u8) -> Option<&'static str> {
+        Some(match id {
+            KEY_EXCHANGE_ID => "key_exchange",
+            HANDSHAKE_ID => "handshake",
+            TX_PROPAGATION_ID => "transaction_propagation",
+            BLOCK_PROPAGATION_ID => "block_propagation",
+            CHAIN_REQUEST_ID => "chain_request",
+            CHAIN_RESPONSE_ID => "chain_response",
+            PING_ID => "ping",
+            OBJECT_REQUEST_ID => "object_request",
+            OBJECT_RESPONSE_ID => "object_response",
+            NOTIFY_INV_REQUEST_ID => "notify_inventory_request",
+            NOTIFY_INV_RESPONSE_ID => "notify_inventory_response",
+            BOOTSTRAP_CHAIN_REQUEST_ID => "bootstrap_chain_request",
+            BOOTSTRAP_CHAIN_RESPONSE_ID => "bootstrap_chain_response",
+            PEER_DISCONNECTED_ID => "peer_disconnected",
+            DISCONNECT_ID => "disconnect",
+            _ => return None
+        })
+    }
 }
 
 impl<'a> Serializer for Packet<'a> {
@@ -141,6 +169,7 @@ impl<'a> Serializer for Packet<'a> {
             BOOTSTRAP_CHAIN_REQUEST_ID => Packet::BootstrapChainRequest(BootstrapChainRequest::read(reader)?),
             BOOTSTRAP_CHAIN_RESPONSE_ID => Packet::BootstrapChainResponse(BootstrapChainResponse::read(reader)?),
             PEER_DISCONNECTED_ID => Packet::PeerDisconnected(PacketPeerDisconnected::read(reader)?),
+            DISCONNECT_ID => Packet::Disconnect(DisconnectReason::read(reader)?),
             id => {
                 debug!("invalid packet id received: {}", id);
                 return Err(ReaderError::InvalidValue)
@@ -170,6 +199,7 @@ impl<'a> Serializer for Packet<'a> {
             Packet::BootstrapChainRequest(request) => (BOOTSTRAP_CHAIN_REQUEST_ID, request),
             Packet::BootstrapChainResponse(response) => (BOOTSTRAP_CHAIN_RESPONSE_ID, response),
             Packet::PeerDisconnected(disconnected) => (PEER_DISCONNECTED_ID, disconnected),
+            Packet::Disconnect(reason) => (DISCONNECT_ID, reason),
         };
 
         let packet = serializer.to_bytes();
@@ -35,6 +35,7 @@ use super::{
         Packet
     },
     peer_list::SharedPeerList,
+    scheduler::{RequestCounters, SyncCandidate},
     connection::Connection,
     error::P2pError
 };
@@ -45,8 +46,11 @@ use std::{
     fmt::{Display, Error, Formatter},
     hash::{Hash as StdHash, Hasher},
     net::{IpAddr, SocketAddr},
-    sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
-    time::Duration
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc
+    },
+    time::{Duration, Instant}
 };
 use tokio::{
     sync::{broadcast, mpsc, oneshot::Sender, Mutex},
@@ -123,6 +127,13 @@ pub struct Peer {
     last_ping: AtomicU64,
     // last time we sent a ping packet to this peer
     last_ping_sent: AtomicU64,
+    // average response time in ms of objects requested, 0 if unknown
+    latency: AtomicU64,
+    // last time this peer sent us a block, transaction or chain we didn't have
+    // set to the connection time until then
+    last_useful_data: AtomicU64,
+    // objects requested from this peer and their outcome
+    request_counters: RequestCounters,
     // cumulative difficulty of peer chain
     cumulative_difficulty: Mutex<CumulativeDifficulty>,
     // All transactions propagated from/to this peer
@@ -146,6 +157,8 @@ pub struct Peer {
     outgoing_address: SocketAddr,
     // Determine if this peer allows to be shared to others and/or through API
     sharable: bool,
+    // Peer announced in its handshake that it can connect to IPv6 addresses
+    ipv6_addresses: bool,
     // Channel to send bytes to the writer task
     tx: Tx,
     // Channel to notify the tasks to exit
@@ -156,7 +169,7 @@ pub struct Peer {
 }
 
 impl Peer {
-    pub fn new(connection: Connection, id: u64, node_tag: Option<String>, local_port: u16, version: String, top_hash: Hash, topoheight: u64, height: u64, pruned_topoheight: Option<u64>, priority: bool, cumulative_difficulty: CumulativeDifficulty, peer_list: SharedPeerList, peers_received: HashSet<SocketAddr>, sharable: bool) -> (Self, Rx) {
+    pub fn new(connection: Connection, id: u64, node_tag: Option<String>, local_port: u16, version: String, top_hash: Hash, topoheight: u64, height: u64, pruned_topoheight: Option<u64>, priority: bool, cumulative_difficulty: CumulativeDifficulty, peer_list: SharedPeerList, peers_received: HashSet<SocketAddr>, sharable: bool, ipv6_addresses: bool) -> (Self, Rx) {
         let mut outgoing_address = *connection.get_address();
         outgoing_address.set_port(local_port);
 
@@ -187,6 +200,9 @@ impl Peer {
             last_peer_list: AtomicU64::new(0),
             last_ping: AtomicU64::new(0),
             last_ping_sent: AtomicU64::new(0),
+            latency: AtomicU64::new(0),
+            last_useful_data: AtomicU64::new(get_current_time_in_seconds()),
+            request_counters: RequestCounters::default(),
             cumulative_difficulty: Mutex::new(cumulative_difficulty),
             txs_cache: Mutex::new(LruCache::new(NonZeroUsize::new(PEER_TX_CACHE_SIZE).unwrap())),
             blocks_propagation: Mutex::new(LruCache::new(NonZeroUsize::new(PEER_BLOCK_CACHE_SIZE).unwrap())),
@@ -198,6 +214,7 @@ impl Peer {
             sync_chain: Mutex::new(None),
             outgoing_address,
             sharable,
+            ipv6_addresses,
             exit_channel,
             tx,
             read_task: Mutex::new(TaskState::Inactive),
@@ -250,6 +267,11 @@ impl Peer {
         &self.version
     }
 
+    // Older versions don't know how to connect to IPv6 addresses
+    pub fn supports_ipv6_addresses(&self) -> bool {
+        self.ipv6_addresses
+    }
+
     // Get the topoheight of the peer
     pub fn get_topoheight(&self) -> u64 {
         self.topoheight.load(Ordering::Acquire)
@@ -368,11 +390,17 @@ impl Peer {
     // This is used to track the number of times we failed to communicate with the peer
     // If the fail count is greater than 20, we should close the connection
     pub fn increment_fail_count(&self) {
+        self.increase_fail_count(1);
+    }
+
+    // Increase the fail count of the peer by a penalty
+    // Protocol violations are penalized more than simple fails
+    pub fn increase_fail_count(&self, penalty: u8) {
         let current_time = get_current_time_in_seconds();
-        // if its long time we didn't get a fail, reset the fail count to 1 (because of current fail)
-        // otherwise, add 1
-        if !self.update_fail_count(current_time, 1) {
-            self.fail_count.fetch_add(1, Ordering::Release);
+        // if its long time we didn't get a fail, reset the fail count to the penalty
+        // otherwise, add it without overflowing
+        if !self.update_fail_count(current_time, penalty) {
+            let _ = self.fail_count.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| Some(count.saturating_add(penalty)));
         }
         self.set_last_fail_count(current_time);
     }
@@ -407,6 +435,11 @@ impl Peer {
 
     // Request a object from this peer and wait on it until we receive it or until timeout 
     pub async fn request_blocking_object(&self, request: ObjectRequest) -> Result<OwnedObjectResponse, P2pError> {
+        self.request_blocking_object_with_timeout(request, Duration::from_millis(PEER_TIMEOUT_REQUEST_OBJECT)).await
+    }
+
+    // Same as request_blocking_object but with a custom timeout
+    pub async fn request_blocking_object_with_timeout(&self, request: ObjectRequest, duration: Duration) -> Result<OwnedObjectResponse, P2pError> {
         trace!("Requesting {} from {}", request, self);
         let receiver = {
             let mut objects = self.objects_requested.lock().await;
@@ -418,10 +451,17 @@ impl Peer {
             objects.insert(request.clone(), sender); // clone is necessary in case timeout has occured
             receiver
         };
-        let object = match timeout(Duration::from_millis(PEER_TIMEOUT_REQUEST_OBJECT), receiver).await {
-            Ok(res) => res?,
+        self.request_counters.on_requested();
+        let start = Instant::now();
+        let object = match timeout(duration, receiver).await {
+            Ok(res) => {
+                let object = res?;
+                self.update_latency(start.elapsed().as_millis() as u64);
+                object
+            },
             Err(e) => {
                 trace!("Requested data has timed out");
+                self.request_counters.on_timed_out();
                 let mut objects = self.objects_requested.lock().await;
                 objects.remove(&request); // remove it from request list
                 return Err(P2pError::AsyncTimeOut(e));
@@ -439,6 +479,7 @@ impl Peer {
             return Err(P2pError::ObjectNotFound(request.clone()));
         }
 
+        self.request_counters.on_fulfilled();
         Ok(object)
     }
 
@@ -456,8 +497,13 @@ impl Peer {
         self.send_packet(Packet::BootstrapChainRequest(BootstrapChainRequest::new(step))).await?;
 
         // wait on the response
+        let start = Instant::now();
         let response: StepResponse = match timeout(Duration::from_millis(PEER_TIMEOUT_BOOTSTRAP_STEP), receiver).await {
-            Ok(res) => res?,
+            Ok(res) => {
+                let response = res?;
+                self.update_latency(start.elapsed().as_millis() as u64);
+                response
+            },
             Err(e) => {
                 debug!("Requested bootstrap chain step {:?} has timed out", step_kind);
                 return Err(P2pError::AsyncTimeOut(e));
@@ -486,8 +532,13 @@ impl Peer {
         self.send_packet(Packet::ChainRequest(request)).await?;
 
         trace!("waiting for chain response");
+        let start = Instant::now();
         let response: ChainResponse = match timeout(Duration::from_secs(CHAIN_SYNC_TIMEOUT_SECS), receiver).await {
-            Ok(res) => res?,
+            Ok(res) => {
+                let response = res?;
+                self.update_latency(start.elapsed().as_millis() as u64);
+                response
+            },
             Err(e) => {
                 debug!("Requested sync chain step timed out");
                 return Err(P2pError::AsyncTimeOut(e));
@@ -545,6 +596,31 @@ impl Peer {
         self.last_ping.store(value, Ordering::Release)
     }
 
+    // Get the average response time in ms of this peer, 0 if unknown
+    pub fn get_latency(&self) -> u64 {
+        self.latency.load(Ordering::Acquire)
+    }
+
+    // Get the last time this peer sent us something useful
+    pub fn get_last_useful_data(&self) -> TimestampSeconds {
+        self.last_useful_data.load(Ordering::Acquire)
+    }
+
+    // Track that this peer just sent us something useful
+    pub fn update_last_useful_data(&self) {
+        self.last_useful_data.store(get_current_time_in_seconds(), Ordering::Release)
+    }
+
+    // Objects requested from this peer and their outcome
+    pub fn get_request_counters(&self) -> &RequestCounters {
+        &self.request_counters
+    }
+
+    // Update the average response time with a new sample
+    fn update_latency(&self, value: u64) {
+        self.latency.store(average_latency(self.get_latency(), value), Ordering::Release);
+    }
+
     // Get the last time a inventory has been requested
     pub fn get_last_inventory(&self) -> TimestampSeconds {
         self.last_inventory.load(Ordering::Acquire)
@@ -657,6 +733,16 @@ impl Peer {
     }
 }
 
+impl SyncCandidate for Arc<Peer> {
+    fn get_id(&self) -> u64 {
+        Peer::get_id(self)
+    }
+
+    fn get_topoheight(&self) -> u64 {
+        Peer::get_topoheight(self)
+    }
+}
+
 impl Display for Peer {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), Error> {
         // update fail counter to have up-to-date data to display
@@ -726,4 +812,31 @@ impl StdHash for Peer {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.get_id().hash(state);
     }
-}
\ No newline at end of file
+}
+
+// Moving average of the response time, a known latency is never 0
+fn average_latency(previous: u64, value: u64) -> u64 {
+    let latency = if previous == 0 {
+        value
+    } else {
+        (previous * 3 + value) / 4
+    };
+    latency.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_latency() {
+        // First sample is used as is
+        assert_eq!(average_latency(0, 200), 200);
+        assert_eq!(average_latency(0, 0), 1);
+
+        // New samples only move the average slowly
+        assert_eq!(average_latency(200, 600), 300);
+        assert_eq!(average_latency(300, 0), 225);
+        assert_eq!(average_latency(1, 0), 1);
+    }
+}
@@ -8,7 +8,13 @@ use crate::{
     },
     p2p::packet::peer_disconnected::PacketPeerDisconnected
 };
-use super::{peer::Peer, packet::Packet, error::P2pError};
+use super::{
+    peer::Peer,
+    packet::Packet,
+    error::P2pError,
+    slots::{PeerSlots, SlotsUsage},
+    version_stats::count_versions
+};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     fmt::{self, Display, Formatter},
@@ -62,7 +68,10 @@ pub struct StoredPeer {
     local_port: u16,
     // Until when the peer is banned
     temp_ban_until: Option<u64>,
-    state: StoredPeerState
+    state: StoredPeerState,
+    // Last version advertised in its handshake
+    #[serde(default)]
+    version: Option<String>
 }
 
 impl PeerList {
@@ -198,11 +207,11 @@ impl PeerList {
     }
 
     // Add a new peer to the list
-    // This will returns an error if peerlist is full
-    pub async fn add_peer(&self, peer: &Arc<Peer>, max_peers: usize) -> Result<(), P2pError> {
+    // This will returns an error if no slot is available for it
+    pub async fn add_peer(&self, peer: &Arc<Peer>, slots: &PeerSlots) -> Result<(), P2pError> {
         {
             let mut peers = self.peers.write().await;
-            if peers.len() >= max_peers {
+            if !slots.can_accept(&Self::count_slots(peers.values()), peer.is_priority(), peer.get_connection().is_out()) {
                 return Err(P2pError::PeerListFull);
             }
 
@@ -229,12 +238,43 @@ impl PeerList {
             stored_peer.set_fail_count(0);
             stored_peer.set_last_seen(get_current_time_in_seconds());
             stored_peer.set_local_port(peer.get_local_port());
+            stored_peer.set_version(peer.get_version().clone());
         } else {
             debug!("Saving {} in stored peerlist", peer);
-            stored_peers.insert(ip, StoredPeer::new(peer.get_local_port(), StoredPeerState::Graylist));
+            let mut stored_peer = StoredPeer::new(peer.get_local_port(), StoredPeerState::Graylist);
+            stored_peer.set_version(peer.get_version().clone());
+            stored_peers.insert(ip, stored_peer);
         }
     }
 
+    // Count the versions run by connected peers and by stored peers
+    // seen during the last `recent_seconds`
+    // Each IP is counted only once
+    pub async fn get_version_stats(&self, recent_seconds: u64) -> HashMap<String, usize> {
+        let mut versions: HashMap<IpAddr, String> = HashMap::new();
+        {
+            let stored_peers = self.stored_peers.read().await;
+            let current_time = get_current_time_in_seconds();
+            for (ip, stored_peer) in stored_peers.iter() {
+                if let Some(version) = stored_peer.get_version() {
+                    if stored_peer.get_last_seen() + recent_seconds >= current_time {
+                        versions.insert(*ip, version.clone());
+                    }
+                }
+            }
+        }
+
+        {
+            // Connected peers have the most up-to-date version
+            let peers = self.peers.read().await;
+            for peer in peers.values() {
+                versions.insert(peer.get_outgoing_address().ip(), peer.get_version().clone());
+            }
+        }
+
+        count_versions(versions.values().map(|v| v.as_str()))
+    }
+
     // Verify if the peer is connected (in peerlist)
     pub async fn has_peer(&self, peer_id: &u64) -> bool {
         let peers = self.peers.read().await;
@@ -260,6 +300,20 @@ impl PeerList {
         self.peers.read().await.values().cloned().collect()
     }
 
+    fn count_slots<'a>(peers: impl Iterator<Item = &'a Arc<Peer>>) -> SlotsUsage {
+        let mut usage = SlotsUsage::default();
+        for peer in peers {
+            usage.add(peer.is_priority(), peer.get_connection().is_out());
+        }
+        usage
+    }
+
+    // Count the connected peers for each kind of slot
+    pub async fn get_slots_usage(&self) -> SlotsUsage {
+        let peers = self.peers.read().await;
+        Self::count_slots(peers.values())
+    }
+
     pub async fn size(&self) -> usize {
         let peers = self.peers.read().await;
         peers.len()
@@ -371,6 +425,12 @@ impl PeerList {
         true
     }
 
+    // Get the fail count of a stored peer, 0 if we don't know it
+    pub async fn get_stored_fail_count(&self, ip: &IpAddr) -> u8 {
+        let stored_peers = self.stored_peers.read().await;
+        stored_peers.get(ip).map(|stored_peer| stored_peer.get_fail_count()).unwrap_or(0)
+    }
+
     pub async fn is_whitelisted(&self, ip: &IpAddr) -> bool {
         self.addr_has_state(ip, StoredPeerState::Whitelist).await
     }
@@ -457,15 +517,70 @@ impl PeerList {
     }
 
     // temp ban a peer address for a duration in seconds
+    // bans are saved directly to disk to be kept across restarts
     pub async fn temp_ban_address(&self, ip: &IpAddr, seconds: u64) {
         let mut stored_peers = self.stored_peers.write().await;
-        if let Some(stored_peer) = stored_peers.get_mut(ip) {
-            stored_peer.set_temp_ban_until(Some(get_current_time_in_seconds() + seconds));
-        } else {
-            stored_peers.insert(ip.clone(), StoredPeer::new(0, StoredPeerState::Graylist));
+        let stored_peer = match stored_peers.entry(*ip) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(StoredPeer::new(0, StoredPeerState::Graylist))
+        };
+        stored_peer.set_temp_ban_until(Some(get_current_time_in_seconds() + seconds));
+
+        if let Err(e) = self.save_peers_to_file(&stored_peers) {
+            error!("Error while saving peerlist after temp ban of {}: {}", ip, e);
+        }
+    }
+
+    // temp ban a peer address for a duration in seconds
+    // and disconnect all peers connected from this address
+    pub async fn ban_address(&self, ip: &IpAddr, seconds: u64) {
+        self.temp_ban_address(ip, seconds).await;
+
+        let peers: Vec<Arc<Peer>> = {
+            let peers = self.peers.read().await;
+            peers.values().filter(|peer| peer.get_connection().get_address().ip() == *ip).cloned().collect()
+        };
+
+        for peer in peers {
+            if let Err(e) = peer.close_internal().await {
+                error!("Error while trying to close peer {} for being banned: {}", peer.get_connection().get_address(), e);
+            }
         }
     }
 
+    // remove the temp ban of a peer address and reset its fail count
+    // returns false if the address was not banned
+    pub async fn unban_address(&self, ip: &IpAddr) -> bool {
+        let mut stored_peers = self.stored_peers.write().await;
+        let Some(stored_peer) = stored_peers.get_mut(ip) else {
+            return false
+        };
+
+        if !stored_peer.is_temp_banned(get_current_time_in_seconds()) {
+            return false
+        }
+
+        stored_peer.set_temp_ban_until(None);
+        stored_peer.set_fail_count(0);
+
+        if let Err(e) = self.save_peers_to_file(&stored_peers) {
+            error!("Error while saving peerlist after unban of {}: {}", ip, e);
+        }
+
+        true
+    }
+
+    // Get all the addresses currently temp banned with their expiry
+    pub async fn get_bans(&self) -> Vec<(IpAddr, TimestampSeconds)> {
+        let current_time = get_current_time_in_seconds();
+        let stored_peers = self.stored_peers.read().await;
+        stored_peers.iter()
+            .filter_map(|(ip, stored_peer)| stored_peer.get_temp_ban_until()
+                .filter(|until| *until > current_time)
+                .map(|until| (*ip, until))
+            ).collect()
+    }
+
     // whitelist a peer address
     // if this peer is already known, change its state to whitelist
     // otherwise create a new StoredPeer with state whitelist
@@ -473,21 +588,22 @@ impl PeerList {
         self.set_state_to_address(ip, StoredPeerState::Whitelist).await;
     }
 
-    pub async fn find_peer_to_connect(&self) -> Option<SocketAddr> {
+    // Addresses of the preferred family are tried first, then the other family
+    pub async fn find_peer_to_connect(&self, prefer_ipv6: bool) -> Option<SocketAddr> {
         // remove all peers that have a high fail count
         let peers = self.peers.read().await;
         let mut stored_peers = self.stored_peers.write().await;
-        stored_peers.retain(|_, stored_peer| *stored_peer.get_state() == StoredPeerState::Whitelist || stored_peer.get_fail_count() < PEER_FAIL_LIMIT);
-
+        // banned peers are kept to not lose their ban
         let current_time = get_current_time_in_seconds();
-        // first lets check in whitelist
-        if let Some(addr) = self.find_peer_to_connect_to_with_state(&peers, &mut stored_peers, current_time, StoredPeerState::Whitelist) {
-            return Some(addr);
-        }
+        stored_peers.retain(|_, stored_peer| *stored_peer.get_state() == StoredPeerState::Whitelist || stored_peer.get_fail_count() < PEER_FAIL_LIMIT || stored_peer.is_temp_banned(current_time));
 
-        // then in graylist
-        if let Some(addr) = self.find_peer_to_connect_to_with_state(&peers, &mut stored_peers, current_time, StoredPeerState::Graylist) {
-            return Some(addr);
+        // first lets check in whitelist, then in graylist
+        for state in [StoredPeerState::Whitelist, StoredPeerState::Graylist] {
+            for ipv6 in [prefer_ipv6, !prefer_ipv6] {
+                if let Some(addr) = self.find_peer_to_connect_to_with_state(&peers, &mut stored_peers, current_time, &state, ipv6) {
+                    return Some(addr);
+                }
+            }
         }
 
         None
@@ -495,10 +611,10 @@ impl PeerList {
 
     // find among stored peers a peer to connect to with the requested StoredPeerState
     // we check that we're not already connected to this peer and that we didn't tried to connect to it recently
-    fn find_peer_to_connect_to_with_state(&self, peers: &HashMap<u64, Arc<Peer>>, stored_peers: &mut HashMap<IpAddr, StoredPeer>, current_time: TimestampSeconds, state: StoredPeerState) -> Option<SocketAddr> {
-        for (ip, stored_peer) in stored_peers {
+    fn find_peer_to_connect_to_with_state(&self, peers: &HashMap<u64, Arc<Peer>>, stored_peers: &mut HashMap<IpAddr, StoredPeer>, current_time: TimestampSeconds, state: &StoredPeerState, ipv6: bool) -> Option<SocketAddr> {
+        for (ip, stored_peer) in stored_peers.iter_mut().filter(|(ip, _)| ip.is_ipv6() == ipv6) {
             let addr = SocketAddr::new(*ip, stored_peer.get_local_port());
-            if *stored_peer.get_state() == state && stored_peer.get_last_connection_try() + (stored_peer.get_fail_count() as u64 * P2P_EXTEND_PEERLIST_DELAY) <= current_time && Self::internal_get_peer_by_addr(peers, &addr).is_none() {
+            if stored_peer.get_state() == state && stored_peer.get_last_connection_try() + (stored_peer.get_fail_count() as u64 * P2P_EXTEND_PEERLIST_DELAY) <= current_time && Self::internal_get_peer_by_addr(peers, &addr).is_none() {
                 stored_peer.set_last_connection_try(current_time);
                 return Some(addr);
             }
@@ -562,7 +678,8 @@ impl StoredPeer {
             fail_count: 0,
             local_port,
             temp_ban_until: None,
-            state
+            state,
+            version: None
         }
     }
 
@@ -574,6 +691,18 @@ impl StoredPeer {
         &self.state
     }
 
+    fn get_last_seen(&self) -> TimestampSeconds {
+        self.last_seen
+    }
+
+    fn get_version(&self) -> Option<&String> {
+        self.version.as_ref()
+    }
+
+    fn set_version(&mut self, version: String) {
+        self.version = Some(version);
+    }
+
     fn set_last_seen(&mut self, last_seen: TimestampSeconds) {
         self.last_seen = last_seen;
     }
@@ -594,6 +723,10 @@ impl StoredPeer {
         self.temp_ban_until = temp_ban_until;
     }
 
+    fn is_temp_banned(&self, current_time: TimestampSeconds) -> bool {
+        self.temp_ban_until.is_some_and(|until| until > current_time)
+    }
+
     fn get_fail_count(&self) -> u8 {
         self.fail_count
     }
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+// Reorder buffer of the sync pipeline
+// Blocks are downloaded in parallel and may arrive in any order,
+// they are kept here until all the previous ones are applied
+pub struct SyncPipeline<T> {
+    // max requests in flight and blocks waiting at the same time
+    capacity: usize,
+    // index of the next block to apply
+    next: usize,
+    // requests sent but not received yet
+    in_flight: usize,
+    // received blocks waiting for a previous one
+    pending: BTreeMap<usize, T>,
+    // highest count of blocks buffered or in flight
+    max_depth: usize
+}
+
+impl<T> SyncPipeline<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            next: 0,
+            in_flight: 0,
+            pending: BTreeMap::new(),
+            max_depth: 0
+        }
+    }
+
+    // Check if a new request can be sent without going above the capacity
+    pub fn has_free_slot(&self) -> bool {
+        self.depth() < self.capacity
+    }
+
+    // Register a new request sent
+    pub fn on_requested(&mut self) {
+        self.in_flight += 1;
+        self.max_depth = self.max_depth.max(self.depth());
+    }
+
+    // Store a received block at its index in the response
+    pub fn on_received(&mut self, index: usize, value: T) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        self.pending.insert(index, value);
+    }
+
+    // Next block to apply, if it was received
+    pub fn pop_ready(&mut self) -> Option<T> {
+        let value = self.pending.remove(&self.next)?;
+        self.next += 1;
+        Some(value)
+    }
+
+    // Blocks in flight and waiting to be applied
+    pub fn depth(&self) -> usize {
+        self.in_flight + self.pending.len()
+    }
+
+    pub fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_order() {
+        let mut pipeline = SyncPipeline::new(3);
+        for _ in 0..3 {
+            assert!(pipeline.has_free_slot());
+            pipeline.on_requested();
+        }
+        assert!(!pipeline.has_free_slot());
+        assert_eq!(pipeline.get_max_depth(), 3);
+
+        // Out of order blocks are held until the first one is received
+        pipeline.on_received(2, "c");
+        pipeline.on_received(1, "b");
+        assert_eq!(pipeline.pop_ready(), None);
+        assert!(!pipeline.has_free_slot());
+
+        pipeline.on_received(0, "a");
+        assert_eq!(pipeline.pop_ready(), Some("a"));
+        assert_eq!(pipeline.pop_ready(), Some("b"));
+        assert_eq!(pipeline.pop_ready(), Some("c"));
+        assert_eq!(pipeline.pop_ready(), None);
+        assert_eq!(pipeline.depth(), 0);
+        assert!(pipeline.has_free_slot());
+    }
+
+    #[test]
+    fn test_pipeline_min_capacity() {
+        let mut pipeline = SyncPipeline::<()>::new(0);
+        assert_eq!(pipeline.get_capacity(), 1);
+        pipeline.on_requested();
+        assert!(!pipeline.has_free_slot());
+    }
+}
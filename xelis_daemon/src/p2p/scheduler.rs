@@ -0,0 +1,251 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex
+    },
+    time::{Duration, Instant}
+};
+use serde::Serialize;
+
+// Timed out requests before a peer is deprioritized for sync
+pub const MAX_STRIKES: usize = 3;
+// Strikes older than this are forgotten
+pub const STRIKES_WINDOW: Duration = Duration::from_secs(10 * 60);
+// How many peers are tried for the same object before giving up
+pub const MAX_REQUEST_ATTEMPTS: usize = 3;
+
+// What the scheduler needs to know about a peer
+pub trait SyncCandidate {
+    fn get_id(&self) -> u64;
+
+    fn get_topoheight(&self) -> u64;
+}
+
+// Objects requests counters of a peer
+#[derive(Default)]
+pub struct RequestCounters {
+    requested: AtomicU64,
+    fulfilled: AtomicU64,
+    timed_out: AtomicU64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RequestCountersSnapshot {
+    pub requested: u64,
+    pub fulfilled: u64,
+    pub timed_out: u64
+}
+
+impl RequestCounters {
+    pub fn on_requested(&self) {
+        self.requested.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn on_fulfilled(&self) {
+        self.fulfilled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn on_timed_out(&self) {
+        self.timed_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> RequestCountersSnapshot {
+        RequestCountersSnapshot {
+            requested: self.requested.load(Ordering::Relaxed),
+            fulfilled: self.fulfilled.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed)
+        }
+    }
+}
+
+// Spread the sync requests over the peers in a round robin way
+// and keep track of the peers that are too slow to answer
+pub struct RequestScheduler {
+    // timeout of each request
+    timeout: Duration,
+    // incremented at each selection
+    cursor: AtomicUsize,
+    // timestamps of the timed out requests per peer id
+    strikes: Mutex<HashMap<u64, VecDeque<Instant>>>
+}
+
+impl RequestScheduler {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            cursor: AtomicUsize::new(0),
+            strikes: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // Register a timed out request for this peer
+    // Returns true if the peer just got deprioritized
+    pub fn add_strike(&self, peer_id: u64, now: Instant) -> bool {
+        let Ok(mut strikes) = self.strikes.lock() else {
+            return false
+        };
+
+        let entries = strikes.entry(peer_id).or_default();
+        Self::clean_strikes(entries, now);
+        entries.push_back(now);
+        entries.len() == MAX_STRIKES
+    }
+
+    // Check if the peer had too many timed out requests recently
+    pub fn is_deprioritized(&self, peer_id: u64, now: Instant) -> bool {
+        let Ok(mut strikes) = self.strikes.lock() else {
+            return false
+        };
+
+        match strikes.get_mut(&peer_id) {
+            Some(entries) => {
+                Self::clean_strikes(entries, now);
+                let deprioritized = entries.len() >= MAX_STRIKES;
+                if entries.is_empty() {
+                    strikes.remove(&peer_id);
+                }
+                deprioritized
+            },
+            None => false
+        }
+    }
+
+    // Forget the strikes of a disconnected peer
+    pub fn remove_peer(&self, peer_id: u64) {
+        if let Ok(mut strikes) = self.strikes.lock() {
+            strikes.remove(&peer_id);
+        }
+    }
+
+    fn clean_strikes(entries: &mut VecDeque<Instant>, now: Instant) {
+        while entries.front().is_some_and(|strike| now.duration_since(*strike) > STRIKES_WINDOW) {
+            entries.pop_front();
+        }
+    }
+
+    // Select the next peer having at least the requested topoheight
+    // Deprioritized peers are only used when no other peer can serve the request
+    pub fn select<'a, P: SyncCandidate>(&self, peers: &'a [P], topoheight: u64, exclude: &HashSet<u64>, now: Instant) -> Option<&'a P> {
+        let mut candidates: Vec<&P> = peers.iter()
+            .filter(|peer| !exclude.contains(&peer.get_id()) && peer.get_topoheight() >= topoheight)
+            .collect();
+
+        if candidates.is_empty() {
+            return None
+        }
+
+        // Order by id so the round robin doesn't depend on the order of the list
+        candidates.sort_by_key(|peer| peer.get_id());
+        let (preferred, deprioritized): (Vec<&P>, Vec<&P>) = candidates.into_iter().partition(|peer| !self.is_deprioritized(peer.get_id(), now));
+        let candidates = if preferred.is_empty() {
+            deprioritized
+        } else {
+            preferred
+        };
+
+        let index = self.cursor.fetch_add(1, Ordering::Relaxed) % candidates.len();
+        Some(candidates[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockPeer {
+        id: u64,
+        topoheight: u64
+    }
+
+    impl SyncCandidate for MockPeer {
+        fn get_id(&self) -> u64 {
+            self.id
+        }
+
+        fn get_topoheight(&self) -> u64 {
+            self.topoheight
+        }
+    }
+
+    fn peers() -> Vec<MockPeer> {
+        vec![
+            MockPeer { id: 3, topoheight: 100 },
+            MockPeer { id: 1, topoheight: 100 },
+            MockPeer { id: 2, topoheight: 50 }
+        ]
+    }
+
+    fn select_ids(scheduler: &RequestScheduler, peers: &[MockPeer], topoheight: u64, exclude: &HashSet<u64>, count: usize, now: Instant) -> Vec<u64> {
+        (0..count).map(|_| scheduler.select(peers, topoheight, exclude, now).unwrap().get_id()).collect()
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let scheduler = RequestScheduler::new(Duration::from_secs(15));
+        let peers = peers();
+        let now = Instant::now();
+
+        assert_eq!(select_ids(&scheduler, &peers, 10, &HashSet::new(), 6, now), vec![1, 2, 3, 1, 2, 3]);
+        // Peer 2 doesn't have the topoheight
+        let selected = select_ids(&scheduler, &peers, 80, &HashSet::new(), 4, now);
+        assert!(!selected.contains(&2));
+        assert!(selected.contains(&1) && selected.contains(&3));
+
+        assert!(scheduler.select(&peers, 101, &HashSet::new(), now).is_none());
+    }
+
+    #[test]
+    fn test_exclude() {
+        let scheduler = RequestScheduler::new(Duration::from_secs(15));
+        let peers = peers();
+        let now = Instant::now();
+
+        let exclude = HashSet::from([1]);
+        assert_eq!(select_ids(&scheduler, &peers, 100, &exclude, 3, now), vec![3, 3, 3]);
+
+        let exclude = HashSet::from([1, 3]);
+        assert!(scheduler.select(&peers, 100, &exclude, now).is_none());
+    }
+
+    #[test]
+    fn test_strikes() {
+        let scheduler = RequestScheduler::new(Duration::from_secs(15));
+        let peers = peers();
+        let now = Instant::now();
+
+        assert!(!scheduler.add_strike(1, now));
+        assert!(!scheduler.add_strike(1, now + Duration::from_secs(60)));
+        assert!(!scheduler.is_deprioritized(1, now + Duration::from_secs(60)));
+        assert!(scheduler.add_strike(1, now + Duration::from_secs(120)));
+
+        let later = now + Duration::from_secs(121);
+        assert!(scheduler.is_deprioritized(1, later));
+        assert_eq!(select_ids(&scheduler, &peers, 100, &HashSet::new(), 3, later), vec![3, 3, 3]);
+
+        // Still used if it's the only one able to answer
+        let exclude = HashSet::from([3]);
+        assert_eq!(scheduler.select(&peers, 100, &exclude, later).unwrap().get_id(), 1);
+
+        // First strike is out of the window
+        assert!(!scheduler.is_deprioritized(1, now + STRIKES_WINDOW + Duration::from_secs(1)));
+
+        scheduler.add_strike(3, now);
+        scheduler.remove_peer(3);
+        assert!(!scheduler.is_deprioritized(3, now));
+    }
+
+    #[test]
+    fn test_counters() {
+        let counters = RequestCounters::default();
+        counters.on_requested();
+        counters.on_requested();
+        counters.on_fulfilled();
+        counters.on_timed_out();
+        assert_eq!(counters.snapshot(), RequestCountersSnapshot { requested: 2, fulfilled: 1, timed_out: 1 });
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Mutex;
+use xelis_common::time::TimestampSeconds;
+use crate::config::{PEER_EVICTION_FAIL_PENALTY, PEER_EVICTION_LATENCY_DIVISOR};
+use super::error::P2pError;
+
+// Connection limits of the P2P server
+// Priority peers (seed, exclusive and priority nodes) have reserved slots
+// that the other peers can't use, so they can always connect to us
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerLimits {
+    // all peers connected, priority ones included
+    pub max_peers: usize,
+    // non-priority peers we connected to
+    pub max_outgoing_peers: usize,
+    // slots only usable by priority peers
+    pub priority_slots: usize
+}
+
+// Peers connected for each kind of slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlotsUsage {
+    pub peers: usize,
+    pub priority: usize,
+    // non-priority peers we connected to
+    pub outgoing: usize
+}
+
+impl SlotsUsage {
+    pub fn add(&mut self, priority: bool, out: bool) {
+        self.peers += 1;
+        if priority {
+            self.priority += 1;
+        } else if out {
+            self.outgoing += 1;
+        }
+    }
+}
+
+impl PeerLimits {
+    pub fn verify(&self) -> Result<(), P2pError> {
+        if self.max_peers == 0 {
+            return Err(P2pError::InvalidMaxPeers)
+        }
+
+        if self.max_outgoing_peers > self.max_peers {
+            return Err(P2pError::InvalidMaxOutgoingPeers)
+        }
+
+        if self.priority_slots >= self.max_peers {
+            return Err(P2pError::InvalidPrioritySlots)
+        }
+
+        Ok(())
+    }
+
+    // Slots usable by the non-priority peers
+    pub fn get_regular_slots(&self) -> usize {
+        self.max_peers - self.priority_slots
+    }
+
+    // Check if a new peer can take a free slot
+    // Priority peers above their reserved slots use the regular ones
+    pub fn can_accept(&self, usage: &SlotsUsage, priority: bool, out: bool) -> bool {
+        if usage.peers >= self.max_peers {
+            return false
+        }
+
+        if priority {
+            return true
+        }
+
+        let used = usage.peers - usage.priority.min(self.priority_slots);
+        used < self.get_regular_slots() && (!out || usage.outgoing < self.max_outgoing_peers)
+    }
+}
+
+// Limits shared by the P2P tasks, they can be updated while running
+// Peers already connected above a new limit are kept
+pub struct PeerSlots {
+    limits: Mutex<PeerLimits>
+}
+
+impl PeerSlots {
+    pub fn new(limits: PeerLimits) -> Result<Self, P2pError> {
+        limits.verify()?;
+        Ok(Self {
+            limits: Mutex::new(limits)
+        })
+    }
+
+    pub fn get_limits(&self) -> PeerLimits {
+        *self.limits.lock().expect("peer slots lock")
+    }
+
+    pub fn set_limits(&self, limits: PeerLimits) -> Result<(), P2pError> {
+        limits.verify()?;
+        *self.limits.lock().expect("peer slots lock") = limits;
+        Ok(())
+    }
+
+    pub fn can_accept(&self, usage: &SlotsUsage, priority: bool, out: bool) -> bool {
+        self.get_limits().can_accept(usage, priority, out)
+    }
+}
+
+// Score of a peer to be evicted, the highest one is evicted first
+// A peer that sent nothing useful for a long time with a high ping is the worst
+pub fn eviction_score(fail_count: u8, latency: u64, last_useful_data: TimestampSeconds, now: TimestampSeconds) -> u64 {
+    now.saturating_sub(last_useful_data)
+        .saturating_add(fail_count as u64 * PEER_EVICTION_FAIL_PENALTY)
+        .saturating_add(latency / PEER_EVICTION_LATENCY_DIVISOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> PeerLimits {
+        PeerLimits {
+            max_peers: 8,
+            max_outgoing_peers: 4,
+            priority_slots: 2
+        }
+    }
+
+    fn usage(regular_in: usize, regular_out: usize, priority: usize) -> SlotsUsage {
+        let mut usage = SlotsUsage::default();
+        for _ in 0..regular_in {
+            usage.add(false, false);
+        }
+        for _ in 0..regular_out {
+            usage.add(false, true);
+        }
+        for _ in 0..priority {
+            usage.add(true, false);
+        }
+        usage
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(limits().verify().is_ok());
+        assert!(matches!(PeerLimits { max_peers: 0, max_outgoing_peers: 0, priority_slots: 0 }.verify(), Err(P2pError::InvalidMaxPeers)));
+        assert!(matches!(PeerLimits { max_outgoing_peers: 9, ..limits() }.verify(), Err(P2pError::InvalidMaxOutgoingPeers)));
+        assert!(matches!(PeerLimits { priority_slots: 8, ..limits() }.verify(), Err(P2pError::InvalidPrioritySlots)));
+    }
+
+    #[test]
+    fn test_reserved_priority_slots() {
+        let limits = limits();
+        // Regular slots are full, the reserved ones are kept for priority peers
+        let full = usage(6, 0, 0);
+        assert!(!limits.can_accept(&full, false, false));
+        assert!(limits.can_accept(&full, true, false));
+        assert!(limits.can_accept(&usage(6, 0, 1), true, false));
+        assert!(!limits.can_accept(&usage(6, 0, 2), true, false));
+
+        // A free reserved slot stays reserved
+        assert!(limits.can_accept(&usage(5, 0, 1), false, false));
+        assert!(!limits.can_accept(&usage(6, 0, 1), false, false));
+
+        // Priority peers above the reserved slots use regular ones
+        assert!(limits.can_accept(&usage(3, 0, 3), false, false));
+        assert!(!limits.can_accept(&usage(4, 0, 4), false, false));
+        assert!(!limits.can_accept(&usage(4, 0, 4), true, false));
+    }
+
+    #[test]
+    fn test_max_outgoing_peers() {
+        let limits = limits();
+        assert!(limits.can_accept(&usage(0, 3, 0), false, true));
+        assert!(!limits.can_accept(&usage(0, 4, 0), false, true));
+        // Incoming and priority peers are not limited by it
+        assert!(limits.can_accept(&usage(0, 4, 0), false, false));
+        assert!(limits.can_accept(&usage(0, 4, 0), true, true));
+    }
+
+    #[test]
+    fn test_hot_reload() {
+        let slots = PeerSlots::new(limits()).unwrap();
+        let current = usage(5, 0, 0);
+        assert!(slots.can_accept(&current, false, false));
+
+        slots.set_limits(PeerLimits { max_peers: 4, max_outgoing_peers: 2, priority_slots: 0 }).unwrap();
+        assert!(!slots.can_accept(&current, false, false));
+
+        // An invalid update is refused and the previous limits are kept
+        assert!(slots.set_limits(PeerLimits { max_peers: 2, max_outgoing_peers: 4, priority_slots: 0 }).is_err());
+        assert_eq!(slots.get_limits().max_peers, 4);
+    }
+
+    #[test]
+    fn test_eviction_score() {
+        let now = 1000;
+        // Idle peers are worse
+        assert!(eviction_score(0, 100, 900, now) > eviction_score(0, 100, 990, now));
+        // Then high ping
+        assert!(eviction_score(0, 2000, 990, now) > eviction_score(0, 100, 990, now));
+        // Failures are penalized
+        assert!(eviction_score(1, 100, 990, now) > eviction_score(0, 100, 990, now));
+        // Clock going backward doesn't overflow
+        assert_eq!(eviction_score(0, 0, now + 10, now), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    io,
+    net::IpAddr
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream
+};
+
+// Minimal SOCKS5 client (RFC 1928) without authentication
+// used to route the outgoing P2P connections through a proxy such as Tor
+
+const SOCKS_VERSION: u8 = 0x05;
+const NO_AUTHENTICATION: u8 = 0x00;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+const REPLY_SUCCEEDED: u8 = 0x00;
+
+#[derive(Error, Debug)]
+pub enum SocksError {
+    #[error("SOCKS5 proxy {} is unreachable: {}", _0, _1)]
+    ProxyUnreachable(String, io::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("SOCKS5 proxy answered with an invalid version {}", _0)]
+    InvalidVersion(u8),
+    #[error("SOCKS5 proxy requires an authentication")]
+    AuthenticationRequired,
+    #[error("SOCKS5 proxy refused the connection: {}", reply_message(*_0))]
+    ConnectionRefused(u8),
+    #[error("SOCKS5 proxy answered with an invalid address type {}", _0)]
+    InvalidAddressType(u8),
+    #[error("Domain name is too long for SOCKS5")]
+    DomainTooLong
+}
+
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused by destination host",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error"
+    }
+}
+
+// Open a connection to host:port through the SOCKS5 proxy
+// If host is not an IP address, it is resolved by the proxy and never locally
+pub async fn connect(proxy: &str, host: &str, port: u16) -> Result<TcpStream, SocksError> {
+    let mut stream = TcpStream::connect(proxy).await
+        .map_err(|e| SocksError::ProxyUnreachable(proxy.to_owned(), e))?;
+
+    // Greeting, only no authentication is supported
+    stream.write_all(&[SOCKS_VERSION, 1, NO_AUTHENTICATION]).await?;
+    let mut response = [0u8; 2];
+    stream.read_exact(&mut response).await?;
+    if response[0] != SOCKS_VERSION {
+        return Err(SocksError::InvalidVersion(response[0]))
+    }
+    if response[1] != NO_AUTHENTICATION {
+        return Err(SocksError::AuthenticationRequired)
+    }
+
+    // Connect request
+    let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&ip.octets());
+        },
+        Ok(IpAddr::V6(ip)) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&ip.octets());
+        },
+        Err(_) => {
+            let len: u8 = host.len().try_into().map_err(|_| SocksError::DomainTooLong)?;
+            request.push(ATYP_DOMAIN);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    // Reply: version, status, reserved, bound address
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(SocksError::InvalidVersion(reply[0]))
+    }
+    if reply[1] != REPLY_SUCCEEDED {
+        return Err(SocksError::ConnectionRefused(reply[1]))
+    }
+
+    // Skip the bound address and port, they are not used
+    let len = match reply[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => stream.read_u8().await? as usize,
+        kind => return Err(SocksError::InvalidAddressType(kind))
+    };
+    let mut bound = vec![0u8; len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use super::*;
+
+    // Accept one SOCKS5 client, check its request and answer with the reply code
+    async fn mock_proxy(expected_request: Vec<u8>, reply: u8) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [SOCKS_VERSION, 1, NO_AUTHENTICATION]);
+            stream.write_all(&[SOCKS_VERSION, NO_AUTHENTICATION]).await.unwrap();
+
+            let mut request = vec![0u8; expected_request.len()];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, expected_request);
+            stream.write_all(&[SOCKS_VERSION, reply, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_connect_domain() {
+        let mut expected = vec![SOCKS_VERSION, CMD_CONNECT, 0, ATYP_DOMAIN, 11];
+        expected.extend_from_slice(b"example.com");
+        expected.extend_from_slice(&2125u16.to_be_bytes());
+
+        let proxy = mock_proxy(expected, REPLY_SUCCEEDED).await;
+        assert!(connect(&proxy, "example.com", 2125).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let expected = vec![SOCKS_VERSION, CMD_CONNECT, 0, ATYP_IPV4, 127, 0, 0, 1, 0x08, 0x4D];
+        let proxy = mock_proxy(expected, 0x05).await;
+        assert!(matches!(connect(&proxy, "127.0.0.1", 2125).await, Err(SocksError::ConnectionRefused(0x05))));
+    }
+}
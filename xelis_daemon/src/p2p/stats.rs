@@ -0,0 +1,106 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use super::packet::Packet;
+
+// Counters are kept for all possible packet ids
+const PACKET_IDS: usize = u8::MAX as usize + 1;
+
+// Traffic counters of the P2P layer since startup
+// Shared by all the connections
+pub struct NetworkStats {
+    // total bytes read from the sockets
+    bytes_in: AtomicU64,
+    // total bytes written on the sockets
+    bytes_out: AtomicU64,
+    // packets received per packet id
+    messages_in: [AtomicU64; PACKET_IDS],
+    // packets sent per packet id
+    messages_out: [AtomicU64; PACKET_IDS],
+    handshakes_succeeded: AtomicU64,
+    handshakes_failed: AtomicU64
+}
+
+impl NetworkStats {
+    pub fn new() -> Self {
+        Self {
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            messages_in: std::array::from_fn(|_| AtomicU64::new(0)),
+            messages_out: std::array::from_fn(|_| AtomicU64::new(0)),
+            handshakes_succeeded: AtomicU64::new(0),
+            handshakes_failed: AtomicU64::new(0)
+        }
+    }
+
+    pub fn add_bytes_in(&self, bytes: usize) {
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes_out(&self, bytes: usize) {
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    // Count a packet received using its id (first byte of the packet)
+    pub fn add_message_in(&self, id: u8) {
+        self.messages_in[id as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Count a packet sent using its id (first byte of the packet)
+    pub fn add_message_out(&self, id: u8) {
+        self.messages_out[id as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_handshake(&self, success: bool) {
+        if success {
+            self.handshakes_succeeded.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.handshakes_failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn get_bytes_in(&self) -> u64 {
+        self.bytes_in.load(Ordering::Relaxed)
+    }
+
+    pub fn get_bytes_out(&self) -> u64 {
+        self.bytes_out.load(Ordering::Relaxed)
+    }
+
+    pub fn get_handshakes_succeeded(&self) -> u64 {
+        self.handshakes_succeeded.load(Ordering::Relaxed)
+    }
+
+    pub fn get_handshakes_failed(&self) -> u64 {
+        self.handshakes_failed.load(Ordering::Relaxed)
+    }
+
+    // Packets received per packet type, types never seen are not included
+    pub fn get_messages_in(&self) -> HashMap<String, u64> {
+        Self::collect_messages(&self.messages_in)
+    }
+
+    // Packets sent per packet type, types never seen are not included
+    pub fn get_messages_out(&self) -> HashMap<String, u64> {
+        Self::collect_messages(&self.messages_out)
+    }
+
+    fn collect_messages(counters: &[AtomicU64; PACKET_IDS]) -> HashMap<String, u64> {
+        counters.iter()
+            .enumerate()
+            .filter_map(|(id, counter)| {
+                let count = counter.load(Ordering::Relaxed);
+                if count == 0 {
+                    return None
+                }
+
+                let name = match Packet::get_name_from_id(id as u8) {
+                    Some(name) => name.to_owned(),
+                    None => format!("unknown_{}", id)
+                };
+                Some((name, count))
+            })
+            .collect()
+    }
+}
@@ -5,7 +5,7 @@ use std::{
         Arc,
         atomic::{AtomicU64, Ordering}
     },
-    collections::HashMap
+    collections::{HashMap, HashSet}
 };
 use bytes::Bytes;
 use tokio::{
@@ -28,9 +28,9 @@ use xelis_common::{
 use crate::{
     core::{
         blockchain::Blockchain,
+        error::BlockchainError,
         storage::Storage
-    },
-    config::PEER_TIMEOUT_REQUEST_OBJECT
+    }
 };
 use super::{
     packet::{
@@ -41,7 +41,9 @@ use super::{
         Packet
     },
     error::P2pError,
-    peer::Peer
+    peer::Peer,
+    peer_list::SharedPeerList,
+    scheduler::{RequestScheduler, MAX_REQUEST_ATTEMPTS}
 };
 use log::{
     error,
@@ -89,11 +91,16 @@ struct Request {
     // If it linked to a group
     group_id: Option<u64>,
     // If it has to be broadcast on handling or not
-    broadcast: bool
+    broadcast: bool,
+    // Topoheight of the object if requested for the sync
+    // Only those are requested again from another peer on timeout
+    topoheight: Option<u64>,
+    // Peers that didn't answer in time
+    timed_out_peers: HashSet<u64>
 }
 
 impl Request {
-    pub fn new(request: ObjectRequest, peer: Arc<Peer>, group_id: Option<u64>, broadcast: bool) -> Self {
+    pub fn new(request: ObjectRequest, peer: Arc<Peer>, group_id: Option<u64>, broadcast: bool, topoheight: Option<u64>) -> Self {
         Self {
             request,
             peer,
@@ -101,7 +108,9 @@ impl Request {
             response: None,
             requested_at: None,
             group_id,
-            broadcast
+            broadcast,
+            topoheight,
+            timed_out_peers: HashSet::new()
         }
     }
 
@@ -113,6 +122,14 @@ impl Request {
         &self.peer
     }
 
+    pub fn set_peer(&mut self, peer: Arc<Peer>) {
+        self.peer = peer;
+    }
+
+    pub fn get_topoheight(&self) -> Option<u64> {
+        self.topoheight
+    }
+
     pub fn set_response(&mut self, response: OwnedObjectResponse) {
         self.response = Some(response);
     }
@@ -251,7 +268,10 @@ pub struct ObjectTracker {
     group: GroupManager,
     // Requests that should be ignored
     // They got canceled but already requested
-    cache: ExpirableCache
+    cache: ExpirableCache,
+    // Used to find another peer when a sync request timed out
+    peer_list: SharedPeerList,
+    scheduler: Arc<RequestScheduler>
 }
 
 // How many requests can be queued in the channel
@@ -260,11 +280,8 @@ const REQUESTER_CHANNEL_BUFFER: usize = 128;
 // It is set to 1 by default to not be spammed by the peer
 const HANDLER_CHANNEL_BUFFER: usize = 16;
 
-// Duration constant for timeout instead of building it at each iteration
-const TIME_OUT: Duration = Duration::from_millis(PEER_TIMEOUT_REQUEST_OBJECT);
-
 impl ObjectTracker {
-    pub fn new<S: Storage>(blockchain: Arc<Blockchain<S>>, server_exit: broadcast::Receiver<()>) -> SharedObjectTracker {
+    pub fn new<S: Storage>(blockchain: Arc<Blockchain<S>>, peer_list: SharedPeerList, scheduler: Arc<RequestScheduler>, server_exit: broadcast::Receiver<()>) -> SharedObjectTracker {
         let (request_sender, request_receiver) = mpsc::channel(REQUESTER_CHANNEL_BUFFER);
         let (handler_sender, handler_receiver) = mpsc::channel(HANDLER_CHANNEL_BUFFER);
 
@@ -273,7 +290,9 @@ impl ObjectTracker {
             handler_sender,
             queue: RwLock::new(Queue::new()),
             group: GroupManager::new(),
-            cache: ExpirableCache::new()
+            cache: ExpirableCache::new(),
+            peer_list,
+            scheduler
         });
         
         // start the requester task loop which send requests to peers
@@ -314,7 +333,7 @@ impl ObjectTracker {
                     break;
                 },
                 _ = interval.tick() => {
-                    self.cache.clean(TIME_OUT).await;
+                    self.cache.clean(self.scheduler.get_timeout()).await;
                 }
             }
         }
@@ -333,7 +352,11 @@ impl ObjectTracker {
     async fn handle_object_response_internal<S: Storage>(&self, blockchain: &Arc<Blockchain<S>>, response: OwnedObjectResponse, broadcast: bool, peer: &Arc<Peer>) -> Result<(), P2pError> {
         match response {
             OwnedObjectResponse::Transaction(tx, hash) => {
-                blockchain.add_tx_to_mempool_with_hash(tx, hash, broadcast).await?;
+                match blockchain.add_tx_to_mempool_with_hash(tx, hash, broadcast).await {
+                    // Refused by our local policy only, the peer did nothing wrong
+                    Err(BlockchainError::TxFilteredByPolicy(_)) => {},
+                    res => res?
+                }
             },
             OwnedObjectResponse::Block(block, _) => {
                 // We don't broadcast it to others peers but we broadcast it to our miners in case
@@ -384,6 +407,7 @@ impl ObjectTracker {
                 match request.take_response() {
                     Some(response) => {
                         let (_, request) = queue.pop().unwrap();
+                        request.get_peer().get_request_counters().on_fulfilled();
                         if let Err(e) = self.handle_object_response_internal(&blockchain, response, request.broadcast(), request.get_peer()).await {
                             if request.get_group_id().is_none() {
                                 warn!("Error while handling object response for {} in ObjectTracker from {}: {}", request.get_hash(), request.get_peer(), e);
@@ -394,8 +418,18 @@ impl ObjectTracker {
                     None => {
                         if let Some(requested_at) = request.get_requested() {
                             // check if the request is timed out
-                            if requested_at.elapsed() > TIME_OUT {
-                                warn!("Request timed out for object {}", request.get_hash());
+                            if requested_at.elapsed() > self.scheduler.get_timeout() {
+                                warn!("Request timed out for object {} from {}", request.get_hash(), request.get_peer());
+                                let peer = Arc::clone(request.get_peer());
+                                peer.get_request_counters().on_timed_out();
+                                if self.scheduler.add_strike(peer.get_id(), Instant::now()) {
+                                    warn!("{} is deprioritized for sync after too many timed out requests", peer);
+                                }
+
+                                if self.retry_request(request).await {
+                                    continue;
+                                }
+
                                 let (_, request) = queue.pop().unwrap();
                                 self.clean_queue(&mut queue, request.get_peer().get_id(), request.get_group_id().map(|v| (v, P2pError::TrackerRequestExpired))).await;
                             } else {
@@ -471,17 +505,18 @@ impl ObjectTracker {
 
     // Request the object from the peer or return false if it is already requested
     pub async fn request_object_from_peer(&self, peer: Arc<Peer>, request: ObjectRequest, broadcast: bool) -> Result<bool, P2pError> {
-        self.request_object_from_peer_with(peer, request, None, false, broadcast).await?;
+        self.request_object_from_peer_with(peer, request, None, false, broadcast, None).await?;
         Ok(true)
     }
 
     // Request the object from the peer and returns the response blocker
-    pub async fn request_object_from_peer_with(&self, peer: Arc<Peer>, request: ObjectRequest, group_id: Option<u64>, blocker: bool, broadcast: bool) -> Result<Option<ResponseBlocker>, P2pError> {
+    // If the topoheight of the object is given, it is requested from another peer on timeout
+    pub async fn request_object_from_peer_with(&self, peer: Arc<Peer>, request: ObjectRequest, group_id: Option<u64>, blocker: bool, broadcast: bool, topoheight: Option<u64>) -> Result<Option<ResponseBlocker>, P2pError> {
         trace!("Requesting object {} from {}", request.get_hash(), peer);
         let (listener, hash) = {
             let mut queue = self.queue.write().await;
             let hash = request.get_hash().clone();
-            let mut req = Request::new(request, peer, group_id, broadcast);
+            let mut req = Request::new(request, peer, group_id, broadcast, topoheight);
 
             let listener = if blocker {
                 Some(req.get_response_blocker())
@@ -516,7 +551,7 @@ impl ObjectTracker {
             }
 
             if let Some(requested_at) = request.get_requested() {
-                if requested_at.elapsed() > TIME_OUT {
+                if requested_at.elapsed() > self.scheduler.get_timeout() {
                     return true;
                 }
             }
@@ -557,6 +592,7 @@ impl ObjectTracker {
                 warn!("Error while requesting object {} using Object Tracker: {}", request_hash, e);
                 Some((peer.get_id(), request.get_group_id().map(|v| (v, e))))
             } else {
+                peer.get_request_counters().on_requested();
                 None
             }
         } else {
@@ -569,4 +605,37 @@ impl ObjectTracker {
             self.clean_queue(&mut queue, peer_id, group).await;
         }
     }
-}
\ No newline at end of file
+
+    // Request again a timed out sync object from another peer having its topoheight
+    // Returns false if it can't be requested again
+    async fn retry_request(&self, request: &mut Request) -> bool {
+        let Some(topoheight) = request.get_topoheight() else {
+            return false
+        };
+
+        request.timed_out_peers.insert(request.get_peer().get_id());
+        if request.timed_out_peers.len() >= MAX_REQUEST_ATTEMPTS {
+            return false
+        }
+
+        let peers: Vec<Arc<Peer>> = self.peer_list.get_cloned_peers().await.into_iter().collect();
+        let Some(peer) = self.scheduler.select(&peers, topoheight, &request.timed_out_peers, Instant::now()) else {
+            debug!("No other peer available to request {}", request.get_hash());
+            return false
+        };
+
+        debug!("Requesting again {} from {}", request.get_hash(), peer);
+        let packet = Bytes::from(Packet::ObjectRequest(Cow::Borrowed(request.get_object())).to_bytes());
+        if let Err(e) = peer.send_bytes(packet).await {
+            debug!("Error while requesting again {} from {}: {}", request.get_hash(), peer, e);
+            return false
+        }
+
+        peer.get_request_counters().on_requested();
+        // the slow peer may still answer once it got handled
+        self.cache.insert(request.get_hash().clone()).await;
+        request.set_peer(Arc::clone(peer));
+        request.set_requested();
+        true
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering}
+};
+
+// Parse the major, minor and patch numbers from a version
+// Build metadata (commit hash) after the '-' is ignored
+// Example: 1.8.0-58bb439 => (1, 8, 0)
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let base = version.split('-').next()?;
+    let mut parts = base.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next().map(|p| p.parse::<u64>()).transpose().ok()?.unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+// Count how many peers run each version
+pub fn count_versions<'a, I: IntoIterator<Item = &'a str>>(versions: I) -> HashMap<String, usize> {
+    let mut stats = HashMap::new();
+    for version in versions {
+        *stats.entry(version.to_owned()).or_insert(0) += 1;
+    }
+    stats
+}
+
+// Suggest to upgrade the node when enough peers run a newer minor/major version
+// The suggestion is only done once per session
+pub struct UpgradeNudge {
+    // fraction of peers required to be on a newer version, between 0 and 1
+    ratio: f64,
+    // set once the suggestion was done
+    nudged: AtomicBool
+}
+
+impl UpgradeNudge {
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            nudged: AtomicBool::new(false)
+        }
+    }
+
+    // Returns the newest version to suggest if the threshold is reached
+    // and the suggestion was not done yet in this session
+    pub fn check(&self, our_version: &str, stats: &HashMap<String, usize>) -> Option<String> {
+        if self.ratio <= 0.0 || self.nudged.load(Ordering::Acquire) {
+            return None
+        }
+
+        let (major, minor, _) = parse_version(our_version)?;
+        let total: usize = stats.values().sum();
+        if total == 0 {
+            return None
+        }
+
+        let mut newer = 0;
+        let mut newest: Option<((u64, u64, u64), &String)> = None;
+        for (version, count) in stats {
+            let Some(parsed) = parse_version(version) else {
+                continue
+            };
+
+            // Only a newer minor or major version is considered as an upgrade
+            if (parsed.0, parsed.1) > (major, minor) {
+                newer += count;
+                if newest.as_ref().map(|(v, _)| parsed > *v).unwrap_or(true) {
+                    newest = Some((parsed, version));
+                }
+            }
+        }
+
+        if (newer as f64) / (total as f64) < self.ratio {
+            return None
+        }
+
+        // Only the first caller can do the suggestion
+        if self.nudged.swap(true, Ordering::AcqRel) {
+            return None
+        }
+
+        newest.map(|(_, version)| version.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.8.0-58bb439"), Some((1, 8, 0)));
+        assert_eq!(parse_version("1.13.2"), Some((1, 13, 2)));
+        assert_eq!(parse_version("2.0"), Some((2, 0, 0)));
+        assert_eq!(parse_version("invalid"), None);
+    }
+
+    #[test]
+    fn test_count_versions() {
+        let stats = count_versions(["1.8.0-a", "1.8.0-a", "1.9.0-b"]);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["1.8.0-a"], 2);
+        assert_eq!(stats["1.9.0-b"], 1);
+    }
+
+    #[test]
+    fn test_nudge_once_per_session() {
+        let nudge = UpgradeNudge::new(0.5);
+        let stats = count_versions(["1.9.0-b", "1.9.1-c", "1.8.0-a"]);
+        assert_eq!(nudge.check("1.8.0-a", &stats), Some("1.9.1-c".to_owned()));
+        // Already suggested in this session
+        assert_eq!(nudge.check("1.8.0-a", &stats), None);
+    }
+
+    #[test]
+    fn test_nudge_threshold() {
+        let nudge = UpgradeNudge::new(0.5);
+        // Patch versions are not considered as an upgrade
+        let stats = count_versions(["1.8.1-b", "1.8.2-c", "1.9.0-d"]);
+        assert_eq!(nudge.check("1.8.0-a", &stats), None);
+
+        // Threshold reached later, nudge is still possible
+        let stats = count_versions(["1.9.0-b", "1.10.0-c", "1.8.0-d"]);
+        assert_eq!(nudge.check("1.8.0-a", &stats), Some("1.10.0-c".to_owned()));
+    }
+}
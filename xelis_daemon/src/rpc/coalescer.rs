@@ -0,0 +1,149 @@
+use xelis_common::{
+    api::daemon::FeePolicyUpdatedEvent,
+    time::TimestampMillis
+};
+
+// Value of an event that can be merged with a newer one
+pub trait Coalesce: Sized {
+    // Merge the newer value into the pending one
+    fn merge(self, newer: Self) -> Self;
+
+    // Merged values may cancel each other, nothing is sent then
+    fn is_relevant(&self) -> bool {
+        true
+    }
+}
+
+// Event carrying no value, it is built when sent
+impl Coalesce for () {
+    fn merge(self, _: Self) -> Self {}
+}
+
+// Keep the policy seen by the subscribers before the first change
+impl Coalesce for FeePolicyUpdatedEvent {
+    fn merge(self, newer: Self) -> Self {
+        Self {
+            previous: self.previous,
+            new: newer.new
+        }
+    }
+
+    fn is_relevant(&self) -> bool {
+        self.previous != self.new
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Emission<T> {
+    // Send the value now
+    Now(T),
+    // Value is pending, flush it after this delay in ms
+    Schedule(u64),
+    // Value was merged in the pending one, a flush is already scheduled
+    Coalesced
+}
+
+// Send at most one event per window
+// Values received during the window are merged and sent once at its end
+pub struct EventCoalescer<T: Coalesce> {
+    window: u64,
+    last_emission: Option<TimestampMillis>,
+    pending: Option<T>
+}
+
+impl<T: Coalesce> EventCoalescer<T> {
+    // A window of 0 sends every event
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            last_emission: None,
+            pending: None
+        }
+    }
+
+    pub fn push(&mut self, value: T, now: TimestampMillis) -> Emission<T> {
+        if let Some(pending) = self.pending.take() {
+            self.pending = Some(pending.merge(value));
+            return Emission::Coalesced
+        }
+
+        match self.last_emission {
+            Some(last) if self.window > 0 && now.saturating_sub(last) < self.window => {
+                self.pending = Some(value);
+                Emission::Schedule(self.window - now.saturating_sub(last))
+            },
+            _ => {
+                self.last_emission = Some(now);
+                Emission::Now(value)
+            }
+        }
+    }
+
+    // Take the pending value at the end of the window
+    pub fn flush(&mut self, now: TimestampMillis) -> Option<T> {
+        let value = self.pending.take()?;
+        if !value.is_relevant() {
+            return None
+        }
+
+        self.last_emission = Some(now);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::api::daemon::FeePolicy;
+    use super::*;
+
+    fn event(previous: u64, new: u64) -> FeePolicyUpdatedEvent {
+        FeePolicyUpdatedEvent {
+            previous: FeePolicy { min_relay_fee_rate: 10000, mempool_fee_rate: previous },
+            new: FeePolicy { min_relay_fee_rate: 10000, mempool_fee_rate: new },
+        }
+    }
+
+    #[test]
+    fn test_fill_drain_coalesced() {
+        let mut coalescer = EventCoalescer::new(1000);
+        // Mempool is full, first change is sent
+        assert_eq!(coalescer.push(event(0, 100), 0), Emission::Now(event(0, 100)));
+
+        // Floor keeps rising then the mempool is drained by a block
+        assert_eq!(coalescer.push(event(100, 200), 100), Emission::Schedule(900));
+        assert_eq!(coalescer.push(event(200, 300), 200), Emission::Coalesced);
+        assert_eq!(coalescer.push(event(300, 0), 300), Emission::Coalesced);
+
+        // Only the whole change is sent
+        assert_eq!(coalescer.flush(1000), Some(event(100, 0)));
+        assert_eq!(coalescer.flush(1000), None);
+
+        // New window started at the flush
+        assert_eq!(coalescer.push(event(0, 100), 1500), Emission::Schedule(500));
+        assert_eq!(coalescer.flush(2000), Some(event(0, 100)));
+        assert_eq!(coalescer.push(event(100, 0), 3000), Emission::Now(event(100, 0)));
+    }
+
+    #[test]
+    fn test_cancelled_changes() {
+        let mut coalescer = EventCoalescer::new(1000);
+        assert_eq!(coalescer.push(event(0, 100), 0), Emission::Now(event(0, 100)));
+
+        // Filled and drained back in the same window
+        assert_eq!(coalescer.push(event(100, 200), 10), Emission::Schedule(990));
+        assert_eq!(coalescer.push(event(200, 100), 20), Emission::Coalesced);
+        assert_eq!(coalescer.flush(1000), None);
+
+        // Nothing was sent, the window is still the previous one
+        assert_eq!(coalescer.push(event(100, 0), 1100), Emission::Now(event(100, 0)));
+    }
+
+    #[test]
+    fn test_no_window() {
+        let mut coalescer = EventCoalescer::new(0);
+        for i in 0..10 {
+            assert_eq!(coalescer.push((), i), Emission::Now(()));
+        }
+        assert_eq!(coalescer.flush(10), None);
+    }
+}
@@ -0,0 +1,124 @@
+use xelis_common::{
+    rpc_server::InternalRpcError,
+    serializer::Serializer
+};
+use super::ApiError;
+
+// Cursors are opaque for clients: hex of [kind][last key returned]
+// The list is ordered by key, so resuming after the last key
+// never returns a duplicate or skips an item that existed before
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CursorKind {
+    Assets = 0,
+    Accounts = 1
+}
+
+pub fn encode_cursor<T: Serializer>(kind: CursorKind, last_key: &T) -> String {
+    let mut bytes = vec![kind as u8];
+    bytes.extend(last_key.to_bytes());
+    hex::encode(bytes)
+}
+
+pub fn decode_cursor<T: Serializer>(kind: CursorKind, cursor: &str) -> Result<T, ApiError> {
+    let bytes = hex::decode(cursor).map_err(|_| ApiError::InvalidCursor)?;
+    match bytes.split_first() {
+        Some((id, key)) if *id == kind as u8 => T::from_bytes(key).map_err(|_| ApiError::InvalidCursor),
+        _ => Err(ApiError::InvalidCursor)
+    }
+}
+
+// Pagination requested by a list endpoint
+pub enum Pagination<T> {
+    // Deprecated skip/maximum pagination
+    Offset {
+        skip: usize,
+        maximum: usize
+    },
+    // Resume after the last key of the previous page, None for the first page
+    Cursor {
+        after: Option<T>,
+        limit: usize
+    }
+}
+
+// Select the pagination mode from the request params
+// The cursor mode is used as soon as a cursor or a limit is set
+pub fn parse_pagination<T: Serializer>(kind: CursorKind, skip: Option<usize>, maximum: Option<usize>, cursor: Option<&str>, limit: Option<usize>, max_items: usize) -> Result<Pagination<T>, InternalRpcError> {
+    let check_max = |value: Option<usize>| match value {
+        Some(value) if value > max_items => Err(InternalRpcError::InvalidParamsAny(ApiError::TooManyItemsRequested(max_items).into())),
+        Some(value) => Ok(value),
+        None => Ok(max_items)
+    };
+
+    if cursor.is_none() && limit.is_none() {
+        return Ok(Pagination::Offset {
+            skip: skip.unwrap_or(0),
+            maximum: check_max(maximum)?
+        })
+    }
+
+    if skip.is_some() || maximum.is_some() {
+        return Err(InternalRpcError::InvalidParamsAny(ApiError::CursorWithOffset.into()))
+    }
+
+    let after = match cursor {
+        Some(cursor) => Some(decode_cursor(kind, cursor).map_err(|e| InternalRpcError::InvalidParamsAny(e.into()))?),
+        None => None
+    };
+
+    Ok(Pagination::Cursor {
+        after,
+        limit: check_max(limit)?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        asset::AssetData,
+        crypto::Hash,
+        network::Network
+    };
+    use crate::core::storage::{AssetProvider, SledStorage};
+    use super::*;
+
+    #[test]
+    fn test_cursor_kind_mismatch() {
+        let cursor = encode_cursor(CursorKind::Assets, &Hash::zero());
+        assert!(decode_cursor::<Hash>(CursorKind::Assets, &cursor).is_ok());
+        assert!(decode_cursor::<Hash>(CursorKind::Accounts, &cursor).is_err());
+        assert!(decode_cursor::<Hash>(CursorKind::Assets, "zz").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assets_inserted_between_pages() {
+        let mut storage = SledStorage::temporary(None, Network::Dev).unwrap();
+        for i in [10u8, 20, 30, 40, 50] {
+            storage.add_asset(&Hash::new([i; 32]), AssetData::new(0, 8)).await.unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut page = 0;
+        loop {
+            let after: Option<Hash> = cursor.as_ref().map(|c| decode_cursor(CursorKind::Assets, c).unwrap());
+            let assets = storage.get_assets_after(after.as_ref(), 2, 0, u64::MAX).await.unwrap();
+            if assets.is_empty() {
+                break;
+            }
+            seen.extend(assets.iter().map(|a| a.get_asset().clone()));
+            cursor = assets.last().map(|a| encode_cursor(CursorKind::Assets, a.get_asset()));
+
+            // Register assets before and after the cursor while paginating
+            if page == 0 {
+                storage.add_asset(&Hash::new([5u8; 32]), AssetData::new(0, 8)).await.unwrap();
+                storage.add_asset(&Hash::new([35u8; 32]), AssetData::new(0, 8)).await.unwrap();
+            }
+            page += 1;
+        }
+
+        // No duplicate, no gap and the asset above the cursor is returned
+        let expected: Vec<Hash> = [10u8, 20, 30, 35, 40, 50].iter().map(|i| Hash::new([*i; 32])).collect();
+        assert_eq!(seen, expected);
+    }
+}
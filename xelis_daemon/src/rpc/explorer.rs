@@ -0,0 +1,433 @@
+use std::sync::Arc;
+use actix_web::{
+    http::header::{AUTHORIZATION, LOCATION},
+    web::{self, Data, Path, Query},
+    HttpRequest,
+    HttpResponse
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use xelis_common::{
+    context::Context,
+    crypto::{Address, Hash},
+    rpc_server::{
+        ClientAuthorization,
+        ClientIp,
+        ConcurrencyGuard,
+        Id,
+        RPCHandler,
+        RPCServerHandler,
+        RpcRequest,
+        JSON_RPC_VERSION
+    },
+    utils::format_xelis
+};
+use crate::core::{blockchain::Blockchain, storage::Storage};
+
+// Blocks shown on the explorer home page
+const RECENT_BLOCKS: u64 = 20;
+
+// Minimal block explorer rendered server side
+// Pages are built from the RPC methods so they follow the same rules
+// (rate limit, authorization) as the JSON RPC endpoint
+pub fn configure<S, H>(cfg: &mut web::ServiceConfig)
+where
+    S: Storage,
+    H: RPCServerHandler<Arc<Blockchain<S>>> + 'static
+{
+    cfg.route("/explorer", web::get().to(index::<S, H>))
+        .route("/explorer/block/{hash}", web::get().to(block::<S, H>))
+        .route("/explorer/tx/{hash}", web::get().to(transaction::<S, H>))
+        .route("/explorer/address/{address}", web::get().to(address::<S, H>))
+        .route("/explorer/search", web::get().to(search::<S, H>));
+}
+
+// Call the RPC methods with the context of the HTTP client
+struct Explorer<'a, S: Storage> {
+    handler: &'a RPCHandler<Arc<Blockchain<S>>>,
+    context: Context,
+    // kept until the page is rendered
+    _guard: Option<ConcurrencyGuard>
+}
+
+impl<'a, S: Storage> Explorer<'a, S> {
+    fn new<H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: &'a H, request: &HttpRequest) -> Result<Self, HttpResponse> {
+        let handler = server.get_rpc_handler();
+        let mut context = Context::new();
+        context.store(handler.get_data().clone());
+        if let Some(value) = request.headers().get(AUTHORIZATION) {
+            context.store(ClientAuthorization(value.clone()));
+        }
+
+        let guard = match server.get_rate_limiter() {
+            Some(limiter) => {
+                let ip = limiter.get_client_ip(request.headers(), request.peer_addr())
+                    .ok_or_else(|| HttpResponse::BadRequest().body("Unknown client address"))?;
+                limiter.check(&ip).map_err(|e| HttpResponse::TooManyRequests().body(e.to_string()))?;
+                let guard = limiter.acquire(ip).map_err(|e| HttpResponse::TooManyRequests().body(e.to_string()))?;
+                context.store(ClientIp(ip));
+                Some(guard)
+            },
+            None => None
+        };
+
+        Ok(Self {
+            handler,
+            context,
+            _guard: guard
+        })
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let request = RpcRequest {
+            jsonrpc: JSON_RPC_VERSION.to_owned(),
+            id: Some(Id::Number(0)),
+            method: method.to_owned(),
+            params: if params.is_null() { None } else { Some(params) }
+        };
+
+        match self.handler.execute_method(&self.context, request).await {
+            Ok(Some(mut response)) => Ok(response["result"].take()),
+            Ok(None) => Ok(Value::Null),
+            Err(e) => Err(e.to_json()["error"]["message"].as_str().unwrap_or("Unknown error").to_owned())
+        }
+    }
+}
+
+macro_rules! explorer {
+    ($server: expr, $request: expr) => {
+        match Explorer::<S>::new($server.get_ref(), &$request) {
+            Ok(explorer) => explorer,
+            Err(response) => return response
+        }
+    };
+}
+
+async fn index<S: Storage, H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: Data<H>, request: HttpRequest) -> HttpResponse {
+    let explorer = explorer!(server, request);
+    let info = match explorer.call("get_info", Value::Null).await {
+        Ok(info) => info,
+        Err(e) => return error_page(&e)
+    };
+
+    let topoheight = info["topoheight"].as_u64().unwrap_or(0);
+    let params = json!({
+        "start_topoheight": topoheight.saturating_sub(RECENT_BLOCKS - 1),
+        "end_topoheight": topoheight
+    });
+    let blocks = match explorer.call("get_blocks_range_by_topoheight", params).await {
+        Ok(blocks) => blocks,
+        Err(e) => return error_page(&e)
+    };
+
+    let mut body = table(&[
+        ("Network", text(&info["network"])),
+        ("Height", text(&info["height"])),
+        ("TopoHeight", text(&info["topoheight"])),
+        ("Stable height", text(&info["stableheight"])),
+        ("Mempool size", text(&info["mempool_size"])),
+        ("Circulating supply", amount(&info["circulating_supply"]))
+    ]);
+
+    body.push_str("<h2>Recent blocks</h2><table><tr><th>TopoHeight</th><th>Height</th><th>Hash</th><th>Type</th><th>Txs</th><th>Miner</th></tr>");
+    for block in blocks.as_array().into_iter().flatten().rev() {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            text(&block["topoheight"]),
+            text(&block["height"]),
+            link("block", &block["hash"]),
+            text(&block["block_type"]),
+            block["txs_hashes"].as_array().map_or(0, |txs| txs.len()),
+            link("address", &block["miner"])
+        ));
+    }
+    body.push_str("</table>");
+
+    page("XELIS Explorer", &body)
+}
+
+async fn block<S: Storage, H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: Data<H>, request: HttpRequest, path: Path<String>) -> HttpResponse {
+    let explorer = explorer!(server, request);
+    let block = match explorer.call("get_block_by_hash", json!({ "hash": path.into_inner(), "include_txs": true })).await {
+        Ok(block) => block,
+        Err(e) => return error_page(&e)
+    };
+
+    let tips = block["tips"].as_array().into_iter().flatten().map(|tip| link("block", tip)).collect::<Vec<_>>().join("<br>");
+    let mut body = table(&[
+        ("Hash", text(&block["hash"])),
+        ("TopoHeight", text(&block["topoheight"])),
+        ("Height", text(&block["height"])),
+        ("Type", text(&block["block_type"])),
+        ("Timestamp", text(&block["timestamp"])),
+        ("Difficulty", text(&block["difficulty"])),
+        ("Miner", link("address", &block["miner"])),
+        ("Reward", amount(&block["reward"])),
+        ("Total fees", amount(&block["total_fees"])),
+        ("Size", format!("{} bytes", text(&block["total_size_in_bytes"]))),
+        ("Tips", tips)
+    ]);
+
+    body.push_str("<h2>Transactions</h2><table><tr><th>Hash</th><th>Source</th><th>Fee</th><th>Nonce</th></tr>");
+    for tx in block["transactions"].as_array().into_iter().flatten() {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            link("tx", &tx["hash"]),
+            link("address", &tx["source"]),
+            amount(&tx["fee"]),
+            text(&tx["nonce"])
+        ));
+    }
+    body.push_str("</table>");
+
+    page("Block", &body)
+}
+
+async fn transaction<S: Storage, H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: Data<H>, request: HttpRequest, path: Path<String>) -> HttpResponse {
+    let explorer = explorer!(server, request);
+    let tx = match explorer.call("get_transaction", json!({ "hash": path.into_inner() })).await {
+        Ok(tx) => tx,
+        Err(e) => return error_page(&e)
+    };
+
+    let blocks = tx["blocks"].as_array().into_iter().flatten().map(|hash| link("block", hash)).collect::<Vec<_>>().join("<br>");
+    let mut body = table(&[
+        ("Hash", text(&tx["hash"])),
+        ("Source", link("address", &tx["source"])),
+        ("Fee", amount(&tx["fee"])),
+        ("Nonce", text(&tx["nonce"])),
+        ("In mempool", text(&tx["in_mempool"])),
+        ("Executed in", link("block", &tx["executed_in_block"])),
+        ("Included in", blocks)
+    ]);
+
+    if let Some(transfers) = tx["data"]["transfers"].as_array() {
+        // amounts are encrypted, only the destinations and assets are public
+        body.push_str("<h2>Transfers</h2><table><tr><th>Asset</th><th>Destination</th></tr>");
+        for transfer in transfers {
+            body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", text(&transfer["asset"]), link("address", &transfer["destination"])));
+        }
+        body.push_str("</table>");
+    } else if let Some(burn) = tx["data"].get("burn") {
+        body.push_str("<h2>Burn</h2>");
+        body.push_str(&table(&[
+            ("Asset", text(&burn["asset"])),
+            ("Amount", text(&burn["amount"]))
+        ]));
+    }
+
+    page("Transaction", &body)
+}
+
+async fn address<S: Storage, H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: Data<H>, request: HttpRequest, path: Path<String>) -> HttpResponse {
+    let explorer = explorer!(server, request);
+    let address = path.into_inner();
+    let registration = match explorer.call("get_account_registration_topoheight", json!({ "address": address })).await {
+        Ok(topoheight) => text(&topoheight),
+        Err(e) => return error_page(&e)
+    };
+    let nonce = match explorer.call("get_nonce", json!({ "address": address })).await {
+        Ok(nonce) => text(&nonce["nonce"]),
+        Err(_) => "-".to_owned()
+    };
+
+    let body = table(&[
+        ("Address", escape(&address)),
+        ("Registered at topoheight", registration),
+        ("Nonce", nonce),
+        ("Balances", "Encrypted, only the owner can see them".to_owned())
+    ]);
+
+    page("Address", &body)
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String
+}
+
+// Search a block by height or hash, a TX by hash or an address
+async fn search<S: Storage, H: RPCServerHandler<Arc<Blockchain<S>>> + 'static>(server: Data<H>, request: HttpRequest, query: Query<SearchQuery>) -> HttpResponse {
+    let explorer = explorer!(server, request);
+    let query = query.q.trim();
+
+    if let Ok(height) = query.parse::<u64>() {
+        return match explorer.call("get_blocks_at_height", json!({ "height": height })).await {
+            Ok(blocks) => match blocks.as_array().map(|blocks| blocks.as_slice()) {
+                Some([block]) => redirect("block", &block["hash"]),
+                Some(blocks) if !blocks.is_empty() => {
+                    let mut body = format!("<h2>Blocks at height {}</h2><table>", height);
+                    for block in blocks {
+                        body.push_str(&format!("<tr><td>{}</td></tr>", link("block", &block["hash"])));
+                    }
+                    body.push_str("</table>");
+                    page("Search", &body)
+                },
+                _ => not_found(query)
+            },
+            Err(e) => error_page(&e)
+        }
+    }
+
+    if Hash::from_hex(query.to_owned()).is_ok() {
+        if explorer.call("get_block_by_hash", json!({ "hash": query })).await.is_ok() {
+            return redirect("block", &json!(query))
+        }
+        if explorer.call("get_transaction", json!({ "hash": query })).await.is_ok() {
+            return redirect("tx", &json!(query))
+        }
+        return not_found(query)
+    }
+
+    if Address::from_string(&query.to_owned()).is_ok() {
+        return redirect("address", &json!(query))
+    }
+
+    not_found(query)
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1100px;padding:0 1em;color:#222}\
+a{color:#0a7d6d;text-decoration:none}a:hover{text-decoration:underline}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5em}\
+td,th{border-bottom:1px solid #ddd;padding:.4em;text-align:left;font-family:monospace;word-break:break-all}\
+th{background:#f4f4f4}form{margin-bottom:1.5em}input[type=text]{width:70%;padding:.4em}";
+
+fn page(title: &str, body: &str) -> HttpResponse {
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>\
+        <h1><a href=\"/explorer\">XELIS Explorer</a></h1>\
+        <form action=\"/explorer/search\" method=\"get\"><input type=\"text\" name=\"q\" placeholder=\"Block hash, height, TX hash or address\"> <input type=\"submit\" value=\"Search\"></form>\
+        {}</body></html>",
+        escape(title), STYLE, body
+    );
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(html)
+}
+
+fn error_page(error: &str) -> HttpResponse {
+    let mut response = page("Error", &format!("<p>Error: {}</p>", escape(error)));
+    *response.status_mut() = actix_web::http::StatusCode::NOT_FOUND;
+    response
+}
+
+fn not_found(query: &str) -> HttpResponse {
+    error_page(&format!("nothing found for '{}'", query))
+}
+
+fn redirect(kind: &str, value: &Value) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((LOCATION, format!("/explorer/{}/{}", kind, value.as_str().unwrap_or_default())))
+        .finish()
+}
+
+fn table(rows: &[(&str, String)]) -> String {
+    let mut html = String::from("<table>");
+    for (name, value) in rows {
+        html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>", name, value));
+    }
+    html.push_str("</table>");
+    html
+}
+
+// Display a JSON value, escaped
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_owned(),
+        Value::String(value) => escape(value),
+        value => escape(&value.to_string())
+    }
+}
+
+fn amount(value: &Value) -> String {
+    value.as_u64().map(format_xelis).unwrap_or_else(|| text(value))
+}
+
+fn link(kind: &str, value: &Value) -> String {
+    match value.as_str() {
+        Some(value) => {
+            let value = escape(value);
+            format!("<a href=\"/explorer/{}/{}\">{}</a>", kind, value, value)
+        },
+        None => text(value)
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+    use crate::{
+        core::{storage::{DagOrderProvider, SledStorage}, testing::test_blockchain},
+        rpc::rpc
+    };
+    use super::*;
+
+    struct TestServer {
+        handler: RPCHandler<Arc<Blockchain<SledStorage>>>
+    }
+
+    impl RPCServerHandler<Arc<Blockchain<SledStorage>>> for TestServer {
+        fn get_rpc_handler(&self) -> &RPCHandler<Arc<Blockchain<SledStorage>>> {
+            &self.handler
+        }
+    }
+
+    async fn body(response: actix_web::dev::ServiceResponse) -> String {
+        String::from_utf8(test::read_body(response).await.to_vec()).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_explorer_routes() {
+        let blockchain = test_blockchain().await;
+        let genesis = blockchain.get_storage().read().await.get_hash_at_topo_height(0).await.unwrap();
+
+        let mut handler = RPCHandler::new(blockchain);
+        rpc::register_methods(&mut handler, false);
+        let app = test::init_service(
+            App::new().app_data(Data::new(TestServer { handler })).configure(configure::<SledStorage, TestServer>)
+        ).await;
+
+        // Home page lists the genesis block
+        let response = test::call_service(&app, test::TestRequest::get().uri("/explorer").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = body(response).await;
+        assert!(html.contains(&format!("/explorer/block/{}", genesis)));
+        assert!(html.contains("Recent blocks"));
+
+        let response = test::call_service(&app, test::TestRequest::get().uri(&format!("/explorer/block/{}", genesis)).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = body(response).await;
+        assert!(html.contains(&genesis.to_string()));
+        assert!(html.contains("Transactions"));
+
+        // Height and hash are redirected to the block page
+        for query in ["0".to_owned(), genesis.to_string()] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(&format!("/explorer/search?q={}", query)).to_request()).await;
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+            assert_eq!(response.headers().get(LOCATION).unwrap().to_str().unwrap(), format!("/explorer/block/{}", genesis));
+        }
+
+        // Unknown values are escaped in the error page
+        let response = test::call_service(&app, test::TestRequest::get().uri("/explorer/search?q=%3Cscript%3E").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let html = body(response).await;
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+
+        let response = test::call_service(&app, test::TestRequest::get().uri(&format!("/explorer/tx/{}", Hash::zero())).to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
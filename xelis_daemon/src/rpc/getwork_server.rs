@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Display,
     num::NonZeroUsize,
     sync::{
@@ -38,6 +38,7 @@ use tokio::sync::Mutex;
 use xelis_common::{
     api::daemon::{
         GetMinerWorkResult,
+        SubmitBlockStatus,
         SubmitMinerWorkParams
     },
     block::{
@@ -49,8 +50,10 @@ use xelis_common::{
         Hashable,
         PublicKey
     },
-    difficulty::Difficulty,
-    immutable::Immutable,
+    difficulty::{
+        check_difficulty,
+        Difficulty
+    },
     rpc_server::{
         InternalRpcError,
         RpcResponseError
@@ -60,7 +63,11 @@ use xelis_common::{
         get_current_time_in_millis,
         TimestampMillis
     },
-    utils::spawn_task
+    utils::{
+        format_difficulty,
+        format_hashrate,
+        spawn_task
+    }
 };
 use crate::{
     core::{
@@ -69,6 +76,11 @@ use crate::{
     },
     config::{
         DEV_PUBLIC_KEY,
+        GETWORK_INITIAL_SHARE_DIFFICULTY_DIVISOR,
+        GETWORK_MIN_SHARE_DIFFICULTY,
+        GETWORK_SHARE_RETARGET_DELAY,
+        GETWORK_SHARE_RETARGET_MAX_FACTOR,
+        MILLIS_PER_SECOND,
         STABLE_LIMIT
     }
 };
@@ -80,7 +92,9 @@ pub type SharedGetWorkServer<S> = Arc<GetWorkServer<S>>;
 pub enum Response {
     NewJob(GetMinerWorkResult),
     BlockAccepted,
-    BlockRejected(String)
+    BlockRejected(String),
+    ShareAccepted,
+    ShareRejected(String)
 }
 
 impl TMessage for Response {
@@ -101,19 +115,42 @@ pub struct Miner {
     // blocks rejected since he is connected
     blocks_rejected: usize,
     // timestamp of the last invalid block received
-    last_invalid_block: TimestampMillis
+    last_invalid_block: TimestampMillis,
+    // share difficulty sent in jobs, None if shares are disabled
+    share_difficulty: Option<Difficulty>,
+    // share difficulty before the last retarget
+    // still accepted for shares found on a previous job
+    previous_share_difficulty: Option<Difficulty>,
+    // shares accepted since he is connected
+    shares_accepted: u64,
+    // shares rejected since he is connected
+    shares_rejected: u64,
+    // sum of the difficulty of all shares accepted
+    shares_work: Difficulty,
+    // shares accepted since the last retarget
+    shares_since_retarget: u64,
+    // timestamp of the last share difficulty retarget
+    last_retarget: TimestampMillis
 }
 
 impl Miner {
     pub fn new(mainnet: bool, key: PublicKey, name: String) -> Self {
+        let now = get_current_time_in_millis();
         Self {
             mainnet,
-            first_seen: get_current_time_in_millis(),
+            first_seen: now,
             key,
             name,
             blocks_accepted: IndexSet::new(),
             blocks_rejected: 0,
-            last_invalid_block: 0
+            last_invalid_block: 0,
+            share_difficulty: None,
+            previous_share_difficulty: None,
+            shares_accepted: 0,
+            shares_rejected: 0,
+            shares_work: Difficulty::zero(),
+            shares_since_retarget: 0,
+            last_retarget: now
         }
     }
 
@@ -132,12 +169,80 @@ impl Miner {
     pub fn get_blocks_accepted(&self) -> usize {
         self.blocks_accepted.len()
     }
+
+    pub fn get_shares_accepted(&self) -> u64 {
+        self.shares_accepted
+    }
+
+    pub fn get_shares_rejected(&self) -> u64 {
+        self.shares_rejected
+    }
+
+    pub fn get_share_difficulty(&self) -> Option<&Difficulty> {
+        self.share_difficulty.as_ref()
+    }
+
+    // Lowest share difficulty accepted for a share submitted by this miner
+    fn get_accepted_share_difficulty(&self) -> Option<Difficulty> {
+        match (self.share_difficulty, self.previous_share_difficulty) {
+            (Some(current), Some(previous)) => Some(current.min(previous)),
+            (current, _) => current
+        }
+    }
+
+    // Effective hashrate based on the shares accepted since he is connected
+    pub fn get_effective_hashrate(&self) -> f64 {
+        let elapsed = get_current_time_in_millis().saturating_sub(self.first_seen).max(1);
+        let work: f64 = self.shares_work.into();
+        work * MILLIS_PER_SECOND as f64 / elapsed as f64
+    }
+
+    // Retarget the share difficulty so the miner send around `shares_per_minute` shares
+    // The first call set the initial share difficulty based on the network difficulty
+    // Returns true if the share difficulty has changed
+    fn retarget_share_difficulty(&mut self, shares_per_minute: u64, network_difficulty: &Difficulty, force: bool) -> bool {
+        let max = (*network_difficulty).max(GETWORK_MIN_SHARE_DIFFICULTY);
+        let now = get_current_time_in_millis();
+        let new_difficulty = match self.share_difficulty {
+            None => *network_difficulty / GETWORK_INITIAL_SHARE_DIFFICULTY_DIVISOR,
+            Some(current) => {
+                let elapsed = now.saturating_sub(self.last_retarget);
+                // Wait enough time to have a correct estimation
+                // unless we receive already more shares than expected in a minute
+                if !force && elapsed < GETWORK_SHARE_RETARGET_DELAY {
+                    return false
+                }
+
+                let lowest = current / GETWORK_SHARE_RETARGET_MAX_FACTOR;
+                let highest = current * GETWORK_SHARE_RETARGET_MAX_FACTOR;
+                // difficulty * shares found / shares expected in the elapsed time
+                let expected = current * self.shares_since_retarget * (60 * MILLIS_PER_SECOND) / (elapsed.max(1) * shares_per_minute);
+                expected.max(lowest).min(highest)
+            }
+        };
+
+        let new_difficulty = new_difficulty.max(GETWORK_MIN_SHARE_DIFFICULTY).min(max);
+        self.shares_since_retarget = 0;
+        self.last_retarget = now;
+        if self.share_difficulty == Some(new_difficulty) {
+            return false
+        }
+
+        debug!("Retarget share difficulty of {} to {}", self.name, format_difficulty(new_difficulty));
+        self.previous_share_difficulty = self.share_difficulty;
+        self.share_difficulty = Some(new_difficulty);
+        true
+    }
 }
 
 impl Display for Miner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let valid_blocks = self.blocks_accepted.iter().map(|h| h.to_string()).collect::<Vec<_>>().join(",");
-        write!(f, "Miner[address={}, name={}, accepted={} ({}), rejected={}]", self.key.as_address(self.mainnet), self.name, self.blocks_accepted.len(), valid_blocks, self.blocks_rejected)
+        write!(f, "Miner[address={}, name={}, accepted={} ({}), rejected={}", self.key.as_address(self.mainnet), self.name, self.blocks_accepted.len(), valid_blocks, self.blocks_rejected)?;
+        if let Some(share_difficulty) = &self.share_difficulty {
+            write!(f, ", shares={}/{}, share difficulty={}, hashrate={}", self.shares_accepted, self.shares_rejected, format_difficulty(*share_difficulty), format_hashrate(self.get_effective_hashrate()))?;
+        }
+        write!(f, "]")
     }
 }
 
@@ -212,23 +317,47 @@ pub struct GetWorkServer<S: Storage> {
     // all potential jobs sent to miners
     // we can keep them in cache up to STABLE_LIMIT blocks
     // so even a late miner have a chance to not be orphaned and be included in chain
-    mining_jobs: Mutex<LruCache<Hash, (BlockHeader, Difficulty)>>,
+    // with the aux commitment used when the job was created
+    mining_jobs: Mutex<LruCache<Hash, (BlockHeader, Difficulty, Option<Hash>)>>,
+    // hashes of the shares accepted for each job, a share can't be counted twice
+    accepted_shares: Mutex<LruCache<Hash, HashSet<Hash>>>,
     last_header_hash: Mutex<Option<Hash>>,
     // used only when a new TX is received in mempool
     last_notify: AtomicU64,
-    notify_rate_limit_ms: u64
+    notify_rate_limit_ms: u64,
+    // expected shares per minute for each miner (0 = shares disabled)
+    shares_per_minute: u64
 }
 
 impl<S: Storage> GetWorkServer<S> {
-    pub fn new(blockchain: Arc<Blockchain<S>>) -> Self {
+    pub fn new(blockchain: Arc<Blockchain<S>>, shares_per_minute: u64) -> Self {
         Self {
             miners: Mutex::new(HashMap::new()),
             blockchain,
             mining_jobs: Mutex::new(LruCache::new(NonZeroUsize::new(STABLE_LIMIT as usize).unwrap())),
+            accepted_shares: Mutex::new(LruCache::new(NonZeroUsize::new(STABLE_LIMIT as usize).unwrap())),
             last_header_hash: Mutex::new(None),
             last_notify: AtomicU64::new(0),
-            notify_rate_limit_ms: 500 // maximum one time every 500ms
+            notify_rate_limit_ms: 500, // maximum one time every 500ms
+            shares_per_minute
+        }
+    }
+
+    // Are shares enabled for the miners
+    pub fn is_share_enabled(&self) -> bool {
+        self.shares_per_minute > 0
+    }
+
+    // Retarget if needed and returns the share difficulty to send to this miner
+    async fn get_share_difficulty_for(&self, addr: &Addr<GetWorkWebSocketHandler<S>>, network_difficulty: &Difficulty) -> Option<Difficulty> {
+        if !self.is_share_enabled() {
+            return None
         }
+
+        let mut miners = self.miners.lock().await;
+        let miner = miners.get_mut(addr)?;
+        miner.retarget_share_difficulty(self.shares_per_minute, network_difficulty, false);
+        miner.share_difficulty
     }
 
     // Returns the number of miners connected to the getwork server
@@ -247,22 +376,24 @@ impl<S: Storage> GetWorkServer<S> {
     // then, send it
     async fn send_new_job(self: Arc<Self>, addr: Addr<GetWorkWebSocketHandler<S>>, key: PublicKey) -> Result<(), InternalRpcError> {
         debug!("Sending new job to miner");
-        let (mut job, height, difficulty) = {
+        let (mut job, height, difficulty, aux_commitment) = {
             let mut hash = self.last_header_hash.lock().await;
             let mut mining_jobs = self.mining_jobs.lock().await;
-            let (job, height, difficulty);
+            let (job, height, difficulty, aux_commitment);
             // if we have a job in cache, and we are rate limited, we can send it
             // otherwise, we generate a new job
             if let Some(hash) = hash.as_ref().filter(|_| self.is_rate_limited().0) {
-                let (header, diff) = mining_jobs.peek(hash).ok_or_else(|| {
+                let (header, diff, aux) = mining_jobs.peek(hash).ok_or_else(|| {
                     error!("No mining job found! How is it possible ?");
                     InternalRpcError::InternalError("No mining job found")
                 })?;
                 job = MinerWork::new(header.get_work_hash(), get_current_time_in_millis());
                 height = header.height;
                 difficulty = *diff;
+                aux_commitment = aux.clone();
             } else {
                 // generate a mining job
+                aux_commitment = self.blockchain.get_aux_commitment().await;
                 let storage = self.blockchain.get_storage().read().await;
                 let header = self.blockchain.get_block_template_for_storage(&storage, DEV_PUBLIC_KEY.clone()).await.context("Error while retrieving block template")?;
                 (difficulty, _) = self.blockchain.get_difficulty_at_tips(&*storage, header.get_tips().iter()).await.context("Error while retrieving difficulty at tips")?;
@@ -273,19 +404,20 @@ impl<S: Storage> GetWorkServer<S> {
                 // save the mining job, and set it as last job
                 let header_work_hash = job.get_header_work_hash();
                 *hash = Some(header_work_hash.clone());
-                mining_jobs.put(header_work_hash.clone(), (header, difficulty));
+                mining_jobs.put(header_work_hash.clone(), (header, difficulty, aux_commitment.clone()));
             }
 
-            (job, height, difficulty)
+            (job, height, difficulty, aux_commitment)
         };
 
         // set miner key and random extra nonce
         job.set_miner(Cow::Owned(key));
-        OsRng.fill_bytes(job.get_extra_nonce());
+        fill_extra_nonce(&mut job, aux_commitment.as_ref());
 
         let topoheight = self.blockchain.get_topo_height();
+        let share_difficulty = self.get_share_difficulty_for(&addr, &difficulty).await;
         debug!("Sending job to new miner");
-        addr.send(Response::NewJob(GetMinerWorkResult { template: job.to_hex(), height, topoheight, difficulty })).await.context("error while sending block template")??;
+        addr.send(Response::NewJob(GetMinerWorkResult { template: job.to_hex(), height, topoheight, difficulty, share_difficulty })).await.context("error while sending block template")??;
         Ok(())
     }
 
@@ -319,19 +451,29 @@ impl<S: Storage> GetWorkServer<S> {
     // we retrieve the block header saved in cache using the mining job "header_work_hash"
     // its used to check that the job come from our server
     // when it's found, we merge the miner job inside the block header
-    async fn accept_miner_job(&self, job: MinerWork<'_>) -> Result<(Response, Hash), InternalRpcError> {
+    // if a share difficulty is set, a POW not meeting the block difficulty is handled as a share
+    async fn accept_miner_job(&self, job: MinerWork<'_>, share_difficulty: Option<Difficulty>) -> Result<(Response, Hash), InternalRpcError> {
         trace!("accept miner job");
         if job.get_miner().is_none() {
             return Err(InternalRpcError::InvalidJSONRequest);
         }
 
+        let job_hash = job.get_header_work_hash().clone();
         let mut miner_header;
+        let difficulty;
         {
             let mining_jobs = self.mining_jobs.lock().await;
-            if let Some((header, _)) = mining_jobs.peek(job.get_header_work_hash()) {
+            if let Some((header, diff, aux_commitment)) = mining_jobs.peek(job.get_header_work_hash()) {
+                // The miner must keep the aux commitment of its job
+                if aux_commitment.as_ref().is_some_and(|commitment| !job.has_aux_commitment(commitment)) {
+                    debug!("Job {} doesn't contain the aux commitment", job.get_header_work_hash());
+                    return Err(InternalRpcError::InvalidParams("Extra nonce doesn't contain the aux commitment"))
+                }
+
                 // job is found in cache, clone it and put miner data inside
                 miner_header = header.clone();
                 miner_header.apply_miner_work(job);
+                difficulty = *diff;
             } else {
                 // really old job, or miner send invalid job
                 debug!("Job {} was not found in cache", job.get_header_work_hash());
@@ -339,10 +481,49 @@ impl<S: Storage> GetWorkServer<S> {
             };
         }
 
-        let block = self.blockchain.build_block_from_header(Immutable::Owned(miner_header)).await.context("Error while building block from header")?;
-        let block_hash = block.hash();
-        Ok(match self.blockchain.add_new_block(block, true, true).await {
-            Ok(_) => (Response::BlockAccepted, block_hash),
+        // A block solution is always submitted to the chain, shares are only counted
+        if let Some(share_difficulty) = share_difficulty {
+            let hash = miner_header.hash();
+            if self.is_share_accepted(&job_hash, &hash).await {
+                return Ok((Response::ShareRejected("Share was already submitted".to_owned()), hash))
+            }
+
+            // PoW hashing is CPU heavy, don't block the runtime with it
+            let blockchain = Arc::clone(&self.blockchain);
+            let (header, pow_hash) = tokio::task::spawn_blocking(move || {
+                let pow_hash = blockchain.get_pow_hash(&miner_header);
+                (miner_header, pow_hash)
+            }).await.context("Error while waiting on the POW hash of the share")?;
+            miner_header = header;
+
+            let pow_hash = pow_hash.context("Error while computing POW hash of the share")?;
+            if !check_difficulty(&pow_hash, &difficulty).context("Error while checking block difficulty")? {
+                if !check_difficulty(&pow_hash, &share_difficulty).context("Error while checking share difficulty")? {
+                    return Ok((Response::ShareRejected("Share difficulty is too low".to_owned()), hash))
+                }
+
+                // Same share may have been submitted while we were hashing it
+                let mut accepted_shares = self.accepted_shares.lock().await;
+                let inserted = match accepted_shares.get_mut(&job_hash) {
+                    Some(shares) => shares.insert(hash.clone()),
+                    None => {
+                        accepted_shares.put(job_hash, HashSet::from([hash.clone()]));
+                        true
+                    }
+                };
+                return Ok(if inserted {
+                    (Response::ShareAccepted, hash)
+                } else {
+                    (Response::ShareRejected("Share was already submitted".to_owned()), hash)
+                })
+            }
+        }
+
+        let block_hash = miner_header.hash();
+        Ok(match self.blockchain.submit_mined_block(miner_header).await {
+            Ok((SubmitBlockStatus::Accepted, block_hash)) => (Response::BlockAccepted, block_hash),
+            Ok((SubmitBlockStatus::StaleTips, block_hash)) => (Response::BlockRejected("Block was built on stale tips".to_owned()), block_hash),
+            Ok((SubmitBlockStatus::InvalidPow, block_hash)) => (Response::BlockRejected("Invalid PoW".to_owned()), block_hash),
             Err(e) => {
                 debug!("Error while accepting miner block: {}", e);
                 (Response::BlockRejected(e.to_string()), block_hash)
@@ -350,13 +531,26 @@ impl<S: Storage> GetWorkServer<S> {
         })
     }
 
+    // Was this share already accepted for this job
+    async fn is_share_accepted(&self, job_hash: &Hash, hash: &Hash) -> bool {
+        let accepted_shares = self.accepted_shares.lock().await;
+        accepted_shares.peek(job_hash).is_some_and(|shares| shares.contains(hash))
+    }
+
     // handle the incoming mining job from the miner
     // decode the block miner, and using its header work hash, retrieve the block header
     // if its block is rejected, resend him the job
     pub async fn handle_block_for(self: Arc<Self>, addr: Addr<GetWorkWebSocketHandler<S>>, submitted_work: SubmitMinerWorkParams) {
         trace!("handle block for");
+        let share_difficulty = if self.is_share_enabled() {
+            let miners = self.miners.lock().await;
+            miners.get(&addr).and_then(|miner| miner.get_accepted_share_difficulty())
+        } else {
+            None
+        };
+
         let (response, hash) = match MinerWork::from_hex(submitted_work.miner_work) {
-            Ok(job) => match self.accept_miner_job(job).await {
+            Ok(job) => match self.accept_miner_job(job, share_difficulty).await {
                 Ok((response, hash)) => (response, Some(hash)),
                 Err(e) => {
                     debug!("Error while accepting miner job: {}", e);
@@ -370,6 +564,7 @@ impl<S: Storage> GetWorkServer<S> {
         };
 
         // update miner stats
+        let mut retargeted = false;
         {
             let mut miners = self.miners.lock().await;
             if let Some(miner) = miners.get_mut(&addr) {
@@ -384,13 +579,32 @@ impl<S: Storage> GetWorkServer<S> {
                         miner.blocks_rejected += 1;
                         miner.last_invalid_block = get_current_time_in_millis();
                     },
+                    Response::ShareAccepted => {
+                        trace!("Miner {} found a share", miner);
+                        miner.shares_accepted += 1;
+                        miner.shares_since_retarget += 1;
+                        if let Some(share_difficulty) = share_difficulty {
+                            miner.shares_work += share_difficulty;
+                        }
+
+                        // Miner is sending too many shares, retarget without waiting
+                        if miner.shares_since_retarget >= self.shares_per_minute {
+                            let network_difficulty = self.blockchain.get_difficulty().await;
+                            retargeted = miner.retarget_share_difficulty(self.shares_per_minute, &network_difficulty, true);
+                        }
+                    },
+                    Response::ShareRejected(_) => {
+                        debug!("Miner {} sent an invalid share", miner);
+                        miner.shares_rejected += 1;
+                    },
                     _ => {}
                 }
             }
         }
 
         spawn_task("getwork-reply", async move {
-            let resend_job = match response {
+            // Resend the job if the block was rejected or to apply the new share difficulty
+            let resend_job = retargeted || match response {
                 Response::BlockRejected(_) => true,
                 _ => false
             };
@@ -444,19 +658,9 @@ impl<S: Storage> GetWorkServer<S> {
 
     // notify every miners connected to the getwork server
     // each miner have his own task so nobody wait on other
-    pub async fn notify_new_job(&self) -> Result<(), InternalRpcError> {
-        trace!("notify new job");
-        // Check that there is at least one miner connected
-        // otherwise, no need to build a new job
-        {
-            let miners = self.miners.lock().await;
-            if miners.is_empty() {
-                debug!("No miners connected, no need to notify them");
-                return Ok(());
-            }
-        }
-    
-        debug!("Notify all miners for a new job");
+    // Build a job from a new block template and save it in cache as the last job
+    async fn create_job<'a>(&self) -> Result<(MinerWork<'a>, u64, Difficulty, Option<Hash>), InternalRpcError> {
+        let aux_commitment = self.blockchain.get_aux_commitment().await;
         let (header, difficulty) = {
             let storage = self.blockchain.get_storage().read().await;
             let header = self.blockchain.get_block_template_for_storage(&storage, DEV_PUBLIC_KEY.clone()).await.context("Error while retrieving block template when notifying new job")?;
@@ -464,7 +668,7 @@ impl<S: Storage> GetWorkServer<S> {
             (header, difficulty)
         };
 
-        let mut job = MinerWork::new(header.get_work_hash(), header.timestamp);
+        let job = MinerWork::new(header.get_work_hash(), header.timestamp);
         let height = header.height;
 
         // save the header used for job in cache
@@ -473,26 +677,50 @@ impl<S: Storage> GetWorkServer<S> {
             let mut last_header_hash = self.last_header_hash.lock().await;
             *last_header_hash = Some(header_work_hash.clone());
             let mut mining_jobs = self.mining_jobs.lock().await;
-            mining_jobs.put(header_work_hash.clone(), (header, difficulty));
+            mining_jobs.put(header_work_hash.clone(), (header, difficulty, aux_commitment.clone()));
         }
 
+        Ok((job, height, difficulty, aux_commitment))
+    }
+
+    pub async fn notify_new_job(&self) -> Result<(), InternalRpcError> {
+        trace!("notify new job");
+        // Check that there is at least one miner connected
+        // otherwise, no need to build a new job
+        {
+            let miners = self.miners.lock().await;
+            if miners.is_empty() {
+                debug!("No miners connected, no need to notify them");
+                return Ok(());
+            }
+        }
+    
+        debug!("Notify all miners for a new job");
+        let (mut job, height, difficulty, aux_commitment) = self.create_job().await?;
+
         // now let's send the job to every miner
         let mut miners = self.miners.lock().await;
         miners.retain(|addr, _| addr.connected());
 
         let topoheight = self.blockchain.get_topo_height();
-        for (addr, miner) in miners.iter() {
+        for (addr, miner) in miners.iter_mut() {
             debug!("Notifying {} for new job", miner);
             let addr = addr.clone();
+            let share_difficulty = if self.is_share_enabled() {
+                miner.retarget_share_difficulty(self.shares_per_minute, &difficulty, false);
+                miner.share_difficulty
+            } else {
+                None
+            };
 
             job.set_miner(Cow::Borrowed(miner.get_public_key()));
-            OsRng.fill_bytes(job.get_extra_nonce());
+            fill_extra_nonce(&mut job, aux_commitment.as_ref());
             let template = job.to_hex();
 
             // New task for each miner in case a miner is slow
             // we don't want to wait for him
             spawn_task("getwork-notify-new-job", async move {
-                match addr.send(Response::NewJob(GetMinerWorkResult { template, height, topoheight, difficulty })).await {
+                match addr.send(Response::NewJob(GetMinerWorkResult { template, height, topoheight, difficulty, share_difficulty })).await {
                     Ok(request) => {
                         if let Err(e) = request {
                             warn!("Error while sending new job to addr {:?}: {}", addr, e);
@@ -506,4 +734,133 @@ impl<S: Storage> GetWorkServer<S> {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Random extra nonce for each miner, keeping the aux commitment of the job in its first bytes
+fn fill_extra_nonce(job: &mut MinerWork, aux_commitment: Option<&Hash>) {
+    OsRng.fill_bytes(job.get_extra_nonce());
+    if let Some(commitment) = aux_commitment {
+        job.set_aux_commitment(commitment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{block::EXTRA_NONCE_AUX_SIZE, crypto::KeyPair};
+    use crate::core::{storage::DifficultyProvider, testing::test_blockchain_with};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_aux_commitment_in_mined_block() {
+        let commitment = Hash::new([42u8; 32]);
+        let commitment_hex = commitment.to_hex();
+        let blockchain = test_blockchain_with(&["--simulator", "blockchain", "--aux-commitment", &commitment_hex]).await;
+        let server = GetWorkServer::new(Arc::clone(&blockchain), 0);
+        let key = KeyPair::new().get_public_key().compress();
+
+        // Miner overwriting the commitment is rejected, PoW is not verified by the simulator
+        let (mut job, _, _, aux_commitment) = server.create_job().await.unwrap();
+        assert_eq!(aux_commitment.as_ref(), Some(&commitment));
+        job.set_miner(Cow::Owned(key.clone()));
+        OsRng.fill_bytes(job.get_extra_nonce());
+        assert!(server.accept_miner_job(job, None).await.is_err());
+
+        let (mut job, _, _, aux_commitment) = server.create_job().await.unwrap();
+        job.set_miner(Cow::Owned(key.clone()));
+        fill_extra_nonce(&mut job, aux_commitment.as_ref());
+        job.set_thread_id_u16(u16::MAX);
+
+        // New value only applies to the next jobs
+        let next = Hash::new([7u8; 32]);
+        blockchain.set_aux_commitment(Some(next.clone())).await;
+
+        let (response, hash) = server.accept_miner_job(job, None).await.unwrap();
+        assert!(matches!(response, Response::BlockAccepted));
+        let header = blockchain.get_storage().read().await.get_block_header_by_hash(&hash).await.unwrap();
+        assert_eq!(header.get_extra_nonce()[..EXTRA_NONCE_AUX_SIZE], commitment.as_bytes()[..EXTRA_NONCE_AUX_SIZE]);
+
+        let (_, _, _, aux_commitment) = server.create_job().await.unwrap();
+        assert_eq!(aux_commitment, Some(next));
+    }
+
+    #[test]
+    fn test_retarget_share_difficulty() {
+        let mut miner = Miner::new(false, KeyPair::new().get_public_key().compress(), "test".to_owned());
+        let network_difficulty = Difficulty::from_u64(10_000_000);
+
+        // Initial share difficulty comes from the network difficulty
+        assert!(miner.retarget_share_difficulty(10, &network_difficulty, false));
+        assert_eq!(miner.get_share_difficulty(), Some(&Difficulty::from_u64(10_000)));
+        assert_eq!(miner.get_accepted_share_difficulty(), Some(Difficulty::from_u64(10_000)));
+
+        // Too early for a correct estimation
+        miner.shares_since_retarget = 20;
+        assert!(!miner.retarget_share_difficulty(10, &network_difficulty, false));
+        assert_eq!(miner.shares_since_retarget, 20);
+
+        // Twice the expected shares in a minute
+        miner.last_retarget -= 60 * MILLIS_PER_SECOND;
+        assert!(miner.retarget_share_difficulty(10, &network_difficulty, false));
+        let difficulty = *miner.get_share_difficulty().unwrap();
+        assert!(difficulty > Difficulty::from_u64(19_900) && difficulty <= Difficulty::from_u64(20_000));
+        assert_eq!(miner.shares_since_retarget, 0);
+        // Shares found on the previous job are still accepted
+        assert_eq!(miner.get_accepted_share_difficulty(), Some(Difficulty::from_u64(10_000)));
+
+        // Forced retarget is bounded by the max factor
+        miner.shares_since_retarget = 10_000;
+        assert!(miner.retarget_share_difficulty(10, &network_difficulty, true));
+        assert_eq!(miner.get_share_difficulty(), Some(&(difficulty * GETWORK_SHARE_RETARGET_MAX_FACTOR)));
+        assert_eq!(miner.get_accepted_share_difficulty(), Some(difficulty));
+
+        // No share at all
+        miner.last_retarget -= 60 * MILLIS_PER_SECOND;
+        assert!(miner.retarget_share_difficulty(10, &network_difficulty, false));
+        assert_eq!(miner.get_share_difficulty(), Some(&difficulty));
+    }
+
+    #[test]
+    fn test_retarget_share_difficulty_bounds() {
+        let mut miner = Miner::new(false, KeyPair::new().get_public_key().compress(), "test".to_owned());
+
+        // Never below the minimum share difficulty
+        assert!(miner.retarget_share_difficulty(10, &Difficulty::from_u64(1000), false));
+        assert_eq!(miner.get_share_difficulty(), Some(&GETWORK_MIN_SHARE_DIFFICULTY));
+
+        miner.last_retarget -= 60 * MILLIS_PER_SECOND;
+        assert!(!miner.retarget_share_difficulty(10, &Difficulty::from_u64(1000), false));
+        assert_eq!(miner.get_share_difficulty(), Some(&GETWORK_MIN_SHARE_DIFFICULTY));
+
+        // Never above the network difficulty
+        let network_difficulty = Difficulty::from_u64(3000);
+        miner.shares_since_retarget = 10_000;
+        assert!(miner.retarget_share_difficulty(10, &network_difficulty, true));
+        assert_eq!(miner.get_share_difficulty(), Some(&network_difficulty));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_share() {
+        let blockchain = test_blockchain_with(&["--simulator", "blockchain"]).await;
+        let server = GetWorkServer::new(Arc::clone(&blockchain), 10);
+        let share_difficulty = Some(Difficulty::from_u64(1));
+
+        let (mut job, _, _, aux_commitment) = server.create_job().await.unwrap();
+        // Unreachable block difficulty, every solution is a share
+        server.mining_jobs.lock().await.peek_mut(job.get_header_work_hash()).unwrap().1 = Difficulty::from_u64(u64::MAX);
+        job.set_miner(Cow::Owned(KeyPair::new().get_public_key().compress()));
+        fill_extra_nonce(&mut job, aux_commitment.as_ref());
+
+        let (response, hash) = server.accept_miner_job(job.clone(), share_difficulty).await.unwrap();
+        assert!(matches!(response, Response::ShareAccepted));
+
+        let (response, duplicate) = server.accept_miner_job(job.clone(), share_difficulty).await.unwrap();
+        assert!(matches!(response, Response::ShareRejected(_)));
+        assert_eq!(duplicate, hash);
+
+        // Another nonce is another share
+        job.set_thread_id_u16(1);
+        let (response, other) = server.accept_miner_job(job, share_difficulty).await.unwrap();
+        assert!(matches!(response, Response::ShareAccepted));
+        assert_ne!(other, hash);
+    }
+}
@@ -1,5 +1,8 @@
 pub mod rpc;
 pub mod getwork_server;
+pub mod cursor;
+pub mod explorer;
+pub mod coalescer;
 
 use crate::{
     core::{
@@ -29,9 +32,13 @@ use actix_web_actors::ws::WsResponseBuilder;
 use serde_json::{Value, json};
 use tokio::sync::Mutex;
 use xelis_common::{
-    api::daemon::NotifyEvent,
+    api::daemon::{
+        BlockTemplateUpdatedEvent,
+        FeePolicyUpdatedEvent,
+        NotifyEvent
+    },
     config,
-    crypto::Address,
+    crypto::{Address, Hash},
     rpc_server::{
         json_rpc,
         websocket,
@@ -43,13 +50,19 @@ use xelis_common::{
         InternalRpcError,
         RPCHandler,
         RPCServerHandler,
+        RpcRateLimiter,
         WebSocketServerHandler
     },
+    time::get_current_time_in_millis,
     utils::spawn_task,
 };
 use std::{
+    borrow::Cow,
     collections::HashSet,
+    fs,
+    io,
     sync::Arc,
+    time::Duration,
 };
 use log::{
     trace,
@@ -58,17 +71,33 @@ use log::{
     warn,
     error,
 };
-use self::getwork_server::{
-    GetWorkWebSocketHandler,
-    SharedGetWorkServer
+use self::{
+    coalescer::{Emission, EventCoalescer},
+    rpc::DaemonRpcAuthorizer,
+    getwork_server::{
+        GetWorkWebSocketHandler,
+        SharedGetWorkServer
+    }
 };
 
 pub type SharedDaemonRpcServer<S> = Arc<DaemonRpcServer<S>>;
 
+// Credentials required by the admin methods
+pub struct AdminCredentials {
+    pub username: String,
+    pub password: String
+}
+
 pub struct DaemonRpcServer<S: Storage> {
     handle: Mutex<Option<ServerHandle>>,
+    // Unix domain socket path, removed on shutdown
+    unix_socket: Option<String>,
     websocket: WebSocketServerShared<EventWebSocketHandler<Arc<Blockchain<S>>, NotifyEvent>>,
-    getwork: Option<SharedGetWorkServer<S>>
+    getwork: Option<SharedGetWorkServer<S>>,
+    rate_limiter: Arc<RpcRateLimiter>,
+    // Fee policy and block template events are coalesced to not flood subscribers on mempool churn
+    fee_policy_events: Mutex<EventCoalescer<FeePolicyUpdatedEvent>>,
+    block_template_events: Mutex<EventCoalescer<()>>
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -80,14 +109,43 @@ pub enum ApiError {
     #[error("P2p engine is not running")]
     NoP2p,
     #[error("WebSocket server is not started")]
-    NoWebSocketServer
+    NoWebSocketServer,
+    #[error("Address index is not enabled")]
+    AddressIndexDisabled,
+    #[error("RPC server is not started")]
+    NoRpcServer,
+    #[error("Invalid cursor")]
+    InvalidCursor,
+    #[error("skip and maximum cannot be used with cursor or limit")]
+    CursorWithOffset,
+    #[error("Maximum items requested cannot be greater than {}", _0)]
+    TooManyItemsRequested(usize),
+    #[error("Transaction {} is in mempool and not executed yet", _0)]
+    TxInMempool(Hash),
+    #[error("Transaction {} is not executed in any block", _0)]
+    TxNotExecuted(Hash),
+    #[error("Transaction {} was not found", _0)]
+    TxNotFound(Hash),
+    #[error("Invalid extra nonce prefix, expected at most {} bytes in hex format", _0)]
+    InvalidExtraNoncePrefix(usize),
+    #[error("No nonce found at or below topoheight {}", _0)]
+    NoNonceAtTopoHeight(u64),
+    #[error("Node is warming up")]
+    WarmingUp,
+    #[error("Block reward can only be projected up to height {}", _0)]
+    BlockRewardProjectionTooFar(u64),
+    #[error("No ordered block found at height {}", _0)]
+    NoBlockAtHeight(u64),
+    #[error("Account has no transaction in mempool")]
+    AccountNotInMempool
 }
 
 impl<S: Storage> DaemonRpcServer<S> {
-    pub async fn new(bind_address: String, blockchain: Arc<Blockchain<S>>, disable_getwork_server: bool) -> Result<SharedDaemonRpcServer<S>, BlockchainError> {
-        let getwork: Option<SharedGetWorkServer<S>> = if !disable_getwork_server {
+    pub async fn new(bind_address: Option<String>, unix_socket: Option<String>, blockchain: Arc<Blockchain<S>>, disable_getwork_server: bool, getwork_shares_per_minute: u64, rate_limit: u64, concurrent_limit: usize, trust_proxy: bool, admin_credentials: Option<AdminCredentials>, enable_explorer: bool, events_coalesce_window: u64) -> Result<SharedDaemonRpcServer<S>, BlockchainError> {
+        // Mining methods are still registered in safe mode to report it to the miners
+        let getwork: Option<SharedGetWorkServer<S>> = if !disable_getwork_server && !blockchain.is_safe_mode() {
             info!("Creating GetWork server...");
-            Some(Arc::new(GetWorkServer::new(blockchain.clone())))
+            Some(Arc::new(GetWorkServer::new(blockchain.clone(), getwork_shares_per_minute)))
         } else {
             None
         };
@@ -95,19 +153,27 @@ impl<S: Storage> DaemonRpcServer<S> {
         // create the RPC Handler which will register and contains all available methods
         let mut rpc_handler = RPCHandler::new(blockchain);
         rpc::register_methods(&mut rpc_handler, !disable_getwork_server);
+        rpc_handler.set_authorizer(Arc::new(DaemonRpcAuthorizer::new(admin_credentials)));
+
+        // always created to resolve the client IP and keep the counters
+        let rate_limiter = Arc::new(RpcRateLimiter::new(rate_limit, concurrent_limit, trust_proxy));
 
         // create the default websocket server (support event & rpc methods)
-        let ws = WebSocketServer::new(EventWebSocketHandler::new(rpc_handler));
+        let ws = WebSocketServer::new(EventWebSocketHandler::with_rate_limiter(rpc_handler, Arc::clone(&rate_limiter)));
 
         let server = Arc::new(Self {
             handle: Mutex::new(None),
+            unix_socket: unix_socket.clone(),
             websocket: ws,
             getwork,
+            rate_limiter,
+            fee_policy_events: Mutex::new(EventCoalescer::new(events_coalesce_window)),
+            block_template_events: Mutex::new(EventCoalescer::new(events_coalesce_window))
         });
 
         {
             let clone = Arc::clone(&server);
-            let http_server = HttpServer::new(move || {
+            let mut http_server = HttpServer::new(move || {
                 let server = Arc::clone(&clone);
                 App::new().app_data(web::Data::from(server))
                     // Traditional HTTP
@@ -115,11 +181,34 @@ impl<S: Storage> DaemonRpcServer<S> {
                     // WebSocket support
                     .route("/json_rpc", web::get().to(websocket::<EventWebSocketHandler<Arc<Blockchain<S>>, NotifyEvent>, DaemonRpcServer<S>>))
                     .route("/getwork/{address}/{worker}", web::get().to(getwork_endpoint::<S>))
+                    .route("/metrics", web::get().to(metrics_endpoint::<S>))
+                    .configure(|cfg| if enable_explorer {
+                        explorer::configure::<S, DaemonRpcServer<S>>(cfg);
+                    })
                     .service(index)
             })
-            .disable_signals()
-            .bind(&bind_address)?
-            .run();
+            .disable_signals();
+
+            if let Some(bind_address) = bind_address {
+                http_server = http_server.bind(&bind_address)?;
+            }
+
+            // Same routes and methods over the Unix domain socket, only its owner can use it
+            if let Some(path) = unix_socket {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+
+                    prepare_unix_socket(&path)?;
+                    http_server = http_server.bind_uds(&path)?;
+                    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+                }
+
+                #[cfg(not(unix))]
+                return Err(io::Error::new(io::ErrorKind::Unsupported, format!("Unix socket {} is not supported on this platform", path)).into());
+            }
+
+            let http_server = http_server.run();
 
             { // save the server handle to be able to stop it later
                 let handle = http_server.handle();
@@ -151,6 +240,67 @@ impl<S: Storage> DaemonRpcServer<S> {
         Ok(())
     }
 
+    // Changes within the coalesce window are sent as one event at its end
+    pub async fn notify_fee_policy_updated(self: &Arc<Self>, event: FeePolicyUpdatedEvent) {
+        if !self.is_event_tracked(&NotifyEvent::FeePolicyUpdated).await {
+            return;
+        }
+
+        let emission = self.fee_policy_events.lock().await.push(event, get_current_time_in_millis());
+        match emission {
+            Emission::Now(event) => self.notify_clients_with(&NotifyEvent::FeePolicyUpdated, event).await,
+            Emission::Schedule(delay) => {
+                let server = Arc::clone(self);
+                spawn_task("rpc-fee-policy-event", async move {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let event = server.fee_policy_events.lock().await.flush(get_current_time_in_millis());
+                    if let Some(event) = event {
+                        server.notify_clients_with(&NotifyEvent::FeePolicyUpdated, event).await;
+                    }
+                });
+            },
+            Emission::Coalesced => trace!("Fee policy update coalesced")
+        }
+    }
+
+    // The template is built when the event is sent, so a coalesced event always has the last one
+    pub async fn notify_block_template_updated(self: &Arc<Self>) {
+        if !self.is_event_tracked(&NotifyEvent::BlockTemplateUpdated).await {
+            return;
+        }
+
+        let emission = self.block_template_events.lock().await.push((), get_current_time_in_millis());
+        match emission {
+            Emission::Now(()) => self.send_block_template_updated().await,
+            Emission::Schedule(delay) => {
+                let server = Arc::clone(self);
+                spawn_task("rpc-block-template-event", async move {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let flushed = server.block_template_events.lock().await.flush(get_current_time_in_millis());
+                    if flushed.is_some() {
+                        server.send_block_template_updated().await;
+                    }
+                });
+            },
+            Emission::Coalesced => trace!("Block template update coalesced")
+        }
+    }
+
+    async fn send_block_template_updated(&self) {
+        let blockchain = self.get_rpc_handler().get_data();
+        match blockchain.get_block_template_job().await {
+            Ok((job_id, height, difficulty)) => {
+                let event = BlockTemplateUpdatedEvent {
+                    job_id: Cow::Owned(job_id),
+                    height,
+                    difficulty
+                };
+                self.notify_clients_with(&NotifyEvent::BlockTemplateUpdated, event).await;
+            },
+            Err(e) => error!("Error while building block template for event: {}", e)
+        }
+    }
+
     pub async fn stop(&self) {
         info!("Stopping RPC Server...");
         let mut handle = self.handle.lock().await;
@@ -160,11 +310,21 @@ impl<S: Storage> DaemonRpcServer<S> {
         } else {
             warn!("RPC Server is not running!");
         }
+
+        if let Some(path) = self.unix_socket.as_ref() {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Error while deleting the Unix socket {}: {}", path, e);
+            }
+        }
     }
 
     pub fn getwork_server(&self) -> &Option<SharedGetWorkServer<S>> {
         &self.getwork
     }
+
+    pub fn get_rate_limiter(&self) -> &Arc<RpcRateLimiter> {
+        &self.rate_limiter
+    }
 }
 
 impl<S: Storage> WebSocketServerHandler<EventWebSocketHandler<Arc<Blockchain<S>>, NotifyEvent>> for DaemonRpcServer<S> {
@@ -177,17 +337,58 @@ impl<S: Storage> RPCServerHandler<Arc<Blockchain<S>>> for DaemonRpcServer<S> {
     fn get_rpc_handler(&self) -> &RPCHandler<Arc<Blockchain<S>>> {
         self.get_websocket().get_handler().get_rpc_handler()
     }
+
+    fn get_rate_limiter(&self) -> Option<&Arc<RpcRateLimiter>> {
+        Some(&self.rate_limiter)
+    }
 }
 
 
+// Remove the socket file left by a stopped process
+// It is kept if a process is still listening on it
+#[cfg(unix)]
+fn prepare_unix_socket(path: &str) -> Result<(), BlockchainError> {
+    use std::os::unix::net::UnixStream;
+
+    if fs::metadata(path).is_ok() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("Unix socket {} is already used by another process", path)).into())
+        }
+
+        warn!("Removing stale Unix socket {}", path);
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
 #[get("/")]
 async fn index() -> impl Responder {
     HttpResponse::Ok().body(format!("Hello, world!\nRunning on: {}", config::VERSION))
 }
 
+// Prometheus metrics of the node
+async fn metrics_endpoint<S: Storage>(server: Data<DaemonRpcServer<S>>) -> impl Responder {
+    let blockchain = server.get_rpc_handler().get_data();
+    let mut body = String::new();
+    {
+        let fee_histogram = blockchain.get_fee_histogram().lock().await;
+        let total = fee_histogram.get_total();
+        total.fees.write_prometheus(&mut body, "xelis_tx_fee", &format!("Fees of the TXs executed in the last {} blocks", fee_histogram.get_window()));
+        total.sizes.write_prometheus(&mut body, "xelis_tx_size_bytes", &format!("Sizes of the TXs executed in the last {} blocks", fee_histogram.get_window()));
+    }
+
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body)
+}
+
 async fn getwork_endpoint<S: Storage>(server: Data<DaemonRpcServer<S>>, request: HttpRequest, stream: Payload, path: Path<(String, String)>) -> Result<HttpResponse, Error> {
     match &server.getwork {
         Some(getwork) => {
+            // Miner work is only accepted once the warm-up is done
+            if !server.get_rpc_handler().get_data().is_ready() {
+                return Ok(HttpResponse::ServiceUnavailable().body("Node is warming up"))
+            }
+
             let (addr, worker) = path.into_inner();
             if worker.len() > 32 {
                 return Ok(HttpResponse::BadRequest().body("Worker name must be less or equal to 32 chars"))
@@ -217,4 +418,167 @@ async fn getwork_endpoint<S: Storage>(server: Data<DaemonRpcServer<S>>, request:
         },
         None => Ok(HttpResponse::NotFound().reason("GetWork server is not enabled").finish()) // getwork server is not started
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mines real blocks at the devnet difficulty, run it with --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_block_orphaned_event_on_reorg() {
+        use rand::Rng;
+        use xelis_common::{
+            api::daemon::BlockOrphanedEvent,
+            crypto::{Hashable, KeyPair},
+            json_rpc::WebSocketJsonRPCClientImpl
+        };
+        use crate::core::{
+            storage::{BlocksAtHeightProvider, DagOrderProvider},
+            testing::{start_blockchain, test_config}
+        };
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let rpc_address = format!("127.0.0.1:{}", port);
+        let first = start_blockchain(test_config(&["--disable-p2p-server", "--disable-getwork-server", "--rpc-bind-address", &rpc_address])).await;
+        let genesis_hash = first.get_top_block_hash().await.unwrap();
+
+        // Second chain sharing the same genesis block
+        let path = std::env::temp_dir().join(format!("xelis-reorg-{}.bin", rand::thread_rng().gen::<u64>()));
+        let path = path.to_str().unwrap();
+        first.export_snapshot(path, 0).await.unwrap();
+        let second = start_blockchain(test_config(&["--disable-p2p-server", "--disable-rpc-server", "--import-snapshot", path])).await;
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(second.get_top_block_hash().await.unwrap(), genesis_hash);
+
+        let client = WebSocketJsonRPCClientImpl::<NotifyEvent>::new(format!("ws://{}/json_rpc", rpc_address)).await.unwrap();
+        let mut receiver = client.subscribe_event::<BlockOrphanedEvent<'static>>(NotifyEvent::BlockOrphaned).await.unwrap();
+
+        // First chain orders its block at topoheight 1
+        let block = first.mine_block(&KeyPair::new().get_public_key().compress()).await.unwrap();
+        let orphaned_hash = block.hash();
+        first.add_new_block(block, false, false).await.unwrap();
+        assert_eq!(first.get_storage().read().await.get_topo_height_for_hash(&orphaned_hash).await.unwrap(), 1);
+
+        // Second chain builds a heavier branch that doesn't reference it
+        let key = KeyPair::new().get_public_key().compress();
+        let mut blocks = Vec::new();
+        for _ in 0..2 {
+            let block = second.mine_block(&key).await.unwrap();
+            second.add_new_block(block.clone(), false, false).await.unwrap();
+            blocks.push(block);
+        }
+
+        for block in blocks {
+            first.add_new_block(block, false, false).await.unwrap();
+        }
+
+        let event = tokio::time::timeout(Duration::from_secs(10), receiver.next()).await.unwrap().unwrap();
+        assert_eq!(*event.block_hash, orphaned_hash);
+        assert_eq!(event.old_topoheight, 1);
+        assert_eq!(*event.base_hash, genesis_hash);
+        assert_eq!(event.base_topoheight, 0);
+
+        // Both blocks at height 1 are still returned
+        let storage = first.get_storage().read().await;
+        assert!(!storage.is_block_topological_ordered(&orphaned_hash).await);
+        assert_eq!(storage.get_blocks_at_height(1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fee_policy_events_coalesced() {
+        use xelis_common::{
+            api::daemon::FeePolicy,
+            config::{COIN_VALUE, XELIS_ASSET},
+            crypto::KeyPair,
+            json_rpc::WebSocketJsonRPCClientImpl,
+            serializer::Serializer,
+            testing::{build_transaction, build_transaction_with_fee},
+            transaction::{builder::{FeeBuilder, TransactionTypeBuilder}, BurnPayload, Reference}
+        };
+        use crate::core::{
+            mempool::get_fee_rate,
+            storage::DagOrderProvider,
+            testing::{fund_account, start_blockchain, test_config}
+        };
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let rpc_address = format!("127.0.0.1:{}", port);
+        // Mempool is full with a single TX, each new one evicts the previous
+        let blockchain = start_blockchain(test_config(&["--disable-p2p-server", "--disable-getwork-server", "--rpc-bind-address", &rpc_address, "--simulator", "blockchain", "--mempool-max-size-bytes", "1", "--rpc-events-coalesce-window", "2000"])).await;
+
+        // First simulated block is mined on start, the next one only after BLOCK_TIME_MILLIS
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while blockchain.get_topo_height() == 0 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }).await.unwrap();
+
+        let balance = 10 * COIN_VALUE;
+        let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new()).collect();
+        for keypair in keypairs.iter() {
+            fund_account(&blockchain, &keypair.get_public_key().compress(), balance).await;
+        }
+
+        let client = WebSocketJsonRPCClientImpl::<NotifyEvent>::new(format!("ws://{}/json_rpc", rpc_address)).await.unwrap();
+        let mut fee_policy = client.subscribe_event::<FeePolicyUpdatedEvent>(NotifyEvent::FeePolicyUpdated).await.unwrap();
+        let mut block_template = client.subscribe_event::<BlockTemplateUpdatedEvent<'static>>(NotifyEvent::BlockTemplateUpdated).await.unwrap();
+
+        let reference = Reference {
+            hash: blockchain.get_storage().read().await.get_hash_at_topo_height(0).await.unwrap(),
+            topoheight: 0
+        };
+        let burn = || TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: COIN_VALUE });
+        let first = build_transaction(&keypairs[0], balance, 0, reference.clone(), burn());
+        let second = build_transaction_with_fee(&keypairs[1], balance, 0, reference.clone(), burn(), FeeBuilder::Value(first.get_fee() * 2));
+        let third = build_transaction_with_fee(&keypairs[2], balance, 0, reference, burn(), FeeBuilder::Value(first.get_fee() * 4));
+        let first_rate = get_fee_rate(first.get_fee(), first.size());
+
+        // Fill: first TX fits alone, then each one evicts the previous and raises the floor
+        blockchain.add_tx_to_mempool(first, false).await.unwrap();
+        assert_eq!(blockchain.get_fee_policy().await.mempool_fee_rate, 0);
+        blockchain.add_tx_to_mempool(second, false).await.unwrap();
+        assert_eq!(blockchain.get_fee_policy().await.mempool_fee_rate, first_rate);
+        blockchain.add_tx_to_mempool(third, false).await.unwrap();
+        assert!(blockchain.get_fee_policy().await.mempool_fee_rate > first_rate);
+
+        // Drain: the block executes the last TX and the mempool has room again
+        let block = blockchain.mine_block(&KeyPair::new().get_public_key().compress()).await.unwrap();
+        assert_eq!(block.get_txs_count(), 1);
+        blockchain.add_new_block(block, false, false).await.unwrap();
+        assert_eq!(blockchain.get_mempool_size().await, 0);
+        assert_eq!(blockchain.get_fee_policy().await.mempool_fee_rate, 0);
+
+        let min_relay_fee_rate = blockchain.get_min_relay_fee_rate();
+        let policy = |mempool_fee_rate| FeePolicy { min_relay_fee_rate, mempool_fee_rate };
+
+        // First change is sent directly
+        let event = tokio::time::timeout(Duration::from_secs(1), fee_policy.next()).await.unwrap().unwrap();
+        assert_eq!(event, FeePolicyUpdatedEvent { previous: policy(0), new: policy(first_rate) });
+
+        // Next ones are merged at the end of the window, the intermediate floor is never sent
+        let event = tokio::time::timeout(Duration::from_secs(5), fee_policy.next()).await.unwrap().unwrap();
+        assert_eq!(event, FeePolicyUpdatedEvent { previous: policy(first_rate), new: policy(0) });
+
+        // Template with the first TX, then only the last one built after the block
+        let event = tokio::time::timeout(Duration::from_secs(1), block_template.next()).await.unwrap().unwrap();
+        assert_eq!(event.height, 2);
+        let event = tokio::time::timeout(Duration::from_secs(5), block_template.next()).await.unwrap().unwrap();
+        let (job_id, height, difficulty) = blockchain.get_block_template_job().await.unwrap();
+        assert_eq!(*event.job_id, job_id);
+        assert_eq!(event.height, height);
+        assert_eq!(event.difficulty, difficulty);
+        assert_eq!(height, 3);
+
+        // Nothing else was pending
+        assert!(tokio::time::timeout(Duration::from_millis(2500), fee_policy.next()).await.is_err());
+        assert!(tokio::time::timeout(Duration::from_millis(100), block_template.next()).await.is_err());
+
+        // A new relay minimum is sent directly once the window is over
+        blockchain.set_min_relay_fee_rate(min_relay_fee_rate * 2).await;
+        let event = tokio::time::timeout(Duration::from_secs(1), fee_policy.next()).await.unwrap().unwrap();
+        assert_eq!(event.previous, policy(0));
+        assert_eq!(event.new, FeePolicy { min_relay_fee_rate: min_relay_fee_rate * 2, mempool_fee_rate: 0 });
+    }
+}
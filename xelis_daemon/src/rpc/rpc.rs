@@ -1,8 +1,11 @@
 use crate::{
     config::{
         BLOCK_TIME_MILLIS,
+        DEFAULT_STATS_BLOCKS,
         DEV_FEES,
-        DEV_PUBLIC_KEY
+        DEV_PUBLIC_KEY,
+        MAX_BLOCK_REWARD_PROJECTION,
+        MAX_GET_MANY_QUERIES
     },
     core::{
         blockchain::{
@@ -14,22 +17,45 @@ use crate::{
         mempool::Mempool,
         storage::Storage
     },
-    p2p::peer::Peer,
-    BLOCK_TIME
+    p2p::peer::Peer
+};
+use super::{
+    cursor::{encode_cursor, parse_pagination, CursorKind, Pagination},
+    AdminCredentials,
+    InternalRpcError,
+    ApiError
 };
-use super::{InternalRpcError, ApiError};
 use xelis_common::{
+    amount::checked_add,
     api::{
+        binary::{
+            encode_binary,
+            BinaryAccountHistoryEntry,
+            BinaryBlock,
+            BinaryPayload,
+            BinaryTransaction,
+            ResponseEncoding
+        },
         daemon::{
+            AccountBalanceHistoryEntry,
             AccountHistoryEntry,
+            AccountAssetWithBalance,
             AccountHistoryType,
+            AddressTransactionEntry,
+            AddressTransactionType,
+            BannedPeerEntry,
+            GetP2pStatsResult,
+            GetRpcStatsResult,
             BlockType,
             CreateMinerWorkParams,
             CreateMinerWorkResult,
             GetAccountAssetsParams,
+            GetAccountAssetsWithBalancesParams,
+            GetAccountBalanceHistoryParams,
             GetAccountHistoryParams,
             GetAccountRegistrationParams,
             GetAccountsParams,
+            GetAddressTransactionsParams,
             GetAssetParams,
             GetAssetsParams,
             GetBalanceAtTopoHeightParams,
@@ -37,19 +63,33 @@ use xelis_common::{
             GetBalanceResult,
             GetBlockAtTopoHeightParams,
             GetBlockByHashParams,
+            GetBlockRewardAtParams,
+            GetBlockRewardAtResult,
             GetBlockTemplateParams,
             GetBlockTemplateResult,
             GetBlocksAtHeightParams,
             GetDifficultyResult,
             GetHeightRangeParams,
             GetInfoResult,
+            GetManyParams,
+            GetManyResult,
             GetMempoolCacheParams,
+            GetMempoolSummaryResult,
             GetNonceAtTopoHeightParams,
             GetNonceParams,
             GetNonceResult,
             GetPeersResponse,
             GetTopBlockParams,
+            GetFeeHistogramParams,
+            EstimateFeesParams,
+            GetFeeHistogramResult,
+            GetStatsParams,
             GetTopoHeightRangeParams,
+            GetBlocksRangeParams,
+            GetAuxCommitmentResult,
+            SetAuxCommitmentParams,
+            GetTransactionExecutorParams,
+            GetTransactionExecutorResult,
             GetTransactionParams,
             GetTransactionsParams,
             HasBalanceParams,
@@ -58,17 +98,29 @@ use xelis_common::{
             HasNonceResult,
             IsAccountRegisteredParams,
             IsTxExecutedInBlockParams,
+            ManyQuery,
+            ManyQueryResult,
             P2pStatusResult,
+            PagedResult,
             PeerEntry,
+            QueryError,
             RPCBlockResponse,
             SizeOnDiskResult,
+            GetStorageStatsResult,
             SubmitBlockParams,
+            SubmitBlockResult,
             SubmitTransactionParams,
             TransactionResponse,
             ValidateAddressParams,
             ValidateAddressResult,
+            ValidateTransactionResult,
             ExtractKeyFromAddressParams,
-            ExtractKeyFromAddressResult
+            ExtractKeyFromAddressResult,
+            TX_IN_MEMPOOL_ERROR_CODE,
+            TX_NOT_EXECUTED_ERROR_CODE,
+            TX_NOT_FOUND_ERROR_CODE,
+            TX_REJECTED_ERROR_CODE,
+            ACCOUNT_NOT_IN_MEMPOOL_ERROR_CODE
         },
         RPCTransaction,
         RPCTransactionType as RPCTransactionType,
@@ -79,7 +131,9 @@ use xelis_common::{
     block::{
         Block,
         BlockHeader,
-        MinerWork
+        MinerWork,
+        EXTRA_NONCE_AUX_SIZE,
+        MAX_EXTRA_NONCE_PREFIX_SIZE
     },
     config::{
         MAXIMUM_SUPPLY,
@@ -88,15 +142,20 @@ use xelis_common::{
         XELIS_ASSET
     },
     context::Context,
-    crypto::Hash,
+    crypto::{Address, Hash, PaymentId},
     difficulty::{
+        compute_difficulty_target_hash,
         CumulativeDifficulty,
         Difficulty
     },
-    immutable::Immutable,
+    emission::get_projected_supply,
     rpc_server::{
         parse_params,
-        RPCHandler
+        ClientAuthorization,
+        ClientIp,
+        RPCHandler,
+        RPCServerHandler,
+        RpcAuthorizer
     },
     serializer::Serializer,
     time::TimestampSeconds,
@@ -106,11 +165,15 @@ use xelis_common::{
     },
     utils::format_hashrate
 };
+use actix_web_httpauth::headers::authorization::{Basic, Scheme};
 use anyhow::Context as AnyContext;
+use async_trait::async_trait;
 use human_bytes::human_bytes;
+use futures_util::future::join_all;
 use serde_json::{json, Value};
-use std::{sync::Arc, borrow::Cow};
+use std::{sync::Arc, borrow::Cow, str::FromStr};
 use log::{info, debug, trace};
+use subtle::ConstantTimeEq;
 
 // Get the block type using the block hash and the blockchain current state
 pub async fn get_block_type_for_block<S: Storage>(blockchain: &Blockchain<S>, storage: &S, hash: &Hash) -> Result<BlockType, InternalRpcError> {
@@ -156,7 +219,7 @@ pub async fn get_block_response<S: Storage>(blockchain: &Blockchain<S>, storage:
             // check that the TX was correctly executed in this block
             // retrieve all fees for valid txs
             if storage.is_tx_executed_in_block(tx_hash, &hash).context("Error while checking if tx was executed")? {
-                total_fees += tx.get_fee();
+                total_fees = checked_add(total_fees, tx.get_fee()).context("Error while computing total fees")?;
             }
         }
     }
@@ -197,6 +260,7 @@ pub async fn get_block_response<S: Storage>(blockchain: &Blockchain<S>, storage:
         total_size_in_bytes,
         extra_nonce: Cow::Borrowed(header.get_extra_nonce()),
         timestamp: header.get_timestamp(),
+        first_seen: storage.get_first_seen(hash).await.context("Error while retrieving first seen")?,
         nonce: header.get_nonce(),
         height: header.get_height(),
         version: header.get_version(),
@@ -232,12 +296,7 @@ pub async fn get_block_response_for_hash<S: Storage>(blockchain: &Blockchain<S>,
     } else {
         let (topoheight, supply, reward, block_type, cumulative_difficulty, difficulty) = get_block_data(blockchain, storage, hash).await?;
         let header = storage.get_block_header_by_hash(&hash).await.context("Error while retrieving full block")?;
-
-        // calculate total size in bytes
-        let mut total_size_in_bytes = header.size();
-        for tx_hash in header.get_txs_hashes() {
-            total_size_in_bytes += storage.get_transaction_size(tx_hash).await.context(format!("Error while retrieving transaction {hash} size"))?;
-        }
+        let total_size_in_bytes = get_block_size_in_bytes(storage, hash, &header).await?;
 
         let mainnet = blockchain.get_network().is_mainnet();
         let (dev_reward, miner_reward) = get_block_rewards(header.get_height(), reward).map(|(dev_reward, miner_reward)| {
@@ -258,6 +317,7 @@ pub async fn get_block_response_for_hash<S: Storage>(blockchain: &Blockchain<S>,
             total_size_in_bytes,
             extra_nonce: Cow::Borrowed(header.get_extra_nonce()),
             timestamp: header.get_timestamp(),
+            first_seen: storage.get_first_seen(hash).await.context("Error while retrieving first seen")?,
             nonce: header.get_nonce(),
             height: header.get_height(),
             version: header.get_version(),
@@ -279,11 +339,87 @@ pub async fn get_transaction_response<S: Storage>(storage: &S, tx: &Arc<Transact
         None
     };
 
+    // Once included in a block, the first seen is read from the storage
+    let first_seen = match first_seen {
+        Some(first_seen) => Some(first_seen),
+        None => get_stored_tx_first_seen(storage, hash).await?
+    };
+
     let data = RPCTransaction::from_tx(tx, hash, storage.is_mainnet());
     let executed_in_block = storage.get_block_executor_for_tx(hash).ok();
     Ok(json!(TransactionResponse { blocks, executed_in_block, data, in_mempool, first_seen }))
 }
 
+// Transactions first seen are exposed in seconds like in the mempool
+async fn get_stored_tx_first_seen<S: Storage>(storage: &S, hash: &Hash) -> Result<Option<TimestampSeconds>, InternalRpcError> {
+    let first_seen = storage.get_first_seen(hash).await.context("Error while retrieving first seen")?;
+    Ok(first_seen.map(|timestamp| timestamp / 1000))
+}
+
+// calculate the total size in bytes of a block with its transactions
+async fn get_block_size_in_bytes<S: Storage>(storage: &S, hash: &Hash, header: &BlockHeader) -> Result<usize, InternalRpcError> {
+    let mut total_size_in_bytes = header.size();
+    for tx_hash in header.get_txs_hashes() {
+        total_size_in_bytes += storage.get_transaction_size(tx_hash).await.context(format!("Error while retrieving transaction {hash} size"))?;
+    }
+    Ok(total_size_in_bytes)
+}
+
+// Same as get_block_response_for_hash without txs but for a binary response
+pub async fn get_binary_block_for_hash<S: Storage>(blockchain: &Blockchain<S>, storage: &S, hash: &Hash) -> Result<BinaryBlock, InternalRpcError> {
+    if !storage.has_block_with_hash(&hash).await.context("Error while checking if block exist")? {
+        return Err(InternalRpcError::InvalidParamsAny(BlockchainError::BlockNotFound(hash.clone()).into()))
+    }
+
+    let (topoheight, supply, reward, block_type, cumulative_difficulty, difficulty) = get_block_data(blockchain, storage, hash).await?;
+    let header = storage.get_block_header_by_hash(&hash).await.context("Error while retrieving full block")?;
+    let total_size_in_bytes = get_block_size_in_bytes(storage, hash, &header).await?;
+    let (dev_reward, miner_reward) = get_block_rewards(header.get_height(), reward).map(|(dev_reward, miner_reward)| {
+        (Some(dev_reward), Some(miner_reward))
+    }).unwrap_or((None, None));
+
+    Ok(BinaryBlock {
+        hash: hash.clone(),
+        topoheight,
+        block_type,
+        difficulty,
+        cumulative_difficulty,
+        supply,
+        reward,
+        miner_reward,
+        dev_reward,
+        total_fees: None,
+        total_size_in_bytes: total_size_in_bytes as u64,
+        first_seen: storage.get_first_seen(hash).await.context("Error while retrieving first seen")?,
+        header: header.as_ref().clone()
+    })
+}
+
+// Same as get_transaction_response_for_hash but for a binary response
+pub async fn get_binary_transaction_for_hash<S: Storage>(storage: &S, mempool: &Mempool, hash: &Hash) -> Result<BinaryTransaction, InternalRpcError> {
+    let (tx, in_mempool, first_seen) = match storage.get_transaction(hash).await {
+        Ok(tx) => (tx, false, get_stored_tx_first_seen(storage, hash).await?),
+        Err(_) => {
+            let tx = mempool.get_sorted_tx(hash).context("Error while retrieving transaction from disk and mempool")?;
+            (tx.get_tx().clone(), true, Some(tx.get_first_seen()))
+        }
+    };
+
+    let blocks = if storage.has_tx_blocks(hash).context("Error while checking if tx in included in blocks")? {
+        Some(storage.get_blocks_for_tx(hash).context("Error while retrieving in which blocks its included")?.into_iter().collect())
+    } else {
+        None
+    };
+
+    Ok(BinaryTransaction {
+        blocks,
+        executed_in_block: storage.get_block_executor_for_tx(hash).ok(),
+        in_mempool,
+        first_seen,
+        tx: tx.as_ref().clone()
+    })
+}
+
 // first check on disk, then check in mempool
 pub async fn get_transaction_response_for_hash<S: Storage>(storage: &S, mempool: &Mempool, hash: &Hash) -> Result<Value, InternalRpcError> {
     match storage.get_transaction(hash).await {
@@ -300,6 +436,7 @@ pub async fn get_peer_entry(peer: &Peer) -> PeerEntry {
     let top_block_hash = { peer.get_top_block_hash().lock().await.clone() };
     let peers = { peer.get_peers().lock().await.clone() };
     let cumulative_difficulty = { peer.get_cumulative_difficulty().lock().await.clone() };
+    let counters = peer.get_request_counters().snapshot();
     PeerEntry {
         id: peer.get_id(),
         addr: Cow::Borrowed(peer.get_connection().get_address()),
@@ -313,7 +450,12 @@ pub async fn get_peer_entry(peer: &Peer) -> PeerEntry {
         peers: Cow::Owned(peers),
         pruned_topoheight: peer.get_pruned_topoheight(),
         cumulative_difficulty: Cow::Owned(cumulative_difficulty),
-        connected_on: peer.get_connection().connected_on()
+        connected_on: peer.get_connection().connected_on(),
+        requested_objects: counters.requested,
+        fulfilled_objects: counters.fulfilled,
+        timed_out_objects: counters.timed_out,
+        compression: peer.get_connection().is_compression_enabled(),
+        compression_ratio: peer.get_connection().get_compression_stats().get_ratio()
     }
 }
 
@@ -341,23 +483,41 @@ pub fn register_methods<S: Storage>(handler: &mut RPCHandler<Arc<Blockchain<S>>>
     handler.register_method("count_accounts", async_handler!(count_accounts::<S>));
     handler.register_method("count_transactions", async_handler!(count_transactions::<S>));
     handler.register_method("submit_transaction", async_handler!(submit_transaction::<S>));
+    handler.register_method("validate_transaction", async_handler!(validate_transaction::<S>));
     handler.register_method("get_transaction", async_handler!(get_transaction::<S>));
     handler.register_method("p2p_status", async_handler!(p2p_status::<S>));
     handler.register_method("get_peers", async_handler!(get_peers::<S>));
+    handler.register_method("get_bans", async_handler!(get_bans::<S>));
+    handler.register_method("get_peer_version_stats", async_handler!(get_peer_version_stats::<S>));
+    handler.register_method("get_p2p_stats", async_handler!(get_p2p_stats::<S>));
+    handler.register_method("get_rpc_stats", async_handler!(get_rpc_stats::<S>));
     handler.register_method("get_mempool", async_handler!(get_mempool::<S>));
+    handler.register_method("get_mempool_summary", async_handler!(get_mempool_summary::<S>));
+    handler.register_method("get_many", async_handler!(get_many::<S>));
     handler.register_method("get_tips", async_handler!(get_tips::<S>));
     handler.register_method("get_dag_order", async_handler!(get_dag_order::<S>));
     handler.register_method("get_blocks_range_by_topoheight", async_handler!(get_blocks_range_by_topoheight::<S>));
     handler.register_method("get_blocks_range_by_height", async_handler!(get_blocks_range_by_height::<S>));
+    handler.register_method("get_blocks_range", async_handler!(get_blocks_range::<S>));
     handler.register_method("get_transactions", async_handler!(get_transactions::<S>));
     handler.register_method("get_account_history", async_handler!(get_account_history::<S>));
+    handler.register_method("get_account_balance_history", async_handler!(get_account_balance_history::<S>));
+    handler.register_method("get_address_transactions", async_handler!(get_address_transactions::<S>));
     handler.register_method("get_account_assets", async_handler!(get_account_assets::<S>));
+    handler.register_method("get_account_assets_with_balances", async_handler!(get_account_assets_with_balances::<S>));
     handler.register_method("get_accounts", async_handler!(get_accounts::<S>));
     handler.register_method("is_account_registered", async_handler!(is_account_registered::<S>));
     handler.register_method("get_account_registration_topoheight", async_handler!(get_account_registration_topoheight::<S>));
     handler.register_method("is_tx_executed_in_block", async_handler!(is_tx_executed_in_block::<S>));
+    handler.register_method("get_transaction_executor", async_handler!(get_transaction_executor::<S>));
+    handler.register_method("get_fee_histogram", async_handler!(get_fee_histogram::<S>));
+    handler.register_method("estimate_fees", async_handler!(estimate_fees::<S>));
+    handler.register_method("get_stats", async_handler!(get_stats::<S>));
     handler.register_method("get_dev_fee_thresholds", async_handler!(get_dev_fee_thresholds::<S>));
+    handler.register_method("get_block_reward_at", async_handler!(get_block_reward_at::<S>));
     handler.register_method("get_size_on_disk", async_handler!(get_size_on_disk::<S>));
+    handler.register_method("get_storage_stats", async_handler!(get_storage_stats::<S>));
+    handler.register_method("get_active_alerts", async_handler!(get_active_alerts::<S>));
     handler.register_method("get_mempool_cache", async_handler!(get_mempool_cache::<S>));
     handler.register_method("get_difficulty", async_handler!(get_difficulty::<S>));
     handler.register_method("validate_address", async_handler!(validate_address::<S>));
@@ -368,6 +528,8 @@ pub fn register_methods<S: Storage>(handler: &mut RPCHandler<Arc<Blockchain<S>>>
         handler.register_method("get_block_template", async_handler!(get_block_template::<S>));
         handler.register_method("create_miner_work", async_handler!(create_miner_work::<S>));
         handler.register_method("submit_block", async_handler!(submit_block::<S>));
+        handler.register_method("get_aux_commitment", async_handler!(get_aux_commitment::<S>));
+        handler.register_method("set_aux_commitment", async_handler!(set_aux_commitment::<S>));
     }
 }
 
@@ -436,14 +598,56 @@ async fn get_block_template<S: Storage>(context: &Context, body: Value) -> Resul
         return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
     }
 
+    // Same as the getwork server, no template is given before the warm-up is done
+    if !blockchain.is_ready() {
+        return Err(InternalRpcError::AnyError(ApiError::WarmingUp.into()))
+    }
+
+    // Prefix must not overlap the worker id bytes used by the miners
+    // nor the aux commitment, it is written right after it when one is set
+    let offset = if blockchain.get_aux_commitment().await.is_some() {
+        EXTRA_NONCE_AUX_SIZE
+    } else {
+        0
+    };
+    let max_prefix_size = MAX_EXTRA_NONCE_PREFIX_SIZE - offset;
+    let prefix = match params.extra_nonce {
+        Some(value) => hex::decode(value.as_str()).ok()
+            .filter(|prefix| prefix.len() <= max_prefix_size)
+            .ok_or(InternalRpcError::InvalidParamsAny(ApiError::InvalidExtraNoncePrefix(max_prefix_size).into()))?,
+        None => Vec::new()
+    };
+
     let storage = blockchain.get_storage().read().await;
-    let block = blockchain.get_block_template_for_storage(&storage, params.address.into_owned().to_public_key()).await.context("Error while retrieving block template")?;
+    let mut block = blockchain.get_block_template_for_storage(&storage, params.address.into_owned().to_public_key()).await.context("Error while retrieving block template")?;
+    block.set_extra_nonce_prefix_at(offset, &prefix);
     let (difficulty, _) = blockchain.get_difficulty_at_tips(&*storage, block.get_tips().iter()).await.context("Error while retrieving difficulty at tips")?;
     let height = block.height;
     let topoheight = blockchain.get_topo_height();
     Ok(json!(GetBlockTemplateResult { template: block.to_hex(), height, topoheight, difficulty }))
 }
 
+async fn get_aux_commitment<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    Ok(json!(GetAuxCommitmentResult {
+        commitment: blockchain.get_aux_commitment().await,
+        size: EXTRA_NONCE_AUX_SIZE
+    }))
+}
+
+// Admin method: the new value is used by the next jobs only
+async fn set_aux_commitment<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: SetAuxCommitmentParams = parse_params(body)?;
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    blockchain.set_aux_commitment(params.commitment).await;
+    Ok(json!(true))
+}
+
 async fn create_miner_work<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: CreateMinerWorkParams = parse_params(body)?;
     
@@ -466,28 +670,38 @@ async fn create_miner_work<S: Storage>(context: &Context, body: Value) -> Result
 }
 
 async fn submit_block<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    blockchain.check_safe_mode()?;
+
     let params: SubmitBlockParams = parse_params(body)?;
     let mut header = BlockHeader::from_hex(params.block_template)?;
     if let Some(work) = params.miner_work {
         let work = MinerWork::from_hex(work)?;
+        if *work.get_header_work_hash() != header.get_work_hash() {
+            return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidMinerWork.into()))
+        }
         header.apply_miner_work(work);
     }
 
-    let blockchain: &Arc<Blockchain<S>> = context.get()?;
-
-    let block = blockchain.build_block_from_header(Immutable::Owned(header)).await?;
-    blockchain.add_new_block(block, true, true).await?;
-    Ok(json!(true))
+    let (status, block_hash) = blockchain.submit_mined_block(header).await?;
+    Ok(json!(SubmitBlockResult {
+        status,
+        block_hash: Cow::Owned(block_hash)
+    }))
 }
 
 async fn get_balance<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetBalanceParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    get_balance_for_storage(blockchain, &storage, params).await
+}
+
+async fn get_balance_for_storage<S: Storage>(blockchain: &Blockchain<S>, storage: &S, params: GetBalanceParams<'_>) -> Result<Value, InternalRpcError> {
     if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
         return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
     }
 
-    let storage = blockchain.get_storage().read().await;
     let (topoheight, version) = storage.get_last_balance(params.address.get_public_key(), &params.asset).await.context("Error while retrieving last balance")?;
     Ok(json!(GetBalanceResult {
         version,
@@ -518,23 +732,34 @@ async fn get_info<S: Storage>(context: &Context, body: Value) -> Result<Value, I
         return Err(InternalRpcError::UnexpectedParams)
     }
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    get_info_for_storage(blockchain, &storage).await
+}
+
+async fn get_info_for_storage<S: Storage>(blockchain: &Blockchain<S>, storage: &S) -> Result<Value, InternalRpcError> {
     let height = blockchain.get_height();
     let topoheight = blockchain.get_topo_height();
     let stableheight = blockchain.get_stable_height();
-    let (top_block_hash, circulating_supply, pruned_topoheight, average_block_time) = {
-        let storage = blockchain.get_storage().read().await;
-        let top_block_hash = storage.get_hash_at_topo_height(topoheight).await.context("Error while retrieving hash at topo height")?;
-        let supply = storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply at topo height")?;
-        let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?;
-        let average_block_time = blockchain.get_average_block_time::<S>(&storage).await.context("Error while retrieving average block time")?;
-        (top_block_hash, supply, pruned_topoheight, average_block_time)
-    };
+    let top_block_hash = storage.get_hash_at_topo_height(topoheight).await.context("Error while retrieving hash at topo height")?;
+    let circulating_supply = storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply at topo height")?;
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?;
+    let average_block_time = blockchain.get_average_block_time::<S>(storage).await.context("Error while retrieving average block time")?;
     let difficulty = blockchain.get_difficulty().await;
     let block_time_target = BLOCK_TIME_MILLIS;
     let block_reward = get_block_reward(circulating_supply);
+    let (dev_reward, miner_reward) = get_block_rewards(height + 1, Some(block_reward)).unwrap_or_default();
     let mempool_size = blockchain.get_mempool_size().await;
+    let mempool_size_bytes = blockchain.get_mempool_size_bytes().await;
+    let mempool_evicted_count = blockchain.get_mempool_evicted_count().await;
     let version = VERSION.into();
     let network = *blockchain.get_network();
+    let (peer_count, max_peers) = {
+        let p2p = { blockchain.get_p2p().read().await.clone() };
+        match p2p {
+            Some(p2p) => (Some(p2p.get_peer_count().await), Some(p2p.get_max_peers())),
+            None => (None, None)
+        }
+    };
 
     Ok(json!(GetInfoResult {
         height,
@@ -549,8 +774,18 @@ async fn get_info<S: Storage>(context: &Context, body: Value) -> Result<Value, I
         average_block_time,
         block_reward,
         mempool_size,
+        mempool_size_bytes,
+        mempool_evicted_count,
         version,
-        network
+        network,
+        address_index_enabled: blockchain.is_address_index_enabled(),
+        peer_count,
+        max_peers,
+        miner_reward,
+        dev_reward,
+        orphaned_blocks: blockchain.get_orphaned_blocks_count(),
+        ready: blockchain.is_ready(),
+        safe_mode: blockchain.is_safe_mode()
     }))
 }
 
@@ -578,9 +813,10 @@ async fn has_nonce<S: Storage>(context: &Context, body: Value) -> Result<Value,
         return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
     }
 
+    // With a topoheight, the account exists if a version is at or below it
     let storage = blockchain.get_storage().read().await;
     let exist = if let Some(topoheight) = params.topoheight {
-        storage.has_nonce_at_exact_topoheight(params.address.get_public_key(), topoheight).await.context("Error while checking nonce at topo for account")?
+        storage.get_nonce_at_maximum_topoheight(params.address.get_public_key(), topoheight).await.context("Error while checking nonce at topo for account")?.is_some()
     } else {
         storage.has_nonce(params.address.get_public_key()).await.context("Error while checking nonce for account")?
     };
@@ -614,9 +850,14 @@ async fn get_nonce_at_topoheight<S: Storage>(context: &Context, body: Value) ->
         return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
     }
 
+    // Latest version at or below the requested topoheight, its topoheight is returned
+    // as the account may not have sent a TX at this exact topoheight
     let storage = blockchain.get_storage().read().await;
-    let nonce = storage.get_nonce_at_exact_topoheight(params.address.get_public_key(), params.topoheight).await.context("Error while retrieving nonce at exact topo height")?;
-    Ok(json!(nonce))
+    let (topoheight, version) = storage.get_nonce_at_maximum_topoheight(params.address.get_public_key(), params.topoheight).await
+        .context("Error while retrieving nonce at topo height")?
+        .ok_or_else(|| InternalRpcError::InvalidParamsAny(ApiError::NoNonceAtTopoHeight(params.topoheight).into()))?;
+
+    Ok(json!(GetNonceResult { topoheight, version }))
 }
 
 async fn get_asset<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
@@ -632,22 +873,32 @@ const MAX_ASSETS: usize = 100;
 async fn get_assets<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetAssetsParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    let maximum = if let Some(maximum) = params.maximum {
-        if maximum > MAX_ASSETS {
-            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum assets requested cannot be greater than {}", MAX_ASSETS))?
-        }
-        maximum
-    } else {
-        MAX_ASSETS
-    };
-    let skip = params.skip.unwrap_or(0);
+    let pagination = parse_pagination(CursorKind::Assets, params.skip, params.maximum, params.cursor.as_deref(), params.limit, MAX_ASSETS)?;
     let storage = blockchain.get_storage().read().await;
     let min = params.minimum_topoheight.unwrap_or(0);
     let max =  params.maximum_topoheight.unwrap_or_else(|| blockchain.get_topo_height());
-    let assets = storage.get_partial_assets(maximum, skip, min, max).await
-        .context("Error while retrieving registered assets")?;
+    match pagination {
+        Pagination::Offset { skip, maximum } => {
+            let assets = storage.get_partial_assets(maximum, skip, min, max).await
+                .context("Error while retrieving registered assets")?;
 
-    Ok(json!(assets))
+            Ok(json!(assets))
+        },
+        Pagination::Cursor { after, limit } => {
+            // Fetch one more asset to know if there is a next page
+            let mut assets = storage.get_assets_after(after.as_ref(), limit + 1, min, max).await
+                .context("Error while retrieving registered assets")?;
+
+            let next_cursor = if assets.len() > limit {
+                assets.truncate(limit);
+                assets.last().map(|asset| encode_cursor(CursorKind::Assets, asset.get_asset()))
+            } else {
+                None
+            };
+
+            Ok(json!(PagedResult { items: assets.into_iter().collect(), next_cursor }))
+        }
+    }
 }
 
 async fn count_assets<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
@@ -681,6 +932,9 @@ async fn count_transactions<S: Storage>(context: &Context, body: Value) -> Resul
 }
 
 async fn submit_transaction<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    blockchain.check_safe_mode()?;
+
     let params: SubmitTransactionParams = parse_params(body)?;
     // x2 because of hex encoding
     if params.data.len() > MAX_TRANSACTION_SIZE * 2 {
@@ -690,12 +944,39 @@ async fn submit_transaction<S: Storage>(context: &Context, body: Value) -> Resul
     let transaction = Transaction::from_hex(params.data)
         .map_err(|err| InternalRpcError::InvalidParamsAny(err.into()))?;
 
-    let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    blockchain.add_tx_to_mempool(transaction, true).await?;
+    blockchain.add_tx_to_mempool(transaction, true).await
+        .map_err(|e| InternalRpcError::CustomAny(TX_REJECTED_ERROR_CODE, e.into()))?;
 
     Ok(json!(true))
 }
 
+// Same verification as submit_transaction, but the TX is neither added to mempool nor broadcasted
+async fn validate_transaction<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let params: SubmitTransactionParams = parse_params(body)?;
+    // x2 because of hex encoding
+    if params.data.len() > MAX_TRANSACTION_SIZE * 2 {
+        return Err(InternalRpcError::InvalidJSONRequest).context(format!("Transaction size cannot be greater than {}", human_bytes(MAX_TRANSACTION_SIZE as f64)))?
+    }
+
+    let transaction = Transaction::from_hex(params.data)
+        .map_err(|err| InternalRpcError::InvalidParamsAny(err.into()))?;
+
+    let hash = transaction.hash();
+    let error = blockchain.validate_tx(&Arc::new(transaction), &hash).await.err().map(|e| {
+        QueryError {
+            code: TX_REJECTED_ERROR_CODE,
+            message: e.to_string()
+        }
+    });
+
+    Ok(json!(ValidateTransactionResult {
+        hash,
+        valid: error.is_none(),
+        error
+    }))
+}
+
 async fn get_transaction<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetTransactionParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -763,6 +1044,131 @@ async fn get_peers<S: Storage>(context: &Context, body: Value) -> Result<Value,
     }
 }
 
+async fn get_bans<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let p2p = { blockchain.get_p2p().read().await.clone() };
+    match p2p.as_ref() {
+        Some(p2p) => {
+            let bans: Vec<BannedPeerEntry> = p2p.get_peer_list().get_bans().await
+                .into_iter()
+                .map(|(ip, banned_until)| BannedPeerEntry { ip, banned_until })
+                .collect();
+            Ok(json!(bans))
+        },
+        None => Err(InternalRpcError::InvalidParamsAny(ApiError::NoP2p.into()))
+    }
+}
+
+async fn get_peer_version_stats<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let p2p = { blockchain.get_p2p().read().await.clone() };
+    match p2p.as_ref() {
+        Some(p2p) => Ok(json!(p2p.get_peer_version_stats().await)),
+        None => Err(InternalRpcError::InvalidParamsAny(ApiError::NoP2p.into()))
+    }
+}
+
+async fn get_p2p_stats<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let p2p = { blockchain.get_p2p().read().await.clone() };
+    match p2p.as_ref() {
+        Some(p2p) => {
+            let rate_limit = p2p.get_connection_rate_limit();
+            let stats = p2p.get_network_stats();
+            Ok(json!(GetP2pStatsResult {
+                rate_limited_connections: p2p.get_rate_limited_connections(),
+                rejected_connections: p2p.get_rejected_connections(),
+                connection_rate_limit: rate_limit.map(|(rate, _)| rate),
+                connection_rate_burst: rate_limit.map(|(_, burst)| burst),
+                bytes_in: stats.get_bytes_in(),
+                bytes_out: stats.get_bytes_out(),
+                messages_in: stats.get_messages_in(),
+                messages_out: stats.get_messages_out(),
+                peer_count: p2p.get_peer_count().await,
+                handshakes_succeeded: stats.get_handshakes_succeeded(),
+                handshakes_failed: stats.get_handshakes_failed()
+            }))
+        },
+        None => Err(InternalRpcError::InvalidParamsAny(ApiError::NoP2p.into()))
+    }
+}
+
+// Methods only available to the node operator
+const ADMIN_METHODS: [&str; 2] = ["get_rpc_stats", "set_aux_commitment"];
+
+// Check the credentials of the admin methods, others are public
+// Without credentials configured, admin methods are only available to clients connecting from localhost
+// Behind a local reverse proxy, its clients are seen as localhost unless --rpc-trust-proxy is set
+pub struct DaemonRpcAuthorizer {
+    credentials: Option<AdminCredentials>
+}
+
+impl DaemonRpcAuthorizer {
+    pub fn new(credentials: Option<AdminCredentials>) -> Self {
+        Self {
+            credentials
+        }
+    }
+}
+
+#[async_trait]
+impl RpcAuthorizer for DaemonRpcAuthorizer {
+    async fn authorize(&self, context: &Context, method: &str) -> Result<(), InternalRpcError> {
+        if !ADMIN_METHODS.contains(&method) {
+            return Ok(())
+        }
+
+        let authorized = match self.credentials.as_ref() {
+            Some(credentials) => context.get_optional::<ClientAuthorization>()
+                .and_then(|ClientAuthorization(value)| Basic::parse(value).ok())
+                .is_some_and(|basic| is_valid_credentials(&basic, credentials)),
+            None => context.get_optional::<ClientIp>().is_some_and(|ClientIp(ip)| ip.is_loopback())
+        };
+
+        if !authorized {
+            return Err(InternalRpcError::Unauthorized)
+        }
+
+        Ok(())
+    }
+}
+
+// Compared in constant time to not leak the credentials through the response time
+fn is_valid_credentials(basic: &Basic, credentials: &AdminCredentials) -> bool {
+    let username = basic.user_id().as_bytes().ct_eq(credentials.username.as_bytes());
+    let password = basic.password().unwrap_or_default().as_bytes().ct_eq(credentials.password.as_bytes());
+    (username & password).into()
+}
+
+// Admin method
+async fn get_rpc_stats<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let rpc = blockchain.get_rpc().read().await;
+    let rpc = rpc.as_ref().ok_or_else(|| InternalRpcError::InvalidParamsAny(ApiError::NoRpcServer.into()))?;
+    let limiter = rpc.get_rate_limiter();
+    Ok(json!(GetRpcStatsResult {
+        throttled_requests: limiter.get_throttled_requests(),
+        concurrent_rejections: limiter.get_concurrent_rejections(),
+        active_clients: limiter.count_active_clients(),
+        rate_limit: limiter.get_requests_per_minute(),
+        concurrent_limit: limiter.get_concurrent_limit(),
+        trust_proxy: limiter.is_proxy_trusted()
+    }))
+}
+
 async fn get_mempool<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     if body != Value::Null {
         return Err(InternalRpcError::UnexpectedParams)
@@ -778,6 +1184,77 @@ async fn get_mempool<S: Storage>(context: &Context, body: Value) -> Result<Value
     Ok(json!(transactions))
 }
 
+async fn get_mempool_summary<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    Ok(get_mempool_summary_for(blockchain).await)
+}
+
+async fn get_mempool_summary_for<S: Storage>(blockchain: &Blockchain<S>) -> Value {
+    let mempool = blockchain.get_mempool().read().await;
+    json!(GetMempoolSummaryResult {
+        size: mempool.size(),
+        size_bytes: mempool.size_bytes(),
+        evicted_count: mempool.get_evicted_count()
+    })
+}
+
+// Execute several sub-queries concurrently against the same storage snapshot
+// Each one is authorized and executed on its own, a failure is only reported under its label
+async fn get_many<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetManyParams<Value> = parse_params(body)?;
+    if params.queries.is_empty() || params.queries.len() > MAX_GET_MANY_QUERIES {
+        return Err(InternalRpcError::InvalidJSONRequest).context(format!("Between 1 and {} queries are allowed", MAX_GET_MANY_QUERIES))?
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let rpc = { blockchain.get_rpc().read().await.clone() };
+    let storage = blockchain.get_storage().read().await;
+    let queries = params.queries.into_iter().map(|(label, query)| {
+        let rpc = rpc.as_ref();
+        let storage = &*storage;
+        async move {
+            let result: Result<Value, InternalRpcError> = async {
+                let query: ManyQuery = serde_json::from_value(query).map_err(InternalRpcError::InvalidJSONParams)?;
+                if let Some(rpc) = rpc {
+                    rpc.get_rpc_handler().authorize_method(context, query.get_method()).await?;
+                }
+                execute_many_query(blockchain, storage, query).await
+            }.await;
+
+            let result = match result {
+                Ok(value) => ManyQueryResult::Result(value),
+                Err(e) => ManyQueryResult::Error(QueryError {
+                    code: e.get_code(),
+                    message: e.to_string()
+                })
+            };
+            (label, result)
+        }
+    });
+
+    let results: GetManyResult = join_all(queries).await.into_iter().collect();
+    Ok(json!(results))
+}
+
+async fn execute_many_query<S: Storage>(blockchain: &Blockchain<S>, storage: &S, query: ManyQuery<'_>) -> Result<Value, InternalRpcError> {
+    match query {
+        ManyQuery::GetInfo => get_info_for_storage(blockchain, storage).await,
+        ManyQuery::GetBlockAtTopoheight(params) => {
+            let hash = storage.get_hash_at_topo_height(params.topoheight).await.context("Error while retrieving hash at topo height")?;
+            get_block_response_for_hash(blockchain, storage, &hash, params.include_txs).await
+        },
+        ManyQuery::GetBalance(params) => get_balance_for_storage(blockchain, storage, params).await,
+        ManyQuery::GetMempoolSummary => Ok(get_mempool_summary_for(blockchain).await),
+        ManyQuery::GetStats(params) => {
+            let stats = blockchain.get_stats_for_storage(storage, params.blocks.unwrap_or(DEFAULT_STATS_BLOCKS)).await?;
+            Ok(json!(stats))
+        }
+    }
+}
+
 async fn get_blocks_at_height<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetBlocksAtHeightParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -857,14 +1334,13 @@ async fn get_blocks_range_by_topoheight<S: Storage>(context: &Context, body: Val
     let (start_topoheight, end_topoheight) = get_range(params.start_topoheight, params.end_topoheight, MAX_BLOCKS, current_topoheight)?;
 
     let storage = blockchain.get_storage().read().await;
-    let mut blocks = Vec::with_capacity((end_topoheight - start_topoheight) as usize);
+    let mut hashes = Vec::with_capacity((end_topoheight - start_topoheight) as usize);
     for i in start_topoheight..=end_topoheight {
         let hash = storage.get_hash_at_topo_height(i).await.context("Error while retrieving hash at topo height")?;
-        let response = get_block_response_for_hash(&blockchain, &storage, &hash, false).await?;
-        blocks.push(response);
+        hashes.push(hash);
     }
 
-    Ok(json!(blocks))
+    get_blocks_range_response(&blockchain, &storage, hashes, params.encoding).await
 }
 
 // get blocks between range of height
@@ -877,18 +1353,69 @@ async fn get_blocks_range_by_height<S: Storage>(context: &Context, body: Value)
     let (start_height, end_height) = get_range(params.start_height, params.end_height, MAX_BLOCKS, current_height)?;
 
     let storage = blockchain.get_storage().read().await;
-    let mut blocks = Vec::with_capacity((end_height - start_height) as usize);
+    let mut hashes = Vec::with_capacity((end_height - start_height) as usize);
     for i in start_height..=end_height {
         let blocks_at_height = storage.get_blocks_at_height(i).await.context("Error while retrieving blocks at height")?;
-        for hash in blocks_at_height {
-            let response = get_block_response_for_hash(&blockchain, &storage, &hash, false).await?;
-            blocks.push(response);
-        }
+        hashes.extend(blocks_at_height);
+    }
+
+    get_blocks_range_response(&blockchain, &storage, hashes, params.encoding).await
+}
+
+// Check the inclusive bounds of a get_blocks_range request
+// and return the number of blocks requested
+fn check_blocks_range(start: u64, end: u64, maximum: u64, current: u64) -> Result<u64, InternalRpcError> {
+    if end < start || end > current {
+        return Err(InternalRpcError::InvalidJSONRequest).context(format!("Invalid range requested, start: {}, end: {}, current topoheight: {}", start, end, current))?
+    }
+
+    // end - start + 1 would overflow for the whole u64 range
+    if end - start >= maximum {
+        return Err(InternalRpcError::InvalidJSONRequest).context(format!("Invalid range count requested, received {} but maximum is {}", (end - start).saturating_add(1), maximum))?
+    }
+
+    Ok(end - start + 1)
+}
+
+// get the blocks between two topoheights for a fast sync
+// each block has its tips so the client can verify the blocks are linked
+async fn get_blocks_range<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetBlocksRangeParams = parse_params(body)?;
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let count = check_blocks_range(params.start_topoheight, params.end_topoheight, blockchain.get_rpc_max_blocks_range(), blockchain.get_topo_height())?;
+
+    let storage = blockchain.get_storage().read().await;
+    let mut blocks = Vec::with_capacity(count as usize);
+    for topoheight in params.start_topoheight..=params.end_topoheight {
+        let hash = storage.get_hash_at_topo_height(topoheight).await.context("Error while retrieving hash at topo height")?;
+        blocks.push(get_block_response_for_hash(&blockchain, &storage, &hash, params.include_txs).await?);
     }
 
     Ok(json!(blocks))
 }
 
+// Build the blocks range response in the requested encoding
+async fn get_blocks_range_response<S: Storage>(blockchain: &Blockchain<S>, storage: &S, hashes: Vec<Hash>, encoding: ResponseEncoding) -> Result<Value, InternalRpcError> {
+    match encoding {
+        ResponseEncoding::Json => {
+            let mut blocks = Vec::with_capacity(hashes.len());
+            for hash in hashes {
+                let response = get_block_response_for_hash(blockchain, storage, &hash, false).await?;
+                blocks.push(response);
+            }
+            Ok(json!(blocks))
+        },
+        ResponseEncoding::Binary => {
+            let mut entries = Vec::with_capacity(hashes.len());
+            for hash in hashes {
+                entries.push(get_binary_block_for_hash(blockchain, storage, &hash).await?);
+            }
+            Ok(json!(encode_binary(&BinaryPayload { mainnet: blockchain.get_network().is_mainnet(), entries })))
+        }
+    }
+}
+
 const MAX_TXS: usize = 20;
 // get up to 20 transactions at once
 // if a tx hash is not present, we keep the order and put json "null" value
@@ -903,6 +1430,22 @@ async fn get_transactions<S: Storage>(context: &Context, body: Value) -> Result<
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
     let storage = blockchain.get_storage().read().await;
     let mempool = blockchain.get_mempool().read().await;
+    if params.encoding == ResponseEncoding::Binary {
+        let mut entries: Vec<Option<BinaryTransaction>> = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            let tx = match get_binary_transaction_for_hash(&*storage, &mempool, &hash).await {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    debug!("Error while retrieving tx {} from storage: {}", hash, e);
+                    None
+                }
+            };
+            entries.push(tx);
+        }
+
+        return Ok(json!(encode_binary(&BinaryPayload { mainnet: storage.is_mainnet(), entries })))
+    }
+
     let mut transactions: Vec<Option<Value>> = Vec::with_capacity(hashes.len());
     for hash in hashes {
         let tx = match get_transaction_response_for_hash(&*storage, &mempool, &hash).await {
@@ -927,6 +1470,16 @@ async fn get_account_history<S: Storage>(context: &Context, body: Value) -> Resu
         return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
     }
 
+    let maximum = if let Some(maximum) = params.maximum {
+        if maximum > MAX_HISTORY {
+            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum history requested cannot be greater than {}", MAX_HISTORY))?
+        }
+        maximum
+    } else {
+        MAX_HISTORY
+    };
+    let skip = params.skip.unwrap_or(0);
+
     let key = params.address.get_public_key();
     let minimum_topoheight = params.minimum_topoheight.unwrap_or(0);
     let storage = blockchain.get_storage().read().await;
@@ -940,6 +1493,7 @@ async fn get_account_history<S: Storage>(context: &Context, body: Value) -> Resu
         Some(storage.get_last_balance(key, &params.asset).await.context("Error while retrieving last balance")?)
     };
 
+    let mut skipped = 0;
     let mut history_count = 0;
     let mut history = Vec::new();
     let is_dev_address = *key == *DEV_PUBLIC_KEY;
@@ -950,6 +1504,18 @@ async fn get_account_history<S: Storage>(context: &Context, body: Value) -> Resu
                 break;
             }
 
+            if skipped < skip {
+                skipped += 1;
+                trace!("Skipping history at topoheight {}", topo);
+                if let Some(previous) = versioned_balance.get_previous_topoheight() {
+                    if previous < pruned_topoheight {
+                        break;
+                    }
+                    version = Some((previous, storage.get_balance_at_exact_topoheight(key, &params.asset, previous).await.context(format!("Error while retrieving previous balance at topo height {previous}"))?));
+                }
+                continue;
+            }
+
             let (hash, block_header) = storage.get_block_header_at_topoheight(topo).await.context(format!("Error while retrieving block header at topo height {topo}"))?;
             // Block reward is only paid in XELIS
             if params.asset == XELIS_ASSET {
@@ -1032,7 +1598,7 @@ async fn get_account_history<S: Storage>(context: &Context, body: Value) -> Resu
             }
 
             history_count += 1;
-            if history_count >= MAX_HISTORY {
+            if history_count >= maximum {
                 break;   
             }        
     
@@ -1047,9 +1613,114 @@ async fn get_account_history<S: Storage>(context: &Context, body: Value) -> Resu
         }
     }
 
+    if params.encoding == ResponseEncoding::Binary {
+        let entries = history.iter().map(BinaryAccountHistoryEntry::from).collect();
+        return Ok(json!(encode_binary(&BinaryPayload { mainnet: blockchain.get_network().is_mainnet(), entries })))
+    }
+
+    Ok(json!(history))
+}
+
+// retrieve balance changes for an account on an asset, aggregated per topoheight
+// Kept apart from get_account_history which returns an entry per transfer, burn or reward
+// with another schema already used by the wallets and explorers
+async fn get_account_balance_history<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAccountBalanceHistoryParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
+        return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
+    }
+
+    let maximum = if let Some(maximum) = params.maximum {
+        if maximum > MAX_HISTORY {
+            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum history requested cannot be greater than {}", MAX_HISTORY))?
+        }
+        maximum
+    } else {
+        MAX_HISTORY
+    };
+
+    let key = params.address.get_public_key();
+    let storage = blockchain.get_storage().read().await;
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?.unwrap_or(0);
+    let minimum_topoheight = params.minimum_topoheight.unwrap_or(0).max(pruned_topoheight);
+    let maximum_topoheight = params.maximum_topoheight.unwrap_or_else(|| blockchain.get_topo_height());
+    if maximum_topoheight < pruned_topoheight {
+        return Err(InternalRpcError::InvalidParams("Maximum topoheight is lower than pruned topoheight"));
+    }
+
+    let versions = storage.get_balance_history(key, &params.asset, minimum_topoheight, maximum_topoheight, maximum).await
+        .context("Error while retrieving balance history")?;
+
+    let mut history = Vec::with_capacity(versions.len());
+    for (topoheight, version) in versions {
+        let (block_hash, block_header) = storage.get_block_header_at_topoheight(topoheight).await.context(format!("Error while retrieving block header at topo height {topoheight}"))?;
+        history.push(AccountBalanceHistoryEntry {
+            topoheight,
+            block_hash: Cow::Owned(block_hash),
+            balance_type: version.get_balance_type(),
+            block_timestamp: block_header.get_timestamp(),
+            previous_topoheight: version.get_previous_topoheight()
+        });
+    }
+
     Ok(json!(history))
 }
 
+const MAX_ADDRESS_TRANSACTIONS: usize = 100;
+// retrieve all transactions and block rewards involving an account using the address index
+async fn get_address_transactions<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAddressTransactionsParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if !blockchain.is_address_index_enabled() {
+        return Err(InternalRpcError::InvalidParamsAny(ApiError::AddressIndexDisabled.into()))
+    }
+
+    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
+        return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
+    }
+
+    let maximum = if let Some(maximum) = params.maximum {
+        if maximum > MAX_ADDRESS_TRANSACTIONS {
+            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum transactions requested cannot be greater than {}", MAX_ADDRESS_TRANSACTIONS))?
+        }
+        maximum
+    } else {
+        MAX_ADDRESS_TRANSACTIONS
+    };
+    let skip = params.skip.unwrap_or(0);
+
+    let topoheight = blockchain.get_topo_height();
+    let minimum_topoheight = params.minimum_topoheight.unwrap_or(0);
+    let maximum_topoheight = params.maximum_topoheight.unwrap_or(topoheight);
+    if maximum_topoheight < minimum_topoheight {
+        return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum topoheight requested must be greater or equal to {}", minimum_topoheight))?
+    }
+
+    let storage = blockchain.get_storage().read().await;
+    let entries = storage.get_address_transactions(params.address.get_public_key(), minimum_topoheight, maximum_topoheight, skip, maximum).await
+        .context("Error while retrieving address transactions")?;
+
+    let mut transactions = Vec::with_capacity(entries.len());
+    for (topoheight, hash, entry_type) in entries {
+        let (block_hash, block_header) = storage.get_block_header_at_topoheight(topoheight).await.context(format!("Error while retrieving block header at topo height {topoheight}"))?;
+        let tx_hash = match entry_type {
+            AddressTransactionType::Mining | AddressTransactionType::DevFee => None,
+            _ => Some(Cow::Owned(hash))
+        };
+
+        transactions.push(AddressTransactionEntry {
+            tx_hash,
+            block_hash: Cow::Owned(block_hash),
+            topoheight,
+            block_timestamp: block_header.get_timestamp(),
+            entry_type
+        });
+    }
+
+    Ok(json!(transactions))
+}
+
 async fn get_account_assets<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetAccountAssetsParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -1063,21 +1734,52 @@ async fn get_account_assets<S: Storage>(context: &Context, body: Value) -> Resul
     Ok(json!(assets))
 }
 
-const MAX_ACCOUNTS: usize = 100;
-// retrieve all available accounts (each account got at least one interaction on chain)
-async fn get_accounts<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
-    let params: GetAccountsParams = parse_params(body)?;
+const MAX_ACCOUNT_ASSETS: usize = 100;
+// retrieve the assets of an account with their last balance in one request
+// assets are ordered by hash, skip and maximum are unstable if the account receives a new asset
+async fn get_account_assets_with_balances<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAccountAssetsWithBalancesParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    let topoheight = blockchain.get_topo_height();
+    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
+        return Err(InternalRpcError::InvalidParamsAny(BlockchainError::InvalidNetwork.into()))
+    }
+
     let maximum = if let Some(maximum) = params.maximum {
-        if maximum > MAX_ACCOUNTS {
-            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum accounts requested cannot be greater than {}", MAX_ACCOUNTS))?
+        if maximum > MAX_ACCOUNT_ASSETS {
+            return Err(InternalRpcError::InvalidJSONRequest).context(format!("Maximum assets requested cannot be greater than {}", MAX_ACCOUNT_ASSETS))?
         }
         maximum
     } else {
-        MAX_ACCOUNTS
+        MAX_ACCOUNT_ASSETS
     };
     let skip = params.skip.unwrap_or(0);
+
+    let key = params.address.get_public_key();
+    let storage = blockchain.get_storage().read().await;
+    let assets = storage.get_assets_for(key).await.context("Error while retrieving assets for account")?;
+
+    let mut balances = Vec::with_capacity(maximum.min(assets.len().saturating_sub(skip)));
+    for asset in assets.into_iter().skip(skip).take(maximum) {
+        let (topoheight, version) = storage.get_last_balance(key, &asset).await.context(format!("Error while retrieving last balance for asset {}", asset))?;
+        balances.push(AccountAssetWithBalance {
+            asset: Cow::Owned(asset),
+            balance: GetBalanceResult {
+                version,
+                topoheight
+            }
+        });
+    }
+
+    Ok(json!(balances))
+}
+
+const MAX_ACCOUNTS: usize = 100;
+// retrieve all available accounts (each account got at least one interaction on chain)
+async fn get_accounts<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAccountsParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let topoheight = blockchain.get_topo_height();
+    let pagination = parse_pagination(CursorKind::Accounts, params.skip, params.maximum, params.cursor.as_deref(), params.limit, MAX_ACCOUNTS)?;
     let minimum_topoheight = if let Some(minimum) = params.minimum_topoheight {
         if minimum > topoheight {
             return Err(InternalRpcError::InvalidJSONRequest).context(format!("Minimum topoheight requested cannot be greater than {}", topoheight))?
@@ -1102,11 +1804,30 @@ async fn get_accounts<S: Storage>(context: &Context, body: Value) -> Result<Valu
 
     let storage = blockchain.get_storage().read().await;
     let mainnet = storage.is_mainnet();
-    let accounts = storage.get_partial_keys(maximum, skip, minimum_topoheight, maximum_topoheight).await
-        .context("Error while retrieving accounts")?
-        .into_iter().map(|key| key.to_address(mainnet)).collect::<Vec<_>>();
+    match pagination {
+        Pagination::Offset { skip, maximum } => {
+            let accounts = storage.get_partial_keys(maximum, skip, minimum_topoheight, maximum_topoheight).await
+                .context("Error while retrieving accounts")?
+                .into_iter().map(|key| key.to_address(mainnet)).collect::<Vec<_>>();
 
-    Ok(json!(accounts))
+            Ok(json!(accounts))
+        },
+        Pagination::Cursor { after, limit } => {
+            // Fetch one more account to know if there is a next page
+            let mut keys = storage.get_keys_after(after.as_ref(), limit + 1, minimum_topoheight, maximum_topoheight).await
+                .context("Error while retrieving accounts")?;
+
+            let next_cursor = if keys.len() > limit {
+                keys.truncate(limit);
+                keys.last().map(|key| encode_cursor(CursorKind::Accounts, key))
+            } else {
+                None
+            };
+
+            let items = keys.into_iter().map(|key| key.to_address(mainnet)).collect::<Vec<_>>();
+            Ok(json!(PagedResult { items, next_cursor }))
+        }
+    }
 }
 
 // Check if the account is registered on chain or not
@@ -1144,6 +1865,77 @@ async fn is_tx_executed_in_block<S: Storage>(context: &Context, body: Value) ->
     Ok(json!(storage.is_tx_executed_in_block(&params.tx_hash, &params.block_hash).context("Error while checking if tx was executed in block")?))
 }
 
+// Find the block in which the TX was executed
+async fn get_transaction_executor<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetTransactionExecutorParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    let hash = params.hash.into_owned();
+
+    if !storage.is_tx_executed_in_a_block(&hash).context("Error while checking if tx was executed")? {
+        let (code, err) = if blockchain.get_mempool().read().await.contains_tx(&hash) {
+            (TX_IN_MEMPOOL_ERROR_CODE, ApiError::TxInMempool(hash))
+        } else if storage.has_transaction(&hash).await.context("Error while checking if tx is stored")? {
+            (TX_NOT_EXECUTED_ERROR_CODE, ApiError::TxNotExecuted(hash))
+        } else {
+            (TX_NOT_FOUND_ERROR_CODE, ApiError::TxNotFound(hash))
+        };
+        return Err(InternalRpcError::CustomAny(code, err.into()))
+    }
+
+    let block_hash = storage.get_block_executor_for_tx(&hash).context("Error while retrieving block executor")?;
+    let block_topoheight = storage.get_topo_height_for_hash(&block_hash).await.context("Error while retrieving block topoheight")?;
+    let block_timestamp = storage.get_timestamp_for_block_hash(&block_hash).await.context("Error while retrieving block timestamp")?;
+
+    Ok(json!(GetTransactionExecutorResult {
+        block_hash: Cow::Owned(block_hash),
+        block_topoheight,
+        block_timestamp
+    }))
+}
+
+// Distribution of the fees and sizes of the TXs executed in the recent blocks
+async fn get_fee_histogram<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetFeeHistogramParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let fee_histogram = blockchain.get_fee_histogram().lock().await;
+    let window = params.window.unwrap_or(fee_histogram.get_window());
+    if window == 0 || window > fee_histogram.get_window() {
+        return Err(InternalRpcError::InvalidParams("Invalid window, must be between 1 and the window configured"))
+    }
+
+    let (blocks, histograms) = fee_histogram.aggregate(window);
+    Ok(json!(GetFeeHistogramResult {
+        blocks,
+        fees: histograms.fees.to_result(),
+        sizes: histograms.sizes.to_result()
+    }))
+}
+
+// Minimum and suggested fees for a TX with the given size and transfers
+async fn estimate_fees<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: EstimateFeesParams = parse_params(body)?;
+    if params.tx_size == 0 || params.tx_size > MAX_TRANSACTION_SIZE {
+        return Err(InternalRpcError::InvalidParams("Invalid TX size"))
+    }
+
+    if params.new_addresses > params.transfers {
+        return Err(InternalRpcError::InvalidParams("New addresses can't be above the transfers count"))
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let estimate = blockchain.estimate_fees(params.tx_size, params.transfers, params.new_addresses).await;
+    Ok(json!(estimate))
+}
+
+// Rolling stats over the last N blocks ordered
+async fn get_stats<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetStatsParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let stats = blockchain.get_stats(params.blocks.unwrap_or(DEFAULT_STATS_BLOCKS)).await?;
+    Ok(json!(stats))
+}
+
 // Get the configured dev fees
 async fn get_dev_fee_thresholds<S: Storage>(_: &Context, body: Value) -> Result<Value, InternalRpcError> {
     if body != Value::Null {
@@ -1153,6 +1945,72 @@ async fn get_dev_fee_thresholds<S: Storage>(_: &Context, body: Value) -> Result<
     Ok(json!(DEV_FEES))
 }
 
+// Compute the expected block reward at any height using the emission curve
+// Future heights are projected from the current circulating supply
+// while past heights are computed from the supply stored at their topoheight
+async fn get_block_reward_at<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetBlockRewardAtParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let current_height = blockchain.get_height();
+
+    let supply = if params.height > current_height {
+        let blocks = params.height - current_height - 1;
+        if blocks >= MAX_BLOCK_REWARD_PROJECTION {
+            return Err(InternalRpcError::InvalidParamsAny(ApiError::BlockRewardProjectionTooFar(current_height + MAX_BLOCK_REWARD_PROJECTION).into()))
+        }
+
+        let supply = {
+            let storage = blockchain.get_storage().read().await;
+            storage.get_supply_at_topo_height(blockchain.get_topo_height()).await.context("Error while retrieving supply at topo height")?
+        };
+
+        // Projection can take some time for heights far in the future
+        tokio::task::spawn_blocking(move || get_projected_supply(supply, blocks, BLOCK_TIME_MILLIS))
+            .await.context("Error while computing projected supply")?
+    } else {
+        // Past heights use the supply stored before the first ordered block at this height
+        let storage = blockchain.get_storage().read().await;
+        let mut topoheight = None;
+        for hash in storage.get_blocks_at_height(params.height).await.context("Error while retrieving blocks at height")? {
+            if storage.is_block_topological_ordered(&hash).await {
+                let topo = storage.get_topo_height_for_hash(&hash).await.context("Error while retrieving topo height")?;
+                topoheight = Some(topoheight.map_or(topo, |current: u64| current.min(topo)));
+            }
+        }
+
+        match topoheight {
+            Some(0) => 0,
+            Some(topoheight) => storage.get_supply_at_topo_height(topoheight - 1).await.context("Error while retrieving supply at topo height")?,
+            None => return Err(InternalRpcError::InvalidParamsAny(ApiError::NoBlockAtHeight(params.height).into()))
+        }
+    };
+
+    let reward = get_block_reward(supply);
+    let (dev_reward, miner_reward) = get_block_rewards(params.height, Some(reward)).unwrap_or_default();
+
+    Ok(json!(GetBlockRewardAtResult {
+        height: params.height,
+        supply,
+        reward,
+        miner_reward,
+        dev_reward
+    }))
+}
+
+// Alerts whose condition is currently met, empty if no alert rule is set
+async fn get_active_alerts<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let alerts = match blockchain.get_alerts() {
+        Some(alerts) => alerts.get_active_alerts().await,
+        None => Vec::new()
+    };
+
+    Ok(json!(alerts))
+}
+
 // Get size on disk of the chain database
 async fn get_size_on_disk<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     if body != Value::Null {
@@ -1169,6 +2027,27 @@ async fn get_size_on_disk<S: Storage>(context: &Context, body: Value) -> Result<
     }))
 }
 
+// Get the entries count and size of each storage column
+// All the storage is iterated, this may take a while on a big chain
+async fn get_storage_stats<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    let columns = storage.get_storage_stats().await.context("Error while retrieving storage stats")?;
+    let size_on_disk = storage.get_size_on_disk().await.context("Error while retrieving size on disk")?;
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?;
+
+    Ok(json!(GetStorageStatsResult {
+        total_entries: columns.iter().map(|column| column.entries).sum(),
+        total_size_bytes: columns.iter().map(|column| column.size_bytes).sum(),
+        columns,
+        size_on_disk,
+        pruned_topoheight
+    }))
+}
+
 // Retrieve the mempool cache for an account
 async fn get_mempool_cache<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetMempoolCacheParams = parse_params(body)?;
@@ -1183,7 +2062,7 @@ async fn get_mempool_cache<S: Storage>(context: &Context, body: Value) -> Result
 
     let mempool = blockchain.get_mempool().read().await;
     let cache = mempool.get_cache_for(params.address.get_public_key())
-        .context("Account not found while retrieving mempool cache")?;
+        .ok_or_else(|| InternalRpcError::CustomAny(ACCOUNT_NOT_IN_MEMPOOL_ERROR_CODE, ApiError::AccountNotInMempool.into()))?;
 
     Ok(json!(cache))
 }
@@ -1195,22 +2074,50 @@ async fn get_difficulty<S: Storage>(context: &Context, body: Value) -> Result<Va
 
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
     let difficulty = blockchain.get_difficulty().await;
-    let hashrate = difficulty / BLOCK_TIME;
+    let target = compute_difficulty_target_hash(&difficulty).context("Error while computing difficulty target")?;
+    let (hashrate, average_block_time) = {
+        let storage = blockchain.get_storage().read().await;
+        let hashrate = blockchain.get_estimated_hashrate::<S>(&storage).await.context("Error while estimating hashrate")?;
+        let average_block_time = blockchain.get_average_block_time::<S>(&storage).await.context("Error while retrieving average block time")?;
+        (hashrate, average_block_time)
+    };
     let hashrate_formatted = format_hashrate(hashrate.into());
     Ok(json!(GetDifficultyResult {
         hashrate,
         hashrate_formatted,
         difficulty,
+        target,
+        average_block_time
     }))
 }
 
-async fn validate_address<S: Storage>(_: &Context, body: Value) -> Result<Value, InternalRpcError> {
+async fn validate_address<S: Storage>(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: ValidateAddressParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
 
-    Ok(json!(ValidateAddressResult {
-        is_valid: params.address.is_normal() || (!params.address.is_normal() && params.allow_integrated),
-        is_integrated: !params.address.is_normal(),
-    }))
+    let result = match Address::from_str(&params.address) {
+        Ok(address) => {
+            let validation = address.validate_for_network(blockchain.get_network());
+            ValidateAddressResult {
+                is_valid: validation.is_valid(params.allow_integrated),
+                is_integrated: validation.is_integrated,
+                is_parsed: true,
+                is_network_valid: validation.is_network_valid,
+                data_size: validation.data_size,
+                error: None
+            }
+        },
+        Err(e) => ValidateAddressResult {
+            is_valid: false,
+            is_integrated: false,
+            is_parsed: false,
+            is_network_valid: false,
+            data_size: None,
+            error: Some(e.to_string())
+        }
+    };
+
+    Ok(json!(result))
 }
 
 async fn extract_key_from_address<S: Storage>(_: &Context, body: Value) -> Result<Value, InternalRpcError> {
@@ -1231,9 +2138,243 @@ async fn split_address<S: Storage>(_: &Context, body: Value) -> Result<Value, In
 
     let (data, address) = address.extract_data();
     let integrated_data = data.ok_or(InternalRpcError::InvalidParams("Address is not an integrated address"))?;
+    let payment_id = PaymentId::from_data(&integrated_data);
 
     Ok(json!(SplitAddressResult {
         address,
-        integrated_data
+        integrated_data,
+        payment_id
     }))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_range_cap() {
+        assert_eq!(check_blocks_range(0, 9, 10, 100).unwrap(), 10);
+        assert_eq!(check_blocks_range(5, 5, 10, 100).unwrap(), 1);
+        // 11 blocks requested
+        assert!(check_blocks_range(0, 10, 10, 100).is_err());
+        assert!(check_blocks_range(0, u64::MAX, u64::MAX, u64::MAX).is_err());
+        assert_eq!(check_blocks_range(1, u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_admin_authorizer() {
+        use std::net::IpAddr;
+        use actix_web::http::header::{HeaderValue, TryIntoHeaderValue};
+
+        async fn is_authorized(authorizer: &DaemonRpcAuthorizer, ip: &str, authorization: Option<HeaderValue>, method: &str) -> bool {
+            let mut context = Context::new();
+            context.store(ClientIp(ip.parse::<IpAddr>().unwrap()));
+            if let Some(value) = authorization {
+                context.store(ClientAuthorization(value));
+            }
+            authorizer.authorize(&context, method).await.is_ok()
+        }
+
+        let basic = |username: &str, password: &str| Some(Basic::new(username.to_owned(), Some(password.to_owned())).try_into_value().unwrap());
+
+        // Without credentials, only localhost
+        let authorizer = DaemonRpcAuthorizer::new(None);
+        assert!(is_authorized(&authorizer, "127.0.0.1", None, "set_aux_commitment").await);
+        assert!(!is_authorized(&authorizer, "1.2.3.4", None, "get_rpc_stats").await);
+        assert!(is_authorized(&authorizer, "1.2.3.4", None, "get_info").await);
+
+        // Localhost is not enough once credentials are set
+        let authorizer = DaemonRpcAuthorizer::new(Some(AdminCredentials {
+            username: "admin".to_owned(),
+            password: "password".to_owned()
+        }));
+        assert!(!is_authorized(&authorizer, "127.0.0.1", None, "get_rpc_stats").await);
+        assert!(!is_authorized(&authorizer, "127.0.0.1", basic("admin", "wrong"), "get_rpc_stats").await);
+        assert!(is_authorized(&authorizer, "1.2.3.4", basic("admin", "password"), "get_rpc_stats").await);
+        assert!(is_authorized(&authorizer, "1.2.3.4", None, "get_info").await);
+    }
+
+    #[test]
+    fn test_blocks_range_bounds() {
+        // Inverted bounds
+        assert!(check_blocks_range(10, 5, 10, 100).is_err());
+        // Above the current topoheight
+        assert!(check_blocks_range(95, 101, 10, 100).is_err());
+        assert_eq!(check_blocks_range(91, 100, 10, 100).unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_account_balance_history() {
+        use xelis_common::{account::BalanceType, crypto::KeyPair};
+        use crate::core::{storage::{DagOrderProvider, SledStorage}, testing::{fund_account, test_blockchain}};
+
+        async fn get_history(handler: &RPCHandler<Arc<Blockchain<SledStorage>>>, address: &Address, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>, maximum: Option<usize>) -> Vec<AccountBalanceHistoryEntry<'static>> {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "get_account_balance_history",
+                "params": {
+                    "address": address,
+                    "asset": XELIS_ASSET,
+                    "minimum_topoheight": minimum_topoheight,
+                    "maximum_topoheight": maximum_topoheight,
+                    "maximum": maximum
+                }
+            }).to_string();
+            let response = handler.handle_request(request.as_bytes()).await.unwrap();
+            serde_json::from_value(response["result"].clone()).unwrap()
+        }
+
+        let blockchain = test_blockchain().await;
+        let key = KeyPair::new().get_public_key().compress();
+        fund_account(&blockchain, &key, 1000).await;
+
+        // Each block reward is a new balance version
+        for _ in 0..2 {
+            let block = blockchain.mine_block(&key).await.unwrap();
+            blockchain.add_new_block(block, false, false).await.unwrap();
+        }
+
+        let mut handler = RPCHandler::new(Arc::clone(&blockchain));
+        register_methods(&mut handler, false);
+        let address = key.as_address(false);
+        let entries = get_history(&handler, &address, None, None, None).await;
+        assert_eq!(entries.len(), 3);
+
+        // Newest first, each entry points to the next one
+        assert!(entries.windows(2).all(|entries| entries[0].previous_topoheight == Some(entries[1].topoheight)));
+        assert!(entries.iter().all(|entry| entry.balance_type == BalanceType::Input));
+        assert_eq!((entries[2].topoheight, entries[2].previous_topoheight), (0, None));
+        {
+            let storage = blockchain.get_storage().read().await;
+            for entry in &entries {
+                assert_eq!(*entry.block_hash, storage.get_hash_at_topo_height(entry.topoheight).await.unwrap());
+            }
+        }
+
+        // Next page continues below the last entry
+        let page = get_history(&handler, &address, None, Some(entries[0].topoheight - 1), Some(1)).await;
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].topoheight, entries[1].topoheight);
+
+        let page = get_history(&handler, &address, Some(1), None, None).await;
+        assert_eq!(page.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_block_reward_at() {
+        use xelis_common::crypto::KeyPair;
+        use crate::core::{storage::BlockDagProvider, testing::test_blockchain};
+
+        let blockchain = test_blockchain().await;
+        let key = KeyPair::new().get_public_key().compress();
+        for _ in 0..2 {
+            let block = blockchain.mine_block(&key).await.unwrap();
+            blockchain.add_new_block(block, false, false).await.unwrap();
+        }
+
+        let mut handler = RPCHandler::new(Arc::clone(&blockchain));
+        register_methods(&mut handler, false);
+        let request = |height: u64| json!({ "jsonrpc": "2.0", "id": 1, "method": "get_block_reward_at", "params": { "height": height } }).to_string();
+        let (supply_0, supply_2) = {
+            let storage = blockchain.get_storage().read().await;
+            (storage.get_supply_at_topo_height(0).await.unwrap(), storage.get_supply_at_topo_height(2).await.unwrap())
+        };
+
+        // Past heights use the supply stored before their block
+        let response = handler.handle_request(request(0).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["supply"], 0);
+        let response = handler.handle_request(request(1).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["supply"], supply_0);
+
+        // Next height is projected from the current supply
+        let response = handler.handle_request(request(3).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["supply"], supply_2);
+        assert_eq!(response["result"]["reward"], get_block_reward(supply_2));
+
+        assert!(handler.handle_request(request(2 + MAX_BLOCK_REWARD_PROJECTION + 1).as_bytes()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_executor_not_found() {
+        use crate::core::testing::test_blockchain;
+
+        let blockchain = test_blockchain().await;
+        let mut handler = RPCHandler::new(blockchain);
+        register_methods(&mut handler, false);
+
+        // Not an invalid params error so clients can tell it apart
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "get_transaction_executor", "params": { "hash": Hash::zero() } }).to_string();
+        let err = handler.handle_request(request.as_bytes()).await.unwrap_err();
+        assert_eq!(err.to_json()["error"]["code"], TX_NOT_FOUND_ERROR_CODE);
+    }
+
+    #[tokio::test]
+    async fn test_get_many_partial_failures() {
+        use crate::core::testing::test_blockchain;
+
+        let blockchain = test_blockchain().await;
+        let mut handler = RPCHandler::new(blockchain);
+        register_methods(&mut handler, false);
+
+        let request = |queries: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": "get_many", "params": { "queries": queries } }).to_string();
+        let response = handler.handle_request(request(json!({
+            "info": { "method": "get_info" },
+            "genesis": { "method": "get_block_at_topoheight", "params": { "topoheight": 0 } },
+            "missing": { "method": "get_block_at_topoheight", "params": { "topoheight": 1000 } },
+            "mempool": { "method": "get_mempool_summary" },
+            "unknown": { "method": "submit_block" }
+        })).as_bytes()).await.unwrap();
+
+        // Results are in the requested order, failures are only reported for their sub-query
+        let results: GetManyResult = serde_json::from_value(response["result"].clone()).unwrap();
+        assert_eq!(results.keys().map(String::as_str).collect::<Vec<_>>(), ["info", "genesis", "missing", "mempool", "unknown"]);
+        assert!(matches!(&results["info"], ManyQueryResult::Result(info) if info["topoheight"] == 0));
+        assert!(matches!(&results["genesis"], ManyQueryResult::Result(block) if block["topoheight"] == 0));
+        assert!(matches!(&results["missing"], ManyQueryResult::Error(_)));
+        assert!(matches!(&results["mempool"], ManyQueryResult::Result(mempool) if mempool["size"] == 0));
+        assert!(matches!(&results["unknown"], ManyQueryResult::Error(e) if e.code == -32602));
+
+        // The sub-queries count is capped
+        let queries = (0..=MAX_GET_MANY_QUERIES).map(|i| (i.to_string(), json!({ "method": "get_info" }))).collect::<serde_json::Map<_, _>>();
+        assert!(handler.handle_request(request(Value::Object(queries)).as_bytes()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_nonce_at_topoheight() {
+        use xelis_common::{account::VersionedNonce, crypto::KeyPair};
+        use crate::core::{storage::NonceProvider, testing::test_blockchain};
+
+        let blockchain = test_blockchain().await;
+
+        let key = KeyPair::new().get_public_key().compress();
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            storage.set_nonce_at_topoheight(&key, 0, &VersionedNonce::new(1, None)).await.unwrap();
+            storage.set_nonce_at_topoheight(&key, 5, &VersionedNonce::new(2, Some(0))).await.unwrap();
+            storage.set_last_nonce_to(&key, 9, &VersionedNonce::new(3, Some(5))).await.unwrap();
+
+            // Previous versions are followed until one is at or below the topoheight
+            let nonce_at = |topoheight| storage.get_nonce_at_maximum_topoheight(&key, topoheight);
+            assert_eq!(nonce_at(4).await.unwrap().map(|(topo, v)| (topo, v.get_nonce())), Some((0, 1)));
+            assert_eq!(nonce_at(7).await.unwrap().map(|(topo, v)| (topo, v.get_nonce())), Some((5, 2)));
+            assert_eq!(nonce_at(20).await.unwrap().map(|(topo, v)| (topo, v.get_nonce())), Some((9, 3)));
+        }
+
+        let address = key.as_address(false);
+        let unknown = KeyPair::new().get_public_key().compress().as_address(false);
+        let mut handler = RPCHandler::new(blockchain);
+        register_methods(&mut handler, false);
+        let request = |method: &str, params: Value| json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+
+        let response = handler.handle_request(request("get_nonce_at_topoheight", json!({ "address": address, "topoheight": 0 })).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["topoheight"], 0);
+        assert_eq!(response["result"]["nonce"], 1);
+        assert!(handler.handle_request(request("get_nonce_at_topoheight", json!({ "address": unknown, "topoheight": 0 })).as_bytes()).await.is_err());
+
+        let response = handler.handle_request(request("has_nonce", json!({ "address": address })).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["exist"], true);
+        let response = handler.handle_request(request("has_nonce", json!({ "address": unknown, "topoheight": 0 })).as_bytes()).await.unwrap();
+        assert_eq!(response["result"]["exist"], false);
+    }
+}
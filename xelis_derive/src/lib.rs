@@ -0,0 +1,108 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input,
+    spanned::Spanned,
+    Data,
+    DeriveInput,
+    Error,
+    Field,
+    Fields,
+    Index
+};
+
+// Derive the xelis_common Serializer trait for a struct
+// Fields are written and read in their declaration order,
+// each field type must already implement Serializer
+//
+// A field marked with #[serializer(skip)] is not written
+// and is set to its Default value when reading
+#[proc_macro_derive(Serializer, attributes(serializer))]
+pub fn derive_serializer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+// Check if the field has the #[serializer(skip)] attribute
+fn is_skipped(field: &Field) -> Result<bool, Error> {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("serializer")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported serializer attribute, expected skip"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new(input.span(), "Serializer can only be derived for structs"))
+    };
+
+    let mut writes = Vec::new();
+    let mut sizes = Vec::new();
+    let mut reads = Vec::new();
+    let mut inits = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let skip = is_skipped(field)?;
+        let ty = &field.ty;
+        // accessor used on self, and the local variable used when reading
+        // locals are prefixed so a field can't shadow the reader
+        let var = format_ident!("__field_{}", i);
+        let member = match &field.ident {
+            Some(ident) => {
+                inits.push(quote!(#ident: #var));
+                quote!(#ident)
+            },
+            None => {
+                inits.push(quote!(#var));
+                let index = Index::from(i);
+                quote!(#index)
+            }
+        };
+
+        if skip {
+            reads.push(quote!(let #var: #ty = ::core::default::Default::default();));
+        } else {
+            writes.push(quote!(::xelis_common::serializer::Serializer::write(&self.#member, writer);));
+            sizes.push(quote!(::xelis_common::serializer::Serializer::size(&self.#member)));
+            reads.push(quote!(let #var = <#ty as ::xelis_common::serializer::Serializer>::read(reader)?;));
+        }
+    }
+
+    let construct = match fields {
+        Fields::Named(_) => quote!(Self { #(#inits),* }),
+        Fields::Unnamed(_) => quote!(Self(#(#inits),*)),
+        Fields::Unit => quote!(Self)
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::xelis_common::serializer::Serializer for #name #ty_generics #where_clause {
+            fn write(&self, writer: &mut ::xelis_common::serializer::Writer) {
+                #(#writes)*
+            }
+
+            fn read(reader: &mut ::xelis_common::serializer::Reader) -> ::core::result::Result<Self, ::xelis_common::serializer::ReaderError> {
+                #(#reads)*
+                ::core::result::Result::Ok(#construct)
+            }
+
+            fn size(&self) -> usize {
+                0 #(+ #sizes)*
+            }
+        }
+    })
+}
@@ -1,2 +1,4 @@
 // daemon address by default when no specified
-pub const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:8080";
\ No newline at end of file
+pub const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:8080";
+// Seconds without a new job before switching to the next daemon
+pub const DEFAULT_JOB_TIMEOUT: u64 = 30;
\ No newline at end of file
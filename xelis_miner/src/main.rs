@@ -2,15 +2,18 @@ pub mod config;
 
 use std::{
     time::Duration,
-    sync::atomic::{
-        AtomicU64,
-        Ordering,
-        AtomicUsize,
-        AtomicBool
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+            AtomicUsize,
+            AtomicBool
+        },
+        Arc
     },
     thread
 };
-use crate::config::DEFAULT_DAEMON_ADDRESS;
+use crate::config::{DEFAULT_DAEMON_ADDRESS, DEFAULT_JOB_TIMEOUT};
 use fern::colors::Color;
 use futures_util::{StreamExt, SinkExt};
 use serde::{Serialize, Deserialize};
@@ -36,13 +39,15 @@ use xelis_common::{
         SubmitMinerWorkParams,
     },
     async_handler,
-    block::MinerWork,
+    block::{AuthorshipKey, MinerWork},
     config::VERSION,
     crypto::{
         Address,
         Hash,
         Hashable,
-        ScratchPad
+        PowBackend,
+        PowHasher,
+        ScratchPadPool
     },
     difficulty::{
         check_difficulty_against_target,
@@ -87,8 +92,13 @@ pub struct MinerConfig {
     #[clap(short, long)]
     miner_address: Option<Address>,
     /// Daemon address to connect to for mining
-    #[clap(long, default_value_t = String::from(DEFAULT_DAEMON_ADDRESS))]
-    daemon_address: String,
+    /// It can be set several times, the miner switches to the next one
+    /// when the connection is lost or no job is received in time.
+    #[clap(long, default_value = DEFAULT_DAEMON_ADDRESS)]
+    daemon_address: Vec<String>,
+    /// Seconds without receiving a new job before switching to the next daemon (0 = disabled)
+    #[clap(long, default_value_t = DEFAULT_JOB_TIMEOUT)]
+    job_timeout: u64,
     /// Set log level
     #[clap(long, value_enum, default_value_t = LogLevel::Info)]
     log_level: LogLevel,
@@ -98,6 +108,10 @@ pub struct MinerConfig {
     /// Iterations to run the benchmark
     #[clap(long, default_value_t = 100)]
     iterations: usize,
+    /// POW hashing implementation to use
+    /// The reference one is slower and only useful to compare the results.
+    #[clap(long, value_enum, default_value_t = PowBackend::Optimized)]
+    pow_backend: PowBackend,
     /// Disable the log file
     #[clap(long)]
     disable_file_logging: bool,
@@ -112,6 +126,10 @@ pub struct MinerConfig {
     /// You will not be able to write CLI commands in it or to have an updated prompt
     #[clap(long)]
     disable_interactive_mode: bool,
+    /// Command history file path
+    /// By default, it is saved next to the log file
+    #[clap(long)]
+    history_file: Option<String>,
     /// Log filename
     /// 
     /// By default filename is xelis-miner.log.
@@ -131,22 +149,43 @@ pub struct MinerConfig {
     num_threads: Option<u16>,
     /// Worker name to be displayed on daemon side
     #[clap(short, long, default_value_t = String::from("default"))]
-    worker: String
+    worker: String,
+    /// Authorship key given by the authorship_key wallet command
+    /// Each block found is tagged with it so the wallet can later prove it mined it.
+    /// Only for solo mining, keep it private: it links all your tagged blocks together.
+    #[clap(long)]
+    authorship_key: Option<String>
 }
 
 #[derive(Clone)]
 enum ThreadNotification<'a> {
-    NewJob(MinerWork<'a>, Difficulty, u64), // block work, difficulty, height
+    NewJob(MinerWork<'a>, Difficulty, Option<Difficulty>, u64), // block work, difficulty, share difficulty, height
     WebSocketClosed, // WebSocket connection has been closed
     Exit // all threads must stop
 }
 
+// Work found by a mining thread to send to the daemon
+enum FoundWork<'a> {
+    Block(MinerWork<'a>),
+    Share(MinerWork<'a>)
+}
+
+impl<'a> FoundWork<'a> {
+    fn get_work(&self) -> &MinerWork<'a> {
+        match self {
+            Self::Block(work) | Self::Share(work) => work
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")] 
 pub enum SocketMessage {
     NewJob(GetMinerWorkResult),
     BlockAccepted,
-    BlockRejected(String)
+    BlockRejected(String),
+    ShareAccepted,
+    ShareRejected(String)
 }
 
 static WEBSOCKET_CONNECTED: AtomicBool = AtomicBool::new(false);
@@ -154,9 +193,21 @@ static CURRENT_TOPO_HEIGHT: AtomicU64 = AtomicU64::new(0);
 static BLOCKS_FOUND: AtomicUsize = AtomicUsize::new(0);
 static BLOCKS_REJECTED: AtomicUsize = AtomicUsize::new(0);
 static HASHRATE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+static SHARES_ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+static SHARES_REJECTED: AtomicUsize = AtomicUsize::new(0);
+// Sum of the difficulty of all shares accepted
+static SHARES_WORK: AtomicU64 = AtomicU64::new(0);
+// Share difficulty of the current job, 0 if shares are disabled
+static CURRENT_SHARE_DIFFICULTY: AtomicU64 = AtomicU64::new(0);
+// How many times we switched to another daemon
+static RECONNECTIONS: AtomicUsize = AtomicUsize::new(0);
 
 lazy_static! {
+    // Daemon address currently used
+    static ref CURRENT_DAEMON: Mutex<String> = Mutex::new(String::new());
     static ref HASHRATE_LAST_TIME: Mutex<Instant> = Mutex::new(Instant::now());
+    // When we started to submit shares, used for the effective hashrate
+    static ref SHARES_START_TIME: Mutex<Option<Instant>> = Mutex::new(None);
 }
 
 // After how many iterations we update the timestamp of the block to avoid too much CPU usage 
@@ -165,7 +216,7 @@ const UPDATE_EVERY_NONCE: u64 = 10;
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     let config: MinerConfig = MinerConfig::parse();
-    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode)?;
+    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode, config.history_file.clone())?;
 
     let detected_threads = match thread::available_parallelism() {
         Ok(value) => value.get() as u16,
@@ -183,15 +234,25 @@ async fn main() -> Result<()> {
 
     info!("Total threads to use: {} (detected: {})", threads, detected_threads);
 
+    let hasher = config.pow_backend.get_hasher();
+    info!("POW backend: {}", hasher.name());
+
     if config.benchmark {
         info!("Benchmark mode enabled, miner will try up to {} threads", threads);
-        benchmark(threads as usize, config.iterations);
+        benchmark(threads as usize, config.iterations, hasher, Arc::new(ScratchPadPool::new(threads as usize)));
         info!("Benchmark finished");
         return Ok(())
     }
 
     let address = config.miner_address.ok_or_else(|| Error::msg("No miner address specified"))?;
     info!("Miner address: {}", address);    
+    let authorship_key = config.authorship_key.map(AuthorshipKey::from_hex)
+        .transpose()
+        .context("Invalid authorship key")?;
+    if authorship_key.is_some() {
+        info!("Blocks found will be tagged with the authorship key");
+    }
+
     if threads != detected_threads {
         warn!("Attention, the number of threads used may not be optimal, recommended is: {}", detected_threads);
     }
@@ -199,16 +260,17 @@ async fn main() -> Result<()> {
     // broadcast channel to send new jobs / exit command to all threads
     let (sender, _) = broadcast::channel::<ThreadNotification>(threads as usize);
     // mpsc channel to send from threads to the "communication" task.
-    let (block_sender, block_receiver) = mpsc::channel::<MinerWork>(threads as usize);
+    let (block_sender, block_receiver) = mpsc::channel::<FoundWork>(threads as usize);
+    let scratch_pads = Arc::new(ScratchPadPool::new(threads as usize));
     for id in 0..threads {
         debug!("Starting thread #{}", id);
-        if let Err(e) = start_thread(id, sender.subscribe(), block_sender.clone()) {
+        if let Err(e) = start_thread(id, sender.subscribe(), block_sender.clone(), hasher, scratch_pads.clone(), authorship_key.clone()) {
             error!("Error while creating Mining Thread #{}: {}", id, e);
         }
     }
 
     // start communication task
-    let task = spawn_task("communication", communication_task(config.daemon_address, sender.clone(), block_receiver, address, config.worker));
+    let task = spawn_task("communication", communication_task(config.daemon_address, config.job_timeout, sender.clone(), block_receiver, address, config.worker));
 
     if let Err(e) = run_prompt(prompt).await {
         error!("Error on running prompt: {}", e);
@@ -225,7 +287,7 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn benchmark(threads: usize, iterations: usize) {
+fn benchmark(threads: usize, iterations: usize, hasher: &'static dyn PowHasher, scratch_pads: Arc<ScratchPadPool>) {
     info!("{0: <10} | {1: <10} | {2: <16} | {3: <13} | {4: <13}", "Threads", "Total Time", "Total Iterations", "Time/PoW (ms)", "Hashrate");
 
     for bench in 1..=threads {
@@ -233,10 +295,12 @@ fn benchmark(threads: usize, iterations: usize) {
         let mut handles = vec![];
         for _ in 0..bench {
             let mut job = MinerWork::new(Hash::zero(), get_current_time_in_millis());
+            let scratch_pads = scratch_pads.clone();
             let handle = thread::spawn(move || {
-                let mut scratch_pad = ScratchPad::default();
+                // Reuse the scratch pads allocated by the previous rounds
+                let mut scratch_pad = scratch_pads.get();
                 for _ in 0..iterations {
-                    let _ = job.get_pow_hash(&mut scratch_pad).unwrap();
+                    let _ = job.get_pow_hash(hasher, &mut scratch_pad).unwrap();
                     job.increase_nonce().unwrap();
                     if job.nonce() % UPDATE_EVERY_NONCE == 0 {
                         job.set_timestamp(get_current_time_in_millis()).unwrap();
@@ -259,18 +323,34 @@ fn benchmark(threads: usize, iterations: usize) {
 // It maintains a WebSocket connection with the daemon and notify all threads when it receive a new job.
 // Its also the task who have the job to send directly the new block found by one of the threads.
 // This allow mining threads to only focus on mining and receiving jobs through memory channels.
-async fn communication_task(daemon_address: String, job_sender: broadcast::Sender<ThreadNotification<'_>>, mut block_receiver: mpsc::Receiver<MinerWork<'_>>, address: Address, worker: String) {
+// If several daemons are set, it switches to the next one when the connection is lost or the job times out.
+async fn communication_task(daemon_addresses: Vec<String>, job_timeout: u64, job_sender: broadcast::Sender<ThreadNotification<'_>>, mut block_receiver: mpsc::Receiver<FoundWork<'_>>, address: Address, worker: String) {
     info!("Starting communication task");
-    let daemon_address = sanitize_daemon_address(&daemon_address);
+    let daemon_addresses: Vec<String> = daemon_addresses.iter().map(|v| sanitize_daemon_address(v)).collect();
+    let job_timeout = if job_timeout > 0 {
+        Some(Duration::from_secs(job_timeout))
+    } else {
+        None
+    };
+
+    let mut index = 0;
+    // count of failed connections in a row, we wait only after trying all daemons
+    let mut failures = 0;
     'main: loop {
+        let daemon_address = &daemon_addresses[index % daemon_addresses.len()];
+        if failures > 0 && failures % daemon_addresses.len() == 0 {
+            warn!("Trying to connect to WebSocket again in 10 seconds...");
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        }
+
         info!("Trying to connect to {}", daemon_address);
         let client = match connect_async(format!("{}/getwork/{}/{}", daemon_address, address.to_string(), worker)).await {
             Ok((client, response)) => {
                 let status = response.status();
                 if status.is_server_error() || status.is_client_error() {
                     error!("Error while connecting to {}, got an unexpected response: {}", daemon_address, status.as_str());
-                    warn!("Trying to connect to WebSocket again in 10 seconds...");
-                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    failures += 1;
+                    index += 1;
                     continue 'main;
                 }
                 client
@@ -287,19 +367,37 @@ async fn communication_task(daemon_address: String, job_sender: broadcast::Sende
                     error!("Error while connecting to {}: {}", daemon_address, e);
                 }
 
-                warn!("Trying to connect to WebSocket again in 10 seconds...");
-                tokio::time::sleep(Duration::from_secs(10)).await;
+                failures += 1;
+                index += 1;
                 continue 'main;
             }
         };
+        failures = 0;
         WEBSOCKET_CONNECTED.store(true, Ordering::SeqCst);
+        *CURRENT_DAEMON.lock().await = daemon_address.clone();
         info!("Connected successfully to {}", daemon_address);
+
+        // Work found while we were disconnected, submitted only if its template is still current
+        let mut pending = Vec::new();
+        while let Ok(found) = block_receiver.try_recv() {
+            pending.push(found);
+        }
+
+        let mut current_job: Option<Hash> = None;
+        let mut last_job = Instant::now();
         let (mut write, mut read) = client.split();
         loop {
+            let timeout = async {
+                match job_timeout {
+                    Some(timeout) => tokio::time::sleep_until(last_job + timeout).await,
+                    None => std::future::pending().await
+                }
+            };
+
             select! {
                 Some(message) = read.next() => { // read all messages from daemon
                     debug!("Received message from daemon: {:?}", message);
-                    match handle_websocket_message(message, &job_sender).await {
+                    match handle_websocket_message(message, &job_sender, &mut current_job, &mut last_job).await {
                         Ok(exit) => {
                             if exit {
                                 debug!("Exiting communication task");
@@ -311,15 +409,32 @@ async fn communication_task(daemon_address: String, job_sender: broadcast::Sende
                             break;
                         }
                     }
+
+                    if let Some(job) = current_job.as_ref() {
+                        for found in pending.drain(..) {
+                            let work = found.get_work();
+                            if work.get_header_work_hash() != job {
+                                warn!("Discarding work found while disconnected, its template {} is not current anymore", work.get_header_work_hash());
+                                continue;
+                            }
+
+                            info!("Submitting work found while disconnected");
+                            if let Err(e) = submit_work(&mut write, found).await {
+                                error!("Error while sending the work found to the daemon: {}", e);
+                            }
+                        }
+                    }
                 },
-                Some(work) = block_receiver.recv() => { // send all valid blocks found to the daemon
-                    info!("submitting new block found...");
-                    let submit = serde_json::json!(SubmitMinerWorkParams { miner_work: work.to_hex() }).to_string();
-                    if let Err(e) = write.send(Message::Text(submit)).await {
-                        error!("Error while sending the block found to the daemon: {}", e);
+                Some(found) = block_receiver.recv() => { // send all valid blocks and shares found to the daemon
+                    if let Err(e) = submit_work(&mut write, found).await {
+                        error!("Error while sending the work found to the daemon: {}", e);
                         break;
                     }
-                    debug!("Block found has been sent to daemon");
+                    debug!("Work found has been sent to daemon");
+                },
+                _ = timeout => {
+                    warn!("No job received from {} since {} seconds", daemon_address, last_job.elapsed().as_secs());
+                    break;
                 }
             }
         }
@@ -329,12 +444,38 @@ async fn communication_task(daemon_address: String, job_sender: broadcast::Sende
             error!("Error while sending WebSocketClosed message to threads");
         }
 
-        warn!("Trying to connect to WebSocket again in 10 seconds...");
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        // switch to the next daemon
+        index += 1;
+        RECONNECTIONS.fetch_add(1, Ordering::SeqCst);
+        if daemon_addresses.len() > 1 {
+            info!("Switching to {}", daemon_addresses[index % daemon_addresses.len()]);
+        } else {
+            warn!("Trying to connect to WebSocket again in 10 seconds...");
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        }
     }
 }
 
-async fn handle_websocket_message(message: Result<Message, TungsteniteError>, job_sender: &broadcast::Sender<ThreadNotification<'_>>) -> Result<bool, Error> {
+// Send a block or share found to the daemon
+async fn submit_work<S>(write: &mut S, found: FoundWork<'_>) -> Result<(), TungsteniteError>
+where
+    S: SinkExt<Message, Error = TungsteniteError> + Unpin
+{
+    let work = match found {
+        FoundWork::Block(work) => {
+            info!("submitting new block found...");
+            work
+        },
+        FoundWork::Share(work) => {
+            debug!("submitting new share found...");
+            work
+        }
+    };
+    let submit = serde_json::json!(SubmitMinerWorkParams { miner_work: work.to_hex() }).to_string();
+    write.send(Message::Text(submit)).await
+}
+
+async fn handle_websocket_message(message: Result<Message, TungsteniteError>, job_sender: &broadcast::Sender<ThreadNotification<'_>>, current_job: &mut Option<Hash>, last_job: &mut Instant) -> Result<bool, Error> {
     match message? {
         Message::Text(text) => {
             debug!("new message from daemon: {}", text);
@@ -342,9 +483,22 @@ async fn handle_websocket_message(message: Result<Message, TungsteniteError>, jo
                 SocketMessage::NewJob(job) => {
                     info!("New job received: difficulty {} at height {}", format_difficulty(job.difficulty), job.height);
                     let block = MinerWork::from_hex(job.template).context("Error while decoding new job received from daemon")?;
+                    *current_job = Some(block.get_header_work_hash().clone());
+                    *last_job = Instant::now();
                     CURRENT_TOPO_HEIGHT.store(job.topoheight, Ordering::SeqCst);
 
-                    if let Err(e) = job_sender.send(ThreadNotification::NewJob(block, job.difficulty, job.height)) {
+                    if let Some(share_difficulty) = job.share_difficulty {
+                        debug!("Share difficulty: {}", format_difficulty(share_difficulty));
+                        CURRENT_SHARE_DIFFICULTY.store(u64::from(share_difficulty), Ordering::SeqCst);
+                        let mut start = SHARES_START_TIME.lock().await;
+                        if start.is_none() {
+                            *start = Some(Instant::now());
+                        }
+                    } else {
+                        CURRENT_SHARE_DIFFICULTY.store(0, Ordering::SeqCst);
+                    }
+
+                    if let Err(e) = job_sender.send(ThreadNotification::NewJob(block, job.difficulty, job.share_difficulty, job.height)) {
                         error!("Error while sending new job to threads: {}", e);
                     }
                 },
@@ -355,6 +509,17 @@ async fn handle_websocket_message(message: Result<Message, TungsteniteError>, jo
                 SocketMessage::BlockRejected(err) => {
                     BLOCKS_REJECTED.fetch_add(1, Ordering::SeqCst);
                     error!("Block submitted has been rejected by network: {}", err);
+                },
+                SocketMessage::ShareAccepted => {
+                    SHARES_ACCEPTED.fetch_add(1, Ordering::SeqCst);
+                    let share_difficulty = CURRENT_SHARE_DIFFICULTY.load(Ordering::SeqCst);
+                    // Can't fail, the closure always returns a value
+                    let _ = SHARES_WORK.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |work| Some(work.saturating_add(share_difficulty)));
+                    debug!("Share submitted has been accepted");
+                },
+                SocketMessage::ShareRejected(err) => {
+                    SHARES_REJECTED.fetch_add(1, Ordering::SeqCst);
+                    warn!("Share submitted has been rejected: {}", err);
                 }
             }
         },
@@ -376,13 +541,14 @@ async fn handle_websocket_message(message: Result<Message, TungsteniteError>, jo
     Ok(false)
 }
 
-fn start_thread(id: u16, mut job_receiver: broadcast::Receiver<ThreadNotification<'static>>, block_sender: mpsc::Sender<MinerWork<'static>>) -> Result<(), Error> {
+fn start_thread(id: u16, mut job_receiver: broadcast::Receiver<ThreadNotification<'static>>, block_sender: mpsc::Sender<FoundWork<'static>>, hasher: &'static dyn PowHasher, scratch_pads: Arc<ScratchPadPool>, authorship_key: Option<AuthorshipKey>) -> Result<(), Error> {
     let builder = thread::Builder::new().name(format!("Mining Thread #{}", id));
     builder.spawn(move || {
         let mut job: MinerWork;
         let mut hash: Hash;
 
-        let mut scratch_pad = ScratchPad::default();
+        // Kept for all the jobs of this thread
+        let mut scratch_pad = scratch_pads.get();
         info!("Mining Thread #{}: started", id);
         'main: loop {
             let message = match job_receiver.blocking_recv() {
@@ -409,12 +575,15 @@ fn start_thread(id: u16, mut job_receiver: broadcast::Receiver<ThreadNotificatio
                     info!("Exiting Mining Thread #{}...", id);
                     break 'main;
                 },
-                ThreadNotification::NewJob(new_job, expected_difficulty, height) => {
+                ThreadNotification::NewJob(new_job, expected_difficulty, share_difficulty, height) => {
                     debug!("Mining Thread #{} received a new job", id);
                     job = new_job;
                     // set thread id in extra nonce for more work spread between threads
                     // u16 support up to 65535 threads
                     job.set_thread_id_u16(id);
+                    if let Some(key) = authorship_key.as_ref() {
+                        job.set_authorship_tag(&key.get_tag(height));
+                    }
 
                     let difficulty_target = match compute_difficulty_target(&expected_difficulty) {
                         Ok(value) => value,
@@ -424,9 +593,24 @@ fn start_thread(id: u16, mut job_receiver: broadcast::Receiver<ThreadNotificatio
                         }
                     };
 
+                    let share_target = match share_difficulty.as_ref().map(compute_difficulty_target).transpose() {
+                        Ok(value) => value,
+                        Err(e) => {
+                            error!("Mining Thread #{}: error on share difficulty target computation: {}", id, e);
+                            None
+                        }
+                    };
+
                     // Solve block
-                    hash = job.get_pow_hash(&mut scratch_pad).unwrap();
+                    hash = job.get_pow_hash(hasher, &mut scratch_pad).unwrap();
                     while !check_difficulty_against_target(&hash, &difficulty_target) {
+                        // Report the share without blocking the mining
+                        if share_target.as_ref().is_some_and(|target| check_difficulty_against_target(&hash, target)) {
+                            if let Err(e) = block_sender.try_send(FoundWork::Share(job.clone())) {
+                                debug!("Mining Thread #{}: share dropped: {}", id, e);
+                            }
+                        }
+
                         job.increase_nonce().unwrap();
                         // check if we have a new job pending
                         // Only update every N iterations to avoid too much CPU usage
@@ -438,13 +622,13 @@ fn start_thread(id: u16, mut job_receiver: broadcast::Receiver<ThreadNotificatio
                             HASHRATE_COUNTER.fetch_add(UPDATE_EVERY_NONCE as usize, Ordering::SeqCst);
                         }
 
-                        hash = job.get_pow_hash(&mut scratch_pad).unwrap();
+                        hash = job.get_pow_hash(hasher, &mut scratch_pad).unwrap();
                     }
 
                     // compute the reference hash for easier finding of the block
                     let block_hash = job.hash();
                     info!("Thread #{}: block {} found at height {} with difficulty {}", id, block_hash, height, format_difficulty(difficulty_from_hash(&hash)));
-                    if let Err(_) = block_sender.blocking_send(job) {
+                    if let Err(_) = block_sender.blocking_send(FoundWork::Block(job)) {
                         error!("Mining Thread #{}: error while sending block found with hash {}", id, block_hash);
                         continue 'main;
                     }
@@ -477,11 +661,35 @@ async fn run_prompt(prompt: ShareablePrompt) -> Result<()> {
             prompt.colorize_str(Color::Yellow, "Rejected"),
             prompt.colorize_string(Color::Green, &format!("{}", BLOCKS_REJECTED.load(Ordering::SeqCst))),
         );
+        let shares = if CURRENT_SHARE_DIFFICULTY.load(Ordering::SeqCst) > 0 {
+            let elapsed = SHARES_START_TIME.lock().await.map_or(0, |start| start.elapsed().as_millis());
+            let effective_hashrate = if elapsed > 0 {
+                SHARES_WORK.load(Ordering::SeqCst) as f64 * 1000f64 / elapsed as f64
+            } else {
+                0f64
+            };
+
+            format!(
+                " | {}: {} | {}",
+                prompt.colorize_str(Color::Yellow, "Shares"),
+                prompt.colorize_string(Color::Green, &format!("{}/{}", SHARES_ACCEPTED.load(Ordering::SeqCst), SHARES_REJECTED.load(Ordering::SeqCst))),
+                prompt.colorize_string(Color::Green, &format!("{} (effective)", format_hashrate(effective_hashrate)))
+            )
+        } else {
+            String::new()
+        };
         let status = if WEBSOCKET_CONNECTED.load(Ordering::SeqCst) {
-            prompt.colorize_str(Color::Green, "Online")
+            let daemon = CURRENT_DAEMON.lock().await;
+            prompt.colorize_string(Color::Green, &format!("Online ({})", daemon))
         } else {
             prompt.colorize_str(Color::Red, "Offline")
         };
+        let reconnections = RECONNECTIONS.load(Ordering::SeqCst);
+        let status = if reconnections > 0 {
+            format!("{} | {}: {}", status, prompt.colorize_str(Color::Yellow, "Reconnects"), prompt.colorize_string(Color::Green, &reconnections.to_string()))
+        } else {
+            status
+        };
         let hashrate = {
             let mut last_time = HASHRATE_LAST_TIME.lock().await;
             let counter = HASHRATE_COUNTER.swap(0, Ordering::SeqCst);
@@ -494,12 +702,13 @@ async fn run_prompt(prompt: ShareablePrompt) -> Result<()> {
 
         Ok(
             format!(
-                "{} | {} | {} | {} | {} | {} {} ",
+                "{} | {} | {} | {} | {}{} | {} {} ",
                 prompt.colorize_str(Color::Blue, "XELIS Miner"),
                 topoheight_str,
                 blocks_found,
                 blocks_rejected,
                 hashrate,
+                shares,
                 status,
                 prompt.colorize_str(Color::BrightBlack, ">>")
             )
@@ -15,7 +15,7 @@ pub use self::{
         XSWDPermissionHandler,
         XSWDNodeMethodHandler
     },
-    rpc::register_methods as register_rpc_methods
+    rpc::{register_methods as register_rpc_methods, WalletRpcAuthorizer}
 };
 
 pub enum APIServer<W>
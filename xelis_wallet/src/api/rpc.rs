@@ -1,21 +1,32 @@
 use std::{sync::Arc, borrow::Cow};
+use actix_web_httpauth::headers::authorization::{Basic, Bearer, Scheme};
 use anyhow::Context as AnyContext;
+use async_trait::async_trait;
+use subtle::ConstantTimeEq;
 use xelis_common::{
     api::{
         wallet::{
+            AddApiKeyParams,
+            ApiKeyPermission,
             BuildTransactionParams,
+            ConfirmDraftParams,
+            CreateDraftParams,
+            DraftParams,
             DeleteParams,
             EstimateFeesParams,
+            GenerateIntegratedAddressParams,
             GetAddressParams,
             GetAssetPrecisionParams,
             GetBalanceParams,
             GetMatchingKeysParams,
+            GetPrivacyReportParams,
             GetTransactionParams,
             GetValueFromKeyParams,
             HasKeyParams,
             ListTransactionsParams,
             QueryDBParams,
             RescanParams,
+            RevokeApiKeyParams,
             StoreParams,
             TransactionResponse,
             SetOnlineModeParams,
@@ -28,22 +39,25 @@ use xelis_common::{
     async_handler,
     config::{VERSION, XELIS_ASSET},
     context::Context,
-    crypto::Hashable,
+    crypto::{Hashable, PaymentId},
     rpc_server::{
         parse_params,
         websocket::WebSocketSessionShared,
+        ClientAuthorization,
         InternalRpcError,
+        RpcAuthorizer,
         RPCHandler
     },
     serializer::Serializer,
-    transaction::builder::FeeBuilder
+    transaction::builder::{FeeBuilder, TransactionTypeBuilder}
 };
 use serde_json::{Value, json};
 use crate::{
+    config::DEFAULT_DRAFT_EXPIRATION,
     wallet::Wallet,
     error::WalletError
 };
-use super::xswd::XSWDWebSocketHandler;
+use super::{xswd::XSWDWebSocketHandler, AuthConfig};
 use log::{info, warn};
 
 // Register all RPC methods
@@ -55,6 +69,7 @@ pub fn register_methods(handler: &mut RPCHandler<Arc<Wallet>>) {
     handler.register_method("get_topoheight", async_handler!(get_topoheight));
     handler.register_method("get_address", async_handler!(get_address));
     handler.register_method("split_address", async_handler!(split_address));
+    handler.register_method("generate_integrated_address", async_handler!(generate_integrated_address));
     handler.register_method("rescan", async_handler!(rescan));
     handler.register_method("get_balance", async_handler!(get_balance));
     handler.register_method("has_balance", async_handler!(has_balance));
@@ -68,6 +83,7 @@ pub fn register_methods(handler: &mut RPCHandler<Arc<Wallet>>) {
     handler.register_method("set_offline_mode", async_handler!(set_offline_mode));
     handler.register_method("sign_data", async_handler!(sign_data));
     handler.register_method("estimate_fees", async_handler!(estimate_fees));
+    handler.register_method("get_privacy_report", async_handler!(get_privacy_report));
 
     // These functions allow to have an encrypted DB directly in the wallet storage
     // You can retrieve keys, values, have differents trees, and store values
@@ -79,6 +95,83 @@ pub fn register_methods(handler: &mut RPCHandler<Arc<Wallet>>) {
     handler.register_method("delete", async_handler!(delete));
     handler.register_method("has_key", async_handler!(has_key));
     handler.register_method("query_db", async_handler!(query_db));
+
+    // Transactions built only after a delay
+    handler.register_method("create_draft", async_handler!(create_draft));
+    handler.register_method("list_drafts", async_handler!(list_drafts));
+    handler.register_method("cancel_draft", async_handler!(cancel_draft));
+    handler.register_method("confirm_draft", async_handler!(confirm_draft));
+
+    // Manage the API keys of the RPC Server
+    handler.register_method("add_api_key", async_handler!(add_api_key));
+    handler.register_method("revoke_api_key", async_handler!(revoke_api_key));
+    handler.register_method("list_api_keys", async_handler!(list_api_keys));
+}
+
+// Permission required to call a RPC method
+// Unknown methods require the admin permission to not reveal which ones exist
+fn get_method_permission(method: &str) -> ApiKeyPermission {
+    match method {
+        "get_version" | "get_network" | "get_nonce" | "get_topoheight" | "get_address"
+        | "split_address" | "generate_integrated_address" | "get_balance" | "has_balance" | "get_tracked_assets"
+        | "get_asset_precision" | "get_transaction" | "list_transactions" | "is_online"
+        | "estimate_fees" | "get_privacy_report" | "get_matching_keys" | "get_value_from_key" | "has_key"
+        | "query_db" | "list_drafts" | "subscribe" | "unsubscribe" => ApiKeyPermission::Read,
+        "build_transaction" | "sign_data" | "create_draft" | "cancel_draft"
+        | "confirm_draft" => ApiKeyPermission::Spend,
+        _ => ApiKeyPermission::Admin
+    }
+}
+
+// Check the credentials of each request sent to the RPC Server
+// The configured username/password has a full access
+// An API key sent as a Bearer token is limited to its permission
+pub struct WalletRpcAuthorizer {
+    auth_config: Option<AuthConfig>
+}
+
+impl WalletRpcAuthorizer {
+    pub fn new(auth_config: Option<AuthConfig>) -> Self {
+        Self {
+            auth_config
+        }
+    }
+}
+
+#[async_trait]
+impl RpcAuthorizer for WalletRpcAuthorizer {
+    async fn authorize(&self, context: &Context, method: &str) -> Result<(), InternalRpcError> {
+        let wallet: &Arc<Wallet> = context.get()?;
+        // Server started without any credential
+        if self.auth_config.is_none() && !wallet.has_api_keys().await? {
+            return Ok(())
+        }
+
+        let Some(ClientAuthorization(value)) = context.get_optional::<ClientAuthorization>() else {
+            return Err(InternalRpcError::Unauthorized)
+        };
+
+        let permission = if let Ok(bearer) = Bearer::parse(value) {
+            wallet.get_api_key_permission(bearer.token()).await?
+        } else {
+            match (Basic::parse(value), self.auth_config.as_ref()) {
+                (Ok(basic), Some(config)) if is_valid_credentials(&basic, config) => Some(ApiKeyPermission::Admin),
+                _ => None
+            }
+        };
+
+        match permission {
+            Some(permission) if permission >= get_method_permission(method) => Ok(()),
+            _ => Err(InternalRpcError::Unauthorized)
+        }
+    }
+}
+
+// Compared in constant time to not leak the credentials through the response time
+fn is_valid_credentials(basic: &Basic, config: &AuthConfig) -> bool {
+    let username = basic.user_id().as_bytes().ct_eq(config.username.as_bytes());
+    let password = basic.password().unwrap_or_default().as_bytes().ct_eq(config.password.as_bytes());
+    (username & password).into()
 }
 
 // Retrieve the version of the wallet
@@ -130,7 +223,7 @@ async fn get_address(context: &Context, body: Value) -> Result<Value, InternalRp
 
     let wallet: &Arc<Wallet> = context.get()?;
     let address = if let Some(data) = params.integrated_data {
-        wallet.get_address_with(data)
+        wallet.generate_integrated_address(data)?
     } else {
         wallet.get_address()
     };
@@ -138,6 +231,16 @@ async fn get_address(context: &Context, body: Value) -> Result<Value, InternalRp
     Ok(json!(address))
 }
 
+// Generate an integrated address embedding the data provided
+async fn generate_integrated_address(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GenerateIntegratedAddressParams = parse_params(body)?;
+
+    let wallet: &Arc<Wallet> = context.get()?;
+    let address = wallet.generate_integrated_address(params.data)?;
+
+    Ok(json!(address))
+}
+
 // Split an integrated address into its address and data
 async fn split_address(_: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: SplitAddressParams = parse_params(body)?;
@@ -145,10 +248,12 @@ async fn split_address(_: &Context, body: Value) -> Result<Value, InternalRpcErr
 
     let (data, address) = address.extract_data();
     let integrated_data = data.ok_or(InternalRpcError::InvalidParams("Address is not an integrated address"))?;
+    let payment_id = PaymentId::from_data(&integrated_data);
 
     Ok(json!(SplitAddressResult {
         address,
-        integrated_data
+        integrated_data,
+        payment_id
     }))
 }
 
@@ -236,11 +341,16 @@ async fn build_transaction(context: &Context, body: Value) -> Result<Value, Inte
     // The lock is kept until the TX is applied to the storage
     // So even if we have few requests building a TX, they wait for the previous one to be applied
     let mut storage = wallet.get_storage().write().await;
-    let (mut state, tx) = wallet.create_transaction_with_storage(&storage, params.tx_type, params.fee.unwrap_or(FeeBuilder::Multiplier(1f64))).await?;
+    let fee = params.fee.unwrap_or(FeeBuilder::Multiplier(1f64));
+    let (mut state, tx) = match params.tx_type {
+        // Burns are verified against our balances before being built
+        TransactionTypeBuilder::Burn(payload) => wallet.create_burn_transaction_with_storage(&storage, payload.asset, payload.amount, fee).await?,
+        tx_type => wallet.create_transaction_with_storage(&storage, tx_type, fee).await?
+    };
 
     // if requested, broadcast the TX ourself
     if params.broadcast {
-        if let Err(e) = wallet.submit_transaction(&tx).await {
+        if let Err(e) = wallet.submit_transaction_with_storage(&mut storage, &tx).await {
             warn!("Clearing Tx cache because of broadcasting error: {}", e);
             storage.clear_tx_cache();
             return Err(e.into());
@@ -264,6 +374,68 @@ async fn build_transaction(context: &Context, body: Value) -> Result<Value, Inte
     }))
 }
 
+// Save a transaction to build and sign only after the delay
+async fn create_draft(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: CreateDraftParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let fee = params.fee.unwrap_or(FeeBuilder::Multiplier(1f64));
+    let expiration = params.expiration.unwrap_or(DEFAULT_DRAFT_EXPIRATION);
+    let draft = wallet.create_draft(params.tx_type, fee, params.delay, expiration).await?;
+    Ok(json!(draft))
+}
+
+// List all drafts not expired
+async fn list_drafts(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+
+    let wallet: &Arc<Wallet> = context.get()?;
+    let drafts = wallet.get_drafts().await?;
+    Ok(json!(drafts))
+}
+
+async fn cancel_draft(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: DraftParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.cancel_draft(params.id).await?;
+    Ok(json!(true))
+}
+
+// Build and sign the transaction of a draft once its delay elapsed
+async fn confirm_draft(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: ConfirmDraftParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    if !wallet.is_online().await && params.broadcast {
+        return Err(WalletError::NotOnlineMode)?
+    }
+
+    if !params.broadcast && !params.tx_as_hex {
+        return Err(InternalRpcError::InvalidParams("Invalid params, should either be broadcasted, or returned in hex format"))
+    }
+
+    let tx = wallet.confirm_draft(params.id).await?;
+    if params.broadcast {
+        if let Err(e) = wallet.submit_transaction(&tx).await {
+            warn!("Clearing Tx cache because of broadcasting error: {}", e);
+            wallet.get_storage().write().await.clear_tx_cache();
+            return Err(e.into());
+        }
+    }
+
+    Ok(json!(TransactionResponse {
+        tx_as_hex: if params.tx_as_hex {
+            Some(hex::encode(tx.to_bytes()))
+        } else {
+            None
+        },
+        inner: DataHash {
+            hash: Cow::Owned(tx.hash()),
+            data: Cow::Owned(tx)
+        }
+    }))
+}
+
 // Estimate fees for a transaction
 async fn estimate_fees(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: EstimateFeesParams = parse_params(body)?;
@@ -273,6 +445,22 @@ async fn estimate_fees(context: &Context, body: Value) -> Result<Value, Internal
     Ok(json!(fees))
 }
 
+// Build a transaction and report what it reveals
+// Nothing is applied to the storage and the TX is never broadcasted
+async fn get_privacy_report(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetPrivacyReportParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let storage = wallet.get_storage().read().await;
+    let fee = params.fee.unwrap_or(FeeBuilder::Multiplier(1f64));
+    let (_, tx) = match params.tx_type {
+        TransactionTypeBuilder::Burn(payload) => wallet.create_burn_transaction_with_storage(&storage, payload.asset, payload.amount, fee).await?,
+        tx_type => wallet.create_transaction_with_storage(&storage, tx_type, fee).await?
+    };
+    let report = wallet.get_privacy_report(&storage, &tx).await?;
+
+    Ok(json!(report))
+}
+
 // List transactions from the wallet storage
 async fn list_transactions(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: ListTransactionsParams = parse_params(body)?;
@@ -434,4 +622,125 @@ async fn query_db(context: &Context, body: Value) -> Result<Value, InternalRpcEr
     let storage = wallet.get_storage().read().await;
     let result = storage.query_db(&tree, params.key, params.value, params.return_on_first)?;
     Ok(json!(result))
-}
\ No newline at end of file
+}
+
+// API keys can't be managed by an application connected through XSWD
+fn check_api_keys_context(context: &Context) -> Result<(), InternalRpcError> {
+    if context.has::<WebSocketSessionShared<XSWDWebSocketHandler<Arc<Wallet>>>>() {
+        return Err(InternalRpcError::Unauthorized)
+    }
+    Ok(())
+}
+
+// Create a new API key for the RPC Server
+// The key is only returned here, the wallet keeps only its hash
+async fn add_api_key(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    check_api_keys_context(context)?;
+    let params: AddApiKeyParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let key = wallet.add_api_key(params.name, params.permission).await?;
+    Ok(json!(key))
+}
+
+// Revoke an API key using its name
+async fn revoke_api_key(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    check_api_keys_context(context)?;
+    let params: RevokeApiKeyParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.revoke_api_key(&params.name).await?;
+    Ok(json!(true))
+}
+
+// List all API keys with their permission
+async fn list_api_keys(context: &Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+
+    check_api_keys_context(context)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let keys = wallet.get_api_keys().await?;
+    Ok(json!(keys))
+}
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{HeaderValue, TryIntoHeaderValue};
+    use crate::testing::test_wallet;
+    use super::*;
+
+    fn bearer(key: &str) -> Option<HeaderValue> {
+        Some(Bearer::new(key.to_owned()).try_into_value().unwrap())
+    }
+
+    fn basic(username: &str, password: &str) -> Option<HeaderValue> {
+        Some(Basic::new(username.to_owned(), Some(password.to_owned())).try_into_value().unwrap())
+    }
+
+    async fn is_authorized(authorizer: &WalletRpcAuthorizer, wallet: &Arc<Wallet>, authorization: Option<HeaderValue>, method: &str) -> bool {
+        let mut context = Context::new();
+        context.store(wallet.clone());
+        if let Some(value) = authorization {
+            context.store(ClientAuthorization(value));
+        }
+        authorizer.authorize(&context, method).await.is_ok()
+    }
+
+    #[test]
+    fn test_method_permission() {
+        assert_eq!(get_method_permission("get_balance"), ApiKeyPermission::Read);
+        assert_eq!(get_method_permission("subscribe"), ApiKeyPermission::Read);
+        assert_eq!(get_method_permission("build_transaction"), ApiKeyPermission::Spend);
+        assert_eq!(get_method_permission("confirm_draft"), ApiKeyPermission::Spend);
+        assert_eq!(get_method_permission("add_api_key"), ApiKeyPermission::Admin);
+        assert_eq!(get_method_permission("rescan"), ApiKeyPermission::Admin);
+        assert_eq!(get_method_permission("unknown_method"), ApiKeyPermission::Admin);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_permissions() {
+        let wallet = test_wallet("api-keys");
+        let authorizer = WalletRpcAuthorizer::new(None);
+
+        // Open while no key exists
+        assert!(is_authorized(&authorizer, &wallet, None, "add_api_key").await);
+
+        let read = wallet.add_api_key("read".to_owned(), ApiKeyPermission::Read).await.unwrap();
+        let spend = wallet.add_api_key("spend".to_owned(), ApiKeyPermission::Spend).await.unwrap();
+        let admin = wallet.add_api_key("admin".to_owned(), ApiKeyPermission::Admin).await.unwrap();
+
+        // Read, spend and admin methods
+        for (key, expected) in [(&read, [true, false, false]), (&spend, [true, true, false]), (&admin, [true, true, true])] {
+            for (method, expected) in ["get_balance", "build_transaction", "revoke_api_key"].into_iter().zip(expected) {
+                assert_eq!(is_authorized(&authorizer, &wallet, bearer(key), method).await, expected, "{}", method);
+            }
+        }
+
+        // Missing, unknown and revoked keys
+        assert!(!is_authorized(&authorizer, &wallet, None, "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, bearer("invalid"), "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, basic("read", &read), "get_balance").await);
+        wallet.revoke_api_key("read").await.unwrap();
+        assert!(!is_authorized(&authorizer, &wallet, bearer(&read), "get_balance").await);
+        assert!(is_authorized(&authorizer, &wallet, bearer(&spend), "get_balance").await);
+    }
+
+    #[tokio::test]
+    async fn test_basic_credentials() {
+        let wallet = test_wallet("basic-credentials");
+        let authorizer = WalletRpcAuthorizer::new(Some(AuthConfig {
+            username: "user".to_owned(),
+            password: "password".to_owned()
+        }));
+
+        assert!(is_authorized(&authorizer, &wallet, basic("user", "password"), "add_api_key").await);
+        assert!(!is_authorized(&authorizer, &wallet, basic("user", "wrong"), "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, basic("other", "password"), "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, basic("user", ""), "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, None, "get_balance").await);
+
+        // API keys are accepted next to the credentials
+        let key = wallet.add_api_key("read".to_owned(), ApiKeyPermission::Read).await.unwrap();
+        assert!(is_authorized(&authorizer, &wallet, bearer(&key), "get_balance").await);
+        assert!(!is_authorized(&authorizer, &wallet, bearer(&key), "add_api_key").await);
+    }
+}
@@ -1,9 +1,5 @@
 use std::sync::Arc;
 
-use actix_web_httpauth::{
-    middleware::HttpAuthentication,
-    extractors::basic::BasicAuth
-};
 use anyhow::Result;
 use log::{info, warn};
 use tokio::sync::Mutex;
@@ -31,13 +27,12 @@ use actix_web::{
     HttpServer,
     web::{Data, self},
     App,
-    dev::{ServerHandle, ServiceRequest},
-    Error,
-    error::{ErrorUnauthorized, ErrorBadGateway, ErrorBadRequest}
+    dev::ServerHandle
 };
 
 pub type WalletRpcServerShared<W> = Arc<WalletRpcServer<W>>;
 
+// Username/password giving a full access to the RPC Server
 pub struct AuthConfig {
     pub username: String,
     pub password: String
@@ -48,29 +43,26 @@ where
     W: Clone + Send + Sync + 'static
 {
     handle: Mutex<Option<ServerHandle>>,
-    websocket: WebSocketServerShared<EventWebSocketHandler<W, NotifyEvent>>,
-    auth_config: Option<AuthConfig>
+    websocket: WebSocketServerShared<EventWebSocketHandler<W, NotifyEvent>>
 }
 
 impl<W> WalletRpcServer<W>
 where
     W: Clone + Send + Sync + 'static
 {
-    pub async fn new(bind_address: String, rpc_handler: RPCHandler<W>, auth_config: Option<AuthConfig>) -> Result<WalletRpcServerShared<W>> {
+    // Credentials are checked for each request by the authorizer of the RPC handler
+    pub async fn new(bind_address: String, rpc_handler: RPCHandler<W>) -> Result<WalletRpcServerShared<W>> {
         let server = Arc::new(Self {
             handle: Mutex::new(None),
-            websocket: WebSocketServer::new(EventWebSocketHandler::new(rpc_handler)),
-            auth_config
+            websocket: WebSocketServer::new(EventWebSocketHandler::new(rpc_handler))
         });
 
         {
             let clone = Arc::clone(&server);
             let http_server = HttpServer::new(move || {
                 let server = Arc::clone(&clone);
-                let auth = HttpAuthentication::basic(auth::<W>);
                 App::new()
                     .app_data(Data::from(server))
-                    .wrap(auth)
                     // WebSocket support
                     .route("/json_rpc", web::get().to(websocket::<EventWebSocketHandler<W, NotifyEvent>, Self>))
                     // HTTP support
@@ -93,19 +85,6 @@ where
         Ok(server)
     }
 
-    async fn authenticate(&self, credentials: BasicAuth) -> Result<(), Error> {
-        if let Some(config) = &self.auth_config {
-            let user = credentials.user_id();
-            let password = credentials.password().ok_or(ErrorBadRequest("Missing password"))?;
-
-            if *config.username != *user || *config.password != *password {
-                return Err(ErrorUnauthorized("Username/password are invalid"))
-            }
-        }
-
-        Ok(())
-    }
-
     pub async fn stop(&self) {
         info!("Stopping RPC Server...");
         let mut handle = self.handle.lock().await;
@@ -136,20 +115,6 @@ where
     }
 }
 
-async fn auth<W>(request: ServiceRequest, credentials: BasicAuth) -> Result<ServiceRequest, (Error, ServiceRequest)>
-where
-    W: Clone + Send + Sync + 'static
-{
-    let data: Option<&Data<WalletRpcServer<W>>> = request.app_data();
-    match data {
-        Some(server) => match server.authenticate(credentials).await {
-            Ok(_) => Ok(request),
-            Err(e) => Err((e, request))
-        },
-        None => Err((ErrorBadGateway("RPC Server was not found"), request))
-    }
-}
-
 #[get("/")]
 async fn index() -> impl Responder {
     HttpResponse::Ok().body(format!("Hello, world!\nRunning on: {}", config::VERSION))
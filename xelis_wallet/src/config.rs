@@ -6,16 +6,54 @@ pub const XSWD_BIND_ADDRESS: &str = "0.0.0.0:44325";
 pub const PASSWORD_HASH_SIZE: usize = 32;
 pub const SALT_SIZE: usize = 32;
 pub const KEY_SIZE: usize = 32;
+// Random bytes of a RPC API key
+pub const API_KEY_SIZE: usize = 32;
+// Time in ms during which a draft can be confirmed once unlocked
+pub const DEFAULT_DRAFT_EXPIRATION: u64 = 7 * 24 * 60 * 60 * 1000;
+// Time in ms to answer an address ownership challenge
+pub const OWNERSHIP_CHALLENGE_EXPIRATION: u64 = 24 * 60 * 60 * 1000;
+// Time in ms after which the ownership of an address must be verified again
+pub const OWNERSHIP_VERIFICATION_EXPIRATION: u64 = 90 * 24 * 60 * 60 * 1000;
+// Transfers of at least this amount (in units of the asset) to an unverified address are warned
+pub const LARGE_TRANSFER_AMOUNT: u64 = 100;
+// Sending again to a destination used in the last topoheights is reported as linkable
+pub const RECENT_DESTINATION_WINDOW: u64 = 5760;
 
 // daemon address by default when no specified
 pub const DEFAULT_DAEMON_ADDRESS: &str = "http://127.0.0.1:8080";
 // Auto reconnect interval in seconds for Network Handler
 pub const AUTO_RECONNECT_INTERVAL: u64 = 5;
+// Interval in seconds between two background syncs with the daemon
+pub const DEFAULT_SYNC_INTERVAL: u64 = 15;
+// Time in seconds after which a hook command is killed
+pub const DEFAULT_HOOK_TIMEOUT: u64 = 30;
+pub const MAX_HOOK_TIMEOUT: u64 = 10 * 60;
+// Hooks allowed to run at the same time
+pub const DEFAULT_HOOKS_MAX_CONCURRENT: usize = 4;
+// Time in ms a submitted transaction can be missing from chain and mempool before being submitted again
+pub const REBROADCAST_GRACE_PERIOD: u64 = 2 * 60 * 1000;
+// Rebroadcasts allowed for the same transaction before giving up
+pub const MAX_REBROADCAST_ATTEMPTS: u32 = 3;
+
+// Argon2id parameters used to derive the key from the password
+// 15 MB, 16 iterations
+pub const PASSWORD_MEMORY_COST: u32 = 15 * 1000;
+pub const PASSWORD_ITERATIONS: u32 = 16;
+pub const PASSWORD_PARALLELISM: u32 = 1;
+
+// Topoheights behind the daemon after which the doctor warns or fails
+pub const DOCTOR_SYNC_GAP_WARN: u64 = 10;
+pub const DOCTOR_SYNC_GAP_FAIL: u64 = 1000;
+// Topoheights after which a pending transaction is reported as stuck
+pub const DOCTOR_PENDING_AGE_WARN: u64 = 100;
+// Time in ms a daemon block can be ahead of our clock
+pub const DOCTOR_CLOCK_SKEW_TOLERANCE: u64 = 30 * 1000;
+// Time in ms after which the daemon top block is reported as old
+pub const DOCTOR_TOP_BLOCK_AGE_WARN: u64 = 10 * 60 * 1000;
 
 lazy_static! {
     pub static ref PASSWORD_ALGORITHM: Argon2<'static> = {
-        // 15 MB, 16 iterations
-        let params = Params::new(15 * 1000, 16, 1,  Some(PASSWORD_HASH_SIZE)).unwrap();
+        let params = Params::new(PASSWORD_MEMORY_COST, PASSWORD_ITERATIONS, PASSWORD_PARALLELISM, Some(PASSWORD_HASH_SIZE)).unwrap();
         Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
     };
 }
\ No newline at end of file
@@ -1,45 +1,98 @@
-use std::{borrow::Cow, collections::HashSet};
+use std::{borrow::Cow, collections::HashSet, marker::PhantomData};
 
-use anyhow::{Context, Result};
-use serde::Serialize;
+use anyhow::{anyhow, Context, Result};
+use futures_util::{stream, Stream, TryStreamExt};
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use tokio::sync::broadcast;
 use xelis_common::{
     json_rpc::{
         WebSocketJsonRPCClient,
         WebSocketJsonRPCClientImpl,
+        JsonRPCError,
+        UNIX_SOCKET_PREFIX,
         JsonRPCResult,
-        EventReceiver
+        EventReceiver,
+        METHOD_NOT_FOUND_CODE,
+        ConnectionState,
+        RequestKind
+    },
+    api::binary::{
+        decode_binary,
+        BinaryAccountHistoryEntry,
+        BinaryBlock,
+        BinaryPayload,
+        ResponseEncoding
     },
     api::daemon::{
+        BlockTemplateUpdatedEvent,
+        FeePolicyUpdatedEvent,
+        EstimateFeesParams,
+        EstimateFeesResult,
         GetBalanceResult,
         GetBalanceAtTopoHeightParams,
         GetBalanceParams,
         GetInfoResult,
+        GetP2pStatsResult,
         SubmitTransactionParams,
+        ValidateTransactionResult,
+        SubmitBlockParams,
+        SubmitBlockResult,
         BlockResponse,
         GetBlockAtTopoHeightParams,
+        GetBlockByHashParams,
+        GetBlocksRangeParams,
+        GetManyParams,
+        GetManyResult,
+        GetStatsParams,
+        ManyQuery,
+        ManyQueryResult,
+        QueryError,
         GetTransactionParams,
+        TransactionResponse,
         GetNonceParams,
         GetNonceResult,
+        GetNonceAtTopoHeightParams,
+        HasNonceParams,
+        HasNonceResult,
         GetAssetsParams,
+        GetAccountsParams,
+        PagedResult,
         IsTxExecutedInBlockParams,
+        GetTransactionExecutorParams,
+        GetTransactionExecutorResult,
+        TX_IN_MEMPOOL_ERROR_CODE,
+        TX_NOT_EXECUTED_ERROR_CODE,
+        TX_NOT_FOUND_ERROR_CODE,
+        TX_REJECTED_ERROR_CODE,
+        ACCOUNT_NOT_IN_MEMPOOL_ERROR_CODE,
         NotifyEvent,
         NewBlockEvent,
         BlockOrderedEvent,
+        BlockOrphanedEvent,
         StableHeightChangedEvent,
         TransactionAddedInMempoolEvent,
+        TransactionRemovedFromMempoolEvent,
         GetAccountAssetsParams,
+        GetAccountAssetsWithBalancesParams,
+        AccountAssetWithBalance,
         GetAssetParams,
         GetMempoolCacheParams,
         GetMempoolCacheResult,
         IsAccountRegisteredParams,
-        TransactionOrphanedEvent
+        TransactionOrphanedEvent,
+        GetAccountHistoryParams,
+        AccountHistoryEntry,
+        GetAccountBalanceHistoryParams,
+        AccountBalanceHistoryEntry,
+        GetTopoHeightRangeParams
     },
     account::VersionedBalance,
     crypto::{
         Address,
-        Hash
+        Hash,
+        Hashable
     },
     transaction::Transaction,
     serializer::Serializer,
@@ -48,19 +101,133 @@ use xelis_common::{
         AssetData
     }
 };
+use crate::error::WalletError;
+
+// Compose the sub-queries of a get_many request
+// Each result is returned under the label given here
+#[derive(Default)]
+pub struct ManyQueryBuilder {
+    queries: IndexMap<String, ManyQuery<'static>>
+}
+
+impl ManyQueryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(mut self, label: impl Into<String>, query: ManyQuery<'static>) -> Self {
+        self.queries.insert(label.into(), query);
+        self
+    }
+
+    pub fn info(self, label: impl Into<String>) -> Self {
+        self.with(label, ManyQuery::GetInfo)
+    }
+
+    pub fn block_at_topoheight(self, label: impl Into<String>, topoheight: u64, include_txs: bool) -> Self {
+        self.with(label, ManyQuery::GetBlockAtTopoheight(GetBlockAtTopoHeightParams { topoheight, include_txs }))
+    }
+
+    pub fn balance(self, label: impl Into<String>, address: Address, asset: Hash) -> Self {
+        self.with(label, ManyQuery::GetBalance(GetBalanceParams {
+            address: Cow::Owned(address),
+            asset: Cow::Owned(asset)
+        }))
+    }
+
+    pub fn mempool_summary(self, label: impl Into<String>) -> Self {
+        self.with(label, ManyQuery::GetMempoolSummary)
+    }
+
+    pub fn stats(self, label: impl Into<String>, blocks: Option<u64>) -> Self {
+        self.with(label, ManyQuery::GetStats(GetStatsParams { blocks }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+pub struct ManyQueryResponse {
+    results: GetManyResult
+}
+
+impl ManyQueryResponse {
+    // Typed result of a sub-query, its error if it failed
+    pub fn get<T: DeserializeOwned>(&self, label: &str) -> Result<T> {
+        match self.results.get(label) {
+            Some(ManyQueryResult::Result(value)) => serde_json::from_value(value.clone()).with_context(|| format!("Error while parsing result of '{}'", label)),
+            Some(ManyQueryResult::Error(e)) => Err(anyhow!("Sub-query '{}' failed: {}", label, e.message)),
+            None => Err(anyhow!("No sub-query named '{}'", label))
+        }
+    }
+
+    pub fn get_error(&self, label: &str) -> Option<&QueryError> {
+        match self.results.get(label) {
+            Some(ManyQueryResult::Error(e)) => Some(e),
+            _ => None
+        }
+    }
+
+    pub fn into_inner(self) -> GetManyResult {
+        self.results
+    }
+}
+
+// Result of get_transaction_executor
+pub enum TransactionExecutor {
+    Executed(GetTransactionExecutorResult<'static>),
+    // TX is in mempool, not executed or unknown
+    NotExecuted,
+    // Daemon doesn't support the method
+    Unsupported
+}
 
 pub struct DaemonAPI {
     client: WebSocketJsonRPCClient<NotifyEvent>,
+    // Encoding requested for the heavy methods
+    encoding: ResponseEncoding
 }
 
 impl DaemonAPI {
     pub async fn new(daemon_address: String) -> Result<Self> {
-        let client = WebSocketJsonRPCClientImpl::new(daemon_address).await?;
+        Self::with_endpoints(vec![daemon_address]).await
+    }
+
+    // Connect to the first reachable daemon endpoint
+    // In case of disconnection, endpoints are retried in order
+    pub async fn with_endpoints(endpoints: Vec<String>) -> Result<Self> {
+        Self::with_encoding(endpoints, ResponseEncoding::Json).await
+    }
+
+    // Connect to a daemon started with --rpc-unix-socket
+    pub async fn with_unix_socket(path: &str) -> Result<Self> {
+        Self::with_endpoints(vec![format!("{}{}", UNIX_SOCKET_PREFIX, path)]).await
+    }
+
+    // Same as with_endpoints but request the heavy results in the given encoding
+    // Daemons not supporting it will still answer in JSON
+    pub async fn with_encoding(endpoints: Vec<String>, encoding: ResponseEncoding) -> Result<Self> {
+        let client = WebSocketJsonRPCClientImpl::with_targets(endpoints).await?;
         Ok(Self {
-            client
+            client,
+            encoding
         })
     }
 
+    pub fn get_encoding(&self) -> ResponseEncoding {
+        self.encoding
+    }
+
+    // Get the endpoint currently used
+    pub fn get_endpoint(&self) -> &String {
+        self.client.get_target()
+    }
+
     // is the websocket connection alive
     pub fn is_online(&self) -> bool {
         self.client.is_online()
@@ -86,8 +253,31 @@ impl DaemonAPI {
         self.client.on_connection_lost().await
     }
 
+    // On connection state changed, including a switch to another endpoint
+    pub async fn on_connection_changed(&self) -> broadcast::Receiver<ConnectionState> {
+        self.client.on_connection_changed().await
+    }
+
     pub async fn call<P: Serialize>(&self, method: &String, params: &P) -> JsonRPCResult<Value> {
-        self.client.call_with(method.as_str(), params).await
+        self.client.call_with_kind(method.as_str(), RequestKind::from_method(method), params).await
+    }
+
+    // Send several requests in a single message, each result can fail independently
+    async fn call_batch_as<P: Serialize, R: DeserializeOwned>(&self, requests: &[(&str, P)]) -> Result<Vec<Result<R>>> {
+        let results = self.client.call_batch(requests).await.context("Error while sending batch request")?;
+        Ok(results.into_iter().map(|res| -> Result<R> { Ok(serde_json::from_value(res?)?) }).collect())
+    }
+
+    // Parse a result that may be in binary or in JSON
+    fn parse_encoded_result<B: Serializer, R: DeserializeOwned>(value: Value, map: impl Fn(B, bool) -> R) -> Result<Vec<R>> {
+        match value {
+            Value::String(data) => {
+                let payload: BinaryPayload<B> = decode_binary(&data).context("Error while decoding binary response")?;
+                let mainnet = payload.mainnet;
+                Ok(payload.entries.into_iter().map(|entry| map(entry, mainnet)).collect())
+            },
+            value => Ok(serde_json::from_value(value).context("Error while parsing JSON response")?)
+        }
     }
 
     pub async fn on_new_block_event(&self) -> Result<EventReceiver<NewBlockEvent>> {
@@ -100,6 +290,11 @@ impl DaemonAPI {
         Ok(receiver)
     }
 
+    pub async fn on_block_orphaned_event(&self) -> Result<EventReceiver<BlockOrphanedEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::BlockOrphaned).await?;
+        Ok(receiver)
+    }
+
     pub async fn on_transaction_orphaned_event(&self) -> Result<EventReceiver<TransactionOrphanedEvent>> {
         let receiver = self.client.subscribe_event(NotifyEvent::TransactionOrphaned).await?;
         Ok(receiver)
@@ -115,55 +310,163 @@ impl DaemonAPI {
         Ok(receiver)
     }
 
+    pub async fn on_transaction_removed_from_mempool_event(&self) -> Result<EventReceiver<TransactionRemovedFromMempoolEvent<'static>>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::TransactionRemovedFromMempool).await?;
+        Ok(receiver)
+    }
+
+    pub async fn on_fee_policy_updated_event(&self) -> Result<EventReceiver<FeePolicyUpdatedEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::FeePolicyUpdated).await?;
+        Ok(receiver)
+    }
+
+    pub async fn on_block_template_updated_event(&self) -> Result<EventReceiver<BlockTemplateUpdatedEvent<'static>>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::BlockTemplateUpdated).await?;
+        Ok(receiver)
+    }
+
+    // Stop receiving an event, its receivers are closed
+    pub async fn unsubscribe_event(&self, event: &NotifyEvent) -> Result<()> {
+        self.client.unsubscribe_event(event).await.context("Error while unsubscribing from event")?;
+        Ok(())
+    }
+
     pub async fn get_version(&self) -> Result<String> {
-        let version = self.client.call("get_version").await.context("Error while retrieving version from daemon")?;
+        let version = self.client.call_with_kind("get_version", RequestKind::ReadOnly, &Value::Null).await.context("Error while retrieving version from daemon")?;
         Ok(version)
     }
 
     pub async fn get_info(&self) -> Result<GetInfoResult> {
-        let info = self.client.call("get_info").await.context("Error while retrieving info from chain")?;
+        let info = self.client.call_with_kind("get_info", RequestKind::ReadOnly, &Value::Null).await.context("Error while retrieving info from chain")?;
         Ok(info)
     }
 
+    pub async fn get_p2p_stats(&self) -> Result<GetP2pStatsResult> {
+        let stats = self.client.call_with_kind("get_p2p_stats", RequestKind::ReadOnly, &Value::Null).await.context("Error while retrieving P2P stats from daemon")?;
+        Ok(stats)
+    }
+
     pub async fn get_asset(&self, asset: &Hash) -> Result<AssetData> {
-        let assets = self.client.call_with("get_asset", &GetAssetParams {
+        let assets = self.client.call_with_kind("get_asset", RequestKind::ReadOnly, &GetAssetParams {
             asset: Cow::Borrowed(asset)
         }).await.context("Error while retrieving asset data")?;
         Ok(assets)
     }
 
     pub async fn get_account_assets(&self, address: &Address) -> Result<HashSet<Hash>> {
-        let assets = self.client.call_with("get_account_assets", &GetAccountAssetsParams {
+        let assets = self.client.call_with_kind("get_account_assets", RequestKind::ReadOnly, &GetAccountAssetsParams {
             address: Cow::Borrowed(address)
         }).await.context("Error while retrieving account assets")?;
         Ok(assets)
     }
 
+    pub async fn get_account_assets_with_balances(&self, address: &Address, skip: Option<usize>, maximum: Option<usize>) -> Result<Vec<AccountAssetWithBalance<'static>>> {
+        let assets = self.client.call_with_kind("get_account_assets_with_balances", RequestKind::ReadOnly, &GetAccountAssetsWithBalancesParams {
+            address: Cow::Borrowed(address),
+            skip,
+            maximum
+        }).await.context("Error while retrieving account assets with balances")?;
+        Ok(assets)
+    }
+
     pub async fn count_assets(&self) -> Result<usize> {
-        let count = self.client.call("count_assets").await?;
+        let count = self.client.call_with_kind("count_assets", RequestKind::ReadOnly, &Value::Null).await?;
         Ok(count)
     }
 
     pub async fn get_assets(&self, skip: Option<usize>, maximum: Option<usize>, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> Result<Vec<AssetWithData>> {
-        let assets = self.client.call_with("get_assets", &GetAssetsParams {
+        let assets = self.client.call_with_kind("get_assets", RequestKind::ReadOnly, &GetAssetsParams {
             maximum,
             skip,
             minimum_topoheight,
-            maximum_topoheight
+            maximum_topoheight,
+            cursor: None,
+            limit: None
         }).await?;
         Ok(assets)
     }
 
+    // Fetch a page of assets, cursor is the next_cursor of the previous page
+    pub async fn get_assets_page(&self, cursor: Option<String>, limit: usize, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> Result<PagedResult<AssetWithData>> {
+        let page = self.client.call_with_kind("get_assets", RequestKind::ReadOnly, &GetAssetsParams {
+            maximum: None,
+            skip: None,
+            minimum_topoheight,
+            maximum_topoheight,
+            cursor,
+            limit: Some(limit)
+        }).await.context("Error while retrieving assets page")?;
+        Ok(page)
+    }
+
+    // Fetch a page of accounts, cursor is the next_cursor of the previous page
+    pub async fn get_accounts_page(&self, cursor: Option<String>, limit: usize, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> Result<PagedResult<Address>> {
+        let page = self.client.call_with_kind("get_accounts", RequestKind::ReadOnly, &GetAccountsParams {
+            maximum: None,
+            skip: None,
+            minimum_topoheight,
+            maximum_topoheight,
+            cursor,
+            limit: Some(limit)
+        }).await.context("Error while retrieving accounts page")?;
+        Ok(page)
+    }
+
+    // Stream all registered assets without buffering them, limit is the size of each page requested
+    pub fn stream_assets(&self, limit: usize, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> impl Stream<Item = Result<AssetWithData>> + '_ {
+        PageIterator::new(self, "get_assets", limit, minimum_topoheight, maximum_topoheight).into_stream()
+    }
+
+    // Stream all accounts without buffering them, limit is the size of each page requested
+    pub fn stream_accounts(&self, limit: usize, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> impl Stream<Item = Result<Address>> + '_ {
+        PageIterator::new(self, "get_accounts", limit, minimum_topoheight, maximum_topoheight).into_stream()
+    }
+
+    // Iterate over all registered assets, one page per call to next_page
+    pub fn iter_assets(&self, limit: usize) -> PageIterator<'_, AssetWithData> {
+        PageIterator::new(self, "get_assets", limit, None, None)
+    }
+
+    // Iterate over all accounts, one page per call to next_page
+    pub fn iter_accounts(&self, limit: usize) -> PageIterator<'_, Address> {
+        PageIterator::new(self, "get_accounts", limit, None, None)
+    }
+
     pub async fn get_balance(&self, address: &Address, asset: &Hash) -> Result<GetBalanceResult> {
-        let balance = self.client.call_with("get_balance", &GetBalanceParams {
+        let balance = self.client.call_with_kind("get_balance", RequestKind::ReadOnly, &GetBalanceParams {
             address: Cow::Borrowed(address),
             asset: Cow::Borrowed(asset),
         }).await.context("Error while retrieving balance")?;
         Ok(balance)
     }
 
+    pub async fn get_account_history(&self, address: &Address, asset: &Hash, skip: Option<usize>, maximum: Option<usize>, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> Result<Vec<AccountHistoryEntry>> {
+        let value = self.client.call_with_kind("get_account_history", RequestKind::ReadOnly, &GetAccountHistoryParams {
+            address: Cow::Borrowed(address),
+            asset: asset.clone(),
+            minimum_topoheight,
+            maximum_topoheight,
+            skip,
+            maximum,
+            encoding: self.encoding
+        }).await.context("Error while retrieving account history")?;
+        Self::parse_encoded_result(value, BinaryAccountHistoryEntry::into_entry)
+    }
+
+    // Balance changes of the account aggregated per topoheight, without the transactions details
+    pub async fn get_account_balance_history(&self, address: &Address, asset: &Hash, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>, maximum: Option<usize>) -> Result<Vec<AccountBalanceHistoryEntry<'static>>> {
+        let history = self.client.call_with_kind("get_account_balance_history", RequestKind::ReadOnly, &GetAccountBalanceHistoryParams {
+            address: Cow::Borrowed(address),
+            asset: asset.clone(),
+            minimum_topoheight,
+            maximum_topoheight,
+            maximum
+        }).await.context("Error while retrieving account balance history")?;
+        Ok(history)
+    }
+
     pub async fn get_balance_at_topoheight(&self, address: &Address, asset: &Hash, topoheight: u64) -> Result<VersionedBalance> {
-        let balance = self.client.call_with("get_balance_at_topoheight", &GetBalanceAtTopoHeightParams {
+        let balance = self.client.call_with_kind("get_balance_at_topoheight", RequestKind::ReadOnly, &GetBalanceAtTopoHeightParams {
             topoheight,
             asset: Cow::Borrowed(asset),
             address: Cow::Borrowed(address)
@@ -172,62 +475,282 @@ impl DaemonAPI {
     }
 
     pub async fn get_block_at_topoheight(&self, topoheight: u64) -> Result<BlockResponse> {
-        let block = self.client.call_with("get_block_at_topoheight", &GetBlockAtTopoHeightParams {
+        let block = self.client.call_with_kind("get_block_at_topoheight", RequestKind::ReadOnly, &GetBlockAtTopoHeightParams {
             topoheight,
             include_txs: false
         }).await.context(format!("Error while fetching block at topoheight {}", topoheight))?;
         Ok(block)
     }
 
+    pub async fn get_block_by_hash(&self, hash: &Hash) -> Result<BlockResponse> {
+        let block = self.client.call_with_kind("get_block_by_hash", RequestKind::ReadOnly, &GetBlockByHashParams {
+            hash: Cow::Borrowed(hash),
+            include_txs: false
+        }).await.context(format!("Error while fetching block {}", hash))?;
+        Ok(block)
+    }
+
     pub async fn get_block_with_txs_at_topoheight(&self, topoheight: u64) -> Result<BlockResponse> {
-        let block = self.client.call_with("get_block_at_topoheight", &GetBlockAtTopoHeightParams {
+        let block = self.client.call_with_kind("get_block_at_topoheight", RequestKind::ReadOnly, &GetBlockAtTopoHeightParams {
             topoheight,
             include_txs: true
         }).await.context(format!("Error while fetching block with txs at topoheight {}", topoheight))?;
         Ok(block)
     }
 
+    // Retrieve the blocks (without txs) between two topoheights, bounds are inclusive
+    pub async fn get_blocks_range_by_topoheight(&self, start_topoheight: Option<u64>, end_topoheight: Option<u64>) -> Result<Vec<BlockResponse>> {
+        let value = self.client.call_with_kind("get_blocks_range_by_topoheight", RequestKind::ReadOnly, &GetTopoHeightRangeParams {
+            start_topoheight,
+            end_topoheight,
+            encoding: self.encoding
+        }).await.context("Error while fetching blocks range")?;
+        Self::parse_encoded_result(value, BinaryBlock::into_response)
+    }
+
+    // Blocks between the two topoheights (inclusive), the daemon limits how many are returned at once
+    pub async fn get_blocks_range(&self, start_topoheight: u64, end_topoheight: u64, include_txs: bool) -> Result<Vec<BlockResponse>> {
+        let blocks = self.client.call_with_kind("get_blocks_range", RequestKind::ReadOnly, &GetBlocksRangeParams {
+            start_topoheight,
+            end_topoheight,
+            include_txs
+        }).await.context(format!("Error while fetching blocks from topoheight {} to {}", start_topoheight, end_topoheight))?;
+        Ok(blocks)
+    }
+
+    // Batched version of get_block_with_txs_at_topoheight
+    pub async fn get_blocks_with_txs_at_topoheights(&self, topoheights: &[u64]) -> Result<Vec<Result<BlockResponse>>> {
+        let requests: Vec<_> = topoheights.iter().map(|topoheight| ("get_block_at_topoheight", GetBlockAtTopoHeightParams {
+            topoheight: *topoheight,
+            include_txs: true
+        })).collect();
+        self.call_batch_as(&requests).await
+    }
+
+    // Batched version of get_balance_at_topoheight
+    pub async fn get_balances_at_topoheights(&self, address: &Address, asset: &Hash, topoheights: &[u64]) -> Result<Vec<Result<VersionedBalance>>> {
+        let requests: Vec<_> = topoheights.iter().map(|topoheight| ("get_balance_at_topoheight", GetBalanceAtTopoHeightParams {
+            topoheight: *topoheight,
+            asset: Cow::Borrowed(asset),
+            address: Cow::Borrowed(address)
+        })).collect();
+        self.call_batch_as(&requests).await
+    }
+
+    // Batched version of get_transaction
+    pub async fn get_transactions(&self, hashes: &[Hash]) -> Result<Vec<Result<Transaction>>> {
+        let requests: Vec<_> = hashes.iter().map(|hash| ("get_transaction", GetTransactionParams {
+            hash: Cow::Borrowed(hash)
+        })).collect();
+        self.call_batch_as(&requests).await
+    }
+
     pub async fn get_transaction(&self, hash: &Hash) -> Result<Transaction> {
-        let tx = self.client.call_with("get_transaction", &GetTransactionParams {
+        let tx = self.client.call_with_kind("get_transaction", RequestKind::ReadOnly, &GetTransactionParams {
             hash: Cow::Borrowed(hash)
         }).await.context(format!("Error while fetching transaction {}", hash))?;
         Ok(tx)
     }
 
-    pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<()> {
-        let _: bool = self.client.call_with("submit_transaction", &SubmitTransactionParams {
+    // Returns the hash of the transaction once accepted by the daemon
+    // If the daemon rejects it, a WalletError::TransactionRejected with its reason is returned
+    pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<Hash> {
+        let res: JsonRPCResult<bool> = self.client.call_with_kind("submit_transaction", RequestKind::Mutating, &SubmitTransactionParams {
             data: transaction.to_hex()
-        }).await?;
-        Ok(())
+        }).await;
+
+        match res {
+            Ok(_) => Ok(transaction.hash()),
+            Err(JsonRPCError::ServerError { code: TX_REJECTED_ERROR_CODE, message, .. }) => Err(WalletError::TransactionRejected(message).into()),
+            Err(e) => Err(e).context("Error while submitting transaction")
+        }
+    }
+
+    // Execute all the sub-queries in one request against the same daemon state
+    pub async fn get_many(&self, queries: ManyQueryBuilder) -> Result<ManyQueryResponse> {
+        let results = self.client.call_with_kind("get_many", RequestKind::ReadOnly, &GetManyParams {
+            queries: queries.queries
+        }).await.context("Error while executing queries")?;
+        Ok(ManyQueryResponse { results })
+    }
+
+    // Verify the transaction against the daemon state without adding it to its mempool
+    pub async fn validate_transaction(&self, transaction: &Transaction) -> Result<ValidateTransactionResult> {
+        let result = self.client.call_with_kind("validate_transaction", RequestKind::ReadOnly, &SubmitTransactionParams {
+            data: transaction.to_hex()
+        }).await.context("Error while validating transaction")?;
+        Ok(result)
+    }
+
+    // Submit a block template with the optional miner work solving it
+    // Both must be in hex format
+    pub async fn submit_block(&self, block_template: String, miner_work: Option<String>) -> Result<SubmitBlockResult<'static>> {
+        let result = self.client.call_with_kind("submit_block", RequestKind::Mutating, &SubmitBlockParams {
+            block_template,
+            miner_work
+        }).await.context("Error while submitting block")?;
+        Ok(result)
     }
 
     pub async fn get_nonce(&self, address: &Address) -> Result<GetNonceResult> {
-        let nonce = self.client.call_with("get_nonce", &GetNonceParams {
+        let nonce = self.client.call_with_kind("get_nonce", RequestKind::ReadOnly, &GetNonceParams {
             address: Cow::Borrowed(address)
         }).await.context(format!("Error while fetching nonce from address {}", address))?;
         Ok(nonce)
     }
 
     pub async fn is_tx_executed_in_block(&self, tx_hash: &Hash, block_hash: &Hash) -> Result<bool> {
-        let is_executed = self.client.call_with("is_tx_executed_in_block", &IsTxExecutedInBlockParams {
+        let is_executed = self.client.call_with_kind("is_tx_executed_in_block", RequestKind::ReadOnly, &IsTxExecutedInBlockParams {
             tx_hash: Cow::Borrowed(tx_hash),
             block_hash: Cow::Borrowed(block_hash)
         }).await.context(format!("Error while checking if tx {} is executed in block {}", tx_hash, block_hash))?;
         Ok(is_executed)
     }
 
-    pub async fn get_mempool_cache(&self, address: &Address) -> Result<GetMempoolCacheResult> {
-        let cache = self.client.call_with("get_mempool_cache", &GetMempoolCacheParams {
+    // Daemons older than this method answer with a method not found error
+    pub async fn get_transaction_executor(&self, hash: &Hash) -> Result<TransactionExecutor> {
+        let res = self.client.call_with_kind("get_transaction_executor", RequestKind::ReadOnly, &GetTransactionExecutorParams {
+            hash: Cow::Borrowed(hash)
+        }).await;
+
+        match res {
+            Ok(executor) => Ok(TransactionExecutor::Executed(executor)),
+            Err(JsonRPCError::ServerError { code: TX_IN_MEMPOOL_ERROR_CODE | TX_NOT_EXECUTED_ERROR_CODE | TX_NOT_FOUND_ERROR_CODE, .. }) => Ok(TransactionExecutor::NotExecuted),
+            Err(JsonRPCError::MethodNotFound | JsonRPCError::ServerError { code: METHOD_NOT_FOUND_CODE, .. }) => Ok(TransactionExecutor::Unsupported),
+            Err(e) => Err(e).context(format!("Error while fetching executor of tx {}", hash))
+        }
+    }
+
+    // Block executing the TX, using get_transaction for daemons without get_transaction_executor
+    pub async fn get_transaction_executor_hash(&self, hash: &Hash) -> Result<Option<Hash>> {
+        match self.get_transaction_executor(hash).await? {
+            TransactionExecutor::Executed(executor) => Ok(Some(executor.block_hash.into_owned())),
+            TransactionExecutor::NotExecuted => Ok(None),
+            TransactionExecutor::Unsupported => {
+                let res: JsonRPCResult<TransactionResponse<'static>> = self.client.call_with_kind("get_transaction", RequestKind::ReadOnly, &GetTransactionParams {
+                    hash: Cow::Borrowed(hash)
+                }).await;
+
+                match res {
+                    Ok(response) => Ok(response.executed_in_block),
+                    // Older daemons have no dedicated error for an unknown TX
+                    Err(JsonRPCError::ServerError { .. }) => Ok(None),
+                    Err(e) => Err(e).context(format!("Error while fetching transaction {}", hash))
+                }
+            }
+        }
+    }
+
+    // Returns None if the account has no TX in mempool
+    pub async fn get_mempool_cache(&self, address: &Address) -> Result<Option<GetMempoolCacheResult>> {
+        let res = self.client.call_with_kind("get_mempool_cache", RequestKind::ReadOnly, &GetMempoolCacheParams {
             address: Cow::Borrowed(address)
-        }).await.context("Error while fetching mempool cache")?;
-        Ok(cache)
+        }).await;
+
+        match res {
+            Ok(cache) => Ok(Some(cache)),
+            Err(JsonRPCError::ServerError { code: ACCOUNT_NOT_IN_MEMPOOL_ERROR_CODE, .. }) => Ok(None),
+            Err(e) => Err(e).context("Error while fetching mempool cache")
+        }
+    }
+
+    // Latest nonce version at or below the topoheight
+    pub async fn get_nonce_at_topoheight(&self, address: &Address, topoheight: u64) -> Result<GetNonceResult> {
+        let nonce = self.client.call_with_kind("get_nonce_at_topoheight", RequestKind::ReadOnly, &GetNonceAtTopoHeightParams {
+            address: Cow::Borrowed(address),
+            topoheight
+        }).await.context(format!("Error while fetching nonce from address {} at topoheight {}", address, topoheight))?;
+        Ok(nonce)
+    }
+
+    // Check if the account has a nonce on chain, optionally at or below a topoheight
+    pub async fn has_nonce(&self, address: &Address, topoheight: Option<u64>) -> Result<bool> {
+        let result: HasNonceResult = self.client.call_with_kind("has_nonce", RequestKind::ReadOnly, &HasNonceParams {
+            address: Cow::Borrowed(address),
+            topoheight
+        }).await.context(format!("Error while checking nonce of address {}", address))?;
+        Ok(result.exist)
     }
 
     pub async fn is_account_registered(&self, address: &Address, in_stable_height: bool) -> Result<bool> {
-        let is_registered = self.client.call_with("is_account_registered", &IsAccountRegisteredParams {
+        let is_registered = self.client.call_with_kind("is_account_registered", RequestKind::ReadOnly, &IsAccountRegisteredParams {
             address: Cow::Borrowed(address),
             in_stable_height,
         }).await.context("Error while checking if account is registered")?;
         Ok(is_registered)
     }
-}
\ No newline at end of file
+
+    // Fees required by the daemon policy and suggested by its mempool
+    pub async fn estimate_fees(&self, tx_size: usize, transfers: usize, new_addresses: usize) -> Result<EstimateFeesResult> {
+        let estimate = self.client.call_with_kind("estimate_fees", RequestKind::ReadOnly, &EstimateFeesParams {
+            tx_size,
+            transfers,
+            new_addresses
+        }).await.context("Error while estimating fees")?;
+        Ok(estimate)
+    }
+}
+
+#[derive(Serialize)]
+struct CursorParams<'a> {
+    cursor: Option<&'a String>,
+    limit: usize,
+    minimum_topoheight: Option<u64>,
+    maximum_topoheight: Option<u64>
+}
+
+// Fetch the pages of a list endpoint using the cursor pagination
+// Items added on the daemon while iterating don't create duplicates
+pub struct PageIterator<'a, T> {
+    api: &'a DaemonAPI,
+    method: &'static str,
+    limit: usize,
+    minimum_topoheight: Option<u64>,
+    maximum_topoheight: Option<u64>,
+    cursor: Option<String>,
+    done: bool,
+    _phantom: PhantomData<T>
+}
+
+impl<'a, T: DeserializeOwned + 'a> PageIterator<'a, T> {
+    fn new(api: &'a DaemonAPI, method: &'static str, limit: usize, minimum_topoheight: Option<u64>, maximum_topoheight: Option<u64>) -> Self {
+        Self {
+            api,
+            method,
+            limit,
+            minimum_topoheight,
+            maximum_topoheight,
+            cursor: None,
+            done: false,
+            _phantom: PhantomData
+        }
+    }
+
+    // Returns None once the last page was returned
+    pub async fn next_page(&mut self) -> Result<Option<Vec<T>>> {
+        if self.done {
+            return Ok(None)
+        }
+
+        let page: PagedResult<T> = self.api.client.call_with(self.method, &CursorParams {
+            cursor: self.cursor.as_ref(),
+            limit: self.limit,
+            minimum_topoheight: self.minimum_topoheight,
+            maximum_topoheight: self.maximum_topoheight
+        }).await.context(format!("Error while fetching page from {}", self.method))?;
+
+        self.done = page.next_cursor.is_none();
+        self.cursor = page.next_cursor;
+        Ok(Some(page.items))
+    }
+
+    // Yield the items one by one, the next page is requested only once the current one is consumed
+    pub fn into_stream(self) -> impl Stream<Item = Result<T>> + 'a {
+        let pages = stream::try_unfold(self, |mut iterator| async move {
+            let page = iterator.next_page().await?;
+            Ok(page.map(|items| (items, iterator)))
+        });
+
+        pages.map_ok(|items| stream::iter(items.into_iter().map(Ok))).try_flatten()
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use xelis_common::{
+    crypto::Hash,
+    network::Network,
+    time::TimestampMillis
+};
+use crate::{
+    config::{
+        DOCTOR_CLOCK_SKEW_TOLERANCE,
+        DOCTOR_PENDING_AGE_WARN,
+        DOCTOR_SYNC_GAP_FAIL,
+        DOCTOR_SYNC_GAP_WARN,
+        DOCTOR_TOP_BLOCK_AGE_WARN,
+        PASSWORD_ITERATIONS,
+        PASSWORD_MEMORY_COST,
+        PASSWORD_PARALLELISM
+    },
+    pending::PendingTransaction
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail
+}
+
+// Result of a single check, it never contains an address, key or amount
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    // what the user can do to fix it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>
+}
+
+impl DoctorCheck {
+    fn pass(name: &'static str, message: String) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            message,
+            hint: None
+        }
+    }
+
+    fn warn(name: &'static str, message: String, hint: &str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            message,
+            hint: Some(hint.to_owned())
+        }
+    }
+
+    fn fail(name: &'static str, message: String, hint: &str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            message,
+            hint: Some(hint.to_owned())
+        }
+    }
+}
+
+// State of the daemon at the time of the checks
+#[derive(Debug, Clone)]
+pub struct DaemonState {
+    pub version: String,
+    pub network: Network,
+    pub topoheight: u64,
+    pub top_block_timestamp: TimestampMillis
+}
+
+// Entries saved in the history per type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HistoryCounts {
+    pub coinbase: usize,
+    pub burn: usize,
+    pub incoming: usize,
+    pub outgoing: usize,
+    pub discovered: usize
+}
+
+// Result of the verification of the wallet storage
+#[derive(Debug, Clone, Default)]
+pub struct StorageIntegrity {
+    // CRC32 of the whole database
+    pub checksum: u32,
+    // history entries that can be decrypted and read
+    pub entries: usize,
+    // history entries that can't be decrypted or read
+    pub corrupted: usize,
+    // topoheight changes that can't be decrypted or read
+    pub corrupted_changes: usize,
+    pub history: HistoryCounts
+}
+
+// Only added to the report when explicitly requested
+#[derive(Debug, Clone, Serialize)]
+pub struct SensitiveInfo {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daemon_address: Option<String>,
+    // asset => formatted balance
+    pub balances: HashMap<Hash, String>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensitive: Option<SensitiveInfo>
+}
+
+impl DoctorReport {
+    // Worst status of all the checks
+    pub fn status(&self) -> CheckStatus {
+        self.checks.iter().map(|check| check.status).max().unwrap_or(CheckStatus::Pass)
+    }
+
+    // Human readable lines of the report
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for check in self.checks.iter() {
+            let status = match check.status {
+                CheckStatus::Pass => "PASS",
+                CheckStatus::Warn => "WARN",
+                CheckStatus::Fail => "FAIL"
+            };
+            lines.push(format!("[{}] {}: {}", status, check.name, check.message));
+            if let Some(hint) = check.hint.as_ref() {
+                lines.push(format!("       {}", hint));
+            }
+        }
+
+        if let Some(sensitive) = self.sensitive.as_ref() {
+            lines.push(format!("Address: {}", sensitive.address));
+            if let Some(daemon_address) = sensitive.daemon_address.as_ref() {
+                lines.push(format!("Daemon address: {}", daemon_address));
+            }
+            for (asset, balance) in sensitive.balances.iter() {
+                lines.push(format!("Balance of {}: {}", asset, balance));
+            }
+        }
+        lines
+    }
+}
+
+// Major and minor parts of a version like 1.13.2-abcdef1
+fn parse_version(version: &str) -> Option<(u64, u64)> {
+    let version = version.split('-').next()?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+pub fn check_wallet(version: &str, read_only: bool) -> DoctorCheck {
+    let mode = if read_only { "read-only" } else { "read-write" };
+    DoctorCheck::pass("wallet", format!("version {}, opened in {} mode", version, mode))
+}
+
+pub fn check_network(network: &Network, daemon: Option<&DaemonState>) -> DoctorCheck {
+    match daemon {
+        Some(daemon) if daemon.network != *network => DoctorCheck::fail("network", format!("wallet is on {} but daemon is on {}", network, daemon.network), "Connect to a daemon running on the same network as the wallet"),
+        _ => DoctorCheck::pass("network", network.to_string())
+    }
+}
+
+pub fn check_daemon(wallet_version: &str, daemon: Result<&DaemonState, &str>) -> DoctorCheck {
+    let daemon = match daemon {
+        Ok(daemon) => daemon,
+        Err(e) => return DoctorCheck::fail("daemon", format!("unreachable: {}", e), "Check that the daemon is running and use 'online_mode' with its address")
+    };
+
+    match (parse_version(wallet_version), parse_version(&daemon.version)) {
+        (Some((major, minor)), Some((daemon_major, daemon_minor))) => {
+            if major != daemon_major {
+                DoctorCheck::fail("daemon", format!("version {} is not compatible with wallet version {}", daemon.version, wallet_version), "Update the wallet and the daemon to the same version")
+            } else if minor != daemon_minor {
+                DoctorCheck::warn("daemon", format!("version {} differs from wallet version {}", daemon.version, wallet_version), "Update the wallet and the daemon to the same version")
+            } else {
+                DoctorCheck::pass("daemon", format!("reachable, version {}", daemon.version))
+            }
+        },
+        _ => DoctorCheck::warn("daemon", format!("reachable, unknown version {}", daemon.version), "Update the daemon to a released version")
+    }
+}
+
+pub fn check_sync_gap(synced_topoheight: u64, daemon: Option<&DaemonState>) -> DoctorCheck {
+    let Some(daemon) = daemon else {
+        return DoctorCheck::warn("sync", format!("synced until topoheight {}, daemon topoheight unknown", synced_topoheight), "Connect to a daemon to check the sync")
+    };
+
+    if synced_topoheight > daemon.topoheight {
+        return DoctorCheck::fail("sync", format!("synced topoheight {} is above daemon topoheight {}", synced_topoheight, daemon.topoheight), "The daemon may be on another chain or still syncing, use 'rescan' once it is synced")
+    }
+
+    let gap = daemon.topoheight - synced_topoheight;
+    let message = format!("{} topoheight(s) behind the daemon", gap);
+    if gap > DOCTOR_SYNC_GAP_FAIL {
+        DoctorCheck::fail("sync", message, "The wallet is not syncing, check the logs and use 'rescan' if it doesn't progress")
+    } else if gap > DOCTOR_SYNC_GAP_WARN {
+        DoctorCheck::warn("sync", message, "Wait for the wallet to sync")
+    } else {
+        DoctorCheck::pass("sync", message)
+    }
+}
+
+pub fn check_pending(pending: &[PendingTransaction], topoheight: u64) -> DoctorCheck {
+    let Some(oldest) = pending.iter().map(|tx| tx.topoheight).min() else {
+        return DoctorCheck::pass("pending", "no pending transaction".to_owned())
+    };
+
+    let age = topoheight.saturating_sub(oldest);
+    let message = format!("{} pending transaction(s), oldest built {} topoheight(s) ago", pending.len(), age);
+    if age > DOCTOR_PENDING_AGE_WARN {
+        DoctorCheck::warn("pending", message, "Use 'repair_pending' to rebuild the pending state from the daemon")
+    } else {
+        DoctorCheck::pass("pending", message)
+    }
+}
+
+pub fn check_storage(integrity: &StorageIntegrity) -> DoctorCheck {
+    let message = format!("checksum {:08x}, {} entries readable", integrity.checksum, integrity.entries);
+    if integrity.corrupted > 0 || integrity.corrupted_changes > 0 {
+        DoctorCheck::fail("storage", format!("{}, {} entries and {} changes corrupted", message, integrity.corrupted, integrity.corrupted_changes), "Restore a backup or recreate the wallet from its seed")
+    } else {
+        DoctorCheck::pass("storage", message)
+    }
+}
+
+pub fn check_kdf() -> DoctorCheck {
+    DoctorCheck::pass("kdf", format!("argon2id, {} KB memory, {} iterations, parallelism {}", PASSWORD_MEMORY_COST, PASSWORD_ITERATIONS, PASSWORD_PARALLELISM))
+}
+
+// The daemon top block is used as reference of the daemon time
+pub fn check_clock(now: TimestampMillis, daemon: Option<&DaemonState>) -> DoctorCheck {
+    let Some(daemon) = daemon else {
+        return DoctorCheck::warn("clock", "daemon time unknown".to_owned(), "Connect to a daemon to check the clock")
+    };
+
+    if daemon.top_block_timestamp > now + DOCTOR_CLOCK_SKEW_TOLERANCE {
+        return DoctorCheck::fail("clock", format!("daemon top block is {} ms in the future", daemon.top_block_timestamp - now), "Synchronize your system clock")
+    }
+
+    let age = now.saturating_sub(daemon.top_block_timestamp);
+    if age > DOCTOR_TOP_BLOCK_AGE_WARN {
+        DoctorCheck::warn("clock", format!("daemon top block is {} ms old", age), "Check that your system clock is correct and that the daemon is synced")
+    } else {
+        DoctorCheck::pass("clock", format!("daemon top block is {} ms old", age))
+    }
+}
+
+pub fn check_history(history: &HistoryCounts) -> DoctorCheck {
+    DoctorCheck::pass("history", format!("{} coinbase, {} burn, {} incoming, {} outgoing, {} discovered", history.coinbase, history.burn, history.incoming, history.outgoing, history.discovered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daemon(topoheight: u64) -> DaemonState {
+        DaemonState {
+            version: "1.13.2-abcdef1".to_owned(),
+            network: Network::Mainnet,
+            topoheight,
+            top_block_timestamp: 1_000_000
+        }
+    }
+
+    #[test]
+    fn test_daemon_unreachable() {
+        let check = check_daemon("1.13.0-1234567", Err("connection refused"));
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.hint.is_some());
+
+        // Checks depending on the daemon can't pass
+        assert_eq!(check_sync_gap(100, None).status, CheckStatus::Warn);
+        assert_eq!(check_clock(1_000_000, None).status, CheckStatus::Warn);
+        assert_eq!(check_network(&Network::Mainnet, None).status, CheckStatus::Pass);
+
+        let report = DoctorReport { checks: vec![check_kdf(), check], sensitive: None };
+        assert_eq!(report.status(), CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_daemon_version() {
+        let daemon = daemon(0);
+        assert_eq!(check_daemon("1.13.0-1234567", Ok(&daemon)).status, CheckStatus::Pass);
+        assert_eq!(check_daemon("1.12.0-1234567", Ok(&daemon)).status, CheckStatus::Warn);
+        assert_eq!(check_daemon("2.0.0-1234567", Ok(&daemon)).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_sync_gap() {
+        assert_eq!(check_sync_gap(100, Some(&daemon(100))).status, CheckStatus::Pass);
+        assert_eq!(check_sync_gap(100, Some(&daemon(100 + DOCTOR_SYNC_GAP_WARN))).status, CheckStatus::Pass);
+        assert_eq!(check_sync_gap(100, Some(&daemon(101 + DOCTOR_SYNC_GAP_WARN))).status, CheckStatus::Warn);
+        assert_eq!(check_sync_gap(100, Some(&daemon(101 + DOCTOR_SYNC_GAP_FAIL))).status, CheckStatus::Fail);
+        // Wallet ahead of the daemon
+        assert_eq!(check_sync_gap(100, Some(&daemon(50))).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_clock() {
+        let daemon = daemon(0);
+        assert_eq!(check_clock(daemon.top_block_timestamp + 1000, Some(&daemon)).status, CheckStatus::Pass);
+        assert_eq!(check_clock(daemon.top_block_timestamp - DOCTOR_CLOCK_SKEW_TOLERANCE - 1, Some(&daemon)).status, CheckStatus::Fail);
+        assert_eq!(check_clock(daemon.top_block_timestamp + DOCTOR_TOP_BLOCK_AGE_WARN + 1, Some(&daemon)).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_network_mismatch() {
+        assert_eq!(check_network(&Network::Testnet, Some(&daemon(0))).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_redacted() {
+        let integrity = StorageIntegrity { checksum: 42, entries: 3, ..Default::default() };
+        let report = DoctorReport { checks: vec![check_storage(&integrity), check_history(&integrity.history)], sensitive: None };
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json.get("sensitive").is_none());
+        assert_eq!(json["checks"][0]["status"], "pass");
+        assert_eq!(report.status(), CheckStatus::Pass);
+
+        let corrupted = StorageIntegrity { corrupted: 1, ..integrity };
+        assert_eq!(check_storage(&corrupted).status, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    time::TimestampMillis,
+    transaction::builder::{FeeBuilder, TransactionTypeBuilder}
+};
+use crate::error::WalletError;
+
+// A transaction that can only be built and signed after a waiting period
+// Nothing from the chain state is stored: the nonce, balances
+// and reference are read again when the draft is confirmed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionDraft {
+    pub id: u64,
+    pub tx_type: TransactionTypeBuilder,
+    pub fee: FeeBuilder,
+    pub created_at: TimestampMillis,
+    // can't be confirmed before this time
+    pub unlock_at: TimestampMillis,
+    // deleted if still not confirmed at this time
+    pub expires_at: TimestampMillis
+}
+
+impl TransactionDraft {
+    // The expiration period starts once the draft is unlocked
+    pub fn new(id: u64, tx_type: TransactionTypeBuilder, fee: FeeBuilder, now: TimestampMillis, delay: u64, expiration: u64) -> Self {
+        let unlock_at = now.saturating_add(delay);
+        Self {
+            id,
+            tx_type,
+            fee,
+            created_at: now,
+            unlock_at,
+            expires_at: unlock_at.saturating_add(expiration)
+        }
+    }
+
+    pub fn is_expired(&self, now: TimestampMillis) -> bool {
+        now >= self.expires_at
+    }
+
+    // Verify that the draft can be signed now
+    pub fn check_confirmable(&self, now: TimestampMillis) -> Result<(), WalletError> {
+        if now < self.unlock_at {
+            return Err(WalletError::DraftLocked(self.id, format_duration(self.unlock_at - now)))
+        }
+
+        if self.is_expired(now) {
+            return Err(WalletError::DraftExpired(self.id))
+        }
+
+        Ok(())
+    }
+}
+
+// Parse a duration such as 90s, 30m, 24h or 7d in milliseconds
+pub fn parse_duration(value: &str) -> Result<u64, WalletError> {
+    let invalid = || WalletError::InvalidDuration(value.to_owned());
+    let (split, _) = value.char_indices().last().ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let unit = match unit {
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => return Err(invalid())
+    };
+
+    amount.checked_mul(unit).ok_or_else(invalid)
+}
+
+// Display a duration in milliseconds rounded to the minute
+pub fn format_duration(ms: u64) -> String {
+    let minutes = (ms + 59_999) / 60_000;
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        config::XELIS_ASSET,
+        transaction::BurnPayload
+    };
+    use super::*;
+
+    const HOUR: u64 = 60 * 60 * 1000;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), 90 * 1000);
+        assert_eq!(parse_duration("24h").unwrap(), 24 * HOUR);
+        assert_eq!(parse_duration("7d").unwrap(), 7 * 24 * HOUR);
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("10w").is_err());
+        assert!(parse_duration("-1h").is_err());
+    }
+
+    #[test]
+    fn test_draft_delay() {
+        let tx_type = TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 1 });
+        let draft = TransactionDraft::new(1, tx_type, FeeBuilder::default(), 1000, 24 * HOUR, 2 * HOUR);
+
+        // Refused before the end of the delay
+        assert!(matches!(draft.check_confirmable(1000), Err(WalletError::DraftLocked(1, _))));
+        assert!(matches!(draft.check_confirmable(1000 + 24 * HOUR - 1), Err(WalletError::DraftLocked(1, _))));
+
+        // Accepted until it expires
+        assert!(draft.check_confirmable(1000 + 24 * HOUR).is_ok());
+        assert!(draft.check_confirmable(1000 + 26 * HOUR - 1).is_ok());
+        assert!(matches!(draft.check_confirmable(1000 + 26 * HOUR), Err(WalletError::DraftExpired(1))));
+
+        // Survives a restart as it is stored in JSON
+        let decoded: TransactionDraft = serde_json::from_slice(&serde_json::to_vec(&draft).unwrap()).unwrap();
+        assert_eq!(decoded.unlock_at, draft.unlock_at);
+        assert_eq!(decoded.expires_at, draft.expires_at);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(1), "1m");
+        assert_eq!(format_duration(90 * 60 * 1000), "1h 30m");
+        assert_eq!(format_duration(25 * HOUR), "1d 1h");
+    }
+}
@@ -11,6 +11,7 @@ use xelis_common::{
     config::XELIS_ASSET,
     crypto::{
         Hash,
+        PaymentId,
         PublicKey
     },
     serializer::{
@@ -19,10 +20,7 @@ use xelis_common::{
         Serializer,
         Writer
     },
-    utils::{
-        format_coin,
-        format_xelis
-    }
+    utils::format_xelis
 };
 use anyhow::Result;
 use crate::storage::EncryptedStorage;
@@ -174,6 +172,13 @@ pub enum EntryData {
         fee: u64,
         // Nonce used
         nonce: u64
+    },
+    // Placeholder for an asset found on our account without a scan
+    // It is replaced by the real entries once the blocks are scanned
+    Discovered {
+        asset: Hash,
+        // Balance found at discovery
+        amount: u64
     }
 }
 
@@ -207,7 +212,11 @@ impl Serializer for EntryData {
                 let nonce = reader.read_u64()?;
 
                 Self::Outgoing { transfers, fee, nonce }
-            }
+            },
+            4 => Self::Discovered {
+                asset: reader.read_hash()?,
+                amount: reader.read_u64()?
+            },
             _ => return Err(ReaderError::InvalidValue)
         }) 
     }
@@ -239,6 +248,11 @@ impl Serializer for EntryData {
                 }
                 writer.write_u64(fee);
                 writer.write_u64(nonce);
+            },
+            Self::Discovered { asset, amount } => {
+                writer.write_u8(4);
+                writer.write_hash(asset);
+                writer.write_u64(amount);
             }
         }
     }
@@ -252,7 +266,8 @@ impl Serializer for EntryData {
             },
             Self::Outgoing { transfers, fee, nonce } => {
                 2 + transfers.iter().map(|t| t.size()).sum::<usize>() + fee.size() + nonce.size()
-            }
+            },
+            Self::Discovered { asset, amount } => asset.size() + amount.size()
         }
     }
 }
@@ -300,6 +315,7 @@ impl TransactionEntry {
                 EntryData::Burn { asset, amount } => RPCEntryType::Burn { asset, amount },
                 EntryData::Incoming { from, transfers } => {
                     let transfers = transfers.into_iter().map(|t| RPCTransferIn {
+                        payment_id: t.extra_data.as_ref().and_then(PaymentId::from_data),
                         asset: t.asset,
                         amount: t.amount,
                         extra_data: t.extra_data
@@ -308,54 +324,58 @@ impl TransactionEntry {
                 },
                 EntryData::Outgoing { transfers, fee, nonce } => {
                     let transfers = transfers.into_iter().map(|t| RPCTransferOut {
+                        payment_id: t.extra_data.as_ref().and_then(PaymentId::from_data),
                         destination: t.destination.to_address(mainnet),
                         asset: t.asset,
                         amount: t.amount,
                         extra_data: t.extra_data
                     }).collect();
                     RPCEntryType::Outgoing { transfers, fee, nonce }
-                }
+                },
+                EntryData::Discovered { asset, amount } => RPCEntryType::Discovered { asset, amount }
             }
         }
     }
 
     pub fn summary(&self, mainnet: bool, storage: &EncryptedStorage) -> Result<String> {
         let entry_str = match self.get_entry() {
-            EntryData::Coinbase { reward } => format!("Coinbase {} XELIS", format_xelis(*reward)),
-            EntryData::Burn { asset, amount } => {
-                let decimals = storage.get_asset_decimals(asset)?;
-                format!("Burn {} of {}", format_coin(*amount, decimals), asset)
-            },
+            EntryData::Coinbase { reward } => format!("Coinbase {}", storage.format_asset_amount(&XELIS_ASSET, *reward)?),
+            EntryData::Burn { asset, amount } => format!("Burn {}", storage.format_asset_amount(asset, *amount)?),
             EntryData::Incoming { from, transfers } => {
                 let mut str = String::new();
                 for transfer in transfers {
-                    if *transfer.get_asset() == XELIS_ASSET {
-                        str.push_str(&format!("Received {} XELIS from {}", format_xelis(transfer.get_amount()), from.as_address(mainnet)));
-                    } else {
-                        let decimals = storage.get_asset_decimals(transfer.get_asset())?;
-                        str.push_str(&format!("Received {} {} from {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), from.as_address(mainnet)));
-                    }
+                    str.push_str(&format!("Received {} from {}", storage.format_asset_amount(transfer.get_asset(), transfer.get_amount())?, from.as_address(mainnet)));
+                    str.push_str(&format_extra_data(transfer.get_extra_data()));
                 }
                 str
             },
             EntryData::Outgoing { transfers, fee, nonce } => {
                 let mut str = format!("Fee: {}, Nonce: {} ", format_xelis(*fee), nonce);
                 for transfer in transfers {
-                    if *transfer.get_asset() == XELIS_ASSET {
-                        str.push_str(&format!("Sent {} XELIS to {}", format_xelis(transfer.get_amount()), transfer.get_destination().as_address(mainnet)));
-                    } else {
-                        let decimals = storage.get_asset_decimals(transfer.get_asset())?;
-                        str.push_str(&format!("Sent {} {} to {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), transfer.get_destination().as_address(mainnet)));
-                    }
+                    str.push_str(&format!("Sent {} to {}", storage.format_asset_amount(transfer.get_asset(), transfer.get_amount())?, transfer.get_destination().as_address(mainnet)));
+                    str.push_str(&format_extra_data(transfer.get_extra_data()));
                 }
                 str
-            }
+            },
+            EntryData::Discovered { asset, amount } => format!("Discovered {} (details pending scan)", storage.format_asset_amount(asset, *amount)?)
         };
 
         Ok(format!("Hash {} at TopoHeight {}: {}", self.hash, self.topoheight, entry_str))
     }
 }
 
+// Data attached to a transfer, such as the one integrated in the address it was sent to
+fn format_extra_data(extra_data: &Option<DataElement>) -> String {
+    let Some(data) = extra_data else {
+        return String::new()
+    };
+
+    match PaymentId::from_data(data) {
+        Some(payment_id) => format!(" (payment id: {})", payment_id),
+        None => format!(" (data: {})", serde_json::to_string(data).unwrap_or_default())
+    }
+}
+
 impl Serializer for TransactionEntry {
     fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
         let hash = reader.read_hash()?;
@@ -408,3 +428,21 @@ impl<'a> Transfer<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovered_entry_serialization() {
+        let asset = Hash::new([1u8; 32]);
+        let entry = TransactionEntry::new(asset.clone(), 42, EntryData::Discovered { asset: asset.clone(), amount: 1000 });
+        let bytes = entry.to_bytes();
+        assert_eq!(bytes.len(), entry.size());
+
+        let decoded = TransactionEntry::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.get_topoheight(), 42);
+        assert!(matches!(decoded.get_entry(), EntryData::Discovered { asset: a, amount: 1000 } if *a == asset));
+        assert!(matches!(decoded.serializable(false).entry, RPCEntryType::Discovered { amount: 1000, .. }));
+    }
+}
@@ -2,7 +2,7 @@ use thiserror::Error;
 use chacha20poly1305::Error as CryptoError;
 use super::network_handler::NetworkError;
 use xelis_common::{
-    crypto::Hash, rpc_server::InternalRpcError, transaction::aead::CipherFormatError, utils::{format_coin, format_xelis}
+    amount::AmountOverflow, block::AuthorshipProofError, crypto::{AddressError, Hash}, network::Network, rpc_server::InternalRpcError, transaction::aead::CipherFormatError, utils::{format_coin, format_xelis}
 };
 use anyhow::Error;
 
@@ -79,6 +79,64 @@ pub enum WalletError {
     CiphertextDecode,
     #[error(transparent)]
     AEADCipherFormatError(#[from] CipherFormatError),
+    #[error("Connection to daemon is lost, transaction submission is paused until it is restored")]
+    DaemonDisconnected,
+    #[error("Unsupported metadata file version {}", _0)]
+    UnsupportedMetadataVersion(u8),
+    #[error("Invalid passphrase for metadata file")]
+    InvalidMetadataPassphrase,
+    #[error("Metadata file was exported on {} but wallet is on {}", _0, _1)]
+    MetadataNetworkMismatch(Network, Network),
+    #[error("Burn amount must be greater than 0")]
+    InvalidBurnAmount,
+    #[error("An API key named '{}' already exists", _0)]
+    ApiKeyAlreadyExists(String),
+    #[error("No API key named '{}'", _0)]
+    ApiKeyNotFound(String),
+    #[error("Invalid duration '{}', expected a number followed by s, m, h or d", _0)]
+    InvalidDuration(String),
+    #[error("No draft with id {}", _0)]
+    DraftNotFound(u64),
+    #[error("Draft {} can't be confirmed yet, wait {}", _0, _1)]
+    DraftLocked(u64, String),
+    #[error("Draft {} has expired", _0)]
+    DraftExpired(u64),
+    #[error("Ownership challenge for {} has expired", _0)]
+    OwnershipChallengeExpired(String),
+    #[error("No ownership challenge for contact {}", _0)]
+    NoOwnershipChallenge(String),
+    #[error("Invalid auto bump policy, bump percent, check interval and max attempts must be above 0")]
+    InvalidAutoBumpPolicy,
+    #[error(transparent)]
+    InvalidAddress(#[from] AddressError),
+    #[error("Invalid hook event '{}', expected incoming_payment, payment_confirmed or sync_completed", _0)]
+    InvalidHookEvent(String),
+    #[error("Hook command can't be empty")]
+    InvalidHookCommand,
+    #[error("Invalid hook timeout, it must be between 1 and {} seconds", _0)]
+    InvalidHookTimeout(u64),
+    #[error("No hook with id {}", _0)]
+    HookNotFound(u64),
+    #[error("Error while executing hook: {}", _0)]
+    HookError(String),
+    #[error("Transaction rejected by the daemon: {}", _0)]
+    TransactionRejected(String),
+    #[error(transparent)]
+    AmountOverflow(#[from] AmountOverflow),
+    #[error("Invalid export format '{}', expected csv or json", _0)]
+    InvalidExportFormat(String),
+    #[error("Wallet is already open in the process {}, it can only be opened in read-only mode", _0)]
+    WalletLocked(u32),
+    #[error("Wallet is open in read-only mode")]
+    ReadOnly,
+    #[error("Integrated data is {} bytes too big: {} bytes, maximum is {} bytes", _0 - _1, _0, _1)]
+    IntegratedDataTooBig(usize, usize),
+    #[error("Block {} was not mined by this wallet", _0)]
+    BlockNotMinedByUs(Hash),
+    #[error("Block {} has no authorship tag of this wallet, was the miner started with its authorship key?", _0)]
+    BlockNotTagged(Hash),
+    #[error(transparent)]
+    AuthorshipProof(#[from] AuthorshipProofError),
 }
 
 impl WalletError {
@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    str::FromStr
+};
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+use xelis_common::{
+    config::{COIN_DECIMALS, XELIS_ASSET},
+    crypto::Hash,
+    time::TimestampMillis
+};
+use anyhow::Context;
+use crate::{
+    entry::{EntryData, TransactionEntry},
+    error::WalletError,
+    storage::EncryptedStorage
+};
+
+pub const CSV_HEADER: &str = "date,direction,asset,amount,fee,counterparty,hash,topoheight";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json
+}
+
+impl FromStr for ExportFormat {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "csv" => Self::Csv,
+            "json" => Self::Json,
+            _ => return Err(WalletError::InvalidExportFormat(s.to_owned()))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub min_topoheight: Option<u64>,
+    pub max_topoheight: Option<u64>,
+    // Only the transfers of this asset are exported
+    pub asset: Option<Hash>
+}
+
+// One line of the export, an entry with several transfers gives one row per transfer
+// Amounts are strings in the asset units so no precision is lost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryRow {
+    // ISO 8601 date of the block, None if the entry was synced without it
+    pub date: Option<String>,
+    pub direction: &'static str,
+    pub asset: Hash,
+    pub amount: String,
+    // Fee in XELIS, only set on the first row of an outgoing transaction
+    pub fee: Option<String>,
+    pub counterparty: Option<String>,
+    pub hash: Hash,
+    pub topoheight: u64
+}
+
+impl HistoryRow {
+    pub fn to_csv(&self) -> String {
+        format!("{},{},{},{},{},{},{},{}",
+            self.date.as_deref().unwrap_or_default(),
+            self.direction,
+            self.asset,
+            self.amount,
+            self.fee.as_deref().unwrap_or_default(),
+            self.counterparty.as_deref().unwrap_or_default(),
+            self.hash,
+            self.topoheight
+        )
+    }
+}
+
+// Exact decimal representation of an amount in atomic units
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string()
+    }
+
+    let unit = 10u128.pow(decimals as u32);
+    let amount = amount as u128;
+    format!("{}.{:0width$}", amount / unit, amount % unit, width = decimals as usize)
+}
+
+pub fn format_date(timestamp: TimestampMillis) -> Option<String> {
+    DateTime::from_timestamp_millis(timestamp as i64).map(|date| date.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+// Write the rows one by one in the requested format
+pub struct HistoryWriter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    rows: usize
+}
+
+impl<W: Write> HistoryWriter<W> {
+    pub fn new(mut writer: W, format: ExportFormat) -> Result<Self, WalletError> {
+        match format {
+            ExportFormat::Csv => writeln!(writer, "{}", CSV_HEADER),
+            ExportFormat::Json => write!(writer, "[")
+        }.context("Error while writing history header")?;
+
+        Ok(Self {
+            writer,
+            format,
+            rows: 0
+        })
+    }
+
+    pub fn write_row(&mut self, row: &HistoryRow) -> Result<(), WalletError> {
+        match self.format {
+            ExportFormat::Csv => writeln!(self.writer, "{}", row.to_csv()).context("Error while writing history row")?,
+            ExportFormat::Json => {
+                if self.rows > 0 {
+                    write!(self.writer, ",").context("Error while writing history row")?;
+                }
+                serde_json::to_writer(&mut self.writer, row).context("Error while writing history row")?;
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    // Close the output and returns the number of rows written
+    pub fn finish(mut self) -> Result<usize, WalletError> {
+        if self.format == ExportFormat::Json {
+            writeln!(self.writer, "]").context("Error while writing history footer")?;
+        }
+        self.writer.flush().context("Error while flushing history")?;
+        Ok(self.rows)
+    }
+}
+
+// Decimals of the assets already used during the export
+pub struct DecimalsCache<'a> {
+    storage: &'a EncryptedStorage,
+    decimals: HashMap<Hash, u8>
+}
+
+impl<'a> DecimalsCache<'a> {
+    pub fn new(storage: &'a EncryptedStorage) -> Self {
+        Self {
+            storage,
+            decimals: HashMap::new()
+        }
+    }
+
+    pub fn format(&mut self, asset: &Hash, amount: u64) -> Result<String, WalletError> {
+        let decimals = match self.decimals.get(asset) {
+            Some(decimals) => *decimals,
+            None => {
+                let decimals = if *asset == XELIS_ASSET {
+                    COIN_DECIMALS
+                } else {
+                    self.storage.get_asset_decimals(asset)?
+                };
+                self.decimals.insert(asset.clone(), decimals);
+                decimals
+            }
+        };
+        Ok(format_amount(amount, decimals))
+    }
+}
+
+// Rows of an entry matching the asset filter
+pub fn build_rows(entry: &TransactionEntry, date: Option<String>, asset_filter: Option<&Hash>, mainnet: bool, decimals: &mut DecimalsCache) -> Result<Vec<HistoryRow>, WalletError> {
+    let mut rows = Vec::new();
+    let mut push = |direction: &'static str, asset: &Hash, amount: String, fee: Option<String>, counterparty: Option<String>| {
+        rows.push(HistoryRow {
+            date: date.clone(),
+            direction,
+            asset: asset.clone(),
+            amount,
+            fee,
+            counterparty,
+            hash: entry.get_hash().clone(),
+            topoheight: entry.get_topoheight()
+        });
+    };
+    let accept = |asset: &Hash| asset_filter.map_or(true, |filter| filter == asset);
+
+    match entry.get_entry() {
+        EntryData::Coinbase { reward } => if accept(&XELIS_ASSET) {
+            push("coinbase", &XELIS_ASSET, decimals.format(&XELIS_ASSET, *reward)?, None, None);
+        },
+        EntryData::Burn { asset, amount } => if accept(asset) {
+            push("burn", asset, decimals.format(asset, *amount)?, None, None);
+        },
+        EntryData::Discovered { asset, amount } => if accept(asset) {
+            push("discovered", asset, decimals.format(asset, *amount)?, None, None);
+        },
+        EntryData::Incoming { from, transfers } => {
+            let from = from.as_address(mainnet).to_string();
+            for transfer in transfers.iter().filter(|t| accept(t.get_asset())) {
+                push("incoming", transfer.get_asset(), decimals.format(transfer.get_asset(), transfer.get_amount())?, None, Some(from.clone()));
+            }
+        },
+        EntryData::Outgoing { transfers, fee, .. } => {
+            let mut fee = Some(decimals.format(&XELIS_ASSET, *fee)?);
+            for transfer in transfers.iter().filter(|t| accept(t.get_asset())) {
+                let destination = transfer.get_destination().as_address(mainnet).to_string();
+                push("outgoing", transfer.get_asset(), decimals.format(transfer.get_asset(), transfer.get_amount())?, fee.take(), Some(destination));
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(0, 8), "0.00000000");
+        assert_eq!(format_amount(123_456_789, 8), "1.23456789");
+        assert_eq!(format_amount(u64::MAX, 8), "184467440737.09551615");
+        assert_eq!(format_amount(42, 0), "42");
+        assert_eq!(format_date(0).unwrap(), "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_export_ignores_display_precision() {
+        // The display of this 18 decimals asset is rounded, the export keeps every digit
+        let value = 1_234_567_891_234_567_891;
+        assert_eq!(xelis_common::utils::format_coin_with_precision(value, 18, 6), "≈1.234568");
+        assert_eq!(format_amount(value, 18), "1.234567891234567891");
+    }
+
+    #[test]
+    fn test_history_writer() {
+        let row = HistoryRow {
+            date: format_date(1_700_000_000_123),
+            direction: "outgoing",
+            asset: XELIS_ASSET,
+            amount: format_amount(150_000_000, COIN_DECIMALS),
+            fee: Some(format_amount(1000, COIN_DECIMALS)),
+            counterparty: None,
+            hash: Hash::zero(),
+            topoheight: 10
+        };
+
+        let mut csv = Vec::new();
+        let mut writer = HistoryWriter::new(&mut csv, ExportFormat::Csv).unwrap();
+        writer.write_row(&row).unwrap();
+        assert_eq!(writer.finish().unwrap(), 1);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv, format!("{}\n2023-11-14T22:13:20.123Z,outgoing,{},1.50000000,0.00001000,,{},10\n", CSV_HEADER, XELIS_ASSET, Hash::zero()));
+
+        let mut json = Vec::new();
+        let mut writer = HistoryWriter::new(&mut json, ExportFormat::Json).unwrap();
+        writer.write_row(&row).unwrap();
+        writer.write_row(&row).unwrap();
+        assert_eq!(writer.finish().unwrap(), 2);
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[0]["amount"], "1.50000000");
+        assert_eq!(value[0]["fee"], "0.00001000");
+    }
+}
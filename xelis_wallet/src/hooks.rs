@@ -0,0 +1,290 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    process::Stdio,
+    str::FromStr,
+    sync::Arc,
+    time::Duration
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::AsyncWriteExt,
+    process::Command,
+    sync::Semaphore,
+    time::timeout
+};
+use xelis_common::{
+    api::wallet::EntryType,
+    utils::spawn_task
+};
+use log::{debug, warn};
+use crate::{
+    config::MAX_HOOK_TIMEOUT,
+    error::WalletError,
+    wallet::Event
+};
+
+// Env variable containing the name of the event
+pub const HOOK_EVENT_ENV: &str = "XELIS_HOOK_EVENT";
+// Env variable containing the network of the wallet
+pub const HOOK_NETWORK_ENV: &str = "XELIS_HOOK_NETWORK";
+
+// Wallet events on which a hook can be triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    // An incoming transfer got executed in a block
+    IncomingPayment,
+    // One of our outgoing transactions got executed in a block
+    PaymentConfirmed,
+    // The wallet is synced with the daemon topoheight
+    SyncCompleted
+}
+
+impl HookEvent {
+    // Hook event matching a wallet event, if any
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::NewTransaction(entry) => match entry.entry {
+                EntryType::Incoming { .. } => Some(Self::IncomingPayment),
+                EntryType::Outgoing { .. } => Some(Self::PaymentConfirmed),
+                _ => None
+            },
+            Event::NewTopoHeight { .. } => Some(Self::SyncCompleted),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IncomingPayment => "incoming_payment",
+            Self::PaymentConfirmed => "payment_confirmed",
+            Self::SyncCompleted => "sync_completed"
+        }
+    }
+}
+
+impl Display for HookEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for HookEvent {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "incoming_payment" => Self::IncomingPayment,
+            "payment_confirmed" => Self::PaymentConfirmed,
+            "sync_completed" => Self::SyncCompleted,
+            _ => return Err(WalletError::InvalidHookEvent(s.to_owned()))
+        })
+    }
+}
+
+// External command to execute on a wallet event
+// It is executed directly, without any shell
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    pub id: u64,
+    pub event: HookEvent,
+    pub command: String,
+    pub args: Vec<String>,
+    // timeout in seconds after which the command is killed
+    pub timeout: u64
+}
+
+impl Hook {
+    // Build a hook from a command line, arguments are split on whitespaces
+    pub fn new(id: u64, event: HookEvent, command_line: &str, timeout: u64) -> Result<Self, WalletError> {
+        if timeout == 0 || timeout > MAX_HOOK_TIMEOUT {
+            return Err(WalletError::InvalidHookTimeout(MAX_HOOK_TIMEOUT))
+        }
+
+        let mut parts = command_line.split_whitespace().map(String::from);
+        let command = parts.next().ok_or(WalletError::InvalidHookCommand)?;
+        Ok(Self {
+            id,
+            event,
+            command,
+            args: parts.collect(),
+            timeout
+        })
+    }
+
+    pub fn get_command_line(&self) -> String {
+        let mut line = self.command.clone();
+        for arg in self.args.iter() {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookOutcome {
+    Success,
+    // Exit code, None if killed by a signal
+    Failed(Option<i32>),
+    TimedOut
+}
+
+// Execute the hook with the event data as JSON on stdin
+// Only the event name and the network are set in its environment
+pub async fn run_hook(hook: &Hook, network: &str, payload: &Value) -> Result<HookOutcome, WalletError> {
+    let mut command = Command::new(&hook.command);
+    command.args(&hook.args)
+        .env_clear()
+        .env(HOOK_EVENT_ENV, hook.event.as_str())
+        .env(HOOK_NETWORK_ENV, network)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    // Keep the PATH so the command can be found and can call other programs
+    if let Some(path) = std::env::var_os("PATH") {
+        command.env("PATH", path);
+    }
+
+    let mut child = command.spawn().map_err(|e| WalletError::HookError(e.to_string()))?;
+    let bytes = serde_json::to_vec(payload).map_err(|e| WalletError::HookError(e.to_string()))?;
+    let duration = Duration::from_secs(hook.timeout);
+
+    let res = timeout(duration, async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The command may not read its stdin, ignore the broken pipe
+            if let Err(e) = stdin.write_all(&bytes).await {
+                debug!("Error while writing payload to hook #{}: {}", hook.id, e);
+            }
+        }
+        child.wait().await
+    }).await;
+
+    match res {
+        Ok(status) => {
+            let status = status.map_err(|e| WalletError::HookError(e.to_string()))?;
+            if status.success() {
+                Ok(HookOutcome::Success)
+            } else {
+                Ok(HookOutcome::Failed(status.code()))
+            }
+        },
+        Err(_) => {
+            if let Err(e) = child.kill().await {
+                warn!("Error while killing hook #{}: {}", hook.id, e);
+            }
+            Ok(HookOutcome::TimedOut)
+        }
+    }
+}
+
+// Execute the hooks registered on the wallet events
+pub struct HookManager {
+    hooks: Vec<Hook>,
+    network: String,
+    // Limit the number of hooks running at the same time
+    semaphore: Arc<Semaphore>
+}
+
+impl HookManager {
+    pub fn new(hooks: Vec<Hook>, network: String, max_concurrent: usize) -> Self {
+        Self {
+            hooks,
+            network,
+            semaphore: Arc::new(Semaphore::new(max_concurrent.max(1)))
+        }
+    }
+
+    pub fn set_hooks(&mut self, hooks: Vec<Hook>) {
+        self.hooks = hooks;
+    }
+
+    // Spawn the hooks of this event in background
+    // Hooks are skipped if too many are still running
+    pub fn trigger(&self, event: HookEvent, payload: &Value) {
+        for hook in self.hooks.iter().filter(|hook| hook.event == event) {
+            let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() else {
+                warn!("Too many hooks running, hook #{} skipped for {}", hook.id, event);
+                continue;
+            };
+
+            let hook = hook.clone();
+            let network = self.network.clone();
+            let payload = payload.clone();
+            spawn_task(format!("hook-{}", hook.id), async move {
+                match run_hook(&hook, &network, &payload).await {
+                    Ok(HookOutcome::Success) => debug!("Hook #{} executed for {}", hook.id, event),
+                    Ok(HookOutcome::Failed(code)) => warn!("Hook #{} failed for {} with exit code {:?}", hook.id, event, code),
+                    Ok(HookOutcome::TimedOut) => warn!("Hook #{} killed after {}s for {}", hook.id, hook.timeout, event),
+                    Err(e) => warn!("Error while executing hook #{}: {}", hook.id, e)
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+    use serde_json::json;
+    use super::*;
+
+    // Script saving its stdin and environment next to itself
+    fn stub_script(name: &str, body: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xelis-hooks-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hook.sh");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_hook_new() {
+        let hook = Hook::new(1, HookEvent::IncomingPayment, " curl -X POST  https://example.com ", 10).unwrap();
+        assert_eq!(hook.command, "curl");
+        assert_eq!(hook.args, vec!["-X", "POST", "https://example.com"]);
+        assert_eq!(hook.get_command_line(), "curl -X POST https://example.com");
+
+        assert!(Hook::new(1, HookEvent::IncomingPayment, "  ", 10).is_err());
+        assert!(Hook::new(1, HookEvent::IncomingPayment, "true", 0).is_err());
+        assert!(Hook::new(1, HookEvent::IncomingPayment, "true", MAX_HOOK_TIMEOUT + 1).is_err());
+        assert_eq!("Sync_Completed".parse::<HookEvent>().unwrap(), HookEvent::SyncCompleted);
+        assert!("new_block".parse::<HookEvent>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hook_payload() {
+        let script = stub_script("payload", "cat > \"$(dirname \"$0\")/stdin\"\necho \"$XELIS_HOOK_EVENT $XELIS_HOOK_NETWORK $HOME\" > \"$(dirname \"$0\")/env\"");
+        let dir = script.parent().unwrap();
+        let hook = Hook::new(1, HookEvent::IncomingPayment, script.to_str().unwrap(), 10).unwrap();
+        let payload = json!({ "hash": "00", "topoheight": 10 });
+
+        assert_eq!(run_hook(&hook, "testnet", &payload).await.unwrap(), HookOutcome::Success);
+        let stdin: Value = serde_json::from_slice(&fs::read(dir.join("stdin")).unwrap()).unwrap();
+        assert_eq!(stdin, payload);
+        // Nothing else from the wallet environment is inherited
+        assert_eq!(fs::read_to_string(dir.join("env")).unwrap().trim_end(), "incoming_payment testnet");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hook_failure_and_timeout() {
+        let script = stub_script("failure", "exit 3");
+        let hook = Hook::new(1, HookEvent::SyncCompleted, script.to_str().unwrap(), 10).unwrap();
+        assert_eq!(run_hook(&hook, "mainnet", &json!({})).await.unwrap(), HookOutcome::Failed(Some(3)));
+        fs::remove_dir_all(script.parent().unwrap()).unwrap();
+
+        let hook = Hook::new(2, HookEvent::SyncCompleted, "sleep 30", 1).unwrap();
+        let start = std::time::Instant::now();
+        assert_eq!(run_hook(&hook, "mainnet", &json!({})).await.unwrap(), HookOutcome::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let hook = Hook::new(3, HookEvent::SyncCompleted, "xelis-hook-command-not-found", 1).unwrap();
+        assert!(run_hook(&hook, "mainnet", &json!({})).await.is_err());
+    }
+}
@@ -5,9 +5,20 @@ pub mod cipher;
 pub mod daemon_api;
 pub mod network_handler;
 pub mod entry;
-pub mod mnemonics;
 pub mod transaction_builder;
 pub mod error;
+pub mod metadata;
+pub mod draft;
+pub mod ownership;
+pub mod privacy;
+pub mod pending;
+pub mod hooks;
+pub mod export;
+pub mod lock;
+pub mod doctor;
+
+#[cfg(test)]
+pub mod testing;
 
 #[cfg(feature = "api_server")]
 pub mod api;
\ No newline at end of file
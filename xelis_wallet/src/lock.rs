@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use anyhow::Context;
+use log::{debug, warn};
+use crate::error::WalletError;
+
+// Lock files are stored in the wallet directory next to the database files
+// The writer lock is exclusive, it contains the pid of the process having the wallet open
+pub const WRITER_LOCK_FILE: &str = "writer.lock";
+// Each reader works on its own snapshot of the database and registers it here
+// as "<pid>-<id>.lock" containing the snapshot path
+pub const READERS_DIR: &str = "readers";
+
+// Unique id for the locks taken by this process
+static NEXT_LOCK_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    Writer,
+    Reader
+}
+
+// Lock held as long as the storage is open, the lock file is deleted on drop
+// A lock left by a crashed process is detected using its pid and replaced
+#[derive(Debug)]
+pub struct WalletLock {
+    kind: LockKind,
+    path: PathBuf,
+    // Copy of the database used by a reader
+    snapshot: Option<PathBuf>
+}
+
+impl WalletLock {
+    // Take the exclusive writer lock of the wallet directory
+    pub fn acquire_writer(dir: &Path) -> Result<Self, WalletError> {
+        fs::create_dir_all(dir).context("Error while creating wallet directory")?;
+        let path = dir.join(WRITER_LOCK_FILE);
+        let id = next_lock_id();
+
+        // The pid is written in a temporary file first, then linked as the lock file
+        // so no other process can read a lock without its pid
+        let tmp = dir.join(format!("{}.{}", WRITER_LOCK_FILE, id));
+        fs::write(&tmp, process::id().to_string()).context("Error while writing wallet lock")?;
+        let res = Self::link_writer_lock(&tmp, &path);
+        if let Err(e) = fs::remove_file(&tmp) {
+            debug!("Error while deleting temporary lock {}: {}", tmp.display(), e);
+        }
+        res?;
+
+        Ok(Self {
+            kind: LockKind::Writer,
+            path,
+            snapshot: None
+        })
+    }
+
+    fn link_writer_lock(tmp: &Path, path: &Path) -> Result<(), WalletError> {
+        // Second attempt is done only after deleting a stale lock
+        for _ in 0..2 {
+            match fs::hard_link(tmp, path) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_pid(path) {
+                    Some(pid) if is_process_alive(pid) => return Err(WalletError::WalletLocked(pid)),
+                    _ => {
+                        warn!("Removing stale wallet lock {}", path.display());
+                        remove_if_exists(path).context("Error while deleting stale wallet lock")?;
+                    }
+                },
+                Err(e) => return Err(anyhow::Error::new(e).context("Error while creating wallet lock").into())
+            }
+        }
+
+        Err(WalletError::WalletLocked(read_pid(path).unwrap_or_default()))
+    }
+
+    // Register a reader and copy the database in a snapshot directory
+    // The snapshot can be opened while a writer has the wallet open
+    pub fn acquire_reader(dir: &Path) -> Result<Self, WalletError> {
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("No wallet found at {}", dir.display()).into())
+        }
+
+        let readers = dir.join(READERS_DIR);
+        fs::create_dir_all(&readers).context("Error while creating readers directory")?;
+        clean_stale_readers(&readers);
+
+        let name = format!("{}-{}", process::id(), next_lock_id());
+        let snapshot = std::env::temp_dir().join(format!("xelis-wallet-snapshot-{}", name));
+        let path = readers.join(format!("{}.lock", name));
+        fs::write(&path, snapshot.to_string_lossy().as_bytes()).context("Error while writing reader lock")?;
+
+        // From here, the lock is cleaned on drop if the copy fails
+        let lock = Self {
+            kind: LockKind::Reader,
+            path,
+            snapshot: Some(snapshot.clone())
+        };
+        copy_database(dir, &snapshot).context("Error while creating wallet snapshot")?;
+
+        Ok(lock)
+    }
+
+    pub fn get_kind(&self) -> LockKind {
+        self.kind
+    }
+
+    // Directory of the database to open
+    pub fn get_snapshot(&self) -> Option<&Path> {
+        self.snapshot.as_deref()
+    }
+}
+
+impl Drop for WalletLock {
+    fn drop(&mut self) {
+        if let Err(e) = remove_if_exists(&self.path) {
+            warn!("Error while deleting wallet lock {}: {}", self.path.display(), e);
+        }
+
+        if let Some(snapshot) = &self.snapshot {
+            if let Err(e) = remove_dir_if_exists(snapshot) {
+                warn!("Error while deleting wallet snapshot {}: {}", snapshot.display(), e);
+            }
+        }
+    }
+}
+
+fn next_lock_id() -> u64 {
+    NEXT_LOCK_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(())
+    }
+}
+
+fn remove_dir_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(())
+    }
+}
+
+// Delete the locks and snapshots of the readers that crashed
+fn clean_stale_readers(readers: &Path) {
+    let Ok(entries) = fs::read_dir(readers) else {
+        return
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let pid = path.file_stem()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('-').next())
+            .and_then(|pid| pid.parse().ok());
+
+        if pid.is_some_and(is_process_alive) {
+            continue;
+        }
+
+        debug!("Removing stale reader lock {}", path.display());
+        if let Ok(snapshot) = fs::read_to_string(&path) {
+            if let Err(e) = remove_dir_if_exists(Path::new(snapshot.trim())) {
+                warn!("Error while deleting stale wallet snapshot {}: {}", snapshot, e);
+            }
+        }
+        if let Err(e) = remove_if_exists(&path) {
+            warn!("Error while deleting stale reader lock {}: {}", path.display(), e);
+        }
+    }
+}
+
+// Copy the database files without the lock files
+fn copy_database(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if name_str == READERS_DIR || name_str.starts_with(WRITER_LOCK_FILE) {
+            continue;
+        }
+
+        let target = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_database(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn is_process_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_process_alive(pid: u32) -> bool {
+    process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+// Without a way to check it, the lock is considered as held
+// It must be deleted by hand after a crash
+#[cfg(not(unix))]
+fn is_process_alive(pid: u32) -> bool {
+    let _ = pid;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_wallet_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xelis-wallet-lock-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_writer_lock() {
+        let dir = temp_wallet_dir("writer");
+        let lock = WalletLock::acquire_writer(&dir).unwrap();
+        assert_eq!(lock.get_kind(), LockKind::Writer);
+        assert!(matches!(WalletLock::acquire_writer(&dir), Err(WalletError::WalletLocked(pid)) if pid == process::id()));
+
+        // Readers don't need the writer lock
+        fs::write(dir.join("db"), b"data").unwrap();
+        let reader = WalletLock::acquire_reader(&dir).unwrap();
+        let snapshot = reader.get_snapshot().unwrap().to_path_buf();
+        assert_eq!(fs::read(snapshot.join("db")).unwrap(), b"data");
+        assert!(!snapshot.join(WRITER_LOCK_FILE).exists());
+        drop(reader);
+        assert!(!snapshot.exists());
+
+        drop(lock);
+        assert!(!dir.join(WRITER_LOCK_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_locks() {
+        let dir = temp_wallet_dir("stale");
+        // pid of a process that can't exist
+        fs::write(dir.join(WRITER_LOCK_FILE), u32::MAX.to_string()).unwrap();
+        let lock = WalletLock::acquire_writer(&dir).unwrap();
+        assert_eq!(read_pid(&dir.join(WRITER_LOCK_FILE)), Some(process::id()));
+        drop(lock);
+
+        let readers = dir.join(READERS_DIR);
+        let snapshot = temp_wallet_dir("stale-snapshot");
+        fs::create_dir_all(&readers).unwrap();
+        fs::write(readers.join(format!("{}-0.lock", u32::MAX)), snapshot.to_string_lossy().as_bytes()).unwrap();
+        let reader = WalletLock::acquire_reader(&dir).unwrap();
+        assert!(!snapshot.exists());
+        assert_eq!(fs::read_dir(&readers).unwrap().count(), 1);
+        drop(reader);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,4 +1,6 @@
 use std::{
+    fs::File,
+    io::BufWriter,
     ops::ControlFlow,
     path::Path,
     sync::Arc,
@@ -6,9 +8,13 @@ use std::{
 };
 use anyhow::{Result, Context};
 use fern::colors::Color;
-use log::{error, info};
+use log::{debug, error, info, warn};
+use rand::seq::index::sample;
+use tokio::sync::broadcast::error::RecvError;
 use clap::Parser;
 use xelis_common::{
+    amount::checked_sub,
+    api::{wallet::RebroadcastFailure, DataElement},
     async_handler,
     config::{
         COIN_DECIMALS,
@@ -18,9 +24,13 @@ use xelis_common::{
     crypto::{
         ecdlp,
         Address,
-        Hashable
+        Hashable,
+        Signature
     },
+    json_rpc::UNIX_SOCKET_PREFIX,
+    mnemonics,
     network::Network,
+    time::get_current_time_in_millis,
     prompt::{
         argument::{
             Arg,
@@ -35,7 +45,8 @@ use xelis_common::{
         },
         LogLevel,
         Prompt,
-        PromptError
+        PromptError,
+        ShareablePrompt
     },
     serializer::Serializer,
     transaction::{
@@ -45,14 +56,22 @@ use xelis_common::{
     },
     utils::{
         format_coin,
-        format_xelis
+        format_xelis,
+        from_coin,
+        spawn_task
     }
 };
-#[cfg(feature = "api_server")]
-use xelis_common::utils::spawn_task;
 use xelis_wallet::{
-    wallet::Wallet,
-    config::{DEFAULT_DAEMON_ADDRESS, DIR_PATH}
+    doctor::CheckStatus,
+    draft::{format_duration, parse_duration, TransactionDraft},
+    pending::AutoBumpPolicy,
+    export::{ExportFilter, ExportFormat},
+    hooks::HookEvent,
+    error::WalletError,
+    metadata::AssetDisplay,
+    ownership::OwnershipChallenge,
+    wallet::{Event, Wallet},
+    config::{DEFAULT_DAEMON_ADDRESS, DEFAULT_SYNC_INTERVAL, DEFAULT_DRAFT_EXPIRATION, DEFAULT_HOOK_TIMEOUT, DEFAULT_HOOKS_MAX_CONCURRENT, DIR_PATH, LARGE_TRANSFER_AMOUNT}
 };
 
 #[cfg(feature = "api_server")]
@@ -66,8 +85,8 @@ use {
         wallet::XSWDEvent,
     },
     xelis_common::{
+        api::wallet::ApiKeyPermission,
         rpc_server::RpcRequest,
-        prompt::ShareablePrompt
     },
     anyhow::Error,
     tokio::sync::mpsc::UnboundedReceiver
@@ -95,11 +114,20 @@ pub struct RPCConfig {
 #[command(styles = xelis_common::get_cli_styles())]
 pub struct Config {
     /// Daemon address to use
-    #[clap(long, default_value_t = String::from(DEFAULT_DAEMON_ADDRESS))]
-    daemon_address: String,
+    /// Can be set several times, others addresses are used as failover in the same order
+    #[clap(long, default_value = DEFAULT_DAEMON_ADDRESS)]
+    daemon_address: Vec<String>,
+    /// Unix domain socket of the daemon to use instead of the daemon address
+    /// The daemon must be started with --rpc-unix-socket
+    #[clap(long)]
+    daemon_socket: Option<String>,
     /// Disable online mode
     #[clap(long)]
     offline_mode: bool,
+    /// Interval in seconds between two background syncs
+    /// It is also used to retry the connection when the daemon is unreachable
+    #[clap(long, default_value_t = DEFAULT_SYNC_INTERVAL)]
+    sync_interval_secs: u64,
     /// Set log level
     #[clap(long, value_enum, default_value_t = LogLevel::Info)]
     log_level: LogLevel,
@@ -117,6 +145,14 @@ pub struct Config {
     /// You will not be able to write CLI commands in it or to have an updated prompt
     #[clap(long)]
     disable_interactive_mode: bool,
+    /// Command history file path
+    /// By default, it is saved next to the log file
+    #[clap(long)]
+    history_file: Option<String>,
+    /// Skip the confirmation of destructive commands
+    /// Useful when the commands are sent by a script
+    #[clap(long)]
+    skip_confirmation: bool,
     /// Log filename
     /// 
     /// By default filename is xelis-wallet.log.
@@ -141,12 +177,32 @@ pub struct Config {
     /// Password used to open wallet
     #[clap(long)]
     password: Option<String>,
+    /// Open the wallet in read-only mode
+    /// 
+    /// A snapshot of the wallet is used, so it can be open while another process uses it.
+    /// Syncing and sending transactions are disabled.
+    #[clap(long)]
+    read_only: bool,
     /// Restore wallet using seed
     #[clap(long)]
     seed: Option<String>,
+    /// Language of the seed, by name or index
+    /// 
+    /// Used for the seed of a new wallet (English by default).
+    /// When restoring, the language is detected from the seed if not set.
+    #[clap(long)]
+    seed_language: Option<String>,
     /// Network selected for chain
     #[clap(long, value_enum, default_value_t = Network::Mainnet)]
     network: Network,
+    /// Execute the commands registered with add_hook on wallet events
+    /// 
+    /// Hooks run arbitrary commands on this machine, only enable it if you trust them.
+    #[clap(long)]
+    enable_hooks: bool,
+    /// Maximum number of hooks running at the same time
+    #[clap(long, default_value_t = DEFAULT_HOOKS_MAX_CONCURRENT)]
+    hooks_max_concurrent: usize,
     /// RPC Server configuration
     #[cfg(feature = "api_server")]
     #[structopt(flatten)]
@@ -170,7 +226,7 @@ impl ecdlp::ProgressTableGenerationReportFunction for LogProgressTableGeneration
 #[tokio::main]
 async fn main() -> Result<()> {
     let config: Config = Config::parse();
-    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode)?;
+    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging, config.disable_file_log_date_based, config.disable_log_color, !config.disable_interactive_mode, config.history_file.clone())?;
 
     #[cfg(feature = "api_server")]
     {
@@ -194,7 +250,16 @@ async fn main() -> Result<()> {
         }
     }
 
+    let seed_language = match config.seed_language.as_deref().map(mnemonics::get_language_index).transpose() {
+        Ok(language) => language,
+        Err(e) => {
+            error!("Invalid seed language: {}", e);
+            return Ok(())
+        }
+    };
+
     let command_manager = CommandManager::new(prompt.clone());
+    command_manager.set_skip_confirmation(config.skip_confirmation);
     command_manager.store_in_context(config.network)?;
 
     command_manager.register_default_commands()?;
@@ -209,20 +274,23 @@ async fn main() -> Result<()> {
 
         let precomputed_tables = Wallet::read_or_generate_precomputed_tables(config.precomputed_tables_path, LogProgressTableGenerationReportFunction)?;
         let p = Path::new(&path);
-        let wallet = if p.exists() && p.is_dir() {
+        let wallet = if config.read_only {
+            info!("Opening wallet {} in read-only mode", path);
+            Wallet::open_read_only(path, password, config.network, precomputed_tables)?
+        } else if p.exists() && p.is_dir() {
             info!("Opening wallet {}", path);
             Wallet::open(path, password, config.network, precomputed_tables)?
         } else {
             info!("Creating a new wallet at {}", path);
-            Wallet::create(path, password, config.seed, config.network, precomputed_tables)?
+            Wallet::create(path, password, config.seed, seed_language, config.network, precomputed_tables)?
         };
 
         apply_config(&wallet, #[cfg(feature = "api_server")] &prompt).await;
         setup_wallet_command_manager(wallet, &command_manager).await?;
     } else {
-        command_manager.add_command(Command::new("open", "Open a wallet", CommandHandler::Async(async_handler!(open_wallet))))?;
-        command_manager.add_command(Command::new("create", "Create a new wallet", CommandHandler::Async(async_handler!(create_wallet))))?;
-        command_manager.add_command(Command::new("recover", "Recover a wallet using a seed", CommandHandler::Async(async_handler!(recover_wallet))))?;
+        command_manager.add_command(Command::with_optional_arguments("open", "Open a wallet (use --read-only if it's open elsewhere)", vec![Arg::new("read-only", ArgType::Bool)], CommandHandler::Async(async_handler!(open_wallet))))?;
+        command_manager.add_command(Command::with_optional_arguments("create", "Create a new wallet (seed language can be selected)", vec![Arg::new("language", ArgType::String)], CommandHandler::Async(async_handler!(create_wallet))))?;
+        command_manager.add_command(Command::with_optional_arguments("recover", "Recover a wallet using a seed (language is detected if not set)", vec![Arg::new("language", ArgType::String)], CommandHandler::Async(async_handler!(recover_wallet))).sensitive())?;
 
         // Display available commands
         command_manager.display_commands()?;
@@ -314,11 +382,27 @@ async fn xswd_handle_request_permission(prompt: &ShareablePrompt, app_state: App
 async fn apply_config(wallet: &Arc<Wallet>, #[cfg(feature = "api_server")] prompt: &ShareablePrompt) {
     let config: Config = Config::parse();
 
-    if !config.offline_mode {
-        info!("Trying to connect to daemon at '{}'", config.daemon_address);
-        if let Err(e) = wallet.set_online_mode(&config.daemon_address, true).await {
+    wallet.set_sync_interval(Duration::from_secs(config.sync_interval_secs));
+    if config.enable_hooks {
+        warn!("Hooks are enabled: the registered commands will be executed on wallet events");
+        match wallet.enable_hooks(config.hooks_max_concurrent).await {
+            Ok(count) => info!("{} hook(s) loaded", count),
+            Err(e) => error!("Error while enabling hooks: {}", e)
+        }
+    }
+
+    if wallet.is_read_only() {
+        warn!("Wallet is open in read-only mode: it won't be synced and can't send transactions");
+    } else if !config.offline_mode {
+        let daemon_addresses = match config.daemon_socket {
+            Some(path) => vec![format!("{}{}", UNIX_SOCKET_PREFIX, path)],
+            None => config.daemon_address
+        };
+        info!("Trying to connect to daemon at '{}'", daemon_addresses.join("', '"));
+        if let Err(e) = wallet.set_online_mode_with_endpoints(&daemon_addresses, true).await {
             error!("Couldn't connect to daemon: {}", e);
-            info!("You can activate online mode using 'online_mode [daemon_address]'");
+            info!("Retrying every {} seconds, use 'offline_mode' to stop or 'online_mode [daemon_address]' to use another daemon", config.sync_interval_secs);
+            wallet.set_online_mode_in_background(daemon_addresses, true).await;
         } else {
             info!("Online mode enabled");
         }
@@ -360,25 +444,54 @@ async fn apply_config(wallet: &Arc<Wallet>, #[cfg(feature = "api_server")] promp
 
 // Function to build the CommandManager when a wallet is open
 async fn setup_wallet_command_manager(wallet: Arc<Wallet>, command_manager: &CommandManager) -> Result<(), CommandError> {
+    spawn_task("wallet-events", wallet_events_handler(Arc::clone(&wallet), command_manager.get_prompt().clone()));
+
     // Delete commands for opening a wallet
     command_manager.remove_command("open")?;
     command_manager.remove_command("recover")?;
     command_manager.remove_command("create")?;
 
     // Add wallet commands
-    command_manager.add_command(Command::new("change_password", "Set a new password to open your wallet", CommandHandler::Async(async_handler!(change_password))))?;
-    command_manager.add_command(Command::with_optional_arguments("transfer", "Send asset to a specified address", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer))))?;
+    command_manager.add_command(Command::new("change_password", "Set a new password to open your wallet", CommandHandler::Async(async_handler!(change_password))).sensitive())?;
+    command_manager.add_command(Command::with_optional_arguments("transfer", "Send asset to a specified address (--delay 24h to confirm it later)", vec![Arg::new("asset", ArgType::Hash), Arg::new("delay", ArgType::String), Arg::new("expire", ArgType::String)], CommandHandler::Async(async_handler!(transfer))))?;
     command_manager.add_command(Command::with_optional_arguments("transfer_all", "Send all your asset balance to a specified address", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer_all))))?;
-    command_manager.add_command(Command::with_required_arguments("burn", "Burn amount of asset", vec![Arg::new("asset", ArgType::Hash), Arg::new("amount", ArgType::Number)], CommandHandler::Async(async_handler!(burn))))?;
+    command_manager.add_command(Command::with_arguments("burn", "Burn amount of asset (XELIS by default)", vec![Arg::new("amount", ArgType::String)], vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(burn))))?;
     command_manager.add_command(Command::new("display_address", "Show your wallet address", CommandHandler::Async(async_handler!(display_address))))?;
+    command_manager.add_command(Command::with_optional_arguments("integrated_address", "Generate an address embedding JSON data (invoice id, order number)", vec![Arg::new("data", ArgType::String)], CommandHandler::Async(async_handler!(integrated_address))))?;
     command_manager.add_command(Command::with_optional_arguments("balance", "List all non-zero balances or show the selected one", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(balance))))?;
     command_manager.add_command(Command::with_optional_arguments("history", "Show all your transactions", vec![Arg::new("page", ArgType::Number)], CommandHandler::Async(async_handler!(history))))?;
     command_manager.add_command(Command::with_optional_arguments("online_mode", "Set your wallet in online mode", vec![Arg::new("daemon_address", ArgType::String)], CommandHandler::Async(async_handler!(online_mode))))?;
     command_manager.add_command(Command::new("offline_mode", "Set your wallet in offline mode", CommandHandler::Async(async_handler!(offline_mode))))?;
-    command_manager.add_command(Command::with_optional_arguments("rescan", "Rescan balance and transactions", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(rescan))))?;
-    command_manager.add_command(Command::with_optional_arguments("seed", "Show seed of selected language", vec![Arg::new("language", ArgType::Number)], CommandHandler::Async(async_handler!(seed))))?;
+    command_manager.add_command(Command::with_optional_arguments("rescan", "Rescan balance and transactions", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(rescan))).with_confirmation("Balances and transactions will be deleted and synced again."))?;
+    command_manager.add_command(Command::with_optional_arguments("discover_assets", "Detect new assets owned without a full rescan", vec![Arg::new("since_topoheight", ArgType::Number), Arg::new("scan", ArgType::Bool)], CommandHandler::Async(async_handler!(discover_assets))))?;
+    command_manager.add_command(Command::with_optional_arguments("seed", "Show seed of selected language", vec![Arg::new("language", ArgType::String)], CommandHandler::Async(async_handler!(seed))).sensitive())?;
+    command_manager.add_command(Command::new("verify_seed", "Check that you saved your seed by asking some of its words", CommandHandler::Async(async_handler!(verify_seed))).sensitive())?;
     command_manager.add_command(Command::new("nonce", "Show current nonce", CommandHandler::Async(async_handler!(nonce))))?;
     command_manager.add_command(Command::new("set_nonce", "Set new nonce", CommandHandler::Async(async_handler!(set_nonce))))?;
+    command_manager.add_command(Command::new("repair_pending", "Rebuild pending nonce and balances from the daemon", CommandHandler::Async(async_handler!(repair_pending))))?;
+    command_manager.add_command(Command::with_optional_arguments("doctor", "Run diagnostic checks and print a redacted report (use --include-sensitive to add address and balances)", vec![Arg::new("json", ArgType::Bool), Arg::new("include-sensitive", ArgType::Bool)], CommandHandler::Async(async_handler!(doctor))))?;
+    command_manager.add_command(Command::new("drafts", "List all transaction drafts", CommandHandler::Async(async_handler!(drafts))))?;
+    command_manager.add_command(Command::with_required_arguments("confirm_draft", "Sign and broadcast a draft once its delay elapsed", vec![Arg::new("id", ArgType::Number)], CommandHandler::Async(async_handler!(confirm_draft))))?;
+    command_manager.add_command(Command::with_required_arguments("cancel_draft", "Delete a transaction draft", vec![Arg::new("id", ArgType::Number)], CommandHandler::Async(async_handler!(cancel_draft))))?;
+    command_manager.add_command(Command::new("pending_transactions", "List the transactions built by the wallet and not yet confirmed", CommandHandler::Async(async_handler!(pending_transactions))))?;
+    command_manager.add_command(Command::with_required_arguments("set_auto_bump", "Automatically bump the fee of a pending transaction stuck in mempool", vec![Arg::new("max_fee", ArgType::String), Arg::new("bump_percent", ArgType::Number), Arg::new("interval", ArgType::Number), Arg::new("max_attempts", ArgType::Number)], CommandHandler::Async(async_handler!(set_auto_bump))))?;
+    command_manager.add_command(Command::new("disable_auto_bump", "Disable the automatic fee bump", CommandHandler::Async(async_handler!(disable_auto_bump))))?;
+    command_manager.add_command(Command::with_arguments("add_hook", "Execute a command on a wallet event (incoming_payment, payment_confirmed, sync_completed)", vec![Arg::new("event", ArgType::String)], vec![Arg::new("timeout", ArgType::Number)], CommandHandler::Async(async_handler!(add_hook))))?;
+    command_manager.add_command(Command::with_required_arguments("remove_hook", "Remove a registered hook", vec![Arg::new("id", ArgType::Number)], CommandHandler::Async(async_handler!(remove_hook))))?;
+    command_manager.add_command(Command::new("hooks", "List all registered hooks", CommandHandler::Async(async_handler!(hooks))))?;
+    command_manager.add_command(Command::with_required_arguments("add_contact", "Save an address under a name", vec![Arg::new("name", ArgType::String), Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(add_contact))))?;
+    command_manager.add_command(Command::with_required_arguments("remove_contact", "Remove a saved contact", vec![Arg::new("name", ArgType::String)], CommandHandler::Async(async_handler!(remove_contact))))?;
+    command_manager.add_command(Command::new("contacts", "List all saved contacts", CommandHandler::Async(async_handler!(contacts))))?;
+    command_manager.add_command(Command::with_arguments("verify_contact", "Create a challenge to be signed by the owner of a contact address", vec![Arg::new("name", ArgType::String)], vec![Arg::new("purpose", ArgType::String)], CommandHandler::Async(async_handler!(verify_contact))))?;
+    command_manager.add_command(Command::with_required_arguments("confirm_contact", "Verify the signature of a contact ownership challenge", vec![Arg::new("name", ArgType::String), Arg::new("signature", ArgType::String)], CommandHandler::Async(async_handler!(confirm_contact))))?;
+    command_manager.add_command(Command::new("sign_message", "Sign a message to prove you control your address", CommandHandler::Async(async_handler!(sign_message))))?;
+    command_manager.add_command(Command::new("authorship_key", "Show the key to start xelis-miner with to tag your solo mined blocks", CommandHandler::Async(async_handler!(authorship_key))).sensitive())?;
+    command_manager.add_command(Command::with_required_arguments("prove_mined", "Create a proof that you mined a block tagged with your authorship key", vec![Arg::new("block_hash", ArgType::Hash)], CommandHandler::Async(async_handler!(prove_mined))))?;
+    command_manager.add_command(Command::with_arguments("set_asset_display", "Show the amounts of an asset with fewer decimals or another unit (no option to reset)", vec![Arg::new("asset", ArgType::Hash)], vec![Arg::new("decimals", ArgType::Number), Arg::new("unit", ArgType::String)], CommandHandler::Async(async_handler!(set_asset_display))))?;
+    command_manager.add_command(Command::with_required_arguments("set_note", "Set a note on a transaction (empty to remove it)", vec![Arg::new("hash", ArgType::Hash)], CommandHandler::Async(async_handler!(set_note))))?;
+    command_manager.add_command(Command::with_required_arguments("export_metadata", "Export contacts, notes and settings (no keys) to an encrypted file", vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(export_metadata))))?;
+    command_manager.add_command(Command::with_arguments("export_history", "Export the transactions history to a CSV or JSON file", vec![Arg::new("path", ArgType::String)], vec![Arg::new("format", ArgType::String), Arg::new("min_topoheight", ArgType::Number), Arg::new("max_topoheight", ArgType::Number), Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(export_history))))?;
+    command_manager.add_command(Command::with_required_arguments("import_metadata", "Import and merge an encrypted metadata file", vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(import_metadata))))?;
 
     #[cfg(feature = "api_server")]
     {
@@ -387,12 +500,20 @@ async fn setup_wallet_command_manager(wallet: Arc<Wallet>, command_manager: &Com
             Arg::new("bind_address", ArgType::String),
             Arg::new("username", ArgType::String),
             Arg::new("password", ArgType::String)
-        ], CommandHandler::Async(async_handler!(start_rpc_server))))?;
+        ], CommandHandler::Async(async_handler!(start_rpc_server))).sensitive())?;
 
         command_manager.add_command(Command::new("start_xswd", "Start the XSWD Server",  CommandHandler::Async(async_handler!(start_xswd))))?;
 
         // Stop API Server (RPC or XSWD)
         command_manager.add_command(Command::new("stop_api_server", "Stop the API (XSWD/RPC) Server", CommandHandler::Async(async_handler!(stop_api_server))))?;
+
+        // API keys of the RPC Server
+        command_manager.add_command(Command::with_required_arguments("rpc_add_key", "Create a RPC API key with read, spend or admin permissions", vec![
+            Arg::new("name", ArgType::String),
+            Arg::new("permissions", ArgType::String)
+        ], CommandHandler::Async(async_handler!(rpc_add_key))))?;
+        command_manager.add_command(Command::with_required_arguments("rpc_revoke_key", "Revoke a RPC API key", vec![Arg::new("name", ArgType::String)], CommandHandler::Async(async_handler!(rpc_revoke_key))))?;
+        command_manager.add_command(Command::new("rpc_list_keys", "List all RPC API keys", CommandHandler::Async(async_handler!(rpc_list_keys))))?;
     }
 
     let mut context = command_manager.get_context().lock()?;
@@ -406,48 +527,7 @@ async fn prompt_message_builder(prompt: &Prompt, command_manager: Option<&Comman
     if let Some(manager) = command_manager {
         let context = manager.get_context().lock()?;
         if let Ok(wallet) = context.get::<Arc<Wallet>>() {
-            let network = wallet.get_network();
-
-            let addr_str = {
-                let addr = &wallet.get_address().to_string()[..8];
-                prompt.colorize_str(Color::Yellow, addr)
-            };
-    
-            let storage = wallet.get_storage().read().await;
-            let topoheight_str = format!(
-                "{}: {}",
-                prompt.colorize_str(Color::Yellow, "TopoHeight"),
-                prompt.colorize_string(Color::Green, &format!("{}", storage.get_synced_topoheight().unwrap_or(0)))
-            );
-            let balance = format!(
-                "{}: {}",
-                prompt.colorize_str(Color::Yellow, "Balance"),
-                prompt.colorize_string(Color::Green, &format_xelis(storage.get_plaintext_balance_for(&XELIS_ASSET).await.unwrap_or(0))),
-            );
-            let status = if wallet.is_online().await {
-                prompt.colorize_str(Color::Green, "Online")
-            } else {
-                prompt.colorize_str(Color::Red, "Offline")
-            };
-            let network_str = if !network.is_mainnet() {
-                format!(
-                    "{} ",
-                    prompt.colorize_string(Color::Red, &network.to_string())
-                )
-            } else { "".into() };
-    
-            return Ok(
-                format!(
-                    "{} | {} | {} | {} | {} {}{} ",
-                    prompt.colorize_str(Color::Blue, "XELIS Wallet"),
-                    addr_str,
-                    topoheight_str,
-                    balance,
-                    status,
-                    network_str,
-                    prompt.colorize_str(Color::BrightBlack, ">>")
-                )
-            )
+            return Ok(build_wallet_prompt_message(prompt, wallet).await)
         }
     }
 
@@ -460,8 +540,91 @@ async fn prompt_message_builder(prompt: &Prompt, command_manager: Option<&Comman
     )
 }
 
+// Build the prompt message showing the wallet status
+async fn build_wallet_prompt_message(prompt: &Prompt, wallet: &Wallet) -> String {
+    let network = wallet.get_network();
+
+    let addr_str = {
+        let addr = &wallet.get_address().to_string()[..8];
+        prompt.colorize_str(Color::Yellow, addr)
+    };
+
+    let storage = wallet.get_storage().read().await;
+    let topoheight_str = format!(
+        "{}: {}",
+        prompt.colorize_str(Color::Yellow, "TopoHeight"),
+        prompt.colorize_string(Color::Green, &format!("{}", storage.get_synced_topoheight().unwrap_or(0)))
+    );
+    let balance = format!(
+        "{}: {}",
+        prompt.colorize_str(Color::Yellow, "Balance"),
+        prompt.colorize_string(Color::Green, &format_xelis(storage.get_plaintext_balance_for(&XELIS_ASSET).await.unwrap_or(0))),
+    );
+    let status = if wallet.is_read_only() {
+        prompt.colorize_str(Color::Magenta, "Read-only")
+    } else if wallet.is_online().await {
+        prompt.colorize_str(Color::Green, "Online")
+    } else {
+        prompt.colorize_str(Color::Red, "Offline")
+    };
+    let network_str = if !network.is_mainnet() {
+        format!(
+            "{} ",
+            prompt.colorize_string(Color::Red, &network.to_string())
+        )
+    } else { "".into() };
+
+    format!(
+        "{} | {} | {} | {} | {} {}{} ",
+        prompt.colorize_str(Color::Blue, "XELIS Wallet"),
+        addr_str,
+        topoheight_str,
+        balance,
+        status,
+        network_str,
+        prompt.colorize_str(Color::BrightBlack, ">>")
+    )
+}
+
+// Refresh the prompt as soon as the wallet state changes
+// instead of waiting for the next prompt update
+async fn wallet_events_handler(wallet: Arc<Wallet>, prompt: ShareablePrompt) {
+    let mut receiver = wallet.subscribe_events().await;
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break
+        };
+
+        match event {
+            Event::NewTransaction(tx) => info!("New transaction {} at topoheight {}", tx.hash, tx.topoheight),
+            Event::TransactionRebroadcastFailed(failed) => {
+                match failed.reason {
+                    RebroadcastFailure::MaxAttemptsReached => warn!("Transaction {} was dropped by the daemon and is still not executed after {} rebroadcasts, you may need to send it again", failed.hash, failed.attempts),
+                    RebroadcastFailure::NonceConflict => warn!("Transaction {} is no longer rebroadcasted, another transaction was created with its nonce {}", failed.hash, failed.nonce)
+                }
+                continue
+            },
+            Event::BalanceChanged(_) | Event::NewTopoHeight { .. } | Event::Online | Event::Offline => {},
+            _ => continue
+        };
+
+        let message = build_wallet_prompt_message(&prompt, &wallet).await;
+        if let Err(e) = prompt.update_prompt(message) {
+            debug!("Error while updating prompt: {}", e);
+        }
+    }
+}
+
 // Open a wallet based on the wallet name and its password
-async fn open_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+async fn open_wallet(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let read_only = if arguments.has_argument("read-only") {
+        arguments.get_value("read-only")?.to_bool()?
+    } else {
+        false
+    };
+
     let prompt = manager.get_prompt();
     let name = prompt.read_input("Wallet name: ".into(), false)
         .await.context("Error while reading wallet name")?;
@@ -484,7 +647,11 @@ async fn open_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(),
         let context = manager.get_context().lock()?;
         let network = context.get::<Network>()?;
         let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::open(dir, password, *network, precomputed_tables)?
+        if read_only {
+            Wallet::open_read_only(dir, password, *network, precomputed_tables)?
+        } else {
+            Wallet::open(dir, password, *network, precomputed_tables)?
+        }
     };
 
     manager.message("Wallet sucessfully opened");
@@ -495,9 +662,21 @@ async fn open_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(),
     Ok(())
 }
 
+// Parse the optional seed language argument, by name or index
+fn get_language_argument(arguments: &mut ArgumentManager) -> Result<Option<usize>, CommandError> {
+    if !arguments.has_argument("language") {
+        return Ok(None)
+    }
+
+    let value = arguments.get_value("language")?.to_string_value()?;
+    let language = mnemonics::get_language_index(&value).context("Invalid seed language")?;
+    Ok(Some(language))
+}
+
 // Create a wallet by requesting name, password
-async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+async fn create_wallet(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
     let prompt = manager.get_prompt();
+    let seed_language = get_language_argument(&mut arguments)?;
 
     let name = prompt.read_input("Wallet name: ".into(), false)
         .await.context("Error while reading wallet name")?;
@@ -529,7 +708,7 @@ async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(
         let context = manager.get_context().lock()?;
         let network = context.get::<Network>()?;
         let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::create(dir, password, None, *network, precomputed_tables)?
+        Wallet::create(dir, password, None, seed_language, *network, precomputed_tables)?
     };
  
     manager.message("Wallet sucessfully created");
@@ -537,7 +716,8 @@ async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(
 
     // Display the seed in prompt
     {
-        let seed = wallet.get_seed(0)?; // TODO language index
+        let language = wallet.get_seed_language().await?;
+        let seed = wallet.get_seed(language)?;
         prompt.read_input(format!("Seed: {}\r\nPress ENTER to continue", seed), false)
             .await.context("Error while displaying seed")?;
     }
@@ -548,17 +728,26 @@ async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(
 }
 
 // Recover a wallet by requesting its seed, name and password
-async fn recover_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+async fn recover_wallet(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
     let prompt = manager.get_prompt();
+    let seed_language = get_language_argument(&mut arguments)?;
 
     let seed = prompt.read_input("Seed: ".into(), false)
         .await.context("Error while reading seed")?;
 
-    let words_count = seed.split_whitespace().count();
-    if words_count != 25 && words_count != 24 {
-        manager.error("Seed must be 24 or 25 (checksum) words long");
-        return Ok(())
-    }
+    // Verify the seed before asking anything else
+    let words = mnemonics::split_seed(&seed);
+    let result = match seed_language {
+        Some(language) => mnemonics::words_to_key_with_language(&words, language).map(|_| language),
+        None => mnemonics::words_to_key_and_language(&words).map(|(_, language)| language)
+    };
+    match result {
+        Ok(language) => manager.message(format!("Seed language: {}", mnemonics::LANGUAGES[language].get_name())),
+        Err(e) => {
+            manager.error(format!("Invalid seed: {}", e));
+            return Ok(())
+        }
+    };
 
     let name = prompt.read_input("Wallet name: ".into(), false)
         .await.context("Error while reading wallet name")?;
@@ -591,7 +780,7 @@ async fn recover_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<
         let context = manager.get_context().lock()?;
         let network = context.get::<Network>()?;
         let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::create(dir, password, Some(seed), *network, precomputed_tables)?
+        Wallet::create(dir, password, Some(seed), seed_language, *network, precomputed_tables)?
     };
 
     manager.message("Wallet sucessfully recovered");
@@ -624,7 +813,21 @@ async fn change_password(manager: &CommandManager, _: ArgumentManager) -> Result
 }
 
 // Create a new transfer to a specified address
-async fn transfer(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// With a delay, only a draft is saved and must be confirmed once the delay elapsed
+async fn transfer(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let delay = if arguments.has_argument("delay") {
+        Some(parse_duration(&arguments.get_value("delay")?.to_string_value()?)
+            .map_err(|e| CommandError::InvalidArgument(e.to_string()))?)
+    } else {
+        None
+    };
+    let expiration = if arguments.has_argument("expire") {
+        parse_duration(&arguments.get_value("expire")?.to_string_value()?)
+            .map_err(|e| CommandError::InvalidArgument(e.to_string()))?
+    } else {
+        DEFAULT_DRAFT_EXPIRATION
+    };
+
     let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
@@ -635,6 +838,9 @@ async fn transfer(manager: &CommandManager, _: ArgumentManager) -> Result<(), Co
         false
     ).await.context("Error while reading address")?;
     let address = Address::from_string(&str_address).context("Invalid address")?;
+    if !address.validate_for_network(wallet.get_network()).is_valid(true) {
+        return Err(CommandError::InvalidArgument(format!("Address is not valid on {}", wallet.get_network())))
+    }
 
     let asset = prompt.read_hash(
         prompt.colorize_str(Color::Green, "Asset (default XELIS): ")
@@ -655,14 +861,10 @@ async fn transfer(manager: &CommandManager, _: ArgumentManager) -> Result<(), Co
     ).await.context("Error while reading amount")?;
 
     let amount = (float_amount * 10u32.pow(decimals as u32) as f64) as u64;
-    manager.message(format!("Sending {} of {} to {}", format_coin(amount, decimals), asset, address.to_string()));
-
-    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
-        manager.message("Transaction has been aborted");
-        return Ok(())
-    }
-
-    manager.message("Building transaction...");
+    let amount_str = wallet.get_storage().read().await.format_asset_amount(&asset, amount).unwrap_or_else(|_| format_coin(amount, decimals));
+    manager.message(format!("Sending {} ({}) to {}", amount_str, asset, address.to_string()));
+    warn_unverified_destination(manager, wallet, &address, amount, decimals).await?;
+    warn_new_account(manager, wallet, &address).await;
 
     let transfer = TransferBuilder {
         destination: address,
@@ -670,8 +872,24 @@ async fn transfer(manager: &CommandManager, _: ArgumentManager) -> Result<(), Co
         asset,
         extra_data: None
     };
-    let tx = wallet.create_transaction(TransactionTypeBuilder::Transfers(vec![transfer]), FeeBuilder::default()).await
-        .context("Error while creating transaction")?;
+    let tx_type = TransactionTypeBuilder::Transfers(vec![transfer]);
+
+    if let Some(delay) = delay {
+        if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+            manager.message("Transaction has been aborted");
+            return Ok(())
+        }
+
+        let draft = wallet.create_draft(tx_type, FeeBuilder::default(), delay, expiration).await
+            .context("Error while saving draft")?;
+        manager.message(format!("Draft #{} saved, confirm it in {} using 'confirm_draft {}'", draft.id, format_duration(delay), draft.id));
+        return Ok(())
+    }
+
+    let Some(tx) = review_transaction(manager, wallet, tx_type).await? else {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    };
 
     broadcast_tx(wallet, manager, tx).await;
     Ok(())
@@ -690,6 +908,9 @@ async fn transfer_all(manager: &CommandManager, mut args: ArgumentManager) -> Re
         false
     ).await.context("Error while reading address")?;
     let address = Address::from_string(&str_address).context("Invalid address")?;
+    if !address.validate_for_network(wallet.get_network()).is_valid(true) {
+        return Err(CommandError::InvalidArgument(format!("Address is not valid on {}", wallet.get_network())))
+    }
 
     let mut asset = args.get_value("asset").and_then(|v| v.to_hash()).ok();
     if asset.is_none() {
@@ -716,15 +937,13 @@ async fn transfer_all(manager: &CommandManager, mut args: ArgumentManager) -> Re
     let estimated_fees = wallet.estimate_fees(tx_type.clone()).await.context("Error while estimating fees")?;
 
     if asset == XELIS_ASSET {
-        amount -= estimated_fees;
+        amount = checked_sub(amount, estimated_fees).context("Not enough funds to pay the fees")?;
     }
 
-    manager.message(format!("Sending {} of {} to {} (fees: {})", format_coin(amount, decimals), asset, address.to_string(), format_xelis(estimated_fees)));
-    
-    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
-        manager.message("Transaction has been aborted");
-        return Ok(())
-    }
+    let amount_str = wallet.get_storage().read().await.format_asset_amount(&asset, amount).unwrap_or_else(|_| format_coin(amount, decimals));
+    manager.message(format!("Sending {} ({}) to {} (fees: {})", amount_str, asset, address.to_string(), format_xelis(estimated_fees)));
+    warn_unverified_destination(manager, wallet, &address, amount, decimals).await?;
+    warn_new_account(manager, wallet, &address).await;
 
     let transfer = TransferBuilder {
         destination: address,
@@ -734,32 +953,41 @@ async fn transfer_all(manager: &CommandManager, mut args: ArgumentManager) -> Re
     };
     let tx_type = TransactionTypeBuilder::Transfers(vec![transfer]);
 
-    manager.message("Building transaction...");
-
-    let tx = wallet.create_transaction(tx_type, FeeBuilder::default()).await
-        .context("Error while creating transaction")?;
+    let Some(tx) = review_transaction(manager, wallet, tx_type).await? else {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    };
 
     broadcast_tx(wallet, manager, tx).await;
     Ok(())
 }
 
 async fn burn(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let amount = arguments.get_value("amount")?.to_number()?;
-    let asset = arguments.get_value("asset")?.to_hash()?;
+    let str_amount = arguments.get_value("amount")?.to_string_value()?;
+    let asset = if arguments.has_argument("asset") {
+        arguments.get_value("asset")?.to_hash()?
+    } else {
+        XELIS_ASSET
+    };
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    {
+    let decimals = {
         let storage = wallet.get_storage().read().await;
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
+        storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
+    };
 
-        manager.message(format!("Burning {} of {}", format_coin(amount, decimals), asset));
-    }
-    let payload = BurnPayload {
-        amount,
-        asset
+    // amount is given in human units, the display override doesn't apply here
+    let amount = from_coin(str_amount, decimals).context("Invalid amount")?;
+    let fees = wallet.estimate_fees(TransactionTypeBuilder::Burn(BurnPayload { asset: asset.clone(), amount })).await
+        .context("Error while estimating fees")?;
+
+    manager.warn("Burned funds are lost forever and can't be recovered");
+    let amount_str = wallet.get_storage().read().await.format_asset_amount(&asset, amount).unwrap_or_else(|_| format_coin(amount, decimals));
+    manager.message(format!("Burning {} ({}) (fees: {})", amount_str, asset, format_xelis(fees)));
+    let Some(tx) = review_transaction(manager, wallet, TransactionTypeBuilder::Burn(BurnPayload { asset, amount })).await? else {
+        manager.message("Burn has been aborted");
+        return Ok(())
     };
-    let tx = wallet.create_transaction(TransactionTypeBuilder::Burn(payload), FeeBuilder::Multiplier(1f64)).await
-        .context("Error while creating transaction")?;
 
     broadcast_tx(wallet, manager, tx).await;
     Ok(())
@@ -773,6 +1001,27 @@ async fn display_address(manager: &CommandManager, _: ArgumentManager) -> Result
     Ok(())
 }
 
+// Generate an integrated address, the JSON data is asked if it's not provided
+// as it may contain spaces
+async fn integrated_address(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let json = if arguments.has_argument("data") {
+        arguments.get_value("data")?.to_string_value()?
+    } else {
+        prompt.read_input(prompt.colorize_str(Color::Green, "Data (JSON): "), false).await
+            .context("Error while reading data")?
+    };
+
+    let data: DataElement = serde_json::from_str(&json).context("Invalid JSON data")?;
+    let address = wallet.generate_integrated_address(data)
+        .map_err(|e| CommandError::InvalidArgument(e.to_string()))?;
+    manager.message(format!("Integrated address: {}", address));
+    Ok(())
+}
+
 // Show current balance for specified asset or list all non-zero balances
 async fn balance(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
@@ -782,13 +1031,12 @@ async fn balance(manager: &CommandManager, mut arguments: ArgumentManager) -> Re
     if arguments.has_argument("asset") {
         let asset = arguments.get_value("asset")?.to_hash()?;
         let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(0);
-        manager.message(format!("Balance for asset {}: {}", asset, format_coin(balance, decimals)));
+        manager.message(format!("Balance for asset {}: {}", asset, storage.format_asset_amount(&asset, balance)?));
     } else {
-        for (asset, decimals) in storage.get_assets_with_decimals().await? {
+        for (asset, _) in storage.get_assets_with_decimals().await? {
             let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
             if balance > 0 {
-                manager.message(format!("Balance for asset {}: {}", asset, format_coin(balance, decimals)));
+                manager.message(format!("Balance for asset {}: {}", asset, storage.format_asset_amount(&asset, balance)?));
             }
         }
     }
@@ -862,12 +1110,12 @@ async fn online_mode(manager: &CommandManager, mut arguments: ArgumentManager) -
 async fn offline_mode(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    if !wallet.is_online().await {
-        manager.error("Wallet is already offline");
-    } else {
-        wallet.set_offline_mode().await.context("Error on offline mode")?;
-        manager.message("Wallet is now offline");
-    }
+    // It can also stop a connection retried in background
+    match wallet.set_offline_mode().await {
+        Ok(()) => manager.message("Wallet is now offline"),
+        Err(WalletError::NotOnlineMode) => manager.error("Wallet is already offline"),
+        Err(e) => return Err(anyhow::Error::from(e).context("Error on offline mode").into())
+    };
     Ok(())
 }
 
@@ -886,6 +1134,39 @@ async fn rescan(manager: &CommandManager, mut arguments: ArgumentManager) -> Res
     Ok(())
 }
 
+async fn discover_assets(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let since_topoheight = if arguments.has_argument("since_topoheight") {
+        arguments.get_value("since_topoheight")?.to_number()?
+    } else {
+        0
+    };
+    let scan = if arguments.has_argument("scan") {
+        arguments.get_value("scan")?.to_bool()?
+    } else {
+        false
+    };
+
+    let assets = wallet.discover_assets(since_topoheight, scan).await.context("Error while discovering assets")?;
+    if assets.is_empty() {
+        manager.message("No new asset found");
+        return Ok(())
+    }
+
+    let storage = wallet.get_storage().read().await;
+    for asset in assets {
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        manager.message(format!("New asset {}: {}", asset, storage.format_asset_amount(&asset, balance)?));
+    }
+
+    if !scan {
+        manager.message("Transactions of these assets are pending, use the scan option to fetch them");
+    }
+
+    Ok(())
+}
+
 async fn seed(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
@@ -896,13 +1177,12 @@ async fn seed(manager: &CommandManager, mut arguments: ArgumentManager) -> Resul
     // check if password is valid
     wallet.is_valid_password(password).await?;
 
-    let language = if arguments.has_argument("language") {
-        arguments.get_value("language")?.to_number()?
-    } else {
-        0
+    let language = match get_language_argument(&mut arguments)? {
+        Some(language) => language,
+        None => wallet.get_seed_language().await?
     };
 
-    let seed = wallet.get_seed(language as usize)?;
+    let seed = wallet.get_seed(language)?;
     prompt.read_input(
         prompt.colorize_string(Color::Green, &format!("Seed: {}\r\nPress ENTER to continue", seed)),
         false
@@ -910,6 +1190,31 @@ async fn seed(manager: &CommandManager, mut arguments: ArgumentManager) -> Resul
     Ok(())
 }
 
+// Ask 3 random words of the seed to verify that it was saved
+async fn verify_seed(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let prompt = manager.get_prompt();
+
+    let language = wallet.get_seed_language().await?;
+    let words = wallet.get_seed_words(language)?;
+    let mut positions = sample(&mut rand::thread_rng(), words.len(), 3).into_vec();
+    positions.sort();
+
+    for position in positions {
+        let word = prompt.read_input(format!("Word #{}: ", position + 1), true)
+            .await.context("Error while reading seed word")?;
+
+        if word.trim().to_lowercase() != words[position].to_lowercase() {
+            manager.error(format!("Word #{} is incorrect, please check your seed backup", position + 1));
+            return Ok(())
+        }
+    }
+
+    manager.message("Seed verified successfully");
+    Ok(())
+}
+
 async fn nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
@@ -938,6 +1243,557 @@ async fn set_nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), C
     Ok(())
 }
 
+// Discard local pending TXs unknown by the daemon and reset the projected balances
+async fn repair_pending(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let report = wallet.repair_pending().await.context("Error while repairing pending state")?;
+
+    manager.message(format!("Confirmed nonce: {}", report.confirmed_nonce));
+    if report.is_clean() {
+        manager.message("Pending state is already in sync with the daemon");
+        return Ok(())
+    }
+
+    manager.message(format!("Unconfirmed nonce: {} -> {}", report.previous_unconfirmed_nonce, report.unconfirmed_nonce));
+    if report.discarded_nonces() > 0 {
+        manager.warn(format!("{} pending nonce(s) discarded", report.discarded_nonces()));
+    }
+
+    if let Some(hash) = report.discarded_tx.as_ref() {
+        manager.warn(format!("Transaction {} was neither in mempool nor executed and has been discarded", hash));
+    }
+
+    let storage = wallet.get_storage().read().await;
+    for (asset, previous, new) in report.changed_balances.iter() {
+        let decimals = storage.get_asset_decimals(asset).unwrap_or(COIN_DECIMALS);
+        let display = |amount: &Option<u64>| amount.map(|v| format_coin(v, decimals)).unwrap_or_else(|| "confirmed".to_owned());
+        manager.message(format!("Projected balance for {}: {} -> {}", asset, display(previous), display(new)));
+    }
+
+    Ok(())
+}
+
+async fn doctor(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let json = if arguments.has_argument("json") {
+        arguments.get_value("json")?.to_bool()?
+    } else {
+        false
+    };
+    let include_sensitive = if arguments.has_argument("include-sensitive") {
+        arguments.get_value("include-sensitive")?.to_bool()?
+    } else {
+        false
+    };
+
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let report = wallet.doctor(include_sensitive).await.context("Error while running diagnostics")?;
+
+    if json {
+        manager.message(serde_json::to_string_pretty(&report).context("Error while serializing report")?);
+        return Ok(())
+    }
+
+    for line in report.to_lines() {
+        manager.message(line);
+    }
+
+    match report.status() {
+        CheckStatus::Pass => manager.message("All checks passed"),
+        CheckStatus::Warn => manager.warn("Some checks have warnings"),
+        CheckStatus::Fail => manager.error("Some checks failed")
+    };
+
+    Ok(())
+}
+
+// Describe what a draft will send
+async fn display_draft(wallet: &Wallet, manager: &CommandManager, draft: &TransactionDraft) {
+    let now = get_current_time_in_millis();
+    let status = if now < draft.unlock_at {
+        format!("unlocks in {}", format_duration(draft.unlock_at - now))
+    } else {
+        format!("ready, expires in {}", format_duration(draft.expires_at.saturating_sub(now)))
+    };
+
+    let storage = wallet.get_storage().read().await;
+    match &draft.tx_type {
+        TransactionTypeBuilder::Transfers(transfers) => {
+            manager.message(format!("Draft #{} ({}):", draft.id, status));
+            for transfer in transfers {
+                let decimals = storage.get_asset_decimals(&transfer.asset).unwrap_or(COIN_DECIMALS);
+                manager.message(format!("- {} of {} to {}", format_coin(transfer.amount, decimals), transfer.asset, transfer.destination));
+            }
+        },
+        TransactionTypeBuilder::Burn(payload) => {
+            let decimals = storage.get_asset_decimals(&payload.asset).unwrap_or(COIN_DECIMALS);
+            manager.message(format!("Draft #{} ({}): burn {} of {}", draft.id, status, format_coin(payload.amount, decimals), payload.asset));
+        }
+    }
+}
+
+async fn drafts(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let drafts = wallet.get_drafts().await.context("Error while loading drafts")?;
+    if drafts.is_empty() {
+        manager.message("No draft saved");
+        return Ok(())
+    }
+
+    for draft in drafts.iter() {
+        display_draft(wallet, manager, draft).await;
+    }
+    Ok(())
+}
+
+// Sign the draft with the current nonce and balances
+async fn confirm_draft(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let id = arguments.get_value("id")?.to_number()?;
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let drafts = wallet.get_drafts().await.context("Error while loading drafts")?;
+    let Some(draft) = drafts.iter().find(|draft| draft.id == id) else {
+        manager.error(format!("No draft #{}", id));
+        return Ok(())
+    };
+
+    display_draft(wallet, manager, draft).await;
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Draft has not been confirmed");
+        return Ok(())
+    }
+
+    manager.message("Building transaction...");
+    let tx = wallet.confirm_draft(id).await
+        .context("Error while confirming draft")?;
+
+    broadcast_tx(wallet, manager, tx).await;
+    Ok(())
+}
+
+async fn cancel_draft(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let id = arguments.get_value("id")?.to_number()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.cancel_draft(id).await.context("Error while cancelling draft")?;
+    manager.message(format!("Draft #{} has been deleted", id));
+    Ok(())
+}
+
+async fn pending_transactions(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let storage = wallet.get_storage().read().await;
+    match storage.get_auto_bump_policy()? {
+        Some(policy) => manager.message(format!("Auto bump: +{}% every {} blocks, up to {} attempts and a fee of {}", policy.bump_percent, policy.check_interval, policy.max_attempts, format_xelis(policy.max_fee))),
+        None => manager.message("Auto bump: disabled")
+    };
+
+    let pending = storage.get_pending_transactions()?;
+    if pending.is_empty() {
+        manager.message("No pending transaction");
+        return Ok(())
+    }
+
+    for tx in pending.iter() {
+        manager.message(format!("Nonce {}: {} with fee {} built at topoheight {}, {} bump(s)", tx.nonce, tx.hash, format_xelis(tx.fee), tx.topoheight, tx.attempts));
+        for replaced in tx.replaced.iter() {
+            manager.message(format!("- replaced {}", replaced));
+        }
+    }
+    Ok(())
+}
+
+async fn set_auto_bump(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let max_fee = from_coin(arguments.get_value("max_fee")?.to_string_value()?, COIN_DECIMALS).context("Invalid max fee")?;
+    let policy = AutoBumpPolicy {
+        max_fee,
+        bump_percent: arguments.get_value("bump_percent")?.to_number()?,
+        check_interval: arguments.get_value("interval")?.to_number()?,
+        max_attempts: arguments.get_value("max_attempts")?.to_number()? as u32
+    };
+    policy.verify().context("Error while verifying policy")?;
+
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.get_storage().write().await.set_auto_bump_policy(Some(&policy))?;
+    manager.message(format!("Pending transactions not mined after {} blocks will be bumped by {}% up to a fee of {}", policy.check_interval, policy.bump_percent, format_xelis(policy.max_fee)));
+    Ok(())
+}
+
+async fn disable_auto_bump(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.get_storage().write().await.set_auto_bump_policy(None)?;
+    manager.message("Auto bump disabled");
+    Ok(())
+}
+
+async fn add_contact(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+    let address = Address::from_string(&arguments.get_value("address")?.to_string_value()?).context("Invalid address")?;
+    if !address.validate_for_network(wallet.get_network()).is_network_valid {
+        return Err(CommandError::InvalidArgument(format!("Address is not on {}", wallet.get_network())))
+    }
+
+    let mut storage = wallet.get_storage().write().await;
+    let mut metadata = storage.get_metadata()?;
+    metadata.set_contact(name.clone(), Some(address.to_string()), get_current_time_in_millis());
+    storage.set_metadata(&metadata)?;
+    manager.message(format!("Contact {} has been saved", name));
+    Ok(())
+}
+
+async fn add_hook(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let event: HookEvent = arguments.get_value("event")?.to_string_value()?.parse().context("Invalid event")?;
+    let timeout = if arguments.has_argument("timeout") {
+        arguments.get_value("timeout")?.to_number()?
+    } else {
+        DEFAULT_HOOK_TIMEOUT
+    };
+
+    manager.warn("The command will be executed on this machine with the event data as JSON on stdin, without any shell");
+    let command_line = prompt.read_input(prompt.colorize_str(Color::Green, "Command: "), false)
+        .await
+        .context("Error while reading command")?;
+
+    let message = format!("Execute '{}' on each {} event ? (Y/N): ", command_line.trim(), event);
+    if prompt.read_valid_str_value(prompt.colorize_string(Color::Yellow, &message), vec!["y", "n"]).await? != "y" {
+        manager.message("Hook not added");
+        return Ok(())
+    }
+
+    let hook = wallet.add_hook(event, &command_line, timeout).await.context("Error while adding hook")?;
+    manager.message(format!("Hook #{} added for {} with a timeout of {}s", hook.id, hook.event, hook.timeout));
+    if !wallet.are_hooks_enabled().await {
+        manager.warn("Hooks are disabled, restart the wallet with --enable-hooks to execute them");
+    }
+    Ok(())
+}
+
+async fn remove_hook(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let id = arguments.get_value("id")?.to_number()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.remove_hook(id).await.context("Error while removing hook")?;
+    manager.message(format!("Hook #{} has been removed", id));
+    Ok(())
+}
+
+async fn hooks(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    if !wallet.are_hooks_enabled().await {
+        manager.warn("Hooks are disabled, use --enable-hooks to execute them");
+    }
+
+    let hooks = wallet.get_storage().read().await.get_hooks()?;
+    if hooks.is_empty() {
+        manager.message("No hook registered");
+        return Ok(())
+    }
+
+    for hook in hooks {
+        manager.message(format!("#{} on {}: {} (timeout {}s)", hook.id, hook.event, hook.get_command_line(), hook.timeout));
+    }
+    Ok(())
+}
+
+async fn remove_contact(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+
+    let mut storage = wallet.get_storage().write().await;
+    let mut metadata = storage.get_metadata()?;
+    if !metadata.get_contacts().any(|(n, _)| *n == name) {
+        manager.error(format!("No contact named {}", name));
+        return Ok(())
+    }
+
+    // Keep the removal so it is synced to other devices
+    metadata.set_contact(name.clone(), None, get_current_time_in_millis());
+    storage.set_metadata(&metadata)?;
+    manager.message(format!("Contact {} has been removed", name));
+    Ok(())
+}
+
+// Only the display of the amounts is changed, never the amounts sent or exported
+async fn set_asset_display(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let asset = arguments.get_value("asset")?.to_hash()?;
+    let decimals = if arguments.has_argument("decimals") {
+        let decimals = arguments.get_value("decimals")?.to_number()?;
+        Some(u8::try_from(decimals).map_err(|_| CommandError::InvalidArgument(format!("Invalid decimals: {}", decimals)))?)
+    } else {
+        None
+    };
+    let unit = if arguments.has_argument("unit") {
+        Some(arguments.get_value("unit")?.to_string_value()?)
+    } else {
+        None
+    };
+
+    let mut storage = wallet.get_storage().write().await;
+    let display = if decimals.is_none() && unit.is_none() {
+        None
+    } else {
+        Some(AssetDisplay { decimals, unit })
+    };
+    let reset = display.is_none();
+
+    let mut metadata = storage.get_metadata()?;
+    metadata.set_asset_display(asset.clone(), display, get_current_time_in_millis());
+    storage.set_metadata(&metadata)?;
+
+    if reset {
+        manager.message(format!("Display override of {} has been removed", asset));
+    } else {
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        manager.message(format!("Balance for asset {} is now shown as {}", asset, storage.format_asset_amount(&asset, balance)?));
+    }
+    Ok(())
+}
+
+async fn contacts(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let storage = wallet.get_storage().read().await;
+    let metadata = storage.get_metadata()?;
+
+    let mut contacts: Vec<_> = metadata.get_contacts().collect();
+    if contacts.is_empty() {
+        manager.message("No contact saved");
+        return Ok(())
+    }
+
+    contacts.sort();
+    let now = get_current_time_in_millis();
+    for (name, address) in contacts {
+        let status = match metadata.get_verification(name) {
+            Some(verification) if verification.is_valid_for(address, now) => format!(" (verified for {} at {})", verification.purpose, verification.verified_at),
+            Some(_) => " (verification expired)".to_owned(),
+            None => String::new()
+        };
+        manager.message(format!("{}: {}{}", name, address, status));
+    }
+    Ok(())
+}
+
+// Create a challenge for the owner of a contact address
+async fn verify_contact(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+    let purpose = if arguments.has_argument("purpose") {
+        arguments.get_value("purpose")?.to_string_value()?
+    } else {
+        "transfers".to_owned()
+    };
+
+    let mut storage = wallet.get_storage().write().await;
+    let mut metadata = storage.get_metadata()?;
+    let Some(address) = metadata.get_contacts().find(|(n, _)| **n == name).map(|(_, address)| address.clone()) else {
+        manager.error(format!("No contact named {}", name));
+        return Ok(())
+    };
+
+    let address = Address::from_string(&address).context("Invalid contact address")?;
+    let now = get_current_time_in_millis();
+    let challenge = OwnershipChallenge::new(&address, purpose, now);
+    manager.message("Ask the owner to sign the following message using the sign_message command:");
+    manager.message(challenge.get_message());
+    manager.message(format!("Then verify the signature using 'confirm_contact {} <signature>'", name));
+
+    metadata.set_ownership_challenge(name, Some(challenge), now);
+    storage.set_metadata(&metadata)?;
+    Ok(())
+}
+
+// Verify the signature of the challenge and record the contact as verified
+async fn confirm_contact(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+    let signature = Signature::from_hex(arguments.get_value("signature")?.to_string_value()?)
+        .map_err(|_| CommandError::InvalidArgument("Invalid signature".to_owned()))?;
+
+    let mut storage = wallet.get_storage().write().await;
+    let mut metadata = storage.get_metadata()?;
+    let challenge = metadata.get_ownership_challenge(&name)
+        .ok_or_else(|| WalletError::NoOwnershipChallenge(name.clone()))
+        .map_err(|e| CommandError::InvalidArgument(e.to_string()))?;
+
+    let now = get_current_time_in_millis();
+    let verification = challenge.verify(&signature, now)
+        .map_err(|e| CommandError::InvalidArgument(e.to_string()))?;
+
+    metadata.set_ownership_challenge(name.clone(), None, now);
+    metadata.set_verification(name.clone(), Some(verification), now);
+    storage.set_metadata(&metadata)?;
+    manager.message(format!("Contact {} has been verified", name));
+    Ok(())
+}
+
+// Sign a message with the wallet key, used to answer ownership challenges
+async fn sign_message(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let message = prompt.read_input(prompt.colorize_str(Color::Green, "Message: "), false).await
+        .context("Error while reading message")?;
+    let signature = wallet.sign_data(message.as_bytes());
+    manager.message(format!("Signature: {}", signature.to_hex()));
+    Ok(())
+}
+
+async fn authorship_key(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    manager.message(format!("Authorship key: {}", wallet.get_authorship_key().to_hex()));
+    manager.message("Start xelis-miner with --authorship-key to tag the blocks found for this wallet");
+    manager.warn("Keep it private, anyone knowing it can link all your tagged blocks together");
+    Ok(())
+}
+
+async fn prove_mined(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let hash = arguments.get_value("block_hash")?.to_hash()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let proof = wallet.prove_mined_block(&hash).await.context("Error while proving block authorship")?;
+    manager.message(format!("Block {} at height {} is proven as mined by {}", proof.get_block_hash(), proof.get_height(), wallet.get_address()));
+    manager.message(format!("Proof: {}", proof.to_hex()));
+    Ok(())
+}
+
+// Warn before a large transfer to an address not verified
+async fn warn_unverified_destination(manager: &CommandManager, wallet: &Wallet, address: &Address, amount: u64, decimals: u8) -> Result<(), CommandError> {
+    if amount < LARGE_TRANSFER_AMOUNT.saturating_mul(10u64.saturating_pow(decimals as u32)) {
+        return Ok(())
+    }
+
+    let storage = wallet.get_storage().read().await;
+    let metadata = storage.get_metadata()?;
+    if !metadata.is_verified_address(&address.to_string(), get_current_time_in_millis()) {
+        manager.warn(format!("{} is not a verified contact, its ownership can be checked with verify_contact", address));
+    }
+    Ok(())
+}
+
+// Warn when the destination has never been seen on chain, the fee includes its account creation
+// Nothing is shown if the daemon can't be reached, the transfer can still be reviewed
+async fn warn_new_account(manager: &CommandManager, wallet: &Wallet, address: &Address) {
+    if let Ok(Some(true)) = wallet.is_new_account(address).await {
+        manager.warn(format!("{} has never been seen on chain, an extra fee is paid to create its account", address));
+    }
+}
+
+async fn set_note(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let hash = arguments.get_value("hash")?.to_hash()?;
+
+    let note = prompt.read_input(prompt.colorize_str(Color::Green, "Note: "), false)
+        .await
+        .context("Error while reading note")?;
+
+    let mut storage = wallet.get_storage().write().await;
+    let mut metadata = storage.get_metadata()?;
+    let note = if note.is_empty() { None } else { Some(note) };
+    let removed = note.is_none();
+    metadata.set_note(hash.clone(), note, get_current_time_in_millis());
+    storage.set_metadata(&metadata)?;
+
+    if removed {
+        manager.message(format!("Note removed from transaction {}", hash));
+    } else {
+        manager.message(format!("Note saved on transaction {}", hash));
+    }
+    Ok(())
+}
+
+async fn export_metadata(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let path = arguments.get_value("path")?.to_string_value()?;
+
+    let passphrase = prompt.read_input(prompt.colorize_str(Color::BrightRed, "Passphrase: "), true)
+        .await
+        .context("Error while reading passphrase")?;
+    let confirm = prompt.read_input(prompt.colorize_str(Color::BrightRed, "Confirm Passphrase: "), true)
+        .await
+        .context("Error while reading passphrase")?;
+
+    if passphrase != confirm {
+        manager.error("Passphrases don't match");
+        return Ok(())
+    }
+
+    wallet.export_metadata(&path, passphrase).await.context("Error while exporting metadata")?;
+    manager.message(format!("Metadata exported to {}", path));
+    Ok(())
+}
+
+async fn export_history(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let format = if arguments.has_argument("format") {
+        arguments.get_value("format")?.to_string_value()?.parse::<ExportFormat>().context("Invalid format")?
+    } else {
+        ExportFormat::default()
+    };
+
+    let mut filter = ExportFilter::default();
+    if arguments.has_argument("min_topoheight") {
+        filter.min_topoheight = Some(arguments.get_value("min_topoheight")?.to_number()?);
+    }
+    if arguments.has_argument("max_topoheight") {
+        filter.max_topoheight = Some(arguments.get_value("max_topoheight")?.to_number()?);
+    }
+    if arguments.has_argument("asset") {
+        filter.asset = Some(arguments.get_value("asset")?.to_hash()?);
+    }
+
+    let file = File::create(&path).context("Error while creating history file")?;
+    let rows = wallet.export_transactions(BufWriter::new(file), format, &filter).await.context("Error while exporting history")?;
+    manager.message(format!("{} rows exported to {}", rows, path));
+    Ok(())
+}
+
+async fn import_metadata(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let path = arguments.get_value("path")?.to_string_value()?;
+
+    let passphrase = prompt.read_input(prompt.colorize_str(Color::BrightRed, "Passphrase: "), true)
+        .await
+        .context("Error while reading passphrase")?;
+
+    let report = wallet.import_metadata(&path, passphrase).await.context("Error while importing metadata")?;
+    manager.message(format!("Metadata imported: {} added, {} updated", report.added, report.updated));
+    for conflict in report.conflicts.iter() {
+        manager.warn(format!("Conflict on {}", conflict));
+    }
+    Ok(())
+}
+
 #[cfg(feature = "api_server")]
 async fn stop_api_server(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
@@ -965,6 +1821,47 @@ async fn start_rpc_server(manager: &CommandManager, mut arguments: ArgumentManag
     Ok(())
 }
 
+#[cfg(feature = "api_server")]
+async fn rpc_add_key(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+    let permission: ApiKeyPermission = arguments.get_value("permissions")?.to_string_value()?
+        .parse()
+        .map_err(|e: &str| CommandError::InvalidArgument(e.to_owned()))?;
+
+    let key = wallet.add_api_key(name.clone(), permission).await.context("Error while creating API key")?;
+    manager.message(format!("API key {} created with {} permission: {}", name, permission, key));
+    manager.warn("Save it now, it can't be displayed again");
+    Ok(())
+}
+
+#[cfg(feature = "api_server")]
+async fn rpc_revoke_key(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let name = arguments.get_value("name")?.to_string_value()?;
+    wallet.revoke_api_key(&name).await.context("Error while revoking API key")?;
+    manager.message(format!("API key {} has been revoked", name));
+    Ok(())
+}
+
+#[cfg(feature = "api_server")]
+async fn rpc_list_keys(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let keys = wallet.get_api_keys().await.context("Error while retrieving API keys")?;
+    if keys.is_empty() {
+        manager.message("No API key created");
+        return Ok(())
+    }
+
+    for entry in keys {
+        manager.message(format!("{}: {}", entry.name, entry.permission));
+    }
+    Ok(())
+}
+
 #[cfg(feature = "api_server")]
 async fn start_xswd(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
@@ -983,16 +1880,52 @@ async fn start_xswd(manager: &CommandManager, _: ArgumentManager) -> Result<(),
 
 // broadcast tx if possible
 // submit_transaction increase the local nonce in storage in case of success
+// Build the transaction and show what it reveals before asking to send it
+// Changes are applied to the storage only once confirmed
+async fn review_transaction(manager: &CommandManager, wallet: &Wallet, tx_type: TransactionTypeBuilder) -> Result<Option<Transaction>, CommandError> {
+    manager.message("Building transaction...");
+    let (mut state, tx, report, topoheight) = {
+        let storage = wallet.get_storage().read().await;
+        let fee = FeeBuilder::default();
+        let (state, tx) = match tx_type {
+            TransactionTypeBuilder::Burn(payload) => wallet.create_burn_transaction_with_storage(&storage, payload.asset, payload.amount, fee).await,
+            tx_type => wallet.create_transaction_with_storage(&storage, tx_type, fee).await
+        }.context("Error while creating transaction")?;
+        let report = wallet.get_privacy_report(&storage, &tx).await.context("Error while analyzing transaction")?;
+        (state, tx, report, storage.get_synced_topoheight().context("Error while retrieving synced topoheight")?)
+    };
+
+    manager.message("Privacy review:");
+    for line in report.to_lines() {
+        manager.message(format!("- {}", line));
+    }
+
+    if !manager.get_prompt().ask_confirmation().await.context("Error while confirming action")? {
+        return Ok(None)
+    }
+
+    // The storage is not locked during the review, build it again if it changed
+    let mut storage = wallet.get_storage().write().await;
+    if storage.get_unconfirmed_nonce() != tx.get_nonce() || storage.get_synced_topoheight().context("Error while retrieving synced topoheight")? != topoheight {
+        manager.error("Wallet state changed during the review, please try again");
+        return Ok(None)
+    }
+
+    state.apply_changes(&mut storage).await.context("Error while applying transaction")?;
+    Ok(Some(tx))
+}
+
 async fn broadcast_tx(wallet: &Wallet, manager: &CommandManager, tx: Transaction) {
     let tx_hash = tx.hash();
     manager.message(format!("Transaction hash: {}", tx_hash));
 
     if wallet.is_online().await {
-        if let Err(e) = wallet.submit_transaction(&tx).await {
-            manager.error(format!("Couldn't submit transaction: {}", e));
-            manager.error("You can try to rescan your balance with the command 'rescan'");
-        } else {
-            manager.message("Transaction submitted successfully!");
+        match wallet.submit_transaction(&tx).await {
+            Ok(hash) => manager.message(format!("Transaction {} submitted successfully!", hash)),
+            Err(e) => {
+                manager.error(format!("Couldn't submit transaction: {}", e));
+                manager.error("You can try to rescan your balance with the command 'rescan'");
+            }
         }
     } else {
         manager.warn("You are currently offline, transaction cannot be send automatically. Please send it manually to the network.");
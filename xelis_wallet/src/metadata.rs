@@ -0,0 +1,348 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    hash::Hash as StdHash
+};
+use anyhow::{Context, Result};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use xelis_common::{
+    crypto::Hash,
+    network::Network,
+    time::TimestampMillis
+};
+use crate::{
+    cipher::Cipher,
+    config::SALT_SIZE,
+    error::WalletError,
+    ownership::{AddressVerification, OwnershipChallenge},
+    wallet::hash_password
+};
+
+// Version of the sync file format
+pub const METADATA_FILE_VERSION: u8 = 1;
+
+// A metadata item with the time it was last changed
+// A removed item is kept with no value so the removal is synced too
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataEntry<T> {
+    pub value: Option<T>,
+    pub updated_at: TimestampMillis
+}
+
+// Non-sensitive wallet metadata that can be synced between devices
+// It never contains any key or seed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WalletMetadata {
+    // contact name => address
+    #[serde(default)]
+    pub contacts: HashMap<String, MetadataEntry<String>>,
+    // transaction hash => note
+    #[serde(default)]
+    pub notes: HashMap<Hash, MetadataEntry<String>>,
+    // asset => is visible
+    #[serde(default)]
+    pub asset_visibility: HashMap<Hash, MetadataEntry<bool>>,
+    // template name => transaction template
+    #[serde(default)]
+    pub templates: HashMap<String, MetadataEntry<Value>>,
+    // asset => confirmations required before considering a transaction as final
+    #[serde(default)]
+    pub confirmation_policies: HashMap<Hash, MetadataEntry<u64>>,
+    // contact name => ownership challenge waiting for its signature
+    #[serde(default)]
+    pub ownership_challenges: HashMap<String, MetadataEntry<OwnershipChallenge>>,
+    // contact name => verified ownership of its address
+    #[serde(default)]
+    pub verifications: HashMap<String, MetadataEntry<AddressVerification>>,
+    // asset => how its amounts are displayed
+    #[serde(default)]
+    pub asset_displays: HashMap<Hash, MetadataEntry<AssetDisplay>>
+}
+
+// Display override of an asset, it only changes how amounts are shown
+// never the amounts sent in a transaction or exported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetDisplay {
+    // decimals shown, the amount is rounded if it's below the asset decimals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    // unit shown after the amount instead of the asset name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>
+}
+
+// An item modified on both sides with different values
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataConflict {
+    // category of the item (contacts, notes...)
+    pub category: &'static str,
+    pub key: String,
+    // true if the imported value was kept
+    pub imported: bool
+}
+
+impl Display for MetadataConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} '{}': kept {} value", self.category, self.key, if self.imported { "imported" } else { "local" })
+    }
+}
+
+// Result of a merge
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    // items that were not present locally
+    pub added: usize,
+    // existing items replaced by a newer imported value
+    pub updated: usize,
+    pub conflicts: Vec<MetadataConflict>
+}
+
+// Merge one category, the newest entry of each item wins
+fn merge_entries<K, V>(category: &'static str, local: &mut HashMap<K, MetadataEntry<V>>, imported: HashMap<K, MetadataEntry<V>>, report: &mut MergeReport)
+where
+    K: StdHash + Eq + Display,
+    V: PartialEq
+{
+    for (key, entry) in imported {
+        match local.get_mut(&key) {
+            Some(current) => {
+                if current.value == entry.value {
+                    // same value, only keep the latest timestamp
+                    current.updated_at = current.updated_at.max(entry.updated_at);
+                    continue
+                }
+
+                let newer = entry.updated_at > current.updated_at;
+                report.conflicts.push(MetadataConflict {
+                    category,
+                    key: key.to_string(),
+                    imported: newer
+                });
+
+                if newer {
+                    *current = entry;
+                    report.updated += 1;
+                }
+            },
+            None => {
+                local.insert(key, entry);
+                report.added += 1;
+            }
+        }
+    }
+}
+
+// Set or remove an item
+fn set_entry<K: StdHash + Eq, V>(entries: &mut HashMap<K, MetadataEntry<V>>, key: K, value: Option<V>, now: TimestampMillis) {
+    entries.insert(key, MetadataEntry { value, updated_at: now });
+}
+
+// Collect only the items that are not removed
+fn values<'a, K, V>(entries: &'a HashMap<K, MetadataEntry<V>>) -> impl Iterator<Item = (&'a K, &'a V)> {
+    entries.iter().filter_map(|(k, e)| e.value.as_ref().map(|v| (k, v)))
+}
+
+impl WalletMetadata {
+    pub fn set_contact(&mut self, name: String, address: Option<String>, now: TimestampMillis) {
+        set_entry(&mut self.contacts, name, address, now);
+    }
+
+    pub fn get_contacts(&self) -> impl Iterator<Item = (&String, &String)> {
+        values(&self.contacts)
+    }
+
+    pub fn set_note(&mut self, hash: Hash, note: Option<String>, now: TimestampMillis) {
+        set_entry(&mut self.notes, hash, note, now);
+    }
+
+    pub fn get_note(&self, hash: &Hash) -> Option<&String> {
+        self.notes.get(hash).and_then(|e| e.value.as_ref())
+    }
+
+    pub fn set_asset_visibility(&mut self, asset: Hash, visible: Option<bool>, now: TimestampMillis) {
+        set_entry(&mut self.asset_visibility, asset, visible, now);
+    }
+
+    // Assets are visible by default
+    pub fn is_asset_visible(&self, asset: &Hash) -> bool {
+        self.asset_visibility.get(asset).and_then(|e| e.value).unwrap_or(true)
+    }
+
+    pub fn set_template(&mut self, name: String, template: Option<Value>, now: TimestampMillis) {
+        set_entry(&mut self.templates, name, template, now);
+    }
+
+    pub fn get_templates(&self) -> impl Iterator<Item = (&String, &Value)> {
+        values(&self.templates)
+    }
+
+    pub fn set_confirmation_policy(&mut self, asset: Hash, confirmations: Option<u64>, now: TimestampMillis) {
+        set_entry(&mut self.confirmation_policies, asset, confirmations, now);
+    }
+
+    pub fn get_confirmation_policy(&self, asset: &Hash) -> Option<u64> {
+        self.confirmation_policies.get(asset).and_then(|e| e.value)
+    }
+
+    pub fn set_ownership_challenge(&mut self, name: String, challenge: Option<OwnershipChallenge>, now: TimestampMillis) {
+        set_entry(&mut self.ownership_challenges, name, challenge, now);
+    }
+
+    pub fn get_ownership_challenge(&self, name: &String) -> Option<&OwnershipChallenge> {
+        self.ownership_challenges.get(name).and_then(|e| e.value.as_ref())
+    }
+
+    pub fn set_verification(&mut self, name: String, verification: Option<AddressVerification>, now: TimestampMillis) {
+        set_entry(&mut self.verifications, name, verification, now);
+    }
+
+    pub fn get_verification(&self, name: &String) -> Option<&AddressVerification> {
+        self.verifications.get(name).and_then(|e| e.value.as_ref())
+    }
+
+    pub fn set_asset_display(&mut self, asset: Hash, display: Option<AssetDisplay>, now: TimestampMillis) {
+        set_entry(&mut self.asset_displays, asset, display, now);
+    }
+
+    pub fn get_asset_display(&self, asset: &Hash) -> Option<&AssetDisplay> {
+        self.asset_displays.get(asset).and_then(|e| e.value.as_ref())
+    }
+
+    // Check if this address belongs to a contact whose ownership is verified
+    pub fn is_verified_address(&self, address: &str, now: TimestampMillis) -> bool {
+        self.get_contacts()
+            .filter(|(_, contact)| contact.as_str() == address)
+            .any(|(name, _)| self.get_verification(name).is_some_and(|v| v.is_valid_for(address, now)))
+    }
+
+    // Merge the imported metadata into this one
+    pub fn merge(&mut self, imported: WalletMetadata) -> MergeReport {
+        let mut report = MergeReport::default();
+        merge_entries("contact", &mut self.contacts, imported.contacts, &mut report);
+        merge_entries("note", &mut self.notes, imported.notes, &mut report);
+        merge_entries("asset visibility", &mut self.asset_visibility, imported.asset_visibility, &mut report);
+        merge_entries("template", &mut self.templates, imported.templates, &mut report);
+        merge_entries("confirmation policy", &mut self.confirmation_policies, imported.confirmation_policies, &mut report);
+        merge_entries("ownership challenge", &mut self.ownership_challenges, imported.ownership_challenges, &mut report);
+        merge_entries("verification", &mut self.verifications, imported.verifications, &mut report);
+        merge_entries("asset display", &mut self.asset_displays, imported.asset_displays, &mut report);
+        report
+    }
+}
+
+// Encrypted content of the sync file
+#[derive(Serialize, Deserialize)]
+struct MetadataPayload {
+    network: Network,
+    metadata: WalletMetadata
+}
+
+// Sync file written on disk
+// Only the salt is in plaintext, the network is encrypted with the metadata
+#[derive(Serialize, Deserialize)]
+struct MetadataFile {
+    version: u8,
+    salt: String,
+    data: String
+}
+
+// Encrypt the metadata using a key derived from the passphrase
+pub fn encrypt_metadata(metadata: &WalletMetadata, network: Network, passphrase: String) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+
+    let key = hash_password(passphrase, &salt)?;
+    let cipher = Cipher::new(&key, None)?;
+
+    let payload = serde_json::to_vec(&MetadataPayload { network, metadata: metadata.clone() })?;
+    let file = MetadataFile {
+        version: METADATA_FILE_VERSION,
+        salt: hex::encode(salt),
+        data: hex::encode(cipher.encrypt_value(&payload)?)
+    };
+
+    Ok(serde_json::to_vec_pretty(&file)?)
+}
+
+// Decrypt a sync file, it must have been exported on the same network
+pub fn decrypt_metadata(content: &[u8], network: Network, passphrase: String) -> Result<WalletMetadata> {
+    let file: MetadataFile = serde_json::from_slice(content).context("Invalid metadata file")?;
+    if file.version != METADATA_FILE_VERSION {
+        return Err(WalletError::UnsupportedMetadataVersion(file.version).into())
+    }
+
+    let salt = hex::decode(&file.salt).context("Invalid salt in metadata file")?;
+    let key = hash_password(passphrase, &salt)?;
+    let cipher = Cipher::new(&key, None)?;
+
+    let data = hex::decode(&file.data).context("Invalid data in metadata file")?;
+    let decrypted = cipher.decrypt_value(&data).map_err(|_| WalletError::InvalidMetadataPassphrase)?;
+    let payload: MetadataPayload = serde_json::from_slice(&decrypted).context("Invalid metadata content")?;
+    if payload.network != network {
+        return Err(WalletError::MetadataNetworkMismatch(payload.network, network).into())
+    }
+
+    Ok(payload.metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(byte: u8) -> Hash {
+        Hash::new([byte; 32])
+    }
+
+    fn sample() -> WalletMetadata {
+        let mut metadata = WalletMetadata::default();
+        metadata.set_contact("alice".into(), Some("xel:alice".into()), 10);
+        metadata.set_note(asset(1), Some("rent".into()), 10);
+        metadata.set_asset_visibility(asset(2), Some(false), 10);
+        metadata.set_template("salary".into(), Some(serde_json::json!({ "amount": 100 })), 10);
+        metadata.set_confirmation_policy(asset(3), Some(8), 10);
+        metadata
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let metadata = sample();
+        let file = encrypt_metadata(&metadata, Network::Testnet, "passphrase".into()).unwrap();
+        let decrypted = decrypt_metadata(&file, Network::Testnet, "passphrase".into()).unwrap();
+        assert_eq!(decrypted, metadata);
+
+        // Wrong passphrase or another network is refused
+        assert!(decrypt_metadata(&file, Network::Testnet, "wrong".into()).is_err());
+        assert!(decrypt_metadata(&file, Network::Mainnet, "passphrase".into()).is_err());
+    }
+
+    #[test]
+    fn test_merge_newer_wins() {
+        let mut local = sample();
+        let mut imported = sample();
+        // changed on both sides, imported is newer
+        imported.set_contact("alice".into(), Some("xel:alice2".into()), 20);
+        // changed on both sides, local is newer
+        local.set_note(asset(1), Some("local rent".into()), 30);
+        imported.set_note(asset(1), Some("imported rent".into()), 20);
+        // removed remotely
+        imported.set_template("salary".into(), None, 20);
+        // only on the imported side
+        imported.set_contact("bob".into(), Some("xel:bob".into()), 5);
+
+        let report = local.merge(imported);
+        assert_eq!(report.added, 1);
+        assert_eq!(report.updated, 2);
+        assert_eq!(report.conflicts.len(), 3);
+        assert!(report.conflicts.contains(&MetadataConflict { category: "note", key: asset(1).to_string(), imported: false }));
+
+        let contacts: HashMap<_, _> = local.get_contacts().collect();
+        assert_eq!(contacts.get(&"alice".to_owned()), Some(&&"xel:alice2".to_owned()));
+        assert_eq!(contacts.get(&"bob".to_owned()), Some(&&"xel:bob".to_owned()));
+        assert_eq!(local.get_note(&asset(1)), Some(&"local rent".to_owned()));
+        assert_eq!(local.get_templates().count(), 0);
+        assert!(!local.is_asset_visible(&asset(2)));
+        assert_eq!(local.get_confirmation_policy(&asset(3)), Some(8));
+    }
+}
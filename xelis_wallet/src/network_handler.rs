@@ -8,8 +8,12 @@ use std::{
 };
 use thiserror::Error;
 use anyhow::Error;
-use log::{debug, error, trace, warn};
-use tokio::{sync::Mutex, task::JoinHandle, time::sleep};
+use log::{debug, error, info, trace, warn};
+use tokio::{
+    sync::Mutex,
+    task::JoinHandle,
+    time::{interval_at, sleep, Instant, MissedTickBehavior}
+};
 use xelis_common::{
     account::CiphertextCache,
     api::{
@@ -17,28 +21,38 @@ use xelis_common::{
             BlockResponse,
             NewBlockEvent
         },
-        wallet::BalanceChanged,
+        wallet::{
+            BalanceChanged,
+            RebroadcastFailure,
+            TransactionBumped,
+            TransactionRebroadcastFailed,
+            TransactionRebroadcasted
+        },
         RPCTransactionType
     },
     asset::AssetWithData,
     config::XELIS_ASSET,
+    json_rpc::{get_unix_socket_path, ConnectionState},
     crypto::{
         elgamal::Ciphertext,
         Address,
-        Hash
+        Hash,
+        Hashable
     },
     serializer::Serializer,
+    time::get_current_time_in_millis,
     utils::{sanitize_daemon_address, spawn_task}
 };
 use crate::{
-    config::AUTO_RECONNECT_INTERVAL,
-    daemon_api::DaemonAPI,
+    config::{AUTO_RECONNECT_INTERVAL, MAX_REBROADCAST_ATTEMPTS, REBROADCAST_GRACE_PERIOD},
+    daemon_api::{DaemonAPI, TransactionExecutor},
     entry::{
         EntryData,
         TransactionEntry,
         TransferIn,
         TransferOut
     },
+    pending::{RebroadcastAction, SubmittedTransaction},
     storage::Balance,
     wallet::{
         Event, Wallet
@@ -77,8 +91,23 @@ impl NetworkHandler {
     // Create a new network handler with a wallet and a daemon address
     // This will create itself a DaemonAPI and verify if connection is possible
     pub async fn new<S: ToString>(wallet: Arc<Wallet>, daemon_address: S) -> Result<SharedNetworkHandler, Error> {
-        let s = daemon_address.to_string();
-        let api = DaemonAPI::new(format!("{}/json_rpc", sanitize_daemon_address(s.as_str()))).await?;
+        Self::with_endpoints(wallet, &[daemon_address]).await
+    }
+
+    // Create a new network handler using several daemon addresses
+    // The first reachable one is used and the others are used as failover
+    pub async fn with_endpoints<S: ToString>(wallet: Arc<Wallet>, daemon_addresses: &[S]) -> Result<SharedNetworkHandler, Error> {
+        // A Unix domain socket serves the JSON-RPC API directly
+        let endpoints = daemon_addresses.iter()
+            .map(|address| {
+                let address = address.to_string();
+                match get_unix_socket_path(&address) {
+                    Some(_) => address,
+                    None => format!("{}/json_rpc", sanitize_daemon_address(&address))
+                }
+            })
+            .collect();
+        let api = DaemonAPI::with_endpoints(endpoints).await?;
         Self::with_api(wallet, Arc::new(api)).await
     }
 
@@ -163,6 +192,12 @@ impl NetworkHandler {
                 handle.await??;
             } else {
                 handle.abort();
+                // Wait until it is really stopped, a sync may be running
+                if let Err(e) = handle.await {
+                    if !e.is_cancelled() {
+                        debug!("Network handler task failed: {}", e);
+                    }
+                }
 
                 // Notify that we are offline
                 self.wallet.propagate_event(Event::Offline).await;
@@ -199,6 +234,7 @@ impl NetworkHandler {
     // Returns assets that changed and returns the highest nonce if we send a transaction
     async fn process_block(&self, address: &Address, block: BlockResponse, topoheight: u64) -> Result<Option<(HashSet<Hash>, Option<u64>)>, Error> {
         let block_hash = block.hash.into_owned();
+        let block_timestamp = block.timestamp;
         debug!("Processing block {} at topoheight {}", block_hash, topoheight);
 
         if block.miner.is_mainnet() != self.wallet.get_network().is_mainnet() {
@@ -231,6 +267,7 @@ impl NetworkHandler {
                         // Store the changes for history
                         if !changes_stored {
                             storage.add_topoheight_to_changes(topoheight, &block_hash)?;
+                            storage.set_block_timestamp(topoheight, block_timestamp)?;
                             changes_stored = true;
                         }
                         true
@@ -339,10 +376,22 @@ impl NetworkHandler {
                     continue;
                 }
 
-                // New transaction entry that may be linked to us, check if TX was executed
-                if !self.api.is_tx_executed_in_block(&tx.hash, &block_hash).await? {
-                    warn!("Transaction {} was a good candidate but was not executed in block {}, skipping", tx.hash, block_hash);
-                    continue;
+                // New transaction entry that may be linked to us, check in which block the TX was executed
+                // A TX included in several blocks is only saved in the one executing it
+                match self.api.get_transaction_executor(&tx.hash).await? {
+                    TransactionExecutor::Executed(executor) => if *executor.block_hash != block_hash {
+                        debug!("Transaction {} is executed in block {} at topoheight {}, skipping it in block {}", tx.hash, executor.block_hash, executor.block_topoheight, block_hash);
+                        continue;
+                    },
+                    TransactionExecutor::NotExecuted => {
+                        warn!("Transaction {} was a good candidate but was not executed, skipping", tx.hash);
+                        continue;
+                    },
+                    // Older daemon, only check if it's executed in this block
+                    TransactionExecutor::Unsupported => if !self.api.is_tx_executed_in_block(&tx.hash, &block_hash).await? {
+                        debug!("Transaction {} is not executed in block {}, skipping", tx.hash, block_hash);
+                        continue;
+                    }
                 }
 
                 // Find the highest nonce
@@ -357,6 +406,7 @@ impl NetworkHandler {
                     // Store the changes for history
                     if !changes_stored {
                         storage.add_topoheight_to_changes(topoheight, &block_hash)?;
+                        storage.set_block_timestamp(topoheight, block_timestamp)?;
                         changes_stored = true;
                     }
                 }
@@ -455,6 +505,62 @@ impl NetworkHandler {
         }
     }
 
+    // Detect the assets owned by our account on the daemon but unknown locally (airdrops for example)
+    // Their data and balance are stored, and a placeholder entry is added to the history until they are scanned
+    // If scan is requested, only the blocks above since_topoheight where these assets changed are processed
+    // Returns the newly discovered assets
+    pub async fn discover_assets(&self, since_topoheight: u64, scan: bool) -> Result<Vec<Hash>, Error> {
+        trace!("discover assets since {}", since_topoheight);
+        let address = self.wallet.get_address();
+        let assets = self.api.get_account_assets(&address).await?;
+
+        let mut discovered = Vec::new();
+        for asset in assets {
+            if {
+                let storage = self.wallet.get_storage().read().await;
+                storage.contains_asset(&asset).await?
+            } {
+                continue;
+            }
+
+            debug!("Discovered new asset {}", asset);
+            let data = self.api.get_asset(&asset).await?;
+            let result = self.api.get_balance(&address, &asset).await?;
+            let mut ciphertext = result.version.take_balance();
+            let value = Arc::clone(&self.wallet).decrypt_ciphertext(ciphertext.decompressed()?.clone()).await?;
+
+            {
+                let mut storage = self.wallet.get_storage().write().await;
+                storage.save_discovered_asset(&asset, &data, result.topoheight, Balance::new(value, ciphertext)).await?;
+            }
+
+            self.wallet.propagate_event(Event::NewAsset(AssetWithData::new(asset.clone(), data))).await;
+            self.wallet.propagate_event(Event::BalanceChanged(BalanceChanged {
+                asset: asset.clone(),
+                balance: value
+            })).await;
+
+            discovered.push(asset);
+        }
+
+        if scan {
+            // Targeted scan: only the versions of the discovered assets are followed
+            let mut topoheight_processed = HashSet::new();
+            let mut highest_nonce = None;
+            for asset in &discovered {
+                match self.get_balance_and_transactions(&mut topoheight_processed, &address, asset, since_topoheight, true, &mut highest_nonce).await {
+                    Ok(()) => {
+                        let mut storage = self.wallet.get_storage().write().await;
+                        storage.delete_transaction(asset)?;
+                    },
+                    Err(e) => error!("Error while scanning discovered asset {}: {}", asset, e)
+                }
+            }
+        }
+
+        Ok(discovered)
+    }
+
     // Locate the last topoheight valid for syncing, this support soft forks, DAG reorgs, etc...
     // Balances and nonce may be outdated, but we will sync them later
     // All transactions / changes above the last valid topoheight will be deleted
@@ -636,6 +742,9 @@ impl NetworkHandler {
                 {
                     let mut storage = self.wallet.get_storage().write().await;
                     storage.add_asset(&asset, data.get_decimals()).await?;
+                    if let Some(metadata) = data.get_metadata() {
+                        storage.set_asset_metadata(&asset, metadata)?;
+                    }
                 }
 
                 // New asset added to the wallet, inform listeners
@@ -712,6 +821,8 @@ impl NetworkHandler {
     // then sync again the head state
     async fn sync(&self, address: &Address, event: Option<NewBlockEvent>) -> Result<(), Error> {
         trace!("sync");
+        // Never run two syncs at the same time
+        let _guard = self.wallet.get_sync_lock().lock().await;
         // First, locate the last topoheight valid for syncing
         let (daemon_topoheight, daemon_block_hash, wallet_topoheight, sync_back) = self.locate_sync_topoheight_and_clean().await?;
         debug!("Daemon topoheight: {}, wallet topoheight: {}, sync back: {}", daemon_topoheight, wallet_topoheight, sync_back);
@@ -767,10 +878,208 @@ impl NetworkHandler {
         // Propagate the event
         self.wallet.propagate_event(Event::NewTopoHeight { topoheight: daemon_topoheight }).await;
         debug!("Synced to topoheight {}", daemon_topoheight);
+
+        if let Err(e) = self.bump_pending_transactions(address, daemon_topoheight).await {
+            warn!("Error while bumping pending transactions: {}", e);
+        }
+
+        if let Err(e) = self.rebroadcast_submitted_transactions(address).await {
+            warn!("Error while rebroadcasting submitted transactions: {}", e);
+        }
+
         Ok(())
     }
 
-    // Runs an infinite loop to sync on each new block added in chain
+    // Forget the confirmed pending transactions and apply the auto bump policy if enabled
+    // Only our last TX can be bumped, the next ones would be invalid as they spend its output balance
+    async fn bump_pending_transactions(&self, address: &Address, topoheight: u64) -> Result<(), Error> {
+        trace!("bump pending transactions");
+        let mut storage = self.wallet.get_storage().write().await;
+        let confirmed_nonce = storage.get_nonce().unwrap_or(0);
+        let mut pending = storage.get_pending_transactions()?;
+        let len = pending.len();
+        pending.retain(|tx| tx.nonce >= confirmed_nonce);
+        if pending.len() != len {
+            storage.set_pending_transactions(&pending)?;
+        }
+
+        let Some(policy) = storage.get_auto_bump_policy()? else {
+            return Ok(())
+        };
+
+        let Some(tx) = pending.last() else {
+            return Ok(())
+        };
+
+        // Another TX was built on top of it, or it got discarded
+        if storage.get_tx_cache().map_or(true, |cache| cache.last_tx_hash_created != tx.hash) {
+            return Ok(())
+        }
+
+        let Some(fee) = tx.get_bump_fee(&policy, topoheight) else {
+            return Ok(())
+        };
+
+        // Only bump a TX that the daemon is still waiting to mine
+        // A TX returned in hex without being broadcasted is never sent by us
+        let in_mempool = match self.api.get_mempool_cache(address).await {
+            Ok(cache) => cache.is_some_and(|cache| cache.txs.contains(&tx.hash)),
+            Err(e) => {
+                warn!("Couldn't fetch the mempool cache of our account: {}", e);
+                false
+            }
+        };
+
+        if !in_mempool {
+            debug!("Pending transaction {} is not in mempool, not bumping it", tx.hash);
+            return Ok(())
+        }
+
+        let (mut state, transaction) = self.wallet.create_bumped_transaction(tx, fee, topoheight).await?;
+        let hash = transaction.hash();
+        if let Err(e) = self.api.submit_transaction(&transaction).await {
+            warn!("Couldn't bump transaction {} to fee {}: {}", tx.hash, fee, e);
+            // Count the attempt so we don't retry at each block
+            let mut failed = tx.clone();
+            failed.attempts += 1;
+            failed.topoheight = topoheight;
+            storage.add_pending_transaction(failed)?;
+            return Ok(())
+        }
+
+        state.apply_changes(&mut storage).await?;
+        // Only the new version can be executed, track it instead of the replaced one
+        storage.remove_submitted_transaction(&tx.hash)?;
+        storage.add_submitted_transaction(SubmittedTransaction::new(transaction, get_current_time_in_millis()))?;
+        drop(storage);
+
+        info!("Transaction {} replaced by {} with fee {}", tx.hash, hash, fee);
+        self.wallet.propagate_event(Event::TransactionBumped(TransactionBumped {
+            hash,
+            replaced: tx.hash.clone(),
+            nonce: tx.nonce,
+            fee,
+            previous_fee: tx.fee,
+            attempts: tx.attempts + 1
+        })).await;
+
+        Ok(())
+    }
+
+    // Submit again our transactions dropped by the daemon (mempool eviction, restart...)
+    // A transaction is sent again only once it's missing from both chain and mempool for the grace period
+    // A newer transaction using the same nonce cancels it, as only one of them can be executed
+    async fn rebroadcast_submitted_transactions(&self, address: &Address) -> Result<(), Error> {
+        trace!("rebroadcast submitted transactions");
+        let mut storage = self.wallet.get_storage().write().await;
+        let submitted = storage.get_submitted_transactions()?;
+        if submitted.is_empty() {
+            return Ok(())
+        }
+
+        let confirmed_nonce = storage.get_nonce().unwrap_or(0);
+        let mempool = match self.api.get_mempool_cache(address).await {
+            Ok(cache) => cache.map(|cache| cache.txs).unwrap_or_default(),
+            Err(e) => {
+                warn!("Couldn't fetch the mempool cache of our account: {}", e);
+                Vec::new()
+            }
+        };
+
+        let now = get_current_time_in_millis();
+        let mut kept = Vec::with_capacity(submitted.len());
+        let mut events = Vec::new();
+        for tx in submitted.iter() {
+            // Kept to be reported until its nonce is used on chain
+            if tx.failed {
+                if tx.nonce >= confirmed_nonce {
+                    kept.push(tx.clone());
+                }
+                continue
+            }
+
+            if self.api.get_transaction_executor_hash(&tx.hash).await?.is_some() {
+                debug!("Submitted transaction {} is executed", tx.hash);
+                continue
+            }
+
+            let conflicting = tx.find_conflict(&submitted).map(|conflict| conflict.hash.clone());
+            if conflicting.is_some() || tx.nonce < confirmed_nonce {
+                warn!("Transaction {} won't be rebroadcasted, its nonce {} is used by another transaction", tx.hash, tx.nonce);
+                events.push(Event::TransactionRebroadcastFailed(TransactionRebroadcastFailed {
+                    hash: tx.hash.clone(),
+                    nonce: tx.nonce,
+                    attempts: tx.rebroadcasts,
+                    reason: RebroadcastFailure::NonceConflict,
+                    conflicting
+                }));
+                continue
+            }
+
+            let mut tx = tx.clone();
+            if !mempool.contains(&tx.hash) {
+                match tx.get_rebroadcast_action(now, REBROADCAST_GRACE_PERIOD, MAX_REBROADCAST_ATTEMPTS) {
+                    RebroadcastAction::Wait => {},
+                    RebroadcastAction::Fail => {
+                        warn!("Transaction {} is still not executed after {} rebroadcasts, giving up", tx.hash, tx.rebroadcasts);
+                        tx.failed = true;
+                        events.push(Event::TransactionRebroadcastFailed(TransactionRebroadcastFailed {
+                            hash: tx.hash.clone(),
+                            nonce: tx.nonce,
+                            attempts: tx.rebroadcasts,
+                            reason: RebroadcastFailure::MaxAttemptsReached,
+                            conflicting: None
+                        }));
+                    },
+                    RebroadcastAction::Rebroadcast => {
+                        // Count the attempt even if rejected so we don't retry at each sync
+                        tx.rebroadcasts += 1;
+                        tx.last_submitted_at = now;
+                        let res = match tx.get_transaction() {
+                            Ok(transaction) => self.api.submit_transaction(&transaction).await.map(|_| ()),
+                            Err(e) => Err(e.into())
+                        };
+
+                        match res {
+                            Ok(()) => {
+                                info!("Transaction {} was dropped by the daemon and got rebroadcasted", tx.hash);
+                                events.push(Event::TransactionRebroadcasted(TransactionRebroadcasted {
+                                    hash: tx.hash.clone(),
+                                    nonce: tx.nonce,
+                                    attempts: tx.rebroadcasts
+                                }));
+                            },
+                            Err(e) => warn!("Couldn't rebroadcast transaction {}: {}", tx.hash, e)
+                        }
+                    }
+                }
+            }
+            kept.push(tx);
+        }
+
+        storage.set_submitted_transactions(&kept)?;
+        drop(storage);
+
+        for event in events {
+            self.wallet.propagate_event(event).await;
+        }
+
+        Ok(())
+    }
+
+    // Check that the daemon is still reachable and sync from our last synced topoheight if we are behind
+    async fn sync_if_behind(&self, address: &Address) -> Result<(), Error> {
+        let info = self.api.get_info().await?;
+        let synced_topoheight = self.wallet.get_storage().read().await.get_synced_topoheight().unwrap_or(0);
+        if info.topoheight > synced_topoheight {
+            debug!("Wallet is behind the daemon ({} < {}), syncing", synced_topoheight, info.topoheight);
+            self.sync(address, None).await?;
+        }
+
+        Ok(())
+    }
+
+        // Runs an infinite loop to sync on each new block added in chain
     // Because of potential forks and DAG reorg during attacks,
     // we verify the last valid topoheight where changes happened
     async fn start_syncing(self: &Arc<Self>) -> Result<(), Error> {
@@ -794,9 +1103,20 @@ impl NetworkHandler {
         // Network events to detect if we are online or offline
         let mut on_connection = self.api.on_connection().await;
         let mut on_connection_lost = self.api.on_connection_lost().await;
+        let mut on_connection_changed = self.api.on_connection_changed().await;
+        let mut endpoint = self.api.get_endpoint().clone();
+
+        // Events may be missed, check periodically that we are still up-to-date
+        let period = self.wallet.get_sync_interval();
+        let mut sync_interval = interval_at(Instant::now() + period, period);
+        sync_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
             tokio::select! {
+                _ = sync_interval.tick() => {
+                    trace!("sync_interval");
+                    self.sync_if_behind(&address).await?;
+                },
                 // Wait on a new block, we don't parse the block directly as it may
                 // have reorg the chain
                 res = on_new_block.next() => {
@@ -862,6 +1182,22 @@ impl NetworkHandler {
                     trace!("on_connection_lost");
                     res?;
                     self.wallet.propagate_event(Event::Offline).await;
+                },
+                res = on_connection_changed.recv() => {
+                    trace!("on_connection_changed");
+                    match res? {
+                        ConnectionState::Connected(address) => {
+                            if address != endpoint {
+                                warn!("Daemon connection failover from {} to {}", endpoint, address);
+                                endpoint = address;
+                            } else {
+                                info!("Connection to daemon {} restored", endpoint);
+                            }
+                        },
+                        ConnectionState::Disconnected => {
+                            warn!("Connection to daemon {} lost, transactions submission is paused", endpoint);
+                        }
+                    }
                 }
             }
         }
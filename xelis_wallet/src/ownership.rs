@@ -0,0 +1,119 @@
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    crypto::{Address, Signature},
+    time::TimestampMillis
+};
+use crate::{
+    config::{OWNERSHIP_CHALLENGE_EXPIRATION, OWNERSHIP_VERIFICATION_EXPIRATION},
+    error::WalletError
+};
+
+// Challenge to prove the control of an address
+// The owner signs its message using the sign_message command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnershipChallenge {
+    pub address: String,
+    pub purpose: String,
+    // random value so a previous signature can't be reused
+    pub nonce: String,
+    pub created_at: TimestampMillis
+}
+
+// An address whose control was proven by its owner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressVerification {
+    pub address: String,
+    pub purpose: String,
+    pub verified_at: TimestampMillis
+}
+
+impl OwnershipChallenge {
+    pub fn new(address: &Address, purpose: String, now: TimestampMillis) -> Self {
+        let mut nonce = [0u8; 16];
+        OsRng.fill_bytes(&mut nonce);
+        Self {
+            address: address.to_string(),
+            purpose,
+            nonce: hex::encode(nonce),
+            created_at: now
+        }
+    }
+
+    // Message to be signed by the owner of the address
+    pub fn get_message(&self) -> String {
+        format!("Prove you control {} for {} (challenge {} created at {})", self.address, self.purpose, self.nonce, self.created_at)
+    }
+
+    pub fn is_expired(&self, now: TimestampMillis) -> bool {
+        now >= self.created_at.saturating_add(OWNERSHIP_CHALLENGE_EXPIRATION)
+    }
+
+    // Verify that the message was signed by the key of the address
+    pub fn verify(&self, signature: &Signature, now: TimestampMillis) -> Result<AddressVerification, WalletError> {
+        if self.is_expired(now) {
+            return Err(WalletError::OwnershipChallengeExpired(self.address.clone()))
+        }
+
+        let address = Address::from_string(&self.address)?;
+        let key = address.get_public_key().decompress().map_err(|_| WalletError::InvalidSignature)?;
+        if !signature.verify(self.get_message().as_bytes(), &key) {
+            return Err(WalletError::InvalidSignature)
+        }
+
+        Ok(AddressVerification {
+            address: self.address.clone(),
+            purpose: self.purpose.clone(),
+            verified_at: now
+        })
+    }
+}
+
+impl AddressVerification {
+    pub fn is_expired(&self, now: TimestampMillis) -> bool {
+        now >= self.verified_at.saturating_add(OWNERSHIP_VERIFICATION_EXPIRATION)
+    }
+
+    // Still valid for this address
+    pub fn is_valid_for(&self, address: &str, now: TimestampMillis) -> bool {
+        self.address == address && !self.is_expired(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::crypto::KeyPair;
+    use super::*;
+
+    #[test]
+    fn test_challenge() {
+        let keypair = KeyPair::new();
+        let address = keypair.get_public_key().to_address(false);
+        let challenge = OwnershipChallenge::new(&address, "withdrawals".into(), 1000);
+
+        let signature = keypair.sign(challenge.get_message().as_bytes());
+        let verification = challenge.verify(&signature, 2000).unwrap();
+        assert!(verification.is_valid_for(&address.to_string(), 2000));
+
+        // Signed by another key
+        let signature = KeyPair::new().sign(challenge.get_message().as_bytes());
+        assert!(matches!(challenge.verify(&signature, 2000), Err(WalletError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_expiration() {
+        let keypair = KeyPair::new();
+        let address = keypair.get_public_key().to_address(false);
+        let challenge = OwnershipChallenge::new(&address, "withdrawals".into(), 0);
+        let signature = keypair.sign(challenge.get_message().as_bytes());
+
+        // Challenge answered too late
+        assert!(matches!(challenge.verify(&signature, OWNERSHIP_CHALLENGE_EXPIRATION), Err(WalletError::OwnershipChallengeExpired(_))));
+
+        // Old verifications must be done again
+        let verification = challenge.verify(&signature, 10).unwrap();
+        assert!(!verification.is_valid_for(&address.to_string(), 10 + OWNERSHIP_VERIFICATION_EXPIRATION));
+        // Contact address changed since
+        assert!(!verification.is_valid_for("xet:other", 10));
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    crypto::{Hash, Hashable},
+    serializer::{ReaderError, Serializer},
+    time::TimestampMillis,
+    transaction::{builder::TransactionTypeBuilder, Reference, Transaction}
+};
+use crate::{error::WalletError, storage::Balance};
+
+// Policy used to automatically bump the fee of our transactions stuck in mempool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoBumpPolicy {
+    // fee a transaction can never go above, bumps included
+    pub max_fee: u64,
+    // fee increase in percent at each bump
+    pub bump_percent: u64,
+    // blocks to wait without being mined before each bump
+    pub check_interval: u64,
+    // bumps allowed for the same nonce
+    pub max_attempts: u32
+}
+
+impl AutoBumpPolicy {
+    pub fn verify(&self) -> Result<(), WalletError> {
+        if self.bump_percent == 0 || self.check_interval == 0 || self.max_attempts == 0 {
+            return Err(WalletError::InvalidAutoBumpPolicy)
+        }
+
+        Ok(())
+    }
+
+    // Fee to use for the next bump, capped to the max fee
+    // None if the max fee is already reached
+    pub fn next_fee(&self, fee: u64) -> Option<u64> {
+        let increase = (fee.saturating_mul(self.bump_percent) / 100).max(1);
+        let next = fee.saturating_add(increase).min(self.max_fee);
+        if next > fee {
+            Some(next)
+        } else {
+            None
+        }
+    }
+}
+
+// A transaction built by the wallet that is not yet confirmed
+// Everything needed to build it again with the same nonce is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    pub hash: Hash,
+    pub nonce: u64,
+    pub fee: u64,
+    pub reference: Reference,
+    pub tx_type: TransactionTypeBuilder,
+    // balances used as input by this nonce
+    pub balances: HashMap<Hash, Balance>,
+    // topoheight at which it was built or bumped
+    pub topoheight: u64,
+    // bumps done for this nonce, failed ones included
+    pub attempts: u32,
+    // previous versions replaced by this one, oldest first
+    pub replaced: Vec<Hash>
+}
+
+impl PendingTransaction {
+    pub fn new(hash: Hash, nonce: u64, fee: u64, reference: Reference, tx_type: TransactionTypeBuilder, balances: HashMap<Hash, Balance>, topoheight: u64) -> Self {
+        Self {
+            hash,
+            nonce,
+            fee,
+            reference,
+            tx_type,
+            balances,
+            topoheight,
+            attempts: 0,
+            replaced: Vec::new()
+        }
+    }
+
+    // Fee to bump it with at this topoheight, if the policy allows it
+    pub fn get_bump_fee(&self, policy: &AutoBumpPolicy, topoheight: u64) -> Option<u64> {
+        if self.attempts >= policy.max_attempts || topoheight < self.topoheight.saturating_add(policy.check_interval) {
+            return None
+        }
+
+        policy.next_fee(self.fee)
+    }
+
+    // Same transaction built again with a higher fee
+    pub fn bumped(&self, hash: Hash, fee: u64, topoheight: u64) -> Self {
+        let mut replaced = self.replaced.clone();
+        replaced.push(self.hash.clone());
+        Self {
+            hash,
+            nonce: self.nonce,
+            fee,
+            reference: self.reference.clone(),
+            tx_type: self.tx_type.clone(),
+            balances: self.balances.clone(),
+            topoheight,
+            attempts: self.attempts + 1,
+            replaced
+        }
+    }
+}
+
+// What to do with a submitted transaction missing from both chain and mempool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebroadcastAction {
+    // Grace period not elapsed since the last submission
+    Wait,
+    Rebroadcast,
+    // No rebroadcast left, give up
+    Fail
+}
+
+// A transaction we submitted to the daemon that was not yet seen executed
+// The signed transaction is kept to be submitted again if the daemon drops it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmittedTransaction {
+    pub hash: Hash,
+    pub nonce: u64,
+    // signed transaction in hex, as sent to the daemon
+    pub tx_as_hex: String,
+    // time of the first submission
+    pub submitted_at: TimestampMillis,
+    // time of the last submission, rebroadcasts included
+    pub last_submitted_at: TimestampMillis,
+    // rebroadcasts done, failed ones included
+    pub rebroadcasts: u32,
+    // no longer rebroadcasted
+    pub failed: bool
+}
+
+impl SubmittedTransaction {
+    pub fn new(transaction: Transaction, submitted_at: TimestampMillis) -> Self {
+        Self {
+            hash: transaction.hash(),
+            nonce: transaction.get_nonce(),
+            tx_as_hex: transaction.to_hex(),
+            submitted_at,
+            last_submitted_at: submitted_at,
+            rebroadcasts: 0,
+            failed: false
+        }
+    }
+
+    pub fn get_transaction(&self) -> Result<Transaction, ReaderError> {
+        Transaction::from_hex(self.tx_as_hex.clone())
+    }
+
+    // Action to take once it is known to be neither executed nor in mempool
+    pub fn get_rebroadcast_action(&self, now: TimestampMillis, grace_period: u64, max_rebroadcasts: u32) -> RebroadcastAction {
+        if now < self.last_submitted_at.saturating_add(grace_period) {
+            RebroadcastAction::Wait
+        } else if self.rebroadcasts >= max_rebroadcasts {
+            RebroadcastAction::Fail
+        } else {
+            RebroadcastAction::Rebroadcast
+        }
+    }
+
+    // Newer transaction submitted by us with the same nonce
+    // Only one of them can be executed, so this one must not be sent again
+    pub fn find_conflict<'a>(&self, submitted: &'a [SubmittedTransaction]) -> Option<&'a SubmittedTransaction> {
+        submitted.iter()
+            .filter(|tx| tx.nonce == self.nonce && tx.hash != self.hash && tx.submitted_at >= self.submitted_at)
+            .max_by_key(|tx| tx.submitted_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        config::XELIS_ASSET,
+        transaction::BurnPayload
+    };
+    use super::*;
+
+    fn policy() -> AutoBumpPolicy {
+        AutoBumpPolicy {
+            max_fee: 1500,
+            bump_percent: 20,
+            check_interval: 3,
+            max_attempts: 5
+        }
+    }
+
+    fn pending(fee: u64) -> PendingTransaction {
+        let reference = Reference {
+            hash: Hash::zero(),
+            topoheight: 10
+        };
+        let tx_type = TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 1 });
+        PendingTransaction::new(Hash::new([1; 32]), 7, fee, reference, tx_type, HashMap::new(), 10)
+    }
+
+    fn submitted(hash: u8, nonce: u64, submitted_at: TimestampMillis) -> SubmittedTransaction {
+        SubmittedTransaction {
+            hash: Hash::new([hash; 32]),
+            nonce,
+            tx_as_hex: String::new(),
+            submitted_at,
+            last_submitted_at: submitted_at,
+            rebroadcasts: 0,
+            failed: false
+        }
+    }
+
+    #[test]
+    fn test_next_fee() {
+        let policy = policy();
+        assert_eq!(policy.next_fee(1000), Some(1200));
+        // Small fees are always increased
+        assert_eq!(policy.next_fee(0), Some(1));
+        // Never above the max fee
+        assert_eq!(policy.next_fee(1400), Some(1500));
+        assert_eq!(policy.next_fee(1500), None);
+        assert_eq!(policy.next_fee(2000), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(policy().verify().is_ok());
+        assert!(AutoBumpPolicy { bump_percent: 0, ..policy() }.verify().is_err());
+        assert!(AutoBumpPolicy { check_interval: 0, ..policy() }.verify().is_err());
+        assert!(AutoBumpPolicy { max_attempts: 0, ..policy() }.verify().is_err());
+    }
+
+    #[test]
+    fn test_bump_schedule() {
+        let policy = policy();
+        let tx = pending(1000);
+        // Not stuck long enough
+        assert_eq!(tx.get_bump_fee(&policy, 12), None);
+        assert_eq!(tx.get_bump_fee(&policy, 13), Some(1200));
+
+        let bumped = tx.bumped(Hash::new([2; 32]), 1200, 13);
+        assert_eq!(bumped.nonce, tx.nonce);
+        assert_eq!(bumped.attempts, 1);
+        assert_eq!(bumped.replaced, vec![tx.hash.clone()]);
+        assert_eq!(bumped.get_bump_fee(&policy, 15), None);
+        assert_eq!(bumped.get_bump_fee(&policy, 16), Some(1440));
+
+        let bumped = bumped.bumped(Hash::new([3; 32]), 1440, 16).bumped(Hash::new([4; 32]), 1500, 19);
+        assert_eq!(bumped.replaced, vec![Hash::new([1; 32]), Hash::new([2; 32]), Hash::new([3; 32])]);
+        // Max fee reached
+        assert_eq!(bumped.get_bump_fee(&policy, 100), None);
+
+        // Max attempts reached
+        let mut tx = pending(1000);
+        tx.attempts = policy.max_attempts;
+        assert_eq!(tx.get_bump_fee(&policy, 100), None);
+    }
+
+    #[test]
+    fn test_serde() {
+        let tx = pending(1000).bumped(Hash::new([2; 32]), 1200, 13);
+        let decoded: PendingTransaction = serde_json::from_slice(&serde_json::to_vec(&tx).unwrap()).unwrap();
+        assert_eq!(decoded.hash, tx.hash);
+        assert_eq!(decoded.replaced, tx.replaced);
+        assert_eq!(decoded.attempts, 1);
+    }
+
+    #[test]
+    fn test_rebroadcast_schedule() {
+        let mut tx = submitted(1, 7, 1000);
+        // Grace period not elapsed
+        assert_eq!(tx.get_rebroadcast_action(1099, 100, 3), RebroadcastAction::Wait);
+        assert_eq!(tx.get_rebroadcast_action(1100, 100, 3), RebroadcastAction::Rebroadcast);
+
+        // Each rebroadcast restarts the grace period
+        tx.rebroadcasts = 1;
+        tx.last_submitted_at = 1100;
+        assert_eq!(tx.get_rebroadcast_action(1150, 100, 3), RebroadcastAction::Wait);
+        assert_eq!(tx.get_rebroadcast_action(1200, 100, 3), RebroadcastAction::Rebroadcast);
+
+        // No rebroadcast left
+        tx.rebroadcasts = 3;
+        assert_eq!(tx.get_rebroadcast_action(1150, 100, 3), RebroadcastAction::Wait);
+        assert_eq!(tx.get_rebroadcast_action(2000, 100, 3), RebroadcastAction::Fail);
+    }
+
+    #[test]
+    fn test_rebroadcast_conflict() {
+        let tx = submitted(1, 7, 1000);
+        let list = vec![tx.clone(), submitted(2, 8, 1100)];
+        assert!(tx.find_conflict(&list).is_none());
+
+        // Newer TX created with the same nonce
+        let list = vec![tx.clone(), submitted(2, 8, 1100), submitted(3, 7, 1200), submitted(4, 7, 1300)];
+        assert_eq!(tx.find_conflict(&list).map(|tx| &tx.hash), Some(&Hash::new([4; 32])));
+
+        // The newest one has no conflict
+        assert!(list[3].find_conflict(&list).is_none());
+    }
+}
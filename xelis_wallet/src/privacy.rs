@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    crypto::{Hash, PublicKey},
+    serializer::Serializer,
+    transaction::{Transaction, TransactionType}
+};
+
+// What a transaction reveals once broadcasted
+// The values below are visible by anyone reading the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivacyReport {
+    pub fee: u64,
+    pub size: usize,
+    pub nonce: u64,
+    // source account, in address format
+    pub source: String,
+    // destinations are visible, only the amounts are hidden
+    pub destinations: usize,
+    pub assets: Vec<Hash>,
+    pub findings: Vec<PrivacyFinding>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum PrivacyFinding {
+    // Extra data is encrypted, only the sender and the recipient can read it
+    EncryptedMemo {
+        transfers: usize
+    },
+    // Burned amounts are public
+    PublicAmount {
+        asset: Hash,
+        amount: u64
+    },
+    // We already sent to this destination recently, both TXs can be linked together
+    ReusedDestination {
+        destination: String,
+        last_topoheight: u64
+    },
+    // The daemon receives the TX first and can associate it with our IP
+    DaemonSeesIp {
+        daemon_address: String
+    },
+    // The TX is broadcasted by our own node
+    LocalDaemon {
+        daemon_address: String
+    },
+    // Not connected, the TX will have to be sent manually
+    NotConnected
+}
+
+impl PrivacyReport {
+    // Analyze a built transaction
+    // recent_destinations contains the last topoheight at which we sent to each key
+    pub fn new(tx: &Transaction, mainnet: bool, recent_destinations: &HashMap<PublicKey, u64>, daemon_address: Option<&str>) -> Self {
+        let mut findings = Vec::new();
+        let mut assets = Vec::new();
+        let mut destinations = HashSet::new();
+        match tx.get_data() {
+            TransactionType::Transfers(transfers) => {
+                let memos = transfers.iter().filter(|transfer| transfer.get_extra_data().is_some()).count();
+                if memos > 0 {
+                    findings.push(PrivacyFinding::EncryptedMemo { transfers: memos });
+                }
+
+                for transfer in transfers {
+                    if !assets.contains(transfer.get_asset()) {
+                        assets.push(transfer.get_asset().clone());
+                    }
+
+                    let destination = transfer.get_destination();
+                    if !destinations.insert(destination) {
+                        continue;
+                    }
+
+                    if let Some(topoheight) = recent_destinations.get(destination) {
+                        findings.push(PrivacyFinding::ReusedDestination {
+                            destination: destination.as_address(mainnet).to_string(),
+                            last_topoheight: *topoheight
+                        });
+                    }
+                }
+            },
+            TransactionType::Burn(payload) => {
+                assets.push(payload.asset.clone());
+                findings.push(PrivacyFinding::PublicAmount {
+                    asset: payload.asset.clone(),
+                    amount: payload.amount
+                });
+            }
+        };
+
+        findings.push(match daemon_address {
+            Some(address) if is_local_endpoint(address) => PrivacyFinding::LocalDaemon { daemon_address: address.to_owned() },
+            Some(address) => PrivacyFinding::DaemonSeesIp { daemon_address: address.to_owned() },
+            None => PrivacyFinding::NotConnected
+        });
+
+        Self {
+            fee: tx.get_fee(),
+            size: tx.size(),
+            nonce: tx.get_nonce(),
+            source: tx.get_source().as_address(mainnet).to_string(),
+            destinations: destinations.len(),
+            assets,
+            findings
+        }
+    }
+
+    // Human readable lines to display before sending
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Public: fee {}, size {} bytes, nonce {}", self.fee, self.size, self.nonce),
+            format!("Public: source account {}", self.source),
+            format!("Public: {} destination(s) and {} asset(s) used, amounts are encrypted", self.destinations, self.assets.len())
+        ];
+
+        for finding in self.findings.iter() {
+            lines.push(match finding {
+                PrivacyFinding::EncryptedMemo { transfers } => format!("{} transfer(s) have a memo, only the recipient can read it", transfers),
+                PrivacyFinding::PublicAmount { asset, amount } => format!("Burned amount {} of {} is public", amount, asset),
+                PrivacyFinding::ReusedDestination { destination, last_topoheight } => format!("You already sent to {} at topoheight {}, both transactions can be linked", destination, last_topoheight),
+                PrivacyFinding::DaemonSeesIp { daemon_address } => format!("Daemon {} will see this transaction coming from your IP, use your own node to avoid it", daemon_address),
+                PrivacyFinding::LocalDaemon { daemon_address } => format!("Transaction is broadcasted by your own node {}", daemon_address),
+                PrivacyFinding::NotConnected => "Wallet is offline, the transaction must be broadcasted manually".to_owned()
+            });
+        }
+        lines
+    }
+}
+
+// Check if the endpoint is on this machine
+fn is_local_endpoint(endpoint: &str) -> bool {
+    let address = endpoint.split_once("://").map(|(_, address)| address).unwrap_or(endpoint);
+    let host = if let Some(host) = address.strip_prefix('[') {
+        // IPv6 address
+        host.split(']').next().unwrap_or(host)
+    } else {
+        address.split(|c| c == ':' || c == '/').next().unwrap_or(address)
+    };
+
+    host == "localhost" || host == "::1" || host.starts_with("127.")
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        api::{DataElement, DataValue},
+        config::{COIN_VALUE, XELIS_ASSET},
+        crypto::KeyPair,
+        testing::build_transaction,
+        transaction::{builder::{TransactionTypeBuilder, TransferBuilder}, BurnPayload, Reference}
+    };
+    use super::*;
+
+    fn transfer(destination: &KeyPair, memo: bool) -> TransferBuilder {
+        TransferBuilder {
+            asset: XELIS_ASSET,
+            amount: 100,
+            destination: destination.get_public_key().to_address(false),
+            extra_data: memo.then(|| DataElement::Value(DataValue::String("invoice".to_owned())))
+        }
+    }
+
+    fn build(data: TransactionTypeBuilder) -> Transaction {
+        let reference = Reference {
+            hash: Hash::zero(),
+            topoheight: 0
+        };
+        build_transaction(&KeyPair::new(), 100 * COIN_VALUE, 0, reference, data)
+    }
+
+    #[test]
+    fn test_public_metadata() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        let tx = build(TransactionTypeBuilder::Transfers(vec![transfer(&alice, false), transfer(&bob, false), transfer(&bob, false)]));
+        let report = PrivacyReport::new(&tx, false, &HashMap::new(), Some("ws://node.example.com:8080"));
+        assert_eq!(report.fee, tx.get_fee());
+        assert_eq!(report.size, tx.size());
+        assert_eq!(report.destinations, 2);
+        assert_eq!(report.assets, vec![XELIS_ASSET]);
+        assert_eq!(report.source, tx.get_source().as_address(false).to_string());
+        assert_eq!(report.findings, vec![PrivacyFinding::DaemonSeesIp { daemon_address: "ws://node.example.com:8080".to_owned() }]);
+    }
+
+    #[test]
+    fn test_memo() {
+        let alice = KeyPair::new();
+        let tx = build(TransactionTypeBuilder::Transfers(vec![transfer(&alice, true), transfer(&alice, false)]));
+        let report = PrivacyReport::new(&tx, false, &HashMap::new(), None);
+        assert_eq!(report.findings, vec![PrivacyFinding::EncryptedMemo { transfers: 1 }, PrivacyFinding::NotConnected]);
+    }
+
+    #[test]
+    fn test_reused_destination() {
+        let (alice, bob) = (KeyPair::new(), KeyPair::new());
+        let tx = build(TransactionTypeBuilder::Transfers(vec![transfer(&alice, false), transfer(&bob, false)]));
+        let mut recent = HashMap::new();
+        recent.insert(bob.get_public_key().compress(), 42);
+
+        let report = PrivacyReport::new(&tx, false, &recent, None);
+        assert_eq!(report.findings[0], PrivacyFinding::ReusedDestination {
+            destination: bob.get_public_key().to_address(false).to_string(),
+            last_topoheight: 42
+        });
+        assert_eq!(report.findings.len(), 2);
+    }
+
+    #[test]
+    fn test_burn() {
+        let tx = build(TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 500 }));
+        let report = PrivacyReport::new(&tx, false, &HashMap::new(), Some("127.0.0.1:8080"));
+        assert_eq!(report.destinations, 0);
+        assert_eq!(report.findings, vec![
+            PrivacyFinding::PublicAmount { asset: XELIS_ASSET, amount: 500 },
+            PrivacyFinding::LocalDaemon { daemon_address: "127.0.0.1:8080".to_owned() }
+        ]);
+    }
+
+    #[test]
+    fn test_local_endpoint() {
+        assert!(is_local_endpoint("http://127.0.0.1:8080"));
+        assert!(is_local_endpoint("ws://localhost:8080/json_rpc"));
+        assert!(is_local_endpoint("[::1]:8080"));
+        assert!(!is_local_endpoint("https://node.xelis.io"));
+        assert!(!is_local_endpoint("ws://localhost.example.com:8080"));
+        assert!(!is_local_endpoint("[2001:db8::1]:8080"));
+    }
+}
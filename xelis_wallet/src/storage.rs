@@ -1,9 +1,11 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
-    num::NonZeroUsize
+    num::NonZeroUsize,
+    path::Path
 };
 use indexmap::IndexMap;
 use lru::LruCache;
+use serde::{Deserialize, Serialize};
 use sled::{
     Tree,
     Db
@@ -11,11 +13,13 @@ use sled::{
 use tokio::sync::Mutex;
 use xelis_common::{
     account::CiphertextCache,
+    asset::{AssetData, AssetMetadata},
     api::{
         query::{
             Query,
             QueryResult
         },
+        wallet::ApiKeyEntry,
         DataElement,
         DataValue
     },
@@ -32,7 +36,10 @@ use xelis_common::{
         Serializer,
         Writer
     },
-    transaction::Reference
+    time::TimestampMillis,
+    transaction::Reference,
+    utils::format_coin_with_precision,
+    config::{COIN_DECIMALS, XELIS_ASSET}
 };
 use anyhow::{
     Context,
@@ -42,12 +49,18 @@ use anyhow::{
 use crate::{
     cipher::Cipher,
     config::SALT_SIZE,
+    doctor::StorageIntegrity,
+    draft::TransactionDraft,
     entry::{
         EntryData,
         TransactionEntry,
         Transfer
     },
-    error::WalletError
+    error::WalletError,
+    metadata::WalletMetadata,
+    hooks::Hook,
+    lock::{LockKind, WalletLock},
+    pending::{AutoBumpPolicy, PendingTransaction, SubmittedTransaction}
 };
 use log::{trace, debug, error};
 
@@ -59,6 +72,8 @@ const PASSWORD_SALT_KEY: &[u8] = b"PSALT";
 // Master key to encrypt/decrypt while interacting with the storage 
 const MASTER_KEY: &[u8] = b"MKEY";
 const PRIVATE_KEY: &[u8] = b"PKEY";
+// Language index of the seed words
+const SEED_LANGUAGE_KEY: &[u8] = b"SLANG";
 
 // const used for online mode
 // represent the daemon topoheight
@@ -66,11 +81,25 @@ const TOPOHEIGHT_KEY: &[u8] = b"TOPH";
 // represent the daemon top block hash
 const TOP_BLOCK_HASH_KEY: &[u8] = b"TOPBH";
 const NETWORK: &[u8] = b"NET";
+// Non-sensitive metadata (contacts, notes...)
+const METADATA_KEY: &[u8] = b"META";
+// RPC API keys indexed by the hash of the key
+const API_KEYS_KEY: &[u8] = b"APIK";
+// Transactions waiting for their delay to be confirmed
+const DRAFTS_KEY: &[u8] = b"DRAFT";
+// Transactions built by the wallet and not yet confirmed
+const PENDING_TXS_KEY: &[u8] = b"PTXS";
+// Transactions submitted by the wallet and not yet executed
+const SUBMITTED_TXS_KEY: &[u8] = b"STXS";
+// Policy to bump the fee of the pending transactions
+const AUTO_BUMP_POLICY_KEY: &[u8] = b"ABMP";
+// Commands executed on wallet events
+const HOOKS_KEY: &[u8] = b"HOOK";
 
 // Default cache size
 const DEFAULT_CACHE_SIZE: usize = 100;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Balance {
     pub amount: u64,
     pub ciphertext: CiphertextCache
@@ -103,7 +132,9 @@ impl Serializer for Balance {
 
 // Use this struct to get access to non-encrypted keys (such as salt for KDF and encrypted master key)
 pub struct Storage {
-    db: Db
+    db: Db,
+    // Released after the database is closed
+    lock: WalletLock
 }
 
 #[derive(Debug, Clone)]
@@ -129,8 +160,12 @@ pub struct EncryptedStorage {
     extra: Tree,
     // all assets tracked by the wallet
     assets: Tree,
+    // metadata (name, ticker) of the assets fetched from the daemon
+    assets_metadata: Tree,
     // This tree is used to store all topoheight where a change in the wallet occured
     changes_topoheight: Tree,
+    // Timestamp of the blocks in which a change occured, used for the history export
+    block_timestamps: Tree,
     // The inner storage
     inner: Storage,
     // Caches
@@ -144,7 +179,9 @@ pub struct EncryptedStorage {
     // Cache for the assets with their decimals
     assets_cache: Mutex<LruCache<Hash, u8>>,
     // Cache for the synced topoheight
-    synced_topoheight: Option<u64>
+    synced_topoheight: Option<u64>,
+    // Opened from a snapshot, all the writes are rejected
+    read_only: bool
 }
 
 impl EncryptedStorage {
@@ -155,14 +192,17 @@ impl EncryptedStorage {
             balances: inner.db.open_tree(&cipher.hash_key("balances"))?,
             extra: inner.db.open_tree(&cipher.hash_key("extra"))?,
             assets: inner.db.open_tree(&cipher.hash_key("assets"))?,
+            assets_metadata: inner.db.open_tree(&cipher.hash_key("assets_metadata"))?,
             changes_topoheight: inner.db.open_tree(&cipher.hash_key("changes_topoheight"))?,
+            block_timestamps: inner.db.open_tree(&cipher.hash_key("block_timestamps"))?,
             cipher,
-            inner,
             balances_cache: Mutex::new(LruCache::new(NonZeroUsize::new(DEFAULT_CACHE_SIZE).unwrap())),
             unconfirmed_balances_cache: Mutex::new(HashMap::new()),
             tx_cache: None,
             assets_cache: Mutex::new(LruCache::new(NonZeroUsize::new(DEFAULT_CACHE_SIZE).unwrap())),
             synced_topoheight: None,
+            read_only: inner.is_read_only(),
+            inner,
         };
 
         if storage.has_network()? {
@@ -192,6 +232,18 @@ impl EncryptedStorage {
         }
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    // Returns an error if the storage is opened in read-only mode
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(WalletError::ReadOnly.into())
+        }
+        Ok(())
+    }
+
     // Key must be hashed or encrypted before calling this function
     fn internal_load<V: Serializer>(&self, tree: &Tree, key: &[u8]) -> Result<V> {
         let data = tree.get(key)?.context(format!("load from disk: tree = {:?}, key = {}", tree.name(), String::from_utf8_lossy(key)))?;
@@ -228,6 +280,7 @@ impl EncryptedStorage {
     // Encrypt key, encrypt data and then save to disk
     // We encrypt instead of hashing to be able to retrieve the key
     fn save_to_disk_with_encrypted_key(&self, tree: &Tree, key: &[u8], value: &[u8]) -> Result<()> {
+        self.check_writable()?;
         let encrypted_key = self.create_encrypted_key(key)?;
         let encrypted_value = self.cipher.encrypt_value(value)?;
         tree.insert(encrypted_key, encrypted_value)?;
@@ -236,6 +289,7 @@ impl EncryptedStorage {
 
     // hash key, encrypt data and then save to disk 
     fn save_to_disk(&self, tree: &Tree, key: &[u8], value: &[u8]) -> Result<()> {
+        self.check_writable()?;
         let hashed_key = self.cipher.hash_key(key);
         tree.insert(hashed_key, self.cipher.encrypt_value(value)?)?;
         Ok(())
@@ -243,6 +297,7 @@ impl EncryptedStorage {
 
     // hash key, encrypt data and then save to disk 
     fn delete_from_disk(&self, tree: &Tree, key: &[u8]) -> Result<()> {
+        self.check_writable()?;
         let hashed_key = self.cipher.hash_key(key);
         tree.remove(hashed_key)?;
         Ok(())
@@ -250,6 +305,7 @@ impl EncryptedStorage {
 
     // hash key, encrypt data and then save to disk 
     fn delete_from_disk_with_encrypted_key(&self, tree: &Tree, key: &[u8]) -> Result<()> {
+        self.check_writable()?;
         let encrypted_key = self.create_encrypted_key(key)?;
         tree.remove(encrypted_key)?;
         Ok(())
@@ -460,6 +516,65 @@ impl EncryptedStorage {
         self.load_from_disk_with_encrypted_key(&self.assets, asset.as_bytes())
     }
 
+    // Save the metadata of an asset so it's not fetched again from the daemon
+    pub fn set_asset_metadata(&mut self, asset: &Hash, metadata: &AssetMetadata) -> Result<()> {
+        self.save_to_disk_with_encrypted_key(&self.assets_metadata, asset.as_bytes(), &metadata.to_bytes())
+    }
+
+    // Retrieve the stored metadata of an asset, None if the asset has no metadata
+    pub fn get_asset_metadata(&self, asset: &Hash) -> Result<Option<AssetMetadata>> {
+        if !self.contains_encrypted_data(&self.assets_metadata, asset.as_bytes())? {
+            return Ok(None)
+        }
+        Ok(Some(self.load_from_disk_with_encrypted_key(&self.assets_metadata, asset.as_bytes())?))
+    }
+
+    // Save an asset discovered on chain with its current balance
+    // A placeholder entry identified by the asset hash is kept in the history until its blocks are scanned
+    pub async fn save_discovered_asset(&mut self, asset: &Hash, data: &AssetData, topoheight: u64, balance: Balance) -> Result<()> {
+        let amount = balance.amount;
+        self.add_asset(asset, data.get_decimals()).await?;
+        if let Some(metadata) = data.get_metadata() {
+            self.set_asset_metadata(asset, metadata)?;
+        }
+        self.set_balance_for(asset, balance).await?;
+
+        if !self.has_transaction(asset)? {
+            let entry = TransactionEntry::new(asset.clone(), topoheight, EntryData::Discovered { asset: asset.clone(), amount });
+            self.save_transaction(asset, &entry)?;
+        }
+
+        Ok(())
+    }
+
+    // Name and ticker of the asset if known, its hash otherwise
+    pub fn get_asset_display_name(&self, asset: &Hash) -> String {
+        match self.get_asset_metadata(asset) {
+            Ok(Some(metadata)) => format!("{} ({})", metadata.get_name(), metadata.get_ticker()),
+            _ => asset.to_string()
+        }
+    }
+
+    // Format an amount of this asset for display, using its display override if one is set
+    // Transactions and exports always use the exact amount
+    pub fn format_asset_amount(&self, asset: &Hash, amount: u64) -> Result<String> {
+        let decimals = if *asset == XELIS_ASSET {
+            COIN_DECIMALS
+        } else {
+            self.get_asset_decimals(asset)?
+        };
+
+        let display = self.get_metadata()?.get_asset_display(asset).cloned();
+        let display_decimals = display.as_ref().and_then(|d| d.decimals).unwrap_or(decimals);
+        let unit = match display.and_then(|d| d.unit) {
+            Some(unit) => unit,
+            None if *asset == XELIS_ASSET => "XELIS".to_owned(),
+            None => self.get_asset_display_name(asset)
+        };
+
+        Ok(format!("{} {}", format_coin_with_precision(amount, decimals, display_decimals), unit))
+    }
+
     // Retrieve the plaintext balance for this asset
     pub async fn get_plaintext_balance_for(&self, asset: &Hash) -> Result<u64> {
         let mut cache = self.balances_cache.lock().await;
@@ -530,6 +645,15 @@ impl EncryptedStorage {
         Ok(())
     }
 
+    // Retrieve the latest unconfirmed balance amount for each asset
+    pub async fn get_unconfirmed_balances_amounts(&self) -> HashMap<Hash, u64> {
+        trace!("get unconfirmed balances amounts");
+        let cache = self.unconfirmed_balances_cache.lock().await;
+        cache.iter()
+            .filter_map(|(asset, balances)| balances.back().map(|b| (asset.clone(), b.amount)))
+            .collect()
+    }
+
     // Determine if we have any balance stored
     pub async fn has_any_balance(&self) -> Result<bool> {
         let cache = self.balances_cache.lock().await;
@@ -619,6 +743,24 @@ impl EncryptedStorage {
         Ok(())
     }
 
+    // Topoheight and hash of the transactions in the range, ordered by topoheight
+    // Only the hashes are kept so the entries can be loaded one by one
+    pub fn get_transactions_hashes_in_range(&self, min_topoheight: Option<u64>, max_topoheight: Option<u64>) -> Result<Vec<(u64, Hash)>> {
+        let mut hashes = Vec::new();
+        for el in self.transactions.iter().values() {
+            let value = el?;
+            let entry = TransactionEntry::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            let topoheight = entry.get_topoheight();
+            if min_topoheight.is_some_and(|min| topoheight < min) || max_topoheight.is_some_and(|max| topoheight > max) {
+                continue;
+            }
+            hashes.push((topoheight, entry.get_hash().clone()));
+        }
+
+        hashes.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        Ok(hashes)
+    }
+
     // Filter when the data is deserialized to not load all transactions in memory
     pub fn get_filtered_transactions(&self, address: Option<&PublicKey>, min_topoheight: Option<u64>, max_topoheight: Option<u64>, accept_incoming: bool, accept_outgoing: bool, accept_coinbase: bool, accept_burn: bool, query: Option<&Query>) -> Result<Vec<TransactionEntry>> {
         let mut transactions = Vec::new();
@@ -650,6 +792,8 @@ impl EncryptedStorage {
                     }).is_some(), Some(transfers.into_iter().map(|t| Transfer::Out(t)).collect::<Vec<_>>())),
                     None => (true, None),
                 },
+                // We don't know the sender of a discovered asset yet
+                EntryData::Discovered { .. } if accept_incoming && address.is_none() => (true, None),
                 _ => (false, None)
             };
 
@@ -691,18 +835,21 @@ impl EncryptedStorage {
 
     // Delete a transaction saved in wallet using its hash
     pub fn delete_transaction(&mut self, hash: &Hash) -> Result<()> {
+        self.check_writable()?;
         self.transactions.remove(self.cipher.hash_key(hash.as_bytes()))?;
         Ok(())
     }
 
     // Delete all transactions from this wallet
     pub fn delete_transactions(&mut self) -> Result<()> {
+        self.check_writable()?;
         self.transactions.clear()?;
         Ok(())
     }
 
     // Delete all balances from this wallet
     pub async fn delete_balances(&mut self) -> Result<()> {
+        self.check_writable()?;
         self.balances.clear()?;
         self.delete_unconfirmed_balances().await?;
         self.balances_cache.lock().await.clear();
@@ -726,6 +873,7 @@ impl EncryptedStorage {
 
     // Delete all assets from this wallet
     pub async fn delete_assets(&mut self) -> Result<()> {
+        self.check_writable()?;
         self.assets.clear()?;
         self.assets_cache.lock().await.clear();
         Ok(())
@@ -795,11 +943,29 @@ impl EncryptedStorage {
         self.load_from_disk(&self.extra, PRIVATE_KEY)
     }
 
+    // Set the language index used to display the seed
+    pub fn set_seed_language(&mut self, language: usize) -> Result<()> {
+        trace!("set seed language to {}", language);
+        self.save_to_disk(&self.extra, SEED_LANGUAGE_KEY, &(language as u8).to_bytes())
+    }
+
+    // Get the language index of the seed, English for wallets created before it was stored
+    pub fn get_seed_language(&self) -> Result<usize> {
+        trace!("get seed language");
+        if !self.contains_data(&self.extra, SEED_LANGUAGE_KEY)? {
+            return Ok(0)
+        }
+
+        let language: u8 = self.load_from_disk(&self.extra, SEED_LANGUAGE_KEY)?;
+        Ok(language as usize)
+    }
+
     // Set the topoheight until which the wallet is synchronized
     pub fn set_synced_topoheight(&mut self, topoheight: u64) -> Result<()> {
         trace!("set synced topoheight to {}", topoheight);
+        self.save_to_disk(&self.extra, TOPOHEIGHT_KEY, &topoheight.to_be_bytes())?;
         self.synced_topoheight = Some(topoheight);
-        self.save_to_disk(&self.extra, TOPOHEIGHT_KEY, &topoheight.to_be_bytes())
+        Ok(())
     }
 
     // Get the topoheight until which the wallet is synchronized
@@ -867,6 +1033,176 @@ impl EncryptedStorage {
         self.contains_data(&self.extra, NETWORK)
     }
 
+    // Get the wallet metadata, stored as JSON as it is exported the same way
+    pub fn get_metadata(&self) -> Result<WalletMetadata> {
+        trace!("get metadata");
+        let hashed_key = self.cipher.hash_key(METADATA_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting metadata")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing metadata")?)
+            },
+            None => Ok(WalletMetadata::default())
+        }
+    }
+
+    // Save the wallet metadata
+    pub fn set_metadata(&mut self, metadata: &WalletMetadata) -> Result<()> {
+        trace!("set metadata");
+        self.save_to_disk(&self.extra, METADATA_KEY, &serde_json::to_vec(metadata)?)
+    }
+
+    // Get the RPC API keys indexed by the hash of the key
+    pub fn get_api_keys(&self) -> Result<HashMap<Hash, ApiKeyEntry>> {
+        trace!("get api keys");
+        let hashed_key = self.cipher.hash_key(API_KEYS_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting API keys")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing API keys")?)
+            },
+            None => Ok(HashMap::new())
+        }
+    }
+
+    // Save the RPC API keys
+    pub fn set_api_keys(&mut self, keys: &HashMap<Hash, ApiKeyEntry>) -> Result<()> {
+        trace!("set api keys");
+        self.save_to_disk(&self.extra, API_KEYS_KEY, &serde_json::to_vec(keys)?)
+    }
+
+    // Get the transaction drafts sorted by id
+    pub fn get_drafts(&self) -> Result<Vec<TransactionDraft>> {
+        trace!("get drafts");
+        let hashed_key = self.cipher.hash_key(DRAFTS_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting drafts")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing drafts")?)
+            },
+            None => Ok(Vec::new())
+        }
+    }
+
+    // Save the transaction drafts
+    pub fn set_drafts(&mut self, drafts: &[TransactionDraft]) -> Result<()> {
+        trace!("set drafts");
+        self.save_to_disk(&self.extra, DRAFTS_KEY, &serde_json::to_vec(drafts)?)
+    }
+
+    // Get the pending transactions sorted by nonce
+    pub fn get_pending_transactions(&self) -> Result<Vec<PendingTransaction>> {
+        trace!("get pending transactions");
+        let hashed_key = self.cipher.hash_key(PENDING_TXS_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting pending transactions")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing pending transactions")?)
+            },
+            None => Ok(Vec::new())
+        }
+    }
+
+    // Save the pending transactions
+    pub fn set_pending_transactions(&mut self, pending: &[PendingTransaction]) -> Result<()> {
+        trace!("set pending transactions");
+        self.save_to_disk(&self.extra, PENDING_TXS_KEY, &serde_json::to_vec(pending)?)
+    }
+
+    // Track a pending transaction, it replaces any other one using the same nonce
+    // Higher nonces are dropped too as they were built on top of the replaced one
+    pub fn add_pending_transaction(&mut self, transaction: PendingTransaction) -> Result<()> {
+        trace!("add pending transaction {}", transaction.hash);
+        let mut pending = self.get_pending_transactions()?;
+        pending.retain(|tx| tx.nonce < transaction.nonce);
+        pending.push(transaction);
+        self.set_pending_transactions(&pending)
+    }
+
+    // Get the submitted transactions, oldest first
+    pub fn get_submitted_transactions(&self) -> Result<Vec<SubmittedTransaction>> {
+        trace!("get submitted transactions");
+        let hashed_key = self.cipher.hash_key(SUBMITTED_TXS_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting submitted transactions")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing submitted transactions")?)
+            },
+            None => Ok(Vec::new())
+        }
+    }
+
+    // Save the submitted transactions
+    pub fn set_submitted_transactions(&mut self, submitted: &[SubmittedTransaction]) -> Result<()> {
+        trace!("set submitted transactions");
+        self.save_to_disk(&self.extra, SUBMITTED_TXS_KEY, &serde_json::to_vec(submitted)?)
+    }
+
+    // Track a transaction accepted by the daemon until it is executed
+    // Submitting the same TX again resets its tracking
+    pub fn add_submitted_transaction(&mut self, transaction: SubmittedTransaction) -> Result<()> {
+        trace!("add submitted transaction {}", transaction.hash);
+        let mut submitted = self.get_submitted_transactions()?;
+        submitted.retain(|tx| tx.hash != transaction.hash);
+        submitted.push(transaction);
+        self.set_submitted_transactions(&submitted)
+    }
+
+    // Stop tracking a submitted transaction
+    // returns true if it was tracked
+    pub fn remove_submitted_transaction(&mut self, hash: &Hash) -> Result<bool> {
+        trace!("remove submitted transaction {}", hash);
+        let mut submitted = self.get_submitted_transactions()?;
+        let len = submitted.len();
+        submitted.retain(|tx| tx.hash != *hash);
+        if submitted.len() == len {
+            return Ok(false)
+        }
+
+        self.set_submitted_transactions(&submitted)?;
+        Ok(true)
+    }
+
+    pub fn get_auto_bump_policy(&self) -> Result<Option<AutoBumpPolicy>> {
+        trace!("get auto bump policy");
+        let hashed_key = self.cipher.hash_key(AUTO_BUMP_POLICY_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting auto bump policy")?;
+                Ok(Some(serde_json::from_slice(&bytes).context("Error while de-serializing auto bump policy")?))
+            },
+            None => Ok(None)
+        }
+    }
+
+    // Set or disable the auto bump policy
+    pub fn set_auto_bump_policy(&mut self, policy: Option<&AutoBumpPolicy>) -> Result<()> {
+        trace!("set auto bump policy");
+        match policy {
+            Some(policy) => self.save_to_disk(&self.extra, AUTO_BUMP_POLICY_KEY, &serde_json::to_vec(policy)?),
+            None => self.delete_from_disk(&self.extra, AUTO_BUMP_POLICY_KEY)
+        }
+    }
+
+    // Get the hooks sorted by id
+    pub fn get_hooks(&self) -> Result<Vec<Hook>> {
+        trace!("get hooks");
+        let hashed_key = self.cipher.hash_key(HOOKS_KEY);
+        match self.extra.get(hashed_key)? {
+            Some(data) => {
+                let bytes = self.cipher.decrypt_value(&data).context("Error while decrypting hooks")?;
+                Ok(serde_json::from_slice(&bytes).context("Error while de-serializing hooks")?)
+            },
+            None => Ok(Vec::new())
+        }
+    }
+
+    // Save the hooks
+    pub fn set_hooks(&mut self, hooks: &[Hook]) -> Result<()> {
+        trace!("set hooks");
+        self.save_to_disk(&self.extra, HOOKS_KEY, &serde_json::to_vec(hooks)?)
+    }
+
     // Add a topoheight where a change occured
     pub fn add_topoheight_to_changes(&mut self, topoheight: u64, block_hash: &Hash) -> Result<()> {
         trace!("add topoheight to changes: {} at {}", topoheight, block_hash);
@@ -879,6 +1215,21 @@ impl EncryptedStorage {
         self.load_from_disk_with_encrypted_key(&self.changes_topoheight, &topoheight.to_be_bytes())
     }
 
+    // Save the timestamp of the block at this topoheight
+    pub fn set_block_timestamp(&mut self, topoheight: u64, timestamp: TimestampMillis) -> Result<()> {
+        trace!("set block timestamp {} at {}", timestamp, topoheight);
+        self.save_to_disk_with_encrypted_key(&self.block_timestamps, &topoheight.to_be_bytes(), &timestamp.to_be_bytes())
+    }
+
+    // Timestamp of the block at this topoheight, None for entries synced before it was stored
+    pub fn get_block_timestamp(&self, topoheight: u64) -> Result<Option<TimestampMillis>> {
+        trace!("get block timestamp at {}", topoheight);
+        if !self.contains_encrypted_data(&self.block_timestamps, &topoheight.to_be_bytes())? {
+            return Ok(None)
+        }
+        Ok(Some(self.load_from_disk_with_encrypted_key(&self.block_timestamps, &topoheight.to_be_bytes())?))
+    }
+
     // Check if the topoheight is present in the changes tree
     pub fn has_topoheight_in_changes(&self, topoheight: u64) -> Result<bool> {
         trace!("has topoheight {} in changes", topoheight);
@@ -889,6 +1240,7 @@ impl EncryptedStorage {
     // This will returns true if a changes was deleted
     pub fn delete_changes_above_topoheight(&mut self, topoheight: u64) -> Result<bool> {
         trace!("delete changes above topoheight {}", topoheight);
+        self.check_writable()?;
         let mut deleted = false;
         for res in self.changes_topoheight.iter().keys() {
             let key = res?;
@@ -915,6 +1267,40 @@ impl EncryptedStorage {
         })
     }
 
+    // Go through the history and the topoheight changes to detect unreadable entries
+    // The history entries are counted per type in the same pass
+    pub fn verify_integrity(&self) -> Result<StorageIntegrity> {
+        trace!("verify integrity");
+        let mut integrity = StorageIntegrity {
+            checksum: self.inner.db.checksum()?,
+            ..Default::default()
+        };
+
+        for el in self.transactions.iter().values() {
+            let value = el?;
+            let entry = match self.cipher.decrypt_value(&value).ok().and_then(|bytes| TransactionEntry::from_bytes(&bytes).ok()) {
+                Some(entry) => entry,
+                None => {
+                    integrity.corrupted += 1;
+                    continue;
+                }
+            };
+
+            integrity.entries += 1;
+            let history = &mut integrity.history;
+            match entry.get_entry() {
+                EntryData::Coinbase { .. } => history.coinbase += 1,
+                EntryData::Burn { .. } => history.burn += 1,
+                EntryData::Incoming { .. } => history.incoming += 1,
+                EntryData::Outgoing { .. } => history.outgoing += 1,
+                EntryData::Discovered { .. } => history.discovered += 1
+            };
+        }
+
+        integrity.corrupted_changes = self.get_topoheight_changes().filter(|res| res.is_err()).count();
+        Ok(integrity)
+    }
+
     // Find highest topoheight in changes
     pub fn get_highest_topoheight_in_changes_below(&self, max: u64) -> Result<u64> {
         trace!("get highest topoheight in changes below {}", max);
@@ -933,14 +1319,33 @@ impl EncryptedStorage {
 }
 
 impl Storage {
+    // Open the wallet database, only one process can have it open
     pub fn new(name: String) -> Result<Self> {
+        let lock = WalletLock::acquire_writer(Path::new(&name))?;
         let db = sled::open(name)?;
 
         Ok(Self {
-            db
+            db,
+            lock
         })
     }
 
+    // Open a snapshot of the wallet database
+    // It can be used while another process has the wallet open
+    pub fn open_read_only(name: String) -> Result<Self> {
+        let lock = WalletLock::acquire_reader(Path::new(&name))?;
+        let db = sled::open(lock.get_snapshot().context("No snapshot for reader lock")?)?;
+
+        Ok(Self {
+            db,
+            lock
+        })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.lock.get_kind() == LockKind::Reader
+    }
+
     // save the encrypted form of the master key
     // it can only be decrypted using the password-based key
     pub fn set_encrypted_master_key(&mut self, encrypted_key: &[u8]) -> Result<()> {
@@ -1005,4 +1410,74 @@ impl Storage {
         self.db.insert(SALT_KEY, salt)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_encrypted(storage: Storage) -> EncryptedStorage {
+        EncryptedStorage::new(storage, &[1u8; 32], [2u8; SALT_SIZE], Network::Dev).unwrap()
+    }
+
+    #[test]
+    fn test_read_only_storage() {
+        let dir = std::env::temp_dir().join(format!("xelis-wallet-read-only-{}", std::process::id()));
+        let name = dir.to_string_lossy().into_owned();
+
+        let mut writer = open_encrypted(Storage::new(name.clone()).unwrap());
+        writer.set_synced_topoheight(10).unwrap();
+        writer.flush().unwrap();
+
+        // Only one writer at a time
+        let err = Storage::new(name.clone()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalletError>(), Some(WalletError::WalletLocked(_))));
+
+        let mut reader = open_encrypted(Storage::open_read_only(name.clone()).unwrap());
+        assert!(reader.is_read_only());
+        assert_eq!(reader.get_synced_topoheight().unwrap(), 10);
+        let err = reader.set_synced_topoheight(20).unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalletError>(), Some(WalletError::ReadOnly)));
+        assert_eq!(reader.get_synced_topoheight().unwrap(), 10);
+
+        // Writes done after the snapshot are not visible to the reader
+        writer.set_synced_topoheight(30).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(reader.get_synced_topoheight().unwrap(), 10);
+
+        drop(reader);
+        drop(writer);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_discovered_asset() {
+        use xelis_common::crypto::KeyPair;
+
+        let dir = std::env::temp_dir().join(format!("xelis-wallet-discovered-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut storage = open_encrypted(Storage::new(dir.to_string_lossy().into_owned()).unwrap());
+
+        // Airdrop of an asset never seen by the wallet
+        let asset = Hash::new([2u8; 32]);
+        let amount = 500;
+        let ciphertext = CiphertextCache::Decompressed(KeyPair::new().get_public_key().encrypt(amount));
+        storage.save_discovered_asset(&asset, &AssetData::new(5, 2), 10, Balance::new(amount, ciphertext)).await.unwrap();
+
+        assert!(storage.contains_asset(&asset).await.unwrap());
+        assert_eq!(storage.get_asset_decimals(&asset).unwrap(), 2);
+        assert!(storage.get_asset_metadata(&asset).unwrap().is_none());
+        assert_eq!(storage.get_balance_for(&asset).await.unwrap().amount, amount);
+
+        // Placeholder in the history until its blocks are scanned
+        let entry = storage.get_transaction(&asset).unwrap();
+        assert_eq!(entry.get_topoheight(), 10);
+        assert!(matches!(entry.get_entry(), EntryData::Discovered { asset: discovered, amount: 500 } if *discovered == asset));
+
+        // Already known assets are not saved twice
+        let ciphertext = CiphertextCache::Decompressed(KeyPair::new().get_public_key().encrypt(amount));
+        assert!(storage.save_discovered_asset(&asset, &AssetData::new(5, 2), 10, Balance::new(amount, ciphertext)).await.is_err());
+
+        drop(storage);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
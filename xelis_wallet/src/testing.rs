@@ -0,0 +1,33 @@
+use std::{ops::ControlFlow, sync::{Arc, OnceLock}};
+use xelis_common::{crypto::ecdlp, network::Network};
+use crate::wallet::{
+    PrecomputedTables,
+    PrecomputedTablesShared,
+    Wallet,
+    LOW_PRECOMPUTED_TABLES_L1
+};
+
+struct NoProgressReport;
+
+impl ecdlp::ProgressTableGenerationReportFunction for NoProgressReport {
+    fn report(&self, _: f64, _: ecdlp::ReportStep) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+// Low tables generated once and shared by all the test wallets
+pub fn test_precomputed_tables() -> PrecomputedTablesShared {
+    static TABLES: OnceLock<PrecomputedTablesShared> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = PrecomputedTables::new(LOW_PRECOMPUTED_TABLES_L1);
+        ecdlp::table_generation::create_table_file_with_progress_report(LOW_PRECOMPUTED_TABLES_L1, tables.get_mut(), NoProgressReport).unwrap();
+        Arc::new(tables)
+    }).clone()
+}
+
+// Devnet wallet created in a temporary directory
+pub fn test_wallet(name: &str) -> Arc<Wallet> {
+    let dir = std::env::temp_dir().join(format!("xelis-wallet-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    Wallet::create(dir.to_string_lossy().into_owned(), "test".to_owned(), None, None, Network::Dev, test_precomputed_tables()).unwrap()
+}
@@ -4,7 +4,7 @@ use xelis_common::{
     crypto::{elgamal::Ciphertext, Hash, PublicKey},
     transaction::{builder::{AccountState, FeeHelper}, Reference}
 };
-use crate::{error::WalletError, storage::{Balance, EncryptedStorage, TxCache}};
+use crate::{error::WalletError, pending::PendingTransaction, storage::{Balance, EncryptedStorage, TxCache}};
 
 // State used to estimate fees for a transaction
 // Because fees can be higher if a destination account is not registered
@@ -46,7 +46,9 @@ pub struct TransactionBuilderState {
     balances: HashMap<Hash, Balance>,
     reference: Reference,
     nonce: u64,
-    tx_hash_built: Option<Hash>
+    tx_hash_built: Option<Hash>,
+    // tracked once the changes are applied
+    pending: Option<PendingTransaction>
 }
 
 impl TransactionBuilderState {
@@ -59,7 +61,8 @@ impl TransactionBuilderState {
             balances: HashMap::new(),
             reference,
             nonce,
-            tx_hash_built: None
+            tx_hash_built: None,
+            pending: None
         }
     }
 
@@ -84,6 +87,11 @@ impl TransactionBuilderState {
         self.tx_hash_built = Some(tx_hash);
     }
 
+    // Keep what is needed to build the TX again with a higher fee
+    pub fn set_pending_transaction(&mut self, pending: PendingTransaction) {
+        self.pending = Some(pending);
+    }
+
     pub async fn apply_changes(&mut self, storage: &mut EncryptedStorage) -> Result<(), WalletError> {
         let last_tx_hash_created = self.tx_hash_built.take().ok_or(WalletError::TxNotBuilt)?;
         for (asset, balance) in self.balances.drain() {
@@ -96,6 +104,10 @@ impl TransactionBuilderState {
             last_tx_hash_created,
         });
 
+        if let Some(pending) = self.pending.take() {
+            storage.add_pending_transaction(pending)?;
+        }
+
         Ok(())
     }
 }
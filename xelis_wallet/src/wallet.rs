@@ -1,69 +1,126 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::{create_dir_all, File},
     io::{Read, Write},
     path::Path,
-    sync::Arc
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc
+    },
+    time::Duration
 };
 use anyhow::{Error, Context};
 use serde::Serialize;
-use tokio::sync::{
-    broadcast::{
-        Sender as BroadcastSender,
-        Receiver as BroadcastReceiver
+use tokio::{
+    sync::{
+        broadcast::{
+            Sender as BroadcastSender,
+            Receiver as BroadcastReceiver
+        },
+        Mutex,
+        RwLock
     },
-    Mutex,
-    RwLock
+    task::JoinHandle,
+    time::sleep
 };
 use xelis_common::{
+    amount::checked_add,
     api::{
         wallet::{
+            ApiKeyEntry,
+            ApiKeyPermission,
             BalanceChanged,
             NotifyEvent,
-            TransactionEntry
+            TransactionBumped,
+            TransactionEntry,
+            TransactionRebroadcastFailed,
+            TransactionRebroadcasted
         },
+        daemon::GetMempoolCacheResult,
         DataElement
     },
     asset::AssetWithData,
+    block::{has_authorship_tag, AuthorshipKey, MinedBlockProof},
+    config::{COIN_DECIMALS, VERSION, XELIS_ASSET},
     crypto::{
         ecdlp::{self, ECDLPTablesFileView},
         elgamal::{Ciphertext, DecryptHandle, PublicKey as DecompressedPublicKey},
+        hash,
         Address,
+        Hash,
         KeyPair,
         PublicKey,
         Signature,
         Hashable,
     },
+    mnemonics,
     network::Network,
     serializer::Serializer,
+    time::get_current_time_in_millis,
     transaction::{
         aead::{self, AEADCipher},
         builder::{
             FeeBuilder,
+            FeeHelper,
             TransactionBuilder,
             TransactionTypeBuilder
         },
+        BurnPayload,
         Reference,
-        Transaction
-    }
+        Transaction,
+        EXTRA_DATA_LIMIT_SIZE
+    },
+    utils::spawn_task
 };
 use crate::{
     cipher::Cipher,
     config::{
+        API_KEY_SIZE,
+        DEFAULT_SYNC_INTERVAL,
         PASSWORD_ALGORITHM,
         PASSWORD_HASH_SIZE,
+        RECENT_DESTINATION_WINDOW,
         SALT_SIZE
     },
     daemon_api::DaemonAPI,
+    doctor::{
+        self,
+        DaemonState,
+        DoctorReport,
+        SensitiveInfo
+    },
+    draft::TransactionDraft,
+    entry::EntryData,
     error::WalletError,
-    mnemonics,
+    export::{
+        build_rows,
+        format_date,
+        DecimalsCache,
+        ExportFilter,
+        ExportFormat,
+        HistoryWriter
+    },
+    hooks::{
+        Hook,
+        HookEvent,
+        HookManager
+    },
+    metadata::{
+        decrypt_metadata,
+        encrypt_metadata,
+        MergeReport
+    },
     network_handler::{
         NetworkHandler,
         SharedNetworkHandler
     },
+    pending::{PendingTransaction, SubmittedTransaction},
+    privacy::PrivacyReport,
     storage::{
+        Balance,
         EncryptedStorage,
-        Storage
+        Storage,
+        TxCache
     },
     transaction_builder::{
         EstimateFeesState,
@@ -76,7 +133,8 @@ use log::{
     trace,
     debug,
     error,
-    info
+    info,
+    warn
 };
 
 #[cfg(feature = "api_server")]
@@ -87,6 +145,7 @@ use {
         XSWDNodeMethodHandler,
         register_rpc_methods,
         XSWD,
+        WalletRpcAuthorizer,
         WalletRpcServer,
         AuthConfig,
         APIServer,
@@ -134,7 +193,13 @@ pub enum Event {
     // Wallet is now in online mode
     Online,
     // Wallet is now in offline mode
-    Offline
+    Offline,
+    // A pending TX got rebuilt with a higher fee and broadcasted
+    TransactionBumped(TransactionBumped),
+    // A submitted TX dropped by the daemon got submitted again
+    TransactionRebroadcasted(TransactionRebroadcasted),
+    // A submitted TX is no longer rebroadcasted
+    TransactionRebroadcastFailed(TransactionRebroadcastFailed)
 }
 
 impl Event {
@@ -146,12 +211,43 @@ impl Event {
             Event::NewAsset(_) => NotifyEvent::NewAsset,
             Event::Rescan { .. } => NotifyEvent::Rescan,
             Event::Online => NotifyEvent::Online,
-            Event::Offline => NotifyEvent::Offline
+            Event::Offline => NotifyEvent::Offline,
+            Event::TransactionBumped(_) => NotifyEvent::TransactionBumped,
+            Event::TransactionRebroadcasted(_) => NotifyEvent::TransactionRebroadcasted,
+            Event::TransactionRebroadcastFailed(_) => NotifyEvent::TransactionRebroadcastFailed
         }
     }
 
 }
 
+// Report of the changes made while repairing the pending state
+#[derive(Debug)]
+pub struct PendingRepairReport {
+    // Nonce confirmed on chain
+    pub confirmed_nonce: u64,
+    // Nonce that would have been used for the next TX before the repair
+    pub previous_unconfirmed_nonce: u64,
+    // Nonce to use for the next TX
+    pub unconfirmed_nonce: u64,
+    // Last TX created that is neither in mempool nor executed
+    pub discarded_tx: Option<Hash>,
+    // Projected balances that changed: asset, previous and new amount
+    // None means no projected balance, the confirmed one is used
+    pub changed_balances: Vec<(Hash, Option<u64>, Option<u64>)>
+}
+
+impl PendingRepairReport {
+    // Number of nonces that were used by TXs not known by the daemon
+    pub fn discarded_nonces(&self) -> u64 {
+        self.previous_unconfirmed_nonce.saturating_sub(self.unconfirmed_nonce)
+    }
+
+    // Check if the local pending state was already in sync with the daemon
+    pub fn is_clean(&self) -> bool {
+        self.previous_unconfirmed_nonce == self.unconfirmed_nonce && self.discarded_tx.is_none() && self.changed_balances.is_empty()
+    }
+}
+
 // This is a 32 bytes aligned struct
 // It is necessary for the precomputed tables points
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Copy, Clone)]
@@ -203,6 +299,8 @@ impl PrecomputedTables {
 }
 
 pub const PRECOMPUTED_TABLES_L1: usize = 26;
+// Smaller tables are generated in a few ms but decoding is slower
+pub const LOW_PRECOMPUTED_TABLES_L1: usize = 13;
 
 pub struct Wallet {
     // Encrypted Wallet Storage
@@ -213,6 +311,12 @@ pub struct Wallet {
     public_key: PublicKey,
     // network handler for online mode to keep wallet synced
     network_handler: Mutex<Option<SharedNetworkHandler>>,
+    // task trying to connect to the daemon in background until it succeeds
+    connection_task: Mutex<Option<JoinHandle<()>>>,
+    // held while syncing, only one sync can run at a time
+    sync_lock: Mutex<()>,
+    // interval in seconds between two background syncs
+    sync_interval: AtomicU64,
     // network on which we are connected
     network: Network,
     // RPC Server
@@ -223,8 +327,15 @@ pub struct Wallet {
     xswd_channel: RwLock<Option<UnboundedSender<XSWDEvent>>>,
     // Event broadcaster
     event_broadcaster: Mutex<Option<BroadcastSender<Event>>>,
+    // Commands executed on wallet events, None until explicitly enabled
+    hooks: RwLock<Option<HookManager>>,
+    // RPC API keys by hash, loaded from the storage on first use
+    api_keys: RwLock<Option<HashMap<Hash, ApiKeyEntry>>>,
     // Precomputed tables byte array
-    precomputed_tables: PrecomputedTablesShared
+    precomputed_tables: PrecomputedTablesShared,
+    // Opened from a snapshot while another process has it open
+    // It can't be synced and can't send transactions
+    read_only: bool
 }
 
 pub fn hash_password(password: String, salt: &[u8]) -> Result<[u8; PASSWORD_HASH_SIZE], WalletError> {
@@ -264,38 +375,53 @@ impl Wallet {
 
     // Create a new wallet with the specificed storage, keypair and its network
     fn new(storage: EncryptedStorage, keypair: KeyPair, network: Network, precomputed_tables: PrecomputedTablesShared) -> Arc<Self> {
+        let read_only = storage.is_read_only();
         let zelf = Self {
             storage: RwLock::new(storage),
             public_key: keypair.get_public_key().compress(),
             keypair,
             network_handler: Mutex::new(None),
+            connection_task: Mutex::new(None),
+            sync_lock: Mutex::new(()),
+            sync_interval: AtomicU64::new(DEFAULT_SYNC_INTERVAL),
             network,
             #[cfg(feature = "api_server")]
             api_server: Mutex::new(None),
             #[cfg(feature = "api_server")]
             xswd_channel: RwLock::new(None),
             event_broadcaster: Mutex::new(None),
-            precomputed_tables
+            hooks: RwLock::new(None),
+            api_keys: RwLock::new(None),
+            precomputed_tables,
+            read_only
         };
 
         Arc::new(zelf)
     }
 
     // Create a new wallet on disk
-    pub fn create(name: String, password: String, seed: Option<String>, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+    // The seed language is detected from the seed if not provided, English is used by default for a new seed
+    pub fn create(name: String, password: String, seed: Option<String>, seed_language: Option<usize>, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
         if name.is_empty() {
             return Err(WalletError::EmptyName.into())
         }
 
         // generate random keypair or recover it from seed
-        let keypair = if let Some(seed) = seed {
-        debug!("Retrieving keypair from seed...");
-        let words: Vec<String> = seed.split_whitespace().map(str::to_string).collect();
-        let key = mnemonics::words_to_key(&words)?;
-            KeyPair::from_private_key(key)
+        let (keypair, seed_language) = if let Some(seed) = seed {
+            debug!("Retrieving keypair from seed...");
+            let words = mnemonics::split_seed(&seed);
+            let (key, language) = match seed_language {
+                Some(language) => (mnemonics::words_to_key_with_language(&words, language)?, language),
+                None => mnemonics::words_to_key_and_language(&words)?
+            };
+            (KeyPair::from_private_key(key), language)
         } else {
             debug!("Generating a new keypair...");
-            KeyPair::new()
+            let language = seed_language.unwrap_or(0);
+            if language >= mnemonics::LANGUAGES.len() {
+                return Err(mnemonics::MnemonicsError::InvalidLanguageIndex(language).into())
+            }
+            (KeyPair::new(), language)
         };
 
         // generate random salt for hashed password
@@ -334,6 +460,7 @@ impl Wallet {
 
         // Store the private key
         storage.set_private_key(&keypair.get_private_key())?;
+        storage.set_seed_language(seed_language)?;
 
         // Flush the storage to be sure its written on disk
         storage.flush()?;
@@ -349,7 +476,22 @@ impl Wallet {
 
         debug!("Creating storage for {}", name);
         let storage = Storage::new(name)?;
-        
+        Self::open_storage(storage, password, network, precomputed_tables)
+    }
+
+    // Open an existing wallet in read-only mode, even if another process has it open
+    // A snapshot of its storage is used, changes done by the other process after it are not visible
+    pub fn open_read_only(name: String, password: String, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if name.is_empty() {
+            return Err(WalletError::EmptyName.into())
+        }
+
+        debug!("Creating read-only storage for {}", name);
+        let storage = Storage::open_read_only(name)?;
+        Self::open_storage(storage, password, network, precomputed_tables)
+    }
+
+    fn open_storage(storage: Storage, password: String, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
         // get password salt for KDF
         debug!("Retrieving password salt from public storage");
         let salt = storage.get_password_salt()?;
@@ -442,6 +584,17 @@ impl Wallet {
             }
         }
 
+        // Execute the hooks registered on this event
+        if let Some(hook_event) = HookEvent::from_event(&event) {
+            let hooks = self.hooks.read().await;
+            if let Some(manager) = hooks.as_ref() {
+                match serde_json::to_value(&event) {
+                    Ok(payload) => manager.trigger(hook_event, &payload),
+                    Err(e) => warn!("Error while serializing event for hooks: {}", e)
+                }
+            }
+        }
+
         // Broadcast to the event broadcaster
         {
             let mut lock = self.event_broadcaster.lock().await;
@@ -484,8 +637,9 @@ impl Wallet {
         }
         let mut rpc_handler = RPCHandler::new(self.clone());
         register_rpc_methods(&mut rpc_handler);
+        rpc_handler.set_authorizer(Arc::new(WalletRpcAuthorizer::new(config)));
 
-        let rpc_server = WalletRpcServer::new(bind_address, rpc_handler, config).await?;
+        let rpc_server = WalletRpcServer::new(bind_address, rpc_handler).await?;
         *lock = Some(APIServer::RPCServer(rpc_server));
         Ok(())
     }
@@ -581,10 +735,14 @@ impl Wallet {
     pub async fn decrypt_ciphertext(self: Arc<Self>, ciphertext: Ciphertext) -> Result<u64, WalletError> {
         trace!("decrypt ciphertext");
         tokio::task::spawn_blocking(move || {
-            let view = ECDLPTablesFileView::<PRECOMPUTED_TABLES_L1>::from_bytes(self.precomputed_tables.get());
-            self.keypair.get_private_key()
-                .decrypt(&view, &ciphertext)
-                .ok_or(WalletError::CiphertextDecode)
+            let bytes = self.precomputed_tables.get();
+            let private_key = self.keypair.get_private_key();
+            let value = if self.precomputed_tables.l1() == LOW_PRECOMPUTED_TABLES_L1 {
+                private_key.decrypt(&ECDLPTablesFileView::<LOW_PRECOMPUTED_TABLES_L1>::from_bytes(bytes), &ciphertext)
+            } else {
+                private_key.decrypt(&ECDLPTablesFileView::<PRECOMPUTED_TABLES_L1>::from_bytes(bytes), &ciphertext)
+            };
+            value.ok_or(WalletError::CiphertextDecode)
         }).await.context("Error while decrypting ciphertext")?
     }
 
@@ -614,6 +772,7 @@ impl Wallet {
     // You must handle "apply changes" to the storage
     pub async fn create_transaction_with_storage(&self, storage: &EncryptedStorage, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<(TransactionBuilderState, Transaction), WalletError> {
         trace!("create transaction with storage");
+        self.check_writable()?;
         let nonce = storage.get_unconfirmed_nonce();
 
         // Build the state for the builder
@@ -636,6 +795,7 @@ impl Wallet {
         );
 
         // Get all balances used
+        let mut balances = HashMap::new();
         for asset in used_assets {
             trace!("Checking balance for asset {}", asset);
             if !storage.has_balance_for(&asset).await? {
@@ -643,13 +803,23 @@ impl Wallet {
             }
 
             let (balance, _) = storage.get_unconfirmed_balance_for(&asset).await?;
-            state.add_balance(asset, balance);
+            balances.insert(asset, balance);
         }
+        state.set_balances(balances.clone());
 
         self.add_registered_keys_for_fees_estimation(state.as_mut(), &fee, &transaction_type).await?;
 
+        // Resolve the fees first, the daemon may require more than the static formula
+        let fee = match fee {
+            FeeBuilder::Multiplier(multiplier) => {
+                let estimated_fees = self.estimate_fees_with_state(state.as_mut(), &transaction_type).await?;
+                FeeBuilder::Value((estimated_fees as f64 * multiplier) as u64)
+            },
+            fee => fee
+        };
+
         // Create the transaction builder
-        let builder = TransactionBuilder::new(0, self.public_key.clone(), transaction_type, fee);
+        let builder = TransactionBuilder::new(0, self.public_key.clone(), transaction_type.clone(), fee);
 
         // Build the final transaction
         let transaction = builder.build(&mut state, &self.keypair)
@@ -657,25 +827,278 @@ impl Wallet {
 
         let tx_hash = transaction.hash();
         debug!("Transaction created: {} with nonce {} and reference {}", tx_hash, transaction.get_nonce(), transaction.get_reference());
-        state.set_tx_hash_built(tx_hash);
+        state.set_tx_hash_built(tx_hash.clone());
+        state.set_pending_transaction(PendingTransaction::new(
+            tx_hash,
+            transaction.get_nonce(),
+            transaction.get_fee(),
+            transaction.get_reference().clone(),
+            transaction_type,
+            balances,
+            storage.get_synced_topoheight()?
+        ));
 
         Ok((state, transaction))
     }
 
+    // Build a pending transaction again with the same nonce, reference and input balances but a higher fee
+    // You must handle "apply changes" to the storage
+    pub async fn create_bumped_transaction(&self, pending: &PendingTransaction, fee: u64, topoheight: u64) -> Result<(TransactionBuilderState, Transaction), WalletError> {
+        trace!("create bumped transaction for {}", pending.hash);
+        self.check_writable()?;
+        let mut state = TransactionBuilderState::new(
+            self.network.is_mainnet(),
+            pending.reference.clone(),
+            pending.nonce
+        );
+        state.set_balances(pending.balances.clone());
+
+        let fee = FeeBuilder::Value(fee);
+        self.add_registered_keys_for_fees_estimation(state.as_mut(), &fee, &pending.tx_type).await?;
+
+        let builder = TransactionBuilder::new(0, self.public_key.clone(), pending.tx_type.clone(), fee);
+        let transaction = builder.build(&mut state, &self.keypair)
+            .map_err(|e| WalletError::Any(e.into()))?;
+
+        let tx_hash = transaction.hash();
+        debug!("Transaction {} bumped to {} with fee {}", pending.hash, tx_hash, transaction.get_fee());
+        state.set_tx_hash_built(tx_hash.clone());
+        state.set_pending_transaction(pending.bumped(tx_hash, transaction.get_fee(), topoheight));
+
+        Ok((state, transaction))
+    }
+
+    // create a transaction burning the amount of asset and apply it to the storage
+    pub async fn create_burn_transaction(&self, asset: Hash, amount: u64) -> Result<Transaction, WalletError> {
+        trace!("create burn transaction");
+        let mut storage = self.storage.write().await;
+        let (mut state, transaction) = self.create_burn_transaction_with_storage(&storage, asset, amount, FeeBuilder::default()).await?;
+
+        state.apply_changes(&mut storage).await?;
+
+        Ok(transaction)
+    }
+
+    // Build a burn after checking the amount against our decrypted balances
+    // Fees are always paid in XELIS, so burning another asset requires a XELIS balance too
+    // You must handle "apply changes" to the storage
+    pub async fn create_burn_transaction_with_storage(&self, storage: &EncryptedStorage, asset: Hash, amount: u64, fee: FeeBuilder) -> Result<(TransactionBuilderState, Transaction), WalletError> {
+        trace!("create burn transaction with storage");
+        self.check_writable()?;
+        if amount == 0 {
+            return Err(WalletError::InvalidBurnAmount)
+        }
+
+        let transaction_type = TransactionTypeBuilder::Burn(BurnPayload { asset: asset.clone(), amount });
+        // Compute the fee first to verify the funds with the exact value used
+        let fee = match fee {
+            FeeBuilder::Value(value) => value,
+            FeeBuilder::Multiplier(multiplier) => (self.estimate_fees(transaction_type.clone()).await? as f64 * multiplier) as u64
+        };
+
+        if !storage.has_balance_for(&asset).await? {
+            return Err(WalletError::BalanceNotFound(asset))
+        }
+
+        let (balance, _) = storage.get_unconfirmed_balance_for(&asset).await?;
+        if asset == XELIS_ASSET {
+            let needed = checked_add(amount, fee)?;
+            if balance.amount < needed {
+                return Err(WalletError::NotEnoughFunds(balance.amount, needed, COIN_DECIMALS, asset))
+            }
+        } else {
+            if balance.amount < amount {
+                let decimals = storage.get_asset_decimals(&asset)?;
+                return Err(WalletError::NotEnoughFunds(balance.amount, amount, decimals, asset))
+            }
+
+            let native = if storage.has_balance_for(&XELIS_ASSET).await? {
+                storage.get_unconfirmed_balance_for(&XELIS_ASSET).await?.0.amount
+            } else {
+                0
+            };
+
+            if native < fee {
+                return Err(WalletError::NotEnoughFundsForFee(fee, native))
+            }
+        }
+
+        self.create_transaction_with_storage(storage, transaction_type, FeeBuilder::Value(fee)).await
+    }
+
     // submit a transaction to the network through the connection to daemon
     // It will increase the local nonce by 1 if the TX is accepted by the daemon
     // returns error if the wallet is in offline mode or if the TX is rejected
-    pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<(), WalletError> {
+    // returns the hash of the transaction accepted by the daemon
+    // Once accepted, it is tracked to be submitted again if the daemon drops it
+    pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<Hash, WalletError> {
         trace!("submit transaction");
+        let hash = self.broadcast_transaction(transaction).await?;
+        let mut storage = self.storage.write().await;
+        Self::track_submitted_transaction(&mut storage, transaction);
+        Ok(hash)
+    }
+
+    // Same as submit_transaction but for a caller already holding the storage lock
+    pub async fn submit_transaction_with_storage(&self, storage: &mut EncryptedStorage, transaction: &Transaction) -> Result<Hash, WalletError> {
+        trace!("submit transaction with storage");
+        let hash = self.broadcast_transaction(transaction).await?;
+        Self::track_submitted_transaction(storage, transaction);
+        Ok(hash)
+    }
+
+    // The TX is already accepted by the daemon, a tracking error must not be reported as a rejection
+    fn track_submitted_transaction(storage: &mut EncryptedStorage, transaction: &Transaction) {
+        if let Err(e) = storage.add_submitted_transaction(SubmittedTransaction::new(transaction.clone(), get_current_time_in_millis())) {
+            warn!("Error while tracking submitted transaction {}: {}", transaction.hash(), e);
+        }
+    }
+
+    async fn broadcast_transaction(&self, transaction: &Transaction) -> Result<Hash, WalletError> {
         let network_handler = self.network_handler.lock().await;
         if let Some(network_handler) = network_handler.as_ref() {
-            network_handler.get_api().submit_transaction(transaction).await?;
-            Ok(())
+            let api = network_handler.get_api();
+            // Don't submit while we are reconnecting / switching to another daemon
+            if !api.is_online() {
+                return Err(WalletError::DaemonDisconnected)
+            }
+
+            // Keep the rejection reason as a distinct error
+            api.submit_transaction(transaction).await
+                .map_err(|e| e.downcast::<WalletError>().unwrap_or_else(WalletError::Any))
         } else {
             Err(WalletError::NotOnlineMode)
         }
     }
 
+    // Check if the destination account was never seen on chain, its creation costs an extra fee
+    // returns None if the wallet is offline
+    pub async fn is_new_account(&self, address: &Address) -> Result<Option<bool>, WalletError> {
+        trace!("is new account");
+        let network_handler = self.network_handler.lock().await;
+        match network_handler.as_ref() {
+            Some(network_handler) if network_handler.is_running().await => {
+                let exist = network_handler.get_api().has_nonce(address, None).await?;
+                Ok(Some(!exist))
+            },
+            _ => Ok(None)
+        }
+    }
+
+    // Discover the assets owned on chain but not known by the wallet without a full rescan
+    // The network handler is cloned so the lock isn't held during the scan
+    pub async fn discover_assets(&self, since_topoheight: u64, scan: bool) -> Result<Vec<Hash>, WalletError> {
+        trace!("discover assets");
+        let network_handler = self.network_handler.lock().await.clone()
+            .ok_or(WalletError::NotOnlineMode)?;
+
+        let assets = network_handler.discover_assets(since_topoheight, scan).await?;
+        Ok(assets)
+    }
+
+    // Rebuild the pending state (unconfirmed nonce and balances) from the daemon
+    // Local pending TXs that are neither in mempool nor executed are discarded
+    // and the projected balances are reset to the ones expected by the mempool
+    // This is safe to call several times as the state is always rebuilt from the daemon
+    pub async fn repair_pending(self: &Arc<Self>) -> Result<PendingRepairReport, WalletError> {
+        trace!("repair pending");
+        let network_handler = self.network_handler.lock().await;
+        let api = network_handler.as_ref().ok_or(WalletError::NotOnlineMode)?.get_api();
+
+        let address = self.get_address();
+        let confirmed_nonce = api.get_nonce(&address).await?.version.get_nonce();
+        let mempool = api.get_mempool_cache(&address).await?;
+
+        // The daemon is asked as the TX may be executed without being scanned yet
+        let last_tx = self.storage.read().await.get_tx_cache().map(|cache| cache.last_tx_hash_created.clone());
+        let discarded_tx = match last_tx {
+            Some(hash) if !mempool.as_ref().is_some_and(|m| m.txs.contains(&hash)) => {
+                if api.get_transaction_executor_hash(&hash).await?.is_none() {
+                    Some(hash)
+                } else {
+                    None
+                }
+            },
+            _ => None
+        };
+
+        self.rebuild_pending(confirmed_nonce, mempool, discarded_tx).await
+    }
+
+    // Rebuild the pending state from the confirmed nonce and the mempool cache of our account
+    // discarded_tx is the last TX created that the daemon doesn't know
+    async fn rebuild_pending(self: &Arc<Self>, confirmed_nonce: u64, mempool: Option<GetMempoolCacheResult>, discarded_tx: Option<Hash>) -> Result<PendingRepairReport, WalletError> {
+        trace!("rebuild pending");
+        let mut storage = self.storage.write().await;
+        let previous_unconfirmed_nonce = storage.get_unconfirmed_nonce();
+        let previous_balances = storage.get_unconfirmed_balances_amounts().await;
+
+        // Decode the balances expected by the mempool before deleting our projection
+        let mempool = mempool.filter(|m| m.max >= confirmed_nonce);
+        let mut mempool_balances = Vec::new();
+        if let Some(mempool) = mempool.as_ref() {
+            for (asset, ciphertext) in mempool.balances.iter() {
+                let mut ciphertext = ciphertext.clone();
+                let amount = if let Some(amount) = storage.get_unconfirmed_balance_decoded_for(asset, ciphertext.compressed()).await? {
+                    amount
+                } else {
+                    trace!("Decrypting mempool balance for asset {}", asset);
+                    let ct = ciphertext.decompressed().context("Error while decompressing mempool balance")?.clone();
+                    Arc::clone(self).decrypt_ciphertext(ct).await?
+                };
+                mempool_balances.push((asset.clone(), Balance::new(amount, ciphertext)));
+            }
+        }
+
+        if storage.get_nonce().map(|n| n != confirmed_nonce).unwrap_or(true) {
+            debug!("Storing confirmed nonce {}", confirmed_nonce);
+            storage.set_nonce(confirmed_nonce)?;
+        }
+
+        storage.delete_unconfirmed_balances().await?;
+
+        let mut new_balances = HashMap::new();
+        for (asset, balance) in mempool_balances {
+            new_balances.insert(asset.clone(), balance.amount);
+            storage.set_unconfirmed_balance_for(asset, balance).await?;
+        }
+
+        let mut unconfirmed_nonce = confirmed_nonce;
+        if let Some((max, last_tx_hash_created)) = mempool.and_then(|m| m.txs.last().cloned().map(|hash| (m.max, hash))) {
+            unconfirmed_nonce = max + 1;
+            storage.set_tx_cache(TxCache {
+                nonce: unconfirmed_nonce,
+                reference: Reference {
+                    topoheight: storage.get_synced_topoheight()?,
+                    hash: storage.get_top_block_hash()?
+                },
+                last_tx_hash_created
+            });
+        }
+
+        let mut changed_balances = Vec::new();
+        for (asset, previous) in previous_balances.iter() {
+            let new = new_balances.get(asset).copied();
+            if new != Some(*previous) {
+                changed_balances.push((asset.clone(), Some(*previous), new));
+            }
+        }
+
+        for (asset, new) in new_balances {
+            if !previous_balances.contains_key(&asset) {
+                changed_balances.push((asset, None, Some(new)));
+            }
+        }
+
+        Ok(PendingRepairReport {
+            confirmed_nonce,
+            previous_unconfirmed_nonce,
+            unconfirmed_nonce,
+            discarded_tx,
+            changed_balances
+        })
+    }
+
     // Search if possible all registered keys for the transaction type
     pub async fn add_registered_keys_for_fees_estimation(&self, state: &mut EstimateFeesState, fee: &FeeBuilder, transaction_type: &TransactionTypeBuilder) -> Result<(), WalletError> {
         trace!("add registered keys for fees estimation");
@@ -713,29 +1136,158 @@ impl Wallet {
 
     // Estimate fees for a given transaction type
     // Estimated fees returned are the minimum required to be valid on chain
+    // or the ones suggested by the daemon if they are higher
     pub async fn estimate_fees(&self, tx_type: TransactionTypeBuilder) -> Result<u64, WalletError> {
         trace!("estimate fees");
         let mut state = EstimateFeesState::new();
 
         self.add_registered_keys_for_fees_estimation(&mut state, &FeeBuilder::default(), &tx_type).await?;
+        self.estimate_fees_with_state(&mut state, &tx_type).await
+    }
 
-        let builder = TransactionBuilder::new(0, self.public_key.clone(), tx_type, FeeBuilder::default());
-        let estimated_fees = builder.estimate_fees(&mut state)
+    // Estimate fees using the registered keys already known by the state
+    // When online, the daemon is asked for its minimum and suggested fees
+    // The static formula is used offline or if the daemon can't answer
+    async fn estimate_fees_with_state(&self, state: &mut EstimateFeesState, tx_type: &TransactionTypeBuilder) -> Result<u64, WalletError> {
+        let builder = TransactionBuilder::new(0, self.public_key.clone(), tx_type.clone(), FeeBuilder::default());
+        let estimated_fees = builder.estimate_fees(state)
             .map_err(|e| WalletError::Any(e.into()))?;
 
+        if let Some(network_handler) = self.network_handler.lock().await.as_ref() {
+            if network_handler.is_running().await {
+                let (transfers, new_addresses) = match tx_type {
+                    TransactionTypeBuilder::Transfers(transfers) => {
+                        let mut new_addresses = 0;
+                        for transfer in transfers {
+                            if !state.account_exists(&transfer.destination.get_public_key())? {
+                                new_addresses += 1;
+                            }
+                        }
+                        (transfers.len(), new_addresses)
+                    },
+                    TransactionTypeBuilder::Burn(_) => (0, 0)
+                };
+
+                match network_handler.get_api().estimate_fees(builder.estimate_size(), transfers, new_addresses).await {
+                    Ok(estimate) => return Ok(estimated_fees.max(estimate.suggested_fee)),
+                    Err(e) => warn!("Error while estimating fees with the daemon, using the static formula: {}", e)
+                }
+            }
+        }
+
         Ok(estimated_fees)
     }
 
+    // Report what a built transaction reveals before broadcasting it
+    pub async fn get_privacy_report(&self, storage: &EncryptedStorage, transaction: &Transaction) -> Result<PrivacyReport, WalletError> {
+        trace!("get privacy report");
+        let min_topoheight = storage.get_synced_topoheight()?.saturating_sub(RECENT_DESTINATION_WINDOW);
+        let mut recent_destinations = HashMap::new();
+        for entry in storage.get_filtered_transactions(None, Some(min_topoheight), None, false, true, false, false, None)? {
+            if let EntryData::Outgoing { transfers, .. } = entry.get_entry() {
+                for transfer in transfers {
+                    let topoheight = recent_destinations.entry(transfer.get_destination().clone()).or_insert(entry.get_topoheight());
+                    *topoheight = (*topoheight).max(entry.get_topoheight());
+                }
+            }
+        }
+
+        let daemon_address = {
+            let network_handler = self.network_handler.lock().await;
+            network_handler.as_ref().map(|handler| handler.get_api().get_endpoint().clone())
+        };
+
+        Ok(PrivacyReport::new(transaction, self.network.is_mainnet(), &recent_destinations, daemon_address.as_deref()))
+    }
+
+    // Run all the diagnostic checks for a support report
+    // Addresses and amounts are only included if requested
+    pub async fn doctor(&self, include_sensitive: bool) -> Result<DoctorReport, WalletError> {
+        trace!("doctor");
+        let (daemon, daemon_address) = {
+            let network_handler = self.network_handler.lock().await;
+            match network_handler.as_ref() {
+                Some(handler) => {
+                    let api = handler.get_api();
+                    let daemon = match api.get_info().await {
+                        Ok(info) => api.get_block_at_topoheight(info.topoheight).await.map(|block| DaemonState {
+                            version: info.version,
+                            network: info.network,
+                            topoheight: info.topoheight,
+                            top_block_timestamp: block.timestamp
+                        }).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string())
+                    };
+                    (daemon, Some(api.get_endpoint().clone()))
+                },
+                None => (Err("wallet is in offline mode".to_owned()), None)
+            }
+        };
+
+        let storage = self.storage.read().await;
+        let synced_topoheight = storage.get_synced_topoheight().unwrap_or(0);
+        let pending = storage.get_pending_transactions()?;
+        let integrity = storage.verify_integrity()?;
+
+        let daemon_state = daemon.as_ref().ok();
+        let topoheight = daemon_state.map(|daemon| daemon.topoheight).unwrap_or(synced_topoheight);
+        let checks = vec![
+            doctor::check_wallet(VERSION, self.is_read_only()),
+            doctor::check_network(&self.network, daemon_state),
+            doctor::check_daemon(VERSION, daemon.as_ref().map_err(String::as_str)),
+            doctor::check_sync_gap(synced_topoheight, daemon_state),
+            doctor::check_pending(&pending, topoheight),
+            doctor::check_storage(&integrity),
+            doctor::check_kdf(),
+            doctor::check_clock(get_current_time_in_millis(), daemon_state),
+            doctor::check_history(&integrity.history)
+        ];
+
+        let sensitive = if include_sensitive {
+            let mut balances = HashMap::new();
+            for asset in storage.get_assets().await? {
+                let amount = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+                balances.insert(asset.clone(), storage.format_asset_amount(&asset, amount)?);
+            }
+
+            Some(SensitiveInfo {
+                address: self.get_address().to_string(),
+                daemon_address,
+                balances
+            })
+        } else {
+            None
+        };
+
+        Ok(DoctorReport {
+            checks,
+            sensitive
+        })
+    }
+
     // set wallet in online mode: start a communication task which will keep the wallet synced
     pub async fn set_online_mode(self: &Arc<Self>, daemon_address: &String, auto_reconnect: bool) -> Result<(), WalletError> {
+        self.set_online_mode_with_endpoints(std::slice::from_ref(daemon_address), auto_reconnect).await
+    }
+
+    // set wallet in online mode using several daemon addresses
+    // the first reachable one is used, others are used as failover in case of disconnection
+    pub async fn set_online_mode_with_endpoints(self: &Arc<Self>, daemon_addresses: &[String], auto_reconnect: bool) -> Result<(), WalletError> {
         trace!("Set online mode");
+        self.check_writable()?;
         if self.is_online().await {
             // user have to set in offline mode himself first
             return Err(WalletError::AlreadyOnlineMode)
         }
 
+        // The user requested another daemon, stop trying the previous ones
+        self.stop_connection_task().await;
+        self.start_network_handler(daemon_addresses, auto_reconnect).await
+    }
+
+    async fn start_network_handler(self: &Arc<Self>, daemon_addresses: &[String], auto_reconnect: bool) -> Result<(), WalletError> {
         // create the network handler
-        let network_handler = NetworkHandler::new(Arc::clone(&self), daemon_address).await?;
+        let network_handler = NetworkHandler::with_endpoints(Arc::clone(&self), daemon_addresses).await?;
         // start the task
         network_handler.start(auto_reconnect).await?;
         *self.network_handler.lock().await = Some(network_handler);
@@ -743,10 +1295,66 @@ impl Wallet {
         Ok(())
     }
 
+    // Try to connect to the daemon every sync interval until it succeeds
+    // It is stopped by setting the wallet in online or offline mode
+    pub async fn set_online_mode_in_background(self: &Arc<Self>, daemon_addresses: Vec<String>, auto_reconnect: bool) {
+        if self.read_only {
+            warn!("Wallet is open in read-only mode, online mode is not available");
+            return;
+        }
+
+        let zelf = Arc::clone(&self);
+        let task = spawn_task("wallet-connection", async move {
+            loop {
+                sleep(zelf.get_sync_interval()).await;
+                debug!("Trying to connect to daemon at '{}'", daemon_addresses.join("', '"));
+                match zelf.start_network_handler(&daemon_addresses, auto_reconnect).await {
+                    Ok(()) => {
+                        info!("Connected to daemon, online mode enabled");
+                        break;
+                    },
+                    Err(e) => debug!("Couldn't connect to daemon: {}", e)
+                };
+            }
+        });
+
+        if let Some(previous) = self.connection_task.lock().await.replace(task) {
+            previous.abort();
+        }
+    }
+
+    // Stop trying to connect in background
+    // Returns true if a task was running
+    async fn stop_connection_task(&self) -> bool {
+        match self.connection_task.lock().await.take() {
+            Some(task) => {
+                let running = !task.is_finished();
+                task.abort();
+                running
+            },
+            None => false
+        }
+    }
+
+    // Interval between two background syncs and connection attempts
+    pub fn set_sync_interval(&self, interval: Duration) {
+        self.sync_interval.store(interval.as_secs().max(1), Ordering::SeqCst);
+    }
+
+    pub fn get_sync_interval(&self) -> Duration {
+        Duration::from_secs(self.sync_interval.load(Ordering::SeqCst))
+    }
+
+    // Lock held by the network handler while syncing
+    pub(crate) fn get_sync_lock(&self) -> &Mutex<()> {
+        &self.sync_lock
+    }
+
     // set the wallet in online mode using a shared daemon API
     // this allows to share the same connection/Daemon API across several wallets to save resources
     pub async fn set_online_mode_with_api(self: &Arc<Self>, daemon_api: Arc<DaemonAPI>, auto_reconnect: bool) -> Result<(), WalletError> {
         trace!("Set online mode with API");
+        self.check_writable()?;
         if self.is_online().await {
             // user have to set in offline mode himself first
             return Err(WalletError::AlreadyOnlineMode)
@@ -764,10 +1372,11 @@ impl Wallet {
     // set wallet in offline mode: stop communication task if exists
     pub async fn set_offline_mode(&self) -> Result<(), WalletError> {
         trace!("Set offline mode");
+        let connecting = self.stop_connection_task().await;
         let mut handler = self.network_handler.lock().await;
         if let Some(network_handler) = handler.take() {
             network_handler.stop().await?;
-        } else {
+        } else if !connecting {
             return Err(WalletError::NotOnlineMode)
         }
 
@@ -849,6 +1458,32 @@ impl Wallet {
         self.keypair.sign(data)
     }
 
+    // Key to give to the miner so it tags the blocks found for this wallet
+    pub fn get_authorship_key(&self) -> AuthorshipKey {
+        AuthorshipKey::from_private_key(self.keypair.get_private_key())
+    }
+
+    // Prove that a block was mined by this wallet using the authorship tag written by the miner
+    // Only the derivation for this block is revealed
+    pub async fn prove_mined_block(&self, hash: &Hash) -> Result<MinedBlockProof, WalletError> {
+        trace!("prove mined block {}", hash);
+        let network_handler = self.network_handler.lock().await.clone()
+            .ok_or(WalletError::NotOnlineMode)?;
+
+        let block = network_handler.get_api().get_block_by_hash(hash).await?;
+        if *block.miner.get_public_key() != self.public_key {
+            return Err(WalletError::BlockNotMinedByUs(hash.clone()))
+        }
+
+        if !has_authorship_tag(&block.extra_nonce, &self.get_authorship_key().get_tag(block.height)) {
+            return Err(WalletError::BlockNotTagged(hash.clone()))
+        }
+
+        let proof = MinedBlockProof::new(&self.keypair, block.hash.into_owned(), block.height);
+        proof.verify_with(hash, block.height, &block.extra_nonce, &self.public_key)?;
+        Ok(proof)
+    }
+
     // Get the public key of the wallet
     pub fn get_public_key(&self) -> &PublicKey {
         &self.public_key
@@ -864,9 +1499,32 @@ impl Wallet {
         self.keypair.get_public_key().to_address_with(self.get_network().is_mainnet(), data)
     }
 
+    // Generate an address embedding data to identify the payments received on it (invoice id, order number)
+    // The data is sent back encrypted in the extra data of each transfer to this address
+    pub fn generate_integrated_address(&self, data: DataElement) -> Result<Address, WalletError> {
+        let size = data.size();
+        if size > EXTRA_DATA_LIMIT_SIZE {
+            return Err(WalletError::IntegratedDataTooBig(size, EXTRA_DATA_LIMIT_SIZE))
+        }
+
+        Ok(self.get_address_with(data))
+    }
+
+    // Language index selected for the seed of this wallet
+    pub async fn get_seed_language(&self) -> Result<usize, Error> {
+        let storage = self.storage.read().await;
+        storage.get_seed_language()
+    }
+
+    // Returns the seed words using the language index provided
+    pub fn get_seed_words(&self, language_index: usize) -> Result<Vec<String>, Error> {
+        let words = mnemonics::key_to_words(self.keypair.get_private_key(), language_index)?;
+        Ok(words)
+    }
+
     // Returns the seed using the language index provided
     pub fn get_seed(&self, language_index: usize) -> Result<String, Error> {
-        let words = mnemonics::key_to_words(self.keypair.get_private_key(), language_index)?;
+        let words = self.get_seed_words(language_index)?;
         Ok(words.join(" "))
     }
 
@@ -886,6 +1544,243 @@ impl Wallet {
     pub fn get_network(&self) -> &Network {
         &self.network
     }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    // Sync and transactions are not allowed in read-only mode
+    fn check_writable(&self) -> Result<(), WalletError> {
+        if self.read_only {
+            return Err(WalletError::ReadOnly)
+        }
+        Ok(())
+    }
+
+    // Export the non-sensitive metadata (contacts, notes...) to an encrypted file
+    // No key is included, so it can be synced between devices using any file service
+    pub async fn export_metadata<P: AsRef<Path>>(&self, path: P, passphrase: String) -> Result<(), Error> {
+        let metadata = {
+            let storage = self.storage.read().await;
+            storage.get_metadata()?
+        };
+
+        let content = encrypt_metadata(&metadata, self.network, passphrase)?;
+        let mut file = File::create(path).context("Error while creating metadata file")?;
+        file.write_all(&content).context("Error while writing metadata file")?;
+        Ok(())
+    }
+
+    // Export the transactions history for accounting, ordered by topoheight
+    // Entries are loaded and written one by one, returns the number of rows written
+    pub async fn export_transactions<W: Write>(&self, writer: W, format: ExportFormat, filter: &ExportFilter) -> Result<usize, WalletError> {
+        let mainnet = self.network.is_mainnet();
+        let storage = self.storage.read().await;
+        let mut decimals = DecimalsCache::new(&storage);
+        let mut writer = HistoryWriter::new(writer, format)?;
+        for (topoheight, hash) in storage.get_transactions_hashes_in_range(filter.min_topoheight, filter.max_topoheight)? {
+            let entry = storage.get_transaction(&hash)?;
+            let date = storage.get_block_timestamp(topoheight)?.and_then(format_date);
+            for row in build_rows(&entry, date, filter.asset.as_ref(), mainnet, &mut decimals)? {
+                writer.write_row(&row)?;
+            }
+        }
+
+        writer.finish()
+    }
+
+    // Import an encrypted metadata file and merge it with the current metadata
+    // For each item, the most recent change is kept
+    pub async fn import_metadata<P: AsRef<Path>>(&self, path: P, passphrase: String) -> Result<MergeReport, Error> {
+        let mut content = Vec::new();
+        File::open(path).context("Error while opening metadata file")?
+            .read_to_end(&mut content).context("Error while reading metadata file")?;
+
+        let imported = decrypt_metadata(&content, self.network, passphrase)?;
+        let mut storage = self.storage.write().await;
+        let mut metadata = storage.get_metadata()?;
+        let report = metadata.merge(imported);
+        storage.set_metadata(&metadata)?;
+        Ok(report)
+    }
+
+    // Start executing the stored hooks on wallet events
+    // This runs arbitrary commands and must be explicitly enabled by the user
+    pub async fn enable_hooks(&self, max_concurrent: usize) -> Result<usize, Error> {
+        let hooks = self.storage.read().await.get_hooks()?;
+        let count = hooks.len();
+        *self.hooks.write().await = Some(HookManager::new(hooks, self.network.to_string(), max_concurrent));
+        Ok(count)
+    }
+
+    pub async fn are_hooks_enabled(&self) -> bool {
+        self.hooks.read().await.is_some()
+    }
+
+    // Register a command to execute on a wallet event
+    pub async fn add_hook(&self, event: HookEvent, command_line: &str, timeout: u64) -> Result<Hook, Error> {
+        let mut storage = self.storage.write().await;
+        let mut hooks = storage.get_hooks()?;
+        let id = hooks.last().map(|hook| hook.id + 1).unwrap_or(0);
+        let hook = Hook::new(id, event, command_line, timeout)?;
+        hooks.push(hook.clone());
+        storage.set_hooks(&hooks)?;
+
+        if let Some(manager) = self.hooks.write().await.as_mut() {
+            manager.set_hooks(hooks);
+        }
+        Ok(hook)
+    }
+
+    pub async fn remove_hook(&self, id: u64) -> Result<(), Error> {
+        let mut storage = self.storage.write().await;
+        let mut hooks = storage.get_hooks()?;
+        let len = hooks.len();
+        hooks.retain(|hook| hook.id != id);
+        if hooks.len() == len {
+            return Err(WalletError::HookNotFound(id).into())
+        }
+        storage.set_hooks(&hooks)?;
+
+        if let Some(manager) = self.hooks.write().await.as_mut() {
+            manager.set_hooks(hooks);
+        }
+        Ok(())
+    }
+
+    // Save a transaction to build only after the delay
+    // Expired drafts are deleted at the same time
+    pub async fn create_draft(&self, tx_type: TransactionTypeBuilder, fee: FeeBuilder, delay: u64, expiration: u64) -> Result<TransactionDraft, Error> {
+        let now = get_current_time_in_millis();
+        let mut storage = self.storage.write().await;
+        let mut drafts = storage.get_drafts()?;
+        drafts.retain(|draft| !draft.is_expired(now));
+
+        let id = drafts.last().map(|draft| draft.id + 1).unwrap_or(0);
+        let draft = TransactionDraft::new(id, tx_type, fee, now, delay, expiration);
+        drafts.push(draft.clone());
+        storage.set_drafts(&drafts)?;
+        Ok(draft)
+    }
+
+    // All drafts not expired
+    pub async fn get_drafts(&self) -> Result<Vec<TransactionDraft>, Error> {
+        let now = get_current_time_in_millis();
+        let mut storage = self.storage.write().await;
+        let mut drafts = storage.get_drafts()?;
+        let len = drafts.len();
+        drafts.retain(|draft| !draft.is_expired(now));
+        if drafts.len() != len {
+            storage.set_drafts(&drafts)?;
+        }
+        Ok(drafts)
+    }
+
+    pub async fn cancel_draft(&self, id: u64) -> Result<(), Error> {
+        let mut storage = self.storage.write().await;
+        let mut drafts = storage.get_drafts()?;
+        let len = drafts.len();
+        drafts.retain(|draft| draft.id != id);
+        if drafts.len() == len {
+            return Err(WalletError::DraftNotFound(id).into())
+        }
+        storage.set_drafts(&drafts)?;
+        Ok(())
+    }
+
+    // Build and sign the transaction of a draft once its delay elapsed
+    // The nonce, balances and reference are the current ones, not the ones at creation
+    // The draft is deleted once the transaction is built, or if it has expired
+    pub async fn confirm_draft(&self, id: u64) -> Result<Transaction, Error> {
+        let now = get_current_time_in_millis();
+        let mut storage = self.storage.write().await;
+        let mut drafts = storage.get_drafts()?;
+        let index = drafts.iter().position(|draft| draft.id == id)
+            .ok_or(WalletError::DraftNotFound(id))?;
+
+        if let Err(e) = drafts[index].check_confirmable(now) {
+            if drafts[index].is_expired(now) {
+                drafts.remove(index);
+                storage.set_drafts(&drafts)?;
+            }
+            return Err(e.into())
+        }
+
+        let draft = drafts.remove(index);
+        let (mut state, transaction) = match draft.tx_type {
+            // Burns are verified against our balances before being built
+            TransactionTypeBuilder::Burn(payload) => self.create_burn_transaction_with_storage(&storage, payload.asset, payload.amount, draft.fee).await?,
+            tx_type => self.create_transaction_with_storage(&storage, tx_type, draft.fee).await?
+        };
+
+        state.apply_changes(&mut storage).await?;
+        storage.set_drafts(&drafts)?;
+        Ok(transaction)
+    }
+
+    // Create a new RPC API key, only its hash is stored
+    // The returned key can't be retrieved later
+    pub async fn add_api_key(&self, name: String, permission: ApiKeyPermission) -> Result<String, Error> {
+        let mut storage = self.storage.write().await;
+        let mut keys = storage.get_api_keys()?;
+        if keys.values().any(|entry| entry.name == name) {
+            return Err(WalletError::ApiKeyAlreadyExists(name).into())
+        }
+
+        let mut bytes = [0u8; API_KEY_SIZE];
+        OsRng.fill_bytes(&mut bytes);
+        let key = hex::encode(bytes);
+        keys.insert(hash(key.as_bytes()), ApiKeyEntry {
+            name,
+            permission,
+            created_at: get_current_time_in_millis()
+        });
+        storage.set_api_keys(&keys)?;
+        *self.api_keys.write().await = Some(keys);
+        Ok(key)
+    }
+
+    // Revoke a RPC API key, it is refused from the next request
+    pub async fn revoke_api_key(&self, name: &str) -> Result<(), Error> {
+        let mut storage = self.storage.write().await;
+        let mut keys = storage.get_api_keys()?;
+        let len = keys.len();
+        keys.retain(|_, entry| entry.name != name);
+        if keys.len() == len {
+            return Err(WalletError::ApiKeyNotFound(name.to_owned()).into())
+        }
+        storage.set_api_keys(&keys)?;
+        *self.api_keys.write().await = Some(keys);
+        Ok(())
+    }
+
+    // Read the cached RPC API keys, they are decrypted from the storage only once
+    async fn with_api_keys<T>(&self, f: impl FnOnce(&HashMap<Hash, ApiKeyEntry>) -> T) -> Result<T, Error> {
+        if let Some(keys) = self.api_keys.read().await.as_ref() {
+            return Ok(f(keys))
+        }
+
+        let keys = self.storage.read().await.get_api_keys()?;
+        let mut cache = self.api_keys.write().await;
+        Ok(f(cache.get_or_insert(keys)))
+    }
+
+    // All the RPC API keys sorted by name
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKeyEntry>, Error> {
+        let mut keys: Vec<ApiKeyEntry> = self.with_api_keys(|keys| keys.values().cloned().collect()).await?;
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(keys)
+    }
+
+    pub async fn has_api_keys(&self) -> Result<bool, Error> {
+        self.with_api_keys(|keys| !keys.is_empty()).await
+    }
+
+    // Permission of a RPC API key, None if it doesn't exist or was revoked
+    pub async fn get_api_key_permission(&self, key: &str) -> Result<Option<ApiKeyPermission>, Error> {
+        let key = hash(key.as_bytes());
+        self.with_api_keys(|keys| keys.get(&key).map(|entry| entry.permission)).await
+    }
 }
 
 #[cfg(feature = "api_server")]
@@ -961,4 +1856,102 @@ impl XSWDNodeMethodHandler for Arc<Wallet> {
 
         Err(RpcResponseError::new(id, WalletError::NotOnlineMode))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_wallet;
+
+    #[tokio::test]
+    async fn test_delayed_draft() {
+        use xelis_common::{account::CiphertextCache, config::COIN_VALUE};
+
+        let wallet = test_wallet("delayed-draft");
+        let balance = COIN_VALUE / 100;
+        {
+            let ciphertext = CiphertextCache::Decompressed(wallet.get_public_key().decompress().unwrap().encrypt(balance));
+            let mut storage = wallet.get_storage().write().await;
+            storage.set_balance_for(&XELIS_ASSET, Balance::new(balance, ciphertext)).await.unwrap();
+            storage.set_nonce(0).unwrap();
+            storage.set_synced_topoheight(0).unwrap();
+            storage.set_top_block_hash(&Hash::zero()).unwrap();
+        }
+
+        let burn = TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 1000 });
+        let draft = wallet.create_draft(burn, FeeBuilder::default(), 500, 60 * 1000).await.unwrap();
+
+        // Refused before the end of the delay
+        let err = wallet.confirm_draft(draft.id).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalletError>(), Some(WalletError::DraftLocked(_, _))));
+        assert_eq!(wallet.get_drafts().await.unwrap().len(), 1);
+
+        // Built with the current nonce and balance
+        sleep(Duration::from_millis(600)).await;
+        let tx = wallet.confirm_draft(draft.id).await.unwrap();
+        assert_eq!(tx.get_nonce(), 0);
+        assert!(wallet.get_drafts().await.unwrap().is_empty());
+
+        let storage = wallet.get_storage().read().await;
+        assert_eq!(storage.get_unconfirmed_nonce(), 1);
+        let (unconfirmed, _) = storage.get_unconfirmed_balance_for(&XELIS_ASSET).await.unwrap();
+        assert_eq!(unconfirmed.amount, balance - 1000 - tx.get_fee());
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_pending() {
+        use xelis_common::{account::CiphertextCache, config::COIN_VALUE};
+
+        let wallet = test_wallet("rebuild-pending");
+        let balance = COIN_VALUE / 100;
+        let stuck = Hash::new([1u8; 32]);
+        {
+            let ciphertext = CiphertextCache::Decompressed(wallet.get_public_key().decompress().unwrap().encrypt(balance));
+            let mut storage = wallet.get_storage().write().await;
+            storage.set_balance_for(&XELIS_ASSET, Balance::new(balance, ciphertext)).await.unwrap();
+            storage.set_nonce(0).unwrap();
+            storage.set_synced_topoheight(0).unwrap();
+            storage.set_top_block_hash(&Hash::zero()).unwrap();
+
+            // Stuck state: TX built with a future nonce that the daemon never received
+            let reference = Reference { topoheight: 0, hash: Hash::zero() };
+            storage.set_tx_cache(TxCache { nonce: 5, reference, last_tx_hash_created: stuck.clone() });
+            let ciphertext = CiphertextCache::Decompressed(KeyPair::new().get_public_key().encrypt(balance / 2));
+            storage.set_unconfirmed_balance_for(XELIS_ASSET, Balance::new(balance / 2, ciphertext)).await.unwrap();
+        }
+
+        let report = wallet.rebuild_pending(0, None, Some(stuck.clone())).await.unwrap();
+        assert_eq!(report.confirmed_nonce, 0);
+        assert_eq!(report.previous_unconfirmed_nonce, 5);
+        assert_eq!(report.unconfirmed_nonce, 0);
+        assert_eq!(report.discarded_nonces(), 5);
+        assert_eq!(report.discarded_tx, Some(stuck));
+        assert_eq!(report.changed_balances, vec![(XELIS_ASSET, Some(balance / 2), None)]);
+        assert_eq!(wallet.get_storage().read().await.get_unconfirmed_nonce(), 0);
+
+        // Nothing left to repair
+        let report = wallet.rebuild_pending(0, None, None).await.unwrap();
+        assert!(report.is_clean());
+
+        // TX in mempool is kept along its projected balance
+        let burn = TransactionTypeBuilder::Burn(BurnPayload { asset: XELIS_ASSET, amount: 1000 });
+        let tx = wallet.create_transaction(burn, FeeBuilder::default()).await.unwrap();
+        let (projected, _) = wallet.get_storage().read().await.get_unconfirmed_balance_for(&XELIS_ASSET).await.unwrap();
+        let mempool = GetMempoolCacheResult {
+            min: 0,
+            max: 0,
+            txs: vec![tx.hash()],
+            balances: HashMap::from([(XELIS_ASSET, projected.ciphertext.clone())])
+        };
+        let report = wallet.rebuild_pending(0, Some(mempool), None).await.unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.unconfirmed_nonce, 1);
+
+        // Executed TX: the mempool is empty and the confirmed nonce is stored
+        let report = wallet.rebuild_pending(1, None, None).await.unwrap();
+        assert_eq!(report.unconfirmed_nonce, 1);
+        assert_eq!(report.discarded_tx, None);
+        assert_eq!(report.changed_balances, vec![(XELIS_ASSET, Some(projected.amount), None)]);
+        assert_eq!(wallet.get_storage().read().await.get_nonce().unwrap(), 1);
+    }
+}